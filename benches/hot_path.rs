@@ -0,0 +1,394 @@
+//! Hot-path benchmarks: message parsing, tracker updates, comparator evaluation,
+//! end-to-end quote → signal latency driven by captured exchange payloads in
+//! `fixtures/`, feed throughput into the tracker under multi-symbol load, and
+//! latest-quote reads under a concurrent publisher.
+//!
+//! Run with `cargo bench --bench hot_path`.
+
+use std::{
+    collections::HashMap,
+    hint::black_box,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use rust_decimal_macros::dec;
+
+use arbitrage_bot::{
+    config::EvaluationConfig,
+    constants::notifications as notif_const,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::{
+            BinanceFuturesOrderBookMsg, BinanceOrderBookMsg, Comparator, MarketSnapshot,
+            MarketTracker, MarketType, OrderBookMsg,
+        },
+    },
+    notifications::alert_gate::AlertGate,
+    ws::{
+        handlers::{BinanceDepthParser, BybitOrderBookParser, MessageParser, TopOfBook},
+        latest::LatestQuotes,
+        quote_bus::Quote,
+    },
+};
+
+const BINANCE_FUTURES_DEPTH: &str = include_str!("../fixtures/binance_futures_depth5.json");
+const BINANCE_SPOT_DEPTH: &str = include_str!("../fixtures/binance_spot_depth.json");
+const BYBIT_ORDERBOOK: &str = include_str!("../fixtures/bybit_orderbook1_linear.json");
+
+fn new_tracker(threshold: Decimal) -> MarketTracker {
+    MarketTracker::new(
+        threshold,
+        None,
+        AlertGate::new(
+            notif_const::DIFF_THRESHOLD,
+            notif_const::RE_ALERT_DELTA,
+            notif_const::COOLDOWN_SECS,
+        ),
+    )
+}
+
+fn bench_parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    // The feed parsers used by the live clients
+    group.bench_function("binance_depth_parser", |b| {
+        b.iter(|| black_box(BinanceDepthParser.parse(black_box(BINANCE_FUTURES_DEPTH))))
+    });
+    group.bench_function("bybit_orderbook_parser", |b| {
+        let parser = BybitOrderBookParser {
+            market_type: MarketType::Futures,
+        };
+        b.iter(|| black_box(parser.parse(black_box(BYBIT_ORDERBOOK))))
+    });
+    group.bench_function("binance_futures_direct", |b| {
+        b.iter(|| {
+            let msg: BinanceFuturesOrderBookMsg =
+                serde_json::from_str(black_box(BINANCE_FUTURES_DEPTH)).unwrap();
+            black_box(msg)
+        })
+    });
+    group.bench_function("binance_spot_direct", |b| {
+        b.iter(|| {
+            let msg: BinanceOrderBookMsg =
+                serde_json::from_str(black_box(BINANCE_SPOT_DEPTH)).unwrap();
+            black_box(msg)
+        })
+    });
+    group.bench_function("bybit_orderbook", |b| {
+        b.iter(|| {
+            let msg: OrderBookMsg = serde_json::from_str(black_box(BYBIT_ORDERBOOK)).unwrap();
+            black_box(msg)
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_tracker_update(c: &mut Criterion) {
+    // Threshold above any realistic spread: measures pure update + compare cost.
+    let mut tracker = new_tracker(Decimal::MAX);
+    let btc = Symbol::intern("BTCUSDT");
+    tracker.update(
+        ExchangeId::Bybit,
+        btc,
+        dec!(112540.8),
+        dec!(112540.9),
+        MarketType::Futures,
+    );
+
+    c.bench_function("tracker_update", |b| {
+        let mut bid = dec!(112543.1);
+        b.iter(|| {
+            bid += dec!(0.1);
+            tracker.update(
+                ExchangeId::Binance,
+                black_box(btc),
+                black_box(bid),
+                black_box(bid + dec!(0.1)),
+                MarketType::Futures,
+            );
+        })
+    });
+}
+
+fn bench_comparator(c: &mut Criterion) {
+    let btc = Symbol::intern("BTCUSDT");
+    let mut snapshots = HashMap::new();
+    snapshots.insert(
+        ExchangeId::Binance,
+        MarketSnapshot::new(
+            ExchangeId::Binance,
+            btc,
+            dec!(112543.1),
+            dec!(112543.2),
+            MarketType::Futures,
+        ),
+    );
+    snapshots.insert(
+        ExchangeId::Bybit,
+        MarketSnapshot::new(
+            ExchangeId::Bybit,
+            btc,
+            dec!(112540.8),
+            dec!(112540.9),
+            MarketType::Futures,
+        ),
+    );
+
+    let mut group = c.benchmark_group("comparator");
+    group.bench_function("no_signal", |b| {
+        let mut comparator = Comparator::new(Decimal::MAX);
+        b.iter(|| black_box(comparator.compare(black_box(&snapshots))))
+    });
+    group.bench_function("signal", |b| {
+        let mut comparator = Comparator::new(Decimal::ZERO);
+        b.iter(|| black_box(comparator.compare(black_box(&snapshots))))
+    });
+    group.finish();
+}
+
+fn bench_quote_to_signal(c: &mut Criterion) {
+    let mut tracker = new_tracker(Decimal::ZERO);
+    let bybit: OrderBookMsg = serde_json::from_str(BYBIT_ORDERBOOK).unwrap();
+    let bybit_bid = bybit.data.b[0][0].parse().unwrap();
+    let bybit_ask = bybit.data.a[0][0].parse().unwrap();
+    tracker.update(
+        ExchangeId::Bybit,
+        Symbol::intern(&bybit.data.s),
+        bybit_bid,
+        bybit_ask,
+        MarketType::Futures,
+    );
+
+    c.bench_function("quote_to_signal", |b| {
+        b.iter(|| {
+            let quote = BinanceDepthParser
+                .parse(black_box(BINANCE_FUTURES_DEPTH))
+                .unwrap();
+            tracker.update(
+                ExchangeId::Binance,
+                quote.symbol,
+                quote.bid,
+                quote.ask,
+                quote.market_type,
+            );
+        })
+    });
+}
+
+/// Concurrent feed tasks, alternating between the two exchanges.
+const FEEDS: usize = 4;
+const SYMBOLS: usize = 50;
+const QUOTES_PER_FEED: usize = 10_000;
+
+fn synthetic_quote(symbol: Symbol, n: usize) -> TopOfBook {
+    let bid = dec!(100) + Decimal::new(n as i64 % 100, 2);
+    TopOfBook {
+        symbol,
+        bid,
+        ask: bid + dec!(0.01),
+        bid_size: None,
+        ask_size: None,
+        market_type: MarketType::Futures,
+        update_id: None,
+    }
+}
+
+fn feed_exchange(feed: usize) -> ExchangeId {
+    if feed.is_multiple_of(2) {
+        ExchangeId::Binance
+    } else {
+        ExchangeId::Bybit
+    }
+}
+
+/// How fast `FEEDS` tasks can hand quotes for `SYMBOLS` symbols to the tracker:
+/// every feed locking one shared tracker vs pushing to the tracker actor.
+fn bench_tracker_contention(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let symbols: Arc<Vec<Symbol>> = Arc::new(
+        (0..SYMBOLS)
+            .map(|i| Symbol::intern(&format!("SYM{}USDT", i)))
+            .collect(),
+    );
+
+    let mut group = c.benchmark_group("tracker_contention");
+    group.throughput(Throughput::Elements((FEEDS * QUOTES_PER_FEED) as u64));
+
+    group.bench_function("shared_mutex", |b| {
+        let tracker = Arc::new(tokio::sync::Mutex::new(new_tracker(Decimal::MAX)));
+        b.iter(|| {
+            rt.block_on(async {
+                let feeds: Vec<_> = (0..FEEDS)
+                    .map(|feed| {
+                        let tracker = tracker.clone();
+                        let symbols = symbols.clone();
+                        tokio::spawn(async move {
+                            for n in 0..QUOTES_PER_FEED {
+                                let quote = synthetic_quote(symbols[n % SYMBOLS], n);
+                                tracker.lock().await.update(
+                                    feed_exchange(feed),
+                                    quote.symbol,
+                                    quote.bid,
+                                    quote.ask,
+                                    quote.market_type,
+                                );
+                            }
+                        })
+                    })
+                    .collect();
+                for feed in feeds {
+                    feed.await.unwrap();
+                }
+            })
+        })
+    });
+
+    group.bench_function("actor", |b| {
+        let tracker = rt.block_on(async { new_tracker(Decimal::MAX).spawn() });
+        b.iter(|| {
+            rt.block_on(async {
+                let feeds: Vec<_> = (0..FEEDS)
+                    .map(|feed| {
+                        let tracker = tracker.clone();
+                        let symbols = symbols.clone();
+                        tokio::spawn(async move {
+                            for n in 0..QUOTES_PER_FEED {
+                                let quote = synthetic_quote(symbols[n % SYMBOLS], n);
+                                tracker.push(feed_exchange(feed), quote);
+                            }
+                        })
+                    })
+                    .collect();
+                for feed in feeds {
+                    feed.await.unwrap();
+                }
+            })
+        })
+    });
+    group.finish();
+}
+
+/// Depth deltas per burst; the best bid/ask moves on every `TOP_CHANGE_EVERY`-th.
+const BURST: usize = 1_000;
+const TOP_CHANGE_EVERY: usize = 10;
+
+/// A burst of quotes for one symbol on alternating exchanges, evaluated on
+/// every quote, only on top-of-book changes, and at most every 100ms.
+fn bench_tracker_burst(c: &mut Criterion) {
+    let btc = Symbol::intern("BTCUSDT");
+    let burst: Vec<(ExchangeId, TopOfBook)> = (0..BURST)
+        .map(|n| (feed_exchange(n), synthetic_quote(btc, n / TOP_CHANGE_EVERY)))
+        .collect();
+
+    let mut group = c.benchmark_group("tracker_burst");
+    group.throughput(Throughput::Elements(BURST as u64));
+    let policies = [
+        ("every_update", EvaluationConfig::EVERY_UPDATE),
+        ("on_change", EvaluationConfig::default()),
+        (
+            "debounced_100ms",
+            EvaluationConfig {
+                on_change_only: true,
+                min_interval_ms: 100,
+            },
+        ),
+    ];
+    for (name, evaluation) in policies {
+        group.bench_function(name, |b| {
+            let mut tracker = new_tracker(Decimal::ZERO).with_evaluation(evaluation);
+            b.iter(|| {
+                for (exchange, quote) in &burst {
+                    tracker.update(
+                        *exchange,
+                        quote.symbol,
+                        quote.bid,
+                        quote.ask,
+                        quote.market_type,
+                    );
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Runs `publish` with ever newer quotes on another thread while `bench` runs.
+fn with_publisher(publish: impl Fn(Arc<Quote>) + Send + 'static, bench: impl FnOnce()) {
+    let btc = Symbol::intern("BTCUSDT");
+    let stop = Arc::new(AtomicBool::new(false));
+    let publisher = {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let mut n = 0;
+            while !stop.load(Ordering::Relaxed) {
+                n += 1;
+                publish(Arc::new(Quote {
+                    exchange: feed_exchange(n),
+                    top: synthetic_quote(btc, n),
+                    polled: false,
+                }));
+            }
+        })
+    };
+    bench();
+    stop.store(true, Ordering::Relaxed);
+    publisher.join().unwrap();
+}
+
+/// Reading both legs' latest quotes for a comparison while a feed publishes
+/// non-stop: a Mutex-guarded map vs lock-free cells.
+fn bench_latest_quote(c: &mut Criterion) {
+    let btc = Symbol::intern("BTCUSDT");
+    let legs = [(ExchangeId::Binance, btc), (ExchangeId::Bybit, btc)];
+    let mut group = c.benchmark_group("latest_quote");
+
+    let map: Arc<Mutex<HashMap<_, Arc<Quote>>>> = Arc::default();
+    let writer = map.clone();
+    with_publisher(
+        move |quote| {
+            writer
+                .lock()
+                .unwrap()
+                .insert((quote.exchange, quote.top.symbol), quote);
+        },
+        || {
+            group.bench_function("mutex_map", |b| {
+                b.iter(|| {
+                    let map = map.lock().unwrap();
+                    black_box(legs.map(|leg| map.get(&leg).cloned()))
+                })
+            });
+        },
+    );
+
+    let latest = LatestQuotes::default();
+    let cells = legs.map(|(exchange, symbol)| latest.cell(exchange, symbol));
+    with_publisher(
+        move |quote| latest.store(&quote),
+        || {
+            group.bench_function("arc_swap_cells", |b| {
+                b.iter(|| black_box(cells.each_ref().map(|cell| cell.load())))
+            });
+        },
+    );
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parsing,
+    bench_tracker_update,
+    bench_comparator,
+    bench_quote_to_signal,
+    bench_tracker_contention,
+    bench_tracker_burst,
+    bench_latest_quote
+);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+//! Generates the gRPC service in `src/grpc` from `proto/arbitrage.proto` when
+//! the `grpc` feature is on. The proto is compiled by `protox`, so building
+//! doesn't need `protoc`.
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    grpc();
+}
+
+#[cfg(feature = "grpc")]
+fn grpc() {
+    const PROTO: &str = "proto/arbitrage.proto";
+
+    println!("cargo:rerun-if-changed={}", PROTO);
+    let descriptors = protox::compile([PROTO], ["proto"]).expect("compiling the proto");
+    tonic_build::configure()
+        .build_client(false)
+        .compile_fds(descriptors)
+        .expect("generating the gRPC service");
+}
@@ -0,0 +1,391 @@
+//! Several accounts per exchange (`[[engine.execution.accounts]]`).
+//!
+//! [`Accounts`] stands in for one exchange and holds an order client per
+//! account of it, each signed with its own API key (e.g. sub-accounts).
+//! Every order picks one by `[engine.execution] routing`:
+//! - `round_robin`: the next account, so each key's rate limits take a
+//!   share of the orders.
+//! - `symbol`: the account whose `symbols` list the traded symbol, or else
+//!   one listing none, to keep strategies on accounts of their own.
+//! - `margin`: the account with the most available margin, asked right
+//!   before the order. Accounts that can't say are passed over.
+//!
+//! A reduce-only exit would be rejected on an account without the position
+//! it closes, so exits go to an account holding a position the exit
+//! shrinks, by what it reported at startup (see `crate::recovery`) and this
+//! run's orders since; failing that, to the one holding the most on that
+//! side. Quotes come from the first account.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use async_trait::async_trait;
+use tokio::sync::mpsc::Sender;
+
+use crate::{
+    config::AccountRouting,
+    error::TradingError,
+    models::{ids::ExchangeId, money::Decimal},
+    state::{self, LegSide, OpenOrder},
+    tca::Fill,
+    ws::exchanges::{Exchange, OrderSide, PriceData},
+};
+
+/// How many orders' accounts are remembered. An order is forgotten once
+/// it's filled, and otherwise when this many newer ones were placed.
+pub const REMEMBERED_ORDERS: usize = 4096;
+
+/// One account's order client.
+pub struct Account {
+    pub name: String,
+    pub exchange: Arc<dyn Exchange>,
+    /// What `routing = "symbol"` sends here.
+    pub symbols: Vec<String>,
+}
+
+/// The accounts of one exchange, routed between per order; see the module
+/// docs.
+pub struct Accounts {
+    id: ExchangeId,
+    symbol: String,
+    routing: AccountRouting,
+    accounts: Vec<Account>,
+    /// The account the next round-robin order goes to.
+    next: AtomicUsize,
+    /// Net quantity each account's futures orders bought this run.
+    positions: Mutex<Vec<Decimal>>,
+    /// The account each order this run went to, by order ID.
+    placed: Mutex<Placed>,
+}
+
+/// The account of each recent order, by order ID, oldest first.
+#[derive(Default)]
+struct Placed {
+    accounts: HashMap<String, usize>,
+    order: VecDeque<String>,
+}
+
+impl Accounts {
+    /// Routes the orders for `symbol` between `accounts`, which must all
+    /// be on the same exchange.
+    ///
+    /// # Panics
+    /// If `accounts` is empty.
+    pub fn new(symbol: &str, routing: AccountRouting, accounts: Vec<Account>) -> Self {
+        assert!(!accounts.is_empty(), "Accounts needs at least one account");
+        Self {
+            id: accounts[0].exchange.id(),
+            symbol: symbol.to_string(),
+            routing,
+            positions: Mutex::new(vec![Decimal::ZERO; accounts.len()]),
+            accounts,
+            next: AtomicUsize::new(0),
+            placed: Mutex::default(),
+        }
+    }
+
+    /// Net quantity each account's futures orders bought this run, by name.
+    pub fn positions(&self) -> Vec<(&str, Decimal)> {
+        let positions = self.positions.lock().unwrap_or_else(|e| e.into_inner());
+        self.accounts
+            .iter()
+            .map(|a| a.name.as_str())
+            .zip(positions.iter().copied())
+            .collect()
+    }
+
+    /// The account a new order goes to.
+    async fn route(&self) -> usize {
+        match self.routing {
+            AccountRouting::RoundRobin => self.round_robin(0..self.accounts.len()),
+            AccountRouting::Symbol => {
+                let listing: Vec<_> = self.listing(|symbols| {
+                    symbols.iter().any(|s| s.eq_ignore_ascii_case(&self.symbol))
+                });
+                if listing.is_empty() {
+                    self.round_robin(self.listing(|symbols| symbols.is_empty()))
+                } else {
+                    self.round_robin(listing)
+                }
+            }
+            AccountRouting::Margin => {
+                let mut best: Option<(usize, Decimal)> = None;
+                for (i, account) in self.accounts.iter().enumerate() {
+                    match account.exchange.available_margin().await {
+                        Ok(margin) if best.is_none_or(|(_, most)| margin > most) => {
+                            best = Some((i, margin))
+                        }
+                        Ok(_) => {}
+                        Err(e) => eprintln!(
+                            "⚠️ No margin for {} account {}: {}",
+                            self.id, account.name, e
+                        ),
+                    }
+                }
+                best.map_or_else(|| self.round_robin(0..self.accounts.len()), |(i, _)| i)
+            }
+        }
+    }
+
+    /// The accounts whose `symbols` match.
+    fn listing(&self, matches: impl Fn(&[String]) -> bool) -> Vec<usize> {
+        (0..self.accounts.len())
+            .filter(|i| matches(&self.accounts[*i].symbols))
+            .collect()
+    }
+
+    /// The next of `candidates`, or the next account if there are none.
+    fn round_robin(&self, candidates: impl IntoIterator<Item = usize>) -> usize {
+        let candidates: Vec<_> = candidates.into_iter().collect();
+        let turn = self.next.fetch_add(1, Ordering::Relaxed);
+        if candidates.is_empty() {
+            turn % self.accounts.len()
+        } else {
+            candidates[turn % candidates.len()]
+        }
+    }
+
+    /// The account a reduce-only exit goes to; see the module docs.
+    async fn route_exit(&self, side: LegSide, quantity: Decimal) -> usize {
+        let held = {
+            let positions = self.positions.lock().unwrap_or_else(|e| e.into_inner());
+            positions
+                .iter()
+                .position(|p| state::reduces(*p, side, quantity))
+                .or_else(|| {
+                    // The position on the side the exit closes.
+                    let closes = |p: Decimal| match side {
+                        LegSide::Buy => -p,
+                        LegSide::Sell => p,
+                    };
+                    (0..positions.len())
+                        .filter(|i| closes(positions[*i]) > Decimal::ZERO)
+                        .max_by_key(|i| closes(positions[*i]))
+                })
+        };
+        match held {
+            Some(i) => i,
+            None => self.route().await,
+        }
+    }
+
+    /// Remembers that order `order_id` went to account `i`, forgetting the
+    /// oldest order past [`REMEMBERED_ORDERS`].
+    fn placed(&self, i: usize, order_id: &str) {
+        let mut placed = self.placed.lock().unwrap_or_else(|e| e.into_inner());
+        if placed.accounts.insert(order_id.to_string(), i).is_none() {
+            placed.order.push_back(order_id.to_string());
+        }
+        while placed.order.len() > REMEMBERED_ORDERS {
+            if let Some(oldest) = placed.order.pop_front() {
+                placed.accounts.remove(&oldest);
+            }
+        }
+    }
+
+    /// The account order `order_id` went to.
+    fn account(&self, order_id: &str) -> Option<usize> {
+        let placed = self.placed.lock().unwrap_or_else(|e| e.into_inner());
+        placed.accounts.get(order_id).copied()
+    }
+
+    /// Forgets order `order_id`, which is done.
+    fn closed(&self, order_id: &str) {
+        let mut placed = self.placed.lock().unwrap_or_else(|e| e.into_inner());
+        if placed.accounts.remove(order_id).is_some() {
+            placed.order.retain(|id| id != order_id);
+        }
+    }
+
+    /// Counts a futures order placed on account `i`.
+    fn filled(&self, i: usize, side: LegSide, quantity: Decimal) {
+        let mut positions = self.positions.lock().unwrap_or_else(|e| e.into_inner());
+        positions[i] += match side {
+            LegSide::Buy => quantity,
+            LegSide::Sell => -quantity,
+        };
+    }
+
+    /// Account `i`'s order client, saying so if there's a choice.
+    fn client(&self, i: usize) -> &dyn Exchange {
+        if self.accounts.len() > 1 {
+            println!(
+                "🔀 {} order routed to account {}",
+                self.id, self.accounts[i].name
+            );
+        }
+        self.accounts[i].exchange.as_ref()
+    }
+}
+
+#[async_trait]
+impl Exchange for Accounts {
+    fn id(&self) -> ExchangeId {
+        self.id
+    }
+
+    async fn subscribe_prices(&self, tx: Sender<PriceData>) {
+        self.accounts[0].exchange.subscribe_prices(tx).await
+    }
+
+    async fn place_order_future(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let i = self.route().await;
+        let leg = LegSide::from(&side);
+        let order_id = self.client(i).place_order_future(side, price, qty).await?;
+        self.filled(i, leg, qty);
+        self.placed(i, &order_id);
+        Ok(order_id)
+    }
+
+    async fn place_exit_future(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let leg = LegSide::from(&side);
+        let i = self.route_exit(leg, qty).await;
+        let order_id = self.client(i).place_exit_future(side, price, qty).await?;
+        self.filled(i, leg, qty);
+        self.placed(i, &order_id);
+        Ok(order_id)
+    }
+
+    async fn place_market_future(
+        &self,
+        side: OrderSide,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let i = self.route().await;
+        let leg = LegSide::from(&side);
+        let order_id = self.client(i).place_market_future(side, qty).await?;
+        self.filled(i, leg, qty);
+        self.placed(i, &order_id);
+        Ok(order_id)
+    }
+
+    async fn place_market_exit_future(
+        &self,
+        side: OrderSide,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let leg = LegSide::from(&side);
+        let i = self.route_exit(leg, qty).await;
+        let order_id = self.client(i).place_market_exit_future(side, qty).await?;
+        self.filled(i, leg, qty);
+        self.placed(i, &order_id);
+        Ok(order_id)
+    }
+
+    async fn place_order_spot(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let i = self.route().await;
+        let order_id = self.client(i).place_order_spot(side, price, qty).await?;
+        self.placed(i, &order_id);
+        Ok(order_id)
+    }
+
+    /// The margin of every account that can tell, added up.
+    async fn available_margin(&self) -> Result<Decimal, TradingError> {
+        let mut total = None;
+        let mut error = None;
+        for account in &self.accounts {
+            match account.exchange.available_margin().await {
+                Ok(margin) => *total.get_or_insert(Decimal::ZERO) += margin,
+                Err(e) => error = Some(e),
+            }
+        }
+        match (total, error) {
+            (Some(total), _) => Ok(total),
+            (None, Some(e)) => Err(e),
+            (None, None) => unreachable!("Accounts has at least one account"),
+        }
+    }
+
+    /// Every account's, each one's remembered for routing its exits.
+    async fn open_orders(&self) -> Result<Vec<OpenOrder>, TradingError> {
+        let mut all = Vec::new();
+        for (i, account) in self.accounts.iter().enumerate() {
+            let orders = account.exchange.open_orders().await?;
+            for order in &orders {
+                self.placed(i, &order.order_id);
+            }
+            all.extend(orders);
+        }
+        Ok(all)
+    }
+
+    /// Every account's, added up; each one's becomes what its exits are
+    /// routed by.
+    async fn position(&self) -> Result<Decimal, TradingError> {
+        let mut held = Vec::new();
+        for account in &self.accounts {
+            held.push(account.exchange.position().await?);
+        }
+        let mut positions = self.positions.lock().unwrap_or_else(|e| e.into_inner());
+        *positions = held;
+        Ok(positions.iter().sum())
+    }
+
+    fn account_of(&self, order_id: &str) -> Option<String> {
+        let i = self.account(order_id)?;
+        Some(self.accounts[i].name.clone())
+    }
+
+    /// Asks the account the order went to.
+    async fn cancel_order(&self, order_id: &str) -> Result<(), TradingError> {
+        match self.account(order_id) {
+            Some(i) => self.accounts[i].exchange.cancel_order(order_id).await,
+            None => Err(TradingError::Unsupported {
+                exchange: self.id.name(),
+                kind: "cancel",
+            }),
+        }
+    }
+
+    /// Asks every account, remembering the one that had it.
+    async fn unanswered_order(
+        &self,
+        side: OrderSide,
+        qty: Decimal,
+    ) -> Result<Option<String>, TradingError> {
+        for (i, account) in self.accounts.iter().enumerate() {
+            if let Some(order_id) = account.exchange.unanswered_order(side.clone(), qty).await? {
+                self.placed(i, &order_id);
+                return Ok(Some(order_id));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Asks the account the order went to, forgetting it once it's done.
+    async fn fill(&self, order_id: &str) -> Result<Fill, TradingError> {
+        match self.account(order_id) {
+            Some(i) => {
+                let fill = self.accounts[i].exchange.fill(order_id).await?;
+                if fill.done {
+                    self.closed(order_id);
+                }
+                Ok(fill)
+            }
+            None => Err(TradingError::Unsupported {
+                exchange: self.id.name(),
+                kind: "fill",
+            }),
+        }
+    }
+}
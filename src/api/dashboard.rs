@@ -0,0 +1,122 @@
+//! The web dashboard: one embedded page and the WebSocket that feeds it.
+//!
+//! Every `[api] push_interval_ms` the socket sends the engine's [`Status`]
+//! (quotes included) and the current spreads; alerts are sent as they
+//! happen, starting with the recent ones on connect. The opportunity
+//! heatmap (see `crate::heatmap`) is sent on connect and then every
+//! minute. Each message is a JSON object tagged by `type`: `status`,
+//! `spreads`, `alert` or `heatmap`.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Serialize;
+use tokio::time::{Duration, Instant};
+
+use super::{unauthorized, Api, TokenQuery};
+use crate::{
+    config,
+    control::{Control, Spread, Status},
+    heatmap::Grid,
+    models::orderbook::Opportunity,
+    state,
+};
+
+const PAGE: &str = include_str!("dashboard.html");
+
+/// How often the heatmap is sent again; it moves slowly.
+const HEATMAP_INTERVAL: Duration = Duration::from_secs(60);
+
+pub(super) fn routes() -> Router<Api> {
+    Router::new()
+        .route("/", get(page))
+        .route("/ws", get(socket))
+}
+
+async fn page() -> Html<&'static str> {
+    Html(PAGE)
+}
+
+async fn socket(
+    State(api): State<Api>,
+    Query(query): Query<TokenQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if !api.accepts(query.token.as_deref()) {
+        return unauthorized();
+    }
+    ws.on_upgrade(move |socket| push_events(socket, api))
+        .into_response()
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Event<'a> {
+    Status(&'a Status),
+    Spreads { at_ms: i64, spreads: Vec<Spread> },
+    Alert(&'a Opportunity),
+    Heatmap(&'a Grid),
+}
+
+/// Pushes events until the browser goes away or the engine shuts down.
+async fn push_events(mut socket: WebSocket, api: Api) {
+    let mut ticker = tokio::time::interval(config::get().api.push_interval());
+    let mut last_alert_ms = 0;
+    let mut heatmap_due = Instant::now();
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = api.cancel.cancelled() => break,
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+                // Nothing to act on from the browser; pings are answered by axum.
+                Some(Ok(_)) => continue,
+            },
+        }
+        let events = snapshot(&api.control, &mut last_alert_ms, &mut heatmap_due).await;
+        for event in events {
+            if socket.send(Message::Text(event.into())).await.is_err() {
+                return;
+            }
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+/// This tick's events as JSON, alerts newer than `last_alert_ms` included,
+/// and the heatmap once `heatmap_due` has passed.
+async fn snapshot(
+    control: &Control,
+    last_alert_ms: &mut i64,
+    heatmap_due: &mut Instant,
+) -> Vec<String> {
+    let status = control.status();
+    let mut events = vec![
+        Event::Status(&status),
+        Event::Spreads {
+            at_ms: state::now_ms(),
+            spreads: status.spreads(),
+        },
+    ];
+    let alerts = control.recent_alerts().await.unwrap_or_default();
+    let fresh: Vec<&Opportunity> = alerts.iter().filter(|a| a.at_ms > *last_alert_ms).collect();
+    if let Some(newest) = fresh.last() {
+        *last_alert_ms = newest.at_ms;
+    }
+    events.extend(fresh.into_iter().map(Event::Alert));
+    let heatmap = (Instant::now() >= *heatmap_due).then(|| control.heatmap());
+    if let Some(heatmap) = &heatmap {
+        *heatmap_due = Instant::now() + HEATMAP_INTERVAL;
+        events.push(Event::Heatmap(heatmap));
+    }
+    events
+        .iter()
+        .filter_map(|event| serde_json::to_string(event).ok())
+        .collect()
+}
@@ -0,0 +1,382 @@
+//! HTTP control API over [`Control`].
+//!
+//! Enabled by `[api] listen`; every request must carry
+//! `Authorization: Bearer <token>`. The admin token from `CONTROL_API_TOKEN`
+//! can do everything; the read-only one from `CONTROL_API_READ_TOKEN`, if
+//! set, only the `GET`s and the dashboard.
+//!
+//! | Method | Path                     | Does                                            |
+//! |--------|--------------------------|-------------------------------------------------|
+//! | GET    | `/status`                | uptime, thresholds, pause flag, quotes, feeds   |
+//! | GET    | `/positions`             | orders placed by execution and the exposure     |
+//! | GET    | `/opportunities/recent`  | the latest opportunities the tracker saw        |
+//! | GET    | `/heatmap`               | opportunities by hour of day (UTC) and symbol   |
+//! | POST   | `/pause`, `/resume`      | stops / restarts execution placing orders       |
+//! | POST   | `/kill`                  | shuts the engine down                           |
+//! | POST   | `/symbols`               | `{"exchange","subscribe":[..],"unsubscribe":[..]}` |
+//! | POST   | `/threshold`             | `{"alert_percent","execution_percent"}`, either optional |
+//! | POST   | `/recording`             | `{"symbol","enabled"}`: raw-feed recording on / off |
+//! | POST   | `/strategies`            | `{"name","enabled","threshold_percent","quantity"}`, all but `name` optional |
+//! | POST   | `/pauses`                | `{"exchange" or "symbol","paused"}`: pauses / resumes just that one |
+//!
+//! `/` serves a web dashboard and `/ws` the WebSocket feeding it (see
+//! [`dashboard`]). Browsers can't set headers on either, so the page is
+//! public and the socket takes the token as `?token=`.
+//!
+//! With `[api] tls_cert` and `tls_key` the API is served over HTTPS, and
+//! with `client_ca` only to clients presenting a certificate from that CA
+//! (see [`tls`]); the tokens are needed either way.
+//!
+//! `POST /signal` takes a [`Signal`] for execution from an external system.
+//! Webhook senders like TradingView can't set headers either, so it takes
+//! the token as `?token=` too, and accepts `WEBHOOK_TOKEN` as well as the
+//! control token: the webhook token can only send signals.
+
+mod dashboard;
+mod tls;
+
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{FromRequest, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config,
+    control::{Control, Signal, Status, StrategyUpdate},
+    error::ControlError,
+    heatmap::Grid,
+    models::{ids::ExchangeId, money::Decimal, orderbook::Opportunity},
+    pauses::{PauseTarget, Paused},
+    portfolio::StrategyBook,
+    secret::SecretString,
+    state::ExecutionState,
+};
+
+#[derive(Clone)]
+struct Api {
+    control: Control,
+    tokens: Arc<Tokens>,
+    cancel: CancellationToken,
+}
+
+/// The API's secrets; see the module docs for what each may do.
+pub struct Tokens {
+    pub admin: SecretString,
+    pub read: Option<SecretString>,
+    pub webhook: Option<SecretString>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Role {
+    Read,
+    Admin,
+}
+
+impl Api {
+    fn role(&self, token: Option<&str>) -> Option<Role> {
+        let token = token?;
+        if self.tokens.admin.matches(token) {
+            Some(Role::Admin)
+        } else if self.tokens.read.as_ref().is_some_and(|t| t.matches(token)) {
+            Some(Role::Read)
+        } else {
+            None
+        }
+    }
+
+    fn accepts(&self, token: Option<&str>) -> bool {
+        self.role(token).is_some()
+    }
+
+    fn accepts_signal(&self, token: Option<&str>) -> bool {
+        self.role(token) == Some(Role::Admin)
+            || token.is_some_and(|t| self.tokens.webhook.as_ref().is_some_and(|w| w.matches(t)))
+    }
+}
+
+/// Binds `addr` and serves the API until `cancel` fires.
+pub async fn serve(
+    addr: SocketAddr,
+    tokens: Tokens,
+    control: Control,
+    cancel: CancellationToken,
+) -> Result<(), ControlError> {
+    let acceptor = tls::acceptor(&config::get().api)?;
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|source| ControlError::Bind { addr, source })?;
+    let app = router(Api {
+        control,
+        tokens: Arc::new(tokens),
+        cancel: cancel.clone(),
+    });
+    match acceptor {
+        Some(acceptor) => {
+            let listener = tls::TlsListener::spawn(listener, acceptor, cancel.clone())
+                .map_err(|source| ControlError::Bind { addr, source })?;
+            tokio::spawn(run(listener, app, cancel));
+        }
+        None => {
+            tokio::spawn(run(listener, app, cancel));
+        }
+    }
+    Ok(())
+}
+
+async fn run<L>(listener: L, app: Router, cancel: CancellationToken)
+where
+    L: axum::serve::Listener,
+    L::Addr: std::fmt::Debug,
+{
+    let server = axum::serve(listener, app).with_graceful_shutdown(cancel.cancelled_owned());
+    if let Err(e) = server.await {
+        eprintln!("❌ Control API stopped: {}", e);
+    }
+}
+
+fn router(api: Api) -> Router {
+    let read = Router::new()
+        .route("/status", get(status))
+        .route("/positions", get(positions))
+        .route("/opportunities/recent", get(recent_opportunities))
+        .route("/heatmap", get(heatmap))
+        .route_layer(middleware::from_fn_with_state(
+            (api.clone(), Role::Read),
+            authorize,
+        ));
+    let admin = Router::new()
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .route("/kill", post(kill))
+        .route("/symbols", post(symbols))
+        .route("/threshold", post(threshold))
+        .route("/recording", post(recording))
+        .route("/strategies", post(strategies))
+        .route("/pauses", post(pauses))
+        .route_layer(middleware::from_fn_with_state(
+            (api.clone(), Role::Admin),
+            authorize,
+        ));
+    read.merge(admin)
+        .route("/signal", post(signal))
+        .merge(dashboard::routes())
+        .with_state(api)
+}
+
+async fn authorize(
+    State((api, needed)): State<(Api, Role)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match api.role(bearer(&request)) {
+        Some(role) if role >= needed => next.run(request).await,
+        Some(_) => (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "this token is read-only" })),
+        )
+            .into_response(),
+        None => unauthorized(),
+    }
+}
+
+fn bearer(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+}
+
+/// `?token=`, for clients that can't set headers.
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(json!({ "error": "missing or invalid token" })),
+    )
+        .into_response()
+}
+
+impl IntoResponse for ControlError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Self::NoFeed(_)
+            | Self::InvalidThreshold(_)
+            | Self::InvalidSignal(_)
+            | Self::InvalidSymbol(_)
+            | Self::InvalidStrategy(_)
+            | Self::InvalidPause(_)
+            | Self::InvalidQuantity(_) => StatusCode::BAD_REQUEST,
+            Self::TrackerGone | Self::ExecutionOff => StatusCode::SERVICE_UNAVAILABLE,
+            Self::SignalsBacklogged => StatusCode::TOO_MANY_REQUESTS,
+            Self::MissingToken(_) | Self::Bind { .. } | Self::Tls(_) | Self::Recording { .. } => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+// ── Handlers ─────────────────────────────────────────────────────────────────
+
+async fn status(State(api): State<Api>) -> Json<Status> {
+    Json(api.control.status())
+}
+
+async fn positions(State(api): State<Api>) -> Json<ExecutionState> {
+    Json(api.control.positions())
+}
+
+async fn recent_opportunities(
+    State(api): State<Api>,
+) -> Result<Json<Vec<Opportunity>>, ControlError> {
+    Ok(Json(api.control.recent_opportunities().await?))
+}
+
+async fn heatmap(State(api): State<Api>) -> Json<Grid> {
+    Json(api.control.heatmap())
+}
+
+async fn pause(State(api): State<Api>) -> StatusCode {
+    api.control.pause();
+    StatusCode::NO_CONTENT
+}
+
+async fn resume(State(api): State<Api>) -> StatusCode {
+    api.control.resume();
+    StatusCode::NO_CONTENT
+}
+
+/// Answers before the shutdown it starts takes the server down.
+async fn kill(State(api): State<Api>) -> StatusCode {
+    api.control.kill();
+    StatusCode::ACCEPTED
+}
+
+#[derive(Deserialize)]
+struct SymbolsRequest {
+    exchange: ExchangeId,
+    #[serde(default)]
+    subscribe: Vec<String>,
+    #[serde(default)]
+    unsubscribe: Vec<String>,
+}
+
+async fn symbols(
+    State(api): State<Api>,
+    Json(request): Json<SymbolsRequest>,
+) -> Result<Json<serde_json::Value>, ControlError> {
+    let subscribe: Vec<&str> = request.subscribe.iter().map(String::as_str).collect();
+    let unsubscribe: Vec<&str> = request.unsubscribe.iter().map(String::as_str).collect();
+    let symbols = api
+        .control
+        .set_symbols(request.exchange, &subscribe, &unsubscribe)?;
+    Ok(Json(
+        json!({ "exchange": request.exchange, "symbols": symbols }),
+    ))
+}
+
+#[derive(Deserialize)]
+struct ThresholdRequest {
+    alert_percent: Option<Decimal>,
+    execution_percent: Option<Decimal>,
+}
+
+/// Queues the signal; whether it trades shows up in the log and in
+/// `/positions`.
+async fn signal(
+    State(api): State<Api>,
+    Query(query): Query<TokenQuery>,
+    request: Request,
+) -> Result<StatusCode, Response> {
+    let token = query.token.as_deref().or(bearer(&request));
+    if !api.accepts_signal(token) {
+        return Err(unauthorized());
+    }
+    let Json(signal) = Json::<Signal>::from_request(request, &())
+        .await
+        .map_err(IntoResponse::into_response)?;
+    api.control
+        .signal(signal)
+        .map_err(IntoResponse::into_response)?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn threshold(
+    State(api): State<Api>,
+    Json(request): Json<ThresholdRequest>,
+) -> Result<StatusCode, ControlError> {
+    api.control
+        .set_thresholds(request.alert_percent, request.execution_percent)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct RecordingRequest {
+    symbol: String,
+    enabled: bool,
+}
+
+async fn recording(
+    State(api): State<Api>,
+    Json(request): Json<RecordingRequest>,
+) -> Result<Json<serde_json::Value>, ControlError> {
+    let recording = api
+        .control
+        .set_recording(&request.symbol, request.enabled)?;
+    Ok(Json(json!({ "recording": recording })))
+}
+
+#[derive(Deserialize)]
+struct StrategyRequest {
+    name: String,
+    enabled: Option<bool>,
+    threshold_percent: Option<Decimal>,
+    quantity: Option<Decimal>,
+}
+
+async fn strategies(
+    State(api): State<Api>,
+    Json(request): Json<StrategyRequest>,
+) -> Result<Json<StrategyBook>, ControlError> {
+    let update = StrategyUpdate {
+        enabled: request.enabled,
+        threshold_percent: request.threshold_percent,
+        quantity: request.quantity,
+    };
+    Ok(Json(api.control.update_strategy(
+        &request.name,
+        &update,
+        "api",
+    )?))
+}
+
+#[derive(Deserialize)]
+struct PauseRequest {
+    exchange: Option<ExchangeId>,
+    symbol: Option<String>,
+    paused: bool,
+}
+
+async fn pauses(
+    State(api): State<Api>,
+    Json(request): Json<PauseRequest>,
+) -> Result<Json<Paused>, ControlError> {
+    let target = PauseTarget::named(request.exchange, request.symbol.as_deref())?;
+    Ok(Json(api.control.set_paused(target, request.paused, "api")))
+}
@@ -0,0 +1,259 @@
+//! Append-only audit log of order actions (`[engine] audit_log`): one JSON
+//! line per placement, amendment or cancellation sent to an exchange, with
+//! the full parameters and the response or error. Changes to a strategy
+//! made at runtime are logged alongside, with where they came from (`via`)
+//! in place of the exchange.
+//!
+//! Lines are hash-chained: each carries the previous line's hash and the
+//! SHA-256 of its own content including it, so an edited, removed or
+//! reordered line breaks the chain from there on. With `AUDIT_LOG_KEY` set,
+//! every hash is also signed (HMAC-SHA256), so the chain can't be rebuilt
+//! after tampering without the key. `arbitrage-bot audit verify [FILE]`
+//! checks a log.
+
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use anyhow::{bail, Context};
+use chrono::{SecondsFormat, Utc};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{StorageError, TradingError},
+    keys,
+    secret::SecretString,
+};
+
+/// The `prev` of the first line.
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+static LOG: OnceLock<AuditLog> = OnceLock::new();
+
+struct AuditLog {
+    path: PathBuf,
+    key: Option<SecretString>,
+    tail: Mutex<Tail>,
+}
+
+struct Tail {
+    file: File,
+    seq: u64,
+    hash: String,
+}
+
+/// Opens `path` for appending, continuing the chain already in it.
+pub fn open(path: &Path) -> Result<(), StorageError> {
+    let (seq, hash) = match last_line(path)? {
+        Some(line) => {
+            let entry: Value = serde_json::from_str(&line)?;
+            (
+                entry["seq"].as_u64().unwrap_or_default(),
+                entry["hash"].as_str().unwrap_or(GENESIS).to_string(),
+            )
+        }
+        None => (0, GENESIS.to_string()),
+    };
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(StorageError::io(path))?;
+    let _ = LOG.set(AuditLog {
+        path: path.to_path_buf(),
+        key: keys::var("AUDIT_LOG_KEY").filter(|k| !k.is_empty()),
+        tail: Mutex::new(Tail { file, seq, hash }),
+    });
+    Ok(())
+}
+
+/// Appends an order action, if the log is open. `params` are the request
+/// parameters as sent; the signature is left out.
+pub fn record(
+    exchange: &str,
+    action: &str,
+    params: &BTreeMap<String, String>,
+    outcome: &Result<Value, TradingError>,
+) {
+    let Some(log) = LOG.get() else {
+        return;
+    };
+    let params: BTreeMap<_, _> = params.iter().filter(|(k, _)| *k != "signature").collect();
+    let (response, error) = match outcome {
+        Ok(response) => (response.clone(), None),
+        Err(e) => (Value::Null, Some(e.to_string())),
+    };
+    append(
+        log,
+        action,
+        json!({
+            "exchange": exchange,
+            "action": action,
+            "params": params,
+            "response": response,
+            "error": error,
+        }),
+    );
+}
+
+/// Appends an operator's change, if the log is open: `via` is where it came
+/// from, e.g. `api` or `telegram:<user id>`.
+pub fn record_change(via: &str, action: &str, params: &Value, outcome: &Result<Value, String>) {
+    let Some(log) = LOG.get() else {
+        return;
+    };
+    let (response, error) = match outcome {
+        Ok(response) => (response.clone(), None),
+        Err(e) => (Value::Null, Some(e.clone())),
+    };
+    append(
+        log,
+        action,
+        json!({
+            "via": via,
+            "action": action,
+            "params": params,
+            "response": response,
+            "error": error,
+        }),
+    );
+}
+
+/// Chains `entry` onto the log and writes it.
+fn append(log: &AuditLog, action: &str, mut entry: Value) {
+    let mut tail = log.tail.lock().unwrap_or_else(|e| e.into_inner());
+    entry["seq"] = (tail.seq + 1).into();
+    entry["time"] = Utc::now()
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+        .into();
+    entry["prev"] = tail.hash.clone().into();
+    let hash = hash(&entry);
+    entry["hash"] = hash.clone().into();
+    if let Some(key) = &log.key {
+        entry["sig"] = sign(key, &hash).into();
+    }
+    let written = writeln!(tail.file, "{}", entry).and_then(|_| tail.file.sync_data());
+    match written {
+        Ok(()) => {
+            tail.seq += 1;
+            tail.hash = hash;
+        }
+        Err(e) => eprintln!(
+            "❌ Couldn't record {} in the audit log {}: {}",
+            action,
+            log.path.display(),
+            e
+        ),
+    }
+}
+
+/// SHA-256 of the entry without `hash` and `sig`, keys sorted.
+fn hash(entry: &Value) -> String {
+    let mut content = entry.clone();
+    if let Some(map) = content.as_object_mut() {
+        map.remove("hash");
+        map.remove("sig");
+    }
+    hex::encode(Sha256::digest(content.to_string().as_bytes()))
+}
+
+fn sign(key: &SecretString, hash: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.expose().as_bytes()).expect("HMAC takes any key length");
+    mac.update(hash.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn last_line(path: &Path) -> Result<Option<String>, StorageError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(StorageError::io(path)(e)),
+    };
+    let mut last = None;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(StorageError::io(path))?;
+        if !line.trim().is_empty() {
+            last = Some(line);
+        }
+    }
+    Ok(last)
+}
+
+/// Checks the chain, and the signatures when `key` is given. Returns the
+/// number of entries.
+pub fn verify(path: &Path, key: Option<&SecretString>) -> anyhow::Result<u64> {
+    let file = File::open(path).with_context(|| format!("reading {}", path.display()))?;
+    let mut prev = GENESIS.to_string();
+    let mut count = 0;
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let n = n + 1;
+        let entry: Value =
+            serde_json::from_str(&line).with_context(|| format!("line {} isn't JSON", n))?;
+        count += 1;
+        if entry["seq"].as_u64() != Some(count) {
+            bail!("line {}: expected seq {}, found {}", n, count, entry["seq"]);
+        }
+        if entry["prev"].as_str() != Some(prev.as_str()) {
+            bail!("line {}: doesn't follow the previous line", n);
+        }
+        let hash = hash(&entry);
+        if entry["hash"].as_str() != Some(hash.as_str()) {
+            bail!("line {}: content doesn't match its hash", n);
+        }
+        if let Some(key) = key {
+            match entry["sig"].as_str() {
+                Some(sig) if SecretString::from(sign(key, &hash)).matches(sig) => {}
+                Some(_) => bail!("line {}: invalid signature", n),
+                None => bail!("line {}: not signed", n),
+            }
+        }
+        prev = hash;
+    }
+    Ok(count)
+}
+
+/// `arbitrage-bot audit verify [FILE]`.
+pub fn run_cli(args: &[String], default: Option<&Path>) {
+    if args.first().map(String::as_str) != Some("verify") {
+        eprintln!("Usage: arbitrage-bot audit verify [FILE]");
+        return;
+    }
+    let Some(path) = args
+        .get(1)
+        .map(PathBuf::from)
+        .or(default.map(Path::to_path_buf))
+    else {
+        eprintln!("❌ No file given and [engine] audit_log is not set");
+        std::process::exit(1);
+    };
+    let key = keys::var("AUDIT_LOG_KEY").filter(|k| !k.is_empty());
+    match verify(&path, key.as_ref()) {
+        Ok(count) => {
+            println!(
+                "✅ {}: {} entries, chain intact{}",
+                path.display(),
+                count,
+                if key.is_some() {
+                    ", signatures valid"
+                } else {
+                    " (signatures not checked: AUDIT_LOG_KEY is not set)"
+                }
+            );
+        }
+        Err(e) => {
+            eprintln!("❌ {}: {:#}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! PnL model for the spot-perp basis strategy (long spot, short perpetual).
+
+use rust_decimal_macros::dec;
+
+use super::funding::FundingRate;
+use crate::models::money::Decimal;
+
+/// A single basis position held between two points in time.
+#[derive(Debug, Clone)]
+pub struct BasisTrade {
+    /// Entry time in milliseconds since epoch.
+    pub entry_time: i64,
+    /// Exit time in milliseconds since epoch.
+    pub exit_time: i64,
+    /// Notional per leg in quote currency.
+    pub notional: Decimal,
+    /// (perp - spot) / spot at entry, in percent.
+    pub entry_basis_percent: Decimal,
+    /// (perp - spot) / spot at exit, in percent.
+    pub exit_basis_percent: Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BasisPnl {
+    /// PnL from the basis converging (or widening) between entry and exit.
+    pub basis_pnl: Decimal,
+    /// Net funding received by the short perp leg.
+    pub funding_pnl: Decimal,
+    /// Number of funding settlements that fell inside the holding period.
+    pub funding_events: usize,
+    /// Entry + exit fees on both legs.
+    pub fees: Decimal,
+    pub total: Decimal,
+}
+
+/// Computes PnL for `trade`, applying every funding settlement in `(entry_time, exit_time]`.
+///
+/// `round_trip_fee_percent` is charged once per leg on the notional.
+pub fn evaluate(
+    trade: &BasisTrade,
+    rates: &[FundingRate],
+    round_trip_fee_percent: Decimal,
+) -> BasisPnl {
+    let basis_pnl =
+        (trade.entry_basis_percent - trade.exit_basis_percent) / dec!(100) * trade.notional;
+
+    let held: Vec<&FundingRate> = rates
+        .iter()
+        .filter(|r| r.funding_time > trade.entry_time && r.funding_time <= trade.exit_time)
+        .collect();
+    // Positive funding is paid by longs to shorts, so the short perp leg receives it.
+    let funding_pnl: Decimal = held.iter().map(|r| r.rate * trade.notional).sum();
+
+    let fees = dec!(2) * round_trip_fee_percent / dec!(100) * trade.notional;
+
+    BasisPnl {
+        basis_pnl,
+        funding_pnl,
+        funding_events: held.len(),
+        fees,
+        total: basis_pnl + funding_pnl - fees,
+    }
+}
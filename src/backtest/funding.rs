@@ -0,0 +1,136 @@
+//! Historical funding-rate ingestion from Binance and Bybit REST.
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::{
+    config,
+    constants::{exchange_names, urls},
+    models::money::Decimal,
+};
+
+/// A single settled funding event for a perpetual contract.
+#[derive(Debug, Clone)]
+pub struct FundingRate {
+    pub exchange: &'static str,
+    pub symbol: String,
+    /// Settlement time in milliseconds since epoch.
+    pub funding_time: i64,
+    /// Funding rate for the interval (e.g. 0.0001 = 0.01%). Positive means longs pay shorts.
+    pub rate: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceFundingRow {
+    funding_time: i64,
+    funding_rate: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitFundingResponse {
+    ret_code: i32,
+    ret_msg: String,
+    result: Option<BybitFundingResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BybitFundingResult {
+    list: Vec<BybitFundingRow>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitFundingRow {
+    funding_rate: String,
+    funding_rate_timestamp: String,
+}
+
+/// Fetches Binance USDⓈ-M funding history in `[start_ms, end_ms]`, oldest first.
+pub async fn fetch_binance(
+    client: &reqwest::Client,
+    symbol: &str,
+    start_ms: i64,
+    end_ms: i64,
+) -> Result<Vec<FundingRate>> {
+    let mut rates = Vec::new();
+    let mut cursor = start_ms;
+
+    while cursor <= end_ms {
+        let url = format!(
+            "{}/fapi/v1/fundingRate?symbol={}&startTime={}&endTime={}&limit=1000",
+            config::get().network.endpoint(urls::BINANCE_REST_FUTURES),
+            symbol.to_uppercase(),
+            cursor,
+            end_ms
+        );
+        let rows: Vec<BinanceFundingRow> = client.get(&url).send().await?.json().await?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        cursor = last.funding_time + 1;
+        let page_len = rows.len();
+
+        for row in rows {
+            rates.push(FundingRate {
+                exchange: exchange_names::BINANCE,
+                symbol: symbol.to_uppercase(),
+                funding_time: row.funding_time,
+                rate: row.funding_rate.parse()?,
+            });
+        }
+        if page_len < 1000 {
+            break;
+        }
+    }
+
+    Ok(rates)
+}
+
+/// Fetches Bybit linear funding history in `[start_ms, end_ms]`, oldest first.
+pub async fn fetch_bybit(
+    client: &reqwest::Client,
+    symbol: &str,
+    start_ms: i64,
+    end_ms: i64,
+) -> Result<Vec<FundingRate>> {
+    let mut rates = Vec::new();
+    // Bybit returns newest first, so page backwards from the end of the range.
+    let mut cursor = end_ms;
+
+    while cursor >= start_ms {
+        let url = format!(
+            "{}/v5/market/funding/history?category=linear&symbol={}&startTime={}&endTime={}&limit=200",
+            config::get().network.endpoint(urls::BYBIT_REST),
+            symbol.to_uppercase(),
+            start_ms,
+            cursor
+        );
+        let resp: BybitFundingResponse = client.get(&url).send().await?.json().await?;
+        if resp.ret_code != 0 {
+            return Err(anyhow!("Bybit funding history error: {}", resp.ret_msg));
+        }
+        let rows = resp.result.map(|r| r.list).unwrap_or_default();
+        let page_len = rows.len();
+
+        let mut oldest = cursor;
+        for row in rows {
+            let funding_time: i64 = row.funding_rate_timestamp.parse()?;
+            oldest = oldest.min(funding_time);
+            rates.push(FundingRate {
+                exchange: exchange_names::BYBIT,
+                symbol: symbol.to_uppercase(),
+                funding_time,
+                rate: row.funding_rate.parse()?,
+            });
+        }
+        if page_len < 200 {
+            break;
+        }
+        cursor = oldest - 1;
+    }
+
+    rates.sort_by_key(|r| r.funding_time);
+    Ok(rates)
+}
@@ -0,0 +1,262 @@
+//! Historical spreads from Binance and Bybit 1m klines, for calibrating
+//! thresholds before any spreads have been recorded.
+//!
+//! Each minute both venues have a kline for, the spread is taken between
+//! their closes the way the tracker takes it between mids: the difference
+//! relative to the Binance close, in percent. A close is a last trade
+//! rather than a mid, so these spreads run somewhat wider than recorded
+//! ones, and anything that opened and closed within the minute is missed.
+
+use std::{fmt, fs::File, io::Write, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{replay::SpreadSample, walk_forward};
+use crate::{
+    config,
+    constants::{exchange_names, urls},
+    models::money::{self, Decimal},
+};
+
+/// Most klines Binance returns per request.
+const BINANCE_PAGE: usize = 1500;
+/// Most klines Bybit returns per request.
+const BYBIT_PAGE: usize = 1000;
+const MINUTE_MS: i64 = 60_000;
+
+/// One minute's kline, reduced to what a spread needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Kline {
+    /// Start of the minute, in milliseconds since epoch.
+    pub open_time: i64,
+    pub close: Decimal,
+}
+
+/// Reads a Binance `GET /fapi/v1/klines` answer: one array per kline,
+/// open time first and close fifth.
+pub fn binance_klines(body: &str) -> Result<Vec<Kline>> {
+    let rows: Vec<Vec<Value>> = serde_json::from_str(body)?;
+    rows.iter()
+        .map(|row| {
+            let open_time = row.first().and_then(Value::as_i64);
+            let close = row.get(4).and_then(Value::as_str).and_then(money::parse);
+            match (open_time, close) {
+                (Some(open_time), Some(close)) => Ok(Kline { open_time, close }),
+                _ => Err(anyhow!(
+                    "Binance kline without an open time and close: {:?}",
+                    row
+                )),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitKlineResponse {
+    ret_code: i32,
+    ret_msg: String,
+    result: Option<BybitKlineResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BybitKlineResult {
+    /// Absent from error answers, whose `result` is `{}`.
+    #[serde(default)]
+    list: Vec<Vec<String>>,
+}
+
+/// Reads a Bybit `GET /v5/market/kline` answer, whose klines are string
+/// arrays with the start time first and close fifth, newest first; returns
+/// them oldest first.
+pub fn bybit_klines(body: &str) -> Result<Vec<Kline>> {
+    let resp: BybitKlineResponse = serde_json::from_str(body)?;
+    if resp.ret_code != 0 {
+        return Err(anyhow!("Bybit kline error: {}", resp.ret_msg));
+    }
+    let mut klines = resp
+        .result
+        .map(|r| r.list)
+        .unwrap_or_default()
+        .iter()
+        .map(|row| {
+            let open_time = row.first().and_then(|t| t.parse().ok());
+            let close = row.get(4).and_then(|c| money::parse(c));
+            match (open_time, close) {
+                (Some(open_time), Some(close)) => Ok(Kline { open_time, close }),
+                _ => Err(anyhow!(
+                    "Bybit kline without a start time and close: {:?}",
+                    row
+                )),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    klines.reverse();
+    Ok(klines)
+}
+
+/// Fetches Binance USDⓈ-M 1m klines in `[start_ms, end_ms]`, oldest first.
+pub async fn fetch_binance(
+    client: &reqwest::Client,
+    symbol: &str,
+    start_ms: i64,
+    end_ms: i64,
+) -> Result<Vec<Kline>> {
+    let mut klines = Vec::new();
+    let mut cursor = start_ms;
+
+    while cursor <= end_ms {
+        let url = format!(
+            "{}/fapi/v1/klines?symbol={}&interval=1m&startTime={}&endTime={}&limit={}",
+            config::get().network.endpoint(urls::BINANCE_REST_FUTURES),
+            symbol.to_uppercase(),
+            cursor,
+            end_ms,
+            BINANCE_PAGE
+        );
+        let page = binance_klines(&client.get(&url).send().await?.text().await?)?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        cursor = last.open_time + MINUTE_MS;
+        let page_len = page.len();
+        klines.extend(page);
+        if page_len < BINANCE_PAGE {
+            break;
+        }
+    }
+
+    Ok(klines)
+}
+
+/// Fetches Bybit linear 1m klines in `[start_ms, end_ms]`, oldest first.
+pub async fn fetch_bybit(
+    client: &reqwest::Client,
+    symbol: &str,
+    start_ms: i64,
+    end_ms: i64,
+) -> Result<Vec<Kline>> {
+    let mut klines = Vec::new();
+    // Bybit returns newest first, so page backwards from the end of the range.
+    let mut cursor = end_ms;
+
+    while cursor >= start_ms {
+        let url = format!(
+            "{}/v5/market/kline?category=linear&symbol={}&interval=1&start={}&end={}&limit={}",
+            config::get().network.endpoint(urls::BYBIT_REST),
+            symbol.to_uppercase(),
+            start_ms,
+            cursor,
+            BYBIT_PAGE
+        );
+        let page = bybit_klines(&client.get(&url).send().await?.text().await?)?;
+        let Some(oldest) = page.first() else {
+            break;
+        };
+        cursor = oldest.open_time - 1;
+        let page_len = page.len();
+        klines.extend(page);
+        if page_len < BYBIT_PAGE {
+            break;
+        }
+    }
+
+    klines.sort_by_key(|k| k.open_time);
+    klines.dedup_by_key(|k| k.open_time);
+    Ok(klines)
+}
+
+/// The spread in every minute both `binance` and `bybit` have a kline for,
+/// as spread log rows (timestamps in seconds).
+pub fn spreads(symbol: &str, binance: &[Kline], bybit: &[Kline]) -> Vec<SpreadSample> {
+    let mut samples = Vec::new();
+    let mut bybit = bybit.iter().peekable();
+    for a in binance {
+        while bybit.next_if(|b| b.open_time < a.open_time).is_some() {}
+        let Some(b) = bybit.next_if(|b| b.open_time == a.open_time) else {
+            continue;
+        };
+        let Some(diff) = money::percent_of((a.close - b.close).abs(), a.close) else {
+            continue;
+        };
+        samples.push(SpreadSample {
+            timestamp: a.open_time / 1000,
+            symbol: symbol.to_uppercase(),
+            exchange_a: exchange_names::BINANCE.to_string(),
+            exchange_b: exchange_names::BYBIT.to_string(),
+            diff_percent: f64::try_from(diff).unwrap_or_default(),
+        });
+    }
+    samples
+}
+
+/// How a symbol's historical spreads are distributed, in percent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    pub symbol: String,
+    pub samples: usize,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+    /// The threshold `quantile` of the spreads calibrates to, as
+    /// `backtest walk-forward` would pick it.
+    pub threshold: f64,
+}
+
+impl Distribution {
+    /// `None` without any samples.
+    pub fn of(
+        symbol: &str,
+        samples: &[SpreadSample],
+        quantile: f64,
+        min_threshold: f64,
+    ) -> Option<Self> {
+        let diffs: Vec<f64> = samples.iter().map(|s| s.diff_percent).collect();
+        let at = |q| walk_forward::calibrate_threshold(&diffs, q, 0.0);
+        Some(Self {
+            symbol: symbol.to_uppercase(),
+            samples: diffs.len(),
+            mean: diffs.iter().sum::<f64>() / diffs.len() as f64,
+            p50: at(0.5)?,
+            p90: at(0.9)?,
+            p99: at(0.99)?,
+            max: at(1.0)?,
+            threshold: walk_forward::calibrate_threshold(&diffs, quantile, min_threshold)?,
+        })
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<14} {:>7} {:>8.4} {:>8.4} {:>8.4} {:>8.4} {:>8.4} {:>9.3}",
+            self.symbol,
+            self.samples,
+            self.mean,
+            self.p50,
+            self.p90,
+            self.p99,
+            self.max,
+            self.threshold
+        )
+    }
+}
+
+/// Writes `samples` as a spread log `backtest walk-forward` can read.
+pub fn write_csv(path: &Path, samples: &[SpreadSample]) -> Result<()> {
+    let mut file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    writeln!(file, "symbol,exchange_a,exchange_b,diff_percent,timestamp")?;
+    for s in samples {
+        writeln!(
+            file,
+            "{},{},{},{:.4}%,{}",
+            s.symbol, s.exchange_a, s.exchange_b, s.diff_percent, s.timestamp
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,227 @@
+//! Offline backtesting over recorded spread data.
+//!
+//! # Usage
+//! ```text
+//! arbitrage-bot backtest walk-forward [CSV_PATH] [--train-hours N] [--test-hours N] [--quantile Q]
+//! arbitrage-bot backtest funding SYMBOL [--days N] [--notional N] [--entry-basis P] [--exit-basis P]
+//! arbitrage-bot backtest klines [SYMBOL...] [--days N] [--quantile Q] [--out CSV_PATH]
+//! ```
+
+pub mod basis;
+pub mod funding;
+pub mod klines;
+pub mod replay;
+pub mod walk_forward;
+
+use std::path::Path;
+
+use rust_decimal_macros::dec;
+
+use crate::{
+    constants::{backtest as bt_const, exchange_names, notifications as notif_const, symbols},
+    net,
+};
+
+/// Parses `--flag value` pairs following the backtest subcommand.
+fn flag_value<T: std::str::FromStr>(args: &[String], flag: &str) -> Option<T> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .and_then(|v| v.parse().ok())
+}
+
+/// Entry point for `arbitrage-bot backtest ...`.
+pub async fn run_cli(args: &[String]) {
+    match args.first().map(String::as_str) {
+        Some("walk-forward") => {
+            let rest = &args[1..];
+            let path = rest
+                .first()
+                .filter(|a| !a.starts_with("--"))
+                .cloned()
+                .unwrap_or_else(|| "arbitrage.csv".to_string());
+
+            let cfg = walk_forward::WalkForwardConfig {
+                train_window_secs: flag_value::<i64>(rest, "--train-hours")
+                    .map(|h| h * 3600)
+                    .unwrap_or(bt_const::DEFAULT_TRAIN_WINDOW_SECS),
+                test_window_secs: flag_value::<i64>(rest, "--test-hours")
+                    .map(|h| h * 3600)
+                    .unwrap_or(bt_const::DEFAULT_TEST_WINDOW_SECS),
+                quantile: flag_value(rest, "--quantile").unwrap_or(bt_const::DEFAULT_QUANTILE),
+                min_threshold: min_threshold(),
+            };
+            if let Err(e) = cfg.validate() {
+                eprintln!("❌ {}", e);
+                return;
+            }
+
+            let samples = match replay::load_csv(&path) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("❌ Failed to load {}: {:#}", path, e);
+                    return;
+                }
+            };
+            println!(
+                "📈 Walk-forward over {} samples from {} (train {}h / test {}h, q={})",
+                samples.len(),
+                path,
+                cfg.train_window_secs / 3600,
+                cfg.test_window_secs / 3600,
+                cfg.quantile
+            );
+            match walk_forward::run(&samples, &cfg) {
+                Ok(results) => walk_forward::print_report(&results),
+                Err(e) => eprintln!("❌ {}", e),
+            }
+        }
+        Some("funding") => {
+            let rest = &args[1..];
+            let Some(symbol) = rest.first().filter(|a| !a.starts_with("--")) else {
+                eprintln!("Usage: arbitrage-bot backtest funding SYMBOL [--days N] [--notional N]");
+                return;
+            };
+            run_funding(symbol, rest).await;
+        }
+        Some("klines") => run_klines(&args[1..]).await,
+        _ => {
+            eprintln!("Usage: arbitrage-bot backtest walk-forward [CSV_PATH] [--train-hours N] [--test-hours N] [--quantile Q]");
+            eprintln!("       arbitrage-bot backtest funding SYMBOL [--days N] [--notional N] [--entry-basis P] [--exit-basis P]");
+            eprintln!("       arbitrage-bot backtest klines [SYMBOL...] [--days N] [--quantile Q] [--out CSV_PATH]");
+        }
+    }
+}
+
+/// The floor under calibrated thresholds.
+fn min_threshold() -> f64 {
+    // Calibration is statistics over recorded diffs, so it stays in f64.
+    f64::try_from(notif_const::DIFF_THRESHOLD / dec!(100)).unwrap_or_default()
+}
+
+/// Estimates the Binance/Bybit spread distribution of each symbol over the
+/// last `--days` from 1m klines; every symbol scanned on both when none are
+/// given.
+async fn run_klines(args: &[String]) {
+    let mut wanted: Vec<String> = args
+        .iter()
+        .take_while(|a| !a.starts_with("--"))
+        .map(|s| s.to_uppercase())
+        .collect();
+    if wanted.is_empty() {
+        wanted = symbols::BYBIT_FUTURES
+            .iter()
+            .filter(|s| {
+                symbols::BINANCE_FUTURES
+                    .iter()
+                    .any(|b| b.eq_ignore_ascii_case(s))
+            })
+            .map(|s| s.to_string())
+            .collect();
+    }
+    let days: i64 = flag_value(args, "--days").unwrap_or(bt_const::DEFAULT_KLINE_DAYS);
+    let quantile = flag_value(args, "--quantile").unwrap_or(bt_const::DEFAULT_QUANTILE);
+    let end_ms = chrono::Utc::now().timestamp_millis();
+    let start_ms = end_ms - days * 86_400_000;
+
+    println!(
+        "📈 Binance/Bybit spreads from 1m klines over {} days (q={})",
+        days, quantile
+    );
+    println!(
+        "{:<14} {:>7} {:>8} {:>8} {:>8} {:>8} {:>8} {:>9}",
+        "symbol", "minutes", "mean%", "p50%", "p90%", "p99%", "max%", "thresh%"
+    );
+    let client = net::http_client();
+    let mut all = Vec::new();
+    for symbol in &wanted {
+        let binance = klines::fetch_binance(&client, symbol, start_ms, end_ms).await;
+        let bybit = klines::fetch_bybit(&client, symbol, start_ms, end_ms).await;
+        let (binance, bybit) = match (binance, bybit) {
+            (Ok(binance), Ok(bybit)) => (binance, bybit),
+            (Err(e), _) => {
+                eprintln!(
+                    "❌ {} {} kline fetch failed: {:#}",
+                    exchange_names::BINANCE,
+                    symbol,
+                    e
+                );
+                continue;
+            }
+            (_, Err(e)) => {
+                eprintln!(
+                    "❌ {} {} kline fetch failed: {:#}",
+                    exchange_names::BYBIT,
+                    symbol,
+                    e
+                );
+                continue;
+            }
+        };
+        let samples = klines::spreads(symbol, &binance, &bybit);
+        match klines::Distribution::of(symbol, &samples, quantile, min_threshold()) {
+            Some(distribution) => println!("{}", distribution),
+            None => eprintln!("⚠️ No minute of {} traded on both venues", symbol),
+        }
+        all.extend(samples);
+    }
+
+    if let Some(path) = flag_value::<String>(args, "--out") {
+        all.sort_by_key(|s| s.timestamp);
+        match klines::write_csv(Path::new(&path), &all) {
+            Ok(()) => println!(
+                "💾 {} spreads written to {}; `backtest walk-forward {}` replays them",
+                all.len(),
+                path,
+                path
+            ),
+            Err(e) => eprintln!("❌ Failed to write {}: {:#}", path, e),
+        }
+    }
+}
+
+/// Backtests holding a spot-perp basis position on each venue over the last `--days`.
+async fn run_funding(symbol: &str, args: &[String]) {
+    let days: i64 = flag_value(args, "--days").unwrap_or(bt_const::DEFAULT_FUNDING_DAYS);
+    let end_ms = chrono::Utc::now().timestamp_millis();
+    let start_ms = end_ms - days * 86_400_000;
+
+    let trade = basis::BasisTrade {
+        entry_time: start_ms,
+        exit_time: end_ms,
+        notional: flag_value(args, "--notional").unwrap_or(bt_const::DEFAULT_NOTIONAL),
+        entry_basis_percent: flag_value(args, "--entry-basis").unwrap_or_default(),
+        exit_basis_percent: flag_value(args, "--exit-basis").unwrap_or_default(),
+    };
+
+    let client = net::http_client();
+    let fetched = [
+        (
+            exchange_names::BINANCE,
+            funding::fetch_binance(&client, symbol, start_ms, end_ms).await,
+        ),
+        (
+            exchange_names::BYBIT,
+            funding::fetch_bybit(&client, symbol, start_ms, end_ms).await,
+        ),
+    ];
+
+    println!(
+        "📈 Basis backtest for {} over {} days, notional {:.2} per leg",
+        symbol.to_uppercase(),
+        days,
+        trade.notional
+    );
+    for (exchange, result) in fetched {
+        match result {
+            Ok(rates) => {
+                let pnl = basis::evaluate(&trade, &rates, bt_const::DEFAULT_ROUND_TRIP_FEE_PERCENT);
+                println!(
+                    "{:<8} | fundings: {:>4} | funding: {:>10.4} | basis: {:>10.4} | fees: {:>8.4} | total: {:>10.4}",
+                    exchange, pnl.funding_events, pnl.funding_pnl, pnl.basis_pnl, pnl.fees, pnl.total
+                );
+            }
+            Err(e) => eprintln!("❌ {} funding fetch failed: {:#}", exchange, e),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Loading of recorded spread rows for offline evaluation.
+//!
+//! The input format is the one produced by [`crate::logger::CsvLogger`]. Columns
+//! are resolved by header name so older recordings (e.g. with an extra
+//! `market_type` column) still load.
+
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+
+/// One recorded cross-exchange spread observation.
+#[derive(Debug, Clone)]
+pub struct SpreadSample {
+    pub timestamp: i64,
+    pub symbol: String,
+    pub exchange_a: String,
+    pub exchange_b: String,
+    pub diff_percent: f64,
+}
+
+/// Reads a spread CSV and returns its rows sorted by timestamp.
+pub fn load_csv(path: &str) -> Result<Vec<SpreadSample>> {
+    let content = fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+    let mut lines = content.lines();
+
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or_else(|| anyhow!("{} is empty", path))?
+        .split(',')
+        .collect();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim() == name)
+            .ok_or_else(|| anyhow!("{} has no `{}` column", path, name))
+    };
+
+    let symbol_idx = column("symbol")?;
+    let exchange_a_idx = column("exchange_a")?;
+    let exchange_b_idx = column("exchange_b")?;
+    let diff_idx = column("diff_percent")?;
+    let timestamp_idx = column("timestamp")?;
+
+    let mut samples = Vec::new();
+    for (line_no, line) in lines.enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').collect();
+        let field = |idx: usize| {
+            fields
+                .get(idx)
+                .map(|f| f.trim())
+                .ok_or_else(|| anyhow!("line {}: missing column {}", line_no + 2, idx))
+        };
+
+        samples.push(SpreadSample {
+            timestamp: field(timestamp_idx)?
+                .parse()
+                .with_context(|| format!("line {}: bad timestamp", line_no + 2))?,
+            symbol: field(symbol_idx)?.to_string(),
+            exchange_a: field(exchange_a_idx)?.to_string(),
+            exchange_b: field(exchange_b_idx)?.to_string(),
+            // The logger writes the diff as e.g. `5.23%`
+            diff_percent: field(diff_idx)?
+                .trim_end_matches('%')
+                .parse()
+                .with_context(|| format!("line {}: bad diff_percent", line_no + 2))?,
+        });
+    }
+
+    samples.sort_by_key(|s| s.timestamp);
+    Ok(samples)
+}
@@ -0,0 +1,197 @@
+//! Walk-forward evaluation of threshold calibration.
+//!
+//! Thresholds are calibrated on a training window and then evaluated on the
+//! window that immediately follows it, after which both windows roll forward by
+//! one test window. This keeps every evaluation strictly out-of-sample.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+
+use super::replay::SpreadSample;
+
+#[derive(Debug, Clone)]
+pub struct WalkForwardConfig {
+    /// Length of the calibration window in seconds.
+    pub train_window_secs: i64,
+    /// Length of the evaluation window in seconds (also the roll step).
+    pub test_window_secs: i64,
+    /// Quantile of the training diffs used as the threshold (e.g. 0.95).
+    pub quantile: f64,
+    /// Thresholds never go below this value (in percent).
+    pub min_threshold: f64,
+}
+
+impl WalkForwardConfig {
+    /// Both windows have to be positive, or the folds never roll forward.
+    pub fn validate(&self) -> Result<()> {
+        if self.train_window_secs <= 0 || self.test_window_secs <= 0 {
+            bail!("--train-hours and --test-hours must be positive");
+        }
+        Ok(())
+    }
+}
+
+/// Result of a single calibrate → evaluate step.
+#[derive(Debug, Clone)]
+pub struct FoldResult {
+    pub symbol: String,
+    /// Exchange pair the spread was measured on, e.g. `binance/bybit`.
+    pub exchanges: String,
+    pub fold: usize,
+    pub test_start: i64,
+    pub threshold: f64,
+    pub train_samples: usize,
+    pub test_samples: usize,
+    pub test_hits: usize,
+    /// Fraction of training samples at or above the threshold.
+    pub expected_hit_rate: f64,
+    /// Fraction of test samples at or above the threshold.
+    pub realized_hit_rate: f64,
+    /// Mean amount (in pp) by which test hits exceeded the threshold.
+    pub mean_test_excess: f64,
+}
+
+/// Threshold that `quantile` of the given diffs fall below, floored at `min_threshold`.
+pub fn calibrate_threshold(diffs: &[f64], quantile: f64, min_threshold: f64) -> Option<f64> {
+    if diffs.is_empty() {
+        return None;
+    }
+    let mut sorted = diffs.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let q = quantile.clamp(0.0, 1.0);
+    let idx = ((sorted.len() - 1) as f64 * q).round() as usize;
+    Some(sorted[idx].max(min_threshold))
+}
+
+fn hit_rate(diffs: &[f64], threshold: f64) -> (usize, f64) {
+    let hits = diffs.iter().filter(|d| **d >= threshold).count();
+    let rate = if diffs.is_empty() {
+        0.0
+    } else {
+        hits as f64 / diffs.len() as f64
+    };
+    (hits, rate)
+}
+
+/// Runs walk-forward evaluation independently for every symbol and exchange pair in `samples`.
+///
+/// `samples` must be sorted by timestamp (as returned by `replay::load_csv`).
+pub fn run(samples: &[SpreadSample], cfg: &WalkForwardConfig) -> Result<Vec<FoldResult>> {
+    cfg.validate()?;
+    let mut by_pair: BTreeMap<(&str, &str, &str), Vec<&SpreadSample>> = BTreeMap::new();
+    for sample in samples {
+        by_pair
+            .entry((&sample.symbol, &sample.exchange_a, &sample.exchange_b))
+            .or_default()
+            .push(sample);
+    }
+
+    let mut results = Vec::new();
+    for ((symbol, exchange_a, exchange_b), rows) in by_pair {
+        let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+            continue;
+        };
+
+        let mut train_start = first.timestamp;
+        let mut fold = 0;
+        while train_start + cfg.train_window_secs < last.timestamp {
+            let test_start = train_start + cfg.train_window_secs;
+            let test_end = test_start + cfg.test_window_secs;
+
+            let window = |from: i64, to: i64| -> Vec<f64> {
+                rows.iter()
+                    .filter(|s| s.timestamp >= from && s.timestamp < to)
+                    .map(|s| s.diff_percent)
+                    .collect()
+            };
+            let train = window(train_start, test_start);
+            let test = window(test_start, test_end);
+
+            if let Some(threshold) = calibrate_threshold(&train, cfg.quantile, cfg.min_threshold) {
+                let (_, expected_hit_rate) = hit_rate(&train, threshold);
+                let (test_hits, realized_hit_rate) = hit_rate(&test, threshold);
+                let excess: f64 = test
+                    .iter()
+                    .filter(|d| **d >= threshold)
+                    .map(|d| d - threshold)
+                    .sum();
+
+                results.push(FoldResult {
+                    symbol: symbol.to_string(),
+                    exchanges: format!("{}/{}", exchange_a, exchange_b),
+                    fold,
+                    test_start,
+                    threshold,
+                    train_samples: train.len(),
+                    test_samples: test.len(),
+                    test_hits,
+                    expected_hit_rate,
+                    realized_hit_rate,
+                    mean_test_excess: if test_hits > 0 {
+                        excess / test_hits as f64
+                    } else {
+                        0.0
+                    },
+                });
+                fold += 1;
+            }
+
+            train_start += cfg.test_window_secs;
+        }
+    }
+
+    Ok(results)
+}
+
+pub fn print_report(results: &[FoldResult]) {
+    println!(
+        "{:<14} {:<14} {:>4} {:>20} {:>9} {:>7} {:>7} {:>6} {:>9} {:>9} {:>8}",
+        "symbol",
+        "exchanges",
+        "fold",
+        "test_start",
+        "thresh%",
+        "n_train",
+        "n_test",
+        "hits",
+        "exp_rate",
+        "real_rate",
+        "excess"
+    );
+    for r in results {
+        let start = chrono::DateTime::from_timestamp(r.test_start, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| r.test_start.to_string());
+        println!(
+            "{:<14} {:<14} {:>4} {:>20} {:>9.3} {:>7} {:>7} {:>6} {:>9.3} {:>9.3} {:>8.3}",
+            r.symbol,
+            r.exchanges,
+            r.fold,
+            start,
+            r.threshold,
+            r.train_samples,
+            r.test_samples,
+            r.test_hits,
+            r.expected_hit_rate,
+            r.realized_hit_rate,
+            r.mean_test_excess
+        );
+    }
+
+    let evaluated: Vec<&FoldResult> = results.iter().filter(|r| r.test_samples > 0).collect();
+    if evaluated.is_empty() {
+        println!("No folds with out-of-sample data — record a longer history first.");
+        return;
+    }
+    let n = evaluated.len() as f64;
+    let mean_expected = evaluated.iter().map(|r| r.expected_hit_rate).sum::<f64>() / n;
+    let mean_realized = evaluated.iter().map(|r| r.realized_hit_rate).sum::<f64>() / n;
+    println!(
+        "--- {} folds | mean expected hit rate {:.3} | mean realized hit rate {:.3} ---",
+        evaluated.len(),
+        mean_expected,
+        mean_realized
+    );
+}
@@ -0,0 +1,61 @@
+//! Headless spread monitoring: feeds, the tracker, alerts, the spread log and
+//! recordings, with execution always off.
+//!
+//! It runs the same pipeline as `arbitrage-bot` (see `engine`) but ignores
+//! `[engine.execution]`, so it never needs API keys with trade permission.
+//! Built with the `monitor` feature set instead of the defaults, the order
+//! client and request signing aren't compiled in at all:
+//!
+//! ```bash
+//! cargo build --release --bin spread-monitor --no-default-features --features monitor
+//! ```
+
+use dotenv::dotenv;
+
+use arbitrage_bot::{
+    config::{self, Config},
+    engine::Engine,
+    keys, runtime,
+};
+
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+
+    let mut config = match Config::load_default() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ Invalid configuration: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    if config.engine.execution.enabled {
+        eprintln!("⚠️ spread-monitor never trades; ignoring [engine.execution] enabled");
+        config.engine.execution.enabled = false;
+    }
+    if cfg!(feature = "execution") {
+        println!(
+            "ℹ️ This build includes the order client; build with \
+             `--no-default-features --features monitor` to leave it out"
+        );
+    }
+    config::init(config);
+
+    if let Err(e) = keys::unlock(&config::get().keys).await {
+        eprintln!("❌ Cannot load secrets: {:#}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = runtime::init(&config::get().runtime) {
+        eprintln!("❌ Cannot start the hot-path runtime: {}", e);
+        std::process::exit(1);
+    }
+
+    println!("👀 Monitoring spreads; execution is off");
+    match Engine::start(&config::get().engine).await {
+        Ok(engine) => engine.run().await,
+        Err(e) => {
+            eprintln!("❌ Engine failed to start: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,13 +1,37 @@
-use anyhow::Result;
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tokio::sync::watch;
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
 use uuid::Uuid;
 
-use crate::constants::urls;
+use crate::{
+    audit,
+    config::RateLimitConfig,
+    constants::{exchange_names, urls},
+    error::TradingError,
+    keys,
+    models::{
+        ids::ExchangeId,
+        money::{self, Decimal},
+    },
+    net,
+    secret::SecretString,
+    state,
+};
 
-use super::{auth::BinanceAuth, order::BinanceOrder};
+use super::{
+    auth::{BinanceAuth, KeyVars},
+    order::BinanceOrder,
+    rate_limits::{RateLimit, RateLimiter},
+};
+
+const CONNECT_MAX_ATTEMPTS: u32 = 5;
+const CONNECT_BASE_BACKOFF_MS: u64 = 1000;
+/// The WS API's answer to a status query for an order it doesn't know.
+const ORDER_DOES_NOT_EXIST: i32 = -2013;
+/// ...and to cancelling one, or one no longer open.
+pub const UNKNOWN_ORDER: i32 = -2011;
 
 /// Response from the Binance WS API for a placed or queried order.
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,6 +42,22 @@ pub struct BinanceOrderResponse {
     pub error: Option<WsError>,
 }
 
+impl BinanceOrderResponse {
+    /// The result, or the exchange's error as a [`TradingError`].
+    pub fn into_result(self, operation: &'static str) -> Result<BinanceOrderResult, TradingError> {
+        match (self.result, self.error) {
+            (Some(result), _) => Ok(result),
+            (None, Some(WsError { code, msg })) => Err(TradingError::Rejected {
+                exchange: ExchangeId::Binance,
+                operation,
+                code,
+                msg,
+            }),
+            (None, None) => Err(TradingError::EmptyResponse { operation }),
+        }
+    }
+}
+
 /// Details of a successful order operation.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -48,6 +88,35 @@ pub struct BinanceOrderResult {
     pub update_time: u64,
 }
 
+/// Response from the Binance WS API for a query listing things, e.g.
+/// `v2/account.balance`.
+#[derive(Debug, Deserialize)]
+struct ListResponse<T> {
+    result: Option<Vec<T>>,
+    error: Option<WsError>,
+}
+
+/// One asset of the futures account.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetBalance {
+    asset: String,
+    available_balance: String,
+}
+
+/// One position of the futures account, from `v2/account.position`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionInfo {
+    pub symbol: String,
+    /// `BOTH` in one-way mode; `LONG` or `SHORT` in hedge mode.
+    pub position_side: String,
+    /// Signed: negative when short.
+    pub position_amt: String,
+    #[serde(default)]
+    pub entry_price: String,
+}
+
 /// Error details returned by the Binance WS API.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WsError {
@@ -60,31 +129,100 @@ pub struct WsError {
 pub struct BinanceTradingClient {
     auth: BinanceAuth,
     ws_stream: WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+    /// Signals rotated credentials (see `crate::keys`).
+    rotations: watch::Receiver<u64>,
+    /// Where rotated credentials are read from.
+    key_vars: KeyVars,
+    /// The usage the answers reported, which paces the requests after them.
+    limits: RateLimiter,
+}
+
+/// Opens a connection to the WS API, retrying with exponential backoff; an
+/// error is returned only after `CONNECT_MAX_ATTEMPTS` consecutive failures.
+async fn open() -> Result<WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>, TradingError> {
+    println!(
+        "Attempting to connect to Binance WS API: {}",
+        urls::BINANCE_URL_FUTURES_API
+    );
+
+    let mut backoff_ms = CONNECT_BASE_BACKOFF_MS;
+    for attempt in 1..=CONNECT_MAX_ATTEMPTS {
+        match net::connect_ws(urls::BINANCE_URL_FUTURES_API).await {
+            Ok((ws_stream, _)) => {
+                println!("[WS] Connection opened successfully.");
+                return Ok(ws_stream);
+            }
+            Err(e) if attempt < CONNECT_MAX_ATTEMPTS => {
+                eprintln!(
+                    "❌ Connect attempt {}/{} failed: {}. Retrying in {}ms...",
+                    attempt, CONNECT_MAX_ATTEMPTS, e, backoff_ms
+                );
+                tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
+                backoff_ms *= 2;
+            }
+            Err(e) => {
+                return Err(TradingError::Connect {
+                    attempts: CONNECT_MAX_ATTEMPTS,
+                    source: Box::new(e),
+                });
+            }
+        }
+    }
+    unreachable!("CONNECT_MAX_ATTEMPTS is at least 1")
 }
 
 impl BinanceTradingClient {
     /// Creates a new BinanceApiClient instance and connects to the WS API.
     ///
+    /// Connection attempts are retried with exponential backoff; an error is
+    /// returned only after `CONNECT_MAX_ATTEMPTS` consecutive failures.
+    ///
     /// # Arguments
     /// * `api_key` - Your Binance API key.
     /// * `api_secret` - Your Binance API secret.
-    pub async fn connect(api_key: String, api_secret: String) -> Result<Self> {
-        let auth = BinanceAuth::new(api_key, api_secret);
+    pub async fn connect(api_key: String, api_secret: SecretString) -> Result<Self, TradingError> {
+        let auth = BinanceAuth::new(api_key, api_secret)?;
         println!(
-            "Attempting to connect to Binance WS API: {}",
-            urls::BINANCE_URL_FUTURES
+            "🔑 Signing Binance requests with an {} key",
+            auth.key_type()
         );
+        Ok(Self {
+            auth,
+            ws_stream: open().await?,
+            rotations: keys::rotations(),
+            key_vars: KeyVars::default(),
+            limits: RateLimiter::default(),
+        })
+    }
 
-        let (ws_stream, _) = connect_async(urls::BINANCE_URL_FUTURES)
-            .await
-            .expect("❌ Failed to connect");
+    /// Replaces the connection, e.g. after it dropped, retrying like
+    /// [`Self::connect`].
+    pub async fn reconnect(&mut self) -> Result<(), TradingError> {
+        let _ = self.ws_stream.close(None).await;
+        self.ws_stream = open().await?;
+        Ok(())
+    }
+
+    /// Picks up rotated credentials from `key_vars` instead of
+    /// `API_KEY_BINANCE` / `SECRET_KEY_BINANCE`, for one of several accounts.
+    pub fn with_key_vars(mut self, key_vars: KeyVars) -> Self {
+        self.key_vars = key_vars;
+        self
+    }
 
-        println!("[WS] Connection opened successfully.");
+    /// Paces requests as `config` says.
+    pub fn with_rate_limits(mut self, config: &RateLimitConfig) -> Self {
+        self.limits = RateLimiter::new(config);
+        self
+    }
 
-        Ok(Self { auth, ws_stream })
+    /// The usage the WS API last reported.
+    pub fn rate_limits(&self) -> &RateLimiter {
+        &self.limits
     }
 
     /// Sends a signed request to the Binance WS API and waits for the response.
+    /// Everything but status queries goes to the audit log.
     ///
     /// # Arguments
     /// * `method` - The WS API method (e.g., "order.place").
@@ -93,11 +231,36 @@ impl BinanceTradingClient {
         &mut self,
         method: &str,
         params_map: std::collections::BTreeMap<String, String>,
-    ) -> Result<BinanceOrderResponse> {
-        // 1. Augment and sign parameters
+    ) -> Result<BinanceOrderResponse, TradingError> {
+        // 1. Augment and sign parameters, with the latest keys
+        self.pick_up_rotated_keys();
         let signed_params = self.auth.augment_and_sign_params(params_map);
 
-        // 2. Build the final JSON request payload
+        let response = self.round_trip(method, &signed_params).await;
+        if method != "order.status" {
+            audit::record(exchange_names::BINANCE, method, &signed_params, &response);
+        }
+        Ok(serde_json::from_value(response?)?)
+    }
+
+    /// Sends signed parameters and returns the raw response to them.
+    async fn round_trip(
+        &mut self,
+        method: &str,
+        signed_params: &std::collections::BTreeMap<String, String>,
+    ) -> Result<Value, TradingError> {
+        // 2. Hold back while the reported usage is near a limit
+        let wait = self.limits.delay(method == "order.place", state::now_ms());
+        if !wait.is_zero() {
+            println!(
+                "🐢 Near a Binance rate limit; holding {} for {}ms",
+                method,
+                wait.as_millis()
+            );
+            tokio::time::sleep(wait).await;
+        }
+
+        // 3. Build the final JSON request payload
         let request_id = Uuid::new_v4().to_string();
         let payload = json!({
             "id": request_id,
@@ -107,7 +270,7 @@ impl BinanceTradingClient {
 
         let payload_str = serde_json::to_string(&payload)?;
 
-        // 3. Send the request
+        // 4. Send the request
         println!(
             "\n[Request {}] Sending signed request for method: '{}'",
             request_id, method
@@ -116,7 +279,7 @@ impl BinanceTradingClient {
             .send(Message::Text(payload_str.into()))
             .await?;
 
-        // 4. Wait for and process the response
+        // 5. Wait for and process the response
         loop {
             let msg = self.ws_stream.next().await;
             match msg {
@@ -126,40 +289,61 @@ impl BinanceTradingClient {
                     // Check if the response contains the ID we sent
                     if response["id"].as_str() == Some(&request_id) {
                         println!("[WS] Received Response for ID: {}", request_id);
-                        let order_response: BinanceOrderResponse =
-                            serde_json::from_value(response)?;
-                        return Ok(order_response);
+                        self.limits
+                            .record(&RateLimit::from_response(&response), state::now_ms());
+                        return Ok(response);
                     } else {
                         // Handle unsolicited messages (like streams if subscribed)
                         println!("[WS] Unsolicited Message: {}", text);
                     }
                 }
-                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
-                    return Err(anyhow::anyhow!("WebSocket connection closed unexpectedly."));
+                Some(Err(e)) => return Err(e.into()),
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(TradingError::ConnectionClosed);
                 }
                 _ => continue, // Ignore other message types (Ping, Pong, Binary)
             }
         }
     }
 
+    /// Switches to rotated keys, if the credential provider brought new
+    /// ones. The WS API signs every request, so the connection stays.
+    fn pick_up_rotated_keys(&mut self) {
+        if !self.rotations.has_changed().unwrap_or(false) {
+            return;
+        }
+        self.rotations.mark_unchanged();
+        match self.key_vars.auth() {
+            Err(TradingError::MissingCredentials(_)) => {}
+            Ok(auth)
+                if auth.api_key() == self.auth.api_key()
+                    && auth.api_secret() == self.auth.api_secret() => {}
+            Ok(auth) => {
+                println!(
+                    "🔑 Binance order client switched to the rotated {} key",
+                    auth.key_type()
+                );
+                self.auth = auth;
+            }
+            Err(e) => eprintln!("❌ Keeping the current Binance key: {}", e),
+        }
+    }
+
     /// Places a new order on Binance Futures.
-    pub async fn future_order_place(&mut self, order: &BinanceOrder) -> Result<BinanceOrderResult> {
+    pub async fn future_order_place(
+        &mut self,
+        order: &BinanceOrder,
+    ) -> Result<BinanceOrderResult, TradingError> {
         // Convert the order struct to the request parameters map
         let params = order.to_params();
 
         // Send the signed request
-        let response = self.send_signed_request("order.place", params).await?;
-
-        match response.result {
-            Some(result) => {
-                println!("✅ Order Placed Successfully (ID: {})", result.order_id);
-                Ok(result)
-            }
-            None => Err(anyhow::anyhow!(
-                "❌ Order Placement Error: {:?}",
-                response.error
-            )),
-        }
+        let result = self
+            .send_signed_request("order.place", params)
+            .await?
+            .into_result("order.place")?;
+        println!("✅ Order Placed Successfully (ID: {})", result.order_id);
+        Ok(result)
     }
 
     /// Cancels a pending order on Binance Futures.
@@ -171,23 +355,17 @@ impl BinanceTradingClient {
         &mut self,
         symbol: String,
         order_id: u64,
-    ) -> Result<BinanceOrderResult> {
+    ) -> Result<BinanceOrderResult, TradingError> {
         let mut params = std::collections::BTreeMap::new();
         params.insert("symbol".to_string(), symbol);
         params.insert("orderId".to_string(), order_id.to_string());
 
-        let response = self.send_signed_request("order.cancel", params).await?;
-
-        match response.result {
-            Some(result) => {
-                println!("✅ Order Cancelled Successfully (ID: {})", result.order_id);
-                Ok(result)
-            }
-            None => Err(anyhow::anyhow!(
-                "❌ Order Cancellation Error: {:?}",
-                response.error
-            )),
-        }
+        let result = self
+            .send_signed_request("order.cancel", params)
+            .await?
+            .into_result("order.cancel")?;
+        println!("✅ Order Cancelled Successfully (ID: {})", result.order_id);
+        Ok(result)
     }
 
     /// Checks the status of a specific order on Binance Futures.
@@ -199,22 +377,106 @@ impl BinanceTradingClient {
         &mut self,
         symbol: String,
         order_id: u64,
-    ) -> Result<BinanceOrderResult> {
+    ) -> Result<BinanceOrderResult, TradingError> {
         let mut params = std::collections::BTreeMap::new();
         params.insert("symbol".to_string(), symbol);
         params.insert("orderId".to_string(), order_id.to_string());
 
-        let response = self.send_signed_request("order.status", params).await?;
+        let result = self
+            .send_signed_request("order.status", params)
+            .await?
+            .into_result("order.status")?;
+        println!("🔍 Order Status Checked (ID: {})", result.order_id);
+        Ok(result)
+    }
 
-        match response.result {
-            Some(result) => {
-                println!("🔍 Order Status Checked (ID: {})", result.order_id);
-                Ok(result)
-            }
-            None => Err(anyhow::anyhow!(
-                "❌ Order Status Check Error: {:?}",
-                response.error
-            )),
+    /// The futures order placed as `client_order_id`, if there is one; a
+    /// retry looks for it before placing the order again.
+    pub async fn future_order_by_client_id(
+        &mut self,
+        symbol: &str,
+        client_order_id: &str,
+    ) -> Result<Option<BinanceOrderResult>, TradingError> {
+        let params = std::collections::BTreeMap::from([
+            ("symbol".to_string(), symbol.to_string()),
+            ("origClientOrderId".to_string(), client_order_id.to_string()),
+        ]);
+        match self
+            .send_signed_request("order.status", params)
+            .await?
+            .into_result("order.status")
+        {
+            Ok(result) => Ok(Some(result)),
+            Err(TradingError::Rejected {
+                code: ORDER_DOES_NOT_EXIST,
+                ..
+            }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The futures account's available balance in `asset`, e.g. "USDT":
+    /// what new positions can still be margined with.
+    pub async fn future_available_balance(&mut self, asset: &str) -> Result<Decimal, TradingError> {
+        let balances: Vec<AssetBalance> = self
+            .signed_list(
+                "v2/account.balance",
+                "account.balance",
+                std::collections::BTreeMap::new(),
+            )
+            .await?;
+        Ok(balances
+            .iter()
+            .find(|b| b.asset == asset)
+            .and_then(|b| money::parse(&b.available_balance))
+            .unwrap_or(Decimal::ZERO))
+    }
+
+    /// The futures orders for `symbol` still open on the book.
+    pub async fn future_open_orders(
+        &mut self,
+        symbol: &str,
+    ) -> Result<Vec<BinanceOrderResult>, TradingError> {
+        let params = std::collections::BTreeMap::from([("symbol".to_string(), symbol.to_string())]);
+        self.signed_list("openOrders.status", "openOrders.status", params)
+            .await
+    }
+
+    /// The futures positions in `symbol`: one in one-way mode, one per side
+    /// in hedge mode.
+    pub async fn future_positions(
+        &mut self,
+        symbol: &str,
+    ) -> Result<Vec<PositionInfo>, TradingError> {
+        let params = std::collections::BTreeMap::from([("symbol".to_string(), symbol.to_string())]);
+        self.signed_list("v2/account.position", "account.position", params)
+            .await
+    }
+
+    /// Sends a signed query answered with a list, with the latest keys.
+    async fn signed_list<T: serde::de::DeserializeOwned>(
+        &mut self,
+        method: &str,
+        operation: &'static str,
+        params: std::collections::BTreeMap<String, String>,
+    ) -> Result<Vec<T>, TradingError> {
+        self.pick_up_rotated_keys();
+        let signed_params = self.auth.augment_and_sign_params(params);
+        let response = self.round_trip(method, &signed_params).await?;
+        match serde_json::from_value(response)? {
+            ListResponse {
+                result: Some(list), ..
+            } => Ok(list),
+            ListResponse {
+                error: Some(WsError { code, msg }),
+                ..
+            } => Err(TradingError::Rejected {
+                exchange: ExchangeId::Binance,
+                operation,
+                code,
+                msg,
+            }),
+            _ => Err(TradingError::EmptyResponse { operation }),
         }
     }
 }
@@ -1,50 +1,193 @@
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
+//! Request signing for the Binance APIs.
+//!
+//! The key type follows from the secret: a PEM private key (PKCS#8, or
+//! PKCS#1 for RSA) means an Ed25519 or RSA API key, anything else is an
+//! HMAC secret. HMAC signatures are hex, Ed25519 and RSA (PKCS#1 v1.5 with
+//! SHA-256) signatures base64, as Binance expects them.
+
 use std::collections::BTreeMap;
+use std::fmt;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use aws_lc_rs::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, RsaKeyPair, RSA_PKCS1_SHA256},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{
+    config::{self, SigningConfig},
+    constants::exchange_names,
+    error::TradingError,
+    keys,
+    secret::SecretString,
+};
+
 type HmacSha256 = Hmac<Sha256>;
 
+/// The secrets (see `crate::keys`) an account's API key and secret are read
+/// from; `API_KEY_BINANCE` / `SECRET_KEY_BINANCE` unless it's one of several
+/// accounts (see `crate::accounts`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyVars {
+    pub api_key: String,
+    pub secret_key: String,
+}
+
+impl Default for KeyVars {
+    fn default() -> Self {
+        Self::new("API_KEY_BINANCE", "SECRET_KEY_BINANCE")
+    }
+}
+
+impl KeyVars {
+    pub fn new(api_key: impl Into<String>, secret_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            secret_key: secret_key.into(),
+        }
+    }
+
+    /// Signs with the latest keys; fails if either isn't set.
+    pub fn auth(&self) -> Result<BinanceAuth, TradingError> {
+        let api_key = keys::var(&self.api_key)
+            .ok_or_else(|| TradingError::MissingCredentials(self.api_key.clone()))?;
+        let secret = keys::var(&self.secret_key)
+            .ok_or_else(|| TradingError::MissingCredentials(self.secret_key.clone()))?;
+        BinanceAuth::new(api_key.expose().into(), secret)
+    }
+}
+
+/// The kind of API key, detected from the secret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    Hmac,
+    Ed25519,
+    Rsa,
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Hmac => write!(f, "HMAC"),
+            Self::Ed25519 => write!(f, "Ed25519"),
+            Self::Rsa => write!(f, "RSA"),
+        }
+    }
+}
+
+enum Signer {
+    Hmac,
+    Ed25519(Ed25519KeyPair),
+    Rsa(RsaKeyPair),
+}
+
+impl fmt::Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Hmac => write!(f, "Hmac"),
+            Self::Ed25519(_) => write!(f, "Ed25519"),
+            Self::Rsa(_) => write!(f, "Rsa"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct BinanceAuth {
     api_key: String,
-    api_secret: String,
+    api_secret: SecretString,
+    signer: Signer,
+    signing: SigningConfig,
 }
 
 impl BinanceAuth {
-    pub fn new(api_key: String, api_secret: String) -> Self {
-        if api_secret == "YOUR_API_SECRET_HERE" || api_key == "YOUR_API_KEY_HERE" {
+    /// Fails if `api_secret` looks like a PEM key but isn't a usable
+    /// Ed25519 or RSA private key. `recvWindow` and the timestamp offset come
+    /// from `[signing]`.
+    pub fn new(api_key: String, api_secret: SecretString) -> Result<Self, TradingError> {
+        if api_secret.matches("YOUR_API_SECRET_HERE") || api_key == "YOUR_API_KEY_HERE" {
             eprintln!("FATAL: Please set valid API_KEY and API_SECRET.");
             // In a real application, you might use an error type here.
             // For this example, we proceed but the API call will likely fail.
         }
-        BinanceAuth {
+        let signer = if api_secret.expose().trim_start().starts_with("-----BEGIN") {
+            private_key(api_secret.expose())?
+        } else {
+            Signer::Hmac
+        };
+        Ok(BinanceAuth {
             api_key,
             api_secret,
-        }
+            signer,
+            signing: config::get().signing.for_exchange(exchange_names::BINANCE),
+        })
     }
 
     pub fn api_key(&self) -> &String {
         &self.api_key
     }
 
-    pub fn api_secret(&self) -> &String {
+    pub fn api_secret(&self) -> &SecretString {
         &self.api_secret
     }
 
-    /// Signs the query string using HMAC SHA256 and the API secret.
+    pub fn key_type(&self) -> KeyType {
+        match self.signer {
+            Signer::Hmac => KeyType::Hmac,
+            Signer::Ed25519(_) => KeyType::Ed25519,
+            Signer::Rsa(_) => KeyType::Rsa,
+        }
+    }
+
+    /// Signs the query string with the API secret or private key.
     ///
     /// # Arguments
     /// * `query` - The query string containing all request parameters.
     ///
     /// # Returns
-    /// The HMAC SHA256 signature as a hex string.
+    /// The signature: hex for HMAC SHA256, base64 for Ed25519 and RSA.
+    ///
+    /// ```
+    /// use arbitrage_bot::binance::BinanceAuth;
+    ///
+    /// // The HMAC example from the Binance API documentation.
+    /// let auth = BinanceAuth::new(
+    ///     "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A".into(),
+    ///     "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j".into(),
+    /// )
+    /// .unwrap();
+    /// assert_eq!(
+    ///     auth.sign_payload(
+    ///         "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1\
+    ///          &recvWindow=5000&timestamp=1499827319559"
+    ///     ),
+    ///     "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+    /// );
+    /// ```
     pub fn sign_payload(&self, query: &str) -> String {
-        let mut mac = HmacSha256::new_from_slice(self.api_secret.as_bytes())
-            .expect("HMAC SHA256 can be initialized");
-        mac.update(query.as_bytes());
-        let result = mac.finalize();
-        hex::encode(result.into_bytes())
+        match &self.signer {
+            Signer::Hmac => {
+                let mut mac = HmacSha256::new_from_slice(self.api_secret.expose().as_bytes())
+                    .expect("HMAC SHA256 can be initialized");
+                mac.update(query.as_bytes());
+                let result = mac.finalize();
+                hex::encode(result.into_bytes())
+            }
+            Signer::Ed25519(key) => BASE64.encode(key.sign(query.as_bytes())),
+            Signer::Rsa(key) => {
+                let mut signature = vec![0; key.public_modulus_len()];
+                key.sign(
+                    &RSA_PKCS1_SHA256,
+                    &SystemRandom::new(),
+                    query.as_bytes(),
+                    &mut signature,
+                )
+                .expect("RSA signing only fails on a wrongly sized buffer");
+                BASE64.encode(signature)
+            }
+        }
     }
 
     /// Augments the request parameters with authentication details and generates the signature.
@@ -59,19 +202,21 @@ impl BinanceAuth {
     /// A BTreeMap containing all signed parameters, including `apiKey`, `timestamp`, `recvWindow`, and `signature`.
     pub fn augment_and_sign_params(
         &self,
-        mut params: BTreeMap<String, String>,
+        params: BTreeMap<String, String>,
     ) -> BTreeMap<String, String> {
-        // Get current timestamp in milliseconds
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_millis()
-            .to_string();
+        self.augment_and_sign_params_at(params, now_ms())
+    }
 
+    /// [`augment_and_sign_params`](Self::augment_and_sign_params) with the
+    /// clock reading given, for reproducible signatures.
+    pub fn augment_and_sign_params_at(
+        &self,
+        mut params: BTreeMap<String, String>,
+        now_ms: i64,
+    ) -> BTreeMap<String, String> {
         // 1. Add mandatory authentication parameters
         params.insert("apiKey".to_string(), self.api_key.clone());
-        params.insert("timestamp".to_string(), timestamp);
-        params.insert("recvWindow".to_string(), 5000.to_string()); // Default 5000ms
+        self.add_timing(&mut params, now_ms);
 
         // 2. Build the query string by sorting keys alphabetically
         // This is crucial for Binance signature validation
@@ -89,4 +234,84 @@ impl BinanceAuth {
 
         params
     }
+
+    /// The signed query string for a REST request, with `timestamp` and
+    /// `recvWindow` added. Unlike the WS API, REST takes the API key in the
+    /// `X-MBX-APIKEY` header rather than as a parameter.
+    pub fn signed_query(&self, params: BTreeMap<String, String>) -> String {
+        self.signed_query_at(params, now_ms())
+    }
+
+    /// [`signed_query`](Self::signed_query) with the clock reading given.
+    pub fn signed_query_at(&self, mut params: BTreeMap<String, String>, now_ms: i64) -> String {
+        self.add_timing(&mut params, now_ms);
+        let query = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+        // Base64 signatures carry `+`, `/` and `=`.
+        let signature = self
+            .sign_payload(&query)
+            .replace('+', "%2B")
+            .replace('/', "%2F")
+            .replace('=', "%3D");
+        format!("{}&signature={}", query, signature)
+    }
+
+    /// `timestamp` (the local clock in milliseconds, shifted by the
+    /// configured offset) and `recvWindow`.
+    fn add_timing(&self, params: &mut BTreeMap<String, String>, now_ms: i64) {
+        let timestamp = now_ms + self.signing.timestamp_offset_ms;
+        params.insert("timestamp".to_string(), timestamp.to_string());
+        params.insert(
+            "recvWindow".to_string(),
+            self.signing.recv_window_ms.to_string(),
+        );
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis() as i64
+}
+
+/// Parses a PEM private key: PKCS#8 Ed25519 or RSA, or PKCS#1 RSA. Escaped
+/// newlines (`\n`, as a one-line environment variable has them) are fine.
+fn private_key(pem: &str) -> Result<Signer, TradingError> {
+    let pem = SecretString::from(pem.replace("\\n", "\n"));
+    let pem = pem.expose();
+    let mut lines = pem.lines().map(str::trim).filter(|l| !l.is_empty());
+    let label = lines
+        .next()
+        .and_then(|l| l.strip_prefix("-----BEGIN "))
+        .and_then(|l| l.strip_suffix("-----"))
+        .ok_or_else(|| TradingError::InvalidKey("malformed PEM header".into()))?;
+    let end = format!("-----END {}-----", label);
+    let body = SecretString::from(lines.take_while(|l| *l != end).collect::<String>());
+    let der = zeroize::Zeroizing::new(
+        BASE64
+            .decode(body.expose())
+            .map_err(|e| TradingError::InvalidKey(format!("malformed PEM body: {}", e)))?,
+    );
+    match label {
+        "PRIVATE KEY" => Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der)
+            .map(Signer::Ed25519)
+            .or_else(|_| RsaKeyPair::from_pkcs8(&der).map(Signer::Rsa))
+            .map_err(|_| {
+                TradingError::InvalidKey("not an Ed25519 or RSA PKCS#8 private key".into())
+            }),
+        "RSA PRIVATE KEY" => RsaKeyPair::from_der(&der)
+            .map(Signer::Rsa)
+            .map_err(|e| TradingError::InvalidKey(format!("invalid RSA private key: {}", e))),
+        "ENCRYPTED PRIVATE KEY" => Err(TradingError::InvalidKey(
+            "encrypted PEM keys aren't supported; use the key file (`keys import`) instead".into(),
+        )),
+        other => Err(TradingError::InvalidKey(format!(
+            "unsupported PEM type {:?}",
+            other
+        ))),
+    }
 }
@@ -1,13 +1,30 @@
-use crate::binance::api::BinanceTradingClient;
-use crate::binance::order::BinanceOrderSide;
+use crate::binance::api::{self, BinanceOrderResult, BinanceTradingClient};
+use crate::binance::auth::KeyVars;
+use crate::binance::order::{self, BinanceOrderSide, OrderType, TimeInForce};
+use crate::binance::spot;
+use crate::binance::trades::{self, FutureTrade};
 use crate::binance::{create_limit_order, BinanceOrder};
-use crate::ws::exchanges::{Exchange, ExchangeError, ExchangeId, OrderSide, PriceData};
-use futures_util::StreamExt;
-use serde_json::Value;
+use crate::config::RateLimitConfig;
+use crate::constants::exchange_names;
+use crate::error::TradingError;
+use crate::fills;
+use crate::fx::Fx;
+use crate::models::{
+    ids::Symbol,
+    money::{self, Decimal},
+};
+use crate::net;
+use crate::retry::RetryPolicy;
+use crate::secret::SecretString;
+use crate::state::{LegSide, OpenOrder};
+use crate::tca::Fill;
+use crate::transfers;
+use crate::ws::exchanges::{Exchange, ExchangeId, OrderSide, PriceData};
+use crate::ws::handlers::{self, BinanceDepthParser, MessageParser};
+use std::collections::HashMap;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::Mutex;
-use tokio::time::{self, Duration};
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio_tungstenite::tungstenite::protocol::Message;
 
 fn map_order_side(side: OrderSide) -> BinanceOrderSide {
     match side {
@@ -16,22 +33,39 @@ fn map_order_side(side: OrderSide) -> BinanceOrderSide {
     }
 }
 
+/// How many unanswered orders are kept to look up.
+const UNANSWERED: usize = 64;
+
 #[derive(Debug)]
 pub struct BinanceExchange {
     pub symbol: String,
     pub ws_url: String,
     trading_client: Mutex<BinanceTradingClient>,
+    /// Spot orders go over REST (see `crate::binance::spot`).
+    rest_client: reqwest::Client,
+    time_in_force: TimeInForce,
+    /// Where spot orders and rotations read the keys from.
+    key_vars: KeyVars,
+    /// How each order placed filled as of its last response, and whether
+    /// it was a spot order.
+    fills: std::sync::Mutex<HashMap<String, (Fill, bool)>>,
+    /// The client order ID, side and quantity of each futures order sent
+    /// without an answer yet, or for good; the last [`UNANSWERED`] are kept.
+    unanswered: std::sync::Mutex<Vec<(String, LegSide, Decimal)>>,
+    /// When orders and cancellations are tried again (see `crate::retry`).
+    retry: RetryPolicy,
+    /// Converts commission paid in another asset (BNB) into the quote
+    /// currency.
+    fx: Option<Fx>,
 }
 
 impl BinanceExchange {
     pub async fn new(
         symbol: &str,
         api_key: String,
-        api_secret: String,
-    ) -> Result<Self, ExchangeError> {
-        let trading_client = BinanceTradingClient::connect(api_key, api_secret)
-            .await
-            .expect("❌ Failed to connect to Binance");
+        api_secret: SecretString,
+    ) -> Result<Self, TradingError> {
+        let trading_client = BinanceTradingClient::connect(api_key, api_secret).await?;
 
         Ok(Self {
             symbol: symbol.to_string(),
@@ -40,8 +74,228 @@ impl BinanceExchange {
                 symbol.to_lowercase()
             ),
             trading_client: Mutex::new(trading_client),
+            rest_client: net::http_client(),
+            time_in_force: TimeInForce::GTC,
+            key_vars: KeyVars::default(),
+            fills: std::sync::Mutex::new(HashMap::new()),
+            unanswered: std::sync::Mutex::default(),
+            fx: None,
+            retry: RetryPolicy::default(),
         })
     }
+
+    /// Reads this account's keys from `key_vars` from now on, e.g. for a
+    /// sub-account (see `crate::accounts`).
+    pub fn with_key_vars(self, key_vars: KeyVars) -> Self {
+        Self {
+            trading_client: Mutex::new(
+                self.trading_client
+                    .into_inner()
+                    .with_key_vars(key_vars.clone()),
+            ),
+            key_vars,
+            ..self
+        }
+    }
+
+    /// Places orders as `time_in_force` instead of GTC. An IOC or FOK order
+    /// that expires without filling anything fails with
+    /// [`TradingError::Unfilled`].
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    /// Paces requests by the reported usage as `config` says (see
+    /// `crate::binance::rate_limits`).
+    pub fn with_rate_limits(self, config: &RateLimitConfig) -> Self {
+        Self {
+            trading_client: Mutex::new(self.trading_client.into_inner().with_rate_limits(config)),
+            ..self
+        }
+    }
+
+    /// Tries orders and cancellations as `retry` says.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Converts commission paid in another asset at `fx`'s rates; without
+    /// it only commission in the quote currency is known.
+    pub fn with_fx(mut self, fx: Fx) -> Self {
+        self.fx = Some(fx);
+        self
+    }
+
+    /// A limit order under a fresh client order ID.
+    fn limit_order(&self, side: OrderSide, price: Decimal, qty: Decimal) -> BinanceOrder {
+        let mut order = create_limit_order(self.symbol.clone(), map_order_side(side), qty, price);
+        order.time_in_force = Some(self.time_in_force.clone());
+        order.client_order_id = Some(order::new_client_order_id());
+        order
+    }
+
+    /// A market order under a fresh client order ID.
+    fn market_order(&self, side: OrderSide, qty: Decimal) -> BinanceOrder {
+        let mut order = self.limit_order(side, Decimal::ZERO, qty);
+        order.order_type = OrderType::MARKET;
+        order.time_in_force = None;
+        order.price = None;
+        order
+    }
+
+    /// Places futures `order` under `retry`. After a dropped connection the
+    /// client reconnects, and once any attempt failed in a way that may have
+    /// placed the order anyway, every later one looks for its client order
+    /// ID first, so a retry never places it twice. Until it's answered, the
+    /// order is kept for [`Exchange::unanswered_order`].
+    async fn place_future(&self, order: &BinanceOrder) -> Result<BinanceOrderResult, TradingError> {
+        let client_order_id = order.client_order_id.as_deref().unwrap_or_default();
+        {
+            let side = match order.side {
+                BinanceOrderSide::BUY => LegSide::Buy,
+                BinanceOrderSide::SELL => LegSide::Sell,
+            };
+            let qty = order.quantity.unwrap_or_default();
+            let mut unanswered = self.unanswered.lock().unwrap_or_else(|e| e.into_inner());
+            unanswered.push((client_order_id.to_string(), side, qty));
+            if unanswered.len() > UNANSWERED {
+                unanswered.remove(0);
+            }
+        }
+        // Stays set: an attempt that failed before placing anything says
+        // nothing about the ones before it.
+        let mut ambiguous = false;
+        let result = self
+            .retry
+            .run("order.place", true, |last| {
+                let dropped = last.is_some_and(is_dropped);
+                ambiguous |= last.is_some_and(TradingError::is_ambiguous);
+                let ambiguous = ambiguous;
+                async move {
+                    let mut client = self.trading_client.lock().await;
+                    if dropped {
+                        client.reconnect().await?;
+                    }
+                    if ambiguous {
+                        if let Some(placed) = client
+                            .future_order_by_client_id(&self.symbol, client_order_id)
+                            .await?
+                        {
+                            println!(
+                                "♻️ Order {} went through before (ID: {}); not placing it again",
+                                client_order_id, placed.order_id
+                            );
+                            return Ok(placed);
+                        }
+                    }
+                    client.future_order_place(order).await
+                }
+            })
+            .await;
+        // An order the exchange may have taken all the same stays.
+        if !result.as_ref().is_err_and(TradingError::is_ambiguous) {
+            let mut unanswered = self.unanswered.lock().unwrap_or_else(|e| e.into_inner());
+            unanswered.retain(|(id, _, _)| id != client_order_id);
+        }
+        result
+    }
+
+    /// Fails an IOC or FOK order that filled nothing; warns about one that
+    /// filled only part of `qty`.
+    fn check_fill(
+        &self,
+        order_id: u64,
+        status: &str,
+        executed_qty: &str,
+        qty: Decimal,
+    ) -> Result<(), TradingError> {
+        if matches!(self.time_in_force, TimeInForce::GTC) {
+            return Ok(());
+        }
+        let executed = money::parse(executed_qty).unwrap_or_default();
+        if executed.is_zero() {
+            return Err(TradingError::Unfilled {
+                order_id: order_id.to_string(),
+                time_in_force: self.time_in_force.to_string(),
+                status: status.to_string(),
+            });
+        }
+        if executed < qty {
+            eprintln!(
+                "⚠️ {} order {} filled only {} of {}",
+                self.time_in_force, order_id, executed, qty
+            );
+        }
+        Ok(())
+    }
+
+    /// What `fills` paid, in the quote currency; `None` without any, or
+    /// with one that can't be converted.
+    fn fee(&self, fills: &[fills::Fill]) -> Option<Decimal> {
+        Fill::from_fills(fills, true, self.fx.as_ref()).fee
+    }
+
+    /// `fill` with the commission its trades paid, once it's done and they
+    /// account for all of it; left without one where they can't be read.
+    async fn with_commission(&self, order_id: u64, mut fill: Fill) -> Fill {
+        if !fill.done || fill.quantity.is_zero() || fill.fee.is_some() {
+            return fill;
+        }
+        match trades::future_trades(&self.rest_client, &self.key_vars, &self.symbol, order_id).await
+        {
+            Ok(trades) => {
+                let fills: Vec<_> = trades.iter().filter_map(FutureTrade::fill).collect();
+                if fills.iter().map(|f| f.qty).sum::<Decimal>() == fill.quantity {
+                    fill.fee = self.fee(&fills);
+                }
+            }
+            Err(e) => eprintln!("⚠️ No commission for Binance order {}: {}", order_id, e),
+        }
+        fill
+    }
+
+    /// Keeps how order `order_id` filled, as of a response saying so.
+    fn keep_fill(&self, order_id: u64, fill: Fill, spot: bool) -> Fill {
+        let mut fills = self.fills.lock().unwrap_or_else(|e| e.into_inner());
+        fills.insert(order_id.to_string(), (fill.clone(), spot));
+        fill
+    }
+}
+
+/// A fill from an order response's status, executed quantity and average
+/// price (zero until anything fills).
+fn fill(status: &str, executed_qty: &str, avg_price: Option<Decimal>, updated_at_ms: i64) -> Fill {
+    Fill {
+        quantity: money::parse(executed_qty).unwrap_or_default(),
+        avg_price: avg_price.filter(|p| !p.is_zero()),
+        updated_at_ms,
+        done: matches!(
+            status,
+            "FILLED" | "CANCELED" | "EXPIRED" | "EXPIRED_IN_MATCH" | "REJECTED"
+        ),
+        // Order queries don't report commission; trades do.
+        fee: None,
+    }
+}
+
+/// Whether `error` left the WS API connection unusable.
+fn is_dropped(error: &TradingError) -> bool {
+    matches!(
+        error,
+        TradingError::Transport(_) | TradingError::ConnectionClosed
+    )
+}
+
+/// The fill in a futures order response.
+fn future_fill(result: &BinanceOrderResult) -> Fill {
+    fill(
+        &result.status,
+        &result.executed_qty,
+        result.avg_price.as_deref().and_then(money::parse),
+        result.update_time as i64,
+    )
 }
 
 #[async_trait::async_trait]
@@ -51,48 +305,39 @@ impl Exchange for BinanceExchange {
     }
 
     async fn subscribe_prices(&self, tx: Sender<PriceData>) {
-        let (ws_tx, mut ws_rx) = tokio::sync::mpsc::channel(32);
+        let (ws_tx, mut ws_rx) = handlers::feed_channel(&self.ws_url);
 
-        let handler = crate::binance::ws_handler::WsHandler::new(self.ws_url.clone(), ws_tx);
+        let handler = crate::binance::ws_handler::WsHandler::new(self.ws_url.clone(), ws_tx)
+            .for_exchange(exchange_names::BINANCE)
+            .with_keepalive(crate::binance::ws_handler::KeepAlive::binance());
         handler.start().await;
 
+        let parser = BinanceDepthParser;
+        let symbol = Symbol::intern(&self.symbol);
         while let Some(msg_result) = ws_rx.recv().await {
             match msg_result {
                 Ok(Message::Text(txt)) => {
-                    let parsed: Value = match serde_json::from_str(&txt) {
-                        Ok(v) => v,
-                        Err(_) => continue, // Ignore non-JSON messages
+                    // Extract top-of-book
+                    let Some(quote) = parser.parse(&txt) else {
+                        continue;
                     };
+                    if quote.bid.is_zero() || quote.ask.is_zero() {
+                        continue;
+                    }
 
-                    // Extract top-of-book
-                    if let (Some(bids), Some(asks)) = (parsed.get("b"), parsed.get("a")) {
-                        if let (Some(bid), Some(ask)) = (bids.get(0), asks.get(0)) {
-                            if let (Some(bid_price_str), Some(ask_price_str)) =
-                                (bid.get(0), ask.get(0))
-                            {
-                                let bid =
-                                    bid_price_str.as_str().unwrap_or("0").parse().unwrap_or(0.0);
-                                let ask =
-                                    ask_price_str.as_str().unwrap_or("0").parse().unwrap_or(0.0);
-
-                                if bid == 0.0 || ask == 0.0 {
-                                    continue;
-                                }
-
-                                let data = PriceData {
-                                    exchange: ExchangeId::Binance,
-                                    symbol: self.symbol.clone(),
-                                    bid,
-                                    ask,
-                                };
-
-                                if tx.send(data).await.is_err() {
-                                    eprintln!("⚠️ Price channel closed. Exiting Binance task.");
-                                    handler.shutdown(); // Stop the WS handler
-                                    return; // Exit task completely
-                                }
-                            }
-                        }
+                    let data = PriceData {
+                        exchange: ExchangeId::Binance,
+                        symbol,
+                        bid: quote.bid,
+                        ask: quote.ask,
+                        bid_size: quote.bid_size,
+                        ask_size: quote.ask_size,
+                    };
+
+                    if tx.send(data).await.is_err() {
+                        eprintln!("⚠️ Price channel closed. Exiting Binance task.");
+                        handler.shutdown(); // Stop the WS handler
+                        return; // Exit task completely
                     }
                 }
                 Ok(Message::Ping(_))
@@ -117,28 +362,312 @@ impl Exchange for BinanceExchange {
     async fn place_order_future(
         &self,
         side: OrderSide,
-        price: f64,
-        qty: f64,
-    ) -> Result<String, ExchangeError> {
-        let binance_side: BinanceOrderSide = map_order_side(side);
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let order = self.limit_order(side, price, qty);
         println!(
-            "📤 Placing {:?} limit order on Binance: price = {}, qty = {}",
-            binance_side, price, qty
+            "📤 Placing {:?} {} limit order on Binance: price = {}, qty = {}",
+            order.side, self.time_in_force, price, qty
         );
-
-        let order: BinanceOrder = create_limit_order(self.symbol.clone(), binance_side, qty, price);
         println!("Order payload: {:?}", order);
-        let mut client = self.trading_client.lock().await;
 
-        match client.future_order_place(&order).await {
+        match self.place_future(&order).await {
             Ok(result) => {
-                println!("✅ Order Placed Successfully (ID: {})", result.order_id);
+                println!(
+                    "✅ Order Placed Successfully (ID: {}, {})",
+                    result.order_id, result.status
+                );
+                self.keep_fill(result.order_id, future_fill(&result), false);
+                self.check_fill(result.order_id, &result.status, &result.executed_qty, qty)?;
                 Ok(result.order_id.to_string())
             }
             Err(e) => {
-                eprintln!("❌ Order placement failed: {:?}", e);
-                Err(ExchangeError::OrderFailed(e.to_string()))
+                eprintln!("❌ Order placement failed: {}", e);
+                Err(e)
             }
         }
     }
+
+    async fn place_exit_future(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let mut order = self.limit_order(side, price, qty);
+        order.reduce_only = Some(true);
+        println!(
+            "📤 Placing {:?} {} reduce-only limit order on Binance: price = {}, qty = {}",
+            order.side, self.time_in_force, price, qty
+        );
+        match self.place_future(&order).await {
+            Ok(result) => {
+                println!(
+                    "✅ Reduce-only Order Placed Successfully (ID: {}, {})",
+                    result.order_id, result.status
+                );
+                self.keep_fill(result.order_id, future_fill(&result), false);
+                self.check_fill(result.order_id, &result.status, &result.executed_qty, qty)?;
+                Ok(result.order_id.to_string())
+            }
+            Err(e) => {
+                eprintln!("❌ Reduce-only order placement failed: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn place_market_future(
+        &self,
+        side: OrderSide,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let order = self.market_order(side, qty);
+        println!(
+            "📤 Placing {:?} market order on Binance: qty = {}",
+            order.side, qty
+        );
+        match self.place_future(&order).await {
+            Ok(result) => {
+                println!(
+                    "✅ Market Order Placed Successfully (ID: {}, {})",
+                    result.order_id, result.status
+                );
+                self.keep_fill(result.order_id, future_fill(&result), false);
+                Ok(result.order_id.to_string())
+            }
+            Err(e) => {
+                eprintln!("❌ Market order placement failed: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn place_market_exit_future(
+        &self,
+        side: OrderSide,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let mut order = self.market_order(side, qty);
+        order.reduce_only = Some(true);
+        println!(
+            "📤 Placing {:?} reduce-only market order on Binance: qty = {}",
+            order.side, qty
+        );
+        match self.place_future(&order).await {
+            Ok(result) => {
+                println!(
+                    "✅ Market Order Placed Successfully (ID: {}, {})",
+                    result.order_id, result.status
+                );
+                self.keep_fill(result.order_id, future_fill(&result), false);
+                Ok(result.order_id.to_string())
+            }
+            Err(e) => {
+                eprintln!("❌ Market order placement failed: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn place_order_spot(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let leg = LegSide::from(&side);
+        let order = self.limit_order(side, price, qty);
+        println!(
+            "📤 Placing {:?} {} spot limit order on Binance: price = {}, qty = {}",
+            order.side, self.time_in_force, price, qty
+        );
+        // Spot orders can't be looked up by client order ID here, so they
+        // are only sent again after the exchange answered.
+        let placed = self
+            .retry
+            .run("spot order.place", false, |_| {
+                spot::order_place(&self.rest_client, &self.key_vars, &order)
+            })
+            .await;
+        match placed {
+            Ok(result) => {
+                println!(
+                    "✅ Spot Order Placed Successfully (ID: {}, {})",
+                    result.order_id, result.status
+                );
+                let avg_price = money::parse(&result.cummulative_quote_qty)
+                    .and_then(|quote| quote.checked_div(money::parse(&result.executed_qty)?));
+                let mut filled = fill(
+                    &result.status,
+                    &result.executed_qty,
+                    avg_price,
+                    result.transact_time,
+                );
+                filled.fee = self.fee(&result.fills(leg));
+                self.keep_fill(result.order_id, filled, true);
+                self.check_fill(result.order_id, &result.status, &result.executed_qty, qty)?;
+                Ok(result.order_id.to_string())
+            }
+            Err(e) => {
+                eprintln!("❌ Spot order placement failed: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn available_margin(&self) -> Result<Decimal, TradingError> {
+        let asset = transfers::currencies(&self.symbol).map_or("USDT", |(_, quote)| quote);
+        let mut client = self.trading_client.lock().await;
+        client.future_available_balance(asset).await
+    }
+
+    /// From the order's placement response while that says it's done;
+    /// otherwise futures orders are asked about again. Spot orders are only
+    /// known as placed. A done futures order's commission is read from its
+    /// trades (see `crate::binance::trades`), a spot order's from the fills
+    /// in its response.
+    async fn fill(&self, order_id: &str) -> Result<Fill, TradingError> {
+        let kept = {
+            let fills = self.fills.lock().unwrap_or_else(|e| e.into_inner());
+            fills.get(order_id).cloned()
+        };
+        let id = match kept {
+            Some((fill, true)) => return Ok(fill),
+            Some((fill, false)) if fill.done && fill.fee.is_some() => return Ok(fill),
+            _ => order_id.parse().map_err(|_| TradingError::Unsupported {
+                exchange: self.id().name(),
+                kind: "fill",
+            })?,
+        };
+        let fill = match kept {
+            Some((fill, _)) if fill.done => fill,
+            _ => {
+                let mut client = self.trading_client.lock().await;
+                let result = client.future_order_status(self.symbol.clone(), id).await?;
+                future_fill(&result)
+            }
+        };
+        let fill = self.with_commission(id, fill).await;
+        Ok(self.keep_fill(id, fill, false))
+    }
+
+    /// Looked up under `retry`, and forgotten once the exchange answered.
+    async fn unanswered_order(
+        &self,
+        side: OrderSide,
+        qty: Decimal,
+    ) -> Result<Option<String>, TradingError> {
+        let side = LegSide::from(&side);
+        let client_order_id = {
+            let unanswered = self.unanswered.lock().unwrap_or_else(|e| e.into_inner());
+            match unanswered
+                .iter()
+                .rev()
+                .find(|(_, s, q)| *s == side && *q == qty)
+            {
+                Some((id, _, _)) => id.clone(),
+                None => return Ok(None),
+            }
+        };
+        let placed = self
+            .retry
+            .run("order.status", true, |last| {
+                let dropped = last.is_some_and(is_dropped);
+                let client_order_id = &client_order_id;
+                async move {
+                    let mut client = self.trading_client.lock().await;
+                    if dropped {
+                        client.reconnect().await?;
+                    }
+                    client
+                        .future_order_by_client_id(&self.symbol, client_order_id)
+                        .await
+                }
+            })
+            .await?;
+        self.unanswered
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(id, _, _)| *id != client_order_id);
+        Ok(placed.map(|placed| {
+            self.keep_fill(placed.order_id, future_fill(&placed), false);
+            placed.order_id.to_string()
+        }))
+    }
+
+    /// Under `retry`. An order a retry finds already cancelled counts as
+    /// cancelled by it: the attempt before may have gone through.
+    async fn cancel_order(&self, order_id: &str) -> Result<(), TradingError> {
+        let id = order_id.parse().map_err(|_| TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "cancel",
+        })?;
+        let cancelled = self
+            .retry
+            .run("order.cancel", true, |last| {
+                let dropped = last.is_some_and(is_dropped);
+                let ambiguous = last.is_some_and(TradingError::is_ambiguous);
+                async move {
+                    let mut client = self.trading_client.lock().await;
+                    if dropped {
+                        client.reconnect().await?;
+                    }
+                    match client.future_order_cancel(self.symbol.clone(), id).await {
+                        Err(
+                            e @ TradingError::Rejected {
+                                code: api::UNKNOWN_ORDER,
+                                ..
+                            },
+                        ) if ambiguous => {
+                            let status =
+                                client.future_order_status(self.symbol.clone(), id).await?;
+                            if status.status == "CANCELED" {
+                                Ok(status)
+                            } else {
+                                Err(e)
+                            }
+                        }
+                        result => result,
+                    }
+                }
+            })
+            .await?;
+        println!("✅ Order {} cancelled", order_id);
+        self.keep_fill(id, future_fill(&cancelled), false);
+        Ok(())
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OpenOrder>, TradingError> {
+        let mut client = self.trading_client.lock().await;
+        let orders = client.future_open_orders(&self.symbol).await?;
+        Ok(orders
+            .into_iter()
+            .map(|order| OpenOrder {
+                order_id: order.order_id.to_string(),
+                side: if order.side == "SELL" {
+                    LegSide::Sell
+                } else {
+                    LegSide::Buy
+                },
+                price: money::parse(&order.price).unwrap_or_default(),
+                quantity: money::parse(&order.orig_qty).unwrap_or_default(),
+                filled: money::parse(&order.executed_qty).unwrap_or_default(),
+                reduce_only: order.reduce_only,
+                updated_at_ms: order.update_time as i64,
+                symbol: order.symbol,
+            })
+            .collect())
+    }
+
+    /// Summed over both sides in hedge mode, where shorts are negative too.
+    async fn position(&self) -> Result<Decimal, TradingError> {
+        let mut client = self.trading_client.lock().await;
+        let positions = client.future_positions(&self.symbol).await?;
+        Ok(positions
+            .iter()
+            .filter_map(|p| money::parse(&p.position_amt))
+            .sum())
+    }
 }
@@ -1,11 +1,25 @@
+#[cfg(feature = "execution")]
 pub mod api;
+#[cfg(feature = "execution")]
 pub mod auth;
+#[cfg(feature = "execution")]
 pub mod binance_exchange;
+#[cfg(feature = "execution")]
 pub mod order;
+#[cfg(feature = "execution")]
+pub mod permissions;
+#[cfg(feature = "execution")]
+pub mod rate_limits;
+#[cfg(feature = "execution")]
+pub mod spot;
+#[cfg(feature = "execution")]
+pub mod trades;
 pub mod ws_handler;
 
 // Re-export the main types for easy access
+#[cfg(feature = "execution")]
 pub use auth::BinanceAuth;
+#[cfg(feature = "execution")]
 pub use order::{
     create_limit_order, BinanceOrder, NewOrderRespType, OrderType, TimeInForce, WorkingType,
 };
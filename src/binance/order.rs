@@ -3,7 +3,10 @@ use std::collections::BTreeMap;
 use std::time::Duration;
 use std::{fmt, time};
 
-use crate::ws::exchanges::ExchangeError;
+use crate::{
+    config::OrderTimeInForce,
+    models::money::{self, Decimal},
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum BinanceOrderSide {
@@ -66,6 +69,16 @@ impl fmt::Display for TimeInForce {
     }
 }
 
+impl From<OrderTimeInForce> for TimeInForce {
+    fn from(time_in_force: OrderTimeInForce) -> Self {
+        match time_in_force {
+            OrderTimeInForce::Gtc => Self::GTC,
+            OrderTimeInForce::Ioc => Self::IOC,
+            OrderTimeInForce::Fok => Self::FOK,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum PositionSide {
     BOTH,
@@ -128,19 +141,19 @@ pub struct BinanceOrder {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub time_in_force: Option<TimeInForce>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub quantity: Option<f64>,
+    pub quantity: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reduce_only: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub price: Option<f64>,
+    pub price: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub stop_price: Option<f64>,
+    pub stop_price: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub close_position: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub activation_price: Option<f64>,
+    pub activation_price: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub callback_rate: Option<f64>,
+    pub callback_rate: Option<Decimal>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub working_type: Option<WorkingType>, // Changed type from String to WorkingType
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -170,7 +183,7 @@ impl BinanceOrder {
         }
 
         if let Some(quantity) = self.quantity {
-            params.insert("quantity".to_string(), quantity.to_string());
+            params.insert("quantity".to_string(), money::to_param(quantity));
         }
 
         if let Some(reduce_only) = self.reduce_only {
@@ -178,11 +191,11 @@ impl BinanceOrder {
         }
 
         if let Some(price) = self.price {
-            params.insert("price".to_string(), price.to_string());
+            params.insert("price".to_string(), money::to_param(price));
         }
 
         if let Some(stop_price) = self.stop_price {
-            params.insert("stopPrice".to_string(), stop_price.to_string());
+            params.insert("stopPrice".to_string(), money::to_param(stop_price));
         }
 
         if let Some(close_position) = self.close_position {
@@ -190,11 +203,14 @@ impl BinanceOrder {
         }
 
         if let Some(activation_price) = self.activation_price {
-            params.insert("activationPrice".to_string(), activation_price.to_string());
+            params.insert(
+                "activationPrice".to_string(),
+                money::to_param(activation_price),
+            );
         }
 
         if let Some(callback_rate) = self.callback_rate {
-            params.insert("callbackRate".to_string(), callback_rate.to_string());
+            params.insert("callbackRate".to_string(), money::to_param(callback_rate));
         }
 
         if let Some(working_type) = &self.working_type {
@@ -220,12 +236,18 @@ impl BinanceOrder {
     }
 }
 
+/// A fresh `newClientOrderId`: at most 36 characters, letters, digits and
+/// dashes, as Binance allows.
+pub fn new_client_order_id() -> String {
+    format!("arb-{}", uuid::Uuid::new_v4().simple())
+}
+
 // Helper function to create a GTC Limit Order
 pub fn create_limit_order(
     symbol: String,
     side: BinanceOrderSide,
-    quantity: f64,
-    price: f64,
+    quantity: Decimal,
+    price: Decimal,
 ) -> BinanceOrder {
     BinanceOrder {
         symbol,
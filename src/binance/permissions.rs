@@ -0,0 +1,149 @@
+//! Startup audit of the Binance API key. Live execution only starts with a
+//! key that can trade futures (spot in inventory mode), can't withdraw and
+//! is restricted to trusted IPs, and, with `[engine.execution] expected_ip`
+//! set, only when requests actually leave from that IP.
+
+use std::{collections::BTreeMap, net::IpAddr};
+
+use serde::Deserialize;
+
+use crate::{
+    binance::BinanceAuth,
+    config::{self, ExecutionConfig, ExecutionMode},
+    constants::urls,
+    error::TradingError,
+    models::ids::ExchangeId,
+    net,
+};
+
+/// `GET /sapi/v1/account/apiRestrictions`, reduced to what the audit needs.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiRestrictions {
+    pub ip_restrict: bool,
+    pub enable_withdrawals: bool,
+    pub enable_futures: bool,
+    #[serde(default)]
+    pub enable_spot_and_margin_trading: bool,
+}
+
+impl ApiRestrictions {
+    /// What makes the key unfit for live trading in `mode`.
+    ///
+    /// ```
+    /// use arbitrage_bot::{binance::permissions::ApiRestrictions, config::ExecutionMode};
+    ///
+    /// let restrictions: ApiRestrictions = serde_json::from_str(
+    ///     r#"{"ipRestrict": true, "enableWithdrawals": true, "enableFutures": true,
+    ///         "enableReading": true, "createTime": 1698645219000}"#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(restrictions.findings(ExecutionMode::Futures), ["withdrawals are enabled"]);
+    /// assert_eq!(
+    ///     restrictions.findings(ExecutionMode::Inventory),
+    ///     ["spot trading is not enabled", "withdrawals are enabled"]
+    /// );
+    /// ```
+    pub fn findings(&self, mode: ExecutionMode) -> Vec<String> {
+        let mut findings = Vec::new();
+        match mode {
+            ExecutionMode::Futures if !self.enable_futures => {
+                findings.push("futures trading is not enabled".to_string())
+            }
+            ExecutionMode::Inventory if !self.enable_spot_and_margin_trading => {
+                findings.push("spot trading is not enabled".to_string())
+            }
+            _ => {}
+        }
+        if self.enable_withdrawals {
+            findings.push("withdrawals are enabled".to_string());
+        }
+        if !self.ip_restrict {
+            findings.push("it is not restricted to trusted IPs".to_string());
+        }
+        findings
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    code: i32,
+    msg: String,
+}
+
+pub async fn fetch(
+    client: &reqwest::Client,
+    auth: &BinanceAuth,
+) -> Result<ApiRestrictions, TradingError> {
+    let url = format!(
+        "{}/sapi/v1/account/apiRestrictions?{}",
+        config::get().network.endpoint(urls::BINANCE_REST_SPOT),
+        auth.signed_query(BTreeMap::new())
+    );
+    let response = client
+        .get(&url)
+        .header("X-MBX-APIKEY", auth.api_key())
+        .send()
+        .await?;
+    if response.status().is_success() {
+        return Ok(response.json().await?);
+    }
+    let status = response.status();
+    let ApiError { code, msg } = response.json().await.unwrap_or(ApiError {
+        code: status.as_u16().into(),
+        msg: status.to_string(),
+    });
+    Err(TradingError::Rejected {
+        exchange: ExchangeId::Binance,
+        operation: "apiRestrictions",
+        code,
+        msg,
+    })
+}
+
+/// Checks the key and the egress IP, reporting every finding; fails if
+/// there are any.
+pub async fn audit(auth: &BinanceAuth, config: &ExecutionConfig) -> Result<(), TradingError> {
+    let client = net::http_client();
+    let mut findings = fetch(&client, auth).await?.findings(config.mode);
+    match (egress_ip(&client, &config.ip_check_url).await, config.expected_ip) {
+        (Ok(ip), Some(expected)) if ip != expected => findings.push(format!(
+            "requests leave from {}, not from the allowlisted {}",
+            ip, expected
+        )),
+        (Ok(ip), Some(_)) => println!("✅ Requests leave from the allowlisted IP {}", ip),
+        (Ok(ip), None) => println!(
+            "🌐 Requests leave from {}; set [engine.execution] expected_ip to check it against the key's allowlist",
+            ip
+        ),
+        (Err(e), Some(_)) => findings.push(format!("the egress IP can't be checked: {}", e)),
+        (Err(e), None) => eprintln!("⚠️ Couldn't look up the egress IP: {}", e),
+    }
+    if findings.is_empty() {
+        println!(
+            "🔐 Binance API key: {} trading enabled, withdrawals disabled, IP-restricted",
+            match config.mode {
+                ExecutionMode::Futures => "futures",
+                ExecutionMode::Inventory => "spot",
+            }
+        );
+        return Ok(());
+    }
+    for finding in &findings {
+        eprintln!("❌ Binance API key: {}", finding);
+    }
+    Err(TradingError::KeyAudit(findings))
+}
+
+async fn egress_ip(client: &reqwest::Client, url: &str) -> Result<IpAddr, String> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    body.trim()
+        .parse()
+        .map_err(|_| format!("{} answered {:?}", url, body.trim()))
+}
@@ -0,0 +1,205 @@
+//! Pacing requests by the usage the WS API reports
+//! (`[engine.execution.rate_limits]`).
+//!
+//! Every WS API answer carries a `rateLimits` array: for each window (order
+//! count per 10 seconds and per minute, request weight per minute) its
+//! limit and how much of it is used. The order client keeps the latest
+//! reading per window. Once a window is `slow_down_percent` used, requests
+//! it counts are spread evenly over the rest of the window. Once it is used
+//! up, they wait for the window to reset instead of drawing a -1003 and,
+//! after repeated ones, an IP ban. Windows reset on the clock (every 10
+//! seconds, on the minute), so a reading only counts within its window.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::time::Duration;
+
+use crate::config::RateLimitConfig;
+
+/// What a window counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RateLimitType {
+    /// Orders placed; only order placement counts towards it.
+    Orders,
+    /// The weight of every request.
+    RequestWeight,
+    #[serde(other)]
+    Other,
+}
+
+/// One entry of a response's `rateLimits`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    pub rate_limit_type: RateLimitType,
+    /// "SECOND", "MINUTE" or "DAY".
+    pub interval: String,
+    pub interval_num: u32,
+    pub limit: u32,
+    pub count: u32,
+}
+
+impl RateLimit {
+    /// The rate limits in a WS API answer; none if it has none.
+    ///
+    /// ```
+    /// use arbitrage_bot::binance::rate_limits::{RateLimit, RateLimitType};
+    ///
+    /// let response = serde_json::json!({
+    ///     "id": "1",
+    ///     "status": 200,
+    ///     "rateLimits": [{"rateLimitType": "ORDERS", "interval": "SECOND",
+    ///                     "intervalNum": 10, "limit": 300, "count": 12}],
+    /// });
+    /// let limits = RateLimit::from_response(&response);
+    /// assert_eq!(limits[0].rate_limit_type, RateLimitType::Orders);
+    /// assert_eq!(limits[0].window_ms(), Some(10_000));
+    /// ```
+    pub fn from_response(response: &Value) -> Vec<Self> {
+        response
+            .get("rateLimits")
+            .and_then(|limits| serde_json::from_value(limits.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// How long the window is; `None` for an interval it doesn't know.
+    pub fn window_ms(&self) -> Option<i64> {
+        let unit: i64 = match self.interval.as_str() {
+            "SECOND" => 1000,
+            "MINUTE" => 60_000,
+            "HOUR" => 3_600_000,
+            "DAY" => 86_400_000,
+            _ => return None,
+        };
+        Some(unit * i64::from(self.interval_num.max(1)))
+    }
+}
+
+/// The latest reading of one window.
+#[derive(Debug, Clone, Copy)]
+struct Usage {
+    limit: u32,
+    count: u32,
+    seen_ms: i64,
+}
+
+/// The usage of every window the WS API reported, shared by the requests
+/// of one order client.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    enabled: bool,
+    slow_down_percent: u32,
+    windows: HashMap<(RateLimitType, i64), Usage>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(&RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            slow_down_percent: config.slow_down_percent,
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Takes the usage reported at `now_ms`.
+    pub fn record(&mut self, limits: &[RateLimit], now_ms: i64) {
+        for limit in limits {
+            let Some(window_ms) = limit.window_ms() else {
+                continue;
+            };
+            self.windows.insert(
+                (limit.rate_limit_type, window_ms),
+                Usage {
+                    limit: limit.limit,
+                    count: limit.count,
+                    seen_ms: now_ms,
+                },
+            );
+        }
+    }
+
+    /// What is left of the most used `kind` window at `now_ms`; `None`
+    /// without a reading in the current window.
+    pub fn remaining(&self, kind: RateLimitType, now_ms: i64) -> Option<u32> {
+        self.current(now_ms)
+            .filter(|(k, ..)| *k == kind)
+            .map(|(_, _, usage)| usage.limit.saturating_sub(usage.count))
+            .min()
+    }
+
+    /// How long to hold off a request at `now_ms`: zero while every window
+    /// it counts towards is below `slow_down_percent`. Orders count towards
+    /// the order windows as well as the weight ones.
+    ///
+    /// ```
+    /// use arbitrage_bot::{
+    ///     binance::rate_limits::{RateLimit, RateLimitType, RateLimiter},
+    ///     config::RateLimitConfig,
+    /// };
+    ///
+    /// let mut limiter = RateLimiter::new(&RateLimitConfig::default());
+    /// let orders = |count| RateLimit {
+    ///     rate_limit_type: RateLimitType::Orders,
+    ///     interval: "SECOND".into(),
+    ///     interval_num: 10,
+    ///     limit: 300,
+    ///     count,
+    /// };
+    /// // 2s into a 10s window.
+    /// limiter.record(&[orders(100)], 2_000);
+    /// assert!(limiter.delay(true, 2_000).is_zero());
+    /// // 90% used: the other 30 orders are spread over the 8s left.
+    /// limiter.record(&[orders(270)], 2_000);
+    /// assert_eq!(limiter.delay(true, 2_000).as_millis(), 266);
+    /// // Status queries don't count as orders.
+    /// assert!(limiter.delay(false, 2_000).is_zero());
+    /// // Used up: until the window resets.
+    /// limiter.record(&[orders(300)], 2_000);
+    /// assert_eq!(limiter.delay(true, 2_000).as_millis(), 8_000);
+    /// // The next window starts afresh.
+    /// assert!(limiter.delay(true, 10_000).is_zero());
+    /// ```
+    pub fn delay(&self, order: bool, now_ms: i64) -> Duration {
+        if !self.enabled {
+            return Duration::ZERO;
+        }
+        self.current(now_ms)
+            .filter(|(kind, ..)| order || *kind != RateLimitType::Orders)
+            .map(|(_, window_ms, usage)| {
+                let until_reset = window_ms - now_ms.rem_euclid(window_ms);
+                let left = usage.limit.saturating_sub(usage.count);
+                let wait_ms = if left == 0 {
+                    until_reset
+                } else if u64::from(usage.count) * 100
+                    >= u64::from(usage.limit) * u64::from(self.slow_down_percent)
+                {
+                    until_reset / i64::from(left)
+                } else {
+                    0
+                };
+                Duration::from_millis(wait_ms.max(0) as u64)
+            })
+            .max()
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// The windows whose reading was taken in the window running at
+    /// `now_ms`.
+    fn current(&self, now_ms: i64) -> impl Iterator<Item = (RateLimitType, i64, Usage)> + '_ {
+        self.windows
+            .iter()
+            .filter(move |((_, window_ms), usage)| {
+                usage.seen_ms.div_euclid(*window_ms) == now_ms.div_euclid(*window_ms)
+            })
+            .map(|(&(kind, window_ms), &usage)| (kind, window_ms, usage))
+    }
+}
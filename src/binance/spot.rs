@@ -0,0 +1,123 @@
+//! Spot orders over the REST API (`POST /api/v3/order`), for inventory mode.
+//! Futures orders go over the WS API (see `api`); spot only trades when
+//! `[engine.execution] mode = "inventory"`, so a request per order does.
+//!
+//! Keys are read for every order, so rotated credentials apply right away.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    audit,
+    binance::{api::WsError, auth::KeyVars, BinanceAuth, BinanceOrder},
+    config,
+    constants::{exchange_names, urls},
+    error::TradingError,
+    fills::Fill,
+    models::{ids::ExchangeId, money},
+    state::LegSide,
+};
+
+/// The part of a spot order response execution uses.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotOrderResult {
+    pub order_id: u64,
+    pub symbol: String,
+    pub status: String,
+    pub executed_qty: String,
+    /// What the fills cost in total, to average over.
+    #[serde(default)]
+    pub cummulative_quote_qty: String,
+    #[serde(default)]
+    pub transact_time: i64,
+    /// Each execution, in a `FULL` response: the default for limit and
+    /// market orders.
+    #[serde(default)]
+    pub fills: Vec<SpotFill>,
+}
+
+/// One execution in a spot order response.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotFill {
+    pub price: String,
+    pub qty: String,
+    pub commission: String,
+    pub commission_asset: String,
+}
+
+impl SpotOrderResult {
+    /// The executions of this order, a `side` one, as fills; those whose
+    /// fields don't parse are left out.
+    pub fn fills(&self, side: LegSide) -> Vec<Fill> {
+        self.fills
+            .iter()
+            .filter_map(|fill| {
+                Some(Fill {
+                    venue: ExchangeId::Binance,
+                    symbol: self.symbol.clone(),
+                    side,
+                    price: money::parse(&fill.price)?,
+                    qty: money::parse(&fill.qty)?,
+                    fee: money::parse(&fill.commission)?,
+                    fee_asset: fill.commission_asset.clone(),
+                    ts: self.transact_time,
+                    order_id: self.order_id.to_string(),
+                    client_order_id: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Places `order` on Binance spot, signed with the keys in `key_vars`.
+pub async fn order_place(
+    client: &reqwest::Client,
+    key_vars: &KeyVars,
+    order: &BinanceOrder,
+) -> Result<SpotOrderResult, TradingError> {
+    let auth = key_vars.auth()?;
+    let params = order.to_params();
+    let url = format!(
+        "{}/api/v3/order?{}",
+        config::get().network.endpoint(urls::BINANCE_REST_SPOT),
+        auth.signed_query(params.clone())
+    );
+
+    let response = send(client, &url, &auth).await;
+    audit::record(
+        exchange_names::BINANCE,
+        "spot order.place",
+        &params,
+        &response,
+    );
+    Ok(serde_json::from_value(response?)?)
+}
+
+/// Posts the signed order and returns the exchange's answer, or its error.
+async fn send(
+    client: &reqwest::Client,
+    url: &str,
+    auth: &BinanceAuth,
+) -> Result<Value, TradingError> {
+    let response = client
+        .post(url)
+        .header("X-MBX-APIKEY", auth.api_key())
+        .send()
+        .await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response.json().await?);
+    }
+    let WsError { code, msg } = response.json().await.unwrap_or(WsError {
+        code: status.as_u16().into(),
+        msg: status.to_string(),
+    });
+    Err(TradingError::Rejected {
+        exchange: ExchangeId::Binance,
+        operation: "spot order.place",
+        code,
+        msg,
+    })
+}
@@ -0,0 +1,95 @@
+//! A futures order's trades over the REST API (`GET /fapi/v1/userTrades`),
+//! for the commission they paid: order responses and status queries only
+//! say how much filled at what average price.
+//!
+//! Keys are read for every request, so rotated credentials apply right away.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::{
+    binance::{api::WsError, auth::KeyVars},
+    config,
+    constants::urls,
+    error::TradingError,
+    fills::Fill,
+    models::{ids::ExchangeId, money},
+    state::LegSide,
+};
+
+/// One trade of a futures order, as `userTrades` lists it.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FutureTrade {
+    pub symbol: String,
+    pub order_id: u64,
+    /// `BUY` or `SELL`.
+    pub side: String,
+    pub price: String,
+    pub qty: String,
+    pub commission: String,
+    pub commission_asset: String,
+    pub time: i64,
+}
+
+impl FutureTrade {
+    /// This trade as a fill; `None` if a field doesn't parse.
+    pub fn fill(&self) -> Option<Fill> {
+        Some(Fill {
+            venue: ExchangeId::Binance,
+            symbol: self.symbol.clone(),
+            side: match self.side.as_str() {
+                "BUY" => LegSide::Buy,
+                "SELL" => LegSide::Sell,
+                _ => return None,
+            },
+            price: money::parse(&self.price)?,
+            qty: money::parse(&self.qty)?,
+            fee: money::parse(&self.commission)?,
+            fee_asset: self.commission_asset.clone(),
+            ts: self.time,
+            order_id: self.order_id.to_string(),
+            client_order_id: None,
+        })
+    }
+}
+
+/// The trades of futures order `order_id` on `symbol`, signed with the keys
+/// in `key_vars`.
+pub async fn future_trades(
+    client: &reqwest::Client,
+    key_vars: &KeyVars,
+    symbol: &str,
+    order_id: u64,
+) -> Result<Vec<FutureTrade>, TradingError> {
+    let auth = key_vars.auth()?;
+    let params = BTreeMap::from([
+        ("symbol".to_string(), symbol.to_string()),
+        ("orderId".to_string(), order_id.to_string()),
+    ]);
+    let url = format!(
+        "{}/fapi/v1/userTrades?{}",
+        config::get().network.endpoint(urls::BINANCE_REST_FUTURES),
+        auth.signed_query(params)
+    );
+    let response = client
+        .get(&url)
+        .header("X-MBX-APIKEY", auth.api_key())
+        .send()
+        .await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response.json().await?);
+    }
+    let WsError { code, msg } = response.json().await.unwrap_or(WsError {
+        code: status.as_u16().into(),
+        msg: status.to_string(),
+    });
+    Err(TradingError::Rejected {
+        exchange: ExchangeId::Binance,
+        operation: "userTrades",
+        code,
+        msg,
+    })
+}
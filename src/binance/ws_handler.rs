@@ -1,19 +1,115 @@
-use futures_util::StreamExt;
+use flate2::read::{DeflateDecoder, GzDecoder};
+use futures_util::{
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
 use rand::prelude::*;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Read;
+use std::pin::Pin;
+use std::sync::{Arc, LazyLock};
 use tokio::sync::Mutex;
+use tokio::sync::{broadcast, mpsc};
 use tokio::time::{self, Duration, Instant};
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{self, WsConfig},
+    error::{Classify, FeedError},
+    latency::Latency,
+    limits::SizeGauge,
+    models::ids::ExchangeId,
+    net::{self, WsStream},
+    state::{self, BreakerTrip},
+    ws::{
+        backpressure,
+        handlers::FeedFrame,
+        tap::{self, FrameTap, Recordings},
+    },
+};
+
+/// The replacement connection of a rotation, on its way to taking over.
+enum Handover {
+    Connecting(Pin<Box<dyn Future<Output = Result<WsStream, String>> + Send>>),
+    /// Open and sent the on-connect messages, plus whatever went through
+    /// `outbound` since (kept in `diverted`); takes over with its first data
+    /// frame, which must arrive by `deadline`.
+    Syncing {
+        write: SplitSink<WsStream, Message>,
+        read: SplitStream<WsStream>,
+        diverted: Vec<Message>,
+        deadline: Instant,
+    },
+}
+
+/// How a [`Handover`] moved on.
+enum HandoverStep {
+    Connected(Box<WsStream>),
+    /// The first data frame of the new connection.
+    Synced(Message),
+    Failed(String),
+}
+
+impl Handover {
+    /// Waits for the next step. Cancel-safe, so it can sit in a `select!`.
+    async fn step(&mut self) -> HandoverStep {
+        match self {
+            Self::Connecting(connecting) => match connecting.await {
+                Ok(ws) => HandoverStep::Connected(Box::new(ws)),
+                Err(e) => HandoverStep::Failed(e),
+            },
+            Self::Syncing { read, deadline, .. } => {
+                let first = time::timeout_at(*deadline, async {
+                    while let Some(msg) = read.next().await {
+                        match msg {
+                            Ok(msg @ (Message::Text(_) | Message::Binary(_))) => return Ok(msg),
+                            Ok(Message::Close(_)) => {
+                                return Err("closed during handover".to_string())
+                            }
+                            Ok(_) => {}
+                            Err(e) => return Err(e.to_string()),
+                        }
+                    }
+                    Err("stream ended during handover".to_string())
+                })
+                .await;
+                match first {
+                    Ok(Ok(msg)) => HandoverStep::Synced(msg),
+                    Ok(Err(e)) => HandoverStep::Failed(e),
+                    // Silence may just as well mean the subscriptions never
+                    // reached it; the current connection is known to work.
+                    Err(_) => {
+                        HandoverStep::Failed(format!("no data within {:?}", ROTATION_SYNC_TIMEOUT))
+                    }
+                }
+            }
+        }
+    }
+}
 
 // --- Configuration Constants ---
-const BASE_BACKOFF_MS: u64 = 1000;
-const MAX_BACKOFF_MS: u64 = 60_000;
-const CONNECTION_ROTATION_DURATION: Duration = Duration::from_secs(23 * 3600); // 23 hours
-const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(60); // 60 seconds without message = dead
-const MAX_DISCONNECTIONS_WINDOW: Duration = Duration::from_secs(300); // 5 minutes
-const MAX_DISCONNECTIONS_LIMIT: usize = 10; // 10 disconnections in 5 mins -> trips circuit breaker
+// Backoff, heartbeat, rotation and circuit-breaker tuning live in `WsConfig`
+// (`[ws]` in the config file).
+const ROTATION_SYNC_TIMEOUT: Duration = Duration::from_secs(10); // wait for first frame on the new connection
+const ROTATION_RETRY_DELAY: Duration = Duration::from_secs(60); // failed handover -> keep old, retry later
+const OUTBOUND_CHANNEL_CAPACITY: usize = 64;
+/// Per-subscriber buffer of the connection-event bus; slower subscribers see
+/// `RecvError::Lagged` instead of blocking the connection.
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Circuit breakers that were still tripped when the previous run saved its
+/// state, by URL (see `crate::state`).
+static RESTORED_TRIPS: LazyLock<std::sync::Mutex<HashMap<String, BreakerTrip>>> =
+    LazyLock::new(Default::default);
+
+/// Holds off every handler for `trip.url` until `trip.until_ms`, as if its
+/// breaker had just tripped. Applies to handlers started afterwards.
+pub fn restore_circuit_breaker(trip: BreakerTrip) {
+    let mut trips = RESTORED_TRIPS.lock().unwrap_or_else(|e| e.into_inner());
+    trips.insert(trip.url.clone(), trip);
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConnectionState {
@@ -24,28 +120,344 @@ pub enum ConnectionState {
     Rotating,
 }
 
+/// Why a live connection ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisconnectReason {
+    ServerClosed,
+    StreamEnded,
+    Error(String),
+    HeartbeatTimeout,
+    SendFailed(String),
+    /// The consumer of the message channel went away.
+    ConsumerDropped,
+    Shutdown,
+}
+
+impl std::fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ServerClosed => write!(f, "closed by server"),
+            Self::StreamEnded => write!(f, "stream ended"),
+            Self::Error(e) => write!(f, "error: {}", e),
+            Self::HeartbeatTimeout => write!(f, "heartbeat timeout"),
+            Self::SendFailed(e) => write!(f, "send failed: {}", e),
+            Self::ConsumerDropped => write!(f, "consumer dropped"),
+            Self::Shutdown => write!(f, "shutdown"),
+        }
+    }
+}
+
+/// Lifecycle events of a managed connection, published on a broadcast channel
+/// so the engine, metrics and notifier can each subscribe.
+#[derive(Debug, Clone)]
+pub enum ConnectionEvent {
+    Connected {
+        url: String,
+    },
+    ConnectFailed {
+        url: String,
+        error: Arc<FeedError>,
+    },
+    Disconnected {
+        url: String,
+        reason: DisconnectReason,
+    },
+    Reconnecting {
+        url: String,
+        retry_in: Duration,
+    },
+    /// Too many disconnections in the window; reconnects pause for `cooldown`.
+    CircuitBreakerTripped {
+        url: String,
+        disconnections: usize,
+        cooldown: Duration,
+    },
+    /// Make-before-break rotation finished: traffic now flows over a fresh
+    /// connection and the old one has been closed.
+    Rotated {
+        url: String,
+    },
+}
+
+impl ConnectionEvent {
+    pub fn url(&self) -> &str {
+        match self {
+            Self::Connected { url }
+            | Self::ConnectFailed { url, .. }
+            | Self::Disconnected { url, .. }
+            | Self::Reconnecting { url, .. }
+            | Self::CircuitBreakerTripped { url, .. }
+            | Self::Rotated { url } => url,
+        }
+    }
+}
+
+/// What a keepalive tick sends.
+#[derive(Debug, Clone)]
+pub enum PingPayload {
+    /// A WebSocket protocol ping frame.
+    Frame,
+    /// An application-level text message, e.g. Bybit's `{"op":"ping"}`.
+    Text(String),
+}
+
+/// Client-side keepalive schedule for a managed connection.
+#[derive(Debug, Clone)]
+pub struct KeepAlive {
+    pub interval: Duration,
+    pub payload: PingPayload,
+}
+
+impl KeepAlive {
+    pub fn ws_frame(interval: Duration) -> Self {
+        Self {
+            interval,
+            payload: PingPayload::Frame,
+        }
+    }
+
+    pub fn text(interval: Duration, payload: impl Into<String>) -> Self {
+        Self {
+            interval,
+            payload: PingPayload::Text(payload.into()),
+        }
+    }
+
+    /// Bybit V5 public/private streams: JSON ping every 20 seconds.
+    pub fn bybit() -> Self {
+        Self::text(Duration::from_secs(20), r#"{"op":"ping"}"#)
+    }
+
+    /// Binance streams: ping frames every 20 seconds. Binance pings us
+    /// anyway; ours are only there to time the round trip.
+    pub fn binance() -> Self {
+        Self::ws_frame(Duration::from_secs(20))
+    }
+
+    fn message(&self) -> Message {
+        match &self.payload {
+            PingPayload::Frame => Message::Ping(vec![].into()),
+            PingPayload::Text(txt) => Message::Text(txt.clone().into()),
+        }
+    }
+
+    /// Whether `msg` answers our ping: a pong frame, or for text pings a
+    /// text frame saying `"pong"` (Bybit's `"ret_msg":"pong"` / `"op":"pong"`).
+    fn is_pong(&self, msg: &Message) -> bool {
+        match (&self.payload, msg) {
+            (PingPayload::Frame, Message::Pong(_)) => true,
+            (PingPayload::Text(_), Message::Text(txt)) => txt.contains(r#""pong""#),
+            _ => false,
+        }
+    }
+}
+
+/// Decodes one binary frame into text.
+pub type BinaryDecoder = Arc<dyn Fn(&[u8]) -> Result<String, String> + Send + Sync>;
+
+/// Decoder for exchanges that compress payloads at the application layer and
+/// deliver them as binary frames (HTX always gzips, OKX can send raw deflate).
+/// Decoded frames are forwarded as `Message::Text`, so parsers never see the
+/// compression.
+///
+/// This is separate from the `permessage-deflate` extension, which tungstenite
+/// does not implement: the handshake never offers it and servers fall back to
+/// uncompressed frames.
+#[derive(Clone)]
+pub enum Decompression {
+    Gzip,
+    /// Raw DEFLATE stream without zlib/gzip headers.
+    Deflate,
+    /// Exchange-specific decoding hook.
+    Custom(BinaryDecoder),
+}
+
+impl Decompression {
+    pub fn custom(hook: impl Fn(&[u8]) -> Result<String, String> + Send + Sync + 'static) -> Self {
+        Self::Custom(Arc::new(hook))
+    }
+
+    /// ```
+    /// use std::io::Write;
+    /// use arbitrage_bot::binance::ws_handler::Decompression;
+    ///
+    /// let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    /// gz.write_all(br#"{"ping":1}"#).unwrap();
+    /// let frame = gz.finish().unwrap();
+    /// assert_eq!(Decompression::Gzip.decode(&frame).unwrap(), r#"{"ping":1}"#);
+    /// ```
+    pub fn decode(&self, bytes: &[u8]) -> Result<String, String> {
+        let mut out = String::new();
+        let read = match self {
+            Self::Gzip => GzDecoder::new(bytes).read_to_string(&mut out),
+            Self::Deflate => DeflateDecoder::new(bytes).read_to_string(&mut out),
+            Self::Custom(hook) => return hook(bytes),
+        };
+        read.map(|_| out)
+            .map_err(|e| format!("decompression failed: {}", e))
+    }
+}
+
+/// Builds the messages to send right after a connection is established
+/// (subscriptions, auth). Called on every (re)connect, so it can produce fresh
+/// signatures or reflect the current subscription set.
+pub type OnConnect = Arc<dyn Fn() -> Vec<Message> + Send + Sync>;
+
 #[derive(Clone)]
 pub struct WsHandler {
     pub url: String,
     pub state: Arc<Mutex<ConnectionState>>,
-    pub shutdown: Arc<AtomicBool>,
-    pub sender: mpsc::Sender<Result<Message, String>>,
+    /// Cancelled to stop the connection loop, closing the live connection.
+    cancel: CancellationToken,
+    pub sender: mpsc::Sender<FeedFrame>,
     pub disconnection_timestamps: Arc<Mutex<Vec<Instant>>>,
+    /// Size of `disconnection_timestamps`, capped by `[limits] disconnect_timestamps`.
+    disconnects: Arc<SizeGauge>,
     pub last_heartbeat: Arc<Mutex<Instant>>,
+    pub on_connect: Option<OnConnect>,
+    /// Outbound messages queued by the application (subscriptions, orders);
+    /// drained into whichever connection is currently live. Lossless: senders
+    /// wait rather than drop.
+    outbound_tx: mpsc::Sender<Message>,
+    outbound_rx: Arc<Mutex<mpsc::Receiver<Message>>>,
+    /// Client-side keepalive; `None` relies on server pings only.
+    pub keepalive: Option<KeepAlive>,
+    /// Applied to incoming binary frames; `None` forwards them untouched.
+    pub decompression: Option<Decompression>,
+    events: broadcast::Sender<ConnectionEvent>,
+    pub config: WsConfig,
+    /// Debug taps receiving a copy of every decoded data frame.
+    taps: Vec<Arc<FrameTap>>,
+    /// Where ping round trips are recorded (see `crate::latency`); set by
+    /// [`WsHandler::for_exchange`].
+    venue: Option<ExchangeId>,
 }
 
 impl WsHandler {
-    pub fn new(url: String, sender: mpsc::Sender<Result<Message, String>>) -> Self {
+    pub fn new(url: String, sender: mpsc::Sender<FeedFrame>) -> Self {
+        let (outbound_tx, outbound_rx) =
+            backpressure::lossless_channel(format!("{} outbound", url), OUTBOUND_CHANNEL_CAPACITY);
+        let disconnects = SizeGauge::register(
+            format!("{} disconnects", url),
+            config::get().limits.disconnect_timestamps,
+        );
         Self {
             url,
             state: Arc::new(Mutex::new(ConnectionState::Disconnected)),
-            shutdown: Arc::new(AtomicBool::new(false)),
+            cancel: CancellationToken::new(),
             sender,
             disconnection_timestamps: Arc::new(Mutex::new(Vec::new())),
+            disconnects,
             last_heartbeat: Arc::new(Mutex::new(Instant::now())),
+            on_connect: None,
+            outbound_tx,
+            outbound_rx: Arc::new(Mutex::new(outbound_rx)),
+            keepalive: None,
+            decompression: None,
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            config: config::get().ws.default.clone(),
+            taps: Vec::new(),
+            venue: None,
         }
     }
 
+    /// Tuning for this connection, usually `config::get().ws.for_exchange(..)`.
+    /// Defaults to `[ws.default]`.
+    pub fn with_config(mut self, config: WsConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Applies the `[ws]` tuning and `[[tap]]` entries configured for
+    /// `exchange` (see `constants::exchange_names`), and records its ping
+    /// round trips with [`Latency::global`].
+    pub fn for_exchange(mut self, exchange: &str) -> Self {
+        self.venue = ExchangeId::from_name(exchange);
+        let taps = tap::taps_for(exchange);
+        taps.into_iter().fold(
+            self.with_config(config::get().ws.for_exchange(exchange)),
+            Self::with_tap,
+        )
+    }
+
+    /// Copy every data frame this connection receives to `tap`.
+    pub fn with_tap(mut self, tap: Arc<FrameTap>) -> Self {
+        self.taps.push(tap);
+        self
+    }
+
+    /// Publish connection events on a shared bus instead of a private one,
+    /// so one subscriber can watch many connections.
+    pub fn with_event_channel(mut self, events: broadcast::Sender<ConnectionEvent>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Stop when `cancel` is cancelled (e.g. a child of the engine's token)
+    /// instead of only on [`WsHandler::shutdown`].
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Token that stops this handler; consumers select on it so they stop too.
+    pub fn cancellation(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Receiver for this handler's connection events (from now on).
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events.subscribe()
+    }
+
+    pub async fn state(&self) -> ConnectionState {
+        self.state.lock().await.clone()
+    }
+
+    /// Never blocks; having no subscribers is fine.
+    fn publish(&self, event: ConnectionEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Run `hook` after each successful connect and send the messages it returns.
+    pub fn with_on_connect(
+        mut self,
+        hook: impl Fn() -> Vec<Message> + Send + Sync + 'static,
+    ) -> Self {
+        self.on_connect = Some(Arc::new(hook));
+        self
+    }
+
+    /// Text frames to (re)send after each successful connect.
+    pub fn with_subscriptions(self, subscriptions: Vec<String>) -> Self {
+        self.with_on_connect(move || {
+            subscriptions
+                .iter()
+                .map(|s| Message::Text(s.clone().into()))
+                .collect()
+        })
+    }
+
+    /// Handle for sending messages over the managed connection.
+    ///
+    /// Messages queued while disconnected are delivered after the next
+    /// connect, following the `on_connect` messages.
+    pub fn outbound(&self) -> mpsc::Sender<Message> {
+        self.outbound_tx.clone()
+    }
+
+    /// Send keepalive pings on the given schedule so idle connections aren't dropped.
+    pub fn with_keepalive(mut self, keepalive: KeepAlive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// Decompress incoming binary frames before handing them to the consumer.
+    pub fn with_decompression(mut self, decompression: Decompression) -> Self {
+        self.decompression = Some(decompression);
+        self
+    }
+
     pub async fn start(&self) {
         let handler = self.clone();
         tokio::spawn(async move {
@@ -54,66 +466,138 @@ impl WsHandler {
     }
 
     pub fn shutdown(&self) {
-        self.shutdown.store(true, Ordering::Relaxed);
+        self.cancel.cancel();
+    }
+
+    /// Sleeps for `duration`; returns `false` if cancelled first.
+    async fn sleep_or_cancel(&self, duration: Duration) -> bool {
+        tokio::select! {
+            _ = time::sleep(duration) => true,
+            _ = self.cancel.cancelled() => false,
+        }
     }
 
     async fn record_disconnection(&self) {
         let mut timestamps = self.disconnection_timestamps.lock().await;
         timestamps.push(Instant::now());
         // Clean up old timestamps
-        timestamps.retain(|t| t.elapsed() < MAX_DISCONNECTIONS_WINDOW);
+        timestamps.retain(|t| t.elapsed() < self.config.circuit_breaker_window());
+        let excess = timestamps.len().saturating_sub(self.disconnects.cap());
+        if excess > 0 {
+            timestamps.drain(..excess);
+            self.disconnects.pruned(excess);
+        }
+        self.disconnects.set(timestamps.len());
     }
 
     async fn check_circuit_breaker(&self) -> bool {
         let mut timestamps = self.disconnection_timestamps.lock().await;
-        timestamps.retain(|t| t.elapsed() < MAX_DISCONNECTIONS_WINDOW);
-        timestamps.len() >= MAX_DISCONNECTIONS_LIMIT
+        timestamps.retain(|t| t.elapsed() < self.config.circuit_breaker_window());
+        self.disconnects.set(timestamps.len());
+        timestamps.len() >= self.config.circuit_breaker_limit
+    }
+
+    /// Waits out a breaker restored from the previous run; returns `false`
+    /// if cancelled first.
+    async fn wait_for_restored_breaker(&self) -> bool {
+        let trip = RESTORED_TRIPS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&self.url)
+            .cloned();
+        let Some(trip) = trip else {
+            return true;
+        };
+        let remaining = trip.until_ms - state::now_ms();
+        if remaining <= 0 {
+            return true;
+        }
+        let remaining = Duration::from_millis(remaining as u64);
+        eprintln!(
+            "🔥 Circuit Breaker was tripped before the restart. Waiting {:?}...",
+            remaining
+        );
+        self.publish(ConnectionEvent::CircuitBreakerTripped {
+            url: self.url.clone(),
+            disconnections: trip.disconnections,
+            cooldown: remaining,
+        });
+        self.sleep_or_cancel(remaining).await
     }
 
     async fn connection_loop(&self) {
-        let mut backoff_ms = BASE_BACKOFF_MS;
-        let mut rotation_deadline = Instant::now() + CONNECTION_ROTATION_DURATION;
+        let mut backoff_ms = self.config.base_backoff_ms;
+        if !self.wait_for_restored_breaker().await {
+            return;
+        }
 
-        while !self.shutdown.load(Ordering::Relaxed) {
+        while !self.cancel.is_cancelled() {
             // 1. Check Circuit Breaker
             if self.check_circuit_breaker().await {
                 eprintln!(
-                    "🔥 Circuit Breaker Tripped! Too many disconnections. Waiting 5 minutes..."
+                    "🔥 Circuit Breaker Tripped! Too many disconnections. Waiting {:?}...",
+                    self.config.circuit_breaker_window()
                 );
-                time::sleep(MAX_DISCONNECTIONS_WINDOW).await;
+                self.publish(ConnectionEvent::CircuitBreakerTripped {
+                    url: self.url.clone(),
+                    disconnections: self.disconnection_timestamps.lock().await.len(),
+                    cooldown: self.config.circuit_breaker_window(),
+                });
+                if !self
+                    .sleep_or_cancel(self.config.circuit_breaker_window())
+                    .await
+                {
+                    break;
+                }
                 // Clear timestamps after waiting to reset the breaker
                 self.disconnection_timestamps.lock().await.clear();
+                self.disconnects.set(0);
             }
 
-            // 2. Check Connection Rotation
-            if Instant::now() >= rotation_deadline {
-                println!("🔄 Proactive Connection Rotation triggered.");
-                *self.state.lock().await = ConnectionState::Rotating;
-                // Update rotation deadline for next cycle
-                rotation_deadline = Instant::now() + CONNECTION_ROTATION_DURATION;
-            }
-
-            // 3. Connect
+            // 2. Connect (rotation happens inside handle_stream, without a gap)
             *self.state.lock().await = ConnectionState::Connecting;
             println!("🔌 Connecting to WebSocket: {}", self.url);
 
-            match connect_async(&self.url).await {
+            let connected = tokio::select! {
+                connected = net::connect_ws(&self.url) => connected,
+                _ = self.cancel.cancelled() => break,
+            };
+            match connected {
                 Ok((ws_stream, _)) => {
                     println!("✅ Connected to WebSocket");
                     *self.state.lock().await = ConnectionState::Connected;
-                    backoff_ms = BASE_BACKOFF_MS; // Reset backoff on successful connection
+                    backoff_ms = self.config.base_backoff_ms; // Reset backoff on successful connection
                     *self.last_heartbeat.lock().await = Instant::now();
+                    self.publish(ConnectionEvent::Connected {
+                        url: self.url.clone(),
+                    });
 
-                    self.handle_stream(ws_stream).await;
+                    let reason = self.handle_stream(ws_stream).await;
+                    self.publish(ConnectionEvent::Disconnected {
+                        url: self.url.clone(),
+                        reason,
+                    });
                 }
                 Err(e) => {
-                    eprintln!("❌ Connection failed: {:?}", e);
+                    let error = FeedError::from(e);
+                    if error.is_retryable() {
+                        eprintln!("❌ Connection failed: {}", error);
+                    } else {
+                        // Retrying won't help until the certificate or config
+                        // changes, so back off fully but keep going so the
+                        // feed recovers by itself.
+                        eprintln!("🔒 Connection to {} failed: {}", self.url, error);
+                        backoff_ms = self.config.max_backoff_ms;
+                    }
+                    self.publish(ConnectionEvent::ConnectFailed {
+                        url: self.url.clone(),
+                        error: Arc::new(error),
+                    });
                 }
             }
 
-            // 4. Handle Disconnection / Reconnect Logic
-            if self.shutdown.load(Ordering::Relaxed) {
-                println!("🛑 WebSocket Handler shutting down.");
+            // 3. Handle Disconnection / Reconnect Logic
+            if self.cancel.is_cancelled() {
                 break;
             }
 
@@ -125,21 +609,128 @@ impl WsHandler {
                                                                     // let jitter = 100;
             let sleep_duration = Duration::from_millis(backoff_ms + jitter);
             println!("⏳ Reconnecting in {:?}...", sleep_duration);
-            time::sleep(sleep_duration).await;
+            self.publish(ConnectionEvent::Reconnecting {
+                url: self.url.clone(),
+                retry_in: sleep_duration,
+            });
+            if !self.sleep_or_cancel(sleep_duration).await {
+                break;
+            }
 
             // Increase backoff for next attempt, capped at MAX
-            backoff_ms = std::cmp::min(backoff_ms * 2, MAX_BACKOFF_MS);
+            backoff_ms = std::cmp::min(backoff_ms * 2, self.config.max_backoff_ms);
         }
+        println!("🛑 WebSocket Handler for {} shut down.", self.url);
         *self.state.lock().await = ConnectionState::Disconnected;
     }
 
-    async fn handle_stream(
+    fn on_connect_messages(&self) -> Vec<Message> {
+        self.on_connect
+            .as_ref()
+            .map(|hook| hook())
+            .unwrap_or_default()
+    }
+
+    /// Turns a data frame into what the consumer receives (decompressing
+    /// binary frames when configured), copying it to any taps. Frames that
+    /// fail to decompress are tapped raw.
+    fn decode_frame(&self, msg: Message) -> FeedFrame {
+        let frame = match (&self.decompression, msg) {
+            (Some(codec), Message::Binary(bytes)) => match codec.decode(&bytes) {
+                Ok(txt) => Ok(Message::Text(txt.into())),
+                Err(e) => {
+                    self.tap(&Message::Binary(bytes));
+                    return Err(FeedError::Decode(e));
+                }
+            },
+            (_, msg) => Ok(msg),
+        };
+        if let Ok(frame) = &frame {
+            self.tap(frame);
+        }
+        frame
+    }
+
+    fn tap(&self, frame: &Message) {
+        for tap in &self.taps {
+            tap.record(&self.url, frame);
+        }
+        Recordings::global().record(&self.url, frame);
+    }
+
+    /// Starts connecting the replacement connection for a rotation.
+    fn start_handover(&self) -> Handover {
+        let url = self.url.clone();
+        Handover::Connecting(Box::pin(async move {
+            net::connect_ws(&url)
+                .await
+                .map(|(ws, _)| ws)
+                .map_err(|e| e.to_string())
+        }))
+    }
+
+    /// Gives up on a rotation's replacement connection: the current one
+    /// stays, gets what `outbound` sent the replacement instead, and the
+    /// rotation is retried after `ROTATION_RETRY_DELAY`.
+    async fn abandon_handover(
         &self,
-        ws_stream: tokio_tungstenite::WebSocketStream<
-            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
-        >,
-    ) {
-        let (mut _write, mut read) = ws_stream.split();
+        error: &str,
+        handover: Option<Handover>,
+        write: &mut SplitSink<WsStream, Message>,
+        rotation: Pin<&mut time::Sleep>,
+    ) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+        eprintln!(
+            "⚠️ Rotation handover failed ({}); keeping current connection",
+            error
+        );
+        *self.state.lock().await = ConnectionState::Connected;
+        rotation.reset(Instant::now() + ROTATION_RETRY_DELAY);
+        if let Some(Handover::Syncing { diverted, .. }) = handover {
+            for msg in diverted {
+                write.send(msg).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_stream(&self, ws_stream: WsStream) -> DisconnectReason {
+        let (mut write, mut read) = ws_stream.split();
+
+        let initial = self.on_connect_messages();
+        let initial_count = initial.len();
+        for msg in initial {
+            if let Err(e) = write.send(msg).await {
+                eprintln!("❌ Failed to send on-connect message: {:?}", e);
+                return DisconnectReason::SendFailed(e.to_string());
+            }
+        }
+        if initial_count > 0 {
+            println!(
+                "📡 Sent {} on-connect message(s) to {}",
+                initial_count, self.url
+            );
+        }
+
+        // Make-before-break rotation: at the deadline a replacement connection
+        // is opened in the background while this one keeps delivering, and the
+        // two are swapped once the new one has produced data. Until then
+        // `outbound` goes to the new one, which is about to take over.
+        let rotation = time::sleep(self.config.rotation_period());
+        tokio::pin!(rotation);
+        let mut handover: Option<Handover> = None;
+
+        // Only one connection is live at a time, so holding the lock for the
+        // lifetime of this stream is uncontended.
+        let mut outbound = self.outbound_rx.lock().await;
+
+        let mut ping_interval = time::interval(
+            self.keepalive
+                .as_ref()
+                .map_or(Duration::from_secs(20), |k| k.interval),
+        );
+        ping_interval.tick().await; // first tick fires immediately — skip it
+                                    // When the unanswered ping went out, for timing its pong.
+        let mut ping_sent: Option<Instant> = None;
 
         loop {
             // Define timeouts
@@ -150,11 +741,20 @@ impl WsHandler {
                     match msg {
                         Some(Ok(msg)) => {
                             *self.last_heartbeat.lock().await = Instant::now();
+                            if let (Some(venue), Some(keepalive), Some(sent)) =
+                                (self.venue, &self.keepalive, ping_sent)
+                            {
+                                if keepalive.is_pong(&msg) {
+                                    Latency::global().record_feed(venue, sent.elapsed());
+                                    ping_sent = None;
+                                }
+                            }
                             match msg {
                                 Message::Text(_) | Message::Binary(_) => {
-                                    if let Err(_) = self.sender.send(Ok(msg)).await {
+                                    let frame = self.decode_frame(msg);
+                                    if self.sender.send(frame).await.is_err() {
                                         eprintln!("❌ Receiver dropped, stopping WebSocket.");
-                                        break;
+                                        break DisconnectReason::ConsumerDropped;
                                     }
                                 }
                                 Message::Ping(_) => {
@@ -165,33 +765,133 @@ impl WsHandler {
                                 }
                                 Message::Close(_) => {
                                      println!("⚠️ Server closed connection.");
-                                     break;
+                                     break DisconnectReason::ServerClosed;
                                 }
                                 _ => {}
                             }
                         }
                          Some(Err(e)) => {
                             eprintln!("❌ WebSocket error: {:?}", e);
-                             let _ = self.sender.send(Err(e.to_string())).await;
-                            break;
+                            let reason = DisconnectReason::Error(e.to_string());
+                             let _ = self.sender.send(Err(e.into())).await;
+                            break reason;
                         }
                         None => {
                              println!("⚠️ WebSocket stream ended.");
-                             break;
+                             break DisconnectReason::StreamEnded;
                         }
                     }
                 }
+                _ = &mut rotation, if handover.is_none() => {
+                    println!("🔄 Rotating connection to {} (make-before-break)", self.url);
+                    *self.state.lock().await = ConnectionState::Rotating;
+                    handover = Some(self.start_handover());
+                }
+                step = async { handover.as_mut().unwrap().step().await }, if handover.is_some() => {
+                    match step {
+                        HandoverStep::Connected(ws) => {
+                            let (mut new_write, new_read) = (*ws).split();
+                            let mut sent = Ok(());
+                            for msg in self.on_connect_messages() {
+                                sent = new_write.send(msg).await;
+                                if sent.is_err() {
+                                    break;
+                                }
+                            }
+                            match sent {
+                                Ok(()) => {
+                                    handover = Some(Handover::Syncing {
+                                        write: new_write,
+                                        read: new_read,
+                                        diverted: Vec::new(),
+                                        deadline: Instant::now() + ROTATION_SYNC_TIMEOUT,
+                                    });
+                                }
+                                Err(e) => {
+                                    handover = None;
+                                    let abandoned = self
+                                        .abandon_handover(&e.to_string(), None, &mut write, rotation.as_mut())
+                                        .await;
+                                    if let Err(e) = abandoned {
+                                        break DisconnectReason::SendFailed(e.to_string());
+                                    }
+                                }
+                            }
+                        }
+                        HandoverStep::Synced(first) => {
+                            let Some(Handover::Syncing {
+                                write: new_write,
+                                read: new_read,
+                                ..
+                            }) = handover.take()
+                            else {
+                                unreachable!("only a syncing handover gets data");
+                            };
+                            let _ = write.send(Message::Close(None)).await;
+                            (write, read) = (new_write, new_read);
+                            // Its pong would come back on the old connection.
+                            ping_sent = None;
+                            *self.last_heartbeat.lock().await = Instant::now();
+                            *self.state.lock().await = ConnectionState::Connected;
+                            rotation.as_mut().reset(Instant::now() + self.config.rotation_period());
+                            println!("✅ Rotation to new connection complete: {}", self.url);
+                            self.publish(ConnectionEvent::Rotated {
+                                url: self.url.clone(),
+                            });
+                            if self.sender.send(self.decode_frame(first)).await.is_err() {
+                                break DisconnectReason::ConsumerDropped;
+                            }
+                        }
+                        HandoverStep::Failed(e) => {
+                            let abandoned = self
+                                .abandon_handover(&e, handover.take(), &mut write, rotation.as_mut())
+                                .await;
+                            if let Err(e) = abandoned {
+                                break DisconnectReason::SendFailed(e.to_string());
+                            }
+                        }
+                    }
+                }
+                Some(out) = outbound.recv() => {
+                    if let Some(Handover::Syncing { write: next, diverted, .. }) = handover.as_mut() {
+                        diverted.push(out.clone());
+                        let Err(e) = next.send(out).await else {
+                            continue;
+                        };
+                        let abandoned = self
+                            .abandon_handover(&e.to_string(), handover.take(), &mut write, rotation.as_mut())
+                            .await;
+                        if let Err(e) = abandoned {
+                            break DisconnectReason::SendFailed(e.to_string());
+                        }
+                        continue;
+                    }
+                    if let Err(e) = write.send(out).await {
+                        eprintln!("❌ Error sending outbound message: {:?}", e);
+                        break DisconnectReason::SendFailed(e.to_string());
+                    }
+                }
+                _ = ping_interval.tick(), if self.keepalive.is_some() => {
+                    if let Some(keepalive) = &self.keepalive {
+                        if let Err(e) = write.send(keepalive.message()).await {
+                            eprintln!("❌ Error sending ping: {:?}", e);
+                            break DisconnectReason::SendFailed(e.to_string());
+                        }
+                        ping_sent = Some(Instant::now());
+                    }
+                }
                 _ = heartbeat_check => {
                      // Check heartbeat
                     let last = *self.last_heartbeat.lock().await;
-                    if last.elapsed() > HEARTBEAT_TIMEOUT {
+                    if last.elapsed() > self.config.heartbeat_timeout() {
                         eprintln!("💓 Heartbeat missed! Force reconnecting...");
-                        break;
-                    }
-                    if self.shutdown.load(Ordering::Relaxed) {
-                        break;
+                        break DisconnectReason::HeartbeatTimeout;
                     }
                 }
+                _ = self.cancel.cancelled() => {
+                    let _ = write.send(Message::Close(None)).await;
+                    break DisconnectReason::Shutdown;
+                }
             }
         }
     }
@@ -0,0 +1,163 @@
+//! InfluxDB line-protocol push (`[influx]`), for Grafana dashboards of
+//! historical spreads.
+//!
+//! Every `interval_ms` the latest quote of each venue and the spread between
+//! every two exchanges quoting a symbol are written in one request:
+//!
+//! ```text
+//! arbitrage_quote,exchange=binance,symbol=BTCUSDT bid=100.5,ask=100.7,mid=100.6 1700000000000000000
+//! arbitrage_spread,symbol=BTCUSDT,exchange_a=binance,exchange_b=bybit percent=0.05 1700000000000000000
+//! ```
+//!
+//! Timestamps are nanoseconds, the default precision of InfluxDB 1.x/2.x and
+//! VictoriaMetrics, so `url` is their plain write endpoint. A failed write is
+//! dropped, not retried: the next sample follows within a second anyway.
+
+use std::{fmt::Write, time::Duration};
+
+use chrono::Utc;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::InfluxConfig,
+    control::{Control, Status},
+    error::BridgeError,
+    keys,
+};
+
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Starts pushing if `url` is set; it runs until `cancel` fires.
+pub fn spawn(config: &InfluxConfig, control: Control, cancel: CancellationToken) {
+    let Some(url) = config.url.clone() else {
+        return;
+    };
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    let token = keys::var("INFLUX_TOKEN");
+    let prefix = config.measurement_prefix.clone();
+    let mut ticker = tokio::time::interval(config.interval());
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    println!("📈 Pushing quotes and spreads to {}", url);
+    tokio::spawn(async move {
+        let mut failing = false;
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = cancel.cancelled() => return,
+            }
+            let at_ns = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+            let body = lines(&control.status(), &prefix, at_ns);
+            if body.is_empty() {
+                continue;
+            }
+            let mut request = client.post(&url).body(body);
+            if let Some(token) = &token {
+                request = request.header("Authorization", format!("Token {}", token.expose()));
+            }
+            match write(request).await {
+                Ok(()) if failing => {
+                    println!("✅ Influx writes succeeding again");
+                    failing = false;
+                }
+                Ok(()) => {}
+                // Reported once per outage, not per sample.
+                Err(e) if !failing => {
+                    eprintln!("❌ Influx write to {} failed: {}", url, e);
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
+
+async fn write(request: reqwest::RequestBuilder) -> Result<(), BridgeError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(BridgeError::InfluxRejected {
+        status: status.as_u16(),
+        body,
+    })
+}
+
+/// The line-protocol body for one sample: a `<prefix>_quote` point per venue
+/// with a quote and a `<prefix>_spread` point per exchange pair.
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use arbitrage_bot::{
+///     bridge::influx::lines,
+///     control::{Status, VenueStatus},
+///     models::ids::ExchangeId,
+/// };
+///
+/// let venue = |exchange, bid: &str, ask: &str| VenueStatus {
+///     exchange,
+///     symbol: "BTCUSDT",
+///     bid: Some(bid.parse().unwrap()),
+///     ask: Some(ask.parse().unwrap()),
+///     polled: false,
+/// };
+/// let status = Status {
+///     uptime_secs: 0,
+///     execution_enabled: false,
+///     paused: false,
+///     alert_threshold_percent: "0.5".parse().unwrap(),
+///     execution_threshold_percent: "1".parse().unwrap(),
+///     venues: vec![
+///         venue(ExchangeId::Binance, "99", "101"),
+///         venue(ExchangeId::Bybit, "100.5", "101.5"),
+///     ],
+///     connections: BTreeMap::new(),
+///     circuit_breakers: Vec::new(),
+///     recording: Vec::new(),
+///     pauses: Default::default(),
+/// };
+/// let body = lines(&status, "arbitrage", 7);
+/// let lines: Vec<_> = body.lines().collect();
+/// assert_eq!(lines[0], "arbitrage_quote,exchange=binance,symbol=BTCUSDT bid=99,ask=101,mid=100 7");
+/// assert_eq!(
+///     lines[2],
+///     "arbitrage_spread,symbol=BTCUSDT,exchange_a=binance,exchange_b=bybit percent=1 7"
+/// );
+/// ```
+pub fn lines(status: &Status, prefix: &str, at_ns: i64) -> String {
+    let mut body = String::new();
+    for venue in &status.venues {
+        let (Some(bid), Some(ask), Some(mid)) = (venue.bid, venue.ask, venue.mid()) else {
+            continue;
+        };
+        let _ = writeln!(
+            body,
+            "{}_quote,exchange={},symbol={} bid={},ask={},mid={} {}",
+            prefix,
+            venue.exchange,
+            venue.symbol,
+            bid.normalize(),
+            ask.normalize(),
+            mid.normalize(),
+            at_ns
+        );
+    }
+    for spread in status.spreads() {
+        let _ = writeln!(
+            body,
+            "{}_spread,symbol={},exchange_a={},exchange_b={} percent={} {}",
+            prefix,
+            spread.symbol,
+            spread.exchange_a,
+            spread.exchange_b,
+            spread.percent.normalize(),
+            at_ns
+        );
+    }
+    body
+}
@@ -0,0 +1,117 @@
+//! Kafka event sink (`[kafka]`), see [`super::sink`].
+//!
+//! Every event goes to partition 0 of its topic, so consumers read them in
+//! the order the bot produced them. Topics must exist or be auto-created by
+//! the brokers. The client retries a failed request with backoff up to
+//! [`RECONNECT_DELAY`] apart, and gives up on it after [`RETRY_DEADLINE`],
+//! so an outage drops events instead of holding up the sink forever.
+
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rskafka::{
+    client::{
+        partition::{Compression, PartitionClient, UnknownTopicHandling},
+        Client, ClientBuilder,
+    },
+    record::Record,
+    BackoffConfig,
+};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use super::sink::{self, EventSink, MirrorSettings};
+use crate::{
+    binance::ws_handler::ConnectionEvent, config::KafkaConfig, control::Control, error::BridgeError,
+};
+
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+pub const RETRY_DEADLINE: Duration = Duration::from_secs(30);
+
+/// Starts mirroring if `brokers` is set; it runs until `cancel` fires.
+pub fn spawn(
+    config: &KafkaConfig,
+    control: Control,
+    connections: broadcast::Receiver<ConnectionEvent>,
+    cancel: CancellationToken,
+) {
+    if config.brokers.is_empty() {
+        return;
+    }
+    let brokers = config.brokers.clone();
+    let settings = MirrorSettings {
+        name: "Kafka",
+        prefix: config.topic_prefix.clone(),
+        quote_sample: config.quote_sample(),
+    };
+    tokio::spawn(async move {
+        let Some(client) = connect(&brokers, &cancel).await else {
+            return;
+        };
+        let sink = KafkaSink {
+            client,
+            partitions: HashMap::new(),
+        };
+        sink::mirror(sink, settings, control, connections, cancel).await;
+    });
+}
+
+struct KafkaSink {
+    client: Client,
+    /// One client per topic, created on its first event.
+    partitions: HashMap<String, PartitionClient>,
+}
+
+#[async_trait]
+impl EventSink for KafkaSink {
+    async fn publish(
+        &mut self,
+        topic: &str,
+        key: &str,
+        payload: Vec<u8>,
+    ) -> Result<(), BridgeError> {
+        if !self.partitions.contains_key(topic) {
+            let partition = self
+                .client
+                .partition_client(topic, 0, UnknownTopicHandling::Retry)
+                .await?;
+            self.partitions.insert(topic.to_string(), partition);
+        }
+        let record = Record {
+            key: Some(key.as_bytes().to_vec()),
+            value: Some(payload),
+            headers: Default::default(),
+            timestamp: Utc::now(),
+        };
+        self.partitions[topic]
+            .produce(vec![record], Compression::NoCompression)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Connects, retrying until it succeeds; `None` once `cancel` fires.
+async fn connect(brokers: &[String], cancel: &CancellationToken) -> Option<Client> {
+    loop {
+        let backoff = BackoffConfig {
+            max_backoff: RECONNECT_DELAY,
+            deadline: Some(RETRY_DEADLINE),
+            ..BackoffConfig::default()
+        };
+        let client = ClientBuilder::new(brokers.to_vec())
+            .backoff_config(backoff)
+            .build();
+        match client.await {
+            Ok(client) => {
+                println!("📤 Mirroring events to Kafka at {}", brokers.join(","));
+                return Some(client);
+            }
+            Err(e) => eprintln!("❌ Kafka connection failed: {}", BridgeError::from(e)),
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+            _ = cancel.cancelled() => return None,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Bridges to the message buses other trading stacks already run.
+//!
+//! Every bridge publishes the same JSON: a [`QuoteEvent`] per normalized
+//! quote and an [`Opportunity`] per spread the tracker finds. A bridge with a
+//! commands channel also takes [`Command`]s, applied through [`Control`] like
+//! the control API's requests.
+//!
+//! Event sinks ([`sink`]) go further: they mirror orders and errors as well,
+//! for downstream processing and auditing. The MQTT publisher is the light
+//! end: rate-limited alerts and a periodic summary for home dashboards.
+//! [`influx`] samples quotes and spreads into a time-series database.
+
+pub mod influx;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+#[cfg(feature = "nats")]
+pub mod nats;
+#[cfg(feature = "redis")]
+pub mod redis;
+pub mod sink;
+
+use serde::Serialize;
+
+use crate::{
+    control::{Command, Control},
+    error::BridgeError,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::{MarketType, Opportunity},
+    },
+    state,
+    ws::quote_bus::Quote,
+};
+
+/// A quote as bridges publish it.
+///
+/// ```
+/// use arbitrage_bot::{
+///     bridge::QuoteEvent,
+///     models::{ids::{ExchangeId, Symbol}, orderbook::MarketType},
+///     ws::{handlers::TopOfBook, quote_bus::Quote},
+/// };
+///
+/// let quote = Quote {
+///     exchange: ExchangeId::Bybit,
+///     top: TopOfBook {
+///         symbol: Symbol::intern("BTCUSDT"),
+///         bid: "100.5".parse().unwrap(),
+///         ask: "100.6".parse().unwrap(),
+///         bid_size: None,
+///         ask_size: None,
+///         market_type: MarketType::Futures,
+///         update_id: Some(7),
+///     },
+///     polled: false,
+/// };
+/// let json = serde_json::to_value(QuoteEvent::from(&quote)).unwrap();
+/// assert_eq!(json["exchange"], "bybit");
+/// assert_eq!(json["bid"], "100.5");
+/// assert_eq!(json["market_type"], "futures");
+/// assert_eq!(json["polled"], false);
+/// ```
+#[derive(Debug, Serialize)]
+pub struct QuoteEvent {
+    pub exchange: ExchangeId,
+    pub symbol: Symbol,
+    pub bid: Decimal,
+    pub ask: Decimal,
+    /// `spot` or `futures`.
+    pub market_type: &'static str,
+    pub update_id: Option<u64>,
+    /// Read over REST while the feed's WebSocket was down.
+    pub polled: bool,
+    /// When the bot published the quote.
+    pub at_ms: i64,
+}
+
+impl From<&Quote> for QuoteEvent {
+    fn from(quote: &Quote) -> Self {
+        Self {
+            exchange: quote.exchange,
+            symbol: quote.top.symbol,
+            bid: quote.top.bid,
+            ask: quote.top.ask,
+            market_type: match quote.top.market_type {
+                MarketType::Spot => "spot",
+                MarketType::Futures => "futures",
+            },
+            update_id: quote.top.update_id,
+            polled: quote.polled,
+            at_ms: state::now_ms(),
+        }
+    }
+}
+
+pub fn quote_json(quote: &Quote) -> String {
+    serde_json::to_string(&QuoteEvent::from(quote)).unwrap_or_default()
+}
+
+pub fn opportunity_json(opportunity: &Opportunity) -> String {
+    serde_json::to_string(opportunity).unwrap_or_default()
+}
+
+/// Parses and applies one command received on `channel`, reporting failures.
+pub async fn handle_command(control: &Control, channel: &str, payload: &[u8]) {
+    let result = match serde_json::from_slice::<Command>(payload) {
+        Ok(command) => {
+            println!("📨 Command from {}: {:?}", channel, command);
+            control
+                .apply(command, channel)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        Err(source) => Err(BridgeError::Command {
+            channel: channel.to_string(),
+            source,
+        }
+        .to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("❌ Command from {} failed: {}", channel, e);
+    }
+}
@@ -0,0 +1,238 @@
+//! MQTT publisher (`[mqtt]`) for home-lab dashboards such as Home Assistant.
+//!
+//! Three topics under the prefix:
+//! - `alert`: an opportunity at or above the alert threshold, at most one per
+//!   symbol and exchange pair every `alert_interval_secs`.
+//! - `stats`: a retained [`Stats`] summary every `stats_interval_secs`.
+//! - `availability`: retained `online` while connected; the broker's last
+//!   will sets `offline` once the connection drops, shutdown included.
+//!
+//! With `discovery_prefix` set, the stats also show up as Home Assistant
+//! sensors without any YAML. The client reconnects on its own; alerts and
+//! stats published while the broker is away are dropped once its queue is
+//! full.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use rumqttc::{AsyncClient, Event, EventLoop, LastWill, MqttOptions, Packet, QoS};
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::MqttConfig,
+    control::{Control, Status},
+    error::BridgeError,
+    keys,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+    },
+};
+
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// The summary published on `<prefix>/stats`.
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub uptime_secs: u64,
+    /// `on`, `paused` or `off`.
+    pub execution: &'static str,
+    pub alert_threshold_percent: Decimal,
+    /// Venues that have delivered a quote, out of `venues`.
+    pub venues_quoting: usize,
+    pub venues: usize,
+    /// Feed connections up, out of `feeds`.
+    pub feeds_connected: usize,
+    pub feeds: usize,
+    pub widest_spread_percent: Option<Decimal>,
+    /// e.g. `BTCUSDT binance/bybit`.
+    pub widest_spread: Option<String>,
+    /// Orders execution has placed or tried to place.
+    pub orders: u64,
+    /// Alerts published since startup.
+    pub alerts: u64,
+}
+
+impl Stats {
+    fn new(status: &Status, orders: u64, alerts: u64) -> Self {
+        let widest = status.spreads().into_iter().next();
+        Self {
+            uptime_secs: status.uptime_secs,
+            execution: match (status.execution_enabled, status.paused) {
+                (false, _) => "off",
+                (true, true) => "paused",
+                (true, false) => "on",
+            },
+            alert_threshold_percent: status.alert_threshold_percent,
+            venues_quoting: status.venues.iter().filter(|v| v.mid().is_some()).count(),
+            venues: status.venues.len(),
+            feeds_connected: status
+                .connections
+                .values()
+                .filter(|link| link.state == "connected")
+                .count(),
+            feeds: status.connections.len(),
+            widest_spread_percent: widest.as_ref().map(|s| s.percent),
+            widest_spread: widest
+                .map(|s| format!("{} {}/{}", s.symbol, s.exchange_a, s.exchange_b)),
+            orders,
+            alerts,
+        }
+    }
+}
+
+/// Starts publishing if `host` is set; it runs until `cancel` fires.
+pub fn spawn(config: &MqttConfig, control: Control, cancel: CancellationToken) {
+    let Some(host) = &config.host else {
+        return;
+    };
+    let availability = format!("{}/availability", config.topic_prefix);
+    let mut options = MqttOptions::new(&config.client_id, host, config.port);
+    options
+        .set_keep_alive(Duration::from_secs(30))
+        .set_last_will(LastWill::new(
+            &availability,
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+    if let Some(username) = &config.username {
+        let password = keys::var("MQTT_PASSWORD").unwrap_or_default();
+        options.set_credentials(username, password.expose());
+    }
+    let (client, events) = AsyncClient::new(options, 64);
+    println!(
+        "📤 Publishing alerts and stats to MQTT at {}:{}",
+        host, config.port
+    );
+    tokio::spawn(drive(
+        events,
+        client.clone(),
+        config.clone(),
+        cancel.clone(),
+    ));
+    tokio::spawn(publish(client, config.clone(), control, cancel));
+}
+
+/// Polls the connection, which is what sends and reconnects. Announces the
+/// bot on every (re)connect.
+async fn drive(
+    mut events: EventLoop,
+    client: AsyncClient,
+    config: MqttConfig,
+    cancel: CancellationToken,
+) {
+    let availability = format!("{}/availability", config.topic_prefix);
+    let mut failing = false;
+    loop {
+        let event = tokio::select! {
+            event = events.poll() => event,
+            _ = cancel.cancelled() => return,
+        };
+        match event {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                failing = false;
+                let _ = client.try_publish(&availability, QoS::AtLeastOnce, true, "online");
+                if let Some(prefix) = &config.discovery_prefix {
+                    for (topic, payload) in discovery(prefix, &config) {
+                        let _ = client.try_publish(topic, QoS::AtLeastOnce, true, payload);
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                // Reported once per outage; polling again reconnects.
+                if !failing {
+                    eprintln!(
+                        "❌ MQTT connection failed: {}",
+                        BridgeError::Mqtt(Box::new(e))
+                    );
+                    failing = true;
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+                    _ = cancel.cancelled() => return,
+                }
+            }
+        }
+    }
+}
+
+async fn publish(
+    client: AsyncClient,
+    config: MqttConfig,
+    control: Control,
+    cancel: CancellationToken,
+) {
+    let alert_topic = format!("{}/alert", config.topic_prefix);
+    let stats_topic = format!("{}/stats", config.topic_prefix);
+    let mut opportunities = control.subscribe_opportunities();
+    let mut ticker = tokio::time::interval(config.stats_interval());
+    let mut last_alert: HashMap<(Symbol, ExchangeId, ExchangeId), Instant> = HashMap::new();
+    let mut alerts = 0;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let orders = control.positions().recorded;
+                let stats = Stats::new(&control.status(), orders, alerts);
+                let payload = serde_json::to_vec(&stats).unwrap_or_default();
+                let _ = client.try_publish(&stats_topic, QoS::AtMostOnce, true, payload);
+            }
+            opportunity = opportunities.recv() => match opportunity {
+                Ok(opportunity) => {
+                    let pair = (opportunity.symbol, opportunity.exchange_a, opportunity.exchange_b);
+                    if last_alert.get(&pair).is_some_and(|at| at.elapsed() < config.alert_interval()) {
+                        continue;
+                    }
+                    last_alert.insert(pair, Instant::now());
+                    alerts += 1;
+                    let payload = serde_json::to_vec(&opportunity).unwrap_or_default();
+                    let _ = client.try_publish(&alert_topic, QoS::AtLeastOnce, false, payload);
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+            _ = cancel.cancelled() => return,
+        }
+    }
+}
+
+/// Home Assistant discovery configs: one sensor per [`Stats`] field worth
+/// graphing, all on one device.
+fn discovery(prefix: &str, config: &MqttConfig) -> Vec<(String, Vec<u8>)> {
+    const SENSORS: &[(&str, &str, Option<&str>)] = &[
+        ("widest_spread_percent", "Widest spread", Some("%")),
+        ("widest_spread", "Widest spread pair", None),
+        ("execution", "Execution", None),
+        ("alert_threshold_percent", "Alert threshold", Some("%")),
+        ("venues_quoting", "Venues quoting", None),
+        ("feeds_connected", "Feeds connected", None),
+        ("orders", "Orders", None),
+        ("alerts", "Alerts", None),
+        ("uptime_secs", "Uptime", Some("s")),
+    ];
+    let id = &config.client_id;
+    SENSORS
+        .iter()
+        .map(|(field, name, unit)| {
+            let payload = json!({
+                "name": name,
+                "unique_id": format!("{}_{}", id, field),
+                "state_topic": format!("{}/stats", config.topic_prefix),
+                "value_template": format!("{{{{ value_json.{} }}}}", field),
+                "availability_topic": format!("{}/availability", config.topic_prefix),
+                "unit_of_measurement": unit,
+                "device": { "identifiers": [id], "name": id },
+            });
+            (
+                format!("{}/sensor/{}/{}/config", prefix, id, field),
+                payload.to_string().into_bytes(),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,65 @@
+//! NATS event sink (`[nats]`), see [`super::sink`].
+//!
+//! Topics are NATS subjects and the key is sent as the `Key` header. The
+//! client reconnects on its own, including when the server isn't up yet at
+//! startup; events published meanwhile are buffered by the client until its
+//! queue is full.
+
+use async_nats::{Client, ConnectOptions, HeaderMap};
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use super::sink::{self, EventSink, MirrorSettings};
+use crate::{
+    binance::ws_handler::ConnectionEvent, config::NatsConfig, control::Control, error::BridgeError,
+};
+
+/// Starts mirroring if `url` is set; it runs until `cancel` fires.
+pub async fn spawn(
+    config: &NatsConfig,
+    control: Control,
+    connections: broadcast::Receiver<ConnectionEvent>,
+    cancel: CancellationToken,
+) -> Result<(), BridgeError> {
+    let Some(url) = &config.url else {
+        return Ok(());
+    };
+    let client = ConnectOptions::new()
+        .retry_on_initial_connect()
+        .connect(url.as_str())
+        .await?;
+    println!("📤 Mirroring events to NATS at {}", url);
+    let settings = MirrorSettings {
+        name: "NATS",
+        prefix: config.subject_prefix.clone(),
+        quote_sample: config.quote_sample(),
+    };
+    tokio::spawn(sink::mirror(
+        NatsSink(client),
+        settings,
+        control,
+        connections,
+        cancel,
+    ));
+    Ok(())
+}
+
+struct NatsSink(Client);
+
+#[async_trait]
+impl EventSink for NatsSink {
+    async fn publish(
+        &mut self,
+        topic: &str,
+        key: &str,
+        payload: Vec<u8>,
+    ) -> Result<(), BridgeError> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Key", key);
+        self.0
+            .publish_with_headers(topic.to_string(), headers, payload.into())
+            .await?;
+        Ok(())
+    }
+}
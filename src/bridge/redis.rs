@@ -0,0 +1,133 @@
+//! Redis pub/sub bridge (`[redis]`).
+//!
+//! Publishes every quote on `quotes_channel` and every opportunity on
+//! `opportunities_channel`, and applies commands received on
+//! `commands_channel`. A lost connection is retried every
+//! [`RECONNECT_DELAY`]; quotes published meanwhile are dropped, not queued.
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use redis::{aio::MultiplexedConnection, AsyncCommands, Client};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_util::sync::CancellationToken;
+
+use super::{handle_command, opportunity_json, quote_json};
+use crate::{config::RedisConfig, control::Control, error::BridgeError};
+
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Starts the bridge; it runs until `cancel` fires.
+pub fn spawn(
+    config: &RedisConfig,
+    control: Control,
+    cancel: CancellationToken,
+) -> Result<(), BridgeError> {
+    let Some(url) = &config.url else {
+        return Ok(());
+    };
+    let client = Client::open(url.as_str())?;
+    tokio::spawn(publish(
+        client.clone(),
+        config.clone(),
+        control.clone(),
+        cancel.clone(),
+    ));
+    if let Some(channel) = config.commands_channel.clone() {
+        tokio::spawn(take_commands(client, channel, control, cancel));
+    }
+    Ok(())
+}
+
+async fn publish(client: Client, config: RedisConfig, control: Control, cancel: CancellationToken) {
+    let mut quotes = control.subscribe_quotes();
+    let mut opportunities = control.subscribe_opportunities();
+    while let Some(mut connection) = connect(&client, &cancel).await {
+        loop {
+            let (channel, payload) = tokio::select! {
+                quote = quotes.recv() => match quote {
+                    Ok(quote) => (&config.quotes_channel, quote_json(&quote)),
+                    Err(RecvError::Lagged(missed)) => {
+                        eprintln!("⚠️ Redis bridge lagged, skipped {} quotes", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+                opportunity = opportunities.recv() => match opportunity {
+                    Ok(opportunity) => (&config.opportunities_channel, opportunity_json(&opportunity)),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                },
+                _ = cancel.cancelled() => return,
+            };
+            if let Err(e) = connection.publish::<_, _, ()>(channel, payload).await {
+                eprintln!("❌ Redis publish failed: {}", BridgeError::from(e));
+                break;
+            }
+        }
+    }
+}
+
+async fn take_commands(
+    client: Client,
+    channel: String,
+    control: Control,
+    cancel: CancellationToken,
+) {
+    loop {
+        let subscribed = async {
+            let mut pubsub = client.get_async_pubsub().await?;
+            pubsub.subscribe(&channel).await?;
+            Ok::<_, BridgeError>(pubsub)
+        };
+        let mut pubsub = match subscribed.await {
+            Ok(pubsub) => pubsub,
+            Err(e) => {
+                eprintln!("❌ Redis subscribe to {} failed: {}", channel, e);
+                if !wait(&cancel).await {
+                    return;
+                }
+                continue;
+            }
+        };
+        println!("📨 Taking commands from Redis channel {}", channel);
+        let mut messages = pubsub.on_message();
+        loop {
+            tokio::select! {
+                message = messages.next() => match message {
+                    Some(message) => handle_command(&control, &channel, message.get_payload_bytes()).await,
+                    None => break,
+                },
+                _ = cancel.cancelled() => return,
+            }
+        }
+        eprintln!("⚠️ Redis subscription to {} dropped", channel);
+    }
+}
+
+/// Connects, retrying until it succeeds; `None` once `cancel` fires.
+async fn connect(client: &Client, cancel: &CancellationToken) -> Option<MultiplexedConnection> {
+    loop {
+        match client.get_multiplexed_async_connection().await {
+            Ok(connection) => {
+                println!(
+                    "📤 Publishing to Redis at {}",
+                    client.get_connection_info().addr
+                );
+                return Some(connection);
+            }
+            Err(e) => eprintln!("❌ Redis connection failed: {}", BridgeError::from(e)),
+        }
+        if !wait(cancel).await {
+            return None;
+        }
+    }
+}
+
+/// Sleeps [`RECONNECT_DELAY`]; `false` if `cancel` fired meanwhile.
+async fn wait(cancel: &CancellationToken) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(RECONNECT_DELAY) => true,
+        _ = cancel.cancelled() => false,
+    }
+}
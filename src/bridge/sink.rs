@@ -0,0 +1,253 @@
+//! Event sinks: brokers that get a copy of everything the bot does, for
+//! downstream processing and auditing.
+//!
+//! [`mirror`] feeds an [`EventSink`] four topics under a prefix:
+//! `<prefix>.quotes` (at most one quote per venue per sample interval),
+//! `<prefix>.opportunities`, `<prefix>.orders` and `<prefix>.errors` (feed
+//! connection failures and orders the exchange refused). Each event is an
+//! [`Event`] as JSON, keyed by symbol, or by the feed URL or exchange for
+//! errors. Fills are not tracked, so there is no fill topic; an order event
+//! with an `order_id` is an order the exchange accepted.
+//!
+//! A sink that falls behind skips quotes and opportunities instead of slowing
+//! the bot down; orders are never skipped.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+
+use super::QuoteEvent;
+use crate::{
+    binance::ws_handler::ConnectionEvent,
+    control::Control,
+    error::{BridgeError, Classify, Severity},
+    models::{
+        ids::{ExchangeId, Symbol},
+        orderbook::Opportunity,
+    },
+    state::{self, OrderLeg},
+};
+
+/// A broker events are mirrored to.
+#[async_trait]
+pub trait EventSink: Send {
+    /// Delivers `payload` to `topic`, keyed by `key`. A failed event is
+    /// reported and dropped; the sink is expected to reconnect on its own.
+    async fn publish(
+        &mut self,
+        topic: &str,
+        key: &str,
+        payload: Vec<u8>,
+    ) -> Result<(), BridgeError>;
+}
+
+/// Everything a sink is sent, tagged by `type`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Quote(QuoteEvent),
+    Opportunity(Opportunity),
+    Order(OrderLeg),
+    Error(ErrorEvent),
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorEvent {
+    /// The feed URL, or the exchange for a refused order.
+    pub source: String,
+    pub message: String,
+    pub severity: Severity,
+    pub at_ms: i64,
+}
+
+impl Event {
+    /// The topic under the prefix, e.g. `quotes`.
+    pub fn topic(&self) -> &'static str {
+        match self {
+            Self::Quote(_) => "quotes",
+            Self::Opportunity(_) => "opportunities",
+            Self::Order(_) => "orders",
+            Self::Error(_) => "errors",
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Quote(q) => q.symbol.as_str(),
+            Self::Opportunity(o) => o.symbol.as_str(),
+            Self::Order(leg) => &leg.symbol,
+            Self::Error(e) => &e.source,
+        }
+    }
+
+    /// The feed failure behind a connection event; `None` for the ones that
+    /// aren't failures.
+    pub fn from_connection(event: &ConnectionEvent) -> Option<Self> {
+        let (message, severity) = match event {
+            ConnectionEvent::ConnectFailed { error, .. } => {
+                (format!("connect failed: {}", error), error.severity())
+            }
+            ConnectionEvent::Disconnected { reason, .. } => {
+                (format!("connection lost ({})", reason), Severity::Warning)
+            }
+            ConnectionEvent::CircuitBreakerTripped {
+                disconnections,
+                cooldown,
+                ..
+            } => (
+                format!(
+                    "circuit breaker tripped after {} disconnections, pausing {:?}",
+                    disconnections, cooldown
+                ),
+                Severity::Warning,
+            ),
+            ConnectionEvent::Connected { .. }
+            | ConnectionEvent::Reconnecting { .. }
+            | ConnectionEvent::Rotated { .. } => return None,
+        };
+        Some(Self::Error(ErrorEvent {
+            source: event.url().to_string(),
+            message,
+            severity,
+            at_ms: state::now_ms(),
+        }))
+    }
+
+    /// The order, followed by an error if the exchange refused it.
+    ///
+    /// ```
+    /// use arbitrage_bot::{
+    ///     bridge::sink::Event,
+    ///     models::ids::ExchangeId,
+    ///     state::{LegSide, OrderLeg},
+    /// };
+    ///
+    /// let leg = OrderLeg {
+    ///     exchange: ExchangeId::Binance,
+    ///     symbol: "BTCUSDT".into(),
+    ///     side: LegSide::Buy,
+    ///     price: "100".parse().unwrap(),
+    ///     quantity: "0.01".parse().unwrap(),
+    ///     order_id: None,
+    ///     account: None,
+    ///     error: Some("insufficient balance".into()),
+    ///     placed_at_ms: 0,
+    /// };
+    /// let events = Event::from_order(&leg);
+    /// let topics: Vec<_> = events.iter().map(Event::topic).collect();
+    /// assert_eq!(topics, ["orders", "errors"]);
+    /// let error = serde_json::to_value(&events[1]).unwrap();
+    /// assert_eq!(error["source"], "binance");
+    /// assert_eq!(error["severity"], "critical");
+    /// ```
+    pub fn from_order(leg: &OrderLeg) -> Vec<Self> {
+        let mut events = vec![Self::Order(leg.clone())];
+        if let Some(error) = &leg.error {
+            events.push(Self::Error(ErrorEvent {
+                source: leg.exchange.name().to_string(),
+                message: format!(
+                    "{:?} {} {} failed: {}",
+                    leg.side, leg.quantity, leg.symbol, error
+                ),
+                severity: Severity::Critical,
+                at_ms: leg.placed_at_ms,
+            }));
+        }
+        events
+    }
+}
+
+/// What [`mirror`] sends where.
+#[derive(Debug, Clone)]
+pub struct MirrorSettings {
+    /// Shown in logs, e.g. `Kafka`.
+    pub name: &'static str,
+    /// Topics are `<prefix>.quotes` and so on.
+    pub prefix: String,
+    /// Minimum gap between two quotes of the same venue; zero sends all.
+    pub quote_sample: Duration,
+}
+
+/// Sends every event to `sink` until `cancel` fires.
+pub async fn mirror(
+    mut sink: impl EventSink,
+    settings: MirrorSettings,
+    control: Control,
+    mut connections: broadcast::Receiver<ConnectionEvent>,
+    cancel: CancellationToken,
+) {
+    let mut quotes = control.subscribe_quotes();
+    let mut opportunities = control.subscribe_opportunities();
+    let mut execution = control.subscribe_execution();
+    let mut seen = execution.borrow_and_update().recorded;
+    let mut last_quote: HashMap<(ExchangeId, Symbol), Instant> = HashMap::new();
+    let mut failing = false;
+    loop {
+        let events = tokio::select! {
+            quote = quotes.recv() => match quote {
+                Ok(quote) => {
+                    let venue = (quote.exchange, quote.top.symbol);
+                    if last_quote.get(&venue).is_some_and(|at| at.elapsed() < settings.quote_sample) {
+                        continue;
+                    }
+                    last_quote.insert(venue, Instant::now());
+                    vec![Event::Quote(QuoteEvent::from(&*quote))]
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+            opportunity = opportunities.recv() => match opportunity {
+                Ok(opportunity) => vec![Event::Opportunity(opportunity)],
+                Err(RecvError::Lagged(missed)) => {
+                    eprintln!("⚠️ {} sink lagged, skipped {} opportunities", settings.name, missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            },
+            changed = execution.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                let state = execution.borrow_and_update();
+                let events = state.since(seen).iter().flat_map(Event::from_order).collect();
+                seen = state.recorded;
+                events
+            }
+            event = connections.recv() => match event {
+                Ok(event) => match Event::from_connection(&event) {
+                    Some(event) => vec![event],
+                    None => continue,
+                },
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            },
+            _ = cancel.cancelled() => return,
+        };
+        for event in events {
+            let topic = format!("{}.{}", settings.prefix, event.topic());
+            let payload = serde_json::to_vec(&event).unwrap_or_default();
+            match sink.publish(&topic, event.key(), payload).await {
+                Ok(()) if failing => {
+                    println!("✅ {} sink delivering again", settings.name);
+                    failing = false;
+                }
+                Ok(()) => {}
+                // Reported once per outage, not per event.
+                Err(e) if !failing => {
+                    eprintln!(
+                        "❌ {} sink publish to {} failed: {}",
+                        settings.name, topic, e
+                    );
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Exchange maintenance windows and new listings (`[calendar]`).
+//!
+//! The schedule comes from a TOML file (`[calendar] file`) and/or a JSON
+//! document at `[calendar] url`, both of this shape:
+//!
+//! ```toml
+//! [[maintenance]]
+//! exchange = "bybit"
+//! symbols = ["BTCUSDT"]          # omit for the whole venue
+//! start = "2026-10-20T02:00:00Z"
+//! end = "2026-10-20T04:00:00Z"
+//! note = "matching engine upgrade"
+//!
+//! [[listing]]
+//! exchange = "binance"
+//! symbol = "NEWUSDT"
+//! at = "2026-10-21T12:00:00Z"
+//! ```
+//!
+//! Execution holds off on a venue (or only the listed symbols) from
+//! `margin_secs` before a window until `margin_secs` after it. Alerts on a
+//! symbol within `listing_window_secs` of its listing on either exchange say
+//! so: fresh listings routinely show huge spreads that can't be traded (thin
+//! books, deposits and withdrawals not yet open).
+//!
+//! Both sources are read again every `refresh_secs`. A source that fails
+//! keeps its previous entries, so a flaky URL never clears a known window.
+
+use std::{fmt, fs, path::Path, sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::CalendarConfig,
+    error::StorageError,
+    models::ids::{ExchangeId, Symbol},
+    net,
+};
+
+/// Maintenance windows and listings, as read from one or more sources.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Schedule {
+    pub maintenance: Vec<Maintenance>,
+    #[serde(rename = "listing")]
+    pub listings: Vec<Listing>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Maintenance {
+    pub exchange: ExchangeId,
+    /// The symbols affected; empty means the whole venue.
+    #[serde(default)]
+    pub symbols: Vec<Symbol>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Listing {
+    pub exchange: ExchangeId,
+    pub symbol: Symbol,
+    pub at: DateTime<Utc>,
+}
+
+impl Schedule {
+    /// Reads a TOML schedule.
+    pub fn load(path: &Path) -> Result<Self, StorageError> {
+        let raw = fs::read_to_string(path).map_err(StorageError::io(path))?;
+        toml::from_str(&raw).map_err(|e| StorageError::Parse {
+            path: path.into(),
+            message: e.to_string(),
+        })
+    }
+
+    fn merge(mut self, other: &Schedule) -> Self {
+        self.maintenance.extend(other.maintenance.iter().cloned());
+        self.listings.extend(other.listings.iter().cloned());
+        self
+    }
+}
+
+impl Maintenance {
+    pub fn covers(&self, exchange: ExchangeId, symbol: Symbol) -> bool {
+        self.exchange == exchange && (self.symbols.is_empty() || self.symbols.contains(&symbol))
+    }
+}
+
+impl fmt::Display for Maintenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} maintenance {} – {}",
+            self.exchange,
+            self.start.format("%Y-%m-%d %H:%M"),
+            self.end.format("%Y-%m-%d %H:%M UTC")
+        )?;
+        if let Some(note) = &self.note {
+            write!(f, " ({})", note)?;
+        }
+        Ok(())
+    }
+}
+
+impl Listing {
+    /// How the listing relates to `now`, e.g. "bybit listed NEWUSDT 12 min ago".
+    pub fn describe(&self, now: DateTime<Utc>) -> String {
+        // To the nearest minute, either way.
+        let minutes = ((now - self.at).num_seconds() + 30).div_euclid(60);
+        if minutes >= 0 {
+            format!(
+                "{} listed {} {} min ago",
+                self.exchange, self.symbol, minutes
+            )
+        } else {
+            format!(
+                "{} lists {} in {} min",
+                self.exchange, self.symbol, -minutes
+            )
+        }
+    }
+}
+
+/// The current schedule, shared by the engine and the tracker and swapped
+/// whole on every refresh.
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    schedule: Arc<ArcSwap<Schedule>>,
+    /// What the file and the URL held at startup, where refreshes carry on.
+    loaded: Arc<(Schedule, Schedule)>,
+    margin: TimeDelta,
+    listing_window: TimeDelta,
+}
+
+impl Calendar {
+    pub fn new(schedule: Schedule, config: &CalendarConfig) -> Self {
+        Self {
+            schedule: Arc::new(ArcSwap::from_pointee(schedule)),
+            loaded: Arc::default(),
+            margin: TimeDelta::seconds(config.margin_secs as i64),
+            listing_window: TimeDelta::seconds(config.listing_window_secs as i64),
+        }
+    }
+
+    /// Reads every configured source, or `None` without any. An unreadable
+    /// file is an error; an unreachable URL only a warning, retried with
+    /// every refresh.
+    pub async fn load(config: &CalendarConfig) -> Result<Option<Self>, StorageError> {
+        if !config.is_enabled() {
+            return Ok(None);
+        }
+        let file = match &config.file {
+            Some(path) => Schedule::load(path)?,
+            None => Schedule::default(),
+        };
+        let remote = match &config.url {
+            Some(url) => fetch(url).await.unwrap_or_else(|e| {
+                eprintln!("⚠️ Calendar {} unreachable: {:#}", url, e);
+                Schedule::default()
+            }),
+            None => Schedule::default(),
+        };
+        let schedule = file.clone().merge(&remote);
+        println!(
+            "📅 Calendar: {} maintenance window(s), {} listing(s)",
+            schedule.maintenance.len(),
+            schedule.listings.len()
+        );
+        Ok(Some(Self {
+            loaded: Arc::new((file, remote)),
+            ..Self::new(schedule, config)
+        }))
+    }
+
+    /// Re-reads the sources every `refresh_secs` until `cancel` fires.
+    pub fn spawn_refresh(&self, config: &CalendarConfig, cancel: CancellationToken) {
+        if config.refresh_secs == 0 {
+            return;
+        }
+        let calendar = self.clone();
+        let config = config.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = calendar.refresh(&config) => {}
+                _ = cancel.cancelled() => {}
+            }
+        });
+    }
+
+    async fn refresh(&self, config: &CalendarConfig) {
+        // What each source last returned, kept while it fails.
+        let (mut file, mut remote) = (*self.loaded).clone();
+        let every = Duration::from_secs(config.refresh_secs);
+        loop {
+            tokio::time::sleep(every).await;
+            if let Some(path) = &config.file {
+                match Schedule::load(path) {
+                    Ok(schedule) => file = schedule,
+                    Err(e) => eprintln!("❌ Calendar reload failed: {}", e),
+                }
+            }
+            if let Some(url) = &config.url {
+                match fetch(url).await {
+                    Ok(schedule) => remote = schedule,
+                    Err(e) => eprintln!("❌ Calendar {} unreachable: {:#}", url, e),
+                }
+            }
+            let schedule = file.clone().merge(&remote);
+            if **self.schedule.load() != schedule {
+                println!(
+                    "📅 Calendar updated: {} maintenance window(s), {} listing(s)",
+                    schedule.maintenance.len(),
+                    schedule.listings.len()
+                );
+                self.schedule.store(Arc::new(schedule));
+            }
+        }
+    }
+
+    /// The maintenance window, widened by the margin, that keeps `symbol` on
+    /// `exchange` from trading at `now`.
+    pub fn maintenance(
+        &self,
+        exchange: ExchangeId,
+        symbol: Symbol,
+        now: DateTime<Utc>,
+    ) -> Option<Maintenance> {
+        self.schedule
+            .load()
+            .maintenance
+            .iter()
+            .find(|m| {
+                m.covers(exchange, symbol)
+                    && m.start - self.margin <= now
+                    && now < m.end + self.margin
+            })
+            .cloned()
+    }
+
+    /// A listing of `symbol` on `exchange` within the listing window of `now`.
+    pub fn listing(
+        &self,
+        exchange: ExchangeId,
+        symbol: Symbol,
+        now: DateTime<Utc>,
+    ) -> Option<Listing> {
+        self.schedule
+            .load()
+            .listings
+            .iter()
+            .find(|l| {
+                l.exchange == exchange
+                    && l.symbol == symbol
+                    && (now - l.at).abs() <= self.listing_window
+            })
+            .cloned()
+    }
+}
+
+async fn fetch(url: &str) -> anyhow::Result<Schedule> {
+    let response = net::http_client()
+        .get(url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.json().await?)
+}
@@ -1 +1,2571 @@
-// # load API keys, symbols, etc
+//! Runtime configuration, loaded from a TOML file at startup.
+//!
+//! The file is `config.toml` in the working directory unless `ARB_CONFIG`
+//! points elsewhere. Every section is optional and a missing file means
+//! defaults, so the bot runs unchanged without one. See
+//! `config.example.toml` for the available keys.
+//!
+//! Any key can also be set from the environment, over the file or with no
+//! file at all (a container without a mounted config): `ARB__` followed by
+//! the section path and key, separated by `__`, in any case. So
+//! `ARB__ENGINE__EXECUTION__ENABLED=true` sets `[engine.execution]
+//! enabled`. A value is read as a TOML value where it is one (`true`, `60`,
+//! `0.5`, `"quoted"`, `["a", "b"]`, `{ key = "value" }`) and as a string
+//! otherwise, commas and all, so a list has to be a TOML array
+//! (`["BTCUSDT", "ETHUSDT"]`). Tables keyed by name, such as
+//! `[fx.currencies]`, and arrays of tables such as `[[tap]]` are set whole,
+//! as an inline table or array: `ARB__FX__CURRENCIES={ USDC = { symbol =
+//! "USDCUSDT" } }`. A table set that way is merged into the file's. An
+//! entry of an array of tables is reached by its index, so
+//! `ARB__ENGINE__EXECUTION__ACCOUNTS__0__SUB_ACCOUNT` sets the first
+//! account's `sub_account`. A value that reads as TOML but not as what its
+//! key takes, such as a numeric sub-account ID for a string key, is taken
+//! as the string.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use rust_decimal_macros::dec;
+use serde::Deserialize;
+
+use crate::{
+    constants::config as cfg_const,
+    models::{ids::ExchangeId, money::Decimal},
+    pauses,
+    state::Balance,
+};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub network: NetworkConfig,
+    pub keys: KeysConfig,
+    pub tls: TlsConfig,
+    pub ws: WsSettings,
+    pub signing: SigningSettings,
+    pub feeds: FeedsConfig,
+    #[serde(rename = "tap")]
+    pub taps: Vec<TapConfig>,
+    pub recording: RecordingConfig,
+    pub notifications: NotificationsConfig,
+    pub engine: EngineConfig,
+    pub calendar: CalendarConfig,
+    pub transfers: TransfersConfig,
+    pub fx: FxConfig,
+    pub volatility: VolatilityConfig,
+    pub liveness: LivenessConfig,
+    pub failover: FailoverConfig,
+    pub pauses: PausesConfig,
+    pub listing_mode: ListingModeConfig,
+    pub runtime: RuntimeConfig,
+    pub limits: LimitsConfig,
+    pub api: ApiConfig,
+    pub tui: TuiConfig,
+    pub grpc: GrpcConfig,
+    pub redis: RedisConfig,
+    pub kafka: KafkaConfig,
+    pub nats: NatsConfig,
+    pub mqtt: MqttConfig,
+    pub influx: InfluxConfig,
+}
+
+/// How the bot reaches exchanges, for both WebSocket and REST traffic.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// `socks5://[user:pass@]host:port` or `http://[user:pass@]host:port`.
+    pub proxy: Option<String>,
+    /// URL prefix rewrites, e.g. `"wss://stream.binance.com:9443" =
+    /// "wss://data-stream.binance.vision"`. The longest matching prefix wins.
+    pub endpoints: HashMap<String, String>,
+    /// Static host → IP overrides (like `curl --resolve`). TLS still
+    /// verifies against the original hostname.
+    pub resolve: HashMap<String, IpAddr>,
+}
+
+impl NetworkConfig {
+    /// Applies the endpoint overrides to `url`.
+    ///
+    /// ```
+    /// use arbitrage_bot::config::NetworkConfig;
+    ///
+    /// let mut net = NetworkConfig::default();
+    /// net.endpoints.insert(
+    ///     "wss://stream.binance.com:9443".into(),
+    ///     "wss://data-stream.binance.vision:9443".into(),
+    /// );
+    /// assert_eq!(
+    ///     net.endpoint("wss://stream.binance.com:9443/ws"),
+    ///     "wss://data-stream.binance.vision:9443/ws"
+    /// );
+    /// assert_eq!(net.endpoint("wss://fstream.binance.com/ws"), "wss://fstream.binance.com/ws");
+    /// ```
+    pub fn endpoint(&self, url: &str) -> String {
+        self.endpoints
+            .iter()
+            .filter(|(prefix, _)| url.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(prefix, replacement)| format!("{}{}", replacement, &url[prefix.len()..]))
+            .unwrap_or_else(|| url.to_string())
+    }
+}
+
+/// rustls options for all exchange connections (see `crate::tls`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// Refuse to negotiate anything older than TLS 1.3.
+    pub tls13_only: bool,
+    /// Host → accepted SHA-256 fingerprints (hex, colons optional) of the
+    /// leaf certificate. Hosts not listed are verified by the CA chain only.
+    pub pins: HashMap<String, Vec<String>>,
+}
+
+impl TlsConfig {
+    /// Whether anything differs from the stock rustls/webpki setup.
+    pub fn is_customized(&self) -> bool {
+        self.tls13_only || !self.pins.is_empty()
+    }
+}
+
+/// Tuning of a managed WebSocket connection (`WsHandler`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WsConfig {
+    pub base_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// A connection with no inbound frame for this long is considered dead.
+    pub heartbeat_timeout_secs: u64,
+    /// Age at which a connection is proactively replaced.
+    pub rotation_period_secs: u64,
+    /// The circuit breaker trips after `circuit_breaker_limit` disconnections
+    /// within `circuit_breaker_window_secs`, and then pauses for that window.
+    pub circuit_breaker_window_secs: u64,
+    pub circuit_breaker_limit: usize,
+}
+
+impl Default for WsConfig {
+    fn default() -> Self {
+        Self {
+            base_backoff_ms: 1000,
+            max_backoff_ms: 60_000,
+            heartbeat_timeout_secs: 60,
+            rotation_period_secs: 23 * 3600, // below the exchanges' 24h limit
+            circuit_breaker_window_secs: 300,
+            circuit_breaker_limit: 10,
+        }
+    }
+}
+
+impl WsConfig {
+    pub fn heartbeat_timeout(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_timeout_secs)
+    }
+
+    pub fn rotation_period(&self) -> Duration {
+        Duration::from_secs(self.rotation_period_secs)
+    }
+
+    pub fn circuit_breaker_window(&self) -> Duration {
+        Duration::from_secs(self.circuit_breaker_window_secs)
+    }
+
+    fn validate(&self, section: &str) -> anyhow::Result<()> {
+        if self.base_backoff_ms == 0 || self.max_backoff_ms < self.base_backoff_ms {
+            bail!("[{}] needs 0 < base_backoff_ms <= max_backoff_ms", section);
+        }
+        if self.heartbeat_timeout_secs == 0 || self.rotation_period_secs == 0 {
+            bail!(
+                "[{}] heartbeat_timeout_secs and rotation_period_secs must be positive",
+                section
+            );
+        }
+        if self.circuit_breaker_limit == 0 {
+            bail!("[{}] circuit_breaker_limit must be positive", section);
+        }
+        Ok(())
+    }
+}
+
+/// Per-exchange partial override of [`WsConfig`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WsOverrides {
+    pub base_backoff_ms: Option<u64>,
+    pub max_backoff_ms: Option<u64>,
+    pub heartbeat_timeout_secs: Option<u64>,
+    pub rotation_period_secs: Option<u64>,
+    pub circuit_breaker_window_secs: Option<u64>,
+    pub circuit_breaker_limit: Option<usize>,
+}
+
+/// `[ws.default]` applies to every connection; `[ws.exchanges.<name>]`
+/// overrides individual values for one exchange (see `constants::exchange_names`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WsSettings {
+    pub default: WsConfig,
+    pub exchanges: HashMap<String, WsOverrides>,
+}
+
+impl WsSettings {
+    pub fn for_exchange(&self, exchange: &str) -> WsConfig {
+        let mut config = self.default.clone();
+        let Some(o) = self.exchanges.get(exchange) else {
+            return config;
+        };
+        config.base_backoff_ms = o.base_backoff_ms.unwrap_or(config.base_backoff_ms);
+        config.max_backoff_ms = o.max_backoff_ms.unwrap_or(config.max_backoff_ms);
+        config.heartbeat_timeout_secs = o
+            .heartbeat_timeout_secs
+            .unwrap_or(config.heartbeat_timeout_secs);
+        config.rotation_period_secs = o
+            .rotation_period_secs
+            .unwrap_or(config.rotation_period_secs);
+        config.circuit_breaker_window_secs = o
+            .circuit_breaker_window_secs
+            .unwrap_or(config.circuit_breaker_window_secs);
+        config.circuit_breaker_limit = o
+            .circuit_breaker_limit
+            .unwrap_or(config.circuit_breaker_limit);
+        config
+    }
+}
+
+/// Timing of signed requests: how long the exchange accepts a request after
+/// its timestamp, and how far that timestamp is shifted from the local clock.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SigningConfig {
+    /// `recvWindow`; larger tolerates slow links, smaller limits how stale
+    /// an order may arrive.
+    pub recv_window_ms: u64,
+    /// Added to the local clock for request timestamps. Negative values keep
+    /// a clock running slightly ahead of the exchange's from being rejected.
+    pub timestamp_offset_ms: i64,
+}
+
+impl Default for SigningConfig {
+    fn default() -> Self {
+        Self {
+            recv_window_ms: 5000,
+            timestamp_offset_ms: 0,
+        }
+    }
+}
+
+impl SigningConfig {
+    fn validate(&self, section: &str) -> anyhow::Result<()> {
+        if !(1..=60_000).contains(&self.recv_window_ms) {
+            bail!("[{}] recv_window_ms must be between 1 and 60000", section);
+        }
+        if self.timestamp_offset_ms.unsigned_abs() >= self.recv_window_ms {
+            bail!(
+                "[{}] timestamp_offset_ms must stay within recv_window_ms",
+                section
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Per-exchange partial override of [`SigningConfig`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SigningOverrides {
+    pub recv_window_ms: Option<u64>,
+    pub timestamp_offset_ms: Option<i64>,
+}
+
+/// `[signing.default]` applies to every exchange; `[signing.exchanges.<name>]`
+/// overrides individual values for one.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SigningSettings {
+    pub default: SigningConfig,
+    pub exchanges: HashMap<String, SigningOverrides>,
+}
+
+impl SigningSettings {
+    pub fn for_exchange(&self, exchange: &str) -> SigningConfig {
+        let mut config = self.default.clone();
+        let Some(o) = self.exchanges.get(exchange) else {
+            return config;
+        };
+        config.recv_window_ms = o.recv_window_ms.unwrap_or(config.recv_window_ms);
+        config.timestamp_offset_ms = o.timestamp_offset_ms.unwrap_or(config.timestamp_offset_ms);
+        config
+    }
+}
+
+/// Market-data streams (`[feeds]`), with per-exchange settings in
+/// `[feeds.<exchange>]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeedsConfig {
+    /// Read the symbols of a feed connection over REST as soon as it
+    /// reconnects (see `crate::gap_fill`).
+    pub gap_fill: bool,
+    pub binance: BinanceFeedConfig,
+}
+
+impl Default for FeedsConfig {
+    fn default() -> Self {
+        Self {
+            gap_fill: true,
+            binance: BinanceFeedConfig::default(),
+        }
+    }
+}
+
+/// Which Binance partial-book stream quotes come from. Each frame is a
+/// complete top-N snapshot, so unlike the diff stream it needs no local book
+/// synced from a REST snapshot. Only futures partial-book frames name their
+/// symbol; spot endpoints still need the diff stream.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BinanceFeedConfig {
+    /// Levels per side: 5, 10 or 20.
+    pub depth_levels: u8,
+    /// Push interval: 100, 250 or 500 ms.
+    pub depth_update_ms: u32,
+}
+
+impl Default for BinanceFeedConfig {
+    fn default() -> Self {
+        Self {
+            depth_levels: 5,
+            depth_update_ms: 100,
+        }
+    }
+}
+
+impl BinanceFeedConfig {
+    /// Stream name for `symbol`; 250ms is the stream's own default and has no
+    /// suffix.
+    ///
+    /// ```
+    /// use arbitrage_bot::config::BinanceFeedConfig;
+    ///
+    /// let mut feed = BinanceFeedConfig::default();
+    /// assert_eq!(feed.depth_stream("BTCUSDT"), "btcusdt@depth5@100ms");
+    /// feed.depth_levels = 20;
+    /// feed.depth_update_ms = 250;
+    /// assert_eq!(feed.depth_stream("BTCUSDT"), "btcusdt@depth20");
+    /// ```
+    pub fn depth_stream(&self, symbol: &str) -> String {
+        let speed = match self.depth_update_ms {
+            250 => String::new(),
+            ms => format!("@{}ms", ms),
+        };
+        format!(
+            "{}@depth{}{}",
+            symbol.to_lowercase(),
+            self.depth_levels,
+            speed
+        )
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if ![5, 10, 20].contains(&self.depth_levels) {
+            bail!("[feeds.binance] depth_levels must be 5, 10 or 20");
+        }
+        if ![100, 250, 500].contains(&self.depth_update_ms) {
+            bail!("[feeds.binance] depth_update_ms must be 100, 250 or 500");
+        }
+        Ok(())
+    }
+}
+
+/// Copies raw inbound frames of one exchange (optionally only those that
+/// mention `symbol`) to a file and/or a local debug WebSocket endpoint.
+/// Declared as `[[tap]]` tables; see `crate::ws::tap`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TapConfig {
+    pub exchange: String,
+    pub symbol: Option<String>,
+    /// Append `timestamp \t url \t frame` lines to this file.
+    pub file: Option<PathBuf>,
+    /// Serve the same lines to WebSocket clients connecting to this address.
+    pub listen: Option<SocketAddr>,
+}
+
+/// Full raw-feed recording per symbol, switched on and off at runtime
+/// through the control API (see `crate::ws::tap::Recordings`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecordingConfig {
+    /// Each symbol's frames go to `<dir>/<SYMBOL>.tsv`, in the `[[tap]]`
+    /// line format.
+    pub dir: PathBuf,
+    /// Symbols recorded from startup.
+    pub symbols: Vec<String>,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("recordings"),
+            symbols: Vec::new(),
+        }
+    }
+}
+
+/// Which channels arbitrage alerts go out on, by symbol and spread (see
+/// `crate::notifications::router`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsConfig {
+    /// `[[notifications.route]]` tables. Without any, every alert from the
+    /// 5% default threshold up goes to Telegram.
+    #[serde(rename = "route")]
+    pub routes: Vec<AlertRoute>,
+    /// How often the alerts routed to the digest are sent, as one summary.
+    pub digest_interval_secs: u64,
+    pub email: EmailConfig,
+    pub telegram: TelegramConfig,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            routes: Vec::new(),
+            digest_interval_secs: 3600,
+            email: EmailConfig::default(),
+            telegram: TelegramConfig::default(),
+        }
+    }
+}
+
+impl NotificationsConfig {
+    pub fn digest_interval(&self) -> Duration {
+        Duration::from_secs(self.digest_interval_secs)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.digest_interval_secs == 0 {
+            bail!("[notifications] digest_interval_secs must be positive");
+        }
+        for route in &self.routes {
+            if route.min_diff < Decimal::ZERO {
+                bail!("[[notifications.route]] min_diff can't be negative");
+            }
+            if route.max_diff.is_some_and(|max| max <= route.min_diff) {
+                bail!("[[notifications.route]] max_diff must be above min_diff");
+            }
+            if route.channels.is_empty() {
+                bail!("[[notifications.route]] needs at least one channel");
+            }
+            if route.symbols.iter().any(|s| s.trim().is_empty()) {
+                bail!("[[notifications.route]] symbols can't be empty");
+            }
+            if route.channels.contains(&AlertChannel::Email) && self.email.url.is_none() {
+                bail!(
+                    "[[notifications.route]] sends to email but [notifications.email] url is unset"
+                );
+            }
+        }
+        if self.email.url.is_some() && (self.email.from.is_empty() || self.email.to.is_empty()) {
+            bail!("[notifications.email] needs `from` and `to` with `url`");
+        }
+        Ok(())
+    }
+}
+
+/// `[[notifications.route]]`: the channels for spreads from `min_diff` up
+/// to `max_diff`. Routes listing a symbol replace the catch-all ones (no
+/// `symbols`) for it; where several match, the alert goes to all of their
+/// channels.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRoute {
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    /// In percent, inclusive.
+    pub min_diff: Decimal,
+    /// In percent, exclusive; no upper bound when unset.
+    pub max_diff: Option<Decimal>,
+    pub channels: Vec<AlertChannel>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertChannel {
+    /// Collected into one summary every `digest_interval_secs`, sent silently
+    /// to Telegram and to email if configured.
+    Digest,
+    Telegram,
+    Email,
+}
+
+/// Alerts by email through an HTTP mail API: each is POSTed to `url` as
+/// `{"from", "to", "subject", "text"}` JSON, with `Authorization: Bearer
+/// <EMAIL_API_KEY>` if that is set.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    /// Off when unset.
+    pub url: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+/// `[notifications.telegram]`: commands taken from the Telegram chat (see
+/// `crate::notifications::telegram_commands`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelegramConfig {
+    /// Take commands from `TELEGRAM_CHAT_ID`; off by default.
+    pub commands: bool,
+    /// Telegram user IDs allowed to send them; anyone in the chat when
+    /// empty.
+    pub allowed_users: Vec<i64>,
+}
+
+/// gRPC streams of quotes, opportunities and execution events (see
+/// `crate::grpc`). Calls must carry `authorization: Bearer <token>` metadata
+/// with the token from `GRPC_TOKEN`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrpcConfig {
+    /// Serve gRPC on this address; off when unset.
+    pub listen: Option<SocketAddr>,
+}
+
+/// Redis pub/sub bridge (see `crate::bridge::redis`). Quotes and
+/// opportunities are published as JSON; commands are only taken with a
+/// `commands_channel`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedisConfig {
+    /// e.g. `redis://127.0.0.1:6379`; off when unset.
+    pub url: Option<String>,
+    pub quotes_channel: String,
+    pub opportunities_channel: String,
+    /// Channel to take [`Command`](crate::control::Command)s from.
+    pub commands_channel: Option<String>,
+}
+
+impl Default for RedisConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            quotes_channel: "arbitrage:quotes".into(),
+            opportunities_channel: "arbitrage:opportunities".into(),
+            commands_channel: None,
+        }
+    }
+}
+
+/// Kafka event sink (see `crate::bridge::kafka`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KafkaConfig {
+    /// Bootstrap brokers, e.g. `["127.0.0.1:9092"]`; off when empty.
+    pub brokers: Vec<String>,
+    /// Topics are `<topic_prefix>.quotes`, `.opportunities`, `.orders` and
+    /// `.errors`.
+    pub topic_prefix: String,
+    /// At most one quote per venue this often; 0 mirrors every quote.
+    pub quote_sample_ms: u64,
+}
+
+impl KafkaConfig {
+    pub fn quote_sample(&self) -> Duration {
+        Duration::from_millis(self.quote_sample_ms)
+    }
+}
+
+impl Default for KafkaConfig {
+    fn default() -> Self {
+        Self {
+            brokers: Vec::new(),
+            topic_prefix: "arbitrage".into(),
+            quote_sample_ms: 1000,
+        }
+    }
+}
+
+/// NATS event sink (see `crate::bridge::nats`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NatsConfig {
+    /// e.g. `nats://127.0.0.1:4222`; off when unset.
+    pub url: Option<String>,
+    /// Subjects are `<subject_prefix>.quotes`, `.opportunities`, `.orders`
+    /// and `.errors`.
+    pub subject_prefix: String,
+    /// At most one quote per venue this often; 0 mirrors every quote.
+    pub quote_sample_ms: u64,
+}
+
+impl NatsConfig {
+    pub fn quote_sample(&self) -> Duration {
+        Duration::from_millis(self.quote_sample_ms)
+    }
+}
+
+impl Default for NatsConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            subject_prefix: "arbitrage".into(),
+            quote_sample_ms: 1000,
+        }
+    }
+}
+
+/// MQTT alerts and summary stats (see `crate::bridge::mqtt`). The password,
+/// if the broker needs one, comes from `MQTT_PASSWORD`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MqttConfig {
+    /// Broker host, e.g. `homeassistant.local`; off when unset.
+    pub host: Option<String>,
+    pub port: u16,
+    pub client_id: String,
+    pub username: Option<String>,
+    /// Topics are `<topic_prefix>/alert`, `/stats` and `/availability`.
+    pub topic_prefix: String,
+    /// How often the retained stats are republished.
+    pub stats_interval_secs: u64,
+    /// At most one alert per symbol and exchange pair this often.
+    pub alert_interval_secs: u64,
+    /// Publish Home Assistant discovery configs under this prefix, e.g.
+    /// `homeassistant`; off when unset.
+    pub discovery_prefix: Option<String>,
+}
+
+impl MqttConfig {
+    pub fn stats_interval(&self) -> Duration {
+        Duration::from_secs(self.stats_interval_secs)
+    }
+
+    pub fn alert_interval(&self) -> Duration {
+        Duration::from_secs(self.alert_interval_secs)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.stats_interval_secs == 0 {
+            bail!("[mqtt] stats_interval_secs must be positive");
+        }
+        Ok(())
+    }
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: None,
+            port: 1883,
+            client_id: "arbitrage-bot".into(),
+            username: None,
+            topic_prefix: "arbitrage".into(),
+            stats_interval_secs: 30,
+            alert_interval_secs: 60,
+            discovery_prefix: None,
+        }
+    }
+}
+
+/// Line-protocol push of quotes and spreads (see `crate::bridge::influx`).
+/// The token, if the database needs one, comes from `INFLUX_TOKEN`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InfluxConfig {
+    /// Write endpoint, e.g. `http://localhost:8086/api/v2/write?org=me&bucket=arbitrage`
+    /// or VictoriaMetrics' `http://localhost:8428/write`; off when unset.
+    pub url: Option<String>,
+    /// Measurements are `<measurement_prefix>_quote` and `_spread`.
+    pub measurement_prefix: String,
+    /// How often a sample is written.
+    pub interval_ms: u64,
+}
+
+impl InfluxConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.interval_ms == 0 {
+            bail!("[influx] interval_ms must be positive");
+        }
+        Ok(())
+    }
+}
+
+impl Default for InfluxConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            measurement_prefix: "arbitrage".into(),
+            interval_ms: 1000,
+        }
+    }
+}
+
+/// Exchange maintenance windows and new listings (see `crate::calendar`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalendarConfig {
+    /// TOML schedule of `[[maintenance]]` and `[[listing]]` tables.
+    pub file: Option<PathBuf>,
+    /// The same schedule as JSON, fetched over HTTP(S).
+    pub url: Option<String>,
+    /// How often the file and URL are read again; 0 never.
+    pub refresh_secs: u64,
+    /// Execution stops this long before a maintenance window and resumes
+    /// this long after it.
+    pub margin_secs: u64,
+    /// Alerts this close to a listing of their symbol are annotated.
+    pub listing_window_secs: u64,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            url: None,
+            refresh_secs: 300,
+            margin_secs: 300,
+            listing_window_secs: 6 * 3600,
+        }
+    }
+}
+
+/// Deposit and withdrawal status of the assets alerted on (see
+/// `crate::transfers`). Needs the `execution` feature for signed requests.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransfersConfig {
+    pub enabled: bool,
+    pub refresh_secs: u64,
+    /// Drop alerts on spreads that can't be closed by moving the asset,
+    /// instead of sending them with a note.
+    pub block: bool,
+}
+
+impl Default for TransfersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            refresh_secs: 300,
+            block: false,
+        }
+    }
+}
+
+impl TransfersConfig {
+    pub fn refresh(&self) -> Duration {
+        Duration::from_secs(self.refresh_secs)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.refresh_secs == 0 {
+            bail!("[transfers] refresh_secs must be positive");
+        }
+        Ok(())
+    }
+}
+
+/// Quote currency conversion, so that e.g. BTCUSDC is compared with BTCUSDT
+/// (see `crate::fx`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FxConfig {
+    pub enabled: bool,
+    /// The currency every other quote currency is converted into.
+    pub reference: String,
+    /// How often the live rates are fetched.
+    pub refresh_secs: u64,
+    /// A pegged currency this far from parity with `reference` is depegged:
+    /// spreads against it are flagged instead of reported.
+    pub depeg_percent: Decimal,
+    /// Where each quote currency's rate comes from, by currency.
+    pub currencies: HashMap<String, FxSource>,
+}
+
+impl Default for FxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reference: "USDT".into(),
+            refresh_secs: 60,
+            depeg_percent: dec!(0.5),
+            currencies: HashMap::new(),
+        }
+    }
+}
+
+/// One quote currency's rate in the reference currency: live from a Binance
+/// spot `symbol` (either way round, e.g. `USDCUSDT` or `USDTUSD`) or a fixed
+/// `rate`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FxSource {
+    pub symbol: Option<String>,
+    pub rate: Option<Decimal>,
+    /// Meant to trade at parity with the reference currency (stablecoins,
+    /// USD), and so watched for a depeg.
+    pub pegged: bool,
+}
+
+impl Default for FxSource {
+    fn default() -> Self {
+        Self {
+            symbol: None,
+            rate: None,
+            pegged: true,
+        }
+    }
+}
+
+impl FxConfig {
+    pub fn refresh(&self) -> Duration {
+        Duration::from_secs(self.refresh_secs)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.reference.is_empty() || self.reference != self.reference.to_uppercase() {
+            bail!("[fx] reference must be an upper-case currency, like \"USDT\"");
+        }
+        if self.refresh_secs == 0 {
+            bail!("[fx] refresh_secs must be positive");
+        }
+        if self.depeg_percent <= Decimal::ZERO {
+            bail!("[fx] depeg_percent must be positive");
+        }
+        for (currency, source) in &self.currencies {
+            if *currency == self.reference {
+                bail!("[fx.currencies] {} is the reference currency", currency);
+            }
+            match (&source.symbol, source.rate) {
+                (Some(symbol), None) => {
+                    let pair = [
+                        format!("{}{}", currency, self.reference),
+                        format!("{}{}", self.reference, currency),
+                    ];
+                    if !pair.contains(symbol) {
+                        bail!(
+                            "[fx.currencies.{}] symbol must be {} or {}",
+                            currency,
+                            pair[0],
+                            pair[1]
+                        );
+                    }
+                }
+                (None, Some(rate)) if rate > Decimal::ZERO => {}
+                (None, Some(_)) => bail!("[fx.currencies.{}] rate must be positive", currency),
+                _ => bail!(
+                    "[fx.currencies.{}] needs exactly one of `symbol` and `rate`",
+                    currency
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// REST liveness probes (see `crate::liveness`): exchanges whose REST API
+/// keeps failing are left out of execution.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LivenessConfig {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    /// How long a probe may take before it counts as failed.
+    pub timeout_ms: u64,
+    /// Failed probes in a row that impair an exchange.
+    pub failures: u32,
+}
+
+impl Default for LivenessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 10,
+            timeout_ms: 3000,
+            failures: 3,
+        }
+    }
+}
+
+impl LivenessConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.interval_secs == 0 || self.timeout_ms == 0 || self.failures == 0 {
+            bail!("[liveness] interval_secs, timeout_ms and failures must be positive");
+        }
+        Ok(())
+    }
+}
+
+/// REST polling for feeds whose WebSocket is down (see `crate::failover`):
+/// their quotes keep coming, flagged as polled, while execution leaves the
+/// venue alone.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FailoverConfig {
+    pub enabled: bool,
+    /// How long a feed may go without a streamed quote before it's polled.
+    pub after_secs: u64,
+    /// How often each such feed is polled.
+    pub poll_interval_ms: u64,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after_secs: 10,
+            poll_interval_ms: 1000,
+        }
+    }
+}
+
+impl FailoverConfig {
+    /// The lowest `poll_interval_ms`, to stay well inside REST rate limits.
+    pub const MIN_POLL_INTERVAL_MS: u64 = 250;
+
+    pub fn after(&self) -> Duration {
+        Duration::from_secs(self.after_secs)
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.after_secs == 0 {
+            bail!("[failover] after_secs must be positive");
+        }
+        if self.poll_interval_ms < Self::MIN_POLL_INTERVAL_MS {
+            bail!(
+                "[failover] poll_interval_ms must be at least {}",
+                Self::MIN_POLL_INTERVAL_MS
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Exchanges and symbols paused at startup (see `crate::pauses`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PausesConfig {
+    pub exchanges: Vec<ExchangeId>,
+    pub symbols: Vec<String>,
+}
+
+impl PausesConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        if let Some(symbol) = self.symbols.iter().find(|s| !pauses::is_symbol(s)) {
+            bail!("[pauses] symbols: {:?} is not a symbol", symbol);
+        }
+        Ok(())
+    }
+}
+
+/// Listing mode for fresh or thin pairs (see `crate::listing_mode`): their
+/// spreads only count once both venues show real depth and trading.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ListingModeConfig {
+    pub enabled: bool,
+    /// The symbols it applies to; others are left alone.
+    pub symbols: Vec<String>,
+    /// How often each venue's book and trades are read.
+    pub interval_secs: u64,
+    /// Depth counts the levels within this many percent of the mid.
+    pub depth_band_percent: Decimal,
+    /// Quote-currency notional every venue must show on each side of the
+    /// book within `depth_band_percent` of the mid, unless overridden below.
+    pub min_depth: Decimal,
+    /// Trades in the last minute every venue must show, likewise.
+    pub min_trades_per_minute: u32,
+    /// Both thresholds per exchange name, e.g. a lower bar for a venue whose
+    /// book is trusted more.
+    pub exchanges: HashMap<String, LiquidityThresholds>,
+}
+
+/// The liquidity a venue must show for a listing-mode symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LiquidityThresholds {
+    pub min_depth: Decimal,
+    pub min_trades_per_minute: u32,
+}
+
+impl Default for ListingModeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            symbols: Vec::new(),
+            interval_secs: 15,
+            depth_band_percent: dec!(1),
+            min_depth: dec!(10000),
+            min_trades_per_minute: 20,
+            exchanges: HashMap::new(),
+        }
+    }
+}
+
+impl ListingModeConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    /// The thresholds for `exchange`.
+    pub fn thresholds(&self, exchange: ExchangeId) -> LiquidityThresholds {
+        self.exchanges
+            .iter()
+            .find(|(name, _)| ExchangeId::from_name(name) == Some(exchange))
+            .map_or(
+                LiquidityThresholds {
+                    min_depth: self.min_depth,
+                    min_trades_per_minute: self.min_trades_per_minute,
+                },
+                |(_, thresholds)| *thresholds,
+            )
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.interval_secs == 0 {
+            bail!("[listing_mode] interval_secs must be positive");
+        }
+        if self.depth_band_percent <= Decimal::ZERO {
+            bail!("[listing_mode] depth_band_percent must be positive");
+        }
+        if self.symbols.is_empty() || self.symbols.iter().any(|s| s.trim().is_empty()) {
+            bail!("[listing_mode] needs symbols, none of them empty");
+        }
+        if self.min_depth < Decimal::ZERO {
+            bail!("[listing_mode] min_depth can't be negative");
+        }
+        for (name, thresholds) in &self.exchanges {
+            if ExchangeId::from_name(name).is_none() {
+                bail!("[listing_mode.exchanges] has unknown exchange {:?}", name);
+            }
+            if thresholds.min_depth < Decimal::ZERO {
+                bail!(
+                    "[listing_mode.exchanges.{}] min_depth can't be negative",
+                    name
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Volatility regimes (see `crate::volatility`): thresholds go up while a
+/// symbol moves violently.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VolatilityConfig {
+    pub enabled: bool,
+    /// How far back realized volatility is measured.
+    pub window_secs: u64,
+    /// At most one price sample per exchange and symbol this often.
+    pub sample_secs: u64,
+    /// Realized volatility over the window, in percent, above which a
+    /// symbol turns volatile.
+    pub volatile_percent: Decimal,
+    /// ... and below which it calms down again.
+    pub calm_percent: Decimal,
+    /// What alert and trade thresholds are multiplied by while volatile.
+    pub threshold_multiplier: Decimal,
+}
+
+impl Default for VolatilityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: 300,
+            sample_secs: 1,
+            volatile_percent: dec!(1),
+            calm_percent: dec!(0.5),
+            threshold_multiplier: dec!(2),
+        }
+    }
+}
+
+impl VolatilityConfig {
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+
+    pub fn sample(&self) -> Duration {
+        Duration::from_secs(self.sample_secs)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.window_secs == 0 || self.sample_secs >= self.window_secs {
+            bail!("[volatility] window_secs must be positive and longer than sample_secs");
+        }
+        if self.calm_percent <= Decimal::ZERO || self.calm_percent > self.volatile_percent {
+            bail!("[volatility] calm_percent must be positive and at most volatile_percent");
+        }
+        if self.threshold_multiplier < Decimal::ONE {
+            bail!("[volatility] threshold_multiplier must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+impl CalendarConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some() || self.url.is_some()
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if let Some(url) = &self.url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                bail!("[calendar] url must be http:// or https://");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `--tui` terminal dashboard (see `crate::tui`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TuiConfig {
+    /// Where the usual console output goes while the dashboard is up.
+    pub log_file: PathBuf,
+    /// How often the dashboard redraws.
+    pub refresh_ms: u64,
+}
+
+impl Default for TuiConfig {
+    fn default() -> Self {
+        Self {
+            log_file: PathBuf::from("arbitrage-tui.log"),
+            refresh_ms: 250,
+        }
+    }
+}
+
+impl TuiConfig {
+    pub fn refresh(&self) -> Duration {
+        Duration::from_millis(self.refresh_ms)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.refresh_ms == 0 {
+            bail!("[tui] refresh_ms must be positive");
+        }
+        Ok(())
+    }
+}
+
+/// What the live pipeline (`crate::engine`) does besides monitoring and alerting.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineConfig {
+    /// Append every spread above the alert threshold to this CSV file.
+    pub spread_log: Option<PathBuf>,
+    /// Save runtime state (orders, exposure, alert gate, tripped circuit
+    /// breakers) here and restore it on startup (see `crate::state`).
+    pub state_file: Option<PathBuf>,
+    /// Append every order action, with parameters and response, to this
+    /// hash-chained JSON-lines file (see `crate::audit`).
+    pub audit_log: Option<PathBuf>,
+    /// Append every feed connection event to this JSON-lines file, for
+    /// `arbitrage-bot events` (see `crate::events`).
+    pub event_log: Option<PathBuf>,
+    /// Append the summary printed on shutdown to this JSON-lines file (see
+    /// `crate::session`).
+    pub session_log: Option<PathBuf>,
+    /// Append each trade's costs, its fills against the quotes it was
+    /// detected at, to this JSON-lines file (see `crate::tca`).
+    pub tca_log: Option<PathBuf>,
+    /// Keep the opportunities by hour of day and symbol here, across runs,
+    /// for `arbitrage-bot heatmap` (see `crate::heatmap`).
+    pub heatmap_file: Option<PathBuf>,
+    /// How long startup waits for every feed to deliver its first quote
+    /// before carrying on with the venues that are live.
+    pub ready_timeout_secs: u64,
+    pub evaluation: EvaluationConfig,
+    pub execution: ExecutionConfig,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            spread_log: None,
+            state_file: None,
+            audit_log: None,
+            event_log: None,
+            session_log: None,
+            tca_log: None,
+            heatmap_file: None,
+            ready_timeout_secs: 30,
+            evaluation: EvaluationConfig::default(),
+            execution: ExecutionConfig::default(),
+        }
+    }
+}
+
+impl EngineConfig {
+    pub fn ready_timeout(&self) -> Duration {
+        Duration::from_secs(self.ready_timeout_secs)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.ready_timeout_secs == 0 {
+            bail!("[engine] ready_timeout_secs must be positive");
+        }
+        self.execution.validate()
+    }
+}
+
+/// When the tracker re-runs the spread comparison for a symbol. Bursts of
+/// depth updates mostly produce quotes that are superseded within
+/// milliseconds; these settings keep the comparator from evaluating each one.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EvaluationConfig {
+    /// Skip quotes that leave an exchange's best bid and ask unchanged.
+    pub on_change_only: bool,
+    /// Evaluate a symbol at most this often; quotes arriving in between are
+    /// evaluated together once the interval has passed. 0 evaluates every quote.
+    pub min_interval_ms: u64,
+}
+
+impl Default for EvaluationConfig {
+    fn default() -> Self {
+        Self {
+            on_change_only: true,
+            min_interval_ms: 0,
+        }
+    }
+}
+
+impl EvaluationConfig {
+    /// Evaluates on every quote, changed or not.
+    pub const EVERY_UPDATE: Self = Self {
+        on_change_only: false,
+        min_interval_ms: 0,
+    };
+
+    pub fn min_interval(&self) -> Duration {
+        Duration::from_millis(self.min_interval_ms)
+    }
+}
+
+/// Order execution on spreads above `threshold_percent`. Off unless enabled
+/// here, and only available in builds with the `execution` feature.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutionConfig {
+    pub enabled: bool,
+    pub symbol: String,
+    /// Order size per leg, in base currency.
+    pub quantity: Decimal,
+    /// Minimum cross-exchange edge (bid minus ask, relative to the ask), in percent.
+    pub threshold_percent: Decimal,
+    /// When a leg fails, unwind the legs of the same trade that went through
+    /// (see `crate::plan`) instead of leaving them open.
+    pub rollback: bool,
+    /// On startup, loads every venue's open orders and position over the
+    /// saved state before trading (see `crate::recovery`).
+    pub recover: bool,
+    /// Checks the API key's permissions before trading; see
+    /// `crate::binance::permissions`.
+    pub audit_key: bool,
+    /// The IP the API key is restricted to, compared with what
+    /// `ip_check_url` sees.
+    pub expected_ip: Option<IpAddr>,
+    /// Answers with the caller's public IP as plain text.
+    pub ip_check_url: String,
+    pub mode: ExecutionMode,
+    /// Balances and skew limit of `mode = "inventory"`.
+    pub inventory: InventoryConfig,
+    /// How measured latency weighs on opportunities (see `crate::latency`).
+    pub latency: LatencyConfig,
+    /// Taker fee per order, in percent of its notional; the pre-order
+    /// re-check and the session summary (see `crate::session`) take it off.
+    pub fee_percent: Decimal,
+    /// Maker fee per order, in percent of its notional; negative where the
+    /// venue pays a rebate for adding liquidity (see `crate::fees`).
+    pub maker_fee_percent: Decimal,
+    /// Right before ordering, the legs' latest quotes are read again and the
+    /// trade is dropped unless its edge, net of the latency haircut and both
+    /// legs' fees, is still above this, in percent.
+    pub recheck_floor_percent: Decimal,
+    /// How long each mode's orders stay on the book.
+    pub time_in_force: TimeInForceConfig,
+    /// Several accounts to trade with, e.g. sub-accounts; without any, the
+    /// one keyed by `API_KEY_BINANCE` / `SECRET_KEY_BINANCE` trades.
+    pub accounts: Vec<AccountConfig>,
+    /// How each order picks one of its exchange's `accounts`.
+    pub routing: AccountRouting,
+    /// Moving collateral between `accounts` when one runs low on margin.
+    pub topup: TopUpConfig,
+    /// How long trade cost analysis follows an order's fills before taking
+    /// it as it stands (see `crate::tca`).
+    pub tca_timeout_secs: u64,
+    /// The contract traded per exchange name, where it isn't the linear
+    /// one quoted as `symbol` (see `crate::contracts`).
+    pub contracts: HashMap<String, ContractConfig>,
+    /// Holding off entries that would pay a large funding payment right
+    /// away (see `crate::funding`).
+    pub funding: FundingConfig,
+    /// Capital and limits per strategy name (see `crate::portfolio`).
+    pub strategies: HashMap<String, StrategyConfig>,
+    /// Trying order requests again after transient failures (see
+    /// `crate::retry`).
+    pub retry: RetryConfig,
+    /// Pacing requests by the usage the exchange reports (see
+    /// `crate::binance::rate_limits`).
+    pub rate_limits: RateLimitConfig,
+    /// What becomes of orders left resting on the book (see
+    /// `crate::expiry`).
+    pub expiry: ExpiryConfig,
+}
+
+/// `[engine.execution.strategies.<name>]`: the virtual capital and limits of
+/// one strategy.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrategyConfig {
+    pub enabled: bool,
+    /// Notional its open positions may come to across venues, in the quote
+    /// currency; unlimited when unset.
+    pub capital: Option<Decimal>,
+    /// Disables it once its PnL is this far below zero, in the quote
+    /// currency.
+    pub max_loss: Option<Decimal>,
+    /// Disables it after this many failed trades.
+    pub max_failed: Option<u64>,
+    /// The minimum edge for its trades, in percent, instead of
+    /// `[engine.execution]`'s.
+    pub threshold_percent: Option<Decimal>,
+    /// What it trades per leg instead of `[engine.execution] quantity`.
+    pub quantity: Option<Decimal>,
+}
+
+impl Default for StrategyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            capital: None,
+            max_loss: None,
+            max_failed: None,
+            threshold_percent: None,
+            quantity: None,
+        }
+    }
+}
+
+/// `[engine.execution.contracts.<exchange>]`: the contract one venue trades.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContractConfig {
+    /// The venue's own symbol, e.g. "BTCUSD" for Bybit's inverse perpetual;
+    /// `symbol` when unset.
+    pub symbol: Option<String>,
+    pub kind: ContractKind,
+    /// USD per contract of an inverse contract; the venue's standard when
+    /// unset (Binance 100 for BTC and 10 for the rest, Bybit 1).
+    pub contract_size: Option<Decimal>,
+}
+
+/// How a contract is sized and settled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContractKind {
+    /// Sized in the base currency, margined and settled in the quote one.
+    #[default]
+    Linear,
+    /// Sized in contracts of a fixed USD value, margined and settled in the
+    /// base currency (coin-margined).
+    Inverse,
+}
+
+/// `[[engine.execution.accounts]]`: one API key to trade with.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountConfig {
+    /// Shown in logs.
+    pub name: String,
+    #[serde(default = "AccountConfig::default_exchange")]
+    pub exchange: ExchangeId,
+    /// The secrets (see `crate::keys`) holding the API key and secret.
+    pub api_key_var: String,
+    pub secret_key_var: String,
+    /// The symbols `routing = "symbol"` sends to this account; an account
+    /// without any takes the symbols no other account lists.
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    /// The sub-account's email on Binance, or its member ID (UID) on
+    /// Bybit, for margin top-ups; unset for the master account.
+    pub sub_account: Option<String>,
+    /// This account's taker and maker fees, in percent, where its VIP tier
+    /// differs from `[engine.execution]`'s.
+    pub fee_percent: Option<Decimal>,
+    pub maker_fee_percent: Option<Decimal>,
+}
+
+impl AccountConfig {
+    fn default_exchange() -> ExchangeId {
+        ExchangeId::Binance
+    }
+}
+
+/// How an order picks an account (see `crate::accounts`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountRouting {
+    /// Each order goes to the next account, spreading rate limits.
+    #[default]
+    RoundRobin,
+    /// Orders go to the account that lists their symbol.
+    Symbol,
+    /// Orders go to the account with the most available margin.
+    Margin,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionMode {
+    /// Both legs are futures orders, settled in margin.
+    #[default]
+    Futures,
+    /// Spot orders against inventory held on both exchanges: sell where it's
+    /// dear, buy where it's cheap, rebalance later (see `crate::state::Inventory`).
+    Inventory,
+}
+
+/// How long an order may stay on the book.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderTimeInForce {
+    /// Good till cancelled: whatever doesn't fill right away rests on the
+    /// book, and may fill later after the other leg's price has moved.
+    #[default]
+    Gtc,
+    /// Immediate or cancel: fills what it can at once, the rest expires.
+    Ioc,
+    /// Fill or kill: fills completely at once or not at all.
+    Fok,
+}
+
+/// `[engine.execution.time_in_force]`: the time in force of the orders
+/// placed in each execution mode.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeInForceConfig {
+    pub futures: OrderTimeInForce,
+    pub inventory: OrderTimeInForce,
+}
+
+impl TimeInForceConfig {
+    pub fn for_mode(&self, mode: ExecutionMode) -> OrderTimeInForce {
+        match mode {
+            ExecutionMode::Futures => self.futures,
+            ExecutionMode::Inventory => self.inventory,
+        }
+    }
+}
+
+/// What becomes of an order still resting once `[engine.execution.expiry]`
+/// `ttl_secs` have passed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpiryAction {
+    /// Re-priced at its side's touch, to rest again; cancelled once it has
+    /// been `max_amends` times.
+    Amend,
+    /// Cancelled, and the rest sent as a market order.
+    Market,
+    /// Cancelled.
+    #[default]
+    Cancel,
+}
+
+/// `[engine.execution.expiry]`: supervising the orders left resting on the
+/// book (see `crate::expiry`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExpiryConfig {
+    pub enabled: bool,
+    /// How long an order may rest before `action` is taken on it.
+    pub ttl_secs: u64,
+    /// How often the open orders are read.
+    pub interval_secs: u64,
+    pub action: ExpiryAction,
+    /// Re-pricings of one order before `amend` cancels it instead.
+    pub max_amends: u32,
+}
+
+impl Default for ExpiryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 60,
+            interval_secs: 5,
+            action: ExpiryAction::Cancel,
+            max_amends: 3,
+        }
+    }
+}
+
+impl ExpiryConfig {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_secs)
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    fn validate(&self, mode: ExecutionMode) -> anyhow::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.ttl_secs == 0 || self.interval_secs == 0 {
+            bail!("[engine.execution.expiry] ttl_secs and interval_secs must be positive");
+        }
+        if mode != ExecutionMode::Futures {
+            bail!(
+                "[engine.execution.expiry] only supervises futures orders; use mode = \"futures\""
+            );
+        }
+        Ok(())
+    }
+}
+
+/// What inventory mode starts from: `[engine.execution.inventory.balances.<name>]`
+/// per exchange, in the symbol's base and quote currency.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InventoryConfig {
+    pub balances: HashMap<String, Balance>,
+    /// Alert once one exchange holds more than this share of either currency.
+    pub max_skew_percent: Decimal,
+    pub rebalance: RebalanceConfig,
+}
+
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self {
+            balances: HashMap::new(),
+            max_skew_percent: dec!(80),
+            rebalance: RebalanceConfig::default(),
+        }
+    }
+}
+
+/// `[engine.execution.latency]`: the edge a trade is expected to lose while
+/// it lands, and when two pairs of exchanges count as equally good.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LatencyConfig {
+    /// Price drift per second of measured latency, in percent, taken off
+    /// every opportunity's edge. 0 turns the haircut off.
+    pub haircut_percent_per_sec: Decimal,
+    /// Opportunities with edges this close, in percentage points, go to the
+    /// faster pair of exchanges.
+    pub similar_edge_percent: Decimal,
+    /// The most a trade may take from its start until every leg is
+    /// acknowledged, in milliseconds; a leg not acknowledged by then fails
+    /// the trade. 0 turns the budget off.
+    pub budget_ms: u64,
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            haircut_percent_per_sec: Decimal::ZERO,
+            similar_edge_percent: dec!(0.02),
+            budget_ms: 0,
+        }
+    }
+}
+
+impl LatencyConfig {
+    /// `budget_ms`, unless it's off.
+    pub fn budget(&self) -> Option<Duration> {
+        (self.budget_ms > 0).then(|| Duration::from_millis(self.budget_ms))
+    }
+}
+
+/// `[engine.execution.funding]`: the funding a trade would pay at the next
+/// settlement, and what to do when that's too much.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FundingConfig {
+    pub enabled: bool,
+    /// How often each venue's next funding time and predicted rate are read.
+    pub interval_secs: u64,
+    /// Only settlements this close count; a trade opened earlier may well be
+    /// closed again by then.
+    pub window_secs: u64,
+    /// The most a trade may pay at the settlement, net over both legs, in
+    /// percent of its notional.
+    pub max_cost_percent: Decimal,
+    pub action: FundingAction,
+}
+
+impl Default for FundingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 60,
+            window_secs: 900,
+            max_cost_percent: dec!(0.01),
+            action: FundingAction::Delay,
+        }
+    }
+}
+
+impl FundingConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.interval_secs == 0 {
+            bail!("[engine.execution.funding] interval_secs must be positive");
+        }
+        if self.max_cost_percent < Decimal::ZERO {
+            bail!("[engine.execution.funding] max_cost_percent can't be negative");
+        }
+        Ok(())
+    }
+}
+
+/// `[engine.execution.retry]`: how often, and how far apart, an order
+/// request is tried.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Tries per request, the first one included; 1 never retries.
+    pub max_attempts: u32,
+    /// The pause before the first retry, doubled before each further one...
+    pub backoff_ms: u64,
+    /// ...up to this.
+    pub max_backoff_ms: u64,
+    /// Only tries again after failures that say a retry can help, e.g. a
+    /// rate limit or a dropped connection, and not after e.g. insufficient
+    /// margin. Fatal failures are never retried either way.
+    pub retryable_only: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 2,
+            backoff_ms: 250,
+            max_backoff_ms: 2000,
+            retryable_only: true,
+        }
+    }
+}
+
+/// Enough to get past a rate limit; beyond it, the market has moved on.
+const MAX_ORDER_ATTEMPTS: u32 = 10;
+
+impl RetryConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        if !(1..=MAX_ORDER_ATTEMPTS).contains(&self.max_attempts) {
+            bail!(
+                "[engine.execution.retry] max_attempts must be between 1 and {}",
+                MAX_ORDER_ATTEMPTS
+            );
+        }
+        if self.backoff_ms > self.max_backoff_ms {
+            bail!("[engine.execution.retry] backoff_ms can't exceed max_backoff_ms");
+        }
+        Ok(())
+    }
+}
+
+/// `[engine.execution.rate_limits]`: holding requests back as the usage the
+/// Binance WS API reports nears its limits.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// How much of a window may be used, in percent, before the rest of its
+    /// requests are spread over what's left of it.
+    pub slow_down_percent: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            slow_down_percent: 80,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        if !(1..=100).contains(&self.slow_down_percent) {
+            bail!("[engine.execution.rate_limits] slow_down_percent must be between 1 and 100");
+        }
+        Ok(())
+    }
+}
+
+/// What execution does with a trade that would pay too much funding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FundingAction {
+    /// Holds off entries on the pair, either way round, until the
+    /// settlement has passed.
+    #[default]
+    Delay,
+    /// Drops the trade; the other direction, which collects the funding,
+    /// still trades.
+    Skip,
+}
+
+/// Rebalancing plans for skewed inventory (see `crate::rebalance`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RebalanceConfig {
+    pub enabled: bool,
+    /// Places the offsetting trades a plan recommends instead of only
+    /// proposing them. Transfers are never executed.
+    pub execute: bool,
+    /// Taker fee per offsetting order, in percent.
+    pub trade_fee_percent: Decimal,
+    /// How long a transfer is assumed to take where the exchange gives no
+    /// estimate.
+    pub transfer_eta_mins: u64,
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            execute: false,
+            trade_fee_percent: dec!(0.1),
+            transfer_eta_mins: 30,
+        }
+    }
+}
+
+impl RebalanceConfig {
+    pub fn transfer_eta(&self) -> Duration {
+        Duration::from_secs(self.transfer_eta_mins * 60)
+    }
+}
+
+impl InventoryConfig {
+    /// The configured balances by exchange.
+    pub fn balances(&self) -> impl Iterator<Item = (ExchangeId, Balance)> + '_ {
+        self.balances
+            .iter()
+            .filter_map(|(name, balance)| Some((ExchangeId::from_name(name)?, *balance)))
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        for (name, balance) in &self.balances {
+            if ExchangeId::from_name(name).is_none() {
+                bail!(
+                    "[engine.execution.inventory.balances] has unknown exchange {:?}",
+                    name
+                );
+            }
+            if balance.base < Decimal::ZERO || balance.quote < Decimal::ZERO {
+                bail!(
+                    "[engine.execution.inventory.balances.{}] can't be negative",
+                    name
+                );
+            }
+        }
+        if self.balances.len() < 2 {
+            bail!("[engine.execution] mode = \"inventory\" needs balances on two exchanges");
+        }
+        if self.max_skew_percent <= dec!(50) || self.max_skew_percent > dec!(100) {
+            bail!("[engine.execution.inventory] max_skew_percent must be over 50 and at most 100");
+        }
+        if self.rebalance.trade_fee_percent < Decimal::ZERO {
+            bail!("[engine.execution.inventory.rebalance] trade_fee_percent can't be negative");
+        }
+        if self.rebalance.execute && !self.rebalance.enabled {
+            bail!("[engine.execution.inventory.rebalance] execute needs enabled = true");
+        }
+        Ok(())
+    }
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            symbol: "BTCUSDT".to_string(),
+            quantity: Decimal::ZERO,
+            threshold_percent: dec!(0.1),
+            rollback: false,
+            recover: true,
+            audit_key: true,
+            expected_ip: None,
+            ip_check_url: "https://api.ipify.org".to_string(),
+            mode: ExecutionMode::Futures,
+            inventory: InventoryConfig::default(),
+            latency: LatencyConfig::default(),
+            fee_percent: dec!(0.05),
+            maker_fee_percent: dec!(0.02),
+            recheck_floor_percent: Decimal::ZERO,
+            time_in_force: TimeInForceConfig::default(),
+            accounts: Vec::new(),
+            routing: AccountRouting::RoundRobin,
+            topup: TopUpConfig::default(),
+            tca_timeout_secs: 30,
+            contracts: HashMap::new(),
+            funding: FundingConfig::default(),
+            strategies: HashMap::new(),
+            retry: RetryConfig::default(),
+            rate_limits: RateLimitConfig::default(),
+            expiry: ExpiryConfig::default(),
+        }
+    }
+}
+
+/// `[engine.execution.topup]`: collateral transfers between the accounts of
+/// an exchange (see `crate::topup`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TopUpConfig {
+    pub enabled: bool,
+    /// Makes the transfers instead of only announcing them.
+    pub execute: bool,
+    pub interval_secs: u64,
+    /// The collateral moved, e.g. "USDT".
+    pub asset: String,
+    /// An account is topped up once its available margin stays below this
+    /// for `low_checks` checks in a row...
+    pub min_margin: Decimal,
+    pub low_checks: u32,
+    /// ...back to this, from an account left with at least as much.
+    pub target_margin: Decimal,
+    /// The most one transfer moves.
+    pub max_transfer: Decimal,
+    /// The most moved in any 24 hours.
+    pub max_daily: Decimal,
+    /// The master account's key, which the transfer endpoints need.
+    pub master_api_key_var: String,
+    pub master_secret_key_var: String,
+}
+
+impl Default for TopUpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            execute: false,
+            interval_secs: 30,
+            asset: "USDT".to_string(),
+            min_margin: Decimal::ZERO,
+            low_checks: 3,
+            target_margin: Decimal::ZERO,
+            max_transfer: Decimal::ZERO,
+            max_daily: Decimal::ZERO,
+            master_api_key_var: "API_KEY_BINANCE".to_string(),
+            master_secret_key_var: "SECRET_KEY_BINANCE".to_string(),
+        }
+    }
+}
+
+impl TopUpConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    fn validate(&self, accounts: &[AccountConfig]) -> anyhow::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.interval_secs == 0 || self.low_checks == 0 || self.asset.is_empty() {
+            bail!("[engine.execution.topup] needs an asset, and interval_secs and low_checks must be positive");
+        }
+        if self.min_margin <= Decimal::ZERO
+            || self.target_margin <= self.min_margin
+            || self.max_transfer <= Decimal::ZERO
+            || self.max_daily < self.max_transfer
+        {
+            bail!("[engine.execution.topup] needs 0 < min_margin < target_margin and 0 < max_transfer <= max_daily");
+        }
+        if accounts.len() < 2 {
+            bail!("[engine.execution.topup] needs two or more [[engine.execution.accounts]]");
+        }
+        if accounts.iter().filter(|a| a.sub_account.is_none()).count() > 1 {
+            bail!("[engine.execution.topup] only one account can be the master (no sub_account)");
+        }
+        Ok(())
+    }
+}
+
+impl ExecutionConfig {
+    pub fn tca_timeout(&self) -> Duration {
+        Duration::from_secs(self.tca_timeout_secs)
+    }
+
+    /// What `exchange`'s contract is configured as, if anything.
+    pub fn contract(&self, exchange: ExchangeId) -> Option<&ContractConfig> {
+        self.contracts
+            .iter()
+            .find(|(name, _)| ExchangeId::from_name(name) == Some(exchange))
+            .map(|(_, contract)| contract)
+    }
+
+    /// The symbol traded on `exchange`.
+    pub fn symbol_on(&self, exchange: ExchangeId) -> &str {
+        self.contract(exchange)
+            .and_then(|contract| contract.symbol.as_deref())
+            .unwrap_or(&self.symbol)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.symbol.is_empty() {
+            bail!("[engine.execution] needs a symbol");
+        }
+        if self.quantity <= Decimal::ZERO || self.threshold_percent <= Decimal::ZERO {
+            bail!("[engine.execution] quantity and threshold_percent must be positive");
+        }
+        if self.latency.haircut_percent_per_sec < Decimal::ZERO
+            || self.latency.similar_edge_percent < Decimal::ZERO
+        {
+            bail!("[engine.execution.latency] haircut_percent_per_sec and similar_edge_percent can't be negative");
+        }
+        if self.fee_percent < Decimal::ZERO || self.recheck_floor_percent < Decimal::ZERO {
+            bail!("[engine.execution] fee_percent and recheck_floor_percent can't be negative");
+        }
+        self.expiry.validate(self.mode)?;
+        if self.mode == ExecutionMode::Inventory {
+            self.inventory.validate()?;
+        }
+        self.validate_accounts()?;
+        self.validate_contracts()?;
+        self.funding.validate()?;
+        self.retry.validate()?;
+        self.rate_limits.validate()?;
+        self.validate_strategies()?;
+        self.topup.validate(&self.accounts)
+    }
+
+    fn validate_strategies(&self) -> anyhow::Result<()> {
+        for (name, strategy) in &self.strategies {
+            if name.trim().is_empty() {
+                bail!("[engine.execution.strategies] names can't be blank");
+            }
+            if strategy.capital.is_some_and(|c| c <= Decimal::ZERO)
+                || strategy.max_loss.is_some_and(|l| l <= Decimal::ZERO)
+                || strategy.max_failed == Some(0)
+                || strategy
+                    .threshold_percent
+                    .is_some_and(|t| t <= Decimal::ZERO)
+                || strategy.quantity.is_some_and(|q| q <= Decimal::ZERO)
+            {
+                bail!(
+                    "[engine.execution.strategies.{}] capital, max_loss, max_failed, threshold_percent and quantity must be positive",
+                    name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_contracts(&self) -> anyhow::Result<()> {
+        for (name, contract) in &self.contracts {
+            if ExchangeId::from_name(name).is_none() {
+                bail!(
+                    "[engine.execution.contracts] has unknown exchange {:?}",
+                    name
+                );
+            }
+            if contract
+                .symbol
+                .as_ref()
+                .is_some_and(|s| s.trim().is_empty())
+            {
+                bail!(
+                    "[engine.execution.contracts.{}] symbol can't be empty",
+                    name
+                );
+            }
+            match (contract.kind, contract.contract_size) {
+                (ContractKind::Linear, Some(_)) => bail!(
+                    "[engine.execution.contracts.{}] contract_size only applies to inverse contracts",
+                    name
+                ),
+                (ContractKind::Inverse, Some(size)) if size <= Decimal::ZERO => bail!(
+                    "[engine.execution.contracts.{}] contract_size must be positive",
+                    name
+                ),
+                (ContractKind::Inverse, _) if self.mode == ExecutionMode::Inventory => bail!(
+                    "[engine.execution.contracts.{}] inverse contracts only trade in futures mode",
+                    name
+                ),
+                // Orders go out through Binance's USDⓈ-M API only.
+                (ContractKind::Inverse, _) => bail!(
+                    "[engine.execution.contracts.{}] inverse contracts can't be traded yet: no venue has a coin-margined order client",
+                    name
+                ),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_accounts(&self) -> anyhow::Result<()> {
+        let mut names = HashSet::new();
+        for account in &self.accounts {
+            if account.name.is_empty()
+                || account.api_key_var.is_empty()
+                || account.secret_key_var.is_empty()
+            {
+                bail!("[[engine.execution.accounts]] needs a name, api_key_var and secret_key_var");
+            }
+            if !names.insert(&account.name) {
+                bail!(
+                    "[[engine.execution.accounts]] name {:?} is used twice",
+                    account.name
+                );
+            }
+            if account.fee_percent.is_some_and(|fee| fee < Decimal::ZERO) {
+                bail!(
+                    "[[engine.execution.accounts]] {}: fee_percent can't be negative",
+                    account.name
+                );
+            }
+            if account.exchange != ExchangeId::Binance {
+                bail!(
+                    "[[engine.execution.accounts]] {}: {} has no order client yet",
+                    account.name,
+                    account.exchange
+                );
+            }
+        }
+        if self.routing == AccountRouting::Symbol
+            && !self.accounts.is_empty()
+            && !self.accounts.iter().any(|a| {
+                a.symbols.is_empty()
+                    || a.symbols
+                        .iter()
+                        .any(|s| s.eq_ignore_ascii_case(&self.symbol))
+            })
+        {
+            bail!(
+                "[engine.execution] routing = \"symbol\", but no account takes {}",
+                self.symbol
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Where the latency-critical tasks (feeds, parsing, the tracker and the
+/// strategy loop) run; see `crate::runtime`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeConfig {
+    pub hot_path: HotPathMode,
+    /// Worker threads of a dedicated hot-path runtime; 1 means a
+    /// current-thread runtime on its own OS thread.
+    pub hot_path_threads: Option<usize>,
+    /// CPU cores to pin the hot-path threads to, assigned in order.
+    pub cores: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HotPathMode {
+    /// Everything shares the main runtime.
+    #[default]
+    Shared,
+    /// The hot path gets its own runtime, away from storage and notifications.
+    Dedicated,
+}
+
+/// Where secrets come from (see `crate::keys`): at most one of the key
+/// file, `[keys.vault]` and `[keys.aws]`; the environment only when none is
+/// set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    /// Written by `arbitrage-bot keys import`.
+    pub file: Option<PathBuf>,
+    /// Holds the key file's passphrase, for unattended starts; prompted for
+    /// when unset.
+    pub passphrase_file: Option<PathBuf>,
+    /// How often secrets are fetched again to pick up rotated keys; 0 never.
+    pub refresh_secs: u64,
+    pub vault: Option<VaultConfig>,
+    pub aws: Option<AwsSecretsConfig>,
+}
+
+impl KeysConfig {
+    pub fn refresh(&self) -> Duration {
+        Duration::from_secs(self.refresh_secs)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let providers = [
+            self.file.is_some(),
+            self.vault.is_some(),
+            self.aws.is_some(),
+        ];
+        if providers.into_iter().filter(|&set| set).count() > 1 {
+            bail!("[keys] file, [keys.vault] and [keys.aws] are mutually exclusive");
+        }
+        Ok(())
+    }
+}
+
+impl Default for KeysConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            passphrase_file: None,
+            refresh_secs: 300,
+            vault: None,
+            aws: None,
+        }
+    }
+}
+
+/// A HashiCorp Vault KV v2 secret. The token comes from `VAULT_TOKEN`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VaultConfig {
+    /// e.g. `https://vault.internal:8200`.
+    pub addr: String,
+    /// The KV v2 mount.
+    #[serde(default = "VaultConfig::default_mount")]
+    pub mount: String,
+    /// The secret's path under the mount, e.g. `arbitrage-bot`.
+    pub path: String,
+}
+
+impl VaultConfig {
+    fn default_mount() -> String {
+        "secret".into()
+    }
+}
+
+/// An AWS Secrets Manager secret. Credentials come from `AWS_ACCESS_KEY_ID`,
+/// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AwsSecretsConfig {
+    /// e.g. `eu-west-1`.
+    pub region: String,
+    /// The secret's name or ARN.
+    pub secret_id: String,
+}
+
+/// The HTTP control API and web dashboard (see `crate::api`). Requests must
+/// carry `Authorization: Bearer <token>` with the admin token from
+/// `CONTROL_API_TOKEN` or the read-only one from `CONTROL_API_READ_TOKEN`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    /// Serve the API on this address; off when unset.
+    pub listen: Option<SocketAddr>,
+    /// How often the dashboard's WebSocket pushes quotes and spreads.
+    pub push_interval_ms: u64,
+    /// Serve HTTPS with this certificate chain and `tls_key` (PEM files).
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    /// Also require client certificates issued by this CA (PEM): mutual TLS.
+    pub client_ca: Option<PathBuf>,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            push_interval_ms: 500,
+            tls_cert: None,
+            tls_key: None,
+            client_ca: None,
+        }
+    }
+}
+
+impl ApiConfig {
+    pub fn push_interval(&self) -> Duration {
+        Duration::from_millis(self.push_interval_ms)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.push_interval_ms == 0 {
+            bail!("[api] push_interval_ms must be positive");
+        }
+        if self.tls_cert.is_some() != self.tls_key.is_some() {
+            bail!("[api] tls_cert and tls_key go together");
+        }
+        if self.client_ca.is_some() && self.tls_cert.is_none() {
+            bail!("[api] client_ca needs tls_cert and tls_key");
+        }
+        Ok(())
+    }
+}
+
+/// Caps on structures that would otherwise grow without bound (see
+/// `crate::limits`). Reaching one prunes the oldest entries and warns.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// How often the sizes are printed.
+    pub report_interval_secs: u64,
+    /// Symbols the tracker keeps snapshots for.
+    pub tracker_symbols: usize,
+    /// Exchange pairs the alert gate remembers between resets.
+    pub alert_keys: usize,
+    /// Disconnect timestamps per connection (for the circuit breaker).
+    pub disconnect_timestamps: usize,
+    /// Unacknowledged subscription requests per combined stream.
+    pub pending_requests: usize,
+    /// Orders execution remembers (and saves with the runtime state).
+    pub order_history: usize,
+    /// Opportunities the tracker keeps for the control API; older ones roll off.
+    pub recent_opportunities: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            report_interval_secs: 300,
+            tracker_symbols: 10_000,
+            alert_keys: 10_000,
+            disconnect_timestamps: 1_000,
+            pending_requests: 1_000,
+            order_history: 1_000,
+            recent_opportunities: 100,
+        }
+    }
+}
+
+impl LimitsConfig {
+    pub fn report_interval(&self) -> Duration {
+        Duration::from_secs(self.report_interval_secs)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.report_interval_secs == 0
+            || self.tracker_symbols == 0
+            || self.alert_keys == 0
+            || self.disconnect_timestamps == 0
+            || self.pending_requests == 0
+            || self.order_history == 0
+            || self.recent_opportunities == 0
+        {
+            bail!("[limits] values must be positive");
+        }
+        Ok(())
+    }
+}
+
+impl RuntimeConfig {
+    pub fn threads(&self) -> usize {
+        self.hot_path_threads.unwrap_or(1)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.threads() == 0 {
+            bail!("[runtime] hot_path_threads must be positive");
+        }
+        if self.hot_path == HotPathMode::Shared
+            && (self.hot_path_threads.is_some() || !self.cores.is_empty())
+        {
+            bail!("[runtime] hot_path_threads and cores need hot_path = \"dedicated\"");
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::load_from(Some(path.as_ref()), std::iter::empty())
+    }
+
+    /// Loads `path`, or defaults without one, with the `ARB__` variables
+    /// among `vars` set over it (see the module docs).
+    pub fn load_from(
+        path: Option<&Path>,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> anyhow::Result<Self> {
+        let overrides = env_overrides(vars)?;
+        let source = match (path, overrides.is_empty()) {
+            (Some(path), true) => path.display().to_string(),
+            (Some(path), false) => format!(
+                "{} and {}* variables",
+                path.display(),
+                cfg_const::ENV_PREFIX
+            ),
+            (None, _) => format!("{}* variables", cfg_const::ENV_PREFIX),
+        };
+        let raw = match path {
+            Some(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?,
+            None => String::new(),
+        };
+        // Without overrides the file is parsed as is, so errors point at
+        // its lines.
+        let config: Self = if overrides.is_empty() {
+            toml::from_str(&raw).with_context(|| format!("parsing {}", source))?
+        } else {
+            let base: toml::Table =
+                toml::from_str(&raw).with_context(|| format!("parsing {}", source))?;
+            let mut overrides = overrides;
+            loop {
+                let mut table = base.clone();
+                for o in &overrides {
+                    set(&mut table, &o.keys, o.value.clone())
+                        .with_context(|| format!("setting {}", o.name))?;
+                }
+                let error = match toml::Value::Table(table).try_into() {
+                    Ok(config) => break config,
+                    Err(error) => error,
+                };
+                let first = culprit(base.clone(), &overrides);
+                if let Some(i) = first {
+                    // Read as TOML, the value wasn't what the key takes; try
+                    // it as the string it was set as.
+                    let mut fallback = overrides.clone();
+                    fallback[i].value = toml::Value::String(fallback[i].raw.clone());
+                    if fallback[i].value != overrides[i].value
+                        && culprit(base.clone(), &fallback) != Some(i)
+                    {
+                        overrides = fallback;
+                        continue;
+                    }
+                }
+                let error = match first {
+                    Some(i) => {
+                        anyhow::Error::from(error).context(format!("setting {}", overrides[i].name))
+                    }
+                    None => error.into(),
+                };
+                return Err(error.context(format!("parsing {}", source)));
+            }
+        };
+        config
+            .validate()
+            .with_context(|| format!("validating {}", source))?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        self.ws.default.validate("ws.default")?;
+        self.signing.default.validate("signing.default")?;
+        self.feeds.binance.validate()?;
+        self.engine.validate()?;
+        self.calendar.validate()?;
+        self.transfers.validate()?;
+        self.fx.validate()?;
+        self.volatility.validate()?;
+        self.liveness.validate()?;
+        self.failover.validate()?;
+        self.pauses.validate()?;
+        self.listing_mode.validate()?;
+        self.runtime.validate()?;
+        self.limits.validate()?;
+        self.api.validate()?;
+        self.tui.validate()?;
+        self.mqtt.validate()?;
+        self.influx.validate()?;
+        self.keys.validate()?;
+        self.notifications.validate()?;
+        if self.recording.symbols.iter().any(|s| s.trim().is_empty()) {
+            bail!("[recording] symbols can't be empty");
+        }
+        for tap in &self.taps {
+            if tap.file.is_none() && tap.listen.is_none() {
+                bail!(
+                    "[[tap]] for {} needs a `file` and/or `listen` sink",
+                    tap.exchange
+                );
+            }
+        }
+        for exchange in self.ws.exchanges.keys() {
+            self.ws
+                .for_exchange(exchange)
+                .validate(&format!("ws.exchanges.{}", exchange))?;
+        }
+        for exchange in self.signing.exchanges.keys() {
+            self.signing
+                .for_exchange(exchange)
+                .validate(&format!("signing.exchanges.{}", exchange))?;
+        }
+        Ok(())
+    }
+
+    /// Loads from `ARB_CONFIG` or `config.toml`, with the `ARB__`
+    /// variables set over it. A missing default file yields defaults; an
+    /// explicitly configured path that can't be read is an error.
+    pub fn load_default() -> anyhow::Result<Self> {
+        let path = match std::env::var(cfg_const::PATH_ENV) {
+            Ok(path) => Some(PathBuf::from(path)),
+            Err(_) if !Path::new(cfg_const::DEFAULT_PATH).exists() => None,
+            Err(_) => Some(PathBuf::from(cfg_const::DEFAULT_PATH)),
+        };
+        Self::load_from(path.as_deref(), std::env::vars())
+    }
+}
+
+/// One `ARB__` variable.
+#[derive(Clone)]
+struct EnvOverride {
+    name: String,
+    /// The key path, in lower case.
+    keys: Vec<String>,
+    value: toml::Value,
+    /// The value as set, for a string key given something that reads as
+    /// another TOML value.
+    raw: String,
+}
+
+/// The `ARB__` variables among `vars`.
+fn env_overrides(
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> anyhow::Result<Vec<EnvOverride>> {
+    let mut overrides = Vec::new();
+    for (name, raw) in vars {
+        let Some(path) = name.strip_prefix(cfg_const::ENV_PREFIX) else {
+            continue;
+        };
+        let keys: Vec<String> = path
+            .split(cfg_const::ENV_SEPARATOR)
+            .map(str::to_lowercase)
+            .collect();
+        if keys.iter().any(String::is_empty) {
+            bail!("{} doesn't name a key", name);
+        }
+        overrides.push(EnvOverride {
+            name,
+            keys,
+            value: env_value(&raw),
+            raw,
+        });
+    }
+    // Sorted, so a variable setting a whole table comes before those
+    // setting keys inside it.
+    overrides.sort_by(|a, b| a.keys.cmp(&b.keys));
+    Ok(overrides)
+}
+
+/// A variable's value: a TOML value, or else the raw string.
+fn env_value(raw: &str) -> toml::Value {
+    if let Ok(mut table) = toml::from_str::<toml::Table>(&format!("value = {}", raw)) {
+        if table.len() == 1 {
+            if let Some(value) = table.remove("value") {
+                return value;
+            }
+        }
+    }
+    toml::Value::String(raw.to_string())
+}
+
+/// The index of the first of `overrides` after which `table` no longer
+/// reads as a config, to name in the error; `None` if `table` didn't read
+/// as one to begin with.
+fn culprit(mut table: toml::Table, overrides: &[EnvOverride]) -> Option<usize> {
+    let reads = |table: &toml::Table| {
+        toml::Value::Table(table.clone())
+            .try_into::<Config>()
+            .is_ok()
+    };
+    if !reads(&table) {
+        return None;
+    }
+    for (i, o) in overrides.iter().enumerate() {
+        set(&mut table, &o.keys, o.value.clone()).ok()?;
+        if !reads(&table) {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Sets `keys` in `table` to `value`, merging it into a table already there.
+/// A key after an array of tables is an index into it.
+fn set(table: &mut toml::Table, keys: &[String], value: toml::Value) -> anyhow::Result<()> {
+    let (key, sections) = keys.split_last().context("no key")?;
+    let mut table = table;
+    let mut sections = sections.iter();
+    while let Some(section) = sections.next() {
+        let entry = match table
+            .entry(section.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        {
+            toml::Value::Array(entries) => {
+                let index = sections
+                    .next()
+                    .and_then(|index| index.parse::<usize>().ok())
+                    .with_context(|| format!("{} needs an index", section))?;
+                entries
+                    .get_mut(index)
+                    .with_context(|| format!("{} has no entry {}", section, index))?
+            }
+            entry => entry,
+        };
+        table = match entry {
+            toml::Value::Table(inner) => inner,
+            _ => bail!("{} isn't a section", section),
+        };
+    }
+    match (table.get_mut(key), value) {
+        (Some(toml::Value::Table(existing)), toml::Value::Table(value)) => {
+            for (inner, value) in value {
+                set(existing, &[inner], value)?;
+            }
+        }
+        (_, value) => {
+            table.insert(key.clone(), value);
+        }
+    }
+    Ok(())
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Installs the process-wide configuration. Only the first call has an effect.
+pub fn init(config: Config) {
+    if CONFIG.set(config).is_err() {
+        eprintln!("⚠️ Configuration already initialised; ignoring reload");
+    }
+}
+
+/// The process-wide configuration (defaults if [`init`] was never called).
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
@@ -7,35 +7,105 @@ pub mod pairs {
     pub const WLFI_USDT_BYBIT: &str = "WLFIUSDT";
 }
 
+/// Symbols the live pipeline streams, in each exchange's own spelling.
+pub mod symbols {
+    pub const BYBIT_FUTURES: &[&str] = &[
+        "WLFIUSDT",
+        "ETHUSDT",
+        "BTCUSDT",
+        "SOLUSDT",
+        "LINKUSDT",
+        "XRPUSDT",
+        "BNBUSDT",
+        "1000PEPEUSDT",
+    ];
+    pub const BINANCE_FUTURES: &[&str] = &[
+        "wlfiusdt",
+        "ethusdt",
+        "btcusdt",
+        "solusdt",
+        "linkusdt",
+        "xrpusdt",
+        "bnbusdt",
+        "1000pepeusdt",
+    ];
+}
+
+pub mod config {
+    pub const DEFAULT_PATH: &str = "config.toml";
+    /// Environment variable overriding the config file path.
+    pub const PATH_ENV: &str = "ARB_CONFIG";
+    /// Prefix of the environment variables setting individual keys.
+    pub const ENV_PREFIX: &str = "ARB__";
+    /// Separates the section and key names in such a variable.
+    pub const ENV_SEPARATOR: &str = "__";
+}
+
 pub mod exchange_names {
     pub const BINANCE: &str = "binance";
     pub const BYBIT: &str = "bybit";
 }
 
 pub mod thresholds {
-    pub const HIGHT_THRESHOLD_10_PERCENT: f64 = 0.1;
-    pub const MID_THRESHOLD_5_PERCENT: f64 = 0.05;
-    pub const LOW_THRESHOLD_2_PERCENT: f64 = 0.02;
-    pub const LOW_THRESHOLD_1_PERCENT: f64 = 0.01;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    pub const HIGHT_THRESHOLD_10_PERCENT: Decimal = dec!(0.1);
+    pub const MID_THRESHOLD_5_PERCENT: Decimal = dec!(0.05);
+    pub const LOW_THRESHOLD_2_PERCENT: Decimal = dec!(0.02);
+    pub const LOW_THRESHOLD_1_PERCENT: Decimal = dec!(0.01);
 }
 
 pub mod notifications {
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
     /// Minimum diff percentage to trigger a Telegram alert (5%).
-    pub const DIFF_THRESHOLD: f64 = 5.0;
+    pub const DIFF_THRESHOLD: Decimal = dec!(5);
     /// Minimum percentage-point increase over the last notified diff to re-alert.
-    pub const RE_ALERT_DELTA: f64 = 1.0;
+    pub const RE_ALERT_DELTA: Decimal = dec!(1);
     /// Minimum seconds between any two Telegram API calls.
     pub const COOLDOWN_SECS: u64 = 120;
     /// Interval in seconds to wipe notification state (24 hours).
     pub const STATE_RESET_SECS: u64 = 86_400;
+    /// Minimum seconds between two reconnect notices for the same feed URL.
+    pub const RECONNECT_ALERT_COOLDOWN_SECS: u64 = 300;
 }
 
 pub mod urls {
     pub const BINANCE_URL_SPOT: &str = "wss://stream.binance.com:9443/ws"; // Spot
+    pub const BINANCE_URL_SPOT_ALT: &str = "wss://stream.binance.com:443/ws"; // Spot, alternate port for redundant feeds
     pub const BINANCE_URL_FUTURES: &str = "wss://fstream.binance.com/ws"; // Futures
+    pub const BINANCE_URL_FUTURES_API: &str = "wss://ws-fapi.binance.com/ws-fapi/v1"; // Futures WS API (orders)
+    pub const BINANCE_URL_SPOT_COMBINED: &str = "wss://stream.binance.com:9443/stream";
+    pub const BINANCE_URL_FUTURES_COMBINED: &str = "wss://fstream.binance.com/stream";
     pub const BYBIT_URL_SPOT: &str = "wss://stream.bybit.com/v5/public/spot"; // Spot
     pub const BYBIT_URL_FUTURES_LINEAR: &str = "wss://stream.bybit.com/v5/public/linear";
+    pub const BYBIT_URL_FUTURES_INVERSE: &str = "wss://stream.bybit.com/v5/public/inverse";
     pub const BYBIT_URL_FUTURES: &str = "wss://stream.bybit.com/v5/trade";
     pub const BYBIT_URL_FUTURES_TESTNET: &str = "wss://stream-testnet.bybit.com/v5/trade";
+    pub const BINANCE_REST_FUTURES: &str = "https://fapi.binance.com";
+    pub const BINANCE_REST_SPOT: &str = "https://api.binance.com";
+    pub const BYBIT_REST: &str = "https://api.bybit.com";
     // Futures
 }
+
+pub mod backtest {
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    /// Default calibration window for walk-forward evaluation (7 days).
+    pub const DEFAULT_TRAIN_WINDOW_SECS: i64 = 7 * 86_400;
+    /// Default evaluation window and roll step (1 day).
+    pub const DEFAULT_TEST_WINDOW_SECS: i64 = 86_400;
+    /// Quantile of the training diffs used as the calibrated threshold.
+    pub const DEFAULT_QUANTILE: f64 = 0.95;
+    /// Default lookback for kline spread histories.
+    pub const DEFAULT_KLINE_DAYS: i64 = 7;
+    /// Default lookback for funding-rate backtests.
+    pub const DEFAULT_FUNDING_DAYS: i64 = 30;
+    /// Default notional per leg for funding-rate backtests.
+    pub const DEFAULT_NOTIONAL: Decimal = dec!(1000);
+    /// Taker fee for opening and closing one leg, in percent.
+    pub const DEFAULT_ROUND_TRIP_FEE_PERCENT: Decimal = dec!(0.1);
+}
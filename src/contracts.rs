@@ -0,0 +1,264 @@
+//! Linear and inverse (coin-margined) contracts
+//! (`[engine.execution.contracts.<exchange>]`).
+//!
+//! A linear perpetual such as BTCUSDT is sized in the base currency and
+//! settles in the quote currency. An inverse one, Binance's BTCUSD_PERP or
+//! Bybit's BTCUSD, is sized in contracts worth a fixed number of USD each and
+//! is margined and settled in the coin. Execution sizes a trade in the base
+//! currency (`quantity`). Against an inverse leg, that's cut down to what the
+//! whole contracts it's worth there come to, and then to the linear leg's
+//! step; the inverse leg trades as many contracts as that is worth, so the
+//! trade stays hedged to within a contract. From there on orders, exposure
+//! and unwinds keep each venue's own unit, the way the venue reports
+//! positions.
+//!
+//! PnL on an inverse contract accrues in the coin: a long of `n` contracts of
+//! `size` USD from `entry` to `exit` makes `n · size · (1/entry − 1/exit)`
+//! coins, worth `n · size / entry · (exit − entry)` at `exit`, the same as a
+//! linear long of `n · size / entry`. The session summary (see
+//! `crate::session`) values each inverse leg at its `n · size` notional, so a
+//! BTCUSD inverse leg against a BTCUSDT linear one adds up like two linear
+//! legs. USD and USDT are taken at par.
+
+use std::collections::HashMap;
+
+use crate::{
+    config::{ContractConfig, ContractKind, ExecutionConfig},
+    models::{
+        ids::ExchangeId,
+        money::{self, Decimal, RoundingStrategy},
+    },
+    transfers,
+};
+
+/// How one venue's contract is sized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contract {
+    pub kind: ContractKind,
+    /// USD per contract of an inverse contract; one for linear ones.
+    pub size: Decimal,
+}
+
+impl Default for Contract {
+    fn default() -> Self {
+        Self::LINEAR
+    }
+}
+
+impl Contract {
+    pub const LINEAR: Self = Self {
+        kind: ContractKind::Linear,
+        size: Decimal::ONE,
+    };
+
+    pub fn inverse(size: Decimal) -> Self {
+        Self {
+            kind: ContractKind::Inverse,
+            size,
+        }
+    }
+
+    /// The contract `exchange` lists as `symbol`, going by its name:
+    /// Binance's coin-margined perpetuals end in `_PERP` and are worth 100
+    /// USD for BTC and 10 for the rest, Bybit's inverse ones are quoted in
+    /// USD and are worth 1.
+    ///
+    /// ```
+    /// use arbitrage_bot::{contracts::Contract, models::ids::ExchangeId};
+    /// use rust_decimal_macros::dec;
+    ///
+    /// assert_eq!(Contract::infer(ExchangeId::Binance, "BTCUSD_PERP"), Contract::inverse(dec!(100)));
+    /// assert_eq!(Contract::infer(ExchangeId::Binance, "ETHUSD_PERP"), Contract::inverse(dec!(10)));
+    /// assert_eq!(Contract::infer(ExchangeId::Bybit, "BTCUSD"), Contract::inverse(dec!(1)));
+    /// assert_eq!(Contract::infer(ExchangeId::Bybit, "BTCUSDT"), Contract::LINEAR);
+    /// ```
+    pub fn infer(exchange: ExchangeId, symbol: &str) -> Self {
+        let symbol = symbol.to_uppercase();
+        match exchange {
+            ExchangeId::Binance => match symbol.strip_suffix("_PERP") {
+                Some("BTCUSD") => Self::inverse(Decimal::ONE_HUNDRED),
+                Some(_) => Self::inverse(Decimal::TEN),
+                None => Self::LINEAR,
+            },
+            ExchangeId::Bybit => match transfers::currencies(&symbol) {
+                Some((_, "USD")) => Self::inverse(Decimal::ONE),
+                _ => Self::LINEAR,
+            },
+        }
+    }
+
+    /// The contract configured for `exchange`'s `symbol`, with the venue's
+    /// standard size where none is set.
+    pub fn configured(exchange: ExchangeId, symbol: &str, config: &ContractConfig) -> Self {
+        match config.kind {
+            ContractKind::Linear => Self::LINEAR,
+            ContractKind::Inverse => Self::inverse(config.contract_size.unwrap_or_else(|| {
+                match Self::infer(exchange, symbol) {
+                    inferred if inferred.is_inverse() => inferred.size,
+                    // Not named like an inverse contract; take the venue's
+                    // usual size anyway.
+                    _ if exchange == ExchangeId::Binance => Decimal::TEN,
+                    _ => Decimal::ONE,
+                }
+            })),
+        }
+    }
+
+    pub fn is_inverse(&self) -> bool {
+        self.kind == ContractKind::Inverse
+    }
+
+    /// What an order for `base` of the base currency at `price` is for, in
+    /// this venue's unit: `base` itself, or the whole contracts it's worth,
+    /// rounded down.
+    pub fn order_quantity(&self, base: Decimal, price: Decimal) -> Decimal {
+        match self.kind {
+            ContractKind::Linear => base,
+            // Rounded first, so a base worked out from whole contracts
+            // comes back as as many.
+            ContractKind::Inverse => money::round_to_step(
+                (base * price / self.size).round_dp(8),
+                Decimal::ONE,
+                RoundingStrategy::ToZero,
+            ),
+        }
+    }
+
+    /// What `quantity` of this venue's unit is worth in the base currency
+    /// at `price`.
+    pub fn base_quantity(&self, quantity: Decimal, price: Decimal) -> Decimal {
+        match self.kind {
+            ContractKind::Linear => quantity,
+            ContractKind::Inverse => (quantity * self.size)
+                .checked_div(price)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// What `quantity` of this venue's unit at `price` is worth in the
+    /// quote currency.
+    pub fn notional(&self, quantity: Decimal, price: Decimal) -> Decimal {
+        match self.kind {
+            ContractKind::Linear => quantity * price,
+            ContractKind::Inverse => quantity * self.size,
+        }
+    }
+
+    /// The PnL of a long of `quantity` from `entry` to `exit`, in the
+    /// currency the contract settles in: quote for linear, base for inverse.
+    /// A short's is the negative.
+    ///
+    /// ```
+    /// use arbitrage_bot::contracts::Contract;
+    /// use rust_decimal_macros::dec;
+    ///
+    /// // 50 contracts of 100 USD, long from 50000 to 62500: 0.02 BTC.
+    /// let inverse = Contract::inverse(dec!(100));
+    /// assert_eq!(inverse.pnl(dec!(50), dec!(50000), dec!(62500)), dec!(0.02));
+    /// // 1250 USD at 62500, as much as a linear long of the 0.1 BTC the
+    /// // contracts were worth at entry.
+    /// assert_eq!(inverse.pnl_in_quote(dec!(50), dec!(50000), dec!(62500)), dec!(1250));
+    /// assert_eq!(Contract::LINEAR.pnl(dec!(0.1), dec!(50000), dec!(62500)), dec!(1250));
+    /// ```
+    pub fn pnl(&self, quantity: Decimal, entry: Decimal, exit: Decimal) -> Decimal {
+        match self.kind {
+            ContractKind::Linear => quantity * (exit - entry),
+            ContractKind::Inverse => {
+                let (Some(entry), Some(exit)) = (
+                    Decimal::ONE.checked_div(entry),
+                    Decimal::ONE.checked_div(exit),
+                ) else {
+                    return Decimal::ZERO;
+                };
+                (quantity * self.size * (entry - exit)).normalize()
+            }
+        }
+    }
+
+    /// [`pnl`](Self::pnl) in the quote currency, valued at `exit`.
+    pub fn pnl_in_quote(&self, quantity: Decimal, entry: Decimal, exit: Decimal) -> Decimal {
+        match self.kind {
+            ContractKind::Linear => self.pnl(quantity, entry, exit),
+            ContractKind::Inverse => (self.pnl(quantity, entry, exit) * exit).normalize(),
+        }
+    }
+}
+
+/// The contract each venue trades; linear where none is configured.
+#[derive(Debug, Clone, Default)]
+pub struct Contracts {
+    contracts: HashMap<ExchangeId, Contract>,
+}
+
+impl Contracts {
+    pub fn new(execution: &ExecutionConfig) -> Self {
+        let contracts = execution
+            .contracts
+            .iter()
+            .filter_map(|(name, config)| {
+                let exchange = ExchangeId::from_name(name)?;
+                let contract =
+                    Contract::configured(exchange, execution.symbol_on(exchange), config);
+                Some((exchange, contract))
+            })
+            .collect();
+        Self { contracts }
+    }
+
+    /// Trades `contract` on `exchange`.
+    pub fn with(mut self, exchange: ExchangeId, contract: Contract) -> Self {
+        self.contracts.insert(exchange, contract);
+        self
+    }
+
+    pub fn get(&self, exchange: ExchangeId) -> Contract {
+        self.contracts.get(&exchange).copied().unwrap_or_default()
+    }
+
+    /// The order quantities of a trade of `base` across `legs` of `(exchange,
+    /// price)`: `base` is cut down to what the inverse legs' whole contracts
+    /// are worth and, with a linear leg, rounded down to its `step`; the
+    /// inverse legs then trade the contracts that is worth. `None` when a leg
+    /// comes to nothing.
+    ///
+    /// ```
+    /// use arbitrage_bot::{
+    ///     contracts::{Contract, Contracts},
+    ///     models::ids::ExchangeId,
+    /// };
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let contracts = Contracts::default().with(ExchangeId::Bybit, Contract::inverse(dec!(1)));
+    /// let legs = [(ExchangeId::Bybit, dec!(60000)), (ExchangeId::Binance, dec!(60100))];
+    /// // 0.01 BTC is 600 contracts at 60000, worth 0.01 BTC.
+    /// assert_eq!(contracts.size(dec!(0.01), dec!(0.001), legs), Some([dec!(600), dec!(0.01)]));
+    /// // 0.0001 BTC is 6 contracts, but less than a linear step.
+    /// assert_eq!(contracts.size(dec!(0.0001), dec!(0.001), legs), None);
+    /// ```
+    pub fn size(
+        &self,
+        base: Decimal,
+        step: Decimal,
+        legs: [(ExchangeId, Decimal); 2],
+    ) -> Option<[Decimal; 2]> {
+        let mut base = legs
+            .iter()
+            .map(|&(exchange, price)| {
+                let contract = self.get(exchange);
+                contract.base_quantity(contract.order_quantity(base, price), price)
+            })
+            .fold(base, Decimal::min);
+        if legs
+            .iter()
+            .any(|&(exchange, _)| !self.get(exchange).is_inverse())
+        {
+            base = money::round_to_step(base, step, RoundingStrategy::ToZero);
+        }
+        let quantities =
+            legs.map(|(exchange, price)| self.get(exchange).order_quantity(base, price));
+        quantities
+            .iter()
+            .all(|quantity| quantity > &Decimal::ZERO)
+            .then_some(quantities)
+    }
+}
@@ -0,0 +1,870 @@
+//! Operating a running engine without a restart.
+//!
+//! [`Control`] is a cheap, cloneable handle to what an operator can see and
+//! change: status, execution's orders and exposure, recent opportunities,
+//! pausing execution, thresholds, the subscribed symbols, raw-feed
+//! recording per symbol, external trade signals, which strategies may
+//! trade and at what threshold and size, and shutting down.
+//! The control API (`crate::api`) is one front end to it.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+    time::Instant,
+};
+
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{
+    broadcast,
+    mpsc::{self, error::TrySendError},
+    watch,
+};
+use tokio_util::sync::CancellationToken;
+
+#[cfg(feature = "execution")]
+use crate::audit;
+#[cfg(any(feature = "binance", feature = "bybit"))]
+use crate::runtime;
+#[cfg(feature = "binance")]
+use crate::ws::binance_client_multiplex::{
+    depth_stream, spawn_orderbook_stream_binance_multiplex, MultiplexHandle,
+};
+#[cfg(feature = "bybit")]
+use crate::ws::bybit_client_futures::run_orderbook_stream_bybit_futures;
+use crate::{
+    binance::ws_handler::ConnectionEvent,
+    error::ControlError,
+    heatmap::{Grid, Heatmap},
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::{self, Decimal},
+        orderbook::{Opportunity, TrackerHandle},
+    },
+    pauses::{PauseTarget, Paused, Pauses},
+    portfolio::StrategyBook,
+    state::{self, BreakerTrip, ExecutionState},
+    ws::{
+        quote_bus::{Quote, QuoteBus},
+        tap::Recordings,
+    },
+};
+use crate::{constants::urls, contracts::Contract};
+
+/// How many signals can wait for execution before new ones are refused.
+pub const SIGNAL_QUEUE: usize = 16;
+
+/// What execution reads before every comparison.
+#[derive(Debug, Clone, Copy)]
+pub struct ExecutionControl {
+    /// No new orders while paused; quotes are still tracked.
+    pub paused: bool,
+    /// Minimum edge to trade, in percent.
+    pub threshold_percent: Decimal,
+}
+
+/// A change to make, as sent over a message bus (see `crate::bridge`): JSON
+/// tagged by `command`.
+///
+/// ```
+/// use arbitrage_bot::control::Command;
+///
+/// let command: Command = serde_json::from_str(
+///     r#"{"command":"symbols","exchange":"bybit","subscribe":["DOGEUSDT"]}"#,
+/// )
+/// .unwrap();
+/// assert!(matches!(command, Command::Symbols { .. }));
+/// assert!(serde_json::from_str::<Command>(r#"{"command":"pause"}"#).is_ok());
+/// assert!(serde_json::from_str::<Command>(
+///     r#"{"command":"recording","symbol":"BTCUSDT","enabled":true}"#,
+/// )
+/// .is_ok());
+/// assert!(serde_json::from_str::<Command>(
+///     r#"{"command":"strategy","name":"spread","threshold_percent":"0.3"}"#,
+/// )
+/// .is_ok());
+/// assert!(serde_json::from_str::<Command>(
+///     r#"{"command":"venue","exchange":"bybit","paused":true}"#,
+/// )
+/// .is_ok());
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Command {
+    Pause,
+    Resume,
+    Kill,
+    Symbols {
+        exchange: ExchangeId,
+        #[serde(default)]
+        subscribe: Vec<String>,
+        #[serde(default)]
+        unsubscribe: Vec<String>,
+    },
+    Threshold {
+        alert_percent: Option<Decimal>,
+        execution_percent: Option<Decimal>,
+    },
+    Recording {
+        symbol: String,
+        enabled: bool,
+    },
+    Strategy {
+        name: String,
+        enabled: Option<bool>,
+        threshold_percent: Option<Decimal>,
+        quantity: Option<Decimal>,
+    },
+    /// Pauses or resumes one exchange or one symbol (see `crate::pauses`).
+    Venue {
+        exchange: Option<ExchangeId>,
+        symbol: Option<String>,
+        paused: bool,
+    },
+}
+
+/// What to change about a strategy; `None` leaves it as it is.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StrategyUpdate {
+    pub enabled: Option<bool>,
+    /// Its minimum edge, in percent.
+    pub threshold_percent: Option<Decimal>,
+    /// What it trades per leg.
+    pub quantity: Option<Decimal>,
+}
+
+/// An external request to trade, e.g. from a TradingView alert or another
+/// scanner: buy `symbol` on `buy` and sell it on `sell`. Execution checks it
+/// against live quotes like its own opportunities, so a signal whose edge is
+/// below the threshold (or arrives while paused or busy) places nothing.
+///
+/// ```
+/// use arbitrage_bot::{control::Signal, models::ids::ExchangeId};
+///
+/// let signal: Signal = serde_json::from_str(
+///     r#"{"symbol":"BTCUSDT","buy":"binance","sell":"bybit","source":"tradingview"}"#,
+/// )
+/// .unwrap();
+/// assert_eq!(signal.buy, ExchangeId::Binance);
+/// assert!(serde_json::from_str::<Signal>(r#"{"symbol":"BTCUSDT"}"#).is_err());
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Signal {
+    pub symbol: String,
+    pub buy: ExchangeId,
+    pub sell: ExchangeId,
+    /// Who sent it, for the log; also the strategy its trades are booked
+    /// to (see `crate::portfolio`).
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+impl Signal {
+    /// Its `source`, or `webhook` without one.
+    pub fn strategy(&self) -> &str {
+        self.source.as_deref().unwrap_or("webhook")
+    }
+}
+
+/// Last reported state of one feed connection.
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkStatus {
+    pub state: &'static str,
+    pub since_ms: i64,
+}
+
+/// Connection states and tripped circuit breakers of every feed, kept up to
+/// date by the engine's connection monitor.
+#[derive(Debug, Default)]
+pub struct FeedHealth {
+    connections: BTreeMap<String, LinkStatus>,
+    trips: HashMap<String, BreakerTrip>,
+}
+
+impl FeedHealth {
+    pub fn record(&mut self, event: &ConnectionEvent) {
+        let now = state::now_ms();
+        let state = match event {
+            ConnectionEvent::Connected { .. } | ConnectionEvent::Rotated { .. } => "connected",
+            ConnectionEvent::ConnectFailed { .. } => "connect_failed",
+            ConnectionEvent::Disconnected { .. } => "disconnected",
+            ConnectionEvent::Reconnecting { .. } => "reconnecting",
+            ConnectionEvent::CircuitBreakerTripped {
+                url,
+                disconnections,
+                cooldown,
+            } => {
+                self.trips.insert(
+                    url.clone(),
+                    BreakerTrip {
+                        url: url.clone(),
+                        disconnections: *disconnections,
+                        until_ms: now + cooldown.as_millis() as i64,
+                    },
+                );
+                "circuit_breaker"
+            }
+        };
+        self.connections.insert(
+            event.url().to_string(),
+            LinkStatus {
+                state,
+                since_ms: now,
+            },
+        );
+    }
+
+    /// Breakers still holding off reconnects; expired ones are forgotten.
+    pub fn active_trips(&mut self) -> Vec<BreakerTrip> {
+        let now = state::now_ms();
+        self.trips.retain(|_, trip| trip.until_ms > now);
+        self.trips.values().cloned().collect()
+    }
+}
+
+/// The engine's market-data feeds, by exchange and symbol.
+#[cfg_attr(not(any(feature = "binance", feature = "bybit")), allow(dead_code))]
+pub struct Feeds {
+    quotes: QuoteBus,
+    events: broadcast::Sender<ConnectionEvent>,
+    cancel: CancellationToken,
+    /// Upper-case (interned) symbols per exchange.
+    symbols: BTreeMap<ExchangeId, BTreeSet<&'static str>>,
+    /// The combined Binance futures stream, opened on the first subscription.
+    #[cfg(feature = "binance")]
+    binance: Option<MultiplexHandle>,
+    /// One connection per Bybit symbol, each stopped by its own token.
+    #[cfg(feature = "bybit")]
+    bybit: HashMap<&'static str, CancellationToken>,
+}
+
+impl Feeds {
+    pub fn new(
+        quotes: QuoteBus,
+        events: broadcast::Sender<ConnectionEvent>,
+        cancel: CancellationToken,
+    ) -> Self {
+        Self {
+            quotes,
+            events,
+            cancel,
+            symbols: BTreeMap::new(),
+            #[cfg(feature = "binance")]
+            binance: None,
+            #[cfg(feature = "bybit")]
+            bybit: HashMap::new(),
+        }
+    }
+
+    /// Starts feeding `symbols` of `exchange` onto the quote bus; symbols
+    /// already fed are skipped.
+    #[cfg_attr(
+        not(any(feature = "binance", feature = "bybit")),
+        allow(unreachable_code)
+    )]
+    pub fn subscribe(
+        &mut self,
+        exchange: ExchangeId,
+        symbols: &[&str],
+    ) -> Result<(), ControlError> {
+        let current = self.symbols.entry(exchange).or_default();
+        let mut added: Vec<&'static str> = Vec::new();
+        for symbol in symbols {
+            let symbol = Symbol::intern(&symbol.to_uppercase()).as_str();
+            if !current.contains(symbol) && !added.contains(&symbol) {
+                added.push(symbol);
+            }
+        }
+        if added.is_empty() {
+            return Ok(());
+        }
+
+        match exchange {
+            #[cfg(feature = "bybit")]
+            ExchangeId::Bybit => {
+                for &symbol in &added {
+                    let cancel = self.cancel.child_token();
+                    self.bybit.insert(symbol, cancel.clone());
+                    runtime::spawn(run_orderbook_stream_bybit_futures(
+                        symbol,
+                        self.quotes.clone(),
+                        feed_url(exchange, symbol),
+                        self.events.clone(),
+                        cancel,
+                    ));
+                }
+            }
+            // One combined-stream connection for all symbols; subscriptions
+            // are throttled to Binance's 5 msg/s limit and replayed on
+            // reconnect.
+            #[cfg(feature = "binance")]
+            ExchangeId::Binance => match &self.binance {
+                Some(stream) => stream.subscribe(added.iter().map(|s| depth_stream(s)).collect()),
+                None => {
+                    let hot_path = runtime::handle();
+                    let _hot = hot_path.enter();
+                    self.binance = Some(spawn_orderbook_stream_binance_multiplex(
+                        &added,
+                        self.quotes.clone(),
+                        feed_url(exchange, added[0]),
+                        self.events.clone(),
+                        self.cancel.clone(),
+                    ));
+                }
+            },
+            #[allow(unreachable_patterns)]
+            _ => return Err(ControlError::NoFeed(exchange)),
+        }
+        self.symbols.entry(exchange).or_default().extend(added);
+        Ok(())
+    }
+
+    /// Stops feeding `symbols` of `exchange`; symbols not fed are skipped.
+    #[cfg_attr(
+        not(any(feature = "binance", feature = "bybit")),
+        allow(unreachable_code)
+    )]
+    pub fn unsubscribe(
+        &mut self,
+        exchange: ExchangeId,
+        symbols: &[&str],
+    ) -> Result<(), ControlError> {
+        let Some(current) = self.symbols.get_mut(&exchange) else {
+            return Ok(());
+        };
+        let removed: Vec<&'static str> = symbols
+            .iter()
+            .filter_map(|symbol| current.take(symbol.to_uppercase().as_str()))
+            .collect();
+        if removed.is_empty() {
+            return Ok(());
+        }
+
+        match exchange {
+            #[cfg(feature = "bybit")]
+            ExchangeId::Bybit => {
+                for symbol in removed {
+                    if let Some(cancel) = self.bybit.remove(symbol) {
+                        cancel.cancel();
+                    }
+                }
+            }
+            #[cfg(feature = "binance")]
+            ExchangeId::Binance => {
+                if let Some(stream) = &self.binance {
+                    stream.unsubscribe(removed.iter().map(|s| depth_stream(s)).collect());
+                }
+            }
+            #[allow(unreachable_patterns)]
+            _ => return Err(ControlError::NoFeed(exchange)),
+        }
+        Ok(())
+    }
+
+    /// Every (exchange, symbol) currently fed.
+    pub fn venues(&self) -> Vec<(ExchangeId, &'static str)> {
+        self.symbols
+            .iter()
+            .flat_map(|(&exchange, symbols)| symbols.iter().map(move |&s| (exchange, s)))
+            .collect()
+    }
+
+    /// Every (exchange, symbol) whose quotes come over the connection to
+    /// `url`.
+    pub fn venues_on(&self, url: &str) -> Vec<(ExchangeId, &'static str)> {
+        self.venues()
+            .into_iter()
+            .filter(|&(exchange, symbol)| feed_url(exchange, symbol) == url)
+            .collect()
+    }
+
+    /// The combined Binance futures stream, e.g. to check pending requests.
+    #[cfg(feature = "binance")]
+    pub fn binance_futures(&self) -> Option<&MultiplexHandle> {
+        self.binance.as_ref()
+    }
+}
+
+/// The connection `symbol`'s quotes on `exchange` come over: one combined
+/// stream for Binance, one connection per symbol for Bybit.
+fn feed_url(exchange: ExchangeId, symbol: &str) -> &'static str {
+    match exchange {
+        ExchangeId::Binance => urls::BINANCE_URL_FUTURES_COMBINED,
+        // Inverse perpetuals (BTCUSD) have their own stream.
+        ExchangeId::Bybit if Contract::infer(exchange, symbol).is_inverse() => {
+            urls::BYBIT_URL_FUTURES_INVERSE
+        }
+        ExchangeId::Bybit => urls::BYBIT_URL_FUTURES_LINEAR,
+    }
+}
+
+/// A snapshot for `GET /status`.
+#[derive(Debug, Serialize)]
+pub struct Status {
+    pub uptime_secs: u64,
+    pub execution_enabled: bool,
+    pub paused: bool,
+    pub alert_threshold_percent: Decimal,
+    pub execution_threshold_percent: Decimal,
+    pub venues: Vec<VenueStatus>,
+    pub connections: BTreeMap<String, LinkStatus>,
+    pub circuit_breakers: Vec<BreakerTrip>,
+    /// Symbols whose raw frames are being recorded.
+    pub recording: Vec<String>,
+    /// Exchanges and symbols paused on their own.
+    pub pauses: Paused,
+}
+
+impl Status {
+    /// Spreads between every two exchanges quoting the same symbol, widest
+    /// first.
+    pub fn spreads(&self) -> Vec<Spread> {
+        let mut mids: BTreeMap<&'static str, Vec<(ExchangeId, Decimal)>> = BTreeMap::new();
+        for venue in &self.venues {
+            if let Some(mid) = venue.mid() {
+                mids.entry(venue.symbol)
+                    .or_default()
+                    .push((venue.exchange, mid));
+            }
+        }
+        let mut spreads = Vec::new();
+        for (symbol, venues) in mids {
+            for (i, &(exchange_a, mid_a)) in venues.iter().enumerate() {
+                for &(exchange_b, mid_b) in &venues[i + 1..] {
+                    if let Some(percent) = money::percent_of((mid_a - mid_b).abs(), mid_a) {
+                        spreads.push(Spread {
+                            symbol,
+                            exchange_a,
+                            exchange_b,
+                            percent,
+                        });
+                    }
+                }
+            }
+        }
+        spreads.sort_by_key(|s| Reverse(s.percent));
+        spreads
+    }
+}
+
+/// The latest quote of one fed (exchange, symbol), if any arrived yet.
+#[derive(Debug, Serialize)]
+pub struct VenueStatus {
+    pub exchange: ExchangeId,
+    pub symbol: &'static str,
+    pub bid: Option<Decimal>,
+    pub ask: Option<Decimal>,
+    /// The quote was read over REST: the feed's WebSocket is down and the
+    /// venue is degraded (see `crate::failover`).
+    pub polled: bool,
+}
+
+impl VenueStatus {
+    pub fn mid(&self) -> Option<Decimal> {
+        Some((self.bid? + self.ask?) / dec!(2))
+    }
+}
+
+/// The gap between two exchanges' mids for one symbol, measured as the
+/// tracker does: `|mid_a - mid_b|` in percent of `mid_a`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Spread {
+    pub symbol: &'static str,
+    pub exchange_a: ExchangeId,
+    pub exchange_b: ExchangeId,
+    pub percent: Decimal,
+}
+
+#[derive(Clone)]
+pub struct Control {
+    started: Instant,
+    quotes: QuoteBus,
+    tracker: TrackerHandle,
+    feeds: Arc<Mutex<Feeds>>,
+    health: Arc<Mutex<FeedHealth>>,
+    execution: watch::Sender<ExecutionState>,
+    execution_control: watch::Sender<ExecutionControl>,
+    execution_enabled: bool,
+    alert_percent: watch::Sender<Decimal>,
+    /// Set once execution is running.
+    signals: Arc<OnceLock<mpsc::Sender<Signal>>>,
+    /// Opportunities by hour of day and symbol.
+    heatmap: Heatmap,
+    /// Exchanges and symbols paused on their own.
+    pauses: Pauses,
+    cancel: CancellationToken,
+}
+
+impl Control {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        quotes: QuoteBus,
+        tracker: TrackerHandle,
+        feeds: Feeds,
+        execution: watch::Sender<ExecutionState>,
+        execution_control: ExecutionControl,
+        execution_enabled: bool,
+        alert_percent: Decimal,
+        cancel: CancellationToken,
+    ) -> Self {
+        Self {
+            started: Instant::now(),
+            quotes,
+            tracker,
+            feeds: Arc::new(Mutex::new(feeds)),
+            health: Arc::default(),
+            execution,
+            execution_control: watch::Sender::new(execution_control),
+            execution_enabled,
+            alert_percent: watch::Sender::new(alert_percent),
+            signals: Arc::default(),
+            heatmap: Heatmap::default(),
+            pauses: Pauses::default(),
+            cancel,
+        }
+    }
+
+    /// Serves `heatmap`, which the engine counts opportunities into (see
+    /// `crate::heatmap`).
+    pub fn with_heatmap(mut self, heatmap: Heatmap) -> Self {
+        self.heatmap = heatmap;
+        self
+    }
+
+    /// Changes `pauses`, which the tracker and execution follow (see
+    /// `crate::pauses`).
+    pub fn with_pauses(mut self, pauses: Pauses) -> Self {
+        self.pauses = pauses;
+        self
+    }
+
+    pub fn feeds(&self) -> MutexGuard<'_, Feeds> {
+        self.feeds.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn health(&self) -> MutexGuard<'_, FeedHealth> {
+        self.health.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Pause flag and threshold, for the execution loop.
+    pub fn execution_control(&self) -> watch::Receiver<ExecutionControl> {
+        self.execution_control.subscribe()
+    }
+
+    /// Hands [`Control::signal`]s to execution; only the first call counts.
+    pub fn take_signals(&self, signals: mpsc::Sender<Signal>) {
+        let _ = self.signals.set(signals);
+    }
+
+    /// Queues `signal` for execution, which decides whether it trades.
+    pub fn signal(&self, signal: Signal) -> Result<(), ControlError> {
+        if signal.buy == signal.sell {
+            return Err(ControlError::InvalidSignal(format!(
+                "buy and sell are both {}",
+                signal.buy
+            )));
+        }
+        let signals = self.signals.get().ok_or(ControlError::ExecutionOff)?;
+        signals.try_send(signal).map_err(|e| match e {
+            TrySendError::Full(_) => ControlError::SignalsBacklogged,
+            TrySendError::Closed(_) => ControlError::ExecutionOff,
+        })
+    }
+
+    pub fn status(&self) -> Status {
+        let control = *self.execution_control.borrow();
+        let venues = self
+            .feeds()
+            .venues()
+            .into_iter()
+            .map(|(exchange, symbol)| {
+                let quote = self.quotes.latest(exchange, symbol).load();
+                VenueStatus {
+                    exchange,
+                    symbol,
+                    bid: quote.as_ref().map(|q| q.top.bid),
+                    ask: quote.as_ref().map(|q| q.top.ask),
+                    polled: quote.as_ref().is_some_and(|q| q.polled),
+                }
+            })
+            .collect();
+        let mut health = self.health();
+        Status {
+            uptime_secs: self.started.elapsed().as_secs(),
+            execution_enabled: self.execution_enabled,
+            paused: control.paused,
+            alert_threshold_percent: *self.alert_percent.borrow(),
+            execution_threshold_percent: control.threshold_percent,
+            venues,
+            connections: health.connections.clone(),
+            circuit_breakers: health.active_trips(),
+            recording: Recordings::global().symbols(),
+            pauses: self.pauses.current(),
+        }
+    }
+
+    /// Orders placed by execution and the exposure they leave.
+    pub fn positions(&self) -> ExecutionState {
+        self.execution.borrow().clone()
+    }
+
+    /// Every quote the feeds publish from now on.
+    pub fn subscribe_quotes(&self) -> broadcast::Receiver<Arc<Quote>> {
+        self.quotes.subscribe()
+    }
+
+    /// Every opportunity the tracker finds from now on.
+    pub fn subscribe_opportunities(&self) -> broadcast::Receiver<Opportunity> {
+        self.tracker.subscribe_opportunities()
+    }
+
+    /// Execution's state, updated after every order.
+    pub fn subscribe_execution(&self) -> watch::Receiver<ExecutionState> {
+        self.execution.subscribe()
+    }
+
+    pub async fn recent_opportunities(&self) -> Result<Vec<Opportunity>, ControlError> {
+        self.tracker
+            .recent_opportunities()
+            .await
+            .ok_or(ControlError::TrackerGone)
+    }
+
+    /// Opportunities so far by hour of day and symbol.
+    pub fn heatmap(&self) -> Grid {
+        self.heatmap.grid()
+    }
+
+    /// Recent opportunities at or above the alert threshold, oldest first.
+    pub async fn recent_alerts(&self) -> Result<Vec<Opportunity>, ControlError> {
+        let threshold = *self.alert_percent.borrow();
+        let mut alerts = self.recent_opportunities().await?;
+        alerts.retain(|o| o.diff_percent >= threshold);
+        Ok(alerts)
+    }
+
+    /// Stops execution from placing new orders.
+    pub fn pause(&self) {
+        self.execution_control.send_modify(|c| c.paused = true);
+        println!("⏸️ Execution paused");
+    }
+
+    pub fn resume(&self) {
+        self.execution_control.send_modify(|c| c.paused = false);
+        println!("▶️ Execution resumed");
+    }
+
+    /// The exchanges and symbols paused on their own, shared with whoever
+    /// follows them.
+    pub fn pauses(&self) -> Pauses {
+        self.pauses.clone()
+    }
+
+    /// Pauses or resumes `target` alone and returns what is paused now. The
+    /// change goes to the audit log as coming `via` the given front end.
+    pub fn set_paused(&self, target: PauseTarget, paused: bool, via: &str) -> Paused {
+        if self.pauses.set(target, paused) {
+            if paused {
+                println!("⏸️ {} paused", target);
+            } else {
+                println!("▶️ {} resumed", target);
+            }
+        }
+        let current = self.pauses.current();
+        #[cfg(feature = "execution")]
+        {
+            let params = serde_json::json!({ "target": target.to_string(), "paused": paused });
+            let outcome = Ok(serde_json::to_value(&current).unwrap_or_default());
+            audit::record_change(via, "pause", &params, &outcome);
+        }
+        #[cfg(not(feature = "execution"))]
+        let _ = via;
+        current
+    }
+
+    /// Shuts the engine down, as Ctrl-C does.
+    pub fn kill(&self) {
+        println!("🛑 Kill requested, shutting down");
+        self.cancel.cancel();
+    }
+
+    pub async fn apply(&self, command: Command, via: &str) -> Result<(), ControlError> {
+        match command {
+            Command::Pause => self.pause(),
+            Command::Resume => self.resume(),
+            Command::Kill => self.kill(),
+            Command::Symbols {
+                exchange,
+                subscribe,
+                unsubscribe,
+            } => {
+                let subscribe: Vec<&str> = subscribe.iter().map(String::as_str).collect();
+                let unsubscribe: Vec<&str> = unsubscribe.iter().map(String::as_str).collect();
+                self.set_symbols(exchange, &subscribe, &unsubscribe)?;
+            }
+            Command::Threshold {
+                alert_percent,
+                execution_percent,
+            } => {
+                self.set_thresholds(alert_percent, execution_percent)
+                    .await?
+            }
+            Command::Recording { symbol, enabled } => {
+                self.set_recording(&symbol, enabled)?;
+            }
+            Command::Strategy {
+                name,
+                enabled,
+                threshold_percent,
+                quantity,
+            } => {
+                let update = StrategyUpdate {
+                    enabled,
+                    threshold_percent,
+                    quantity,
+                };
+                self.update_strategy(&name, &update, via)?;
+            }
+            Command::Venue {
+                exchange,
+                symbol,
+                paused,
+            } => {
+                let target = PauseTarget::named(exchange, symbol.as_deref())?;
+                self.set_paused(target, paused, via);
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts or stops recording every feed's raw frames for `symbol` (see
+    /// `crate::ws::tap::Recordings`) and returns the symbols now recorded.
+    pub fn set_recording(&self, symbol: &str, enabled: bool) -> Result<Vec<String>, ControlError> {
+        let recordings = Recordings::global();
+        if enabled {
+            recordings.start(symbol)?;
+        } else {
+            recordings.stop(symbol)?;
+        }
+        Ok(recordings.symbols())
+    }
+
+    /// Lets strategy `name` trade again or stops it, and/or changes its
+    /// threshold and quantity, without touching the others (see
+    /// `crate::portfolio`); returns its book. The change and its outcome go
+    /// to the audit log as coming `via` the given front end.
+    pub fn update_strategy(
+        &self,
+        name: &str,
+        update: &StrategyUpdate,
+        via: &str,
+    ) -> Result<StrategyBook, ControlError> {
+        let result = self.change_strategy(name, update);
+        #[cfg(feature = "execution")]
+        {
+            let params = serde_json::json!({ "name": name, "update": update });
+            let outcome = result
+                .as_ref()
+                .map(|book| serde_json::to_value(book).unwrap_or_default())
+                .map_err(ToString::to_string);
+            audit::record_change(via, "strategy", &params, &outcome);
+        }
+        #[cfg(not(feature = "execution"))]
+        let _ = via;
+        result
+    }
+
+    fn change_strategy(
+        &self,
+        name: &str,
+        update: &StrategyUpdate,
+    ) -> Result<StrategyBook, ControlError> {
+        if !self.execution_enabled {
+            return Err(ControlError::ExecutionOff);
+        }
+        if name.trim().is_empty() {
+            return Err(ControlError::InvalidStrategy(name.to_string()));
+        }
+        if let Some(percent) = update.threshold_percent.filter(|p| *p <= dec!(0)) {
+            return Err(ControlError::InvalidThreshold(percent));
+        }
+        if let Some(quantity) = update.quantity.filter(|q| *q <= dec!(0)) {
+            return Err(ControlError::InvalidQuantity(quantity));
+        }
+        let mut book = StrategyBook::default();
+        self.execution.send_modify(|state| {
+            let portfolio = &mut state.portfolio;
+            if let Some(enabled) = update.enabled {
+                portfolio.set_enabled(name, enabled);
+            }
+            if let Some(percent) = update.threshold_percent {
+                portfolio.set_threshold(name, percent);
+            }
+            if let Some(quantity) = update.quantity {
+                portfolio.set_quantity(name, quantity);
+            }
+            book = portfolio.get(name).cloned().unwrap_or_default();
+        });
+        if let Some(enabled) = update.enabled {
+            println!(
+                "{} Strategy {} {}",
+                if enabled { "▶️" } else { "⏸️" },
+                name,
+                if enabled { "enabled" } else { "disabled" }
+            );
+        }
+        if let Some(percent) = update.threshold_percent {
+            println!("🎚️ Strategy {} threshold set to {}%", name, percent);
+        }
+        if let Some(quantity) = update.quantity {
+            println!("🎚️ Strategy {} quantity set to {}", name, quantity);
+        }
+        Ok(book)
+    }
+
+    /// Changes the symbols fed from `exchange` and returns the new set.
+    pub fn set_symbols(
+        &self,
+        exchange: ExchangeId,
+        subscribe: &[&str],
+        unsubscribe: &[&str],
+    ) -> Result<Vec<&'static str>, ControlError> {
+        let mut feeds = self.feeds();
+        feeds.unsubscribe(exchange, unsubscribe)?;
+        feeds.subscribe(exchange, subscribe)?;
+        let symbols: Vec<_> = feeds
+            .venues()
+            .into_iter()
+            .filter(|(e, _)| *e == exchange)
+            .map(|(_, symbol)| symbol)
+            .collect();
+        println!("📡 {} symbols now: {}", exchange, symbols.join(", "));
+        Ok(symbols)
+    }
+
+    /// Changes the alert threshold and/or the execution edge, both in percent.
+    pub async fn set_thresholds(
+        &self,
+        alert_percent: Option<Decimal>,
+        execution_percent: Option<Decimal>,
+    ) -> Result<(), ControlError> {
+        for percent in alert_percent.iter().chain(&execution_percent) {
+            if *percent <= dec!(0) {
+                return Err(ControlError::InvalidThreshold(*percent));
+            }
+        }
+        if let Some(percent) = alert_percent {
+            self.tracker.set_alert_threshold(percent).await;
+            self.alert_percent.send_replace(percent);
+            println!("🎚️ Alert threshold set to {}%", percent);
+        }
+        if let Some(percent) = execution_percent {
+            self.execution_control
+                .send_modify(|c| c.threshold_percent = percent);
+            println!("🎚️ Execution threshold set to {}%", percent);
+        }
+        Ok(())
+    }
+}
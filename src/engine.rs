@@ -0,0 +1,1244 @@
+//! The live pipeline: one entrypoint for monitoring, alerting, spread logging
+//! and, optionally, execution.
+//!
+//! Feeds publish every quote on a [`QuoteBus`]. The [`MarketTracker`] follows
+//! it to compare spreads across exchanges, gate Telegram alerts and write the
+//! spread log. With `[engine.execution] enabled = true` in a build with the
+//! `execution` feature, an [`ArbitrageEngine`] follows the same bus and places
+//! orders; in inventory mode it trades spot on its own feeds instead.
+//! Connection events of every feed go to one monitor that escalates repeated
+//! failures and announces reconnects.
+//!
+//! Startup runs in order: storage (the spread log), monitoring (notifier,
+//! tracker, connection monitor), feeds (connected and delivering quotes on
+//! every venue, or `[engine] ready_timeout_secs` passed), then execution: the
+//! trading client connects only after the feeds are up, and the strategy is
+//! armed only once its symbol has fresh quotes from every exchange.
+//!
+//! Every task the engine spawns holds (a clone of) its [`CancellationToken`];
+//! [`Engine::shutdown`] (or Ctrl-C) stops them all, closing the exchange
+//! connections. With `[engine] state_file` set, the state a restart must not
+//! lose (see `crate::state`) is loaded in the storage phase and saved while
+//! running and on shutdown. With `[calendar]` set, the maintenance and
+//! listing schedule (see `crate::calendar`) is read in the storage phase too.
+
+use std::{collections::HashMap, path::PathBuf, time::Instant};
+
+use rust_decimal_macros::dec;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio_util::sync::CancellationToken;
+
+#[cfg(feature = "api")]
+use crate::api;
+use crate::bridge;
+#[cfg(feature = "grpc")]
+use crate::grpc;
+#[cfg(feature = "telegram")]
+use crate::notifications::{telegram::TelegramNotifier, telegram_commands};
+#[cfg(feature = "binance")]
+use crate::ws::binance_client_multiplex::MultiplexHandle;
+use crate::{
+    binance::ws_handler::{self, ConnectionEvent, EVENT_CHANNEL_CAPACITY},
+    calendar::Calendar,
+    config::{self, EngineConfig},
+    constants::{notifications as notif_const, symbols},
+    contracts::Contracts,
+    control::{Control, ExecutionControl, Feeds},
+    error::{Classify, Error, Severity},
+    events::EventLog,
+    failover::Failover,
+    fees::FeeSchedule,
+    fx::Fx,
+    gap_fill,
+    heatmap::{Grid, Heatmap},
+    listing_mode::ListingMode,
+    liveness::Liveness,
+    logger::CsvLogger,
+    models::{
+        ids::{ExchangeId, Symbol},
+        orderbook::{MarketTracker, TrackerHandle},
+    },
+    notifications::{
+        alert_gate::AlertGate,
+        email::EmailNotifier,
+        router::{self, AlertRouter},
+        telegram::Notification,
+    },
+    pauses::Pauses,
+    runtime,
+    session::{Session, SessionLog},
+    state::{self, EngineState, ExecutionState},
+    tca::Tca,
+    transfers::TransferStatus,
+    volatility::Volatility,
+    ws::{quote_bus::QuoteBus, tap::Recordings},
+};
+#[cfg(any(feature = "api", feature = "grpc"))]
+use crate::{error::ControlError, keys};
+#[cfg(feature = "metrics")]
+use crate::{limits, ws::backpressure};
+
+pub struct Engine {
+    quotes: QuoteBus,
+    events: broadcast::Sender<ConnectionEvent>,
+    tracker: TrackerHandle,
+    // Kept so the notifier lives as long as the engine; inventory mode's
+    // skew alerts go out on it too.
+    #[cfg_attr(not(feature = "execution"), allow(dead_code))]
+    telegram_tx: Option<mpsc::Sender<Notification>>,
+    cancel: CancellationToken,
+    state_file: Option<PathBuf>,
+    /// Execution's orders and exposure; carried over untouched when
+    /// execution is off.
+    execution: watch::Sender<ExecutionState>,
+    control: Control,
+    /// Maintenance windows and listings, with `[calendar]` set.
+    #[cfg_attr(not(feature = "execution"), allow(dead_code))]
+    calendar: Option<Calendar>,
+    /// Deposit and withdrawal status, with `[transfers]` enabled.
+    #[cfg_attr(not(feature = "execution"), allow(dead_code))]
+    transfers: Option<TransferStatus>,
+    /// Volatility regimes, with `[volatility]` enabled.
+    #[cfg_attr(not(feature = "execution"), allow(dead_code))]
+    volatility: Option<Volatility>,
+    /// REST probe results, with `[liveness]` enabled.
+    #[cfg_attr(not(feature = "execution"), allow(dead_code))]
+    liveness: Option<Liveness>,
+    /// Feeds polled over REST, with `[failover]` enabled.
+    #[cfg_attr(not(feature = "execution"), allow(dead_code))]
+    failover: Option<Failover>,
+    /// Book and trade readings for thin pairs, with `[listing_mode]` enabled.
+    #[cfg_attr(not(feature = "execution"), allow(dead_code))]
+    listing_mode: Option<ListingMode>,
+    /// Quote currency rates, with `[fx]` enabled.
+    #[cfg_attr(not(feature = "execution"), allow(dead_code))]
+    fx: Option<Fx>,
+    /// What this run did, printed on shutdown.
+    session: Session,
+    session_log: Option<SessionLog>,
+    /// The costs of the trades executed this run.
+    tca: Tca,
+    /// Opportunities by hour of day and symbol, kept in `[engine]
+    /// heatmap_file` if set.
+    heatmap: Heatmap,
+    heatmap_file: Option<PathBuf>,
+}
+
+impl Engine {
+    /// Brings the pipeline up phase by phase (see the module docs). Must be
+    /// called inside a Tokio runtime.
+    pub async fn start(config: &EngineConfig) -> Result<Self, Error> {
+        // ── 1. Storage ───────────────────────────────────────────────────
+        // Opened before anything connects, so a bad path fails fast.
+        startup_phase(1, "storage");
+        let spread_log = match &config.spread_log {
+            Some(path) => {
+                let logger = CsvLogger::new(&path.to_string_lossy())?;
+                println!("📝 Logging spreads to {}", path.display());
+                Some(logger)
+            }
+            None => None,
+        };
+        let saved = match &config.state_file {
+            Some(path) => restore_state(path)?,
+            None => EngineState::default(),
+        };
+        for trip in saved.circuit_breakers {
+            ws_handler::restore_circuit_breaker(trip);
+        }
+        open_audit_log(config)?;
+        let event_log = match &config.event_log {
+            Some(path) => {
+                let log = EventLog::open(path)?;
+                println!("📝 Logging connection events to {}", path.display());
+                Some(log)
+            }
+            None => None,
+        };
+        let session_log = match &config.session_log {
+            Some(path) => {
+                let log = SessionLog::open(path)?;
+                println!("📝 Logging session summaries to {}", path.display());
+                Some(log)
+            }
+            None => None,
+        };
+        let tca = match &config.tca_log {
+            Some(path) => {
+                let tca = Tca::default().with_log(path)?;
+                println!("📝 Logging trade costs to {}", path.display());
+                tca
+            }
+            None => Tca::default(),
+        };
+        let heatmap = match &config.heatmap_file {
+            Some(path) => {
+                let grid = Grid::load(path)?.unwrap_or_default();
+                println!("🔥 Keeping the opportunity heatmap in {}", path.display());
+                Heatmap::new(grid)
+            }
+            None => Heatmap::default(),
+        };
+        let calendar = Calendar::load(&config::get().calendar).await?;
+
+        // ── 2. Monitoring ────────────────────────────────────────────────
+        startup_phase(2, "monitoring");
+        #[cfg(feature = "telegram")]
+        let telegram_tx = TelegramNotifier::spawn();
+        #[cfg(not(feature = "telegram"))]
+        let telegram_tx = {
+            eprintln!("⚠️ This build has no `telegram` feature; alerts are not sent");
+            None
+        };
+        // Email, if configured, sits next to Telegram behind the router.
+        let notifications = &config::get().notifications;
+        let telegram_tx = router::spawn(telegram_tx, EmailNotifier::spawn(&notifications.email));
+
+        // Alert gate (dedup + cooldown), routing by `[[notifications.route]]`.
+        // Alerts start at the lowest routed spread.
+        let alert_router = AlertRouter::new(&notifications.routes);
+        let alert_threshold = alert_router.floor().unwrap_or(notif_const::DIFF_THRESHOLD);
+        let digest = alert_router.uses_digest();
+        let mut alert_gate = AlertGate::new(
+            alert_threshold,
+            notif_const::RE_ALERT_DELTA,
+            notif_const::COOLDOWN_SECS,
+        )
+        .with_router(alert_router);
+        alert_gate.restore(saved.alerts);
+
+        // Market tracker. The comparator threshold is the alert threshold / 100 because
+        // the comparator works with a raw ratio multiplied by 100 internally.
+        // Runs as its own task; feeds hand it quotes without waiting on a lock.
+        let mut tracker =
+            MarketTracker::new(alert_threshold / dec!(100), telegram_tx.clone(), alert_gate)
+                .with_evaluation(config.evaluation);
+        if let Some(logger) = spread_log {
+            tracker = tracker.with_spread_log(logger);
+        }
+        if let Some(calendar) = &calendar {
+            tracker = tracker.with_calendar(calendar.clone());
+        }
+        let transfers = &config::get().transfers;
+        let transfer_status = transfers.enabled.then(TransferStatus::default);
+        if let Some(status) = &transfer_status {
+            tracker = tracker.with_transfers(status.clone(), transfers.block);
+        }
+        let fx = config::get().fx.enabled.then(|| Fx::new(&config::get().fx));
+        if let Some(fx) = &fx {
+            tracker = tracker.with_fx(fx.clone());
+        }
+        let volatility = &config::get().volatility;
+        let volatility = volatility.enabled.then(|| Volatility::new(volatility));
+        if let Some(volatility) = &volatility {
+            tracker = tracker.with_volatility(volatility.clone());
+        }
+        let liveness = &config::get().liveness;
+        let liveness = liveness.enabled.then(|| Liveness::new(liveness.failures));
+        let failover = config::get().failover.enabled.then(Failover::new);
+        let listing_mode = &config::get().listing_mode;
+        let listing_mode = listing_mode.enabled.then(|| ListingMode::new(listing_mode));
+        if let Some(listing_mode) = &listing_mode {
+            tracker = tracker.with_listing_mode(listing_mode.clone());
+        }
+        let pauses = Pauses::new(&config::get().pauses);
+        tracker = tracker.with_pauses(pauses.clone());
+
+        // Quote bus. Feeds publish every quote here; the tracker is one subscriber, and
+        // strategies, recorders or dashboards can subscribe independently.
+        // Tracker and feeds live on the hot-path runtime (see `crate::runtime`).
+        let cancel = CancellationToken::new();
+        let quotes = QuoteBus::default();
+        let tracker = {
+            let hot_path = runtime::handle();
+            let _hot = hot_path.enter();
+            let tracker = tracker.spawn();
+            tracker.follow(&quotes, cancel.clone());
+            tracker
+        };
+        spawn_alert_reset(tracker.clone(), cancel.clone());
+        heatmap.follow(tracker.subscribe_opportunities(), cancel.clone());
+        if digest {
+            spawn_digest(
+                tracker.clone(),
+                notifications.digest_interval(),
+                cancel.clone(),
+            );
+        }
+        if let Some(calendar) = &calendar {
+            calendar.spawn_refresh(&config::get().calendar, cancel.clone());
+        }
+        if let Some(status) = &transfer_status {
+            spawn_transfer_status(status, cancel.clone());
+        }
+        if let Some(fx) = &fx {
+            fx.spawn_refresh(telegram_tx.clone(), cancel.clone());
+        }
+        if let Some(liveness) = &liveness {
+            liveness.spawn_probes(&config::get().liveness, telegram_tx.clone(), cancel.clone());
+        }
+        if let Some(listing_mode) = &listing_mode {
+            listing_mode.spawn_probes(cancel.clone());
+        }
+
+        // Connection events
+        let (events, events_rx) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+
+        let session = Session::start(
+            &quotes,
+            tracker.subscribe_opportunities(),
+            events.subscribe(),
+            &saved.execution,
+            config.execution.fee_percent,
+            cancel.clone(),
+        )
+        .with_contracts(Contracts::new(&config.execution))
+        .with_fees(FeeSchedule::from_config(&config.execution));
+        let execution = watch::Sender::new(saved.execution);
+        let control = Control::new(
+            quotes.clone(),
+            tracker.clone(),
+            Feeds::new(quotes.clone(), events.clone(), cancel.clone()),
+            execution.clone(),
+            ExecutionControl {
+                paused: false,
+                threshold_percent: config.execution.threshold_percent,
+            },
+            config.execution.enabled && cfg!(feature = "execution"),
+            alert_threshold,
+            cancel.clone(),
+        )
+        .with_heatmap(heatmap.clone())
+        .with_pauses(pauses);
+        spawn_connection_monitor(
+            events_rx,
+            telegram_tx.clone(),
+            control.clone(),
+            event_log,
+            cancel.clone(),
+        );
+        if config::get().feeds.gap_fill {
+            gap_fill::spawn(
+                quotes.clone(),
+                control.clone(),
+                events.subscribe(),
+                cancel.clone(),
+            );
+        }
+        if let Some(failover) = &failover {
+            failover.spawn(
+                &config::get().failover,
+                quotes.clone(),
+                control.clone(),
+                telegram_tx.clone(),
+                cancel.clone(),
+            );
+        }
+
+        let mut engine = Self {
+            quotes,
+            events,
+            tracker,
+            telegram_tx,
+            cancel,
+            state_file: config.state_file.clone(),
+            execution,
+            control,
+            calendar,
+            transfers: transfer_status,
+            volatility,
+            liveness,
+            failover,
+            listing_mode,
+            fx,
+            session,
+            session_log,
+            tca,
+            heatmap,
+            heatmap_file: config.heatmap_file.clone(),
+        };
+
+        // ── 3. Feeds ─────────────────────────────────────────────────────
+        startup_phase(3, "feeds");
+        for symbol in &config::get().recording.symbols {
+            Recordings::global().start(symbol)?;
+        }
+        engine.start_feeds();
+        engine.wait_for_feeds(config.ready_timeout()).await;
+
+        // ── 4. Execution ─────────────────────────────────────────────────
+        if config.execution.enabled {
+            startup_phase(4, "execution");
+            engine.start_execution(config).await?;
+        }
+
+        engine.start_api().await?;
+        engine.start_grpc().await?;
+        engine.start_redis()?;
+        engine.start_kafka();
+        engine.start_nats().await?;
+        engine.start_mqtt();
+        engine.start_influx();
+        engine.start_telegram_commands();
+        Ok(engine)
+    }
+
+    pub fn quotes(&self) -> &QuoteBus {
+        &self.quotes
+    }
+
+    /// The combined Binance futures stream, e.g. to check pending requests.
+    #[cfg(feature = "binance")]
+    pub fn binance_futures(&self) -> Option<MultiplexHandle> {
+        self.control.feeds().binance_futures().cloned()
+    }
+
+    /// Status, positions and runtime changes (pause, thresholds, symbols).
+    pub fn control(&self) -> &Control {
+        &self.control
+    }
+
+    /// Stops every task the engine started; [`Engine::run`] returns.
+    pub fn shutdown(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Token cancelled on [`Engine::shutdown`], for tasks started alongside
+    /// the engine.
+    pub fn cancellation(&self) -> CancellationToken {
+        self.cancel.child_token()
+    }
+
+    /// Connection events of every feed.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events.subscribe()
+    }
+
+    /// Trades `config.execution.symbol` on every exchange with an order client,
+    /// fed from the same quotes the tracker sees.
+    #[cfg(feature = "execution")]
+    async fn start_execution(&mut self, config: &EngineConfig) -> Result<(), Error> {
+        use std::sync::Arc;
+
+        use crate::{
+            accounts::{Account, Accounts},
+            binance::{auth::KeyVars, binance_exchange::BinanceExchange, permissions},
+            config::{ExecutionConfig, ExecutionMode},
+            control::SIGNAL_QUEUE,
+            expiry::Expiry,
+            funding::Funding,
+            latency::Latency,
+            rebalance::Planner,
+            recovery,
+            retry::RetryPolicy,
+            topup::{Member, TopUps},
+            ws::exchanges::{ArbitrageEngine, Exchange},
+        };
+
+        /// The order client of the Binance account keyed by `key_vars`.
+        async fn binance(
+            execution: &ExecutionConfig,
+            key_vars: KeyVars,
+            fx: Option<&Fx>,
+        ) -> Result<BinanceExchange, Error> {
+            let auth = key_vars.auth()?;
+            if execution.audit_key {
+                permissions::audit(&auth, execution).await?;
+            }
+            let exchange = BinanceExchange::new(
+                execution.symbol_on(ExchangeId::Binance),
+                auth.api_key().clone(),
+                auth.api_secret().clone(),
+            )
+            .await?
+            .with_time_in_force(execution.time_in_force.for_mode(execution.mode).into())
+            .with_retry(RetryPolicy::from(&execution.retry))
+            .with_rate_limits(&execution.rate_limits)
+            .with_key_vars(key_vars);
+            Ok(match fx {
+                Some(fx) => exchange.with_fx(fx.clone()),
+                None => exchange,
+            })
+        }
+
+        let execution = &config.execution;
+        let binance: Arc<dyn Exchange> = if execution.accounts.is_empty() {
+            Arc::new(binance(execution, KeyVars::default(), self.fx.as_ref()).await?)
+        } else {
+            let mut accounts = Vec::new();
+            let mut members = Vec::new();
+            for account in &execution.accounts {
+                println!("🔑 Binance account {}", account.name);
+                let key_vars = KeyVars::new(&account.api_key_var, &account.secret_key_var);
+                let exchange: Arc<dyn Exchange> =
+                    Arc::new(binance(execution, key_vars, self.fx.as_ref()).await?);
+                members.push(Member {
+                    name: account.name.clone(),
+                    sub_account: account.sub_account.clone(),
+                    exchange: exchange.clone(),
+                });
+                accounts.push(Account {
+                    name: account.name.clone(),
+                    exchange,
+                    symbols: account.symbols.clone(),
+                });
+            }
+            if execution.topup.enabled {
+                TopUps::new(ExchangeId::Binance, &execution.topup).spawn(
+                    members,
+                    self.telegram_tx.clone(),
+                    self.cancel.clone(),
+                );
+            }
+            Arc::new(Accounts::new(
+                execution.symbol_on(ExchangeId::Binance),
+                execution.routing,
+                accounts,
+            ))
+        };
+        // Bybit has no order client yet, so only pairs of exchanges that both
+        // have one can actually trade; the rest fail at execution time.
+        let exchanges = vec![binance];
+        if exchanges.len() < 2 {
+            eprintln!(
+                "⚠️ Execution needs an order client on both legs; only {} has one",
+                exchanges
+                    .iter()
+                    .map(|e| e.id().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        if execution.recover && execution.mode == ExecutionMode::Futures {
+            for outcome in recovery::recover(&exchanges, &self.execution).await {
+                match outcome {
+                    Ok(recovered) if recovered.exposure != recovered.tracked => {
+                        println!("⚠️ The saved state was off; recovered {}", recovered)
+                    }
+                    Ok(recovered) => println!("♻️ Recovered {}", recovered),
+                    Err((exchange, e)) => eprintln!(
+                        "⚠️ Couldn't recover {} orders and position, keeping the saved state: {}",
+                        exchange, e
+                    ),
+                }
+            }
+            self.save_state().await;
+        }
+        if execution.expiry.enabled {
+            Expiry::new(
+                &execution.expiry,
+                exchanges.clone(),
+                &self.quotes,
+                |exchange| execution.symbol_on(exchange),
+                self.execution.clone(),
+            )
+            .spawn(self.cancel.clone());
+            println!(
+                "⌛ Orders resting over {}s are expired ({:?})",
+                execution.expiry.ttl_secs, execution.expiry.action
+            );
+        }
+
+        let hot_path = runtime::handle();
+        let _hot = hot_path.enter();
+        let threshold = execution.threshold_percent / dec!(100);
+        let mut arbitrage = match execution.mode {
+            ExecutionMode::Futures => ArbitrageEngine::from_bus(
+                exchanges,
+                &self.quotes,
+                |exchange| execution.symbol_on(exchange),
+                threshold,
+                execution.quantity,
+            ),
+            // The bus carries futures quotes; spot legs bring their own feeds.
+            ExecutionMode::Inventory => {
+                let inventory = &execution.inventory;
+                self.execution.send_modify(|state| {
+                    for exchange in state.seed_inventory(inventory.balances()) {
+                        println!("📦 Inventory on {} set from the config", exchange);
+                    }
+                    for (exchange, held) in &state.inventory {
+                        println!(
+                            "📦 {} holds {} base, {} quote",
+                            exchange, held.balance.base, held.balance.quote
+                        );
+                    }
+                });
+                let arbitrage = ArbitrageEngine::new(exchanges, threshold, execution.quantity)
+                    .with_inventory(inventory.max_skew_percent, self.telegram_tx.clone());
+                if inventory.rebalance.enabled {
+                    arbitrage.with_rebalancing(Planner::new(
+                        &inventory.rebalance,
+                        self.transfers.clone(),
+                    ))
+                } else {
+                    arbitrage
+                }
+            }
+        }
+        .with_state(self.execution.clone())
+        .with_control(self.control.execution_control())
+        .with_rollback(execution.rollback)
+        .with_latency(Latency::global().clone(), execution.latency.clone())
+        .with_recheck(execution.recheck_floor_percent, execution.fee_percent)
+        .with_fees(FeeSchedule::from_config(execution))
+        .with_contracts(Contracts::new(execution))
+        .with_strategies(execution.strategies.clone())
+        .with_pauses(self.control.pauses());
+        if let Some(calendar) = &self.calendar {
+            arbitrage = arbitrage.with_calendar(calendar.clone());
+        }
+        if let Some(volatility) = &self.volatility {
+            arbitrage = arbitrage.with_volatility(volatility.clone());
+        }
+        if let Some(liveness) = &self.liveness {
+            arbitrage = arbitrage.with_liveness(liveness.clone());
+        }
+        if let Some(failover) = &self.failover {
+            arbitrage = arbitrage.with_failover(failover.clone());
+        }
+        if let Some(listing_mode) = &self.listing_mode {
+            arbitrage = arbitrage.with_listing_mode(listing_mode.clone());
+        }
+        // Spot legs pay no funding.
+        if execution.funding.enabled && execution.mode == ExecutionMode::Futures {
+            let funding = Funding::new(&execution.funding);
+            funding.spawn_probes(
+                [ExchangeId::Binance, ExchangeId::Bybit]
+                    .into_iter()
+                    .map(|exchange| {
+                        let symbol = execution.symbol_on(exchange).to_uppercase();
+                        (exchange, Symbol::intern(&symbol))
+                    })
+                    .collect(),
+                self.cancel.clone(),
+            );
+            arbitrage = arbitrage.with_funding(funding);
+        }
+        arbitrage = arbitrage.with_tca(self.tca.clone(), execution.tca_timeout());
+        let (signals, signals_rx) = tokio::sync::mpsc::channel(SIGNAL_QUEUE);
+        arbitrage = arbitrage.with_signals(signals_rx);
+        self.control.take_signals(signals);
+        println!(
+            "💸 Execution enabled for {} ({} per leg, edge ≥ {}%, {:?} mode)",
+            execution.symbol, execution.quantity, execution.threshold_percent, execution.mode
+        );
+
+        // Armed only once every exchange quoting the symbol has fresh data,
+        // so the first comparison never runs against a missing leg. Spot
+        // feeds aren't on the bus; the engine waits for them by itself.
+        let venues: Vec<_> = match execution.mode {
+            ExecutionMode::Futures => self
+                .control
+                .feeds()
+                .venues()
+                .into_iter()
+                .filter(|(exchange, symbol)| {
+                    symbol.eq_ignore_ascii_case(execution.symbol_on(*exchange))
+                })
+                .collect(),
+            ExecutionMode::Inventory => Vec::new(),
+        };
+        if execution.mode == ExecutionMode::Futures && venues.len() < 2 {
+            eprintln!(
+                "⚠️ {} is fed from {} exchange(s); execution needs quotes from two",
+                execution.symbol,
+                venues.len()
+            );
+        }
+        let quotes = self.quotes.clone();
+        let retry = config.ready_timeout();
+        let cancel = self.cancel.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = async {
+                    loop {
+                        let silent = quotes.wait_for_quotes(&venues, retry).await;
+                        if silent.is_empty() {
+                            break;
+                        }
+                        eprintln!("⏳ Execution waiting for quotes from {}", describe(&silent));
+                    }
+                    println!("💸 Execution armed");
+                    arbitrage.run().await
+                } => {}
+                _ = cancel.cancelled() => {}
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(not(feature = "execution"))]
+    async fn start_execution(&mut self, _config: &EngineConfig) -> Result<(), Error> {
+        eprintln!(
+            "⚠️ [engine.execution] is enabled, but this build has no `execution` feature; monitoring only"
+        );
+        Ok(())
+    }
+
+    /// Serves the control API if `[api] listen` is set.
+    #[cfg(feature = "api")]
+    async fn start_api(&self) -> Result<(), Error> {
+        let Some(addr) = config::get().api.listen else {
+            return Ok(());
+        };
+        let token = keys::var("CONTROL_API_TOKEN")
+            .filter(|t| !t.is_empty())
+            .ok_or(ControlError::MissingToken("CONTROL_API_TOKEN"))?;
+        let tokens = api::Tokens {
+            admin: token,
+            read: keys::var("CONTROL_API_READ_TOKEN").filter(|t| !t.is_empty()),
+            webhook: keys::var("WEBHOOK_TOKEN").filter(|t| !t.is_empty()),
+        };
+        api::serve(addr, tokens, self.control.clone(), self.cancel.clone()).await?;
+        let api_config = &config::get().api;
+        println!(
+            "🌐 Control API listening on {}://{}{}",
+            if api_config.tls_cert.is_some() {
+                "https"
+            } else {
+                "http"
+            },
+            addr,
+            if api_config.client_ca.is_some() {
+                " (client certificates required)"
+            } else {
+                ""
+            }
+        );
+        Ok(())
+    }
+
+    #[cfg(not(feature = "api"))]
+    async fn start_api(&self) -> Result<(), Error> {
+        if config::get().api.listen.is_some() {
+            eprintln!("⚠️ [api] listen is set, but this build has no `api` feature");
+        }
+        Ok(())
+    }
+
+    /// Serves the gRPC streams if `[grpc] listen` is set.
+    #[cfg(feature = "grpc")]
+    async fn start_grpc(&self) -> Result<(), Error> {
+        let Some(addr) = config::get().grpc.listen else {
+            return Ok(());
+        };
+        let token = keys::var("GRPC_TOKEN")
+            .filter(|t| !t.is_empty())
+            .ok_or(ControlError::MissingToken("GRPC_TOKEN"))?;
+        grpc::serve(addr, token, self.control.clone(), self.cancel.clone()).await?;
+        println!("📡 gRPC streams listening on {}", addr);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    async fn start_grpc(&self) -> Result<(), Error> {
+        if config::get().grpc.listen.is_some() {
+            eprintln!("⚠️ [grpc] listen is set, but this build has no `grpc` feature");
+        }
+        Ok(())
+    }
+
+    /// Bridges to Redis pub/sub if `[redis] url` is set. Redis being down
+    /// doesn't hold up startup; the bridge keeps reconnecting.
+    #[cfg(feature = "redis")]
+    fn start_redis(&self) -> Result<(), Error> {
+        let config = &config::get().redis;
+        bridge::redis::spawn(config, self.control.clone(), self.cancel.clone())?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "redis"))]
+    fn start_redis(&self) -> Result<(), Error> {
+        if config::get().redis.url.is_some() {
+            eprintln!("⚠️ [redis] url is set, but this build has no `redis` feature");
+        }
+        Ok(())
+    }
+
+    /// Mirrors events to Kafka if `[kafka] brokers` is set. Like Redis, the
+    /// brokers being down doesn't hold up startup.
+    #[cfg(feature = "kafka")]
+    fn start_kafka(&self) {
+        bridge::kafka::spawn(
+            &config::get().kafka,
+            self.control.clone(),
+            self.subscribe_events(),
+            self.cancel.clone(),
+        );
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    fn start_kafka(&self) {
+        if !config::get().kafka.brokers.is_empty() {
+            eprintln!("⚠️ [kafka] brokers is set, but this build has no `kafka` feature");
+        }
+    }
+
+    /// Mirrors events to NATS if `[nats] url` is set.
+    #[cfg(feature = "nats")]
+    async fn start_nats(&self) -> Result<(), Error> {
+        bridge::nats::spawn(
+            &config::get().nats,
+            self.control.clone(),
+            self.subscribe_events(),
+            self.cancel.clone(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "nats"))]
+    async fn start_nats(&self) -> Result<(), Error> {
+        if config::get().nats.url.is_some() {
+            eprintln!("⚠️ [nats] url is set, but this build has no `nats` feature");
+        }
+        Ok(())
+    }
+
+    /// Pushes quotes and spreads to InfluxDB if `[influx] url` is set.
+    fn start_influx(&self) {
+        bridge::influx::spawn(
+            &config::get().influx,
+            self.control.clone(),
+            self.cancellation(),
+        );
+    }
+
+    /// Publishes alerts and stats over MQTT if `[mqtt] host` is set.
+    #[cfg(feature = "mqtt")]
+    fn start_mqtt(&self) {
+        bridge::mqtt::spawn(
+            &config::get().mqtt,
+            self.control.clone(),
+            self.cancel.clone(),
+        );
+    }
+
+    #[cfg(not(feature = "mqtt"))]
+    fn start_mqtt(&self) {
+        if config::get().mqtt.host.is_some() {
+            eprintln!("⚠️ [mqtt] host is set, but this build has no `mqtt` feature");
+        }
+    }
+
+    /// Takes strategy commands from the Telegram chat if
+    /// `[notifications.telegram] commands` is on.
+    #[cfg(feature = "telegram")]
+    fn start_telegram_commands(&self) {
+        telegram_commands::spawn(
+            &config::get().notifications.telegram,
+            self.control.clone(),
+            self.cancel.clone(),
+        );
+    }
+
+    #[cfg(not(feature = "telegram"))]
+    fn start_telegram_commands(&self) {
+        if config::get().notifications.telegram.commands {
+            eprintln!(
+                "⚠️ [notifications.telegram] commands is on, but this build has no `telegram` feature"
+            );
+        }
+    }
+
+    /// Waits until every feed has delivered a quote, or `timeout` passes.
+    /// Silent venues are reported but don't block startup.
+    async fn wait_for_feeds(&self, timeout: std::time::Duration) {
+        let venues = self.control.feeds().venues();
+        let silent = self.quotes.wait_for_quotes(&venues, timeout).await;
+        if silent.is_empty() {
+            println!("✅ All {} venues are delivering quotes", venues.len());
+        } else {
+            eprintln!(
+                "⚠️ No quotes after {:?} from {}; carrying on",
+                timeout,
+                describe(&silent)
+            );
+        }
+    }
+
+    fn start_feeds(&self) {
+        // --- BYBIT SPOT (DISABLED) ---
+        // for symbol in ["WLFIUSDT", "ETHUSDT", "BTCUSDT"] {
+        //     let quotes = self.quotes.clone();
+        //     let events = self.events.clone();
+        //     let cancel = self.cancel.clone();
+        //     tokio::spawn(async move {
+        //         run_orderbook_stream_bybit(symbol, quotes, urls::BYBIT_URL_SPOT, events, cancel).await;
+        //     });
+        // }
+
+        // --- BINANCE SPOT (DISABLED) ---
+        // Two connections (ports 9443 and 443); first copy of each update wins.
+        // for symbol in ["wlfiusdt", "ethusdt", "btcusdt"] {
+        //     let quotes = self.quotes.clone();
+        //     let events = self.events.clone();
+        //     let cancel = self.cancel.clone();
+        //     tokio::spawn(async move {
+        //         binance_client::run_orderbook_stream_binance_redundant(
+        //             symbol,
+        //             quotes,
+        //             &[urls::BINANCE_URL_SPOT, urls::BINANCE_URL_SPOT_ALT],
+        //             events,
+        //             cancel,
+        //         )
+        //         .await;
+        //     });
+        // }
+
+        // --- FUTURES ---
+        let mut feeds = self.control.feeds();
+        for (exchange, symbols) in [
+            (ExchangeId::Bybit, symbols::BYBIT_FUTURES),
+            (ExchangeId::Binance, symbols::BINANCE_FUTURES),
+        ] {
+            if let Err(e) = feeds.subscribe(exchange, symbols) {
+                eprintln!("⚠️ {}; not scanning {}", e, symbols.join(", "));
+            }
+        }
+
+        let venues = feeds.venues();
+        println!(
+            "--- Scanning started for: {} (Futures) ---",
+            venues
+                .iter()
+                .map(|(exchange, symbol)| format!("{} {}", exchange, symbol))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    /// Keeps the pipeline alive, printing a heartbeat and queue stats every
+    /// minute and the capped structures' sizes and trade costs (see
+    /// `crate::tca`) every `[limits] report_interval_secs`, until
+    /// [`Engine::shutdown`] or Ctrl-C. Stats and sizes are reported only in
+    /// builds with the `metrics` feature.
+    ///
+    /// The runtime state is saved with every heartbeat, after every order and
+    /// once more on the way out.
+    pub async fn run(self) {
+        let mut heartbeat = tokio::time::interval(std::time::Duration::from_secs(60));
+        let mut sizes = tokio::time::interval(config::get().limits.report_interval());
+        heartbeat.tick().await; // first ticks fire immediately — skip them
+        sizes.tick().await;
+        let mut orders = self.execution.subscribe();
+        loop {
+            tokio::select! {
+                _ = heartbeat.tick() => {}
+                _ = sizes.tick() => {
+                    report_sizes();
+                    self.report_tca();
+                    continue;
+                }
+                // `self` holds the sender, so this never fails.
+                _ = orders.changed() => {
+                    self.save_state().await;
+                    continue;
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    println!("🛑 Ctrl-C received, shutting down");
+                    self.shutdown();
+                    break;
+                }
+                _ = self.cancel.cancelled() => break,
+            }
+            println!("--- Scanning active: {} ---", chrono::Local::now());
+            report_queues();
+            self.save_state().await;
+            self.save_heatmap();
+        }
+        self.save_state().await;
+        self.save_heatmap();
+        self.report_session();
+    }
+
+    /// Prints the average costs of the trades executed so far, if any.
+    fn report_tca(&self) {
+        let stats = self.tca.stats();
+        if stats.trades > 0 {
+            println!("{}", stats);
+        }
+    }
+
+    /// Prints what this run did and appends it to `[engine] session_log`.
+    fn report_session(&self) {
+        let summary = self.session.summary(&self.execution.borrow());
+        println!("{}", summary);
+        if let Some(log) = &self.session_log {
+            if let Err(e) = log.append(&summary) {
+                eprintln!("❌ Failed to log the session summary: {}", e);
+            }
+        }
+    }
+
+    /// Writes the opportunity heatmap to `[engine] heatmap_file`, if set.
+    fn save_heatmap(&self) {
+        if let Some(path) = &self.heatmap_file {
+            if let Err(e) = self.heatmap.save(path) {
+                eprintln!("❌ Failed to save the heatmap: {}", e);
+            }
+        }
+    }
+
+    /// Writes the runtime state to `[engine] state_file`, if set.
+    async fn save_state(&self) {
+        let Some(path) = &self.state_file else {
+            return;
+        };
+        let now = state::now_ms();
+        let circuit_breakers = self.control.health().active_trips();
+        let execution = self.execution.borrow().clone();
+        let state = EngineState {
+            saved_at_ms: now,
+            execution,
+            // The tracker outlives the engine's tasks, so this only fails if
+            // it panicked; the gate then starts afresh next time.
+            alerts: self.tracker.alert_state().await.unwrap_or_default(),
+            circuit_breakers,
+        };
+        if let Err(e) = state.save(path) {
+            eprintln!("❌ Failed to save state: {}", e);
+        }
+    }
+}
+
+/// Loads the state saved by a previous run, if any.
+#[cfg(feature = "execution")]
+fn open_audit_log(config: &EngineConfig) -> Result<(), Error> {
+    if let Some(path) = &config.audit_log {
+        crate::audit::open(path)?;
+        println!("🧾 Recording order actions to {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "execution"))]
+fn open_audit_log(config: &EngineConfig) -> Result<(), Error> {
+    if config.audit_log.is_some() {
+        eprintln!("⚠️ [engine] audit_log is set, but this build has no `execution` feature");
+    }
+    Ok(())
+}
+
+/// Keeps `status` current from the exchanges' asset endpoints.
+#[cfg(feature = "execution")]
+fn spawn_transfer_status(status: &TransferStatus, cancel: CancellationToken) {
+    let config = &config::get().transfers;
+    println!(
+        "🚚 Checking deposit and withdrawal status every {}s",
+        config.refresh_secs
+    );
+    status.spawn_refresh(config, cancel);
+}
+
+#[cfg(not(feature = "execution"))]
+fn spawn_transfer_status(_status: &TransferStatus, _cancel: CancellationToken) {
+    eprintln!(
+        "⚠️ [transfers] is enabled, but this build has no `execution` feature to sign its requests"
+    );
+}
+
+fn restore_state(path: &std::path::Path) -> Result<EngineState, Error> {
+    let Some(saved) = EngineState::load(path)? else {
+        println!("💾 No saved state at {}; starting fresh", path.display());
+        return Ok(EngineState::default());
+    };
+    let saved_at = chrono::DateTime::from_timestamp_millis(saved.saved_at_ms)
+        .map(|t| t.with_timezone(&chrono::Local).to_string())
+        .unwrap_or_default();
+    println!(
+        "💾 Restored state from {} (saved {})",
+        path.display(),
+        saved_at
+    );
+    let execution = &saved.execution;
+    if execution.is_exposed() {
+        eprintln!(
+            "⚠️ Open exposure from the previous run: {}",
+            execution
+                .exposure
+                .iter()
+                .filter(|(_, quantity)| !quantity.is_zero())
+                .map(|(exchange, quantity)| format!("{} {}", exchange, quantity))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    for leg in execution.orders.iter().filter(|leg| leg.order_id.is_none()) {
+        eprintln!(
+            "⚠️ Unplaced {:?} leg on {} {} from the previous run: {}",
+            leg.side,
+            leg.exchange,
+            leg.symbol,
+            leg.error.as_deref().unwrap_or("unknown error")
+        );
+    }
+    Ok(saved)
+}
+
+/// Prints the depth of every queue that has seen traffic (see
+/// `crate::ws::backpressure`).
+#[cfg(feature = "metrics")]
+fn report_queues() {
+    for q in backpressure::queue_stats() {
+        if q.high_water == 0 && q.coalesced == 0 {
+            continue;
+        }
+        println!(
+            "📊 queue {} [{:?}]: depth {}{} (high-water {}, coalesced {})",
+            q.name,
+            q.policy,
+            q.depth,
+            q.capacity.map(|c| format!("/{}", c)).unwrap_or_default(),
+            q.high_water,
+            q.coalesced
+        );
+    }
+}
+
+/// Prints the size of every capped map and buffer (see `crate::limits`).
+#[cfg(feature = "metrics")]
+fn report_sizes() {
+    for s in limits::size_stats() {
+        println!(
+            "🧮 {}: {}/{} (high-water {}, pruned {})",
+            s.name, s.size, s.cap, s.high_water, s.pruned
+        );
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+fn report_queues() {}
+
+#[cfg(not(feature = "metrics"))]
+fn report_sizes() {}
+
+/// `bybit BTCUSDT, binance ETHUSDT`
+fn describe(venues: &[(ExchangeId, Symbol)]) -> String {
+    venues
+        .iter()
+        .map(|(exchange, symbol)| format!("{} {}", exchange, symbol))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn startup_phase(n: u8, name: &str) {
+    println!("🚦 Startup {}/4: {}", n, name);
+}
+
+/// Wipes the alert gate's state every `STATE_RESET_SECS`.
+fn spawn_alert_reset(tracker: TrackerHandle, cancel: CancellationToken) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+            notif_const::STATE_RESET_SECS,
+        ));
+        interval.tick().await; // first tick fires immediately — skip it
+        loop {
+            tokio::select! {
+                _ = interval.tick() => tracker.reset_alerts().await,
+                _ = cancel.cancelled() => break,
+            }
+        }
+    });
+}
+
+/// Sends the alert digest every `period`.
+fn spawn_digest(tracker: TrackerHandle, period: std::time::Duration, cancel: CancellationToken) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await; // first tick fires immediately — skip it
+        loop {
+            tokio::select! {
+                _ = interval.tick() => tracker.flush_digest().await,
+                _ = cancel.cancelled() => break,
+            }
+        }
+    });
+}
+
+/// Repeated connect failures for the same endpoint are escalated, and
+/// reconnects are announced on Telegram (at most once per cooldown per URL).
+/// Every event also updates the feed health shown by [`Control::status`].
+fn spawn_connection_monitor(
+    mut events_rx: broadcast::Receiver<ConnectionEvent>,
+    telegram_tx: Option<mpsc::Sender<Notification>>,
+    control: Control,
+    event_log: Option<EventLog>,
+    cancel: CancellationToken,
+) {
+    tokio::spawn(async move {
+        let cooldown = std::time::Duration::from_secs(notif_const::RECONNECT_ALERT_COOLDOWN_SECS);
+        let mut consecutive_failures: HashMap<String, u32> = HashMap::new();
+        let mut last_alert: HashMap<String, Instant> = HashMap::new();
+        loop {
+            let event = tokio::select! {
+                event = events_rx.recv() => event,
+                _ = cancel.cancelled() => break,
+            };
+            let event = match event {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    eprintln!("⚠️ Connection monitor lagged, {} events skipped", missed);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            control.health().record(&event);
+            if let Some(log) = &event_log {
+                if let Err(e) = log.append(&event) {
+                    eprintln!("❌ Event log write failed: {}", e);
+                }
+            }
+            let (url, reason, severity) = match event {
+                ConnectionEvent::Connected { url } => {
+                    consecutive_failures.remove(&url);
+                    continue;
+                }
+                ConnectionEvent::ConnectFailed { url, error } => {
+                    let failures = consecutive_failures.entry(url.clone()).or_insert(0);
+                    *failures += 1;
+                    if failures.is_multiple_of(5) {
+                        eprintln!(
+                            "⚠️ {} unreachable ({} consecutive failures): {}",
+                            url, failures, error
+                        );
+                    }
+                    (url, format!("connect failed: {}", error), error.severity())
+                }
+                ConnectionEvent::Disconnected { url, reason } => (
+                    url,
+                    format!("connection lost ({})", reason),
+                    Severity::Warning,
+                ),
+                ConnectionEvent::CircuitBreakerTripped {
+                    url,
+                    disconnections,
+                    cooldown,
+                } => (
+                    url,
+                    format!(
+                        "circuit breaker tripped after {} disconnections, pausing {:?}",
+                        disconnections, cooldown
+                    ),
+                    Severity::Warning,
+                ),
+                ConnectionEvent::Reconnecting { .. } | ConnectionEvent::Rotated { .. } => continue,
+            };
+
+            let Some(ref tx) = telegram_tx else {
+                continue;
+            };
+            if last_alert.get(&url).is_some_and(|t| t.elapsed() < cooldown) {
+                continue;
+            }
+            last_alert.insert(url.clone(), Instant::now());
+            let _ = tx.try_send(Notification::FeedReconnecting {
+                url,
+                reason,
+                severity,
+            });
+        }
+    });
+}
@@ -0,0 +1,525 @@
+//! Crate-wide error types.
+//!
+//! Each subsystem has its own error enum; [`Error`] wraps them all. Every error
+//! answers two questions through [`Classify`]: is retrying the same operation
+//! worthwhile, and how loudly should it be reported. Supervisors (the
+//! WebSocket reconnect loop) use the former to pick a backoff, the
+//! notification router uses the latter to decide how to alert. Execution
+//! also asks a [`TradingError`] whether it is rate-limited (resend the order)
+//! or fatal (stop trading on that exchange).
+//!
+//! `anyhow` stays in use at the edges (config loading, CLI commands) where
+//! errors are only ever printed.
+
+use std::{fmt, net::SocketAddr, path::PathBuf};
+
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio_tungstenite::tungstenite::error::Error as WsError;
+
+use crate::{
+    models::{ids::ExchangeId, money::Decimal},
+    net::ConnectError,
+};
+
+/// How urgently an error needs a human.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Expected noise (a dropped connection that will be re-established).
+    Info,
+    /// Degraded but self-healing; worth a silent notification.
+    Warning,
+    /// Won't recover without intervention (bad credentials, pin mismatch,
+    /// rejected orders).
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Info => write!(f, "info"),
+            Self::Warning => write!(f, "warning"),
+            Self::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+pub trait Classify {
+    /// Whether retrying the same operation unchanged can succeed.
+    fn is_retryable(&self) -> bool;
+
+    fn severity(&self) -> Severity;
+}
+
+impl Classify for ConnectError {
+    fn is_retryable(&self) -> bool {
+        !self.is_tls()
+    }
+
+    fn severity(&self) -> Severity {
+        if self.is_tls() {
+            Severity::Critical
+        } else {
+            Severity::Warning
+        }
+    }
+}
+
+/// Market-data feed failures.
+#[derive(Debug, Error)]
+pub enum FeedError {
+    #[error(transparent)]
+    Connect(Box<ConnectError>),
+    #[error("stream error: {0}")]
+    Stream(Box<WsError>),
+    #[error("decompression failed: {0}")]
+    Decode(String),
+}
+
+impl From<ConnectError> for FeedError {
+    fn from(e: ConnectError) -> Self {
+        Self::Connect(Box::new(e))
+    }
+}
+
+impl From<WsError> for FeedError {
+    fn from(e: WsError) -> Self {
+        Self::Stream(Box::new(e))
+    }
+}
+
+impl Classify for FeedError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            Self::Connect(e) => e.is_retryable(),
+            Self::Stream(_) => true,
+            // The next frame may well decode; the broken one is lost either way.
+            Self::Decode(_) => false,
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        match self {
+            Self::Connect(e) => e.severity(),
+            Self::Stream(_) => Severity::Info,
+            Self::Decode(_) => Severity::Warning,
+        }
+    }
+}
+
+/// Binance error codes that mean "slow down", not "this request is wrong".
+const BINANCE_RATE_LIMIT_CODES: [i32; 2] = [-1003, -1015];
+/// Binance error codes for bad keys, signatures or permissions.
+const BINANCE_AUTH_CODES: [i32; 4] = [-1002, -1022, -2014, -2015];
+/// Bybit `retCode`s that mean "slow down".
+const BYBIT_RATE_LIMIT_CODES: [i32; 2] = [10006, 10018];
+/// Bybit `retCode`s for bad keys, signatures, permissions or IPs.
+const BYBIT_AUTH_CODES: [i32; 5] = [10003, 10004, 10005, 10009, 10010];
+
+/// Order placement and account API failures.
+#[derive(Debug, Error)]
+pub enum TradingError {
+    #[error("connecting to the trading API failed after {attempts} attempts: {source}")]
+    Connect {
+        attempts: u32,
+        source: Box<ConnectError>,
+    },
+    #[error("trading connection error: {0}")]
+    Transport(Box<WsError>),
+    #[error("trading connection closed unexpectedly")]
+    ConnectionClosed,
+    #[error("malformed trading API message: {0}")]
+    Serialization(#[from] serde_json::Error),
+    /// The exchange answered with an error; `code` is its own (Binance
+    /// error code, Bybit `retCode` or HTTP status).
+    #[error("{exchange} {operation} rejected ({code}): {msg}")]
+    Rejected {
+        exchange: ExchangeId,
+        operation: &'static str,
+        code: i32,
+        msg: String,
+    },
+    /// The exchange answered with neither a result nor an error.
+    #[error("{operation} returned no result")]
+    EmptyResponse { operation: &'static str },
+    #[error("{0} is not set")]
+    MissingCredentials(String),
+    #[error("invalid SECRET_KEY_BINANCE: {0}")]
+    InvalidKey(String),
+    #[error("trading REST request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// The API key isn't fit for live trading; see `binance::permissions`.
+    #[error("API key audit failed: {}", .0.join("; "))]
+    KeyAudit(Vec<String>),
+    /// The exchange's client can't place this kind of order.
+    #[error("{exchange} has no {kind} order client")]
+    Unsupported {
+        exchange: &'static str,
+        kind: &'static str,
+    },
+    /// A reduce-only order that, by the tracked exposure, would have grown
+    /// or flipped the position; it was not sent.
+    #[error(
+        "reduce-only {side} of {quantity} on {exchange} would not reduce its {exposure} exposure"
+    )]
+    NotReducing {
+        exchange: &'static str,
+        side: String,
+        quantity: String,
+        exposure: String,
+    },
+    /// An order not acknowledged within `[engine.execution.latency]
+    /// budget_ms` of its trade's start; it's cancelled once it is.
+    #[error("no ack from {exchange} within the {budget_ms} ms latency budget")]
+    OverBudget {
+        exchange: &'static str,
+        budget_ms: u64,
+    },
+    /// An IOC or FOK order expired without filling anything.
+    #[error("{time_in_force} order {order_id} expired unfilled ({status})")]
+    Unfilled {
+        order_id: String,
+        time_in_force: String,
+        status: String,
+    },
+}
+
+impl From<WsError> for TradingError {
+    fn from(e: WsError) -> Self {
+        Self::Transport(Box::new(e))
+    }
+}
+
+impl Classify for TradingError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            Self::Connect { source, .. } => source.is_retryable(),
+            Self::Transport(_) | Self::ConnectionClosed | Self::Http(_) => true,
+            Self::Rejected { .. } => self.is_rate_limited(),
+            Self::Serialization(_)
+            | Self::EmptyResponse { .. }
+            | Self::MissingCredentials(_)
+            | Self::InvalidKey(_)
+            | Self::KeyAudit(_)
+            | Self::Unsupported { .. }
+            | Self::NotReducing { .. }
+            | Self::OverBudget { .. }
+            | Self::Unfilled { .. } => false,
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        match self {
+            Self::Rejected { .. } if self.is_rate_limited() => Severity::Warning,
+            Self::Transport(_) | Self::ConnectionClosed | Self::Http(_) => Severity::Warning,
+            // An order that didn't go through may leave one leg unhedged.
+            _ => Severity::Critical,
+        }
+    }
+}
+
+impl TradingError {
+    /// Whether the exchange refused the key: bad signature, permissions or
+    /// IP.
+    pub fn is_auth(&self) -> bool {
+        match self {
+            Self::Rejected {
+                exchange: ExchangeId::Binance,
+                code,
+                ..
+            } => BINANCE_AUTH_CODES.contains(code),
+            Self::Rejected {
+                exchange: ExchangeId::Bybit,
+                code,
+                ..
+            } => BYBIT_AUTH_CODES.contains(code),
+            _ => false,
+        }
+    }
+
+    /// Whether the exchange only asked to slow down. Nothing was placed, so
+    /// unlike a transport failure the same order can be sent again.
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            Self::Rejected {
+                exchange: ExchangeId::Binance,
+                code,
+                ..
+            } => BINANCE_RATE_LIMIT_CODES.contains(code),
+            Self::Rejected {
+                exchange: ExchangeId::Bybit,
+                code,
+                ..
+            } => BYBIT_RATE_LIMIT_CODES.contains(code),
+            _ => false,
+        }
+    }
+
+    /// Whether the exchange may have acted on the request even so: it went
+    /// out, but no answer to it could be read.
+    pub fn is_ambiguous(&self) -> bool {
+        matches!(
+            self,
+            Self::Transport(_)
+                | Self::ConnectionClosed
+                | Self::Serialization(_)
+                | Self::EmptyResponse { .. }
+                | Self::Http(_)
+        )
+    }
+
+    /// Whether no further order can go through until someone fixes the
+    /// keys or the connection setup; execution stops trading on one.
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            Self::Connect { source, .. } => source.is_tls(),
+            Self::MissingCredentials(_) | Self::InvalidKey(_) | Self::KeyAudit(_) => true,
+            _ => self.is_auth(),
+        }
+    }
+}
+
+/// Local persistence failures (CSV logs, recordings).
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+    #[error("serialization failed: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+impl StorageError {
+    pub fn io(path: impl Into<PathBuf>) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.into();
+        move |source| Self::Io { path, source }
+    }
+}
+
+impl Classify for StorageError {
+    fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Io { source, .. } if matches!(
+                source.kind(),
+                std::io::ErrorKind::Interrupted | std::io::ErrorKind::WouldBlock
+            )
+        )
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+}
+
+/// Notification delivery failures.
+#[derive(Debug, Error)]
+pub enum NotifyError {
+    #[error("{0} is not set")]
+    MissingConfig(&'static str),
+    #[error("network error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("API error ({status}): {body}")]
+    Api { status: u16, body: String },
+    #[error("notification queue full")]
+    QueueFull,
+    #[error("notification worker gone")]
+    QueueClosed,
+}
+
+impl<T> From<TrySendError<T>> for NotifyError {
+    fn from(e: TrySendError<T>) -> Self {
+        match e {
+            TrySendError::Full(_) => Self::QueueFull,
+            TrySendError::Closed(_) => Self::QueueClosed,
+        }
+    }
+}
+
+impl Classify for NotifyError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            Self::Http(_) => true,
+            // 429 (rate limited) and 5xx
+            Self::Api { status, .. } => *status == 429 || *status >= 500,
+            Self::MissingConfig(_) | Self::QueueFull | Self::QueueClosed => false,
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        match self {
+            Self::MissingConfig(_) | Self::QueueClosed => Severity::Critical,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+/// Operator commands that can't be carried out, and a control API that
+/// can't start.
+#[derive(Debug, Error)]
+pub enum ControlError {
+    #[error("{0} has no feed in this build")]
+    NoFeed(ExchangeId),
+    #[error("thresholds must be positive, got {0}")]
+    InvalidThreshold(Decimal),
+    #[error("tracker has stopped")]
+    TrackerGone,
+    #[error("execution is not running")]
+    ExecutionOff,
+    #[error("invalid signal: {0}")]
+    InvalidSignal(String),
+    #[error("too many signals waiting for execution")]
+    SignalsBacklogged,
+    #[error("{0} is not set")]
+    MissingToken(&'static str),
+    #[error("cannot listen on {addr}: {source}")]
+    Bind {
+        addr: SocketAddr,
+        source: std::io::Error,
+    },
+    #[error("control API TLS: {0}")]
+    Tls(String),
+    #[error("invalid symbol to record: {0:?}")]
+    InvalidSymbol(String),
+    #[error("invalid strategy name: {0:?}")]
+    InvalidStrategy(String),
+    #[error("name one exchange or one symbol to pause: {0}")]
+    InvalidPause(String),
+    #[error("quantities must be positive, got {0}")]
+    InvalidQuantity(Decimal),
+    #[error("cannot record {symbol}: {source}")]
+    Recording {
+        symbol: String,
+        source: std::io::Error,
+    },
+}
+
+impl Classify for ControlError {
+    fn is_retryable(&self) -> bool {
+        false
+    }
+
+    fn severity(&self) -> Severity {
+        match self {
+            Self::MissingToken(_) | Self::Bind { .. } | Self::Tls(_) => Severity::Critical,
+            _ => Severity::Info,
+        }
+    }
+}
+
+/// Failures of a message-bus bridge (see `crate::bridge`). The bridges
+/// reconnect on their own; these are only ever reported.
+#[derive(Debug, Error)]
+pub enum BridgeError {
+    #[cfg(feature = "redis")]
+    #[error("redis: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[cfg(feature = "kafka")]
+    #[error("kafka: {0}")]
+    Kafka(#[from] rskafka::client::error::Error),
+    #[cfg(feature = "nats")]
+    #[error("nats: {0}")]
+    NatsConnect(#[from] async_nats::ConnectError),
+    #[cfg(feature = "nats")]
+    #[error("nats: {0}")]
+    NatsPublish(#[from] async_nats::PublishError),
+    #[cfg(feature = "mqtt")]
+    #[error("mqtt: {0}")]
+    Mqtt(Box<rumqttc::ConnectionError>),
+    #[error("influx: {0}")]
+    Influx(#[from] reqwest::Error),
+    #[error("influx rejected the write ({status}): {body}")]
+    InfluxRejected { status: u16, body: String },
+    #[error("invalid command on {channel}: {source}")]
+    Command {
+        channel: String,
+        source: serde_json::Error,
+    },
+}
+
+impl Classify for BridgeError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(feature = "redis")]
+            Self::Redis(e) => e.kind() != redis::ErrorKind::InvalidClientConfig,
+            #[cfg(feature = "kafka")]
+            Self::Kafka(_) => true,
+            #[cfg(feature = "nats")]
+            Self::NatsConnect(e) => !matches!(
+                e.kind(),
+                async_nats::ConnectErrorKind::ServerParse
+                    | async_nats::ConnectErrorKind::AuthorizationViolation
+                    | async_nats::ConnectErrorKind::Tls
+            ),
+            #[cfg(feature = "nats")]
+            Self::NatsPublish(_) => true,
+            #[cfg(feature = "mqtt")]
+            Self::Mqtt(e) => !matches!(**e, rumqttc::ConnectionError::ConnectionRefused(_)),
+            Self::Influx(_) => true,
+            // Bad credentials or a missing database won't fix themselves.
+            Self::InfluxRejected { status, .. } => *status >= 500 || *status == 429,
+            Self::Command { .. } => false,
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        match self {
+            #[cfg(feature = "redis")]
+            Self::Redis(_) => Severity::Warning,
+            #[cfg(feature = "kafka")]
+            Self::Kafka(_) => Severity::Warning,
+            #[cfg(feature = "nats")]
+            Self::NatsConnect(_) | Self::NatsPublish(_) => Severity::Warning,
+            #[cfg(feature = "mqtt")]
+            Self::Mqtt(_) => Severity::Warning,
+            Self::Influx(_) | Self::InfluxRejected { .. } => Severity::Warning,
+            Self::Command { .. } => Severity::Info,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Feed(#[from] FeedError),
+    #[error(transparent)]
+    Trading(#[from] TradingError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Notify(#[from] NotifyError),
+    #[error(transparent)]
+    Control(#[from] ControlError),
+    #[error(transparent)]
+    Bridge(#[from] BridgeError),
+}
+
+impl Classify for Error {
+    fn is_retryable(&self) -> bool {
+        match self {
+            Self::Feed(e) => e.is_retryable(),
+            Self::Trading(e) => e.is_retryable(),
+            Self::Storage(e) => e.is_retryable(),
+            Self::Notify(e) => e.is_retryable(),
+            Self::Control(e) => e.is_retryable(),
+            Self::Bridge(e) => e.is_retryable(),
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        match self {
+            Self::Feed(e) => e.severity(),
+            Self::Trading(e) => e.severity(),
+            Self::Storage(e) => e.severity(),
+            Self::Notify(e) => e.severity(),
+            Self::Control(e) => e.severity(),
+            Self::Bridge(e) => e.severity(),
+        }
+    }
+}
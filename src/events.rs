@@ -0,0 +1,336 @@
+//! `arbitrage-bot events`: a timeline of what the bot saw and did, read back
+//! from storage for post-mortems.
+//!
+//! Opportunities come from the spread log (`[engine] spread_log`), orders
+//! and their errors from the state file (`state_file`, which only keeps the
+//! latest `[limits] order_history` orders), and connection events from the
+//! event log (`event_log`), a JSON-lines file the engine appends every
+//! connection event to. Sources that aren't configured or don't exist yet
+//! are skipped.
+//!
+//! ```text
+//! arbitrage-bot events --since 2h --type trade,error
+//! ```
+
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backtest::replay,
+    binance::ws_handler::ConnectionEvent,
+    config::EngineConfig,
+    error::StorageError,
+    state::{self, EngineState, OrderLeg},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventKind {
+    Opportunity,
+    Trade,
+    Error,
+    Connection,
+}
+
+impl FromStr for EventKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim() {
+            "opportunity" | "opportunities" => Self::Opportunity,
+            "trade" | "trades" | "order" | "orders" => Self::Trade,
+            "error" | "errors" => Self::Error,
+            "connection" | "connections" => Self::Connection,
+            other => bail!(
+                "unknown event type {:?} (opportunity, trade, error, connection)",
+                other
+            ),
+        })
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Opportunity => "OPPORTUNITY",
+            Self::Trade => "TRADE",
+            Self::Error => "ERROR",
+            Self::Connection => "CONNECTION",
+        })
+    }
+}
+
+/// One line of the timeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub at_ms: i64,
+    pub kind: EventKind,
+    pub text: String,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = chrono::DateTime::from_timestamp_millis(self.at_ms)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+            .unwrap_or_else(|| self.at_ms.to_string());
+        write!(f, "{}  {:<11}  {}", at, self.kind, self.text)
+    }
+}
+
+/// A connection event as the event log keeps it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionRecord {
+    pub at_ms: i64,
+    pub url: String,
+    /// What happened, e.g. "disconnected: closed by server".
+    pub event: String,
+    /// Whether it was a failure rather than routine.
+    #[serde(default)]
+    pub error: bool,
+}
+
+impl ConnectionRecord {
+    pub fn new(event: &ConnectionEvent, at_ms: i64) -> Self {
+        let (description, error) = match event {
+            ConnectionEvent::Connected { .. } => ("connected".to_string(), false),
+            ConnectionEvent::ConnectFailed { error, .. } => {
+                (format!("connect failed: {}", error), true)
+            }
+            ConnectionEvent::Disconnected { reason, .. } => {
+                (format!("disconnected: {}", reason), false)
+            }
+            ConnectionEvent::Reconnecting { retry_in, .. } => {
+                (format!("reconnecting in {:?}", retry_in), false)
+            }
+            ConnectionEvent::CircuitBreakerTripped {
+                disconnections,
+                cooldown,
+                ..
+            } => (
+                format!(
+                    "circuit breaker tripped after {} disconnections, pausing {:?}",
+                    disconnections, cooldown
+                ),
+                true,
+            ),
+            ConnectionEvent::Rotated { .. } => ("rotated".to_string(), false),
+        };
+        Self {
+            at_ms,
+            url: event.url().to_string(),
+            event: description,
+            error,
+        }
+    }
+}
+
+/// Appends connection events to `[engine] event_log`.
+pub struct EventLog {
+    path: PathBuf,
+}
+
+impl EventLog {
+    /// Creates the file if it doesn't exist, so a bad path fails at startup.
+    pub fn open(path: &Path) -> Result<Self, StorageError> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(StorageError::io(path))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn append(&self, event: &ConnectionEvent) -> Result<(), StorageError> {
+        let record = ConnectionRecord::new(event, state::now_ms());
+        let line = serde_json::to_string(&record)?;
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(StorageError::io(&self.path))?;
+        writeln!(file, "{}", line).map_err(StorageError::io(&self.path))
+    }
+}
+
+/// Every event the sources in `config` hold, oldest first.
+pub fn load(config: &EngineConfig) -> anyhow::Result<Vec<Event>> {
+    let mut events = Vec::new();
+    if let Some(path) = config.spread_log.as_deref().filter(|p| p.exists()) {
+        events.extend(opportunities(path)?);
+    }
+    if let Some(path) = &config.state_file {
+        if let Some(saved) = EngineState::load(path)? {
+            events.extend(orders(&saved));
+        }
+    }
+    if let Some(path) = &config.event_log {
+        events.extend(connections(path)?);
+    }
+    events.sort_by_key(|e| e.at_ms);
+    Ok(events)
+}
+
+/// The spreads in a spread log.
+pub fn opportunities(path: &Path) -> anyhow::Result<Vec<Event>> {
+    let samples = replay::load_csv(&path.to_string_lossy())?;
+    Ok(samples
+        .into_iter()
+        .map(|s| Event {
+            at_ms: s.timestamp * 1000,
+            kind: EventKind::Opportunity,
+            text: format!(
+                "{} {}/{} {:.2}%",
+                s.symbol, s.exchange_a, s.exchange_b, s.diff_percent
+            ),
+        })
+        .collect())
+}
+
+/// The orders in a saved state; failed ones are errors.
+pub fn orders(saved: &EngineState) -> Vec<Event> {
+    saved
+        .execution
+        .orders
+        .iter()
+        .map(|order| {
+            let OrderLeg {
+                exchange,
+                symbol,
+                side,
+                price,
+                quantity,
+                ..
+            } = order;
+            let leg = format!(
+                "{} {} {} on {} @ {}",
+                side, quantity, symbol, exchange, price
+            );
+            let (kind, text) = match (&order.order_id, &order.error) {
+                (_, Some(error)) => (EventKind::Error, format!("{} failed: {}", leg, error)),
+                (Some(id), None) => (EventKind::Trade, format!("{} → order {}", leg, id)),
+                (None, None) => (EventKind::Trade, leg),
+            };
+            Event {
+                at_ms: order.placed_at_ms,
+                kind,
+                text,
+            }
+        })
+        .collect()
+}
+
+/// The connection events in an event log; failures are errors.
+pub fn connections(path: &Path) -> anyhow::Result<Vec<Event>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(StorageError::io(path)(e).into()),
+    };
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_no, line)| {
+            let record: ConnectionRecord = serde_json::from_str(line)
+                .with_context(|| format!("{} line {}", path.display(), line_no + 1))?;
+            Ok(Event {
+                at_ms: record.at_ms,
+                kind: if record.error {
+                    EventKind::Error
+                } else {
+                    EventKind::Connection
+                },
+                text: format!("{} {}", record.url, record.event),
+            })
+        })
+        .collect()
+}
+
+/// `90s`, `15m`, `2h` or `7d`.
+///
+/// ```
+/// use std::time::Duration;
+/// use arbitrage_bot::events::parse_since;
+///
+/// assert_eq!(parse_since("2h"), Some(Duration::from_secs(7200)));
+/// assert_eq!(parse_since("15m"), Some(Duration::from_secs(900)));
+/// assert_eq!(parse_since("2 hours"), None);
+/// ```
+pub fn parse_since(s: &str) -> Option<Duration> {
+    let unit = match s.chars().last()? {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86400,
+        _ => return None,
+    };
+    let count: u64 = s[..s.len() - 1].parse().ok()?;
+    Some(Duration::from_secs(count * unit))
+}
+
+/// The events at or after `since_ms` of the given kinds (all of them if
+/// `kinds` is empty).
+pub fn filter(events: Vec<Event>, since_ms: i64, kinds: &[EventKind]) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|e| e.at_ms >= since_ms && (kinds.is_empty() || kinds.contains(&e.kind)))
+        .collect()
+}
+
+/// Entry point for `arbitrage-bot events [--since 2h] [--type trade,error]`.
+pub fn run_cli(args: &[String], config: &EngineConfig) {
+    let flag = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let since = match flag("--since").map(|s| parse_since(s)) {
+        None => Duration::from_secs(86400),
+        Some(Some(since)) => since,
+        Some(None) => {
+            eprintln!("Usage: arbitrage-bot events [--since 2h] [--type trade,error]");
+            std::process::exit(1);
+        }
+    };
+    let kinds: Vec<EventKind> = match flag("--type")
+        .map(|types| types.split(',').map(str::parse).collect())
+        .transpose()
+    {
+        Ok(kinds) => kinds.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+    if config.spread_log.is_none() && config.state_file.is_none() && config.event_log.is_none() {
+        eprintln!("❌ None of [engine] spread_log, state_file and event_log is set");
+        std::process::exit(1);
+    }
+
+    let events = match load(config) {
+        Ok(events) => events,
+        Err(e) => {
+            eprintln!("❌ Reading events failed: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    let since_ms = state::now_ms() - since.as_millis() as i64;
+    let events = filter(events, since_ms, &kinds);
+    if events.is_empty() {
+        println!("No events in the last {:?}", since);
+        return;
+    }
+    println!("🕒 {} event(s), times in UTC", events.len());
+    for event in events {
+        println!("{}", event);
+    }
+}
@@ -0,0 +1,304 @@
+//! Expiring resting orders (`[engine.execution.expiry]`).
+//!
+//! A GTC leg that doesn't fill at once, whether placed as a maker or as a
+//! taker that missed its price, rests on the book, and can fill hours later
+//! at a price the other leg has long moved away from. Every
+//! `interval_secs` each order client is asked for its open orders (see
+//! `Exchange::open_orders`), and an order still resting `ttl_secs` after it
+//! was first seen is expired by `action`:
+//!
+//! - `amend`: it's cancelled and the rest placed again at its side's touch
+//!   (the best bid for a buy, the best ask for a sell), to rest another
+//!   `ttl_secs`. After `max_amends` re-pricings, or without a quote to
+//!   re-price at, it's only cancelled;
+//! - `market`: it's cancelled and the rest sent as a market order;
+//! - `cancel`: it's cancelled.
+//!
+//! The rest is what hadn't filled when the cancel took, as the exchange
+//! reports it (see `Exchange::fill`), or as of the last read where it
+//! doesn't report fills; if asking fails, the order is only cancelled. A
+//! reduce-only order's rest is placed reduce-only again.
+//!
+//! Ages are counted from the first read that found the order, so an order
+//! expires up to `interval_secs` late. A cancelled rest comes back off the
+//! exposure, which counted it as filled, and its replacement is recorded
+//! like any other order. Only futures orders are supervised.
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use tokio::{
+    sync::watch,
+    time::{self, Instant},
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{ExpiryAction, ExpiryConfig},
+    error::TradingError,
+    models::{ids::ExchangeId, money::Decimal},
+    state::{self, ExecutionState, LegSide, OpenOrder, OrderLeg},
+    ws::{
+        exchanges::{Exchange, OrderSide},
+        latest::QuoteCell,
+        quote_bus::QuoteBus,
+    },
+};
+
+/// What was done with an order that rested past its TTL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expired {
+    pub exchange: ExchangeId,
+    pub order: OpenOrder,
+    /// `Cancel` where `amend` ran out of re-pricings or quotes.
+    pub action: ExpiryAction,
+    /// The order placed for the rest, or why placing it failed; `None`
+    /// when it was only cancelled.
+    pub replacement: Option<Result<String, String>>,
+}
+
+impl fmt::Display for Expired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} @ {} (order {}) rested past its TTL",
+            self.exchange,
+            self.order.side,
+            (self.order.quantity - self.order.filled).normalize(),
+            self.order.price.normalize(),
+            self.order.order_id
+        )?;
+        let done = match self.action {
+            ExpiryAction::Amend => "re-priced",
+            ExpiryAction::Market => "sent at market",
+            ExpiryAction::Cancel => return write!(f, "; cancelled"),
+        };
+        match &self.replacement {
+            Some(Ok(order_id)) => write!(f, "; {} as order {}", done, order_id),
+            Some(Err(e)) => write!(f, "; cancelled, but not {}: {}", done, e),
+            None => write!(f, "; cancelled"),
+        }
+    }
+}
+
+/// An order seen resting.
+struct Resting {
+    since: Instant,
+    /// Re-pricings of the order it replaced, and of that one's.
+    amends: u32,
+}
+
+/// Watches the orders `exchanges` have resting; see the module docs.
+pub struct Expiry {
+    config: ExpiryConfig,
+    exchanges: Vec<Arc<dyn Exchange>>,
+    /// The latest quote of the symbol each exchange trades.
+    quotes: HashMap<ExchangeId, Arc<QuoteCell>>,
+    state: watch::Sender<ExecutionState>,
+    resting: HashMap<(ExchangeId, String), Resting>,
+}
+
+impl Expiry {
+    /// Re-prices at the latest quotes on `bus` for the symbol each exchange
+    /// trades, and keeps `state`'s exposure and orders up to date.
+    pub fn new<'a>(
+        config: &ExpiryConfig,
+        exchanges: Vec<Arc<dyn Exchange>>,
+        bus: &QuoteBus,
+        symbol_on: impl Fn(ExchangeId) -> &'a str,
+        state: watch::Sender<ExecutionState>,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            quotes: exchanges
+                .iter()
+                .map(|e| (e.id(), bus.latest(e.id(), symbol_on(e.id()))))
+                .collect(),
+            exchanges,
+            state,
+            resting: HashMap::new(),
+        }
+    }
+
+    /// Reads every exchange's open orders and expires those past the TTL;
+    /// returns what was done with each, and the exchanges that couldn't
+    /// say or refused a cancellation. Orders that couldn't be cancelled are
+    /// tried again on the next check.
+    pub async fn check(&mut self) -> Vec<Result<Expired, (ExchangeId, TradingError)>> {
+        let mut outcomes = Vec::new();
+        for exchange in self.exchanges.clone() {
+            let id = exchange.id();
+            let orders = match exchange.open_orders().await {
+                Ok(orders) => orders,
+                Err(e) => {
+                    outcomes.push(Err((id, e)));
+                    continue;
+                }
+            };
+            // Orders no longer open filled or were cancelled elsewhere.
+            self.resting.retain(|(exchange, order_id), _| {
+                *exchange != id || orders.iter().any(|o| o.order_id == *order_id)
+            });
+            let now = Instant::now();
+            for order in orders {
+                let key = (id, order.order_id.clone());
+                let resting = self.resting.entry(key.clone()).or_insert(Resting {
+                    since: now,
+                    amends: 0,
+                });
+                if now.duration_since(resting.since) < self.config.ttl() {
+                    continue;
+                }
+                let amends = resting.amends;
+                match self.expire(&*exchange, order, amends).await {
+                    Ok(expired) => {
+                        self.resting.remove(&key);
+                        if let (ExpiryAction::Amend, Some(Ok(order_id))) =
+                            (expired.action, &expired.replacement)
+                        {
+                            self.resting.insert(
+                                (id, order_id.clone()),
+                                Resting {
+                                    since: now,
+                                    amends: amends + 1,
+                                },
+                            );
+                        }
+                        outcomes.push(Ok(expired));
+                    }
+                    Err(e) => outcomes.push(Err((id, e))),
+                }
+            }
+        }
+        outcomes
+    }
+
+    /// Cancels `order` and, for `amend` and `market`, places its rest
+    /// again.
+    async fn expire(
+        &self,
+        exchange: &dyn Exchange,
+        order: OpenOrder,
+        amends: u32,
+    ) -> Result<Expired, TradingError> {
+        let id = exchange.id();
+        let quote = self
+            .quotes
+            .get(&id)
+            .and_then(|cell| cell.load())
+            .map(|quote| (quote.top.bid, quote.top.ask));
+        // The price to rest at, and the one a market order likely fills at.
+        let (touch, crossing) = match (quote, order.side) {
+            (Some((bid, ask)), LegSide::Buy) => (Some(bid), ask),
+            (Some((bid, ask)), LegSide::Sell) => (Some(ask), bid),
+            (None, _) => (None, order.price),
+        };
+        let action = match (self.config.action, touch) {
+            (ExpiryAction::Amend, Some(_)) if amends < self.config.max_amends => {
+                ExpiryAction::Amend
+            }
+            (ExpiryAction::Amend, _) => ExpiryAction::Cancel,
+            (action, _) => action,
+        };
+
+        exchange.cancel_order(&order.order_id).await?;
+        // What filled before the cancel took, not as of the last read.
+        let mut order = order;
+        let mut action = match exchange.fill(&order.order_id).await {
+            Ok(fill) => {
+                order.filled = fill.quantity.max(order.filled);
+                action
+            }
+            Err(TradingError::Unsupported { .. }) => action,
+            Err(e) => {
+                eprintln!(
+                    "⚠️ No fill for cancelled {} order {}, not placing its rest: {}",
+                    id, order.order_id, e
+                );
+                ExpiryAction::Cancel
+            }
+        };
+        self.state.send_modify(|state| state.cancelled(id, &order));
+
+        let side = OrderSide::from(order.side);
+        let rest = order.quantity - order.filled;
+        if rest <= Decimal::ZERO {
+            action = ExpiryAction::Cancel;
+        }
+        let placed = match (action, touch) {
+            (ExpiryAction::Amend, Some(price)) if order.reduce_only => {
+                Some((exchange.place_exit_future(side, price, rest).await, price))
+            }
+            (ExpiryAction::Amend, Some(price)) => {
+                Some((exchange.place_order_future(side, price, rest).await, price))
+            }
+            (ExpiryAction::Market, _) if order.reduce_only => Some((
+                exchange.place_market_exit_future(side, rest).await,
+                crossing,
+            )),
+            (ExpiryAction::Market, _) => {
+                Some((exchange.place_market_future(side, rest).await, crossing))
+            }
+            _ => None,
+        };
+        let replacement = placed.map(|(result, price)| {
+            self.record(exchange, &order, price, rest, &result);
+            result.map_err(|e| e.to_string())
+        });
+        Ok(Expired {
+            exchange: id,
+            order,
+            action,
+            replacement,
+        })
+    }
+
+    /// Adds the order placed in `order`'s place to the published state.
+    fn record(
+        &self,
+        exchange: &dyn Exchange,
+        order: &OpenOrder,
+        price: Decimal,
+        quantity: Decimal,
+        result: &Result<String, TradingError>,
+    ) {
+        let (order_id, error) = match result {
+            Ok(id) => (Some(id.clone()), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let account = order_id.as_deref().and_then(|id| exchange.account_of(id));
+        self.state.send_modify(|state| {
+            state.record(OrderLeg {
+                exchange: exchange.id(),
+                symbol: order.symbol.clone(),
+                side: order.side,
+                price,
+                quantity,
+                order_id,
+                account,
+                error,
+                placed_at_ms: state::now_ms(),
+            })
+        });
+    }
+
+    /// Checks every `interval_secs` until `cancel` fires.
+    pub fn spawn(mut self, cancel: CancellationToken) {
+        tokio::spawn(async move {
+            let mut interval = time::interval(self.config.interval());
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = cancel.cancelled() => break,
+                }
+                for outcome in self.check().await {
+                    match outcome {
+                        Ok(expired) => println!("⌛ {}", expired),
+                        Err((exchange, e)) => {
+                            eprintln!("⚠️ Couldn't expire {} orders: {}", exchange, e)
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,323 @@
+//! REST polling for feeds whose WebSocket is down (`[failover]`).
+//!
+//! A feed that has streamed no quote for `after_secs` (or none since
+//! startup) is polled over REST instead, once every `poll_interval_ms`:
+//! Binance `GET /fapi/v1/ticker/bookTicker`, Bybit `GET /v5/market/tickers`.
+//! The quotes go out on the bus like streamed ones, flagged as polled (see
+//! [`Quote::polled`]), so the tracker, alerts and dashboards keep seeing
+//! the market instead of going blind. While any of an exchange's feeds is
+//! polled the exchange is degraded: a polled quote is seconds old, so
+//! execution stops picking trades with a leg on it. The first streamed
+//! quote of a polled feed ends its polling. Both changes are logged and
+//! sent to Telegram.
+//!
+//! [`Quote::polled`]: crate::ws::quote_bus::Quote::polled
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use arc_swap::ArcSwap;
+use log::warn;
+use serde::Deserialize;
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc},
+    time::{self, Instant, MissedTickBehavior},
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{self, FailoverConfig},
+    constants::urls,
+    contracts::Contract,
+    control::Control,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money,
+        orderbook::MarketType,
+    },
+    net,
+    notifications::telegram::Notification,
+    ws::{handlers::TopOfBook, quote_bus::QuoteBus},
+};
+
+/// A change [`Failover::record`] made to one feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// Its WebSocket went quiet; it's polled now.
+    Degraded,
+    /// It streams again.
+    Recovered,
+}
+
+/// The feeds being polled per exchange, shared by the poller and execution.
+#[derive(Debug, Clone, Default)]
+pub struct Failover {
+    polled: Arc<ArcSwap<HashMap<ExchangeId, HashSet<Symbol>>>>,
+}
+
+impl Failover {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `symbol`'s feed on `exchange` is polled.
+    pub fn is_polled(&self, exchange: ExchangeId, symbol: Symbol) -> bool {
+        self.polled
+            .load()
+            .get(&exchange)
+            .is_some_and(|symbols| symbols.contains(&symbol))
+    }
+
+    /// Whether execution should leave `exchange` alone: one of its feeds is
+    /// polled.
+    pub fn is_degraded(&self, exchange: ExchangeId) -> bool {
+        self.polled
+            .load()
+            .get(&exchange)
+            .is_some_and(|symbols| !symbols.is_empty())
+    }
+
+    /// Takes whether `symbol`'s feed on `exchange` is to be polled; returns
+    /// the change it made, if any.
+    pub fn record(&self, exchange: ExchangeId, symbol: Symbol, polled: bool) -> Option<Transition> {
+        if self.is_polled(exchange, symbol) == polled {
+            return None;
+        }
+        self.polled.rcu(|venues| {
+            let mut venues = HashMap::clone(venues);
+            let symbols = venues.entry(exchange).or_default();
+            if polled {
+                symbols.insert(symbol);
+            } else {
+                symbols.remove(&symbol);
+            }
+            venues
+        });
+        Some(if polled {
+            Transition::Degraded
+        } else {
+            Transition::Recovered
+        })
+    }
+
+    /// Watches the feeds `control` has open and polls the quiet ones into
+    /// `quotes` until `cancel` fires; `alerts` hear when one is polled or
+    /// streams again.
+    pub fn spawn(
+        &self,
+        config: &FailoverConfig,
+        quotes: QuoteBus,
+        control: Control,
+        alerts: Option<mpsc::Sender<Notification>>,
+        cancel: CancellationToken,
+    ) {
+        let failover = self.clone();
+        let (after, every) = (config.after(), config.poll_interval());
+        let announce = move |exchange: ExchangeId, symbol: Symbol, transition| {
+            match transition {
+                Transition::Degraded => eprintln!(
+                    "🐢 {} {} WebSocket quiet for {}s; polling REST, not trading on {}",
+                    exchange,
+                    symbol,
+                    after.as_secs(),
+                    exchange
+                ),
+                Transition::Recovered => {
+                    println!("✅ {} {} streaming again", exchange, symbol)
+                }
+            }
+            if let Some(alerts) = &alerts {
+                let _ = alerts.try_send(Notification::FeedDegraded {
+                    exchange: exchange.to_string(),
+                    symbol: symbol.to_string(),
+                    recovered: transition == Transition::Recovered,
+                });
+            }
+        };
+        tokio::spawn(async move {
+            let client = net::http_client();
+            let mut streamed = quotes.subscribe();
+            // When each feed last streamed, or was first seen.
+            let mut last: HashMap<(ExchangeId, Symbol), Instant> = HashMap::new();
+            let mut interval = time::interval(every);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    quote = streamed.recv() => match quote {
+                        Ok(quote) if !quote.polled => {
+                            let (exchange, symbol) = (quote.exchange, quote.top.symbol);
+                            last.insert((exchange, symbol), Instant::now());
+                            if let Some(transition) = failover.record(exchange, symbol, false) {
+                                announce(exchange, symbol, transition);
+                            }
+                        }
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    },
+                    _ = interval.tick() => {
+                        let now = Instant::now();
+                        let venues: Vec<_> = control
+                            .feeds()
+                            .venues()
+                            .into_iter()
+                            .map(|(exchange, symbol)| (exchange, Symbol::intern(symbol)))
+                            .collect();
+                        // Feeds closed meanwhile are no longer polled.
+                        last.retain(|venue, _| venues.contains(venue));
+                        let closed: Vec<_> = failover
+                            .polled
+                            .load()
+                            .iter()
+                            .flat_map(|(&exchange, symbols)| {
+                                symbols.iter().map(move |&s| (exchange, s))
+                            })
+                            .filter(|venue| !venues.contains(venue))
+                            .collect();
+                        for (exchange, symbol) in closed {
+                            failover.record(exchange, symbol, false);
+                        }
+                        for (exchange, symbol) in venues {
+                            let seen = *last.entry((exchange, symbol)).or_insert(now);
+                            if now.duration_since(seen) < after {
+                                continue;
+                            }
+                            if let Some(transition) = failover.record(exchange, symbol, true) {
+                                announce(exchange, symbol, transition);
+                            }
+                            match poll(&client, exchange, symbol).await {
+                                Ok(top) => quotes.publish_polled(exchange, top),
+                                Err(e) => warn!("Polling {} {} failed: {}", exchange, symbol, e),
+                            }
+                        }
+                    }
+                    _ = cancel.cancelled() => break,
+                }
+            }
+        });
+    }
+}
+
+/// Binance `GET /fapi/v1/ticker/bookTicker` for one symbol.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceBookTicker {
+    bid_price: String,
+    ask_price: String,
+    #[serde(default)]
+    bid_qty: Option<String>,
+    #[serde(default)]
+    ask_qty: Option<String>,
+}
+
+/// Bybit's v5 envelope.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitResponse<T> {
+    ret_code: i32,
+    ret_msg: String,
+    result: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BybitTickers {
+    list: Vec<BybitTicker>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitTicker {
+    bid1_price: String,
+    ask1_price: String,
+    #[serde(default)]
+    bid1_size: Option<String>,
+    #[serde(default)]
+    ask1_size: Option<String>,
+}
+
+/// The unsigned endpoint with `symbol`'s best bid and ask on `exchange`.
+pub fn ticker_url(exchange: ExchangeId, symbol: &str) -> String {
+    let url = match exchange {
+        ExchangeId::Binance => format!(
+            "{}/fapi/v1/ticker/bookTicker?symbol={}",
+            urls::BINANCE_REST_FUTURES,
+            symbol
+        ),
+        ExchangeId::Bybit => {
+            let category = if Contract::infer(exchange, symbol).is_inverse() {
+                "inverse"
+            } else {
+                "linear"
+            };
+            format!(
+                "{}/v5/market/tickers?category={}&symbol={}",
+                urls::BYBIT_REST,
+                category,
+                symbol
+            )
+        }
+    };
+    config::get().network.endpoint(&url)
+}
+
+/// Reads `symbol`'s best bid and ask on `exchange` over REST.
+pub async fn poll(
+    client: &reqwest::Client,
+    exchange: ExchangeId,
+    symbol: Symbol,
+) -> Result<TopOfBook, String> {
+    let url = ticker_url(exchange, symbol.as_str());
+    let (bid, ask, bid_size, ask_size) = match exchange {
+        ExchangeId::Binance => {
+            let ticker: BinanceBookTicker = get(client, &url).await?;
+            (
+                ticker.bid_price,
+                ticker.ask_price,
+                ticker.bid_qty,
+                ticker.ask_qty,
+            )
+        }
+        ExchangeId::Bybit => {
+            let response: BybitResponse<BybitTickers> = get(client, &url).await?;
+            let ticker = match response.result {
+                Some(result) if response.ret_code == 0 => result.list.into_iter().next(),
+                _ => {
+                    return Err(format!(
+                        "Bybit error {}: {}",
+                        response.ret_code, response.ret_msg
+                    ))
+                }
+            }
+            .ok_or_else(|| format!("no ticker for {}", symbol))?;
+            (
+                ticker.bid1_price,
+                ticker.ask1_price,
+                ticker.bid1_size,
+                ticker.ask1_size,
+            )
+        }
+    };
+    let price = |s: &str| money::parse(s).ok_or_else(|| format!("bad price {:?}", s));
+    Ok(TopOfBook {
+        symbol,
+        bid: price(&bid)?,
+        ask: price(&ask)?,
+        bid_size: bid_size.as_deref().and_then(money::parse),
+        ask_size: ask_size.as_deref().and_then(money::parse),
+        market_type: MarketType::Futures,
+        update_id: None,
+    })
+}
+
+async fn get<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<T, String> {
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {}", status));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,152 @@
+//! Maker and taker fees per account.
+//!
+//! `[engine.execution] fee_percent` is what an order taking liquidity pays
+//! and `maker_fee_percent` what one adding it pays, negative where the venue
+//! pays a rebate instead. An account on another VIP tier sets its own
+//! `fee_percent` / `maker_fee_percent` under `[[engine.execution.accounts]]`.
+//!
+//! Execution's orders cross the spread, so a [`FeeSchedule`] charges taker
+//! fees unless it's set to [`Liquidity::Maker`], for orders that rest on the
+//! book on both legs; there a rebate on each leg adds to the edge rather
+//! than taking from it. The pre-order re-check charges each leg the highest
+//! fee among its exchange's accounts, as the account is only picked when
+//! the order goes out (see `crate::accounts`); the session summary charges
+//! each order its own account's.
+
+use std::collections::HashMap;
+
+use crate::{
+    config::ExecutionConfig,
+    models::{ids::ExchangeId, money::Decimal},
+};
+
+/// Whether an order takes liquidity off the book or adds to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Liquidity {
+    Maker,
+    #[default]
+    Taker,
+}
+
+/// One fee tier, in percent of an order's notional.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeRates {
+    pub maker_percent: Decimal,
+    pub taker_percent: Decimal,
+}
+
+impl FeeRates {
+    pub fn percent(&self, liquidity: Liquidity) -> Decimal {
+        match liquidity {
+            Liquidity::Maker => self.maker_percent,
+            Liquidity::Taker => self.taker_percent,
+        }
+    }
+}
+
+/// The fees of every account, and which of them orders pay.
+#[derive(Debug, Clone)]
+pub struct FeeSchedule {
+    default: FeeRates,
+    /// Each account's exchange and tier, by name.
+    accounts: HashMap<String, (ExchangeId, FeeRates)>,
+    liquidity: Liquidity,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self::flat(Decimal::ZERO)
+    }
+}
+
+impl FeeSchedule {
+    /// `percent` on every order, maker or taker.
+    pub fn flat(percent: Decimal) -> Self {
+        Self::new(FeeRates {
+            maker_percent: percent,
+            taker_percent: percent,
+        })
+    }
+
+    /// `default` on every account that doesn't have its own tier.
+    pub fn new(default: FeeRates) -> Self {
+        Self {
+            default,
+            accounts: HashMap::new(),
+            liquidity: Liquidity::Taker,
+        }
+    }
+
+    /// `[engine.execution]`'s fees, and those of its accounts.
+    pub fn from_config(execution: &ExecutionConfig) -> Self {
+        let default = FeeRates {
+            maker_percent: execution.maker_fee_percent,
+            taker_percent: execution.fee_percent,
+        };
+        execution
+            .accounts
+            .iter()
+            .fold(Self::new(default), |schedule, account| {
+                let rates = FeeRates {
+                    maker_percent: account.maker_fee_percent.unwrap_or(default.maker_percent),
+                    taker_percent: account.fee_percent.unwrap_or(default.taker_percent),
+                };
+                schedule.with_account(account.exchange, &account.name, rates)
+            })
+    }
+
+    /// Charges account `name` on `exchange` its own `rates`.
+    pub fn with_account(mut self, exchange: ExchangeId, name: &str, rates: FeeRates) -> Self {
+        self.accounts.insert(name.to_string(), (exchange, rates));
+        self
+    }
+
+    /// Charges orders as `liquidity`; taker by default.
+    pub fn with_liquidity(mut self, liquidity: Liquidity) -> Self {
+        self.liquidity = liquidity;
+        self
+    }
+
+    pub fn liquidity(&self) -> Liquidity {
+        self.liquidity
+    }
+
+    /// What an order on `account` pays, in percent; the default tier's
+    /// without one.
+    pub fn percent(&self, account: Option<&str>) -> Decimal {
+        account
+            .and_then(|name| self.accounts.get(name))
+            .map_or(self.default, |(_, rates)| *rates)
+            .percent(self.liquidity)
+    }
+
+    /// The most an order on `exchange` can pay, in percent, whichever of its
+    /// accounts it goes to.
+    ///
+    /// ```
+    /// use arbitrage_bot::{
+    ///     fees::{FeeRates, FeeSchedule, Liquidity},
+    ///     models::ids::ExchangeId,
+    /// };
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let rates = |maker_percent, taker_percent| FeeRates { maker_percent, taker_percent };
+    /// let schedule = FeeSchedule::new(rates(dec!(0.02), dec!(0.05)))
+    ///     .with_account(ExchangeId::Binance, "vip", rates(dec!(-0.005), dec!(0.03)))
+    ///     .with_account(ExchangeId::Binance, "sub", rates(dec!(0.01), dec!(0.04)));
+    /// assert_eq!(schedule.highest(ExchangeId::Binance), dec!(0.04));
+    /// assert_eq!(schedule.highest(ExchangeId::Bybit), dec!(0.05));
+    /// // Resting on the book, the VIP account earns a rebate.
+    /// let maker = schedule.with_liquidity(Liquidity::Maker);
+    /// assert_eq!(maker.percent(Some("vip")), dec!(-0.005));
+    /// assert_eq!(maker.highest(ExchangeId::Binance), dec!(0.01));
+    /// ```
+    pub fn highest(&self, exchange: ExchangeId) -> Decimal {
+        self.accounts
+            .values()
+            .filter(|(on, _)| *on == exchange)
+            .map(|(_, rates)| rates.percent(self.liquidity))
+            .max()
+            .unwrap_or_else(|| self.default.percent(self.liquidity))
+    }
+}
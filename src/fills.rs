@@ -0,0 +1,58 @@
+//! One fill type for every exchange.
+//!
+//! An order client reads an order's executions, however its exchange
+//! reports them, into [`Fill`]s, one per execution, and
+//! `tca::Fill::from_fills` aggregates them into the summary the order's leg
+//! costs are measured from.
+//!
+//! A fee isn't always paid in the quote currency: Binance takes it in BNB
+//! at a discount when BNB-paid fees are on. Such fees are converted at the
+//! live `[fx]` rates, so the fee asset has to be listed under
+//! `[fx.currencies]` (`BNB` from `BNBUSDT`, not pegged); one that can't be
+//! converted leaves the order's fee unknown rather than counted as free.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    fx::Fx,
+    models::{ids::ExchangeId, money::Decimal},
+    state::LegSide,
+    transfers,
+};
+
+/// One execution of an order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fill {
+    pub venue: ExchangeId,
+    pub symbol: String,
+    pub side: LegSide,
+    pub price: Decimal,
+    pub qty: Decimal,
+    /// Negative for a rebate.
+    pub fee: Decimal,
+    pub fee_asset: String,
+    /// When it filled, in ms by the exchange's clock.
+    pub ts: i64,
+    pub order_id: String,
+    pub client_order_id: Option<String>,
+}
+
+impl Fill {
+    /// `price * qty`, in the symbol's quote currency.
+    pub fn notional(&self) -> Decimal {
+        self.price * self.qty
+    }
+
+    /// `fee` in `quote`, converted at `fx`'s rates if paid in another asset.
+    pub fn fee_in(&self, quote: &str, fx: Option<&Fx>) -> Option<Decimal> {
+        if self.fee_asset == quote {
+            return Some(self.fee);
+        }
+        fx?.convert(self.fee, &self.fee_asset, quote)
+    }
+
+    /// The quote currency of `symbol`, if it ends in a known one.
+    pub fn quote_asset(&self) -> Option<&str> {
+        transfers::currencies(&self.symbol).map(|(_, quote)| quote)
+    }
+}
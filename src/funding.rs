@@ -0,0 +1,313 @@
+//! Funding-aware entry timing (`[engine.execution.funding]`).
+//!
+//! Perpetuals settle funding every few hours: whoever holds a position at
+//! the settlement pays or collects the rate on its notional, longs paying
+//! shorts while the rate is positive. Every `interval_secs` each venue's next
+//! settlement time and predicted rate for the traded symbol are read over REST
+//! (Binance `GET /fapi/v1/premiumIndex`, Bybit `GET /v5/market/tickers`).
+//! Before execution opens a trade it adds up what the two legs would pay at
+//! settlements within the next `window_secs`: the long leg pays its venue's
+//! rate, the short leg collects its own. When that comes to more than
+//! `max_cost_percent` of the notional, `action = "delay"` holds off entries on
+//! the pair, either way round, until the settlement has passed, and `"skip"`
+//! drops only that trade, so the other direction, which would collect the
+//! funding, still trades. A failed reading, or one older than three
+//! intervals, is taken as unknown and holds nothing off.
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use arc_swap::ArcSwap;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{self, FundingConfig},
+    constants::urls,
+    contracts::Contract,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::{self, Decimal},
+    },
+    net, state,
+};
+
+/// A venue's next funding settlement for a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FundingReading {
+    /// The predicted rate, as a fraction; positive means longs pay.
+    pub rate: Decimal,
+    pub next_funding_ms: i64,
+    pub at_ms: i64,
+}
+
+/// What a trade would pay in funding at the next settlement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FundingCost {
+    /// Net over both legs, in percent of the notional; negative when the
+    /// trade collects.
+    pub percent: Decimal,
+    /// The earliest settlement it includes.
+    pub settles_at_ms: i64,
+}
+
+impl fmt::Display for FundingCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let settles = DateTime::<Utc>::from_timestamp_millis(self.settles_at_ms).map_or_else(
+            || self.settles_at_ms.to_string(),
+            |t| t.format("%H:%M UTC").to_string(),
+        );
+        write!(
+            f,
+            "would pay {}% in funding at {}",
+            self.percent.round_dp(4).normalize(),
+            settles
+        )
+    }
+}
+
+/// The latest reading per venue and symbol, or why it failed.
+type Readings = HashMap<(ExchangeId, Symbol), Result<FundingReading, String>>;
+
+/// The latest readings per venue and symbol, shared by the probe task and
+/// execution.
+#[derive(Debug, Clone)]
+pub struct Funding {
+    config: Arc<FundingConfig>,
+    readings: Arc<ArcSwap<Readings>>,
+}
+
+impl Funding {
+    pub fn new(config: &FundingConfig) -> Self {
+        Self {
+            config: Arc::new(config.clone()),
+            readings: Arc::default(),
+        }
+    }
+
+    pub fn config(&self) -> &FundingConfig {
+        &self.config
+    }
+
+    /// Takes a reading of `exchange`'s next settlement for `symbol`.
+    pub fn record(
+        &self,
+        exchange: ExchangeId,
+        symbol: Symbol,
+        reading: Result<FundingReading, String>,
+    ) {
+        self.readings.rcu(|readings| {
+            let mut readings = HashMap::clone(readings);
+            readings.insert((exchange, symbol), reading.clone());
+            readings
+        });
+    }
+
+    /// `exchange`'s latest reading for `symbol`, unless it failed or is
+    /// stale at `now_ms`.
+    pub fn reading(
+        &self,
+        exchange: ExchangeId,
+        symbol: Symbol,
+        now_ms: i64,
+    ) -> Option<FundingReading> {
+        let readings = self.readings.load();
+        let reading = *readings.get(&(exchange, symbol))?.as_ref().ok()?;
+        let max_age = self.config.interval().as_millis() as i64 * 3;
+        (now_ms - reading.at_ms <= max_age).then_some(reading)
+    }
+
+    /// What buying `symbols[0]` on `buy` and selling `symbols[1]` on `sell`
+    /// at `now_ms` would pay at the settlements within `window_secs`; `None`
+    /// when neither leg has one coming up.
+    ///
+    /// ```
+    /// use arbitrage_bot::{config::FundingConfig, funding::{Funding, FundingReading}, models::ids::{ExchangeId, Symbol}};
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let funding = Funding::new(&FundingConfig { enabled: true, ..FundingConfig::default() });
+    /// let btc = Symbol::intern("BTCUSDT");
+    /// let reading = |rate, next_funding_ms| Ok(FundingReading { rate, next_funding_ms, at_ms: 0 });
+    /// // Binance settles in a minute at 0.05%, Bybit in an hour.
+    /// funding.record(ExchangeId::Binance, btc, reading(dec!(0.0005), 60_000));
+    /// funding.record(ExchangeId::Bybit, btc, reading(dec!(0.0001), 3_600_000));
+    ///
+    /// // Long on Binance pays 0.05%; Bybit's settlement is outside the window.
+    /// let cost = funding.cost([ExchangeId::Binance, ExchangeId::Bybit], [btc, btc], 0).unwrap();
+    /// assert_eq!(cost.percent, dec!(0.05));
+    /// // Short there collects it.
+    /// let cost = funding.cost([ExchangeId::Bybit, ExchangeId::Binance], [btc, btc], 0).unwrap();
+    /// assert_eq!(cost.percent, dec!(-0.05));
+    /// ```
+    pub fn cost(
+        &self,
+        [buy, sell]: [ExchangeId; 2],
+        [buy_symbol, sell_symbol]: [Symbol; 2],
+        now_ms: i64,
+    ) -> Option<FundingCost> {
+        let window = self.config.window_secs as i64 * 1000;
+        let upcoming = |exchange, symbol| {
+            self.reading(exchange, symbol, now_ms)
+                .filter(|r| r.next_funding_ms >= now_ms && r.next_funding_ms - now_ms <= window)
+        };
+        let legs = [
+            (upcoming(buy, buy_symbol), Decimal::ONE),
+            (upcoming(sell, sell_symbol), Decimal::NEGATIVE_ONE),
+        ];
+        let settles_at_ms = legs
+            .iter()
+            .filter_map(|(reading, _)| Some(reading.as_ref()?.next_funding_ms))
+            .min()?;
+        let rate: Decimal = legs
+            .iter()
+            .filter_map(|(reading, sign)| Some(reading.as_ref()?.rate * sign))
+            .sum();
+        Some(FundingCost {
+            percent: rate * Decimal::ONE_HUNDRED,
+            settles_at_ms,
+        })
+    }
+
+    /// [`cost`](Self::cost), when it's more than `max_cost_percent`.
+    pub fn excessive(
+        &self,
+        legs: [ExchangeId; 2],
+        symbols: [Symbol; 2],
+        now_ms: i64,
+    ) -> Option<FundingCost> {
+        self.cost(legs, symbols, now_ms)
+            .filter(|cost| cost.percent > self.config.max_cost_percent)
+    }
+
+    /// Reads the next settlement of every `(exchange, symbol)` in `venues`
+    /// now and then every `interval_secs`, until `cancel` fires. Failures are
+    /// reported once per outage.
+    pub fn spawn_probes(&self, venues: Vec<(ExchangeId, Symbol)>, cancel: CancellationToken) {
+        let funding = self.clone();
+        tokio::spawn(async move {
+            let client = net::http_client();
+            let mut interval = tokio::time::interval(funding.config.interval());
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = cancel.cancelled() => break,
+                }
+                for &(exchange, symbol) in &venues {
+                    let reading = fetch(&client, exchange, symbol.as_str()).await;
+                    let failing = matches!(
+                        funding.readings.load().get(&(exchange, symbol)),
+                        Some(Err(_))
+                    );
+                    match &reading {
+                        Err(e) if !failing => eprintln!(
+                            "❌ Reading {} funding on {} failed: {}",
+                            symbol, exchange, e
+                        ),
+                        Ok(_) if failing => {
+                            println!("✅ {} funding on {} readable again", symbol, exchange)
+                        }
+                        _ => {}
+                    }
+                    funding.record(exchange, symbol, reading);
+                }
+            }
+        });
+    }
+}
+
+/// Binance `GET /fapi/v1/premiumIndex` for one symbol.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinancePremiumIndex {
+    last_funding_rate: String,
+    next_funding_time: i64,
+}
+
+/// Bybit's v5 envelope.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitResponse<T> {
+    ret_code: i32,
+    ret_msg: String,
+    result: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BybitTickers {
+    list: Vec<BybitTicker>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitTicker {
+    funding_rate: String,
+    /// Milliseconds, as a string.
+    next_funding_time: String,
+}
+
+/// Reads `exchange`'s next settlement for `symbol`.
+pub async fn fetch(
+    client: &reqwest::Client,
+    exchange: ExchangeId,
+    symbol: &str,
+) -> Result<FundingReading, String> {
+    let (rate, next_funding_ms) = match exchange {
+        ExchangeId::Binance => {
+            let base = config::get().network.endpoint(urls::BINANCE_REST_FUTURES);
+            let index: BinancePremiumIndex = get(
+                client,
+                &format!("{}/fapi/v1/premiumIndex?symbol={}", base, symbol),
+            )
+            .await?;
+            (index.last_funding_rate, index.next_funding_time)
+        }
+        ExchangeId::Bybit => {
+            let base = config::get().network.endpoint(urls::BYBIT_REST);
+            let category = if Contract::infer(exchange, symbol).is_inverse() {
+                "inverse"
+            } else {
+                "linear"
+            };
+            let response: BybitResponse<BybitTickers> = get(
+                client,
+                &format!(
+                    "{}/v5/market/tickers?category={}&symbol={}",
+                    base, category, symbol
+                ),
+            )
+            .await?;
+            let ticker = match response.result {
+                Some(result) if response.ret_code == 0 => result.list.into_iter().next(),
+                _ => {
+                    return Err(format!(
+                        "Bybit error {}: {}",
+                        response.ret_code, response.ret_msg
+                    ))
+                }
+            }
+            .ok_or_else(|| format!("no ticker for {}", symbol))?;
+            let next = ticker
+                .next_funding_time
+                .parse()
+                .map_err(|_| format!("bad nextFundingTime {:?}", ticker.next_funding_time))?;
+            (ticker.funding_rate, next)
+        }
+    };
+    Ok(FundingReading {
+        rate: money::parse(&rate).ok_or_else(|| format!("bad funding rate {:?}", rate))?,
+        next_funding_ms,
+        at_ms: state::now_ms(),
+    })
+}
+
+async fn get<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<T, String> {
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {}", status));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,289 @@
+//! Quote currency conversion (`[fx]`).
+//!
+//! The same base trades against several quote currencies (BTCUSDT, BTCUSDC,
+//! BTCUSD), and their prices only compare once they're in one currency.
+//! With `[fx]` enabled the tracker converts every quote in a currency listed
+//! under `[fx.currencies]` into `reference` and compares it with the
+//! reference-quoted symbol: BTCUSDC on Bybit against BTCUSDT on Binance. A
+//! rate is fixed in the config or read from a Binance spot ticker every
+//! `refresh_secs`. Quotes in a currency whose live rate hasn't arrived yet
+//! are held back, and currencies not listed are compared as before, only
+//! against their own symbol.
+//!
+//! A spread between two quote currencies is only arbitrage while the rate
+//! between them holds. Once a pegged currency drifts more than
+//! `depeg_percent` from parity, spreads against it are the depeg itself:
+//! they are neither logged nor alerted on, and a depeg alert goes out
+//! instead (and another when it's back). Spreads within one quote currency
+//! are unaffected. Execution only ever trades its own symbol, so it isn't
+//! either.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use arc_swap::ArcSwap;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{self, FxConfig},
+    constants::urls,
+    models::{
+        ids::Symbol,
+        money::{self, Decimal},
+    },
+    net,
+    notifications::telegram::Notification,
+    transfers,
+};
+
+/// Binance `GET /api/v3/ticker/price`, one entry per symbol.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BinanceTicker {
+    pub symbol: String,
+    pub price: String,
+}
+
+/// The configured rates, shared by the tracker and the refresh task.
+#[derive(Debug, Clone)]
+pub struct Fx {
+    config: Arc<FxConfig>,
+    /// Units of `reference` per unit of each currency.
+    rates: Arc<ArcSwap<HashMap<String, Decimal>>>,
+}
+
+impl Fx {
+    /// Starts out with the fixed rates; live ones arrive with the first
+    /// refresh.
+    pub fn new(config: &FxConfig) -> Self {
+        let fixed = config
+            .currencies
+            .iter()
+            .filter_map(|(currency, source)| Some((currency.clone(), source.rate?)))
+            .collect();
+        Self {
+            config: Arc::new(config.clone()),
+            rates: Arc::new(ArcSwap::from_pointee(fixed)),
+        }
+    }
+
+    pub fn reference(&self) -> &str {
+        &self.config.reference
+    }
+
+    /// What one unit of `currency` is worth in the reference currency.
+    pub fn rate(&self, currency: &str) -> Option<Decimal> {
+        if currency == self.config.reference {
+            return Some(Decimal::ONE);
+        }
+        self.rates.load().get(currency).copied()
+    }
+
+    /// `amount` of `from` in `to`, through the reference currency; `None`
+    /// until both rates are known.
+    ///
+    /// ```
+    /// use arbitrage_bot::{config::{FxConfig, FxSource}, fx::Fx};
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let mut config = FxConfig::default();
+    /// config.currencies.insert("BNB".into(), FxSource { rate: Some(dec!(600)), pegged: false, ..FxSource::default() });
+    /// config.currencies.insert("USDC".into(), FxSource { rate: Some(dec!(0.9998)), ..FxSource::default() });
+    /// let fx = Fx::new(&config);
+    ///
+    /// assert_eq!(fx.convert(dec!(0.001), "BNB", "USDT"), Some(dec!(0.6)));
+    /// assert_eq!(fx.convert(dec!(0.9998), "USDC", "USDT"), Some(dec!(0.99960004)));
+    /// assert_eq!(fx.convert(dec!(1), "BIT", "USDT"), None);
+    /// ```
+    pub fn convert(&self, amount: Decimal, from: &str, to: &str) -> Option<Decimal> {
+        if from == to {
+            return Some(amount);
+        }
+        (amount * self.rate(from)?).checked_div(self.rate(to)?)
+    }
+
+    pub fn set_rate(&self, currency: &str, rate: Decimal) {
+        self.rates.rcu(|rates| {
+            let mut rates = HashMap::clone(rates);
+            rates.insert(currency.to_string(), rate);
+            rates
+        });
+    }
+
+    /// Takes the rates of every configured currency quoted in `tickers`,
+    /// either way round.
+    pub fn apply(&self, tickers: &[BinanceTicker]) {
+        for ticker in tickers {
+            let Some(price) = money::parse(&ticker.price).filter(|p| !p.is_zero()) else {
+                continue;
+            };
+            for (currency, source) in &self.config.currencies {
+                if source.symbol.as_deref() != Some(&ticker.symbol) {
+                    continue;
+                }
+                let rate = if ticker.symbol.starts_with(currency.as_str()) {
+                    price
+                } else {
+                    Decimal::ONE / price
+                };
+                self.set_rate(currency, rate);
+            }
+        }
+    }
+
+    /// The symbol `symbol`'s quotes are compared under and the rate that
+    /// converts them to it.
+    ///
+    /// ```
+    /// use arbitrage_bot::{config::{FxConfig, FxSource}, fx::Fx, models::ids::Symbol};
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let mut config = FxConfig::default();
+    /// config.currencies.insert("USDC".into(), FxSource { rate: Some(dec!(0.9998)), ..FxSource::default() });
+    /// config.currencies.insert("FDUSD".into(), FxSource { symbol: Some("FDUSDUSDT".into()), ..FxSource::default() });
+    /// let fx = Fx::new(&config);
+    ///
+    /// assert_eq!(fx.normalize(Symbol::intern("BTCUSDC")), Some((Symbol::intern("BTCUSDT"), dec!(0.9998))));
+    /// assert_eq!(fx.normalize(Symbol::intern("BTCUSDT")), Some((Symbol::intern("BTCUSDT"), dec!(1))));
+    /// // Not configured: compared as is.
+    /// assert_eq!(fx.normalize(Symbol::intern("ETHBTC")), Some((Symbol::intern("ETHBTC"), dec!(1))));
+    /// // Configured, but the live rate hasn't arrived.
+    /// assert_eq!(fx.normalize(Symbol::intern("BTCFDUSD")), None);
+    /// ```
+    pub fn normalize(&self, symbol: Symbol) -> Option<(Symbol, Decimal)> {
+        let unchanged = Some((symbol, Decimal::ONE));
+        let Some((base, quote)) = transfers::currencies(symbol.as_str()) else {
+            return unchanged;
+        };
+        if quote == self.config.reference || !self.config.currencies.contains_key(quote) {
+            return unchanged;
+        }
+        let rate = self.rate(quote)?;
+        Some((
+            Symbol::intern(&format!("{}{}", base, self.config.reference)),
+            rate,
+        ))
+    }
+
+    /// How far a pegged `currency` is from parity, in percent, once that's
+    /// more than `depeg_percent`.
+    pub fn depeg(&self, currency: &str) -> Option<Decimal> {
+        if !self.config.currencies.get(currency)?.pegged {
+            return None;
+        }
+        let percent = (self.rate(currency)? - Decimal::ONE) * Decimal::ONE_HUNDRED;
+        (percent.abs() > self.config.depeg_percent).then_some(percent)
+    }
+
+    /// Fetches the live rates now and then every `refresh_secs`, until
+    /// `cancel` fires, and announces every currency that depegs or recovers.
+    pub fn spawn_refresh(
+        &self,
+        alerts: Option<mpsc::Sender<Notification>>,
+        cancel: CancellationToken,
+    ) {
+        let fx = self.clone();
+        tokio::spawn(async move {
+            let client = net::http_client();
+            let symbols: Vec<_> = fx
+                .config
+                .currencies
+                .values()
+                .filter_map(|source| source.symbol.clone())
+                .collect();
+            let mut failing = false;
+            let mut depegged = HashSet::new();
+            let mut interval = tokio::time::interval(fx.config.refresh());
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = cancel.cancelled() => break,
+                }
+                if !symbols.is_empty() {
+                    match fetch(&client, &symbols).await {
+                        Ok(tickers) => {
+                            if failing {
+                                println!("✅ FX rates readable again");
+                            }
+                            failing = false;
+                            fx.apply(&tickers);
+                        }
+                        // Reported once per outage; the last rates stay in use.
+                        Err(e) if !failing => {
+                            failing = true;
+                            eprintln!("❌ Fetching FX rates failed: {:#}", e);
+                        }
+                        Err(_) => {}
+                    }
+                }
+                fx.announce(&mut depegged, alerts.as_ref());
+            }
+        });
+    }
+
+    /// Reports the pegged currencies that left or came back to parity since
+    /// the last call, going by `depegged`.
+    fn announce(
+        &self,
+        depegged: &mut HashSet<String>,
+        alerts: Option<&mpsc::Sender<Notification>>,
+    ) {
+        for currency in self.config.currencies.keys() {
+            let Some(rate) = self.rate(currency) else {
+                continue;
+            };
+            let percent = self.depeg(currency);
+            let recovered = match percent {
+                Some(percent) if depegged.insert(currency.clone()) => {
+                    eprintln!(
+                        "⚠️ {} depegged: {} {} ({:+.2}%); spreads against it aren't reported",
+                        currency,
+                        rate.normalize(),
+                        self.config.reference,
+                        percent
+                    );
+                    false
+                }
+                None if depegged.remove(currency) => {
+                    println!(
+                        "✅ {} back at {} {}",
+                        currency,
+                        rate.normalize(),
+                        self.config.reference
+                    );
+                    true
+                }
+                _ => continue,
+            };
+            if let Some(alerts) = alerts {
+                let _ = alerts.try_send(Notification::Depeg {
+                    currency: currency.clone(),
+                    reference: self.config.reference.clone(),
+                    rate,
+                    percent: percent.unwrap_or_default(),
+                    recovered,
+                });
+            }
+        }
+    }
+}
+
+async fn fetch(client: &reqwest::Client, symbols: &[String]) -> anyhow::Result<Vec<BinanceTicker>> {
+    // `symbols=["USDCUSDT","FDUSDUSDT"]`, URL-encoded; symbols are
+    // alphanumeric.
+    let url = format!(
+        "{}/api/v3/ticker/price?symbols=%5B%22{}%22%5D",
+        config::get().network.endpoint(urls::BINANCE_REST_SPOT),
+        symbols.join("%22%2C%22")
+    );
+    let response = client
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.json().await?)
+}
@@ -0,0 +1,98 @@
+//! Catching up on the market after a feed reconnects (`[feeds] gap_fill`).
+//!
+//! While a connection is down and resubscribing, the tracker holds the last
+//! quotes it streamed, which may be well off the market by the time the
+//! feed is back. As soon as a feed connection reconnects, every symbol it
+//! carries is read once over REST (see [`failover::poll`]) and published as
+//! a polled quote, so the tracker and the latest-quote cells are current
+//! again before the first streamed update arrives. A snapshot that loses
+//! the race to a streamed quote is dropped instead of overwriting it. The
+//! first connection of a feed is left alone: there is nothing to catch up
+//! on yet.
+
+use std::{collections::HashSet, sync::Arc};
+
+use futures_util::future::join_all;
+use log::warn;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    binance::ws_handler::ConnectionEvent,
+    control::Control,
+    failover,
+    models::ids::{ExchangeId, Symbol},
+    net,
+    ws::quote_bus::QuoteBus,
+};
+
+/// Watches `events` and, each time a feed connection `control` has open
+/// comes back after dropping, publishes a REST snapshot of its symbols to
+/// `quotes`; until `cancel` fires.
+pub fn spawn(
+    quotes: QuoteBus,
+    control: Control,
+    mut events: broadcast::Receiver<ConnectionEvent>,
+    cancel: CancellationToken,
+) {
+    tokio::spawn(async move {
+        let client = net::http_client();
+        // Connections that dropped since they last connected.
+        let mut dropped: HashSet<String> = HashSet::new();
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = cancel.cancelled() => break,
+            };
+            match event {
+                Ok(ConnectionEvent::Disconnected { url, .. }) => {
+                    dropped.insert(url);
+                }
+                Ok(ConnectionEvent::Connected { url }) if dropped.remove(&url) => {
+                    let venues = control.feeds().venues_on(&url);
+                    if !venues.is_empty() {
+                        fill(&client, &quotes, &url, venues).await;
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Reads every venue in `venues` over REST at once and publishes the
+/// snapshots no streamed quote beat.
+async fn fill(
+    client: &reqwest::Client,
+    quotes: &QuoteBus,
+    url: &str,
+    venues: Vec<(ExchangeId, &'static str)>,
+) {
+    let snapshots = venues.into_iter().map(|(exchange, symbol)| async move {
+        let cell = quotes.latest(exchange, symbol);
+        let before = cell.load();
+        let snapshot = failover::poll(client, exchange, Symbol::intern(symbol)).await;
+        (exchange, symbol, cell, before, snapshot)
+    });
+    let mut filled = 0;
+    for (exchange, symbol, cell, before, snapshot) in join_all(snapshots).await {
+        match snapshot {
+            Ok(top) => {
+                let streamed = match (cell.load(), before) {
+                    (Some(now), Some(before)) => !Arc::ptr_eq(&now, &before),
+                    (now, _) => now.is_some(),
+                };
+                if !streamed {
+                    quotes.publish_polled(exchange, top);
+                    filled += 1;
+                }
+            }
+            Err(e) => warn!("Gap fill for {} {} failed: {}", exchange, symbol, e),
+        }
+    }
+    println!(
+        "🩹 {} reconnected; {} quote(s) caught up over REST",
+        url, filled
+    );
+}
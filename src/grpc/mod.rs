@@ -0,0 +1,227 @@
+//! gRPC streams of the bot's data for external consumers, e.g. a separate
+//! execution service or a research notebook.
+//!
+//! Enabled by `[grpc] listen`. The service is defined in
+//! `proto/arbitrage.proto`; every call must carry `authorization: Bearer
+//! <token>` metadata with the token from `GRPC_TOKEN`. A subscriber that
+//! falls behind skips ahead instead of slowing the bot down, and every stream
+//! ends when the engine shuts down.
+
+use std::{collections::HashSet, net::SocketAddr, pin::Pin};
+
+use futures_util::{future, stream, Stream, StreamExt};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::{BroadcastStream, TcpListenerStream, WatchStream};
+use tokio_util::sync::CancellationToken;
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    transport::Server,
+    Request, Response, Status,
+};
+
+use crate::{
+    control::Control,
+    error::ControlError,
+    models::{money, orderbook, orderbook::MarketType},
+    secret::SecretString,
+    state::{self, ExecutionState, LegSide, OrderLeg},
+    ws::quote_bus,
+};
+
+pub mod proto {
+    tonic::include_proto!("arbitrage.v1");
+}
+
+use proto::arbitrage_feed_server::{ArbitrageFeed, ArbitrageFeedServer};
+
+/// Binds `addr` and serves the streams until `cancel` fires.
+pub async fn serve(
+    addr: SocketAddr,
+    token: SecretString,
+    control: Control,
+    cancel: CancellationToken,
+) -> Result<(), ControlError> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|source| ControlError::Bind { addr, source })?;
+    let expected = SecretString::from(format!("Bearer {}", token.expose()));
+    if expected.expose().parse::<MetadataValue<Ascii>>().is_err() {
+        return Err(ControlError::MissingToken("a valid GRPC_TOKEN"));
+    }
+    let feed = Feed {
+        control,
+        cancel: cancel.clone(),
+    };
+    // `Status` as the error is tonic's interceptor signature.
+    #[allow(clippy::result_large_err)]
+    let service = ArbitrageFeedServer::with_interceptor(feed, move |request: Request<()>| {
+        match request.metadata().get("authorization") {
+            Some(token) if token.to_str().is_ok_and(|t| expected.matches(t)) => Ok(request),
+            _ => Err(Status::unauthenticated("missing or invalid token")),
+        }
+    });
+    tokio::spawn(async move {
+        let server = Server::builder()
+            .add_service(service)
+            .serve_with_incoming_shutdown(
+                TcpListenerStream::new(listener),
+                cancel.cancelled_owned(),
+            );
+        if let Err(e) = server.await {
+            eprintln!("❌ gRPC server stopped: {}", e);
+        }
+    });
+    Ok(())
+}
+
+type EventStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+struct Feed {
+    control: Control,
+    cancel: CancellationToken,
+}
+
+impl Feed {
+    /// Boxes `events`, ending them when the engine shuts down.
+    fn stream<T: Send + 'static>(
+        &self,
+        events: impl Stream<Item = T> + Send + 'static,
+    ) -> Response<EventStream<T>> {
+        let events = events
+            .map(Ok)
+            .take_until(self.cancel.clone().cancelled_owned());
+        Response::new(Box::pin(events))
+    }
+}
+
+#[tonic::async_trait]
+impl ArbitrageFeed for Feed {
+    type StreamQuotesStream = EventStream<proto::Quote>;
+    type StreamOpportunitiesStream = EventStream<proto::Opportunity>;
+    type StreamExecutionStream = EventStream<proto::ExecutionEvent>;
+
+    async fn stream_quotes(
+        &self,
+        request: Request<proto::QuoteFilter>,
+    ) -> Result<Response<Self::StreamQuotesStream>, Status> {
+        let filter = request.into_inner();
+        let exchanges = lower_set(filter.exchanges);
+        let symbols = upper_set(filter.symbols);
+        // Lagged receivers yield an error once, then carry on from the oldest
+        // quote still buffered.
+        let quotes = BroadcastStream::new(self.control.subscribe_quotes()).filter_map(move |q| {
+            future::ready(q.ok().filter(|q| {
+                (exchanges.is_empty() || exchanges.contains(q.exchange.name()))
+                    && (symbols.is_empty() || symbols.contains(q.top.symbol.as_str()))
+            }))
+        });
+        Ok(self.stream(quotes.map(|q| proto::Quote::from(&*q))))
+    }
+
+    async fn stream_opportunities(
+        &self,
+        request: Request<proto::OpportunityFilter>,
+    ) -> Result<Response<Self::StreamOpportunitiesStream>, Status> {
+        let filter = request.into_inner();
+        let symbols = upper_set(filter.symbols);
+        let min_percent = match filter.min_percent.as_str() {
+            "" => None,
+            percent => Some(money::parse(percent).ok_or_else(|| {
+                Status::invalid_argument(format!("min_percent {:?} is not a number", percent))
+            })?),
+        };
+        let opportunities = BroadcastStream::new(self.control.subscribe_opportunities())
+            .filter_map(move |o| {
+                future::ready(o.ok().filter(|o| {
+                    (symbols.is_empty() || symbols.contains(o.symbol.as_str()))
+                        && min_percent.is_none_or(|min| o.diff_percent >= min)
+                }))
+            });
+        Ok(self.stream(opportunities.map(|o| proto::Opportunity::from(&o))))
+    }
+
+    async fn stream_execution(
+        &self,
+        _request: Request<proto::ExecutionFilter>,
+    ) -> Result<Response<Self::StreamExecutionStream>, Status> {
+        let mut execution = self.control.subscribe_execution();
+        let mut seen = execution.borrow_and_update().recorded;
+        // Orders recorded in quick succession can arrive as one change.
+        let events = WatchStream::from_changes(execution).flat_map(move |state| {
+            let events: Vec<_> = state
+                .since(seen)
+                .iter()
+                .map(|leg| execution_event(leg, &state))
+                .collect();
+            seen = state.recorded;
+            stream::iter(events)
+        });
+        Ok(self.stream(events))
+    }
+}
+
+// ── Conversions ──────────────────────────────────────────────────────────────
+
+impl From<&quote_bus::Quote> for proto::Quote {
+    fn from(quote: &quote_bus::Quote) -> Self {
+        Self {
+            exchange: quote.exchange.name().to_string(),
+            symbol: quote.top.symbol.to_string(),
+            bid: quote.top.bid.to_string(),
+            ask: quote.top.ask.to_string(),
+            market_type: match quote.top.market_type {
+                MarketType::Spot => "spot",
+                MarketType::Futures => "futures",
+            }
+            .to_string(),
+            update_id: quote.top.update_id,
+            at_ms: state::now_ms(),
+        }
+    }
+}
+
+impl From<&orderbook::Opportunity> for proto::Opportunity {
+    fn from(o: &orderbook::Opportunity) -> Self {
+        Self {
+            symbol: o.symbol.to_string(),
+            exchange_a: o.exchange_a.name().to_string(),
+            exchange_b: o.exchange_b.name().to_string(),
+            mid_a: o.mid_a.to_string(),
+            mid_b: o.mid_b.to_string(),
+            diff_percent: o.diff_percent.to_string(),
+            at_ms: o.at_ms,
+        }
+    }
+}
+
+fn execution_event(leg: &OrderLeg, state: &ExecutionState) -> proto::ExecutionEvent {
+    proto::ExecutionEvent {
+        order: Some(proto::OrderLeg {
+            exchange: leg.exchange.name().to_string(),
+            symbol: leg.symbol.clone(),
+            side: match leg.side {
+                LegSide::Buy => "buy",
+                LegSide::Sell => "sell",
+            }
+            .to_string(),
+            price: leg.price.to_string(),
+            quantity: leg.quantity.to_string(),
+            order_id: leg.order_id.clone(),
+            error: leg.error.clone(),
+            placed_at_ms: leg.placed_at_ms,
+        }),
+        exposure: state
+            .exposure
+            .iter()
+            .map(|(exchange, quantity)| (exchange.name().to_string(), quantity.to_string()))
+            .collect(),
+    }
+}
+
+fn lower_set(names: Vec<String>) -> HashSet<String> {
+    names.iter().map(|n| n.to_lowercase()).collect()
+}
+
+fn upper_set(names: Vec<String>) -> HashSet<String> {
+    names.iter().map(|n| n.to_uppercase()).collect()
+}
@@ -0,0 +1,272 @@
+//! When spreads occur: opportunities by hour of day (UTC) and symbol.
+//!
+//! The engine counts every opportunity the tracker finds into its symbol's
+//! cell for the hour it was found in, along with the widest spread seen
+//! there. With `[engine] heatmap_file` set the grid is loaded on startup
+//! and saved every minute and on shutdown, so it builds up across runs.
+//! `arbitrage-bot heatmap` prints it from that file, `GET /heatmap` serves
+//! it, and the web dashboard shows it.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::EngineConfig,
+    error::StorageError,
+    models::{money::Decimal, orderbook::Opportunity},
+};
+
+pub const HOURS: usize = 24;
+
+/// The opportunities of one symbol in one hour of the day.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Cell {
+    pub opportunities: u64,
+    /// The widest spread, in percent.
+    pub max_percent: Decimal,
+}
+
+/// Every symbol's cells, one per hour of the day in UTC.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Grid {
+    /// When counting started.
+    pub since_ms: i64,
+    pub symbols: BTreeMap<String, [Cell; HOURS]>,
+}
+
+impl Grid {
+    /// Counts `opportunity` into its symbol's cell for the hour it was found.
+    ///
+    /// ```
+    /// use arbitrage_bot::{
+    ///     heatmap::Grid,
+    ///     models::{ids::{ExchangeId, Symbol}, orderbook::Opportunity},
+    /// };
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let mut grid = Grid::default();
+    /// grid.record(&Opportunity {
+    ///     symbol: Symbol::intern("BTCUSDT"),
+    ///     exchange_a: ExchangeId::Binance,
+    ///     exchange_b: ExchangeId::Bybit,
+    ///     mid_a: dec!(100),
+    ///     mid_b: dec!(101),
+    ///     diff_percent: dec!(1),
+    ///     // 14:30 UTC
+    ///     at_ms: 52_200_000,
+    /// });
+    /// assert_eq!(grid.symbols["BTCUSDT"][14].opportunities, 1);
+    /// assert_eq!(grid.busiest("BTCUSDT"), Some(14));
+    /// ```
+    pub fn record(&mut self, opportunity: &Opportunity) {
+        let Some(hour) = hour_of(opportunity.at_ms) else {
+            return;
+        };
+        if self.since_ms == 0 {
+            self.since_ms = opportunity.at_ms;
+        }
+        let cell = &mut self
+            .symbols
+            .entry(opportunity.symbol.to_string())
+            .or_default()[hour];
+        cell.opportunities += 1;
+        cell.max_percent = cell.max_percent.max(opportunity.diff_percent);
+    }
+
+    /// The hour `symbol` had the most opportunities in, if it had any.
+    pub fn busiest(&self, symbol: &str) -> Option<usize> {
+        let cells = self.symbols.get(symbol)?;
+        (0..HOURS)
+            .filter(|&hour| cells[hour].opportunities > 0)
+            .max_by_key(|&hour| (cells[hour].opportunities, std::cmp::Reverse(hour)))
+    }
+
+    /// Opportunities per hour across every symbol.
+    pub fn totals(&self) -> [u64; HOURS] {
+        let mut totals = [0; HOURS];
+        for cells in self.symbols.values() {
+            for (total, cell) in totals.iter_mut().zip(cells) {
+                *total += cell.opportunities;
+            }
+        }
+        totals
+    }
+
+    /// Reads the grid saved at `path`; `Ok(None)` if there is none yet.
+    pub fn load(path: &Path) -> Result<Option<Self>, StorageError> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(StorageError::io(path)(e)),
+        };
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    /// Writes the grid to `path`, replacing what was there.
+    pub fn save(&self, path: &Path) -> Result<(), StorageError> {
+        let json = serde_json::to_string(self)?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json).map_err(StorageError::io(&tmp))?;
+        fs::rename(&tmp, path).map_err(StorageError::io(path))
+    }
+
+    /// The grid as a table of counts, or of the widest spreads with `max`.
+    pub fn table(&self, max: bool) -> String {
+        let width = self
+            .symbols
+            .keys()
+            .map(String::len)
+            .max()
+            .unwrap_or(0)
+            .max(6);
+        let mut out = format!("{:width$}", "UTC", width = width);
+        for hour in 0..HOURS {
+            out.push_str(&format!(" {:>5}", format!("{:02}", hour)));
+        }
+        out.push_str("  busiest\n");
+        for (symbol, cells) in &self.symbols {
+            out.push_str(&format!("{:width$}", symbol, width = width));
+            for cell in cells {
+                let value = match (cell.opportunities, max) {
+                    (0, _) => ".".to_string(),
+                    (_, true) => cell.max_percent.round_dp(2).normalize().to_string(),
+                    (n, false) => compact(n),
+                };
+                out.push_str(&format!(" {:>5}", value));
+            }
+            if let Some(hour) = self.busiest(symbol) {
+                out.push_str(&format!("  {:02}:00", hour));
+            }
+            out.push('\n');
+        }
+        if !max {
+            out.push_str(&format!("{:width$}", "all", width = width));
+            for total in self.totals() {
+                out.push_str(&format!(" {:>5}", compact(total)));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// The hour of the day, in UTC, `at_ms` falls in.
+fn hour_of(at_ms: i64) -> Option<usize> {
+    chrono::DateTime::from_timestamp_millis(at_ms).map(|t| t.hour() as usize)
+}
+
+/// `n` in at most five characters.
+fn compact(n: u64) -> String {
+    match n {
+        0..=99_999 => n.to_string(),
+        100_000..=9_999_999 => format!("{}k", n / 1000),
+        _ => format!("{}M", n / 1_000_000),
+    }
+}
+
+/// The grid, shared by the task counting into it and its readers.
+#[derive(Debug, Clone, Default)]
+pub struct Heatmap {
+    grid: Arc<Mutex<Grid>>,
+}
+
+impl Heatmap {
+    /// Carries on counting into `grid`.
+    pub fn new(grid: Grid) -> Self {
+        Self {
+            grid: Arc::new(Mutex::new(grid)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Grid> {
+        self.grid.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn grid(&self) -> Grid {
+        self.lock().clone()
+    }
+
+    pub fn record(&self, opportunity: &Opportunity) {
+        self.lock().record(opportunity);
+    }
+
+    /// Counts what `opportunities` receives until `cancel` fires. Must be
+    /// called inside a Tokio runtime.
+    pub fn follow(
+        &self,
+        mut opportunities: broadcast::Receiver<Opportunity>,
+        cancel: CancellationToken,
+    ) {
+        let heatmap = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let opportunity = tokio::select! {
+                    opportunity = opportunities.recv() => opportunity,
+                    _ = cancel.cancelled() => break,
+                };
+                match opportunity {
+                    Ok(opportunity) => heatmap.record(&opportunity),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Writes the grid to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), StorageError> {
+        self.grid().save(path)
+    }
+}
+
+/// `arbitrage-bot heatmap [--max]`: prints the grid saved at `[engine]
+/// heatmap_file`, counts by default and the widest spreads with `--max`.
+pub fn run_cli(args: &[String], config: &EngineConfig) {
+    let max = match args {
+        [] => false,
+        [flag] if flag == "--max" => true,
+        _ => {
+            eprintln!("Usage: arbitrage-bot heatmap [--max]");
+            std::process::exit(1);
+        }
+    };
+    let Some(path) = &config.heatmap_file else {
+        eprintln!("❌ [engine] heatmap_file is not set");
+        std::process::exit(1);
+    };
+    let grid = match Grid::load(path) {
+        Ok(Some(grid)) if !grid.symbols.is_empty() => grid,
+        Ok(_) => {
+            println!("No opportunities recorded in {} yet", path.display());
+            return;
+        }
+        Err(e) => {
+            eprintln!("❌ Reading the heatmap failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let since = chrono::DateTime::from_timestamp_millis(grid.since_ms)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default();
+    println!(
+        "🔥 {} by hour of day since {}\n",
+        if max {
+            "Widest spread (%)"
+        } else {
+            "Opportunities"
+        },
+        since
+    );
+    print!("{}", grid.table(max));
+}
@@ -0,0 +1,185 @@
+//! AWS Secrets Manager provider (`[keys.aws]`): one secret whose
+//! `SecretString` is a JSON object keyed like the environment variables,
+//! e.g. `{"API_KEY_BINANCE": "...", "SECRET_KEY_BINANCE": "..."}`.
+//!
+//! AWS credentials come from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and,
+//! for temporary ones, `AWS_SESSION_TOKEN`; instance profiles aren't
+//! queried. Requests are signed with Signature Version 4.
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use super::{secrets, CredentialProvider, Secrets, REQUEST_TIMEOUT};
+use crate::{config::AwsSecretsConfig, secret::SecretString};
+
+const SERVICE: &str = "secretsmanager";
+const TARGET: &str = "secretsmanager.GetSecretValue";
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+
+pub struct AwsSecretsProvider {
+    client: reqwest::Client,
+    region: String,
+    secret_id: String,
+    access_key_id: String,
+    secret_access_key: SecretString,
+    session_token: Option<SecretString>,
+}
+
+impl AwsSecretsProvider {
+    pub fn new(config: &AwsSecretsConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+            region: config.region.clone(),
+            secret_id: config.secret_id.clone(),
+            access_key_id: std::env::var("AWS_ACCESS_KEY_ID")
+                .ok()
+                .context("AWS_ACCESS_KEY_ID is not set")?,
+            secret_access_key: std::env::var("AWS_SECRET_ACCESS_KEY")
+                .ok()
+                .context("AWS_SECRET_ACCESS_KEY is not set")?
+                .into(),
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok().map(Into::into),
+        })
+    }
+
+    fn host(&self) -> String {
+        format!("{}.{}.amazonaws.com", SERVICE, self.region)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Response {
+    secret_string: Option<String>,
+}
+
+#[async_trait]
+impl CredentialProvider for AwsSecretsProvider {
+    fn describe(&self) -> String {
+        format!("{} in {}", self.secret_id, self.host())
+    }
+
+    async fn fetch(&self) -> anyhow::Result<Secrets> {
+        let host = self.host();
+        let body = json!({ "SecretId": self.secret_id }).to_string();
+        let now = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+        // Signed headers, sorted by name.
+        let mut headers = vec![
+            ("content-type", CONTENT_TYPE.to_string()),
+            ("host", host.clone()),
+            ("x-amz-date", now.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.expose().to_string()));
+        }
+        headers.push(("x-amz-target", TARGET.to_string()));
+        let signed_headers: Vec<_> = headers.iter().map(|(name, _)| *name).collect();
+        let signed_headers = signed_headers.join(";");
+        let canonical_request = format!(
+            "POST\n/\n\n{}\n{}\n{}",
+            headers
+                .iter()
+                .map(|(name, value)| format!("{}:{}\n", name, value))
+                .collect::<String>(),
+            signed_headers,
+            hex::encode(Sha256::digest(body.as_bytes()))
+        );
+        let signature = signature(
+            self.secret_access_key.expose(),
+            &now,
+            &self.region,
+            SERVICE,
+            &canonical_request,
+        );
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}/{}/{}/aws4_request, SignedHeaders={}, Signature={}",
+            self.access_key_id,
+            &now[..8],
+            self.region,
+            SERVICE,
+            signed_headers,
+            signature
+        );
+
+        let mut request = self.client.post(format!("https://{}/", host));
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.header(*name, value);
+        }
+        let response = request
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            bail!("secrets manager answered {}: {}", status, text);
+        }
+        let response: Response = response.json().await?;
+        let Some(secret) = response.secret_string else {
+            bail!("{} has no SecretString", self.secret_id);
+        };
+        let plain = serde_json::from_str(&secret)
+            .with_context(|| format!("{} is not a JSON object of strings", self.secret_id))?;
+        Ok(secrets(plain))
+    }
+}
+
+/// The Signature Version 4 signature of `canonical_request`, made at
+/// `date_time` (`YYYYMMDDTHHMMSSZ`).
+///
+/// ```
+/// use arbitrage_bot::keys::aws::signature;
+///
+/// // The example from the AWS Signature Version 4 documentation.
+/// let canonical_request = "GET\n/\nAction=ListUsers&Version=2010-05-08\n\
+///     content-type:application/x-www-form-urlencoded; charset=utf-8\n\
+///     host:iam.amazonaws.com\nx-amz-date:20150830T123600Z\n\n\
+///     content-type;host;x-amz-date\n\
+///     e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+/// assert_eq!(
+///     signature(
+///         "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+///         "20150830T123600Z",
+///         "us-east-1",
+///         "iam",
+///         canonical_request,
+///     ),
+///     "5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+/// );
+/// ```
+pub fn signature(
+    secret_access_key: &str,
+    date_time: &str,
+    region: &str,
+    service: &str,
+    canonical_request: &str,
+) -> String {
+    let date = &date_time[..8];
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        date_time,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let mut key = hmac(format!("AWS4{}", secret_access_key).as_bytes(), date);
+    for part in [region, service, "aws4_request"] {
+        key = hmac(&key, part);
+    }
+    hex::encode(hmac(&key, &string_to_sign))
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,216 @@
+//! The encrypted key file (`[keys] file`): AES-256-GCM under a key derived
+//! from a passphrase with Argon2id, written by `arbitrage-bot keys import`.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+
+use super::{secrets, CredentialProvider, Secrets, SECRETS};
+use crate::{config::KeysConfig, secret::SecretString};
+
+/// Re-reads the key file on every fetch, so `keys import` rotates secrets
+/// without a restart. The passphrase is kept for that.
+pub struct FileProvider {
+    path: PathBuf,
+    passphrase: SecretString,
+}
+
+impl FileProvider {
+    /// Reads the passphrase from `[keys] passphrase_file`, or prompts for it.
+    pub fn new(path: PathBuf, config: &KeysConfig) -> anyhow::Result<Self> {
+        let passphrase = passphrase(&path, config).context(
+            "reading the passphrase (set [keys] passphrase_file when there is no terminal)",
+        )?;
+        Ok(Self {
+            path,
+            passphrase: passphrase.into(),
+        })
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for FileProvider {
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    async fn fetch(&self) -> anyhow::Result<Secrets> {
+        // Key derivation takes a while on purpose; keep it off the runtime.
+        let (path, passphrase) = (self.path.clone(), self.passphrase.clone());
+        let plain =
+            tokio::task::spawn_blocking(move || KeyFile::read(&path)?.decrypt(passphrase.expose()))
+                .await??;
+        Ok(secrets(plain))
+    }
+}
+
+/// Adds the secrets set in the environment to `path`, creating it if needed.
+pub fn import(path: &Path) -> anyhow::Result<()> {
+    let found: BTreeMap<String, String> = SECRETS
+        .iter()
+        .filter_map(|name| Some((name.to_string(), std::env::var(name).ok()?)))
+        .collect();
+    if found.is_empty() {
+        bail!("none of {} is set", SECRETS.join(", "));
+    }
+    let (passphrase, mut secrets) = if path.exists() {
+        let passphrase =
+            rpassword::prompt_password(format!("🔑 Passphrase for {}: ", path.display()))?;
+        let secrets = KeyFile::read(path)?.decrypt(&passphrase)?;
+        (passphrase, secrets)
+    } else {
+        let passphrase = rpassword::prompt_password("🔑 New passphrase: ")?;
+        if passphrase.is_empty() {
+            bail!("the passphrase can't be empty");
+        }
+        if rpassword::prompt_password("🔑 Repeat it: ")? != passphrase {
+            bail!("the passphrases don't match");
+        }
+        (passphrase, BTreeMap::new())
+    };
+    let names: Vec<_> = found.keys().cloned().collect();
+    secrets.extend(found);
+    KeyFile::encrypt(&secrets, &passphrase)?.write(path)?;
+    println!("🔐 Stored {} in {}", names.join(", "), path.display());
+    println!(
+        "   Set [keys] file = {:?} and remove them from .env and the environment.",
+        path.display().to_string()
+    );
+    Ok(())
+}
+
+pub fn list(path: &Path, config: &KeysConfig) -> anyhow::Result<()> {
+    let passphrase = passphrase(path, config)?;
+    for name in KeyFile::read(path)?.decrypt(&passphrase)?.keys() {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+fn passphrase(path: &Path, config: &KeysConfig) -> anyhow::Result<String> {
+    match &config.passphrase_file {
+        Some(file) => {
+            let raw = std::fs::read_to_string(file)
+                .with_context(|| format!("reading the passphrase from {}", file.display()))?;
+            Ok(raw.trim_end_matches(['\r', '\n']).to_string())
+        }
+        None => Ok(rpassword::prompt_password(format!(
+            "🔑 Passphrase for {}: ",
+            path.display()
+        ))?),
+    }
+}
+
+/// The key file as stored: JSON with base64 fields. The Argon2 parameters
+/// are kept so a file stays readable if the defaults change.
+#[derive(Debug, Serialize, Deserialize)]
+struct KeyFile {
+    version: u32,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl KeyFile {
+    const VERSION: u32 = 1;
+
+    fn encrypt(secrets: &BTreeMap<String, String>, passphrase: &str) -> anyhow::Result<Self> {
+        use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit, Nonce};
+        use rand::RngCore;
+
+        let params = argon2::Params::default();
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        rand::rngs::OsRng.fill_bytes(&mut salt);
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let key = derive_key(
+            passphrase,
+            &salt,
+            params.m_cost(),
+            params.t_cost(),
+            params.p_cost(),
+        )?;
+        let plaintext = serde_json::to_vec(secrets)?;
+        let ciphertext = Aes256Gcm::new(&key.into())
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| anyhow!("encryption failed"))?;
+        Ok(Self {
+            version: Self::VERSION,
+            m_cost: params.m_cost(),
+            t_cost: params.t_cost(),
+            p_cost: params.p_cost(),
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    fn decrypt(&self, passphrase: &str) -> anyhow::Result<BTreeMap<String, String>> {
+        use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit, Nonce};
+
+        if self.version != Self::VERSION {
+            bail!("unsupported key file version {}", self.version);
+        }
+        let salt = BASE64.decode(&self.salt).context("invalid salt")?;
+        let nonce = BASE64.decode(&self.nonce).context("invalid nonce")?;
+        let ciphertext = BASE64
+            .decode(&self.ciphertext)
+            .context("invalid ciphertext")?;
+        if nonce.len() != 12 {
+            bail!("invalid nonce");
+        }
+        let key = derive_key(passphrase, &salt, self.m_cost, self.t_cost, self.p_cost)?;
+        let plaintext = Aes256Gcm::new(&key.into())
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| anyhow!("wrong passphrase or corrupted key file"))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn read(path: &Path) -> anyhow::Result<Self> {
+        let raw =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&raw).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Replaces `path` atomically, readable by the owner only.
+    fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let tmp = path.with_extension("tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options
+            .open(&tmp)
+            .with_context(|| format!("writing {}", tmp.display()))?;
+        serde_json::to_writer_pretty(&file, self)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path).with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> anyhow::Result<[u8; 32]> {
+    use argon2::{Algorithm, Argon2, Params, Version};
+
+    let params =
+        Params::new(m_cost, t_cost, p_cost, Some(32)).map_err(|e| anyhow!("argon2: {}", e))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("argon2: {}", e))?;
+    Ok(key)
+}
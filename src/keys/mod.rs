@@ -0,0 +1,209 @@
+//! Secrets: exchange keys and service tokens, read from a credential
+//! provider (`[keys]`) or the environment.
+//!
+//! Providers (`keystore` feature):
+//! - [`file::FileProvider`]: the encrypted key file written by
+//!   `arbitrage-bot keys import`, unlocked with a passphrase.
+//! - [`vault::VaultProvider`]: a HashiCorp Vault KV v2 secret.
+//! - [`aws::AwsSecretsProvider`]: an AWS Secrets Manager secret.
+//!
+//! [`unlock`] fetches the secrets once at startup and then every
+//! `refresh_secs`, so rotated keys are picked up without a restart; whoever
+//! holds on to a secret watches [`rotations`]. [`var`] serves the latest
+//! ones. A secret the provider doesn't have still falls back to its
+//! environment variable.
+
+#[cfg(feature = "keystore")]
+pub mod aws;
+#[cfg(feature = "keystore")]
+pub mod file;
+#[cfg(feature = "keystore")]
+pub mod vault;
+
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use arc_swap::ArcSwap;
+use tokio::sync::watch;
+
+#[cfg(feature = "keystore")]
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+#[cfg(feature = "keystore")]
+use async_trait::async_trait;
+
+use crate::{config::KeysConfig, secret::SecretString};
+
+/// The environment variables `keys import` stores.
+pub const SECRETS: &[&str] = &[
+    "API_KEY_BINANCE",
+    "SECRET_KEY_BINANCE",
+    "API_KEY_BYBIT",
+    "SECRET_KEY_BYBIT",
+    "TELEGRAM_KEY",
+    "CONTROL_API_TOKEN",
+    "CONTROL_API_READ_TOKEN",
+    "WEBHOOK_TOKEN",
+    "GRPC_TOKEN",
+    "MQTT_PASSWORD",
+    "INFLUX_TOKEN",
+    "AUDIT_LOG_KEY",
+];
+
+/// Where `keys` commands read and write when `[keys] file` is unset.
+pub const DEFAULT_FILE: &str = "keys.enc";
+
+#[cfg(feature = "keystore")]
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Secrets keyed by environment variable name.
+pub type Secrets = BTreeMap<String, SecretString>;
+
+static CURRENT: LazyLock<ArcSwap<Secrets>> = LazyLock::new(Default::default);
+static ROTATIONS: LazyLock<watch::Sender<u64>> = LazyLock::new(|| watch::Sender::new(0));
+
+/// Where secrets come from.
+#[cfg(feature = "keystore")]
+#[async_trait]
+pub trait CredentialProvider: Send + Sync {
+    /// Shown in logs, e.g. the key file's path.
+    fn describe(&self) -> String;
+
+    /// Every secret the provider has, keyed by environment variable name.
+    async fn fetch(&self) -> anyhow::Result<Secrets>;
+}
+
+/// The secret `name`, from the provider or else the environment.
+pub fn var(name: &str) -> Option<SecretString> {
+    CURRENT
+        .load()
+        .get(name)
+        .cloned()
+        .or_else(|| std::env::var(name).ok().map(SecretString::from))
+}
+
+/// Changes every time a refresh brings different secrets.
+pub fn rotations() -> watch::Receiver<u64> {
+    ROTATIONS.subscribe()
+}
+
+/// Fetches the secrets from the configured provider, if any, and keeps
+/// refreshing them on the current runtime.
+#[cfg(feature = "keystore")]
+pub async fn unlock(config: &KeysConfig) -> anyhow::Result<()> {
+    let Some(provider) = provider(config)? else {
+        return Ok(());
+    };
+    let secrets = provider.fetch().await?;
+    println!(
+        "🔓 Loaded {} secrets from {}",
+        secrets.len(),
+        provider.describe()
+    );
+    CURRENT.store(Arc::new(secrets));
+    if config.refresh_secs > 0 {
+        tokio::spawn(refresh(provider, config.refresh()));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "keystore"))]
+pub async fn unlock(config: &KeysConfig) -> anyhow::Result<()> {
+    if config.file.is_some() || config.vault.is_some() || config.aws.is_some() {
+        eprintln!("⚠️ [keys] is set, but this build has no `keystore` feature");
+    }
+    Ok(())
+}
+
+/// Wraps what a provider parsed.
+#[cfg(feature = "keystore")]
+fn secrets(plain: BTreeMap<String, String>) -> Secrets {
+    plain
+        .into_iter()
+        .map(|(name, value)| (name, value.into()))
+        .collect()
+}
+
+#[cfg(feature = "keystore")]
+fn provider(config: &KeysConfig) -> anyhow::Result<Option<Box<dyn CredentialProvider>>> {
+    Ok(if let Some(path) = &config.file {
+        Some(Box::new(file::FileProvider::new(path.clone(), config)?))
+    } else if let Some(vault) = &config.vault {
+        Some(Box::new(vault::VaultProvider::new(vault)?))
+    } else if let Some(aws) = &config.aws {
+        Some(Box::new(aws::AwsSecretsProvider::new(aws)?))
+    } else {
+        None
+    })
+}
+
+#[cfg(feature = "keystore")]
+async fn refresh(provider: Box<dyn CredentialProvider>, every: Duration) {
+    let mut failing = false;
+    loop {
+        tokio::time::sleep(every).await;
+        match provider.fetch().await {
+            Ok(secrets) => {
+                if failing {
+                    println!("✅ Secrets from {} readable again", provider.describe());
+                    failing = false;
+                }
+                if **CURRENT.load() == secrets {
+                    continue;
+                }
+                let changed: Vec<_> = {
+                    let current = CURRENT.load();
+                    secrets
+                        .iter()
+                        .filter(|(name, value)| current.get(*name) != Some(value))
+                        .map(|(name, _)| name.as_str())
+                        .collect()
+                };
+                println!(
+                    "🔑 Secrets rotated in {}: {}",
+                    provider.describe(),
+                    changed.join(", ")
+                );
+                CURRENT.store(Arc::new(secrets));
+                ROTATIONS.send_modify(|n| *n += 1);
+            }
+            // Reported once per outage; the secrets already loaded stay in use.
+            Err(e) if !failing => {
+                eprintln!(
+                    "❌ Refreshing secrets from {} failed: {:#}",
+                    provider.describe(),
+                    e
+                );
+                failing = true;
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// `arbitrage-bot keys import|list [FILE]`.
+#[cfg(feature = "keystore")]
+pub fn run_cli(args: &[String], config: &KeysConfig) {
+    let path = args
+        .get(1)
+        .map(PathBuf::from)
+        .or_else(|| config.file.clone())
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_FILE));
+    let result = match args.first().map(String::as_str) {
+        Some("import") => file::import(&path),
+        Some("list") => file::list(&path, config),
+        _ => {
+            eprintln!("Usage: arbitrage-bot keys import|list [FILE]");
+            return;
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("❌ {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "keystore"))]
+pub fn run_cli(_args: &[String], _config: &KeysConfig) {
+    eprintln!("❌ keys commands need the `keystore` feature");
+    std::process::exit(1);
+}
@@ -0,0 +1,70 @@
+//! HashiCorp Vault provider (`[keys.vault]`): one KV v2 secret whose fields
+//! are named like the environment variables, e.g. `API_KEY_BINANCE`. The
+//! Vault token comes from `VAULT_TOKEN`.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{secrets, CredentialProvider, Secrets, REQUEST_TIMEOUT};
+use crate::{config::VaultConfig, secret::SecretString};
+
+pub struct VaultProvider {
+    client: reqwest::Client,
+    url: String,
+    token: SecretString,
+}
+
+impl VaultProvider {
+    pub fn new(config: &VaultConfig) -> anyhow::Result<Self> {
+        let token = std::env::var("VAULT_TOKEN")
+            .ok()
+            .context("VAULT_TOKEN is not set")?;
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+            url: format!(
+                "{}/v1/{}/data/{}",
+                config.addr.trim_end_matches('/'),
+                config.mount,
+                config.path
+            ),
+            token: token.into(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    data: Data,
+}
+
+#[derive(Deserialize)]
+struct Data {
+    data: BTreeMap<String, String>,
+}
+
+#[async_trait]
+impl CredentialProvider for VaultProvider {
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+
+    async fn fetch(&self) -> anyhow::Result<Secrets> {
+        let response = self
+            .client
+            .get(&self.url)
+            .header("X-Vault-Token", self.token.expose())
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!("vault answered {}", status);
+        }
+        let body: Response = response.json().await.context("parsing the vault secret")?;
+        Ok(secrets(body.data.data))
+    }
+}
@@ -0,0 +1,152 @@
+//! Per-venue latency, measured as the bot runs.
+//!
+//! Two numbers are kept per exchange, each a moving average: the feed
+//! round trip, from every keepalive ping to its pong (frames on Binance,
+//! `{"op":"ping"}` on Bybit), and the order round trip, from sending an
+//! order to its acknowledgement. Every WebSocket connection and every
+//! engine report to [`Latency::global`].
+//!
+//! A trade is only as quick as its slower leg: that leg's quote reached us
+//! about half a ping round trip after it was made, and its order takes an
+//! order round trip to land (a ping round trip until one was timed). The
+//! engine takes `[engine.execution.latency] haircut_percent_per_sec` off an
+//! opportunity's edge for every second of that, and when several pairs of
+//! exchanges clear the threshold with edges within `similar_edge_percent`,
+//! trades the fastest. Exchanges nothing was measured on yet get no
+//! haircut and come last.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+
+use crate::models::{ids::ExchangeId, money::Decimal};
+
+/// Weight of the newest sample in the moving averages.
+const SMOOTHING: f64 = 0.2;
+
+/// What was measured on one exchange.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VenueLatency {
+    /// Ping to pong.
+    pub feed: Option<Duration>,
+    /// Order sent to order acknowledged.
+    pub order: Option<Duration>,
+}
+
+impl VenueLatency {
+    /// How long after a quote was made an order against it lands: half a
+    /// feed round trip plus an order round trip.
+    pub fn expected(&self) -> Option<Duration> {
+        match (self.feed, self.order) {
+            (Some(feed), order) => Some(feed / 2 + order.unwrap_or(feed)),
+            (None, order) => order,
+        }
+    }
+}
+
+/// A pair of exchanges to trade across and its edge, as a fraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Route {
+    pub buy: ExchangeId,
+    pub sell: ExchangeId,
+    pub edge: Decimal,
+}
+
+/// Measured latencies, shared by the feeds and the engine.
+#[derive(Debug, Clone, Default)]
+pub struct Latency {
+    venues: Arc<ArcSwap<HashMap<ExchangeId, VenueLatency>>>,
+}
+
+impl Latency {
+    /// The process-wide measurements every connection and engine feeds.
+    pub fn global() -> &'static Latency {
+        static GLOBAL: OnceLock<Latency> = OnceLock::new();
+        GLOBAL.get_or_init(Latency::default)
+    }
+
+    pub fn get(&self, exchange: ExchangeId) -> VenueLatency {
+        self.venues
+            .load()
+            .get(&exchange)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn record_feed(&self, exchange: ExchangeId, round_trip: Duration) {
+        self.update(exchange, |venue| {
+            venue.feed = Some(smooth(venue.feed, round_trip))
+        });
+    }
+
+    pub fn record_order(&self, exchange: ExchangeId, round_trip: Duration) {
+        self.update(exchange, |venue| {
+            venue.order = Some(smooth(venue.order, round_trip))
+        });
+    }
+
+    fn update(&self, exchange: ExchangeId, change: impl Fn(&mut VenueLatency)) {
+        self.venues.rcu(|venues| {
+            let mut venues = HashMap::clone(venues);
+            change(venues.entry(exchange).or_default());
+            venues
+        });
+    }
+
+    /// How long a trade across `a` and `b` takes to land: the slower
+    /// exchange's [`VenueLatency::expected`].
+    pub fn pair(&self, a: ExchangeId, b: ExchangeId) -> Option<Duration> {
+        match (self.get(a).expected(), self.get(b).expected()) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// The edge (as a fraction) a trade across `a` and `b` is expected to
+    /// lose, at `percent_per_sec` of the price per second it takes.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use arbitrage_bot::{latency::Latency, models::ids::ExchangeId};
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let latency = Latency::default();
+    /// latency.record_feed(ExchangeId::Binance, Duration::from_millis(40));
+    /// latency.record_order(ExchangeId::Binance, Duration::from_millis(80));
+    /// // 20ms for the quote, 80ms for the order, at 0.5% a second.
+    /// assert_eq!(latency.haircut(ExchangeId::Binance, ExchangeId::Bybit, dec!(0.5)), dec!(0.0005));
+    /// ```
+    pub fn haircut(&self, a: ExchangeId, b: ExchangeId, percent_per_sec: Decimal) -> Decimal {
+        self.pair(a, b).map_or(Decimal::ZERO, |latency| {
+            let micros = Decimal::from(latency.as_micros() as u64);
+            micros / Decimal::from(1_000_000) * percent_per_sec / Decimal::ONE_HUNDRED
+        })
+    }
+
+    /// The fastest of `routes` whose edge is within `similar` (a fraction)
+    /// of the best one's; ties go to the bigger edge.
+    pub fn fastest(&self, routes: &[Route], similar: Decimal) -> Option<Route> {
+        let best = routes.iter().map(|r| r.edge).max()?;
+        routes
+            .iter()
+            .filter(|r| best - r.edge <= similar)
+            .min_by_key(|r| {
+                (
+                    self.pair(r.buy, r.sell).unwrap_or(Duration::MAX),
+                    std::cmp::Reverse(r.edge),
+                )
+            })
+            .copied()
+    }
+}
+
+/// The moving average after `sample`; the first sample is taken as is.
+fn smooth(average: Option<Duration>, sample: Duration) -> Duration {
+    average.map_or(sample, |average| {
+        average.mul_f64(1.0 - SMOOTHING) + sample.mul_f64(SMOOTHING)
+    })
+}
@@ -0,0 +1,61 @@
+mod macros;
+
+#[cfg(feature = "execution")]
+pub mod accounts;
+#[cfg(feature = "api")]
+pub mod api;
+#[cfg(feature = "execution")]
+pub mod audit;
+pub mod backtest;
+pub mod binance;
+pub mod bridge;
+pub mod calendar;
+pub mod config;
+pub mod constants;
+pub mod contracts;
+pub mod control;
+pub mod engine;
+pub mod error;
+pub mod events;
+#[cfg(feature = "execution")]
+pub mod expiry;
+pub mod failover;
+pub mod fees;
+pub mod fills;
+pub mod funding;
+pub mod fx;
+pub mod gap_fill;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod heatmap;
+pub mod keys;
+pub mod latency;
+pub mod limits;
+pub mod listing_mode;
+pub mod liveness;
+pub mod logger;
+pub mod models;
+pub mod net;
+pub mod notifications;
+pub mod pauses;
+pub mod plan;
+pub mod portfolio;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod rebalance;
+#[cfg(feature = "execution")]
+pub mod recovery;
+pub mod retry;
+pub mod runtime;
+pub mod secret;
+pub mod session;
+pub mod state;
+pub mod tca;
+pub mod tls;
+#[cfg(feature = "execution")]
+pub mod topup;
+pub mod transfers;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod volatility;
+pub mod ws;
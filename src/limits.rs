@@ -0,0 +1,96 @@
+//! Hard caps for long-lived maps and buffers, and their sizes for reporting.
+//!
+//! Every structure that would otherwise grow for as long as the process runs
+//! registers a [`SizeGauge`] with a cap from `[limits]`. Its owner keeps the
+//! gauge current and, when an insert would go over the cap, prunes and calls
+//! [`SizeGauge::pruned`], which warns. [`size_stats`] lists every live gauge;
+//! the engine prints them every `report_interval_secs`.
+
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc, Mutex, OnceLock,
+};
+
+/// Point-in-time view of one registered gauge.
+#[derive(Debug, Clone)]
+pub struct SizeStats {
+    pub name: String,
+    pub size: usize,
+    pub cap: usize,
+    pub high_water: usize,
+    /// Entries removed because the cap was reached.
+    pub pruned: u64,
+}
+
+pub struct SizeGauge {
+    name: String,
+    cap: usize,
+    size: AtomicUsize,
+    high_water: AtomicUsize,
+    pruned: AtomicU64,
+}
+
+impl SizeGauge {
+    /// Registers a gauge reported under `name`. It is reported for as long as
+    /// the returned handle (or a clone) is alive.
+    pub fn register(name: impl Into<String>, cap: usize) -> Arc<Self> {
+        let gauge = Arc::new(Self {
+            name: name.into(),
+            cap,
+            size: AtomicUsize::new(0),
+            high_water: AtomicUsize::new(0),
+            pruned: AtomicU64::new(0),
+        });
+        if let Ok(mut gauges) = registry().lock() {
+            gauges.push(gauge.clone());
+        }
+        gauge
+    }
+
+    pub fn cap(&self) -> usize {
+        self.cap
+    }
+
+    /// Whether one more entry would go over the cap.
+    pub fn is_full(&self, size: usize) -> bool {
+        size >= self.cap
+    }
+
+    pub fn set(&self, size: usize) {
+        self.size.store(size, Ordering::Relaxed);
+        self.high_water.fetch_max(size, Ordering::Relaxed);
+    }
+
+    /// Records that `removed` entries were pruned to stay under the cap.
+    pub fn pruned(&self, removed: usize) {
+        let total = self.pruned.fetch_add(removed as u64, Ordering::Relaxed) + removed as u64;
+        eprintln!(
+            "⚠️ {} reached its cap of {}; pruned {} (total {})",
+            self.name, self.cap, removed, total
+        );
+    }
+
+    fn snapshot(&self) -> SizeStats {
+        SizeStats {
+            name: self.name.clone(),
+            size: self.size.load(Ordering::Relaxed),
+            cap: self.cap,
+            high_water: self.high_water.load(Ordering::Relaxed),
+            pruned: self.pruned.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn registry() -> &'static Mutex<Vec<Arc<SizeGauge>>> {
+    static REGISTRY: OnceLock<Mutex<Vec<Arc<SizeGauge>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Stats for every live gauge; gauges whose owner is gone are pruned.
+pub fn size_stats() -> Vec<SizeStats> {
+    let Ok(mut gauges) = registry().lock() else {
+        return Vec::new();
+    };
+    gauges.retain(|g| Arc::strong_count(g) > 1);
+    gauges.iter().map(|g| g.snapshot()).collect()
+}
@@ -0,0 +1,363 @@
+//! Listing mode (`[listing_mode]`) for freshly listed or thin pairs, such
+//! as WLFI.
+//!
+//! Top of book on a fresh listing is routinely fictional: a few small orders
+//! quoted where nothing trades. For the configured symbols, every
+//! `interval_secs` each venue's order book and recent trades are read over
+//! REST (Binance `GET /fapi/v1/depth` and 1-minute `klines`, Bybit `GET
+//! /v5/market/orderbook` and `recent-trade`). A spread between two venues
+//! only counts, for alerts and for execution, while both show at least
+//! `min_depth` of notional on each side of the book within
+//! `depth_band_percent` of the mid, and `min_trades_per_minute` trades.
+//! `[listing_mode.exchanges.<name>]` sets other thresholds for one venue, to
+//! trust its book more or less than the other's. A failed reading, or one
+//! older than three intervals, counts as showing nothing.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
+
+use arc_swap::ArcSwap;
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{self, ListingModeConfig},
+    constants::urls,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::{self, Decimal},
+    },
+    net, state,
+};
+
+/// What one venue's book and trades showed for a symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Liquidity {
+    /// Bid notional within the band below the mid.
+    pub bid_depth: Decimal,
+    /// Ask notional within the band above the mid.
+    pub ask_depth: Decimal,
+    pub trades_per_minute: u32,
+    pub at_ms: i64,
+}
+
+impl Liquidity {
+    /// Measures a book of `(price, quantity)` levels, best first, within
+    /// `band_percent` of its mid.
+    ///
+    /// ```
+    /// use arbitrage_bot::listing_mode::Liquidity;
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let bids = [(dec!(99.5), dec!(10)), (dec!(98), dec!(1000))];
+    /// let asks = [(dec!(100.5), dec!(20))];
+    /// let liquidity = Liquidity::measure(&bids, &asks, 12, dec!(1), 0);
+    /// // The mid is 100; the bid at 98 is outside 1% of it.
+    /// assert_eq!(liquidity.bid_depth, dec!(995));
+    /// assert_eq!(liquidity.ask_depth, dec!(2010));
+    /// ```
+    pub fn measure(
+        bids: &[(Decimal, Decimal)],
+        asks: &[(Decimal, Decimal)],
+        trades_per_minute: u32,
+        band_percent: Decimal,
+        at_ms: i64,
+    ) -> Self {
+        let (bid_depth, ask_depth) = match (bids.first(), asks.first()) {
+            (Some((bid, _)), Some((ask, _))) => {
+                let mid = (bid + ask) / Decimal::TWO;
+                let band = mid * band_percent / Decimal::ONE_HUNDRED;
+                let within = |levels: &[(Decimal, Decimal)]| {
+                    levels
+                        .iter()
+                        .filter(|(price, _)| (*price - mid).abs() <= band)
+                        .map(|(price, quantity)| price * quantity)
+                        .sum()
+                };
+                (within(bids), within(asks))
+            }
+            // A one-sided book has no mid to measure against.
+            _ => (Decimal::ZERO, Decimal::ZERO),
+        };
+        Self {
+            bid_depth,
+            ask_depth,
+            trades_per_minute,
+            at_ms,
+        }
+    }
+
+    /// Why this falls short of `thresholds`, if it does.
+    pub fn shortfall(&self, thresholds: &config::LiquidityThresholds) -> Option<String> {
+        let depth = self.bid_depth.min(self.ask_depth);
+        if depth < thresholds.min_depth {
+            return Some(format!(
+                "depth {} is under {}",
+                depth.round_dp(2).normalize(),
+                thresholds.min_depth.normalize()
+            ));
+        }
+        if self.trades_per_minute < thresholds.min_trades_per_minute {
+            return Some(format!(
+                "{} trade(s) a minute, under {}",
+                self.trades_per_minute, thresholds.min_trades_per_minute
+            ));
+        }
+        None
+    }
+}
+
+/// Why a venue's quotes for a listing-mode symbol don't count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ineligible {
+    pub exchange: ExchangeId,
+    pub reason: String,
+}
+
+impl fmt::Display for Ineligible {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is too thin: {}", self.exchange, self.reason)
+    }
+}
+
+/// The latest reading per venue and symbol, or why it failed.
+type Readings = HashMap<(ExchangeId, Symbol), Result<Liquidity, String>>;
+
+/// The latest readings per venue and symbol, shared by the probe task, the
+/// tracker and execution.
+#[derive(Debug, Clone)]
+pub struct ListingMode {
+    config: Arc<ListingModeConfig>,
+    symbols: Arc<HashSet<Symbol>>,
+    readings: Arc<ArcSwap<Readings>>,
+}
+
+impl ListingMode {
+    pub fn new(config: &ListingModeConfig) -> Self {
+        Self {
+            symbols: Arc::new(
+                config
+                    .symbols
+                    .iter()
+                    .map(|s| Symbol::intern(&s.to_uppercase()))
+                    .collect(),
+            ),
+            config: Arc::new(config.clone()),
+            readings: Arc::default(),
+        }
+    }
+
+    /// Whether `symbol` is in listing mode.
+    pub fn applies(&self, symbol: Symbol) -> bool {
+        self.symbols.contains(&symbol)
+    }
+
+    /// Takes a reading of `exchange`'s book and trades for `symbol`.
+    pub fn record(&self, exchange: ExchangeId, symbol: Symbol, reading: Result<Liquidity, String>) {
+        self.readings.rcu(|readings| {
+            let mut readings = HashMap::clone(readings);
+            readings.insert((exchange, symbol), reading.clone());
+            readings
+        });
+    }
+
+    /// Why `exchange`'s quotes for `symbol` don't count right now; `None`
+    /// when they do, or `symbol` isn't in listing mode.
+    pub fn shortfall(&self, exchange: ExchangeId, symbol: Symbol) -> Option<String> {
+        if !self.applies(symbol) {
+            return None;
+        }
+        let readings = self.readings.load();
+        let liquidity = match readings.get(&(exchange, symbol)) {
+            None => return Some("no reading yet".into()),
+            Some(Err(e)) => return Some(format!("reading failed: {}", e)),
+            Some(Ok(liquidity)) => liquidity,
+        };
+        let max_age = self.config.interval().as_millis() as i64 * 3;
+        if state::now_ms() - liquidity.at_ms > max_age {
+            return Some("reading is stale".into());
+        }
+        liquidity.shortfall(&self.config.thresholds(exchange))
+    }
+
+    /// The first of `exchanges` that keeps a spread on `symbol` from
+    /// counting.
+    pub fn ineligible(&self, symbol: Symbol, exchanges: [ExchangeId; 2]) -> Option<Ineligible> {
+        exchanges.into_iter().find_map(|exchange| {
+            Some(Ineligible {
+                exchange,
+                reason: self.shortfall(exchange, symbol)?,
+            })
+        })
+    }
+
+    /// Reads every venue's book and trades for every listing-mode symbol now
+    /// and then every `interval_secs`, until `cancel` fires.
+    pub fn spawn_probes(&self, cancel: CancellationToken) {
+        let listing = self.clone();
+        tokio::spawn(async move {
+            let client = net::http_client();
+            let mut interval = tokio::time::interval(listing.config.interval());
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = cancel.cancelled() => break,
+                }
+                for &symbol in listing.symbols.iter() {
+                    for exchange in [ExchangeId::Binance, ExchangeId::Bybit] {
+                        let before = listing.shortfall(exchange, symbol);
+                        let reading = fetch(
+                            &client,
+                            exchange,
+                            symbol.as_str(),
+                            listing.config.depth_band_percent,
+                        )
+                        .await;
+                        listing.record(exchange, symbol, reading);
+                        match (before, listing.shortfall(exchange, symbol)) {
+                            (Some(_), None) => println!(
+                                "🐣 {} on {} is liquid enough; its spreads count",
+                                symbol, exchange
+                            ),
+                            (None, Some(reason)) => eprintln!(
+                                "🐣 {} on {} is too thin ({}); its spreads don't count",
+                                symbol, exchange, reason
+                            ),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Binance `GET /fapi/v1/depth` and Bybit's `result` of `GET
+/// /v5/market/orderbook`: levels of `[price, quantity]` strings.
+#[derive(Debug, Deserialize)]
+struct Book {
+    #[serde(alias = "b")]
+    bids: Vec<(String, String)>,
+    #[serde(alias = "a")]
+    asks: Vec<(String, String)>,
+}
+
+impl Book {
+    fn levels(side: &[(String, String)]) -> Vec<(Decimal, Decimal)> {
+        side.iter()
+            .filter_map(|(price, quantity)| Some((money::parse(price)?, money::parse(quantity)?)))
+            .collect()
+    }
+}
+
+/// Bybit's v5 envelope.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitResponse<T> {
+    ret_code: i32,
+    ret_msg: String,
+    result: Option<T>,
+}
+
+impl<T> BybitResponse<T> {
+    fn into_result(self) -> Result<T, String> {
+        match self.result {
+            Some(result) if self.ret_code == 0 => Ok(result),
+            _ => Err(format!("Bybit error {}: {}", self.ret_code, self.ret_msg)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BybitTrades {
+    list: Vec<BybitTrade>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BybitTrade {
+    /// Milliseconds, as a string.
+    time: String,
+}
+
+/// Reads `exchange`'s futures book and trades for `symbol`.
+pub async fn fetch(
+    client: &reqwest::Client,
+    exchange: ExchangeId,
+    symbol: &str,
+    band_percent: Decimal,
+) -> Result<Liquidity, String> {
+    let now = state::now_ms();
+    let (book, trades) = match exchange {
+        ExchangeId::Binance => {
+            let base = config::get().network.endpoint(urls::BINANCE_REST_FUTURES);
+            let book: Book = get(
+                client,
+                &format!("{}/fapi/v1/depth?symbol={}&limit=100", base, symbol),
+            )
+            .await?;
+            // The last closed minute; the second candle is still open.
+            let klines: Vec<Vec<serde_json::Value>> = get(
+                client,
+                &format!(
+                    "{}/fapi/v1/klines?symbol={}&interval=1m&limit=2",
+                    base, symbol
+                ),
+            )
+            .await?;
+            let trades = klines
+                .first()
+                .and_then(|kline| kline.get(8)?.as_u64())
+                .unwrap_or(0);
+            (book, trades)
+        }
+        ExchangeId::Bybit => {
+            let base = config::get().network.endpoint(urls::BYBIT_REST);
+            let book: BybitResponse<Book> = get(
+                client,
+                &format!(
+                    "{}/v5/market/orderbook?category=linear&symbol={}&limit=200",
+                    base, symbol
+                ),
+            )
+            .await?;
+            let trades: BybitResponse<BybitTrades> = get(
+                client,
+                &format!(
+                    "{}/v5/market/recent-trade?category=linear&symbol={}&limit=1000",
+                    base, symbol
+                ),
+            )
+            .await?;
+            let trades = trades
+                .into_result()?
+                .list
+                .iter()
+                .filter_map(|trade| trade.time.parse::<i64>().ok())
+                .filter(|time| now - time <= 60_000)
+                .count() as u64;
+            (book.into_result()?, trades)
+        }
+    };
+    Ok(Liquidity::measure(
+        &Book::levels(&book.bids),
+        &Book::levels(&book.asks),
+        trades.try_into().unwrap_or(u32::MAX),
+        band_percent,
+        now,
+    ))
+}
+
+async fn get<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<T, String> {
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {}", status));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
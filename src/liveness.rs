@@ -0,0 +1,172 @@
+//! REST liveness probes (`[liveness]`).
+//!
+//! A feed's WebSocket can stay up while the exchange's REST API, which
+//! spot orders, balances and transfer status go through, fails or times
+//! out. Every `interval_secs` each exchange's unsigned ping endpoint is
+//! probed (Binance `GET /fapi/v1/ping`, Bybit `GET /v5/market/time`), with
+//! `timeout_ms` to answer. After `failures` failed probes in a row the
+//! exchange is execution-impaired: execution stops picking trades with a
+//! leg on it, while its quotes keep feeding the tracker and alerts. The
+//! first probe that goes through clears it. Both changes are logged and
+//! sent to Telegram.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{self, LivenessConfig},
+    constants::urls,
+    models::ids::ExchangeId,
+    net,
+    notifications::telegram::Notification,
+};
+
+/// What the probes found on one exchange.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProbeState {
+    /// Failed probes since the last one that went through.
+    pub failures: u32,
+    pub impaired: bool,
+    /// Why the latest probe failed.
+    pub error: Option<String>,
+}
+
+/// A change [`Liveness::record`] made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    Impaired,
+    Recovered,
+}
+
+/// Probe results per exchange, shared by the probe task and execution.
+#[derive(Debug, Clone)]
+pub struct Liveness {
+    /// Failed probes in a row that impair an exchange.
+    failures: u32,
+    venues: Arc<ArcSwap<HashMap<ExchangeId, ProbeState>>>,
+}
+
+impl Liveness {
+    pub fn new(failures: u32) -> Self {
+        Self {
+            failures,
+            venues: Arc::default(),
+        }
+    }
+
+    pub fn get(&self, exchange: ExchangeId) -> ProbeState {
+        self.venues
+            .load()
+            .get(&exchange)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether execution should leave `exchange` alone.
+    pub fn is_impaired(&self, exchange: ExchangeId) -> bool {
+        self.venues
+            .load()
+            .get(&exchange)
+            .is_some_and(|state| state.impaired)
+    }
+
+    /// Takes a probe's outcome; returns the change it made, if any.
+    pub fn record(&self, exchange: ExchangeId, result: Result<(), String>) -> Option<Transition> {
+        let before = self.get(exchange);
+        let after = match result {
+            Ok(()) => ProbeState::default(),
+            Err(error) => ProbeState {
+                failures: before.failures + 1,
+                impaired: before.impaired || before.failures + 1 >= self.failures,
+                error: Some(error),
+            },
+        };
+        let transition = match (before.impaired, after.impaired) {
+            (false, true) => Some(Transition::Impaired),
+            (true, false) => Some(Transition::Recovered),
+            _ => None,
+        };
+        self.venues.rcu(|venues| {
+            let mut venues = HashMap::clone(venues);
+            venues.insert(exchange, after.clone());
+            venues
+        });
+        transition
+    }
+
+    /// Probes every exchange now and then every `interval_secs`, until
+    /// `cancel` fires; `alerts` hear when one is impaired or recovers.
+    pub fn spawn_probes(
+        &self,
+        config: &LivenessConfig,
+        alerts: Option<mpsc::Sender<Notification>>,
+        cancel: CancellationToken,
+    ) {
+        let liveness = self.clone();
+        let (every, timeout) = (config.interval(), config.timeout());
+        tokio::spawn(async move {
+            let client = net::http_client();
+            let mut interval = tokio::time::interval(every);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = cancel.cancelled() => break,
+                }
+                for exchange in [ExchangeId::Binance, ExchangeId::Bybit] {
+                    let result = probe(&client, exchange, timeout).await;
+                    let error = result.as_ref().err().cloned();
+                    match liveness.record(exchange, result) {
+                        Some(Transition::Impaired) => eprintln!(
+                            "🚧 {} REST is failing ({}); not trading on it",
+                            exchange,
+                            error.as_deref().unwrap_or_default()
+                        ),
+                        Some(Transition::Recovered) => {
+                            println!("✅ {} REST is back; trading on it again", exchange)
+                        }
+                        None => continue,
+                    }
+                    if let Some(alerts) = &alerts {
+                        let _ = alerts.try_send(Notification::VenueImpaired {
+                            exchange: exchange.to_string(),
+                            recovered: error.is_none(),
+                            error: error.unwrap_or_default(),
+                        });
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// The unsigned endpoint that tells whether `exchange`'s REST API answers.
+pub fn probe_url(exchange: ExchangeId) -> String {
+    let url = match exchange {
+        ExchangeId::Binance => format!("{}/fapi/v1/ping", urls::BINANCE_REST_FUTURES),
+        ExchangeId::Bybit => format!("{}/v5/market/time", urls::BYBIT_REST),
+    };
+    config::get().network.endpoint(&url)
+}
+
+/// Whether `exchange`'s REST API answered within `timeout` with a success.
+pub async fn probe(
+    client: &reqwest::Client,
+    exchange: ExchangeId,
+    timeout: Duration,
+) -> Result<(), String> {
+    let response = client
+        .get(probe_url(exchange))
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", status))
+    }
+}
@@ -1,15 +1,22 @@
-use crate::models::orderbook::{BinanceOrderBookMsg, MarketSnapshot, OrderBookMsg};
+use crate::{
+    error::StorageError,
+    models::{
+        money::{self, Decimal},
+        orderbook::{BinanceOrderBookMsg, MarketSnapshot, OrderBookMsg},
+    },
+};
+use rust_decimal_macros::dec;
 use std::fs::OpenOptions;
 use std::io::Write;
 
 pub fn _log_orderbook(msg: &OrderBookMsg) {
     if let (Some(bid), Some(ask)) = (msg.data.b.first(), msg.data.a.first()) {
-        let bid_price: f64 = bid[0].parse().unwrap_or(0.0);
-        let bid_size: f64 = bid[1].parse().unwrap_or(0.0);
-        let ask_price: f64 = ask[0].parse().unwrap_or(0.0);
-        let ask_size: f64 = ask[1].parse().unwrap_or(0.0);
+        let bid_price = money::parse(&bid[0]).unwrap_or_default();
+        let bid_size = money::parse(&bid[1]).unwrap_or_default();
+        let ask_price = money::parse(&ask[0]).unwrap_or_default();
+        let ask_size = money::parse(&ask[1]).unwrap_or_default();
 
-        let mid_price = (bid_price + ask_price) / 2.0;
+        let mid_price = (bid_price + ask_price) / dec!(2);
 
         println!(
             "📊 {} | Bid: {:.4} ({:.4}) | Ask: {:.4} ({:.4}) | Mid: {:.4} | Seq: {}",
@@ -20,12 +27,12 @@ pub fn _log_orderbook(msg: &OrderBookMsg) {
 
 pub fn _log_binance_orderbook(msg: &BinanceOrderBookMsg) {
     if let (Some(bid), Some(ask)) = (msg.bids.first(), msg.asks.first()) {
-        let bid_price: f64 = bid[0].parse().unwrap_or(0.0);
-        let bid_size: f64 = bid[1].parse().unwrap_or(0.0);
-        let ask_price: f64 = ask[0].parse().unwrap_or(0.0);
-        let ask_size: f64 = ask[1].parse().unwrap_or(0.0);
+        let bid_price = money::parse(&bid[0]).unwrap_or_default();
+        let bid_size = money::parse(&bid[1]).unwrap_or_default();
+        let ask_price = money::parse(&ask[0]).unwrap_or_default();
+        let ask_size = money::parse(&ask[1]).unwrap_or_default();
 
-        let mid_price = (bid_price + ask_price) / 2.0;
+        let mid_price = (bid_price + ask_price) / dec!(2);
 
         println!(
             "📊 {} | Bid: {:.4} ({:.4}) | Ask: {:.4} ({:.4}) | Mid: {:.4}",
@@ -38,29 +45,42 @@ pub struct CsvLogger {
 }
 
 impl CsvLogger {
-    pub fn new(path: &str) -> Self {
+    pub fn new(path: &str) -> Result<Self, StorageError> {
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
             .open(path)
-            .unwrap();
+            .map_err(StorageError::io(path))?;
 
         // Write header if file is empty
         use std::io::Seek;
-        if file.seek(std::io::SeekFrom::End(0)).unwrap() == 0 {
+        if file
+            .seek(std::io::SeekFrom::End(0))
+            .map_err(StorageError::io(path))?
+            == 0
+        {
             writeln!(
                 file,
                 "symbol,exchange_a,exchange_b,bid_a,ask_a,mid_a,bid_b,ask_b,mid_b,diff_percent,timestamp"
-            ).unwrap();
+            )
+            .map_err(StorageError::io(path))?;
         }
 
-        Self {
+        Ok(Self {
             path: path.to_string(),
-        }
+        })
     }
 
-    pub fn log(&self, a: &MarketSnapshot, b: &MarketSnapshot, diff: f64) {
-        let mut file = OpenOptions::new().append(true).open(&self.path).unwrap();
+    pub fn log(
+        &self,
+        a: &MarketSnapshot,
+        b: &MarketSnapshot,
+        diff: Decimal,
+    ) -> Result<(), StorageError> {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(StorageError::io(&self.path))?;
 
         let line = format!(
             "{},{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.2}%,{}",
@@ -77,7 +97,7 @@ impl CsvLogger {
             a.timestamp
         );
 
-        writeln!(file, "{}", line).unwrap();
+        writeln!(file, "{}", line).map_err(StorageError::io(&self.path))
 
         // also print it to console
         // println!("After write file::{}", line);
@@ -1,172 +1,150 @@
-use std::{env, sync::Arc};
-
-use tokio::sync::Mutex;
+use std::env;
 
 use dotenv::dotenv;
-mod macros;
-
-use crate::{
-    binance::{api::BinanceTradingClient, order::BinanceOrderSide},
-    constants::{notifications as notif_const, urls},
-    models::orderbook::MarketTracker,
-    notifications::{alert_gate::AlertGate, telegram::TelegramNotifier},
-    ws::{
-        binance_client::{self, run_orderbook_stream_binance},
-        // binance_client_multiplex::_run_orderbook_stream_binance,
-        bybit_client_futures::run_orderbook_stream_bybit_futures,
-    },
-};
-
-mod binance;
-use binance::{create_limit_order, BinanceAuth};
+#[cfg(feature = "execution")]
+use rust_decimal_macros::dec;
 
-mod constants;
-mod logger;
-mod models;
-pub mod notifications;
-mod ws;
+#[cfg(feature = "execution")]
+use arbitrage_bot::binance::{
+    api::BinanceTradingClient, create_limit_order, order::BinanceOrderSide, BinanceAuth,
+};
+#[cfg(feature = "tui")]
+use arbitrage_bot::tui::{self, OutputRedirect};
+use arbitrage_bot::{
+    backtest,
+    config::{self, Config},
+    engine::Engine,
+    events, heatmap, keys, runtime,
+};
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
 
-    let api_key = env::var("API_KEY_BINANCE")
-        .or_else(|_| env::var("API_KEY_BINANCE"))
-        .expect("API_KEY_BINANCE not set");
-    let secret_key = env::var("SECRET_KEY_BINANCE")
-        .or_else(|_| env::var("SECRET_KEY_BINANCE"))
-        .expect("SECRET_KEY_BINANCE not set");
-
-    let auth = BinanceAuth::new(api_key, secret_key);
-    println!(
-        "API Key: {}, api secret {}",
-        auth.api_key(),
-        auth.api_secret()
-    );
+    match Config::load_default() {
+        Ok(config) => config::init(config),
+        Err(e) => {
+            eprintln!("❌ Invalid configuration: {:#}", e);
+            std::process::exit(1);
+        }
+    }
 
-    // ── Telegram Notifier ────────────────────────────────────────────
-    let telegram_tx = TelegramNotifier::spawn();
+    // ── Offline subcommands ──────────────────────────────────────────
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("backtest") {
+        backtest::run_cli(&args[1..]).await;
+        return;
+    }
+    if args.first().map(String::as_str) == Some("keys") {
+        keys::run_cli(&args[1..], &config::get().keys);
+        return;
+    }
+    if args.first().map(String::as_str) == Some("events") {
+        events::run_cli(&args[1..], &config::get().engine);
+        return;
+    }
+    if args.first().map(String::as_str) == Some("heatmap") {
+        heatmap::run_cli(&args[1..], &config::get().engine);
+        return;
+    }
 
-    // ── Alert Gate (dedup + cooldown) ────────────────────────────────
-    let alert_gate = AlertGate::new(
-        notif_const::DIFF_THRESHOLD,
-        notif_const::RE_ALERT_DELTA,
-        notif_const::COOLDOWN_SECS,
-    );
+    if let Err(e) = keys::unlock(&config::get().keys).await {
+        eprintln!("❌ Cannot load secrets: {:#}", e);
+        std::process::exit(1);
+    }
+    // Needs AUDIT_LOG_KEY, which may come from the credential provider.
+    if args.first().map(String::as_str) == Some("audit") {
+        audit_cli(&args[1..]);
+        return;
+    }
 
-    // ── Market Tracker ───────────────────────────────────────────────
-    // The comparator threshold is DIFF_THRESHOLD / 100 because the
-    // comparator works with a raw ratio multiplied by 100 internally.
-    let tracker = Arc::new(Mutex::new(MarketTracker::new(
-        notif_const::DIFF_THRESHOLD / 100.0,
-        "arbitrage.csv",
-        telegram_tx,
-        alert_gate,
-    )));
-
-    // ── 24-hour state reset scheduler ────────────────────────────────
-    {
-        let tracker_reset = tracker.clone();
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(
-                notif_const::STATE_RESET_SECS,
-            ));
-            interval.tick().await; // first tick fires immediately — skip it
-            loop {
-                interval.tick().await;
-                tracker_reset.lock().await.alert_gate.reset();
-            }
-        });
+    if let Err(e) = runtime::init(&config::get().runtime) {
+        eprintln!("❌ Cannot start the hot-path runtime: {}", e);
+        std::process::exit(1);
     }
 
-    let mut handles = vec![];
+    // ── Live pipeline ────────────────────────────────────────────────
+    // Monitoring, alerting, spread logging and (if enabled) execution all
+    // run off the same feeds.
+    let dashboard = redirect_for_dashboard(args.iter().any(|a| a == "--tui"));
+    match Engine::start(&config::get().engine).await {
+        Ok(engine) => run(engine, dashboard).await,
+        Err(e) => {
+            #[cfg(feature = "tui")]
+            drop(dashboard);
+            eprintln!("❌ Engine failed to start: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
 
-    // --- BYBIT SPOT (DISABLED) ---
-    // let symbols_bybit_spot = vec!["WLFIUSDT", "ETHUSDT", "BTCUSDT"];
-    // for symbol in symbols_bybit_spot {
-    //     let tracker_clone = tracker.clone();
-    //     let symbol_owned = symbol.to_string();
-    //     handles.push(tokio::spawn(async move {
-    //         run_orderbook_stream_bybit(&symbol_owned, tracker_clone, urls::BYBIT_URL_SPOT).await;
-    //     }));
-    // }
+/// With `--tui`, moves console output to `[tui] log_file` before the engine
+/// starts printing.
+#[cfg(feature = "tui")]
+fn redirect_for_dashboard(tui: bool) -> Option<OutputRedirect> {
+    if !tui {
+        return None;
+    }
+    let log_file = &config::get().tui.log_file;
+    println!(
+        "📺 Starting the dashboard; console output goes to {}",
+        log_file.display()
+    );
+    match OutputRedirect::to_file(log_file) {
+        Ok(output) => Some(output),
+        Err(e) => {
+            eprintln!("❌ Cannot start the dashboard: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
 
-    // --- BYBIT FUTURES ---
-    let symbols_bybit_futures = vec![
-        "WLFIUSDT",
-        "ETHUSDT",
-        "BTCUSDT",
-        "SOLUSDT",
-        "LINKUSDT",
-        "XRPUSDT",
-        "BNBUSDT",
-        "1000PEPEUSDT",
-    ];
-    for symbol in symbols_bybit_futures {
-        let tracker_clone = tracker.clone();
-        let symbol_owned = symbol.to_string();
-        handles.push(tokio::spawn(async move {
-            run_orderbook_stream_bybit_futures(
-                &symbol_owned,
-                tracker_clone,
-                urls::BYBIT_URL_FUTURES_LINEAR,
-            )
-            .await;
-        }));
+#[cfg(not(feature = "tui"))]
+fn redirect_for_dashboard(tui: bool) -> Option<()> {
+    if tui {
+        eprintln!("⚠️ --tui needs the `tui` feature; carrying on without the dashboard");
     }
+    None
+}
 
-    // --- BINANCE SPOT (DISABLED) ---
-    // let symbols_binance_spot = vec!["wlfiusdt", "ethusdt", "btcusdt"];
-    // for symbol in symbols_binance_spot {
-    //     let tracker_clone = tracker.clone();
-    //     let symbol_owned = symbol.to_string();
-    //     handles.push(tokio::spawn(async move {
-    //         // Note: binance scanner might need uppercase or lowercase depending on implementation
-    //         // Looking at previous code, it seems to handle it or expect lowercase for streams?
-    //         // binance_client.rs: line 29: let stream_name = format!("{}@depth", symbol.to_lowercase());
-    //         // So casing here doesn't matter too much but let's stick to what we have.
-    //         binance_client::run_orderbook_stream_binance(
-    //             &symbol_owned,
-    //             tracker_clone,
-    //             urls::BINANCE_URL_SPOT,
-    //         )
-    //         .await;
-    //     }));
-    // }
+#[cfg(feature = "tui")]
+async fn run(engine: Engine, dashboard: Option<OutputRedirect>) {
+    let Some(output) = dashboard else {
+        return engine.run().await;
+    };
+    let ui = tokio::spawn(tui::run(
+        engine.control().clone(),
+        output,
+        engine.cancellation(),
+    ));
+    tokio::spawn(async move {
+        if let Ok(Err(e)) = ui.await {
+            eprintln!(
+                "❌ Dashboard failed: {}; carrying on with console output",
+                e
+            );
+        }
+    });
+    engine.run().await;
+}
 
-    // --- BINANCE FUTURES ---
-    let symbols_binance_futures = vec![
-        "wlfiusdt",
-        "ethusdt",
-        "btcusdt",
-        "solusdt",
-        "linkusdt",
-        "xrpusdt",
-        "bnbusdt",
-        "1000pepeusdt",
-    ];
-    for symbol in symbols_binance_futures {
-        let tracker_clone = tracker.clone();
-        let symbol_owned = symbol.to_string();
-        handles.push(tokio::spawn(async move {
-            binance_client::run_orderbook_stream_binance(
-                &symbol_owned,
-                tracker_clone,
-                urls::BINANCE_URL_FUTURES,
-            )
-            .await;
-        }));
-    }
+#[cfg(not(feature = "tui"))]
+async fn run(engine: Engine, _dashboard: Option<()>) {
+    engine.run().await
+}
 
-    println!("--- Scanning started for: WLFI, ETH, BTC, SOL, LINK, XRP, BNB, 1000PEPE on Binance & Bybit (Spot & Futures) ---");
+#[cfg(feature = "execution")]
+fn audit_cli(args: &[String]) {
+    arbitrage_bot::audit::run_cli(args, config::get().engine.audit_log.as_deref());
+}
 
-    // Keep the main thread alive and log heartbeat
-    loop {
-        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
-        println!("--- Scanning active: {} ---", chrono::Local::now());
-    }
+#[cfg(not(feature = "execution"))]
+fn audit_cli(_args: &[String]) {
+    eprintln!("❌ audit commands need the `execution` feature");
+    std::process::exit(1);
 }
 
+#[cfg(feature = "execution")]
 async fn test_limit_order_ws(auth: &BinanceAuth) -> Result<(), Box<dyn std::error::Error>> {
     let mut client: BinanceTradingClient =
         BinanceTradingClient::connect(auth.api_key().clone(), auth.api_secret().clone()).await?;
@@ -175,8 +153,8 @@ async fn test_limit_order_ws(auth: &BinanceAuth) -> Result<(), Box<dyn std::erro
     let order = create_limit_order(
         "LTCUSDT".to_string(),
         BinanceOrderSide::BUY,
-        0.23, // quantity
-        9.7,  // price
+        dec!(0.23), // quantity
+        dec!(9.7),  // price
     );
 
     println!(
@@ -3,15 +3,17 @@ use serde::Serialize;
 use serde_json::json;
 use sha2::Sha256;
 
+use crate::{config, constants::exchange_names, secret::SecretString};
+
 type HmacSha256 = Hmac<Sha256>;
 
 pub struct BybitAuth {
     api_key: String,
-    secret: String,
+    secret: SecretString,
 }
 
 impl BybitAuth {
-    pub fn new(api_key: impl Into<String>, secret: impl Into<String>) -> Self {
+    pub fn new(api_key: impl Into<String>, secret: impl Into<SecretString>) -> Self {
         Self {
             api_key: api_key.into(),
             secret: secret.into(),
@@ -30,11 +32,36 @@ impl BybitAuth {
     /// The message might be "GET/realtime{expires}" or something defined in docs
     pub fn sign(&self, expires: i64) -> String {
         let payload = format!("GET/realtime{}", expires);
-        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes()).unwrap();
+        let mut mac = HmacSha256::new_from_slice(self.secret.expose().as_bytes()).unwrap();
         mac.update(payload.as_bytes());
         let result = mac.finalize().into_bytes();
         hex::encode(result)
     }
+
+    /// Signs a v5 REST request: HMAC SHA256 over the timestamp, API key,
+    /// `recv_window` and the query string (or JSON body), hex-encoded.
+    pub fn sign_rest(&self, timestamp: i64, recv_window: u64, payload: &str) -> String {
+        let payload = format!("{}{}{}{}", timestamp, self.api_key, recv_window, payload);
+        let mut mac = HmacSha256::new_from_slice(self.secret.expose().as_bytes()).unwrap();
+        mac.update(payload.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// The `X-BAPI-*` headers of a signed v5 REST request for `payload`,
+    /// timed by `[signing]` for Bybit.
+    pub fn rest_headers(&self, payload: &str) -> [(&'static str, String); 4] {
+        let signing = config::get().signing.for_exchange(exchange_names::BYBIT);
+        let timestamp = chrono::Utc::now().timestamp_millis() + signing.timestamp_offset_ms;
+        [
+            ("X-BAPI-API-KEY", self.api_key.clone()),
+            ("X-BAPI-TIMESTAMP", timestamp.to_string()),
+            ("X-BAPI-RECV-WINDOW", signing.recv_window_ms.to_string()),
+            (
+                "X-BAPI-SIGN",
+                self.sign_rest(timestamp, signing.recv_window_ms, payload),
+            ),
+        ]
+    }
 }
 
 #[derive(Serialize)]
@@ -56,12 +83,14 @@ struct BybitOrderCreateArgs {
 }
 
 #[derive(serde::Serialize)]
-struct BybitAuthMsg {
+pub struct BybitAuthMsg {
     op: String,                   // "auth"
     args: Vec<serde_json::Value>, // [apiKey, expires, signature]
 }
 
 impl BybitAuth {
+    /// Builds a fresh auth frame; suitable as a `WsHandler` on-connect hook since
+    /// each call signs a new `expires`.
     pub fn auth_msg(&self) -> BybitAuthMsg {
         let expires = self.expires();
         let sig = self.sign(expires);
@@ -0,0 +1,136 @@
+//! Cheap, copyable identifiers for exchanges and symbols.
+//!
+//! Quotes, snapshots and map keys on the hot path carry these instead of
+//! `String`s, so a tick neither allocates nor hashes text. A [`Symbol`] is a
+//! `u32` into a process-wide registry; each distinct name is stored once and
+//! lives for the rest of the process, which is fine for the bounded set of
+//! instruments a bot trades.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{LazyLock, RwLock},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::constants::exchange_names;
+
+/// Serialized as its name (see `constants::exchange_names`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExchangeId {
+    Binance,
+    Bybit,
+}
+
+impl ExchangeId {
+    /// Maps a feed's exchange name (see `constants::exchange_names`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            exchange_names::BINANCE => Some(Self::Binance),
+            exchange_names::BYBIT => Some(Self::Bybit),
+            _ => None,
+        }
+    }
+
+    /// The name used in config, logs and alerts (see `constants::exchange_names`).
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Binance => exchange_names::BINANCE,
+            Self::Bybit => exchange_names::BYBIT,
+        }
+    }
+}
+
+impl fmt::Display for ExchangeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An interned instrument name such as `BTCUSDT`. Names are case-sensitive;
+/// exchanges report symbols in upper case.
+///
+/// ```
+/// use arbitrage_bot::models::ids::Symbol;
+///
+/// let btc = Symbol::intern("BTCUSDT");
+/// assert_eq!(btc, Symbol::intern("BTCUSDT"));
+/// assert_ne!(btc, Symbol::intern("ETHUSDT"));
+/// assert_eq!(btc.as_str(), "BTCUSDT");
+/// assert_eq!(btc, "BTCUSDT");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+#[derive(Default)]
+struct Registry {
+    ids: HashMap<&'static str, Symbol>,
+    names: Vec<&'static str>,
+}
+
+static REGISTRY: LazyLock<RwLock<Registry>> = LazyLock::new(Default::default);
+
+impl Symbol {
+    /// The ID for `name`, registering it on first use.
+    pub fn intern(name: &str) -> Self {
+        if let Some(&symbol) = REGISTRY
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .ids
+            .get(name)
+        {
+            return symbol;
+        }
+        let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(&symbol) = registry.ids.get(name) {
+            return symbol;
+        }
+        let name: &'static str = Box::leak(name.into());
+        let symbol = Symbol(registry.names.len() as u32);
+        registry.names.push(name);
+        registry.ids.insert(name, symbol);
+        symbol
+    }
+
+    pub fn as_str(self) -> &'static str {
+        REGISTRY.read().unwrap_or_else(|e| e.into_inner()).names[self.0 as usize]
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Self::intern(name)
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::intern(&String::deserialize(deserializer)?))
+    }
+}
@@ -1,2 +1,5 @@
+#[cfg(feature = "execution")]
 pub mod bybit_make_orders;
+pub mod ids;
+pub mod money;
 pub mod orderbook;
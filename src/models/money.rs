@@ -0,0 +1,53 @@
+//! Decimal helpers for prices, quantities and PnL.
+//!
+//! Exchange price strings are parsed straight into [`Decimal`], so amounts never
+//! pass through binary floating point. Before a value goes into an order it is
+//! snapped to the symbol's tick/step size with an explicit rounding direction
+//! and serialized in plain notation without trailing zeros.
+
+pub use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+
+/// Parses an exchange decimal string such as `"112543.10"`.
+pub fn parse(s: &str) -> Option<Decimal> {
+    s.parse().ok()
+}
+
+/// Rounds `value` to a multiple of `step` (price tick or lot size).
+///
+/// Use `ToZero` for quantities so an order never exceeds the intended size,
+/// and `ToNegativeInfinity` / `ToPositiveInfinity` to keep buy / sell prices
+/// on the passive side. A zero `step` leaves `value` unchanged.
+///
+/// ```
+/// use arbitrage_bot::models::money::{round_to_step, Decimal, RoundingStrategy};
+///
+/// let qty: Decimal = "0.123456".parse().unwrap();
+/// let step: Decimal = "0.001".parse().unwrap();
+/// assert_eq!(round_to_step(qty, step, RoundingStrategy::ToZero).to_string(), "0.123");
+/// ```
+pub fn round_to_step(value: Decimal, step: Decimal, strategy: RoundingStrategy) -> Decimal {
+    if step.is_zero() {
+        return value;
+    }
+    ((value / step).round_dp_with_strategy(0, strategy) * step).normalize()
+}
+
+/// Formats an amount for an order parameter: plain notation, no trailing zeros.
+///
+/// ```
+/// use arbitrage_bot::models::money::{to_param, Decimal};
+///
+/// let a: Decimal = "0.1".parse().unwrap();
+/// let b: Decimal = "0.2".parse().unwrap();
+/// assert_eq!(to_param(a + b), "0.3");
+/// assert_eq!(to_param("25.000".parse().unwrap()), "25");
+/// ```
+pub fn to_param(value: Decimal) -> String {
+    value.normalize().to_string()
+}
+
+/// `part / whole` in percent, or `None` if `whole` is zero.
+pub fn percent_of(part: Decimal, whole: Decimal) -> Option<Decimal> {
+    part.checked_div(whole).map(|ratio| ratio * dec!(100))
+}
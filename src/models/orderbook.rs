@@ -1,13 +1,52 @@
 use chrono::Utc;
+use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tokio::sync::mpsc;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc, oneshot,
+    },
+    time::{self, MissedTickBehavior},
+};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
+    calendar::Calendar,
+    config::{self, EvaluationConfig},
+    fx::Fx,
+    limits::SizeGauge,
+    listing_mode::ListingMode,
     logger::CsvLogger,
-    notifications::{alert_gate::AlertGate, telegram::AppAlert},
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::{self, Decimal},
+    },
+    notifications::{
+        alert_gate::AlertGate,
+        telegram::{AppAlert, Notification},
+    },
+    pauses::Pauses,
+    state::AlertGateState,
+    transfers::{self, TransferStatus},
+    volatility::Volatility,
+    ws::{
+        backpressure::{self, CoalescingQueue},
+        handlers::TopOfBook,
+        quote_bus::QuoteBus,
+    },
 };
 
+/// Buffer for tracker commands (alert resets, state queries); quotes bypass it.
+const TRACKER_COMMAND_CAPACITY: usize = 8;
+
+/// Opportunities a slow subscriber may fall behind before it skips ahead.
+const OPPORTUNITY_CHANNEL_CAPACITY: usize = 256;
+
 #[derive(Debug, Deserialize)]
 pub struct OrderBookMsg {
     pub topic: String,
@@ -33,6 +72,8 @@ pub struct BinanceOrderBookMsg {
     pub event_type: String,
     #[serde(rename = "s")]
     pub symbol: String,
+    #[serde(rename = "u", default)]
+    pub final_update_id: Option<u64>,
     #[serde(rename = "b")]
     pub bids: Vec<Vec<String>>,
     #[serde(rename = "a")]
@@ -81,22 +122,28 @@ pub enum BinanceDepthUpdate {
 
 #[derive(Debug, Clone)]
 pub struct MarketSnapshot {
-    pub exchange: String,
-    pub symbol: String,
-    pub bid: f64,
-    pub ask: f64,
-    pub mid: f64,
+    pub exchange: ExchangeId,
+    pub symbol: Symbol,
+    pub bid: Decimal,
+    pub ask: Decimal,
+    pub mid: Decimal,
     pub timestamp: i64,
     // DETERMINE WHETHER WE NEED THIS OR NOT
     // market_type: MarketType,
 }
 
 impl MarketSnapshot {
-    pub fn new(exchange: &str, symbol: &str, bid: f64, ask: f64, market_type: MarketType) -> Self {
-        let mid = (bid + ask) / 2.0;
+    pub fn new(
+        exchange: ExchangeId,
+        symbol: Symbol,
+        bid: Decimal,
+        ask: Decimal,
+        market_type: MarketType,
+    ) -> Self {
+        let mid = (bid + ask) / dec!(2);
         Self {
-            exchange: exchange.to_string(),
-            symbol: symbol.to_string(),
+            exchange,
+            symbol,
             bid,
             ask,
             mid,
@@ -107,25 +154,27 @@ impl MarketSnapshot {
 }
 
 pub struct Comparator {
-    pub threshold: f64, // e.g., 0.1 = 10%
-    pub biggest_diff: f64,
+    pub threshold: Decimal, // e.g., 0.1 = 10%
+    pub biggest_diff: Decimal,
 }
 
 impl Comparator {
-    pub fn new(threshold: f64) -> Self {
+    pub fn new(threshold: Decimal) -> Self {
         Self {
             threshold,
-            biggest_diff: 0.0,
+            biggest_diff: Decimal::ZERO,
         }
     }
 
-    /// Compare snapshots only across *different exchanges*
+    /// Compare snapshots only across *different exchanges*. Pairs come in
+    /// `ExchangeId` order, and the diff is relative to the first one's mid.
     pub fn compare(
         &mut self,
-        snapshots: &HashMap<String, MarketSnapshot>,
-    ) -> Vec<(MarketSnapshot, MarketSnapshot, f64)> {
+        snapshots: &HashMap<ExchangeId, MarketSnapshot>,
+    ) -> Vec<(MarketSnapshot, MarketSnapshot, Decimal)> {
         let mut results = Vec::new();
-        let exchanges: Vec<&String> = snapshots.keys().collect();
+        let mut exchanges: Vec<&ExchangeId> = snapshots.keys().collect();
+        exchanges.sort();
 
         for (i, exchange_a) in exchanges.iter().enumerate() {
             for exchange_b in &exchanges[i + 1..] {
@@ -140,7 +189,9 @@ impl Comparator {
                 // However user asked just for "price difference".
                 // Let's stick closer to "spread":
 
-                let diff = ((a.mid - b.mid).abs() / a.mid * 100.0);
+                let Some(diff) = money::percent_of((a.mid - b.mid).abs(), a.mid) else {
+                    continue; // no mid price yet
+                };
 
                 if diff >= self.threshold {
                     // Only update biggest_diff if it's actually bigger
@@ -156,73 +207,592 @@ impl Comparator {
     }
 }
 
+/// A spread at or above the comparator threshold, kept for operators (see
+/// [`TrackerHandle::recent_opportunities`]).
+#[derive(Debug, Clone, Serialize)]
+pub struct Opportunity {
+    pub symbol: Symbol,
+    pub exchange_a: ExchangeId,
+    pub exchange_b: ExchangeId,
+    pub mid_a: Decimal,
+    pub mid_b: Decimal,
+    pub diff_percent: Decimal,
+    pub at_ms: i64,
+}
+
 pub struct MarketTracker {
     // Symbol -> Exchange -> Snapshot
-    data: HashMap<String, HashMap<String, MarketSnapshot>>,
+    data: HashMap<Symbol, HashMap<ExchangeId, MarketSnapshot>>,
     comparator: Comparator,
-    logger: CsvLogger,
+    logger: Option<CsvLogger>,
     pub alert_gate: AlertGate,
-    telegram_tx: Option<mpsc::Sender<AppAlert>>,
+    telegram_tx: Option<mpsc::Sender<Notification>>,
+    evaluation: EvaluationConfig,
+    /// When each symbol was last evaluated (only tracked with a `min_interval`).
+    last_evaluated: HashMap<Symbol, Instant>,
+    /// Symbols with quotes held back by `min_interval`.
+    pending: HashSet<Symbol>,
+    /// Size of `data`, capped by `[limits] tracker_symbols`.
+    symbols: Arc<SizeGauge>,
+    /// The latest opportunities, oldest first; at most `[limits]
+    /// recent_opportunities`, after which the oldest roll off.
+    recent: VecDeque<Opportunity>,
+    /// Every opportunity as it is found.
+    opportunities: broadcast::Sender<Opportunity>,
+    /// Listings that alerts on their symbol point out.
+    calendar: Option<Calendar>,
+    /// Suspended deposits and withdrawals that alerts point out, and whether
+    /// they hold the alert back instead.
+    transfers: Option<(TransferStatus, bool)>,
+    /// Converts quotes in other quote currencies to the reference one.
+    fx: Option<Fx>,
+    /// Raises the alert threshold of symbols in a volatile regime.
+    volatility: Option<Volatility>,
+    /// Drops the spreads of listing-mode symbols on venues too thin to trust.
+    listing_mode: Option<ListingMode>,
+    /// Drops the spreads with a leg on a paused exchange or symbol.
+    pauses: Pauses,
 }
 
 impl MarketTracker {
     pub fn new(
-        threshold: f64,
-        log_path: &str,
-        telegram_tx: Option<mpsc::Sender<AppAlert>>,
+        threshold: Decimal,
+        telegram_tx: Option<mpsc::Sender<Notification>>,
         alert_gate: AlertGate,
     ) -> Self {
         Self {
             data: HashMap::new(),
             comparator: Comparator::new(threshold),
-            logger: CsvLogger::new(log_path),
+            logger: None,
             alert_gate,
             telegram_tx,
+            evaluation: EvaluationConfig::EVERY_UPDATE,
+            last_evaluated: HashMap::new(),
+            pending: HashSet::new(),
+            symbols: SizeGauge::register("tracker symbols", config::get().limits.tracker_symbols),
+            recent: VecDeque::new(),
+            opportunities: broadcast::channel(OPPORTUNITY_CHANNEL_CAPACITY).0,
+            calendar: None,
+            transfers: None,
+            fx: None,
+            volatility: None,
+            listing_mode: None,
+            pauses: Pauses::default(),
         }
     }
 
+    /// Evaluates less often under bursty updates (see [`EvaluationConfig`]).
+    pub fn with_evaluation(mut self, evaluation: EvaluationConfig) -> Self {
+        self.evaluation = evaluation;
+        self
+    }
+
+    /// Also appends every spread above the threshold to `logger`.
+    pub fn with_spread_log(mut self, logger: CsvLogger) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Notes on alerts when either exchange lists the symbol around then
+    /// (see `crate::calendar`).
+    pub fn with_calendar(mut self, calendar: Calendar) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
+    /// Notes on alerts when the spread can't be closed by moving the asset
+    /// (see `crate::transfers`); with `block`, drops them instead.
+    pub fn with_transfers(mut self, status: TransferStatus, block: bool) -> Self {
+        self.transfers = Some((status, block));
+        self
+    }
+
+    /// Compares symbols quoted in different currencies at `fx`'s rates, and
+    /// leaves out spreads against a depegged one (see `crate::fx`).
+    pub fn with_fx(mut self, fx: Fx) -> Self {
+        self.fx = Some(fx);
+        self
+    }
+
+    /// Samples every quote into `volatility`, and while a symbol is volatile
+    /// only alerts on spreads that clear the raised threshold, noting the
+    /// regime (see `crate::volatility`).
+    pub fn with_volatility(mut self, volatility: Volatility) -> Self {
+        self.volatility = Some(volatility);
+        self
+    }
+
+    /// Leaves out the spreads of listing-mode symbols while either venue
+    /// lacks the depth or trades `listing_mode` asks for (see
+    /// `crate::listing_mode`): no log entry, opportunity or alert.
+    pub fn with_listing_mode(mut self, listing_mode: ListingMode) -> Self {
+        self.listing_mode = Some(listing_mode);
+        self
+    }
+
+    /// Leaves out the spreads of paused symbols and those with a leg on a
+    /// paused exchange (see `crate::pauses`): no log entry, opportunity or
+    /// alert.
+    pub fn with_pauses(mut self, pauses: Pauses) -> Self {
+        self.pauses = pauses;
+        self
+    }
+
     pub fn update(
         &mut self,
-        exchange: &str,
-        symbol: &str,
-        bid: f64,
-        ask: f64,
+        exchange: ExchangeId,
+        symbol: Symbol,
+        bid: Decimal,
+        ask: Decimal,
         _market_type: MarketType,
     ) {
-        let snapshot = MarketSnapshot::new(exchange, symbol, bid, ask, _market_type);
+        // Quotes in another quote currency are kept under the reference
+        // symbol, in its currency, unless the exchange quotes that too.
+        let (key, bid, ask) = match &self.fx {
+            None => (symbol, bid, ask),
+            Some(fx) => {
+                let Some((key, rate)) = fx.normalize(symbol) else {
+                    return;
+                };
+                let native = self
+                    .data
+                    .get(&key)
+                    .and_then(|snapshots| snapshots.get(&exchange))
+                    .is_some_and(|s| s.symbol == key);
+                if key != symbol && native {
+                    return;
+                }
+                (key, bid * rate, ask * rate)
+            }
+        };
+        let changed = match self
+            .data
+            .get_mut(&key)
+            .and_then(|snapshots| snapshots.get_mut(&exchange))
+        {
+            // Same top of book: just refresh the timestamp.
+            Some(old) if old.symbol == symbol && old.bid == bid && old.ask == ask => {
+                old.timestamp = Utc::now().timestamp();
+                false
+            }
+            _ => {
+                let snapshot = MarketSnapshot::new(exchange, symbol, bid, ask, _market_type);
+
+                if self.symbols.is_full(self.data.len()) && !self.data.contains_key(&key) {
+                    self.prune_stalest_symbol();
+                }
+                let symbols = self.data.len();
+                let symbol_entry = self.data.entry(key).or_insert_with(HashMap::new);
+
+                // Insert or overwrite the snapshot for this exchange
+                symbol_entry.insert(exchange, snapshot);
+                if self.data.len() != symbols {
+                    self.symbols.set(self.data.len());
+                }
+                true
+            }
+        };
+
+        if let Some(volatility) = &self.volatility {
+            volatility.record(exchange, key, (bid + ask) / dec!(2));
+        }
+
+        if self.evaluation.on_change_only && !changed {
+            return;
+        }
+
+        let interval = self.evaluation.min_interval();
+        if !interval.is_zero() {
+            let now = Instant::now();
+            match self.last_evaluated.get_mut(&key) {
+                Some(last) if now.duration_since(*last) < interval => {
+                    self.pending.insert(key);
+                    return;
+                }
+                Some(last) => *last = now,
+                None => {
+                    self.last_evaluated.insert(key, now);
+                }
+            }
+            self.pending.remove(&key);
+        }
+
+        self.evaluate(key);
+    }
 
-        let symbol_entry = self
+    /// Forgets the symbol that has gone longest without a quote.
+    fn prune_stalest_symbol(&mut self) {
+        let stalest = self
             .data
-            .entry(symbol.to_string())
-            .or_insert_with(HashMap::new);
+            .iter()
+            .min_by_key(|(_, snapshots)| snapshots.values().map(|s| s.timestamp).max())
+            .map(|(symbol, _)| *symbol);
+        if let Some(symbol) = stalest {
+            self.data.remove(&symbol);
+            self.last_evaluated.remove(&symbol);
+            self.pending.remove(&symbol);
+            self.symbols.pruned(1);
+        }
+    }
 
-        // Insert or overwrite the snapshot for this exchange
-        symbol_entry.insert(exchange.to_string(), snapshot);
+    /// Evaluates the symbols whose quotes were held back by `min_interval`
+    /// and whose interval has now passed.
+    pub fn flush_due(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let interval = self.evaluation.min_interval();
+        let now = Instant::now();
+        let due: Vec<Symbol> = self
+            .pending
+            .iter()
+            .filter(|symbol| {
+                self.last_evaluated
+                    .get(symbol)
+                    .is_none_or(|last| now.duration_since(*last) >= interval)
+            })
+            .copied()
+            .collect();
+        for symbol in due {
+            self.pending.remove(&symbol);
+            self.last_evaluated.insert(symbol, now);
+            self.evaluate(symbol);
+        }
+    }
 
-        // Compare using the updated map for this symbol
-        let results = self.comparator.compare(symbol_entry);
-        // CSV logging disabled — using Telegram notifications instead
-        // for (a, b, diff) in &results {
-        //     self.logger.log(a, b, *diff);
-        // }
+    /// Compares the latest snapshots of `symbol` across exchanges, then logs
+    /// and alerts on the spreads above the threshold.
+    fn evaluate(&mut self, symbol: Symbol) {
+        let Some(symbol_entry) = self.data.get(&symbol) else {
+            return;
+        };
+        let mut results = self.comparator.compare(symbol_entry);
+        if let Some(fx) = &self.fx {
+            results.retain(|(a, b, _)| !depegged(fx, a, b));
+        }
+        if let Some(listing_mode) = &self.listing_mode {
+            results.retain(|(a, b, _)| {
+                listing_mode
+                    .ineligible(symbol, [a.exchange, b.exchange])
+                    .is_none()
+            });
+        }
+        results.retain(|(a, b, _)| {
+            self.pauses
+                .holding(symbol, [a.exchange, b.exchange])
+                .is_none()
+        });
+        if let Some(logger) = &self.logger {
+            for (a, b, diff) in &results {
+                if let Err(e) = logger.log(a, b, *diff) {
+                    eprintln!("❌ Spread log write failed: {}", e);
+                }
+            }
+        }
+        for (a, b, diff) in &results {
+            self.remember(symbol, a, b, *diff);
+        }
 
         // ── Telegram alerts ──────────────────────────────────────────
         if let Some(ref tx) = self.telegram_tx {
+            let multiplier = self
+                .volatility
+                .as_ref()
+                .map_or(Decimal::ONE, |v| v.multiplier(symbol));
             for (a, b, diff) in results {
+                if diff < self.alert_gate.min_diff() * multiplier {
+                    continue;
+                }
+                let transfer = self.transfer_note(&a, &b);
+                if transfer.is_some() && self.transfers.as_ref().is_some_and(|(_, block)| *block) {
+                    continue;
+                }
+                let notes: Vec<_> = [
+                    self.fx_note(symbol, &a, &b),
+                    self.listing_note(a.symbol, [a.exchange, b.exchange]),
+                    transfer,
+                    self.volatility.as_ref().and_then(|v| v.note(symbol)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                let note = (!notes.is_empty()).then(|| notes.join("; "));
                 self.alert_gate.maybe_send(
                     tx,
-                    &a.symbol,
-                    &a.exchange,
-                    &b.exchange,
-                    a.bid,
-                    a.ask,
-                    a.mid,
-                    b.bid,
-                    b.ask,
-                    b.mid,
-                    diff,
+                    AppAlert {
+                        symbol: symbol.as_str().to_string(),
+                        exchange_a: a.exchange.name().to_string(),
+                        exchange_b: b.exchange.name().to_string(),
+                        bid_a: a.bid,
+                        ask_a: a.ask,
+                        mid_a: a.mid,
+                        bid_b: b.bid,
+                        ask_b: b.ask,
+                        mid_b: b.mid,
+                        diff_percent: diff,
+                        note,
+                        channels: BTreeSet::new(),
+                    },
                 );
             }
         }
     }
+
+    /// Points out a listing of `symbol` on either exchange around now.
+    fn listing_note(&self, symbol: Symbol, exchanges: [ExchangeId; 2]) -> Option<String> {
+        let calendar = self.calendar.as_ref()?;
+        let now = Utc::now();
+        let listing = exchanges
+            .into_iter()
+            .find_map(|exchange| calendar.listing(exchange, symbol, now))?;
+        Some(format!(
+            "{}; spreads around a listing are rarely tradeable",
+            listing.describe(now)
+        ))
+    }
+
+    /// Points out the sides quoted in another currency than the other side
+    /// and the rate they were converted at.
+    fn fx_note(&self, symbol: Symbol, a: &MarketSnapshot, b: &MarketSnapshot) -> Option<String> {
+        let fx = self.fx.as_ref().filter(|_| a.symbol != b.symbol)?;
+        let converted: Vec<_> = [a, b]
+            .into_iter()
+            .filter(|s| s.symbol != symbol)
+            .filter_map(|s| {
+                let (_, quote) = transfers::currencies(s.symbol.as_str())?;
+                Some(format!(
+                    "{} quotes {}, converted at {} {} per {}",
+                    s.exchange,
+                    s.symbol,
+                    fx.rate(quote)?.normalize(),
+                    fx.reference(),
+                    quote
+                ))
+            })
+            .collect();
+        (!converted.is_empty()).then(|| converted.join("; "))
+    }
+
+    /// Points out a suspended transfer from the cheaper exchange to the
+    /// dearer one.
+    fn transfer_note(&self, a: &MarketSnapshot, b: &MarketSnapshot) -> Option<String> {
+        let (status, _) = self.transfers.as_ref()?;
+        let (buy, sell) = if a.mid <= b.mid { (a, b) } else { (b, a) };
+        let blocker = status.blocker(a.symbol, buy.exchange, sell.exchange)?;
+        Some(format!(
+            "{}; the gap can't be closed by moving the asset",
+            blocker
+        ))
+    }
+}
+
+impl MarketTracker {
+    fn remember(
+        &mut self,
+        symbol: Symbol,
+        a: &MarketSnapshot,
+        b: &MarketSnapshot,
+        diff_percent: Decimal,
+    ) {
+        if self.recent.len() >= config::get().limits.recent_opportunities {
+            self.recent.pop_front();
+        }
+        let opportunity = Opportunity {
+            symbol,
+            exchange_a: a.exchange,
+            exchange_b: b.exchange,
+            mid_a: a.mid,
+            mid_b: b.mid,
+            diff_percent,
+            at_ms: Utc::now().timestamp_millis(),
+        };
+        // No subscribers is fine.
+        let _ = self.opportunities.send(opportunity.clone());
+        self.recent.push_back(opportunity);
+    }
+
+    /// Alerts (and spread-log entries) from `percent` up. The comparator
+    /// works on the same scale as at startup: `percent / 100`.
+    pub fn set_alert_threshold(&mut self, percent: Decimal) {
+        self.comparator.threshold = percent / dec!(100);
+        self.alert_gate.set_min_diff(percent);
+    }
+}
+
+/// Whether `a` and `b` are quoted in different currencies and either one
+/// has lost its peg.
+fn depegged(fx: &Fx, a: &MarketSnapshot, b: &MarketSnapshot) -> bool {
+    let (Some((_, quote_a)), Some((_, quote_b))) = (
+        transfers::currencies(a.symbol.as_str()),
+        transfers::currencies(b.symbol.as_str()),
+    ) else {
+        return false;
+    };
+    quote_a != quote_b && (fx.depeg(quote_a).is_some() || fx.depeg(quote_b).is_some())
+}
+
+// ── Tracker actor ────────────────────────────────────────────────────────────
+
+/// Pending quotes are keyed per exchange and symbol.
+type QuoteKey = (ExchangeId, Symbol);
+
+enum TrackerCommand {
+    ResetAlerts,
+    FlushDigest,
+    AlertState(oneshot::Sender<AlertGateState>),
+    RecentOpportunities(oneshot::Sender<Vec<Opportunity>>),
+    SetAlertThreshold(Decimal),
+}
+
+/// Cheap, cloneable handle to a [`MarketTracker`] running as its own task.
+///
+/// Feeds never wait on the tracker: quotes go into one coalescing queue that
+/// keeps the latest quote per exchange and symbol, and the tracker task
+/// applies them in arrival order. The task stops once every handle is dropped.
+#[derive(Clone)]
+pub struct TrackerHandle {
+    quotes: Arc<CoalescingQueue<QuoteKey, TopOfBook>>,
+    commands: mpsc::Sender<TrackerCommand>,
+    opportunities: broadcast::Sender<Opportunity>,
+}
+
+impl TrackerHandle {
+    /// Never blocks; replaces any quote for the same exchange and symbol that
+    /// the tracker has not applied yet.
+    pub fn push(&self, exchange: ExchangeId, quote: TopOfBook) {
+        self.quotes.push((exchange, quote.symbol), quote);
+    }
+
+    /// Feeds every quote published on `bus` into the tracker until `cancel` is
+    /// cancelled. If the tracker falls behind the bus it skips ahead; it only
+    /// needs the latest quotes.
+    pub fn follow(&self, bus: &QuoteBus, cancel: CancellationToken) {
+        let tracker = self.clone();
+        let mut quotes = bus.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let quote = tokio::select! {
+                    quote = quotes.recv() => quote,
+                    _ = cancel.cancelled() => break,
+                };
+                match quote {
+                    Ok(quote) => tracker.push(quote.exchange, quote.top.clone()),
+                    Err(RecvError::Lagged(missed)) => {
+                        eprintln!(
+                            "⚠️ Tracker lagged behind the quote bus, skipped {} quotes",
+                            missed
+                        );
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Every opportunity found from now on.
+    pub fn subscribe_opportunities(&self) -> broadcast::Receiver<Opportunity> {
+        self.opportunities.subscribe()
+    }
+
+    /// Clears the alert gate's dedup and cooldown state.
+    pub async fn reset_alerts(&self) {
+        let _ = self.commands.send(TrackerCommand::ResetAlerts).await;
+    }
+
+    /// Sends the alert digest (see [`AlertGate::flush_digest`]).
+    pub async fn flush_digest(&self) {
+        let _ = self.commands.send(TrackerCommand::FlushDigest).await;
+    }
+
+    /// The latest opportunities, oldest first; `None` if the tracker task has
+    /// stopped.
+    pub async fn recent_opportunities(&self) -> Option<Vec<Opportunity>> {
+        let (tx, rx) = oneshot::channel();
+        self.commands
+            .send(TrackerCommand::RecentOpportunities(tx))
+            .await
+            .ok()?;
+        rx.await.ok()
+    }
+
+    /// See [`MarketTracker::set_alert_threshold`].
+    pub async fn set_alert_threshold(&self, percent: Decimal) {
+        let _ = self
+            .commands
+            .send(TrackerCommand::SetAlertThreshold(percent))
+            .await;
+    }
+
+    /// The alert gate's dedup and cooldown state; `None` if the tracker task
+    /// has stopped.
+    pub async fn alert_state(&self) -> Option<AlertGateState> {
+        let (tx, rx) = oneshot::channel();
+        self.commands
+            .send(TrackerCommand::AlertState(tx))
+            .await
+            .ok()?;
+        rx.await.ok()
+    }
+}
+
+impl MarketTracker {
+    /// Moves the tracker into its own task. Must be called inside a Tokio runtime.
+    pub fn spawn(mut self) -> TrackerHandle {
+        let quotes = Arc::new(CoalescingQueue::<QuoteKey, TopOfBook>::new(
+            "tracker quotes",
+        ));
+        let (commands, mut command_rx) =
+            backpressure::lossless_channel("tracker commands", TRACKER_COMMAND_CAPACITY);
+
+        // Held-back symbols are picked up within half an interval of falling due.
+        let debounced = !self.evaluation.min_interval().is_zero();
+        let mut flush =
+            time::interval((self.evaluation.min_interval() / 2).max(Duration::from_millis(1)));
+        flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let opportunities = self.opportunities.clone();
+        let pending = quotes.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    quote = pending.pop() => {
+                        let Some(((exchange, symbol), quote)) = quote else { break };
+                        self.update(exchange, symbol, quote.bid, quote.ask, quote.market_type);
+                    }
+                    _ = flush.tick(), if debounced => self.flush_due(),
+                    command = command_rx.recv() => match command {
+                        Some(TrackerCommand::ResetAlerts) => self.alert_gate.reset(),
+                        Some(TrackerCommand::FlushDigest) => {
+                            if let Some(tx) = &self.telegram_tx {
+                                self.alert_gate.flush_digest(tx);
+                            }
+                        }
+                        Some(TrackerCommand::AlertState(reply)) => {
+                            let _ = reply.send(self.alert_gate.state());
+                        }
+                        Some(TrackerCommand::RecentOpportunities(reply)) => {
+                            let _ = reply.send(self.recent.iter().cloned().collect());
+                        }
+                        Some(TrackerCommand::SetAlertThreshold(percent)) => {
+                            self.set_alert_threshold(percent);
+                        }
+                        // Every handle is gone: apply what is left and stop.
+                        None => {
+                            while let Some(((exchange, symbol), quote)) = pending.try_pop() {
+                                self.update(exchange, symbol, quote.bid, quote.ask, quote.market_type);
+                            }
+                            self.flush_due();
+                            break;
+                        }
+                    },
+                }
+            }
+        });
+
+        TrackerHandle {
+            quotes,
+            commands,
+            opportunities,
+        }
+    }
 }
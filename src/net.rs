@@ -0,0 +1,253 @@
+//! Outbound connections that honour the `[network]` configuration: endpoint
+//! overrides, static DNS entries and a SOCKS5/HTTP proxy.
+//!
+//! tokio-tungstenite has no proxy support, so WebSocket connections open the
+//! TCP stream here (directly or through the proxy tunnel) and then run the
+//! TLS + WebSocket handshake on top of it, using the shared rustls config
+//! from [`crate::tls`].
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_socks::tcp::Socks5Stream;
+use tokio_tungstenite::{
+    client_async_tls_with_config,
+    tungstenite::{
+        client::IntoClientRequest,
+        error::{Error as WsError, UrlError},
+        handshake::client::Response,
+        http::Uri,
+    },
+    Connector, MaybeTlsStream, WebSocketStream,
+};
+
+use crate::{
+    config::{self, NetworkConfig},
+    tls::{self, PinMismatch},
+};
+
+pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectError {
+    #[error("connecting to {host}:{port} failed: {source}")]
+    Tcp {
+        host: String,
+        port: u16,
+        source: std::io::Error,
+    },
+    #[error("proxy error: {0}")]
+    Proxy(String),
+    #[error("TLS handshake with {host} failed: {reason}")]
+    Tls { host: String, reason: String },
+    #[error(transparent)]
+    PinMismatch(#[from] PinMismatch),
+    #[error("WebSocket handshake failed: {0}")]
+    Handshake(#[from] WsError),
+}
+
+impl ConnectError {
+    /// Whether retrying the same endpoint can't succeed without a config or
+    /// certificate change.
+    pub fn is_tls(&self) -> bool {
+        matches!(self, Self::Tls { .. } | Self::PinMismatch(_))
+    }
+
+    /// tokio-rustls reports handshake failures as I/O errors wrapping the
+    /// `rustls::Error`; pull those out into typed variants.
+    fn classify(host: &str, error: WsError) -> Self {
+        let WsError::Io(io) = &error else {
+            return Self::Handshake(error);
+        };
+        let Some(tls_error) = io.get_ref().and_then(|e| e.downcast_ref::<rustls::Error>()) else {
+            return Self::Handshake(error);
+        };
+        if let rustls::Error::InvalidCertificate(rustls::CertificateError::Other(other)) = tls_error
+        {
+            if let Some(pin) = other.0.downcast_ref::<PinMismatch>() {
+                return Self::PinMismatch(PinMismatch {
+                    host: pin.host.clone(),
+                    fingerprint: pin.fingerprint.clone(),
+                });
+            }
+        }
+        Self::Tls {
+            host: host.to_string(),
+            reason: tls_error.to_string(),
+        }
+    }
+}
+
+/// Drop-in replacement for `tokio_tungstenite::connect_async`.
+pub async fn connect_ws(url: &str) -> Result<(WsStream, Response), ConnectError> {
+    let net = &config::get().network;
+    let request = net.endpoint(url).into_client_request()?;
+
+    let host = request
+        .uri()
+        .host()
+        .ok_or(WsError::Url(UrlError::NoHostName))?
+        .to_string();
+    let port = request
+        .uri()
+        .port_u16()
+        .or_else(|| match request.uri().scheme_str() {
+            Some("wss") => Some(443),
+            Some("ws") => Some(80),
+            _ => None,
+        })
+        .ok_or(WsError::Url(UrlError::UnsupportedUrlScheme))?;
+
+    let socket = open_tcp(net, &host, port).await?;
+    let _ = socket.set_nodelay(true);
+    let connector = Connector::Rustls(tls::client_config());
+    client_async_tls_with_config(request, socket, None, Some(connector))
+        .await
+        .map_err(|e| ConnectError::classify(&host, e))
+}
+
+/// `reqwest` client builder with the proxy and DNS overrides applied. Use
+/// `config::get().network.endpoint(url)` for the request URLs.
+pub fn http_client_builder() -> reqwest::ClientBuilder {
+    let net = &config::get().network;
+    let mut builder = reqwest::Client::builder();
+    if config::get().tls.is_customized() {
+        builder = builder.tls_backend_preconfigured((*tls::client_config()).clone());
+    }
+    if let Some(proxy) = &net.proxy {
+        match reqwest::Proxy::all(proxy) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => eprintln!("⚠️ Ignoring invalid proxy {}: {}", proxy, e),
+        }
+    }
+    for (host, ip) in &net.resolve {
+        // Port 0 keeps the port from the request URL.
+        builder = builder.resolve(host, (*ip, 0).into());
+    }
+    builder
+}
+
+/// `reqwest` client with the network configuration applied.
+pub fn http_client() -> reqwest::Client {
+    http_client_builder().build().unwrap_or_else(|e| {
+        eprintln!(
+            "⚠️ Failed to build configured HTTP client ({}); using defaults",
+            e
+        );
+        reqwest::Client::new()
+    })
+}
+
+async fn open_tcp(net: &NetworkConfig, host: &str, port: u16) -> Result<TcpStream, ConnectError> {
+    // A DNS override replaces the hostname for the TCP leg only; TLS still
+    // uses `host` for SNI and certificate checks.
+    let target = net
+        .resolve
+        .get(host)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| host.to_string());
+
+    let Some(proxy) = &net.proxy else {
+        return TcpStream::connect((target.as_str(), port))
+            .await
+            .map_err(|source| ConnectError::Tcp {
+                host: target,
+                port,
+                source,
+            });
+    };
+
+    let proxy: Uri = proxy
+        .parse()
+        .map_err(|e| ConnectError::Proxy(format!("invalid proxy url {}: {}", proxy, e)))?;
+    let proxy_host = proxy
+        .host()
+        .ok_or_else(|| ConnectError::Proxy("proxy url has no host".to_string()))?;
+    let credentials = proxy
+        .authority()
+        .and_then(|a| a.as_str().rsplit_once('@'))
+        .and_then(|(userinfo, _)| userinfo.split_once(':'));
+
+    match proxy.scheme_str() {
+        Some("socks5") | Some("socks5h") => {
+            let proxy_addr = (proxy_host, proxy.port_u16().unwrap_or(1080));
+            let stream = match credentials {
+                Some((user, pass)) => {
+                    Socks5Stream::connect_with_password(
+                        proxy_addr,
+                        (target.as_str(), port),
+                        user,
+                        pass,
+                    )
+                    .await
+                }
+                None => Socks5Stream::connect(proxy_addr, (target.as_str(), port)).await,
+            }
+            .map_err(|e| ConnectError::Proxy(e.to_string()))?;
+            Ok(stream.into_inner())
+        }
+        Some("http") => {
+            let proxy_port = proxy.port_u16().unwrap_or(8080);
+            let mut stream =
+                TcpStream::connect((proxy_host, proxy_port))
+                    .await
+                    .map_err(|source| ConnectError::Tcp {
+                        host: proxy_host.to_string(),
+                        port: proxy_port,
+                        source,
+                    })?;
+            http_connect(&mut stream, &target, port, credentials)
+                .await
+                .map_err(|e| ConnectError::Proxy(e.to_string()))?;
+            Ok(stream)
+        }
+        other => Err(ConnectError::Proxy(format!(
+            "unsupported proxy scheme {:?}",
+            other
+        ))),
+    }
+}
+
+/// Opens an HTTP `CONNECT` tunnel to `host:port` over `stream`.
+async fn http_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    credentials: Option<(&str, &str)>,
+) -> std::io::Result<()> {
+    let mut request = format!(
+        "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n",
+        host = host,
+        port = port
+    );
+    if let Some((user, pass)) = credentials {
+        request.push_str(&format!(
+            "Proxy-Authorization: Basic {}\r\n",
+            BASE64.encode(format!("{}:{}", user, pass))
+        ));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read the response head byte by byte so nothing past it is consumed.
+    let mut head = Vec::with_capacity(256);
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > 8192 {
+            return Err(std::io::Error::other("proxy response header too large"));
+        }
+        let byte = stream.read_u8().await?;
+        head.push(byte);
+    }
+
+    let status_line = String::from_utf8_lossy(&head);
+    let status_line = status_line.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some("200") => Ok(()),
+        _ => Err(std::io::Error::other(format!(
+            "proxy CONNECT failed: {}",
+            status_line
+        ))),
+    }
+}
@@ -4,13 +4,31 @@
 //! 1. `diff_percent >= min_diff` (e.g. 5%)
 //! 2. For the same pair key, the diff jumped by at least `re_alert_delta` (e.g. 1pp)
 //! 3. At least `cooldown` time has passed since the last send
+//!
+//! The [`AlertRouter`] picks each alert's channels by symbol and spread.
+//! Alerts routed to the digest skip guards 2 and 3: the digest keeps the
+//! biggest spread per pair until [`AlertGate::flush_digest`] sends them all.
+//!
+//! Time is tokio's, so a paused test runtime can step through cooldowns.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
 
-use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use tokio::{sync::mpsc, time::Instant};
 
-use tokio::sync::mpsc;
+use crate::{
+    config::{self, AlertChannel},
+    error::NotifyError,
+    limits::SizeGauge,
+    models::money::Decimal,
+    state::{self, AlertGateState},
+};
 
-use super::telegram::AppAlert;
+use super::{
+    router::AlertRouter,
+    telegram::{AppAlert, Notification},
+};
 
 /// Composite key for deduplication: "SYMBOL|EXCHANGE_A|EXCHANGE_B"
 fn pair_key(symbol: &str, exchange_a: &str, exchange_b: &str) -> String {
@@ -25,53 +43,71 @@ fn pair_key(symbol: &str, exchange_a: &str, exchange_b: &str) -> String {
 
 pub struct AlertGate {
     /// Last diff% we actually notified for each pair key.
-    last_notified: HashMap<String, f64>,
+    last_notified: HashMap<String, Decimal>,
     /// When the last Telegram API call was made (global).
     last_send_time: Option<Instant>,
     /// Minimum diff% required to even consider alerting.
-    min_diff: f64,
+    min_diff: Decimal,
     /// The new diff must exceed last notified diff by at least this many pp.
-    re_alert_delta: f64,
+    re_alert_delta: Decimal,
     /// Global cooldown between any two sends.
     cooldown: Duration,
+    /// Size of `last_notified`, capped by `[limits] alert_keys`.
+    keys: Arc<SizeGauge>,
+    /// Which channels each alert goes to.
+    router: AlertRouter,
+    /// The biggest alert per pair key since the last digest; at most
+    /// `[limits] alert_keys` pairs.
+    digest: HashMap<String, AppAlert>,
 }
 
 impl AlertGate {
-    pub fn new(min_diff: f64, re_alert_delta: f64, cooldown_secs: u64) -> Self {
+    pub fn new(min_diff: Decimal, re_alert_delta: Decimal, cooldown_secs: u64) -> Self {
         Self {
             last_notified: HashMap::new(),
             last_send_time: None,
             min_diff,
             re_alert_delta,
             cooldown: Duration::from_secs(cooldown_secs),
+            keys: SizeGauge::register("alert gate keys", config::get().limits.alert_keys),
+            router: AlertRouter::default(),
+            digest: HashMap::new(),
         }
     }
 
-    /// Evaluate all three guards and, if they pass, enqueue the alert.
+    /// Routes alerts by `[[notifications.route]]` instead of sending them
+    /// all to Telegram.
+    pub fn with_router(mut self, router: AlertRouter) -> Self {
+        self.router = router;
+        self
+    }
+
+    /// Evaluate all three guards and, if they pass, enqueue `alert` on the
+    /// channels the router picks for it; its own `channels` are ignored.
     ///
     /// This is intentionally **synchronous** (`try_send`) so we never block
     /// the hot path that feeds `MarketTracker::update`.
-    pub fn maybe_send(
-        &mut self,
-        tx: &mpsc::Sender<AppAlert>,
-        symbol: &str,
-        exchange_a: &str,
-        exchange_b: &str,
-        bid_a: f64,
-        ask_a: f64,
-        mid_a: f64,
-        bid_b: f64,
-        ask_b: f64,
-        mid_b: f64,
-        diff_percent: f64,
-    ) {
+    pub fn maybe_send(&mut self, tx: &mpsc::Sender<Notification>, alert: AppAlert) {
+        let diff_percent = alert.diff_percent;
         // ── Guard 1: minimum diff ────────────────────────────────────────
         if diff_percent < self.min_diff {
             return;
         }
 
+        let mut channels = self.router.channels(&alert.symbol, diff_percent);
+        let key = pair_key(&alert.symbol, &alert.exchange_a, &alert.exchange_b);
+        let alert = AppAlert {
+            channels: BTreeSet::new(),
+            ..alert
+        };
+        if channels.remove(&AlertChannel::Digest) {
+            self.add_to_digest(&key, &alert);
+        }
+        if channels.is_empty() {
+            return;
+        }
+
         // ── Guard 2: re-alert delta ──────────────────────────────────────
-        let key = pair_key(symbol, exchange_a, exchange_b);
         if let Some(&prev) = self.last_notified.get(&key) {
             if diff_percent < prev + self.re_alert_delta {
                 return; // not a big enough jump
@@ -86,37 +122,112 @@ impl AlertGate {
         }
 
         // ── All guards passed — fire it ──────────────────────────────────
-        let alert = AppAlert {
-            symbol: symbol.to_string(),
-            exchange_a: exchange_a.to_string(),
-            exchange_b: exchange_b.to_string(),
-            bid_a,
-            ask_a,
-            mid_a,
-            bid_b,
-            ask_b,
-            mid_b,
-            diff_percent,
-        };
+        let alert = AppAlert { channels, ..alert };
 
         // Non-blocking send — if the channel is full we just drop the alert.
-        match tx.try_send(alert) {
+        match tx
+            .try_send(Notification::Arbitrage(alert))
+            .map_err(NotifyError::from)
+        {
             Ok(_) => {
+                if self.keys.is_full(self.last_notified.len())
+                    && !self.last_notified.contains_key(&key)
+                {
+                    self.prune_smallest();
+                }
                 self.last_notified.insert(key, diff_percent);
+                self.keys.set(self.last_notified.len());
                 self.last_send_time = Some(Instant::now());
             }
-            Err(mpsc::error::TrySendError::Full(_)) => {
-                eprintln!("[AlertGate] Channel full — alert dropped for {}", key);
+            Err(e @ NotifyError::QueueFull) => {
+                eprintln!("[AlertGate] {} — alert dropped for {}", e, key);
             }
-            Err(mpsc::error::TrySendError::Closed(_)) => {
-                eprintln!("[AlertGate] Channel closed — Telegram worker gone");
+            Err(e) => {
+                eprintln!("[AlertGate] {}", e);
             }
         }
     }
 
+    /// Keeps `alert` for the next digest unless the pair already has a
+    /// bigger spread in it.
+    fn add_to_digest(&mut self, key: &str, alert: &AppAlert) {
+        let full = self.digest.len() >= self.keys.cap();
+        match self.digest.get_mut(key) {
+            Some(kept) if kept.diff_percent >= alert.diff_percent => {}
+            Some(kept) => *kept = alert.clone(),
+            None if !full => {
+                self.digest.insert(key.to_string(), alert.clone());
+            }
+            None => {}
+        }
+    }
+
+    /// Sends the alerts collected for the digest, biggest spread first, and
+    /// starts a new one. Nothing is sent when there are none.
+    pub fn flush_digest(&mut self, tx: &mpsc::Sender<Notification>) {
+        if self.digest.is_empty() {
+            return;
+        }
+        let mut alerts: Vec<_> = self.digest.drain().map(|(_, alert)| alert).collect();
+        alerts.sort_by_key(|alert| std::cmp::Reverse(alert.diff_percent));
+        if let Err(e) = tx
+            .try_send(Notification::Digest(alerts))
+            .map_err(NotifyError::from)
+        {
+            eprintln!("[AlertGate] {} — digest dropped", e);
+        }
+    }
+
+    /// Forgets the pair with the smallest notified diff, the one most likely
+    /// to re-alert anyway.
+    fn prune_smallest(&mut self) {
+        let smallest = self
+            .last_notified
+            .iter()
+            .min_by_key(|(_, diff)| **diff)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = smallest {
+            self.last_notified.remove(&key);
+            self.keys.pruned(1);
+        }
+    }
+
+    pub fn min_diff(&self) -> Decimal {
+        self.min_diff
+    }
+
+    pub fn set_min_diff(&mut self, min_diff: Decimal) {
+        self.min_diff = min_diff;
+    }
+
+    /// The dedup and cooldown state, for saving across restarts.
+    pub fn state(&self) -> AlertGateState {
+        AlertGateState {
+            last_notified: self.last_notified.clone(),
+            last_send_ms: self
+                .last_send_time
+                .map(|t| state::now_ms() - t.elapsed().as_millis() as i64),
+        }
+    }
+
+    /// Picks up where a previous run left off: pairs it already alerted on
+    /// don't re-alert, and its cooldown still applies.
+    pub fn restore(&mut self, saved: AlertGateState) {
+        self.last_notified = saved.last_notified;
+        self.last_send_time = saved.last_send_ms.and_then(|ms| {
+            let ago = Duration::from_millis(state::now_ms().saturating_sub(ms).max(0) as u64);
+            Instant::now().checked_sub(ago)
+        });
+        while self.last_notified.len() > self.keys.cap() {
+            self.prune_smallest();
+        }
+        self.keys.set(self.last_notified.len());
+    }
+
     /// Wipe all tracked state (called by the 24-hour scheduler).
     pub fn reset(&mut self) {
         self.last_notified.clear();
+        self.keys.set(0);
         self.last_send_time = None;
         println!("[AlertGate] Notification state reset (24h scheduler)");
     }
@@ -0,0 +1,139 @@
+//! Email alerts through an HTTP mail API (`[notifications.email]`).
+//!
+//! Like the Telegram worker, a dedicated task drains a channel of
+//! [`Notification`]s. It sends the arbitrage alerts routed to email and the
+//! digests; everything else stays on Telegram.
+
+use log::{error, info, warn};
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::{
+    config::EmailConfig,
+    error::{Classify, NotifyError},
+    keys, net,
+};
+
+use super::telegram::{AppAlert, Notification};
+
+/// Pause before the single retry of a transient send failure.
+const RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+#[derive(Serialize)]
+struct SendEmailPayload<'a> {
+    from: &'a str,
+    to: &'a [String],
+    subject: &'a str,
+    text: &'a str,
+}
+
+pub struct EmailNotifier {
+    client: reqwest::Client,
+    config: EmailConfig,
+}
+
+impl EmailNotifier {
+    /// Spawns the background email worker; `None` when `url` is unset.
+    pub fn spawn(config: &EmailConfig) -> Option<mpsc::Sender<Notification>> {
+        config.url.as_ref()?;
+        let client = net::http_client_builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        let notifier = Self {
+            client,
+            config: config.clone(),
+        };
+
+        let (tx, mut rx) = mpsc::channel::<Notification>(100);
+        tokio::spawn(async move {
+            info!("[Email] Worker started.");
+            while let Some(notification) = rx.recv().await {
+                let (subject, text) = match notification {
+                    Notification::Arbitrage(alert) => (
+                        format!(
+                            "Arbitrage alert: {} {:.2}%",
+                            alert.symbol, alert.diff_percent
+                        ),
+                        alert_text(&alert),
+                    ),
+                    Notification::Digest(alerts) => (
+                        format!("Alert digest: {} alert(s)", alerts.len()),
+                        alerts.iter().map(alert_line).collect::<Vec<_>>().join("\n"),
+                    ),
+                    _ => continue,
+                };
+                notifier.deliver(&subject, &text).await;
+            }
+            info!("[Email] Worker stopped.");
+        });
+        Some(tx)
+    }
+
+    /// Sends the email, retrying once if the failure is transient.
+    async fn deliver(&self, subject: &str, text: &str) {
+        let mut result = self.send(subject, text).await;
+        if let Err(e) = &result {
+            if e.is_retryable() {
+                warn!("[Email] {}, retrying once", e);
+                tokio::time::sleep(RETRY_DELAY).await;
+                result = self.send(subject, text).await;
+            }
+        }
+        match result {
+            Ok(()) => info!("[Email] Sent: {}", subject),
+            Err(e) => error!("[Email] {}", e),
+        }
+    }
+
+    async fn send(&self, subject: &str, text: &str) -> Result<(), NotifyError> {
+        let url = self
+            .config
+            .url
+            .as_deref()
+            .ok_or(NotifyError::MissingConfig("[notifications.email] url"))?;
+        let payload = SendEmailPayload {
+            from: &self.config.from,
+            to: &self.config.to,
+            subject,
+            text,
+        };
+        let mut request = self.client.post(url).json(&payload);
+        if let Some(key) = keys::var("EMAIL_API_KEY") {
+            request = request.bearer_auth(key.expose());
+        }
+        let resp = request.send().await?;
+        if resp.status().is_success() {
+            return Ok(());
+        }
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        Err(NotifyError::Api { status, body })
+    }
+}
+
+fn alert_line(alert: &AppAlert) -> String {
+    format!(
+        "{} {} <-> {} {:.2}%",
+        alert.symbol, alert.exchange_a, alert.exchange_b, alert.diff_percent
+    )
+}
+
+fn alert_text(alert: &AppAlert) -> String {
+    let mut text = format!(
+        "{}\n\n{}: bid {:.4} ask {:.4} mid {:.4}\n{}: bid {:.4} ask {:.4} mid {:.4}",
+        alert_line(alert),
+        alert.exchange_a,
+        alert.bid_a,
+        alert.ask_a,
+        alert.mid_a,
+        alert.exchange_b,
+        alert.bid_b,
+        alert.ask_b,
+        alert.mid_b,
+    );
+    if let Some(note) = &alert.note {
+        text.push_str(&format!("\n\nNote: {}", note));
+    }
+    text
+}
@@ -1,2 +1,5 @@
 pub mod alert_gate;
+pub mod email;
+pub mod router;
 pub mod telegram;
+pub mod telegram_commands;
@@ -0,0 +1,137 @@
+//! Where arbitrage alerts go, by symbol and spread.
+//!
+//! `[[notifications.route]]` bands map spreads to channels, e.g. 1–2% to the
+//! digest only, 2–5% to Telegram and above 5% to Telegram and email. The
+//! [`AlertGate`](super::alert_gate::AlertGate) asks [`AlertRouter`] for each
+//! alert's channels: digest entries are buffered and summed up periodically,
+//! the rest go out right away on the worker [`spawn`] puts in front of the
+//! Telegram and email workers.
+
+use std::collections::BTreeSet;
+
+use tokio::sync::mpsc;
+
+use crate::{
+    config::{AlertChannel, AlertRoute},
+    error::NotifyError,
+    models::money::Decimal,
+};
+
+use super::telegram::Notification;
+
+/// The configured routes; none sends every alert to Telegram.
+#[derive(Debug, Clone, Default)]
+pub struct AlertRouter {
+    routes: Vec<AlertRoute>,
+}
+
+impl AlertRouter {
+    pub fn new(routes: &[AlertRoute]) -> Self {
+        let routes = routes
+            .iter()
+            .cloned()
+            .map(|mut route| {
+                for symbol in &mut route.symbols {
+                    *symbol = symbol.to_uppercase();
+                }
+                route
+            })
+            .collect();
+        Self { routes }
+    }
+
+    /// The channels for a `diff` percent spread on `symbol`; empty when no
+    /// band covers it.
+    ///
+    /// ```
+    /// use arbitrage_bot::{
+    ///     config::{AlertChannel, AlertRoute},
+    ///     notifications::router::AlertRouter,
+    /// };
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let route = |min_diff, max_diff, channels: &[AlertChannel]| AlertRoute {
+    ///     symbols: Vec::new(),
+    ///     min_diff,
+    ///     max_diff,
+    ///     channels: channels.to_vec(),
+    /// };
+    /// let router = AlertRouter::new(&[
+    ///     route(dec!(1), Some(dec!(2)), &[AlertChannel::Digest]),
+    ///     route(dec!(2), None, &[AlertChannel::Telegram]),
+    /// ]);
+    /// assert!(router.channels("BTCUSDT", dec!(0.5)).is_empty());
+    /// assert_eq!(router.channels("BTCUSDT", dec!(1.5)), [AlertChannel::Digest].into());
+    /// assert_eq!(router.channels("BTCUSDT", dec!(2)), [AlertChannel::Telegram].into());
+    /// assert_eq!(router.floor(), Some(dec!(1)));
+    /// ```
+    pub fn channels(&self, symbol: &str, diff: Decimal) -> BTreeSet<AlertChannel> {
+        if self.routes.is_empty() {
+            return BTreeSet::from([AlertChannel::Telegram]);
+        }
+        let symbol = symbol.to_uppercase();
+        let own = self.routes.iter().any(|r| r.symbols.contains(&symbol));
+        self.routes
+            .iter()
+            .filter(|r| {
+                if own {
+                    r.symbols.contains(&symbol)
+                } else {
+                    r.symbols.is_empty()
+                }
+            })
+            .filter(|r| diff >= r.min_diff && r.max_diff.is_none_or(|max| diff < max))
+            .flat_map(|r| r.channels.iter().copied())
+            .collect()
+    }
+
+    /// The lowest spread any route alerts on; `None` without routes.
+    pub fn floor(&self) -> Option<Decimal> {
+        self.routes.iter().map(|r| r.min_diff).min()
+    }
+
+    /// Whether any route sends to the digest.
+    pub fn uses_digest(&self) -> bool {
+        self.routes
+            .iter()
+            .any(|r| r.channels.contains(&AlertChannel::Digest))
+    }
+}
+
+/// Hands each notification to the workers it is for: arbitrage alerts by
+/// their channels, digests to both, everything else to Telegram. With no
+/// email worker the Telegram one is returned as it is.
+pub fn spawn(
+    telegram: Option<mpsc::Sender<Notification>>,
+    email: Option<mpsc::Sender<Notification>>,
+) -> Option<mpsc::Sender<Notification>> {
+    let Some(email) = email else {
+        return telegram;
+    };
+    let (tx, mut rx) = mpsc::channel::<Notification>(100);
+    tokio::spawn(async move {
+        while let Some(notification) = rx.recv().await {
+            let (to_telegram, to_email) = match &notification {
+                Notification::Arbitrage(alert) => (
+                    alert.channels.contains(&AlertChannel::Telegram),
+                    alert.channels.contains(&AlertChannel::Email),
+                ),
+                Notification::Digest(_) => (true, true),
+                _ => (true, false),
+            };
+            if let Some(telegram) = telegram.as_ref().filter(|_| to_telegram) {
+                forward(telegram, notification.clone());
+            }
+            if to_email {
+                forward(&email, notification);
+            }
+        }
+    });
+    Some(tx)
+}
+
+fn forward(tx: &mpsc::Sender<Notification>, notification: Notification) {
+    if let Err(e) = tx.try_send(notification).map_err(NotifyError::from) {
+        eprintln!("[Notifications] {} — notification dropped", e);
+    }
+}
@@ -2,51 +2,142 @@
 //!
 //! # Architecture
 //! A dedicated Tokio task owns the [`TelegramNotifier`] and drains an `mpsc` channel
-//! of [`AppAlert`] messages, keeping the main application loop completely non-blocking.
+//! of [`Notification`] messages, keeping the main application loop completely non-blocking.
+//!
+//! The message types are always available; the notifier itself is part of the
+//! `telegram` feature.
 //!
 //! # Usage
 //! ```no_run
-//! use crate::notifications::telegram::{TelegramNotifier, AppAlert};
-//!
+//! # #[cfg(not(feature = "telegram"))]
+//! # fn main() {}
+//! # #[cfg(feature = "telegram")]
 //! #[tokio::main]
 //! async fn main() {
+//!     use arbitrage_bot::{
+//!         config::AlertChannel,
+//!         notifications::telegram::{AppAlert, Notification, TelegramNotifier},
+//!     };
+//!     use rust_decimal_macros::dec;
+//!
 //!     if let Some(tx) = TelegramNotifier::spawn() {
-//!         let _ = tx.try_send(AppAlert {
+//!         let _ = tx.try_send(Notification::Arbitrage(AppAlert {
 //!             symbol: "BTCUSDT".into(),
 //!             exchange_a: "binance".into(),
 //!             exchange_b: "bybit".into(),
-//!             bid_a: 100_000.0, ask_a: 100_010.0, mid_a: 100_005.0,
-//!             bid_b: 94_000.0,  ask_b: 94_010.0,  mid_b: 94_005.0,
-//!             diff_percent: 6.38,
-//!         });
+//!             bid_a: dec!(100000), ask_a: dec!(100010), mid_a: dec!(100005),
+//!             bid_b: dec!(94000),  ask_b: dec!(94010),  mid_b: dec!(94005),
+//!             diff_percent: dec!(6.38),
+//!             note: None,
+//!             channels: [AlertChannel::Telegram].into(),
+//!         }));
 //!     }
 //! }
 //! ```
 
-use log::{error, info, warn};
-use serde::Serialize;
-use std::env;
-use tokio::sync::mpsc;
+use std::collections::BTreeSet;
+
+use crate::{config::AlertChannel, error::Severity, models::money::Decimal};
+#[cfg(feature = "telegram")]
+use {
+    crate::{
+        error::{Classify, NotifyError},
+        keys, net,
+        secret::SecretString,
+    },
+    log::{error, info, warn},
+    serde::Serialize,
+    std::env,
+    tokio::sync::mpsc,
+};
 
 // ── Public Message Type ──────────────────────────────────────────────────────
 
 /// Arbitrage alert payload sent over the notification channel.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct AppAlert {
     pub symbol: String,
     pub exchange_a: String,
     pub exchange_b: String,
-    pub bid_a: f64,
-    pub ask_a: f64,
-    pub mid_a: f64,
-    pub bid_b: f64,
-    pub ask_b: f64,
-    pub mid_b: f64,
-    pub diff_percent: f64,
+    pub bid_a: Decimal,
+    pub ask_a: Decimal,
+    pub mid_a: Decimal,
+    pub bid_b: Decimal,
+    pub ask_b: Decimal,
+    pub mid_b: Decimal,
+    pub diff_percent: Decimal,
+    /// Why the spread may not be tradeable, e.g. a fresh listing.
+    pub note: Option<String>,
+    /// Where it goes right away (see `crate::notifications::router`).
+    pub channels: BTreeSet<AlertChannel>,
+}
+
+/// Everything the Telegram worker can deliver.
+#[derive(Debug, Clone)]
+pub enum Notification {
+    Arbitrage(AppAlert),
+    /// The alerts routed to the digest since the last one, biggest spread
+    /// first.
+    Digest(Vec<AppAlert>),
+    /// A market-data feed lost its connection and is reconnecting.
+    FeedReconnecting {
+        url: String,
+        reason: String,
+        severity: Severity,
+    },
+    /// In inventory mode, one exchange holds more of a currency than
+    /// `[engine.execution.inventory] max_skew_percent` allows.
+    InventorySkew {
+        exchange: String,
+        asset: String,
+        percent: Decimal,
+        limit: Decimal,
+    },
+    /// A plan to even out skewed inventory (see `crate::rebalance`), and
+    /// whether execution is placing its offsetting trades.
+    Rebalance {
+        plan: String,
+        executing: bool,
+    },
+    /// A pegged quote currency drifted from parity with `[fx] reference`
+    /// (see `crate::fx`), or is back.
+    Depeg {
+        currency: String,
+        reference: String,
+        rate: Decimal,
+        percent: Decimal,
+        recovered: bool,
+    },
+    /// An exchange's REST API kept failing its liveness probes, so
+    /// execution leaves it alone (see `crate::liveness`), or is back.
+    VenueImpaired {
+        exchange: String,
+        error: String,
+        recovered: bool,
+    },
+    /// A feed's WebSocket went quiet, so its quotes are polled over REST
+    /// and execution leaves the exchange alone (see `crate::failover`), or
+    /// it's streaming again.
+    FeedDegraded {
+        exchange: String,
+        symbol: String,
+        recovered: bool,
+    },
+    /// Collateral moved between accounts (see `crate::topup`): the transfer
+    /// ID or why it failed, or `None` when only announced.
+    MarginTopUp {
+        plan: String,
+        result: Option<Result<String, String>>,
+    },
 }
 
+/// Pause before the single retry of a transient send failure.
+#[cfg(feature = "telegram")]
+const RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
 // ── Telegram API Payload ─────────────────────────────────────────────────────
 
+#[cfg(feature = "telegram")]
 #[derive(Serialize)]
 struct SendMessagePayload<'a> {
     chat_id: &'a str,
@@ -55,23 +146,35 @@ struct SendMessagePayload<'a> {
     disable_notification: bool,
 }
 
+#[cfg(feature = "telegram")]
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 // ── Notifier ─────────────────────────────────────────────────────────────────
 
+#[cfg(feature = "telegram")]
 pub struct TelegramNotifier {
     client: reqwest::Client,
-    bot_token: String,
+    bot_token: SecretString,
     chat_id: String,
 }
 
+#[cfg(feature = "telegram")]
 impl TelegramNotifier {
     /// Spawns the background Telegram worker.
     ///
     /// Returns `None` (with a warning log) when env vars are missing.
-    pub fn spawn() -> Option<mpsc::Sender<AppAlert>> {
-        let bot_token = match env::var("TELEGRAM_KEY") {
-            Ok(t) if !t.is_empty() => t,
+    pub fn spawn() -> Option<mpsc::Sender<Notification>> {
+        let bot_token = match keys::var("TELEGRAM_KEY") {
+            Some(t) if !t.is_empty() => t,
             _ => {
-                warn!("TELEGRAM_KEY missing — Telegram notifications disabled.");
+                warn!(
+                    "{} — Telegram notifications disabled.",
+                    NotifyError::MissingConfig("TELEGRAM_KEY")
+                );
                 return None;
             }
         };
@@ -79,12 +182,15 @@ impl TelegramNotifier {
         let chat_id = match env::var("TELEGRAM_CHAT_ID") {
             Ok(id) if !id.is_empty() => id,
             _ => {
-                warn!("TELEGRAM_CHAT_ID missing — Telegram notifications disabled.");
+                warn!(
+                    "{} — Telegram notifications disabled.",
+                    NotifyError::MissingConfig("TELEGRAM_CHAT_ID")
+                );
                 return None;
             }
         };
 
-        let client = reqwest::Client::builder()
+        let client = net::http_client_builder()
             .timeout(std::time::Duration::from_secs(10))
             .build()
             .unwrap_or_else(|_| reqwest::Client::new());
@@ -95,12 +201,57 @@ impl TelegramNotifier {
             chat_id,
         };
 
-        let (tx, mut rx) = mpsc::channel::<AppAlert>(100);
+        let (tx, mut rx) = mpsc::channel::<Notification>(100);
 
         tokio::spawn(async move {
             info!("[Telegram] Worker started.");
-            while let Some(alert) = rx.recv().await {
-                notifier.send_message(&alert).await;
+            while let Some(notification) = rx.recv().await {
+                match notification {
+                    Notification::Arbitrage(alert) => {
+                        if alert.channels.contains(&AlertChannel::Telegram) {
+                            notifier.send_alert(&alert).await
+                        }
+                    }
+                    Notification::Digest(alerts) => notifier.send_digest(&alerts).await,
+                    Notification::FeedReconnecting {
+                        url,
+                        reason,
+                        severity,
+                    } => notifier.send_reconnecting(&url, &reason, severity).await,
+                    Notification::InventorySkew {
+                        exchange,
+                        asset,
+                        percent,
+                        limit,
+                    } => notifier.send_skew(&exchange, &asset, percent, limit).await,
+                    Notification::Rebalance { plan, executing } => {
+                        notifier.send_rebalance(&plan, executing).await
+                    }
+                    Notification::Depeg {
+                        currency,
+                        reference,
+                        rate,
+                        percent,
+                        recovered,
+                    } => {
+                        notifier
+                            .send_depeg(&currency, &reference, rate, percent, recovered)
+                            .await
+                    }
+                    Notification::VenueImpaired {
+                        exchange,
+                        error,
+                        recovered,
+                    } => notifier.send_impaired(&exchange, &error, recovered).await,
+                    Notification::FeedDegraded {
+                        exchange,
+                        symbol,
+                        recovered,
+                    } => notifier.send_degraded(&exchange, &symbol, recovered).await,
+                    Notification::MarginTopUp { plan, result } => {
+                        notifier.send_topup(&plan, result).await
+                    }
+                }
             }
             info!("[Telegram] Worker stopped.");
         });
@@ -108,8 +259,8 @@ impl TelegramNotifier {
         Some(tx)
     }
 
-    async fn send_message(&self, alert: &AppAlert) {
-        let text = format!(
+    async fn send_alert(&self, alert: &AppAlert) {
+        let mut text = format!(
             "🚨 <b>Arbitrage Alert</b>\n\n\
              📌 <b>Symbol:</b>  <code>{symbol}</code>\n\
              🏦 <b>Exchanges:</b> <code>{exch_a}</code> ↔ <code>{exch_b}</code>\n\n\
@@ -127,31 +278,226 @@ impl TelegramNotifier {
             mid_b = alert.mid_b,
             diff = alert.diff_percent,
         );
+        if let Some(note) = &alert.note {
+            text.push_str(&format!("\n\n⚠️ <i>{}</i>", escape_html(note)));
+        }
+
+        let summary = format!(
+            "Alert sent: {} ({} ↔ {}) {:.2}%",
+            alert.symbol, alert.exchange_a, alert.exchange_b, alert.diff_percent
+        );
+        self.deliver(&text, false, &summary).await;
+    }
+
+    async fn send_digest(&self, alerts: &[AppAlert]) {
+        let lines: Vec<_> = alerts
+            .iter()
+            .map(|alert| {
+                format!(
+                    "• <code>{}</code> {} ↔ {} <code>{:.2}%</code>",
+                    alert.symbol, alert.exchange_a, alert.exchange_b, alert.diff_percent
+                )
+            })
+            .collect();
+        let text = format!("🗞️ <b>Alert Digest</b>\n\n{}", lines.join("\n"));
+        // A summary, not a call to act.
+        self.deliver(
+            &text,
+            true,
+            &format!("Digest sent: {} alert(s)", alerts.len()),
+        )
+        .await;
+    }
+
+    async fn send_reconnecting(&self, url: &str, reason: &str, severity: Severity) {
+        let critical = severity >= Severity::Critical;
+        let text = format!(
+            "{icon} <b>Feed Reconnecting</b>\n\n\
+             🌐 <code>{url}</code>\n\
+             ❗ {reason}",
+            icon = if critical { "🔒" } else { "🔌" },
+            url = escape_html(url),
+            reason = escape_html(reason),
+        );
+        // Connection noise should not buzz the phone; failures that need a
+        // human (bad certificate, pin mismatch) should.
+        self.deliver(
+            &text,
+            !critical,
+            &format!("Reconnect notice sent ({}): {}", severity, url),
+        )
+        .await;
+    }
+
+    async fn send_skew(&self, exchange: &str, asset: &str, percent: Decimal, limit: Decimal) {
+        let text = format!(
+            "⚖️ <b>Inventory Skew</b>\n\n\
+             🏦 <code>{exchange}</code> holds <code>{percent:.1}%</code> of the {asset} inventory \
+             (limit <code>{limit}%</code>)\n\
+             🔁 Rebalance before that side runs dry",
+            exchange = exchange,
+            asset = escape_html(asset),
+            percent = percent,
+            limit = limit.normalize(),
+        );
+        self.deliver(
+            &text,
+            false,
+            &format!("Skew alert sent: {} {} {:.1}%", exchange, asset, percent),
+        )
+        .await;
+    }
+
+    async fn send_rebalance(&self, plan: &str, executing: bool) {
+        let mut text = format!(
+            "🔁 <b>Rebalance Plan</b>\n\n<pre>{}</pre>",
+            escape_html(plan)
+        );
+        if executing {
+            text.push_str("\n\n⚙️ Placing the offsetting trades");
+        }
+        self.deliver(&text, false, "Rebalance plan sent").await;
+    }
 
-        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+    async fn send_depeg(
+        &self,
+        currency: &str,
+        reference: &str,
+        rate: Decimal,
+        percent: Decimal,
+        recovered: bool,
+    ) {
+        let text = if recovered {
+            format!(
+                "✅ <b>Peg Restored</b>\n\n\
+                 💱 1 {currency} = <code>{rate}</code> {reference}\n\
+                 📊 Spreads against {currency} are reported again",
+                currency = escape_html(currency),
+                reference = escape_html(reference),
+                rate = rate.normalize(),
+            )
+        } else {
+            format!(
+                "🪙 <b>Depeg</b>\n\n\
+                 💱 1 {currency} = <code>{rate}</code> {reference} (<code>{percent:+.2}%</code>)\n\
+                 🚫 Spreads against {currency} are the depeg, not arbitrage; not reported",
+                currency = escape_html(currency),
+                reference = escape_html(reference),
+                rate = rate.normalize(),
+                percent = percent,
+            )
+        };
+        self.deliver(
+            &text,
+            false,
+            &format!("Depeg notice sent: {} {:+.2}%", currency, percent),
+        )
+        .await;
+    }
+
+    async fn send_impaired(&self, exchange: &str, error: &str, recovered: bool) {
+        let text = if recovered {
+            format!(
+                "✅ <b>REST Restored</b>\n\n\
+                 🏦 {exchange} answers its liveness probes again\n\
+                 💸 Execution trades on it again",
+                exchange = escape_html(exchange),
+            )
+        } else {
+            format!(
+                "🚧 <b>REST Failing</b>\n\n\
+                 🏦 {exchange}: <code>{error}</code>\n\
+                 🚫 Execution leaves it alone; its quotes are still monitored",
+                exchange = escape_html(exchange),
+                error = escape_html(error),
+            )
+        };
+        self.deliver(
+            &text,
+            false,
+            &format!("Liveness notice sent: {} recovered={}", exchange, recovered),
+        )
+        .await;
+    }
+
+    async fn send_degraded(&self, exchange: &str, symbol: &str, recovered: bool) {
+        let text = if recovered {
+            format!(
+                "✅ <b>Feed Streaming</b>\n\n\
+                 🏦 {exchange} <code>{symbol}</code> streams quotes again\n\
+                 💸 Execution trades on it again",
+                exchange = escape_html(exchange),
+                symbol = escape_html(symbol),
+            )
+        } else {
+            format!(
+                "🐢 <b>Feed Degraded</b>\n\n\
+                 🏦 {exchange} <code>{symbol}</code>: WebSocket quiet, polling REST\n\
+                 🚫 Execution leaves {exchange} alone until it streams again",
+                exchange = escape_html(exchange),
+                symbol = escape_html(symbol),
+            )
+        };
+        self.deliver(
+            &text,
+            false,
+            &format!(
+                "Failover notice sent: {} {} recovered={}",
+                exchange, symbol, recovered
+            ),
+        )
+        .await;
+    }
+
+    async fn send_topup(&self, plan: &str, result: Option<Result<String, String>>) {
+        let outcome = match result {
+            Some(Ok(id)) => format!("✅ Moved, transfer <code>{}</code>", escape_html(&id)),
+            Some(Err(error)) => format!("❌ Failed: <code>{}</code>", escape_html(&error)),
+            None => "⏸️ Not moved: execute is off".to_string(),
+        };
+        let text = format!(
+            "💸 <b>Margin Top-Up</b>\n\n{}\n{}",
+            escape_html(plan),
+            outcome
+        );
+        self.deliver(&text, false, "Margin top-up sent").await;
+    }
+
+    /// Sends `text`, retrying once if the failure is transient.
+    async fn deliver(&self, text: &str, silent: bool, summary: &str) {
+        let mut result = self.send_message(text, silent).await;
+        if let Err(e) = &result {
+            if e.is_retryable() {
+                warn!("[Telegram] {}, retrying once", e);
+                tokio::time::sleep(RETRY_DELAY).await;
+                result = self.send_message(text, silent).await;
+            }
+        }
+        match result {
+            Ok(()) => info!("[Telegram] {}", summary),
+            Err(e) => error!("[Telegram] {}", e),
+        }
+    }
+
+    async fn send_message(&self, text: &str, silent: bool) -> Result<(), NotifyError> {
+        let url = format!(
+            "https://api.telegram.org/bot{}/sendMessage",
+            self.bot_token.expose()
+        );
 
         let payload = SendMessagePayload {
             chat_id: &self.chat_id,
-            text: &text,
+            text,
             parse_mode: "HTML",
-            disable_notification: false,
+            disable_notification: silent,
         };
 
-        match self.client.post(&url).json(&payload).send().await {
-            Ok(resp) if resp.status().is_success() => {
-                info!(
-                    "[Telegram] Alert sent: {} ({} ↔ {}) {:.2}%",
-                    alert.symbol, alert.exchange_a, alert.exchange_b, alert.diff_percent
-                );
-            }
-            Ok(resp) => {
-                let status = resp.status();
-                let body = resp.text().await.unwrap_or_default();
-                error!("[Telegram] API error ({}): {}", status, body);
-            }
-            Err(e) => {
-                error!("[Telegram] Network error: {}", e);
-            }
+        let resp = self.client.post(&url).json(&payload).send().await?;
+        if resp.status().is_success() {
+            return Ok(());
         }
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        Err(NotifyError::Api { status, body })
     }
 }
@@ -0,0 +1,395 @@
+//! Strategy and pause commands from the Telegram chat
+//! (`[notifications.telegram]`).
+//!
+//! With `commands = true`, the bot behind `TELEGRAM_KEY` long-polls Telegram
+//! for messages to `TELEGRAM_CHAT_ID` and, when `allowed_users` is set, takes
+//! commands only from those users:
+//!
+//! ```text
+//! /strategies                          every strategy's book
+//! /strategy <name> on | off            lets it trade, or stops it
+//! /strategy <name> threshold <percent> its minimum edge
+//! /strategy <name> size <quantity>     what it trades per leg
+//! /pauses                              what is paused on its own
+//! /pause <exchange | symbol>           leaves it out of alerts and trades
+//! /resume <exchange | symbol>          takes it back in
+//! ```
+//!
+//! Changes go through [`Control::update_strategy`] and
+//! [`Control::set_paused`] like the control API's `POST /strategies` and
+//! `POST /pauses`, so they land in the audit log as coming via
+//! `telegram:<user id>`, and the bot replies with the strategy's book or
+//! what is paused, or why the change was refused. Messages sent while the bot wasn't polling are
+//! dropped rather than acted on late.
+//!
+//! Parsing is always available; the poller is part of the `telegram`
+//! feature.
+
+use std::fmt::Write;
+
+use crate::{
+    control::{Control, StrategyUpdate},
+    models::money::{self, Decimal},
+    pauses::{PauseTarget, Paused},
+    portfolio::StrategyBook,
+};
+#[cfg(feature = "telegram")]
+use {
+    crate::{config::TelegramConfig, error::NotifyError, keys, net, secret::SecretString},
+    log::{info, warn},
+    serde::{Deserialize, Serialize},
+    std::{env, time::Duration},
+    tokio_util::sync::CancellationToken,
+};
+
+pub const USAGE: &str = "/strategies\n\
+    /strategy <name> on|off\n\
+    /strategy <name> threshold <percent>\n\
+    /strategy <name> size <quantity>\n\
+    /pauses\n\
+    /pause <exchange|symbol>\n\
+    /resume <exchange|symbol>";
+
+/// One command from the chat.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BotCommand {
+    Strategies,
+    Strategy {
+        name: String,
+        update: StrategyUpdate,
+    },
+    Pauses,
+    Pause {
+        target: PauseTarget,
+        paused: bool,
+    },
+}
+
+impl BotCommand {
+    /// Reads a message: `None` when it isn't a command, an error with the
+    /// usage when it isn't one of these.
+    ///
+    /// ```
+    /// use arbitrage_bot::notifications::telegram_commands::BotCommand;
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let Some(Ok(BotCommand::Strategy { name, update })) =
+    ///     BotCommand::parse("/strategy@arb_bot spread threshold 0.3")
+    /// else {
+    ///     panic!("not a strategy command");
+    /// };
+    /// assert_eq!(name, "spread");
+    /// assert_eq!(update.threshold_percent, Some(dec!(0.3)));
+    /// assert!(BotCommand::parse("/strategy spread size -1").unwrap().is_err());
+    /// assert!(matches!(
+    ///     BotCommand::parse("/pause bybit"),
+    ///     Some(Ok(BotCommand::Pause { paused: true, .. }))
+    /// ));
+    /// assert!(BotCommand::parse("gm").is_none());
+    /// ```
+    pub fn parse(text: &str) -> Option<Result<Self, String>> {
+        let mut words = text.split_whitespace();
+        let command = words.next()?.strip_prefix('/')?;
+        // In a group, commands can be addressed to one bot: /strategy@arb_bot.
+        let command = command.split('@').next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+        let parsed = match (command, args.as_slice()) {
+            ("strategies", []) => Some(Self::Strategies),
+            ("strategy", [name, change @ ..]) => update(change).map(|update| Self::Strategy {
+                name: name.to_string(),
+                update,
+            }),
+            ("pauses", []) => Some(Self::Pauses),
+            ("pause" | "resume", [target]) => {
+                PauseTarget::parse(target).map(|target| Self::Pause {
+                    target,
+                    paused: command == "pause",
+                })
+            }
+            _ => None,
+        };
+        Some(parsed.ok_or_else(|| format!("Commands:\n{}", USAGE)))
+    }
+
+    /// Carries it out through `control`, as coming `via`; returns the reply.
+    pub fn run(&self, control: &Control, via: &str) -> String {
+        match self {
+            Self::Strategies => {
+                let state = control.positions();
+                if state.portfolio.strategies().is_empty() {
+                    return "No strategy has traded yet.".into();
+                }
+                state
+                    .portfolio
+                    .strategies()
+                    .iter()
+                    .map(|(name, book)| describe(name, book))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            Self::Strategy { name, update } => match control.update_strategy(name, update, via) {
+                Ok(book) => describe(name, &book),
+                Err(e) => format!("❌ {}", e),
+            },
+            Self::Pauses => describe_pauses(&control.pauses().current()),
+            Self::Pause { target, paused } => {
+                describe_pauses(&control.set_paused(*target, *paused, via))
+            }
+        }
+    }
+}
+
+/// `on`, `off`, `threshold <percent>` or `size <quantity>`, with positive
+/// numbers.
+fn update(change: &[&str]) -> Option<StrategyUpdate> {
+    let positive = |s: &str| money::parse(s).filter(|d| *d > Decimal::ZERO);
+    let update = match change {
+        ["on"] => StrategyUpdate {
+            enabled: Some(true),
+            ..StrategyUpdate::default()
+        },
+        ["off"] => StrategyUpdate {
+            enabled: Some(false),
+            ..StrategyUpdate::default()
+        },
+        ["threshold", percent] => StrategyUpdate {
+            threshold_percent: Some(positive(percent.trim_end_matches('%'))?),
+            ..StrategyUpdate::default()
+        },
+        ["size", quantity] => StrategyUpdate {
+            quantity: Some(positive(quantity)?),
+            ..StrategyUpdate::default()
+        },
+        _ => return None,
+    };
+    Some(update)
+}
+
+/// One line on a strategy: whether it trades, its own settings, its PnL.
+fn describe(name: &str, book: &StrategyBook) -> String {
+    let mut line = match &book.disabled {
+        Some(reason) => format!("⏸️ {}: {}", name, reason),
+        None => format!("▶️ {}", name),
+    };
+    if let Some(percent) = book.threshold_percent {
+        let _ = write!(line, ", threshold {}%", percent.normalize());
+    }
+    if let Some(quantity) = book.quantity {
+        let _ = write!(line, ", size {}", quantity.normalize());
+    }
+    let _ = write!(
+        line,
+        ", PnL {} over {} trade(s), {} failed",
+        book.pnl.round_dp(2),
+        book.trades,
+        book.failed
+    );
+    line
+}
+
+/// What is paused on its own, in one line.
+fn describe_pauses(paused: &Paused) -> String {
+    let names: Vec<&str> = paused
+        .exchanges
+        .iter()
+        .map(|e| e.name())
+        .chain(paused.symbols.iter().map(String::as_str))
+        .collect();
+    if names.is_empty() {
+        "▶️ Nothing is paused.".into()
+    } else {
+        format!("⏸️ Paused: {}", names.join(", "))
+    }
+}
+
+// ── Poller ───────────────────────────────────────────────────────────────────
+
+#[cfg(feature = "telegram")]
+const API: &str = "https://api.telegram.org";
+
+/// How long one `getUpdates` call waits for a message.
+#[cfg(feature = "telegram")]
+const POLL_SECS: u64 = 30;
+
+#[cfg(feature = "telegram")]
+#[derive(Serialize)]
+struct GetUpdates<'a> {
+    offset: i64,
+    timeout: u64,
+    allowed_updates: &'a [&'a str],
+}
+
+#[cfg(feature = "telegram")]
+#[derive(Debug, Deserialize)]
+struct Updates {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<Update>,
+    description: Option<String>,
+}
+
+#[cfg(feature = "telegram")]
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[cfg(feature = "telegram")]
+#[derive(Debug, Deserialize)]
+struct Message {
+    chat: Chat,
+    from: Option<User>,
+    text: Option<String>,
+}
+
+#[cfg(feature = "telegram")]
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+#[cfg(feature = "telegram")]
+#[derive(Debug, Deserialize)]
+struct User {
+    id: i64,
+}
+
+#[cfg(feature = "telegram")]
+#[derive(Serialize)]
+struct Reply<'a> {
+    chat_id: &'a str,
+    text: &'a str,
+}
+
+#[cfg(feature = "telegram")]
+struct Bot {
+    client: reqwest::Client,
+    bot_token: SecretString,
+    chat_id: String,
+    allowed_users: Vec<i64>,
+}
+
+#[cfg(feature = "telegram")]
+impl Bot {
+    /// The next updates after `offset`, waiting up to `timeout` seconds.
+    async fn updates(&self, offset: i64, timeout: u64) -> Result<Vec<Update>, NotifyError> {
+        let url = format!("{}/bot{}/getUpdates", API, self.bot_token.expose());
+        let resp = self
+            .client
+            .post(&url)
+            .json(&GetUpdates {
+                offset,
+                timeout,
+                allowed_updates: &["message"],
+            })
+            .send()
+            .await
+            .map_err(reqwest::Error::without_url)?;
+        let status = resp.status().as_u16();
+        let body = resp.text().await.map_err(reqwest::Error::without_url)?;
+        match serde_json::from_str::<Updates>(&body) {
+            Ok(updates) if updates.ok => Ok(updates.result),
+            Ok(updates) => Err(NotifyError::Api {
+                status,
+                body: updates.description.unwrap_or(body),
+            }),
+            Err(_) => Err(NotifyError::Api { status, body }),
+        }
+    }
+
+    async fn reply(&self, text: &str) {
+        let url = format!("{}/bot{}/sendMessage", API, self.bot_token.expose());
+        let payload = Reply {
+            chat_id: &self.chat_id,
+            text,
+        };
+        match self.client.post(&url).json(&payload).send().await {
+            Ok(resp) if resp.status().is_success() => {}
+            Ok(resp) => warn!("[Telegram] Reply failed: HTTP {}", resp.status()),
+            Err(e) => warn!("[Telegram] Reply failed: {}", e.without_url()),
+        }
+    }
+
+    /// The command in `message`, if it's one from the chat and an allowed
+    /// user, with who sent it.
+    fn command(&self, message: &Message) -> Option<(i64, Result<BotCommand, String>)> {
+        if message.chat.id.to_string() != self.chat_id {
+            return None;
+        }
+        let user = message.from.as_ref()?.id;
+        if !self.allowed_users.is_empty() && !self.allowed_users.contains(&user) {
+            warn!("[Telegram] Ignoring a command from user {}", user);
+            return None;
+        }
+        Some((user, BotCommand::parse(message.text.as_deref()?)?))
+    }
+}
+
+/// Takes commands from the chat until `cancel` fires, if `[notifications.telegram]
+/// commands` is on and the bot is configured.
+#[cfg(feature = "telegram")]
+pub fn spawn(config: &TelegramConfig, control: Control, cancel: CancellationToken) {
+    if !config.commands {
+        return;
+    }
+    let (Some(bot_token), Ok(chat_id)) = (
+        keys::var("TELEGRAM_KEY").filter(|t| !t.is_empty()),
+        env::var("TELEGRAM_CHAT_ID"),
+    ) else {
+        eprintln!(
+            "⚠️ [notifications.telegram] commands is on, but TELEGRAM_KEY or TELEGRAM_CHAT_ID is unset"
+        );
+        return;
+    };
+    let client = net::http_client_builder()
+        .timeout(Duration::from_secs(POLL_SECS + 10))
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new());
+    let bot = Bot {
+        client,
+        bot_token,
+        chat_id,
+        allowed_users: config.allowed_users.clone(),
+    };
+    tokio::spawn(async move {
+        // Start after the latest update, dropping what came in while down.
+        let mut offset = match bot.updates(-1, 0).await {
+            Ok(updates) => updates.last().map_or(0, |u| u.update_id + 1),
+            Err(_) => 0,
+        };
+        info!("[Telegram] Taking commands.");
+        loop {
+            let updates = tokio::select! {
+                updates = bot.updates(offset, POLL_SECS) => updates,
+                _ = cancel.cancelled() => break,
+            };
+            let updates = match updates {
+                Ok(updates) => updates,
+                Err(e) => {
+                    warn!("[Telegram] Polling for commands failed: {}", e);
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_secs(5)) => continue,
+                        _ = cancel.cancelled() => break,
+                    }
+                }
+            };
+            for update in updates {
+                offset = offset.max(update.update_id + 1);
+                let Some((user, command)) = update.message.as_ref().and_then(|m| bot.command(m))
+                else {
+                    continue;
+                };
+                let reply = match command {
+                    Ok(command) => {
+                        println!("📨 Command from Telegram user {}: {:?}", user, command);
+                        command.run(&control, &format!("telegram:{}", user))
+                    }
+                    Err(usage) => usage,
+                };
+                bot.reply(&reply).await;
+            }
+        }
+        info!("[Telegram] Stopped taking commands.");
+    });
+}
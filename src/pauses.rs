@@ -0,0 +1,180 @@
+//! Pausing a single exchange or symbol (`[pauses]`).
+//!
+//! A paused exchange or symbol is left out of everything that acts on
+//! spreads, without touching the rest of the bot: the tracker logs no
+//! spread, records no opportunity and sends no alert with a leg on it, and
+//! execution places no order on it. Its feeds keep streaming, so it is
+//! compared again the moment it's resumed. `[pauses]` lists what is paused
+//! at startup; the control API (`POST /pauses`), the command bus and the
+//! Telegram `/pause` and `/resume` commands change it at runtime through
+//! `Control::set_paused`. The global pause (`POST /pause`) still stops all
+//! of execution.
+
+use std::{collections::BTreeSet, fmt, sync::Arc};
+
+use arc_swap::ArcSwap;
+use serde::Serialize;
+
+use crate::{
+    config::PausesConfig,
+    error::ControlError,
+    models::ids::{ExchangeId, Symbol},
+};
+
+/// What to pause: one exchange, or one symbol on every exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseTarget {
+    Exchange(ExchangeId),
+    Symbol(Symbol),
+}
+
+impl PauseTarget {
+    /// An exchange name, or else a symbol (upper-cased); `None` for
+    /// anything that can't be either.
+    ///
+    /// ```
+    /// use arbitrage_bot::{models::ids::ExchangeId, pauses::PauseTarget};
+    ///
+    /// assert_eq!(
+    ///     PauseTarget::parse("bybit"),
+    ///     Some(PauseTarget::Exchange(ExchangeId::Bybit))
+    /// );
+    /// assert_eq!(
+    ///     PauseTarget::parse("wlfiusdt").unwrap().to_string(),
+    ///     "WLFIUSDT"
+    /// );
+    /// assert_eq!(PauseTarget::parse("../etc"), None);
+    /// ```
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Some(exchange) = ExchangeId::from_name(&name.to_lowercase()) {
+            return Some(Self::Exchange(exchange));
+        }
+        is_symbol(name).then(|| Self::Symbol(Symbol::intern(&name.to_uppercase())))
+    }
+
+    /// The target of a request naming either an `exchange` or a `symbol`.
+    pub fn named(exchange: Option<ExchangeId>, symbol: Option<&str>) -> Result<Self, ControlError> {
+        match (exchange, symbol) {
+            (Some(exchange), None) => Ok(Self::Exchange(exchange)),
+            (None, Some(symbol)) if is_symbol(symbol) => {
+                Ok(Self::Symbol(Symbol::intern(&symbol.to_uppercase())))
+            }
+            (None, Some(symbol)) => Err(ControlError::InvalidPause(format!(
+                "{:?} is not a symbol",
+                symbol
+            ))),
+            _ => Err(ControlError::InvalidPause(
+                "give either `exchange` or `symbol`".into(),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PauseTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exchange(exchange) => exchange.fmt(f),
+            Self::Symbol(symbol) => symbol.fmt(f),
+        }
+    }
+}
+
+/// Whether `name` can be a symbol: letters and digits only.
+pub fn is_symbol(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// What is paused, for `GET /status`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Paused {
+    pub exchanges: BTreeSet<ExchangeId>,
+    pub symbols: BTreeSet<String>,
+}
+
+/// The paused exchanges and symbols, shared by the tracker, execution and
+/// [`Control`](crate::control::Control).
+#[derive(Debug, Clone, Default)]
+pub struct Pauses {
+    paused: Arc<ArcSwap<Paused>>,
+}
+
+impl Pauses {
+    /// Starts with what `config` pauses.
+    pub fn new(config: &PausesConfig) -> Self {
+        let paused = Paused {
+            exchanges: config.exchanges.iter().copied().collect(),
+            symbols: config.symbols.iter().map(|s| s.to_uppercase()).collect(),
+        };
+        Self {
+            paused: Arc::new(ArcSwap::from_pointee(paused)),
+        }
+    }
+
+    pub fn current(&self) -> Paused {
+        Paused::clone(&self.paused.load())
+    }
+
+    pub fn is_paused(&self, target: PauseTarget) -> bool {
+        let paused = self.paused.load();
+        match target {
+            PauseTarget::Exchange(exchange) => paused.exchanges.contains(&exchange),
+            PauseTarget::Symbol(symbol) => paused.symbols.contains(symbol.as_str()),
+        }
+    }
+
+    /// Pauses or resumes `target`; returns whether that changed anything.
+    pub fn set(&self, target: PauseTarget, paused: bool) -> bool {
+        if self.is_paused(target) == paused {
+            return false;
+        }
+        self.paused.rcu(|current| {
+            let mut current = Paused::clone(current);
+            match (target, paused) {
+                (PauseTarget::Exchange(exchange), true) => {
+                    current.exchanges.insert(exchange);
+                }
+                (PauseTarget::Exchange(exchange), false) => {
+                    current.exchanges.remove(&exchange);
+                }
+                (PauseTarget::Symbol(symbol), true) => {
+                    current.symbols.insert(symbol.to_string());
+                }
+                (PauseTarget::Symbol(symbol), false) => {
+                    current.symbols.remove(symbol.as_str());
+                }
+            }
+            current
+        });
+        true
+    }
+
+    /// What keeps `symbol` from being traded between `exchanges`, if
+    /// anything is paused.
+    ///
+    /// ```
+    /// use arbitrage_bot::{
+    ///     models::ids::{ExchangeId, Symbol},
+    ///     pauses::{PauseTarget, Pauses},
+    /// };
+    ///
+    /// let pauses = Pauses::default();
+    /// let btc = Symbol::intern("BTCUSDT");
+    /// let legs = [ExchangeId::Binance, ExchangeId::Bybit];
+    /// assert!(pauses.holding(btc, legs).is_none());
+    /// pauses.set(PauseTarget::Exchange(ExchangeId::Bybit), true);
+    /// assert_eq!(
+    ///     pauses.holding(btc, legs),
+    ///     Some(PauseTarget::Exchange(ExchangeId::Bybit))
+    /// );
+    /// ```
+    pub fn holding(&self, symbol: Symbol, exchanges: [ExchangeId; 2]) -> Option<PauseTarget> {
+        let paused = self.paused.load();
+        if paused.symbols.contains(symbol.as_str()) {
+            return Some(PauseTarget::Symbol(symbol));
+        }
+        exchanges
+            .into_iter()
+            .find(|exchange| paused.exchanges.contains(exchange))
+            .map(PauseTarget::Exchange)
+    }
+}
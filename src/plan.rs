@@ -0,0 +1,288 @@
+//! Execution plans: the orders one opportunity needs, as stages of legs.
+//!
+//! A cross-exchange arbitrage is two legs placed at once, but a triangular
+//! or quote-normalized route needs more, and some of them only once the
+//! previous ones are in. An [`ExecutionPlan`] is a list of stages: the legs
+//! of a stage are placed concurrently, and a stage only starts once every
+//! leg before it was accepted. After a failed leg the remaining stages are
+//! skipped.
+//!
+//! Each leg's outcome is tracked in a [`PlanReport`], kept with the state.
+//! Legs that went through before (or alongside) a failure leave a position
+//! nothing offsets; with `[engine.execution] rollback = true`, [`reconcile`]
+//! turns them into unwinding orders on the same exchanges, taking the
+//! latest quote, and the report says how each unwind went. Unwinds are
+//! reduce-only: they can shrink a position, never grow or flip it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+    },
+    state::LegSide,
+};
+
+/// One order of a plan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedLeg {
+    pub exchange: ExchangeId,
+    pub side: LegSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    /// May only shrink the exchange's position: sent reduce-only, and
+    /// refused if the tracked exposure says it wouldn't.
+    #[serde(default)]
+    pub reduce_only: bool,
+}
+
+impl PlannedLeg {
+    pub fn buy(exchange: ExchangeId, price: Decimal, quantity: Decimal) -> Self {
+        Self {
+            exchange,
+            side: LegSide::Buy,
+            price,
+            quantity,
+            reduce_only: false,
+        }
+    }
+
+    pub fn sell(exchange: ExchangeId, price: Decimal, quantity: Decimal) -> Self {
+        Self {
+            exchange,
+            side: LegSide::Sell,
+            price,
+            quantity,
+            reduce_only: false,
+        }
+    }
+
+    /// The same leg, only allowed to shrink a position.
+    pub fn reducing(mut self) -> Self {
+        self.reduce_only = true;
+        self
+    }
+}
+
+/// Stages of legs: concurrent within a stage, one stage after the other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionPlan {
+    pub symbol: Symbol,
+    pub stages: Vec<Vec<PlannedLeg>>,
+    /// Unwind the accepted legs if another one fails.
+    pub rollback: bool,
+    /// Whose trade it is, for its book (see `crate::portfolio`).
+    pub strategy: Option<String>,
+}
+
+impl ExecutionPlan {
+    pub fn new(symbol: Symbol) -> Self {
+        Self {
+            symbol,
+            stages: Vec::new(),
+            rollback: false,
+            strategy: None,
+        }
+    }
+
+    /// The two legs of a cross-exchange arbitrage, placed at once.
+    pub fn arbitrage(symbol: Symbol, buy: PlannedLeg, sell: PlannedLeg) -> Self {
+        Self::new(symbol).then([buy, sell])
+    }
+
+    /// Adds a stage, placed once everything before it was accepted.
+    pub fn then(mut self, legs: impl IntoIterator<Item = PlannedLeg>) -> Self {
+        self.stages.push(legs.into_iter().collect());
+        self
+    }
+
+    pub fn with_rollback(mut self, rollback: bool) -> Self {
+        self.rollback = rollback;
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: &str) -> Self {
+        self.strategy = Some(strategy.to_string());
+        self
+    }
+
+    /// Every leg in order, with its stage.
+    pub fn legs(&self) -> impl Iterator<Item = (usize, &PlannedLeg)> {
+        self.stages
+            .iter()
+            .enumerate()
+            .flat_map(|(stage, legs)| legs.iter().map(move |leg| (stage, leg)))
+    }
+}
+
+/// Where one leg of a plan got to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LegStatus {
+    Pending,
+    Placed {
+        order_id: String,
+    },
+    Failed {
+        error: String,
+    },
+    /// Not placed, because a leg of an earlier stage failed.
+    Skipped,
+    /// Not acknowledged within the latency budget, so it failed the plan;
+    /// `order_id` went through later and was cancelled.
+    LateCancelled {
+        order_id: String,
+    },
+    /// Not acknowledged within the latency budget, and `order_id` couldn't
+    /// be cancelled once it was: it's taken to have filled, and leaves a
+    /// position like a placed leg of a failed plan.
+    LateFilled {
+        order_id: String,
+    },
+    /// Placed, then offset by `order_id` after another leg failed.
+    Unwound {
+        order_id: String,
+    },
+    /// Placed, and the order meant to offset it failed: the position is
+    /// still open.
+    UnwindFailed {
+        order_id: String,
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegReport {
+    pub stage: usize,
+    pub leg: PlannedLeg,
+    pub status: LegStatus,
+}
+
+/// The outcome of a plan, leg by leg, in plan order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanReport {
+    /// Counts up from 1 over the state's lifetime.
+    pub id: u64,
+    pub symbol: String,
+    pub legs: Vec<LegReport>,
+    pub started_at_ms: i64,
+    #[serde(default)]
+    pub strategy: Option<String>,
+}
+
+impl PlanReport {
+    /// Every leg pending.
+    pub fn new(id: u64, plan: &ExecutionPlan, started_at_ms: i64) -> Self {
+        Self {
+            id,
+            symbol: plan.symbol.to_string(),
+            legs: plan
+                .legs()
+                .map(|(stage, leg)| LegReport {
+                    stage,
+                    leg: leg.clone(),
+                    status: LegStatus::Pending,
+                })
+                .collect(),
+            started_at_ms,
+            strategy: plan.strategy.clone(),
+        }
+    }
+
+    /// Whether every leg was placed.
+    pub fn is_complete(&self) -> bool {
+        self.legs
+            .iter()
+            .all(|l| matches!(l.status, LegStatus::Placed { .. }))
+    }
+
+    /// Whether any leg failed, late ones included.
+    pub fn failed(&self) -> bool {
+        self.legs.iter().any(|l| {
+            matches!(
+                l.status,
+                LegStatus::Failed { .. }
+                    | LegStatus::LateCancelled { .. }
+                    | LegStatus::LateFilled { .. }
+            )
+        })
+    }
+
+    /// Legs left with an open position: placed (or filled late) and not
+    /// unwound.
+    pub fn open_legs(&self) -> impl Iterator<Item = &LegReport> {
+        self.legs.iter().filter(|l| {
+            matches!(
+                l.status,
+                LegStatus::Placed { .. }
+                    | LegStatus::LateFilled { .. }
+                    | LegStatus::UnwindFailed { .. }
+            )
+        })
+    }
+}
+
+/// The orders that unwind a failed plan: each placed leg (or one that
+/// filled late), the other way round and reduce-only, at the bid (to sell
+/// back) or ask (to buy back) `quotes` gives for its exchange, or at its
+/// own price without one. Paired with the index of the leg they unwind.
+/// Nothing unless a leg failed.
+///
+/// ```
+/// use arbitrage_bot::{
+///     models::ids::{ExchangeId, Symbol},
+///     plan::{reconcile, ExecutionPlan, LegStatus, PlanReport, PlannedLeg},
+/// };
+/// use rust_decimal_macros::dec;
+///
+/// let plan = ExecutionPlan::arbitrage(
+///     Symbol::intern("BTCUSDT"),
+///     PlannedLeg::buy(ExchangeId::Binance, dec!(100), dec!(0.1)),
+///     PlannedLeg::sell(ExchangeId::Bybit, dec!(101), dec!(0.1)),
+/// );
+/// let mut report = PlanReport::new(1, &plan, 0);
+/// report.legs[0].status = LegStatus::Placed { order_id: "1".into() };
+/// report.legs[1].status = LegStatus::Failed { error: "rejected".into() };
+///
+/// let unwinds = reconcile(&report, |_| Some((dec!(99.9), dec!(100.2))));
+/// assert_eq!(
+///     unwinds,
+///     [(0, PlannedLeg::sell(ExchangeId::Binance, dec!(99.9), dec!(0.1)).reducing())]
+/// );
+/// ```
+pub fn reconcile(
+    report: &PlanReport,
+    quotes: impl Fn(ExchangeId) -> Option<(Decimal, Decimal)>,
+) -> Vec<(usize, PlannedLeg)> {
+    if !report.failed() {
+        return Vec::new();
+    }
+    report
+        .legs
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| {
+            matches!(
+                l.status,
+                LegStatus::Placed { .. } | LegStatus::LateFilled { .. }
+            )
+        })
+        .map(|(index, l)| {
+            let quote = quotes(l.leg.exchange);
+            let unwind = match l.leg.side {
+                LegSide::Buy => PlannedLeg::sell(
+                    l.leg.exchange,
+                    quote.map_or(l.leg.price, |(bid, _)| bid),
+                    l.leg.quantity,
+                ),
+                LegSide::Sell => PlannedLeg::buy(
+                    l.leg.exchange,
+                    quote.map_or(l.leg.price, |(_, ask)| ask),
+                    l.leg.quantity,
+                ),
+            };
+            (index, unwind.reducing())
+        })
+        .collect()
+}
@@ -0,0 +1,256 @@
+//! Capital and accounting per strategy (`[engine.execution.strategies.<name>]`).
+//!
+//! A strategy is whatever proposes a trade: `spread` for the engine's own
+//! opportunities, a signal's `source` for external ones (see
+//! `crate::control::Signal`). Every plan carries its strategy, and once it
+//! has run the strategy's book takes it in: the net quantity left open per
+//! venue, the last price traded there, gross PnL, and trades and failures.
+//! PnL is counted like the session summary's (see `crate::session`): a
+//! trade that went through on every leg makes its sells less its buys, a leg
+//! unwound after another failed makes what the unwind sold less what it
+//! bought, and a leg left open makes nothing until it's closed.
+//!
+//! `capital` caps what a strategy's open positions come to, valued at the
+//! last price it traded on each venue; a trade that would take them beyond
+//! it is refused. A strategy whose PnL falls `max_loss` below zero, or that
+//! has had `max_failed` trades fail, is disabled: it trades nothing more
+//! until it's enabled again through the control API, while the others go on.
+//! Books are kept with the execution state, so a disabled strategy stays
+//! disabled across restarts.
+//!
+//! A strategy trades at its own `threshold_percent` and `quantity` where it
+//! has them. The control API and the Telegram commands can change both at
+//! runtime; those changes are kept in its book, over the config's.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::StrategyConfig,
+    contracts::Contracts,
+    models::{ids::ExchangeId, money::Decimal},
+    plan::{LegStatus, PlanReport},
+    state::{LegSide, OrderLeg},
+};
+
+/// The strategy of the engine's own opportunities.
+pub const SPREAD: &str = "spread";
+
+/// One strategy's positions and results.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrategyBook {
+    /// Net quantity bought per venue, in the venue's unit.
+    pub exposure: BTreeMap<ExchangeId, Decimal>,
+    /// The last price traded per venue.
+    pub marks: BTreeMap<ExchangeId, Decimal>,
+    /// Gross PnL in the quote currency; see the module docs.
+    pub pnl: Decimal,
+    pub trades: u64,
+    pub failed: u64,
+    /// Why it may not trade, while it may not.
+    pub disabled: Option<String>,
+    /// Its minimum edge in percent, as set through the control API.
+    pub threshold_percent: Option<Decimal>,
+    /// Its quantity per leg, as set through the control API.
+    pub quantity: Option<Decimal>,
+}
+
+impl StrategyBook {
+    /// What its open positions come to at their marks.
+    pub fn deployed(&self, contracts: &Contracts) -> Decimal {
+        self.exposure
+            .iter()
+            .map(|(&exchange, quantity)| {
+                let mark = self.marks.get(&exchange).copied().unwrap_or_default();
+                contracts.get(exchange).notional(quantity.abs(), mark)
+            })
+            .sum()
+    }
+
+    /// Takes an order in as filled.
+    fn hold(&mut self, exchange: ExchangeId, side: LegSide, quantity: Decimal, price: Decimal) {
+        *self.exposure.entry(exchange).or_default() += match side {
+            LegSide::Buy => quantity,
+            LegSide::Sell => -quantity,
+        };
+        self.marks.insert(exchange, price);
+    }
+}
+
+/// What an order brought in: its notional if it sold, less it if it bought.
+fn cash(
+    contracts: &Contracts,
+    exchange: ExchangeId,
+    side: LegSide,
+    quantity: Decimal,
+    price: Decimal,
+) -> Decimal {
+    let notional = contracts.get(exchange).notional(quantity, price);
+    match side {
+        LegSide::Buy => -notional,
+        LegSide::Sell => notional,
+    }
+}
+
+/// Every strategy's book, by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Portfolio {
+    strategies: BTreeMap<String, StrategyBook>,
+}
+
+impl Portfolio {
+    pub fn get(&self, strategy: &str) -> Option<&StrategyBook> {
+        self.strategies.get(strategy)
+    }
+
+    pub fn strategies(&self) -> &BTreeMap<String, StrategyBook> {
+        &self.strategies
+    }
+
+    /// Takes `report` into its strategy's book, if it has one; `orders` has
+    /// the unwinds it placed.
+    pub fn record(&mut self, report: &PlanReport, orders: &[OrderLeg], contracts: &Contracts) {
+        let Some(strategy) = &report.strategy else {
+            return;
+        };
+        let book = self.book(strategy);
+        book.trades += 1;
+        if report.failed() {
+            book.failed += 1;
+        }
+        let complete = report.is_complete();
+        for leg in &report.legs {
+            let planned = &leg.leg;
+            let entry = (
+                planned.exchange,
+                planned.side,
+                planned.quantity,
+                planned.price,
+            );
+            match &leg.status {
+                LegStatus::Placed { .. }
+                | LegStatus::LateFilled { .. }
+                | LegStatus::UnwindFailed { .. } => {
+                    book.hold(entry.0, entry.1, entry.2, entry.3);
+                    if complete {
+                        book.pnl += cash(contracts, entry.0, entry.1, entry.2, entry.3);
+                    }
+                }
+                LegStatus::Unwound { order_id } => {
+                    let Some(unwind) = orders
+                        .iter()
+                        .rev()
+                        .find(|o| o.order_id.as_deref() == Some(order_id))
+                    else {
+                        continue;
+                    };
+                    for (exchange, side, quantity, price) in [
+                        entry,
+                        (unwind.exchange, unwind.side, unwind.quantity, unwind.price),
+                    ] {
+                        book.hold(exchange, side, quantity, price);
+                        book.pnl += cash(contracts, exchange, side, quantity, price);
+                    }
+                }
+                LegStatus::Pending
+                | LegStatus::Failed { .. }
+                | LegStatus::Skipped
+                | LegStatus::LateCancelled { .. } => {}
+            }
+        }
+    }
+
+    /// Why `strategy` may not open a trade of `notional` (both legs, in the
+    /// quote currency), if it may not.
+    pub fn refusal(
+        &self,
+        strategy: &str,
+        config: Option<&StrategyConfig>,
+        notional: Decimal,
+        contracts: &Contracts,
+    ) -> Option<String> {
+        if config.is_some_and(|c| !c.enabled) {
+            return Some("is disabled in the config".into());
+        }
+        let book = self.strategies.get(strategy);
+        if let Some(reason) = book.and_then(|b| b.disabled.as_ref()) {
+            return Some(format!("is disabled: {}", reason));
+        }
+        let capital = config.and_then(|c| c.capital)?;
+        let deployed = book.map_or(Decimal::ZERO, |b| b.deployed(contracts));
+        (deployed + notional > capital).then(|| {
+            format!(
+                "would have {} of its {} capital deployed",
+                (deployed + notional).round_dp(2),
+                capital.normalize()
+            )
+        })
+    }
+
+    /// Disables `strategy` once it's past `config`'s limits; returns why
+    /// when that's just happened.
+    pub fn enforce(&mut self, strategy: &str, config: Option<&StrategyConfig>) -> Option<String> {
+        let config = config?;
+        let book = self.strategies.get_mut(strategy)?;
+        if book.disabled.is_some() {
+            return None;
+        }
+        let reason = if config.max_loss.is_some_and(|max| book.pnl <= -max) {
+            format!("lost {}", (-book.pnl).round_dp(2))
+        } else if config.max_failed.is_some_and(|max| book.failed >= max) {
+            format!("{} failed trade(s)", book.failed)
+        } else {
+            return None;
+        };
+        book.disabled = Some(reason.clone());
+        Some(reason)
+    }
+
+    /// Lets `strategy` trade again, or stops it, keeping its book.
+    pub fn set_enabled(&mut self, strategy: &str, enabled: bool) -> &StrategyBook {
+        let book = self.book(strategy);
+        book.disabled = (!enabled).then(|| "disabled through the control API".to_string());
+        book
+    }
+
+    /// Trades `strategy` only at edges above `percent`.
+    pub fn set_threshold(&mut self, strategy: &str, percent: Decimal) -> &StrategyBook {
+        let book = self.book(strategy);
+        book.threshold_percent = Some(percent);
+        book
+    }
+
+    /// Trades `quantity` per leg for `strategy`.
+    pub fn set_quantity(&mut self, strategy: &str, quantity: Decimal) -> &StrategyBook {
+        let book = self.book(strategy);
+        book.quantity = Some(quantity);
+        book
+    }
+
+    /// `strategy`'s minimum edge in percent, if it has its own.
+    pub fn threshold_percent(
+        &self,
+        strategy: &str,
+        config: Option<&StrategyConfig>,
+    ) -> Option<Decimal> {
+        self.strategies
+            .get(strategy)
+            .and_then(|b| b.threshold_percent)
+            .or_else(|| config.and_then(|c| c.threshold_percent))
+    }
+
+    /// `strategy`'s quantity per leg, if it has its own.
+    pub fn quantity(&self, strategy: &str, config: Option<&StrategyConfig>) -> Option<Decimal> {
+        self.strategies
+            .get(strategy)
+            .and_then(|b| b.quantity)
+            .or_else(|| config.and_then(|c| c.quantity))
+    }
+
+    fn book(&mut self, strategy: &str) -> &mut StrategyBook {
+        self.strategies.entry(strategy.to_string()).or_default()
+    }
+}
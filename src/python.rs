@@ -0,0 +1,328 @@
+//! Python bindings (`python` feature), for prototyping opportunity logic in
+//! Python on top of the bot's feeds and order client.
+//!
+//! ```python
+//! from decimal import Decimal
+//! import arbitrage_bot
+//!
+//! bot = arbitrage_bot.Bot()            # config.toml / ARB_CONFIG, as the binary
+//! for q in bot.quotes(symbol="BTCUSDT"):
+//!     other = bot.latest("bybit" if q.exchange == "binance" else "binance", q.symbol)
+//!     if other and other.bid > q.ask * Decimal("1.002"):
+//!         bot.place_order(q.exchange, q.symbol, "buy", q.ask, Decimal("0.001"))
+//! ```
+//!
+//! [`Bot`] starts the same [`Engine`] as the binary: feeds, tracker, alerts
+//! and, if `[engine.execution]` enables it, the built-in executor. Prices and
+//! quantities are `decimal.Decimal`. Configuration is process-wide, so a
+//! second `Bot` is refused. Orders placed from Python are not recorded
+//! in execution's positions.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+};
+use tokio::{
+    runtime::{Handle, Runtime},
+    sync::broadcast::{self, error::RecvError},
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::{self, Config},
+    engine::Engine,
+    keys,
+    models::{ids::ExchangeId, money::Decimal, orderbook::MarketType},
+    runtime,
+    ws::quote_bus,
+};
+
+/// How often a blocked `next(quotes)` checks for Ctrl-C.
+const SIGNAL_POLL: Duration = Duration::from_millis(100);
+
+/// Set by the first [`Bot`]; configuration can't be loaded twice.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[pymodule]
+fn arbitrage_bot(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Bot>()?;
+    m.add_class::<Quote>()?;
+    m.add_class::<Quotes>()?;
+    Ok(())
+}
+
+/// One normalized top-of-book quote.
+#[pyclass(module = "arbitrage_bot", frozen, get_all)]
+#[derive(Clone)]
+pub struct Quote {
+    pub exchange: &'static str,
+    pub symbol: &'static str,
+    pub bid: Decimal,
+    pub ask: Decimal,
+    /// `spot` or `futures`.
+    pub market_type: &'static str,
+    pub update_id: Option<u64>,
+}
+
+#[pymethods]
+impl Quote {
+    fn __repr__(&self) -> String {
+        format!(
+            "Quote({} {} bid={} ask={})",
+            self.exchange, self.symbol, self.bid, self.ask
+        )
+    }
+}
+
+impl From<&quote_bus::Quote> for Quote {
+    fn from(quote: &quote_bus::Quote) -> Self {
+        Self {
+            exchange: quote.exchange.name(),
+            symbol: quote.top.symbol.as_str(),
+            bid: quote.top.bid,
+            ask: quote.top.ask,
+            market_type: match quote.top.market_type {
+                MarketType::Spot => "spot",
+                MarketType::Futures => "futures",
+            },
+            update_id: quote.top.update_id,
+        }
+    }
+}
+
+/// Every quote published from the moment [`Bot::quotes`] was called. A
+/// consumer that falls behind skips ahead to the oldest quote still buffered.
+#[pyclass(module = "arbitrage_bot")]
+pub struct Quotes {
+    quotes: broadcast::Receiver<Arc<quote_bus::Quote>>,
+    handle: Handle,
+    exchange: Option<ExchangeId>,
+    symbol: Option<String>,
+    cancel: CancellationToken,
+}
+
+#[pymethods]
+impl Quotes {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Blocks until the next matching quote; `StopIteration` once the bot
+    /// has shut down.
+    fn __next__(mut slf: PyRefMut<'_, Self>, py: Python<'_>) -> PyResult<Option<Quote>> {
+        let this = &mut *slf;
+        loop {
+            if this.cancel.is_cancelled() {
+                return Ok(None);
+            }
+            let next = py.allow_threads(|| {
+                this.handle
+                    .block_on(async { tokio::time::timeout(SIGNAL_POLL, this.quotes.recv()).await })
+            });
+            match next {
+                Ok(Ok(quote)) => {
+                    let wanted = this.exchange.is_none_or(|e| e == quote.exchange)
+                        && this
+                            .symbol
+                            .as_deref()
+                            .is_none_or(|s| s == quote.top.symbol.as_str());
+                    if wanted {
+                        return Ok(Some(Quote::from(&*quote)));
+                    }
+                }
+                Ok(Err(RecvError::Lagged(_))) => {}
+                Ok(Err(RecvError::Closed)) => return Ok(None),
+                Err(_) => py.check_signals()?,
+            }
+        }
+    }
+}
+
+/// A running engine.
+#[pyclass(module = "arbitrage_bot", frozen)]
+pub struct Bot {
+    runtime: Runtime,
+    engine: Engine,
+    #[cfg(feature = "execution")]
+    trading: tokio::sync::Mutex<Option<crate::binance::api::BinanceTradingClient>>,
+}
+
+#[pymethods]
+impl Bot {
+    /// Loads `config` (or `config.toml` / `ARB_CONFIG`) and starts the engine,
+    /// returning once every feed delivers quotes or `[engine]
+    /// ready_timeout_secs` passes.
+    #[new]
+    #[pyo3(signature = (config=None))]
+    fn new(py: Python<'_>, config: Option<std::path::PathBuf>) -> PyResult<Self> {
+        if STARTED.swap(true, Ordering::SeqCst) {
+            return Err(PyRuntimeError::new_err(
+                "a Bot was already created in this process",
+            ));
+        }
+        dotenv::dotenv().ok();
+        let loaded = match config {
+            Some(path) => Config::load(path),
+            None => Config::load_default(),
+        };
+        config::init(loaded.map_err(|e| PyValueError::new_err(format!("{:#}", e)))?);
+        let runtime = Runtime::new()?;
+        runtime
+            .block_on(keys::unlock(&config::get().keys))
+            .map_err(|e| PyValueError::new_err(format!("{:#}", e)))?;
+        runtime::init(&config::get().runtime)?;
+        let engine = py
+            .allow_threads(|| runtime.block_on(Engine::start(&config::get().engine)))
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(Self {
+            runtime,
+            engine,
+            #[cfg(feature = "execution")]
+            trading: Default::default(),
+        })
+    }
+
+    /// The quote stream, optionally only from `exchange` and/or for `symbol`.
+    #[pyo3(signature = (exchange=None, symbol=None))]
+    fn quotes(&self, exchange: Option<&str>, symbol: Option<&str>) -> PyResult<Quotes> {
+        Ok(Quotes {
+            quotes: self.engine.quotes().subscribe(),
+            handle: self.runtime.handle().clone(),
+            exchange: exchange.map(exchange_id).transpose()?,
+            symbol: symbol.map(str::to_uppercase),
+            cancel: self.engine.cancellation(),
+        })
+    }
+
+    /// The latest quote of `symbol` on `exchange`, if one arrived yet.
+    fn latest(&self, exchange: &str, symbol: &str) -> PyResult<Option<Quote>> {
+        let quote = self
+            .engine
+            .quotes()
+            .latest(exchange_id(exchange)?, &symbol.to_uppercase())
+            .load();
+        Ok(quote.as_deref().map(Quote::from))
+    }
+
+    /// What the control API's `/status` returns, as a dict.
+    fn status<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let json = serde_json::to_string(&self.engine.control().status())
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        py.import("json")?.call_method1("loads", (json,))
+    }
+
+    /// Places a limit order and returns the exchange's order ID. Binance is
+    /// the only exchange with an order client; credentials come from
+    /// `API_KEY_BINANCE` / `SECRET_KEY_BINANCE`.
+    fn place_order(
+        &self,
+        py: Python<'_>,
+        exchange: &str,
+        symbol: &str,
+        side: &str,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> PyResult<String> {
+        let exchange = exchange_id(exchange)?;
+        let buy = match side.to_lowercase().as_str() {
+            "buy" => true,
+            "sell" => false,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "side must be buy or sell, got {:?}",
+                    side
+                )))
+            }
+        };
+        if price <= Decimal::ZERO || quantity <= Decimal::ZERO {
+            return Err(PyValueError::new_err("price and quantity must be positive"));
+        }
+        if exchange != ExchangeId::Binance {
+            return Err(PyValueError::new_err(format!(
+                "{} has no order client",
+                exchange
+            )));
+        }
+        let symbol = symbol.to_uppercase();
+        py.allow_threads(|| {
+            self.runtime
+                .block_on(self.place(&symbol, buy, price, quantity))
+        })
+    }
+
+    /// Stops the engine; quote iterators end.
+    fn shutdown(&self) {
+        self.engine.shutdown();
+    }
+}
+
+impl Bot {
+    #[cfg(feature = "execution")]
+    async fn place(
+        &self,
+        symbol: &str,
+        buy: bool,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> PyResult<String> {
+        use crate::{
+            binance::{api::BinanceTradingClient, create_limit_order, order::BinanceOrderSide},
+            error::TradingError,
+        };
+
+        let to_py = |e: TradingError| PyRuntimeError::new_err(e.to_string());
+        let mut trading = self.trading.lock().await;
+        if trading.is_none() {
+            let api_key = keys::var("API_KEY_BINANCE")
+                .ok_or_else(|| to_py(TradingError::MissingCredentials("API_KEY_BINANCE".into())))?;
+            let secret_key = keys::var("SECRET_KEY_BINANCE").ok_or_else(|| {
+                to_py(TradingError::MissingCredentials(
+                    "SECRET_KEY_BINANCE".into(),
+                ))
+            })?;
+            let client = BinanceTradingClient::connect(api_key.expose().into(), secret_key)
+                .await
+                .map_err(to_py)?;
+            *trading = Some(client);
+        }
+        let side = if buy {
+            BinanceOrderSide::BUY
+        } else {
+            BinanceOrderSide::SELL
+        };
+        let order = create_limit_order(symbol.to_string(), side, quantity, price);
+        let result = trading
+            .as_mut()
+            .expect("connected above")
+            .future_order_place(&order)
+            .await
+            .map_err(to_py)?;
+        Ok(result.order_id.to_string())
+    }
+
+    #[cfg(not(feature = "execution"))]
+    async fn place(
+        &self,
+        _symbol: &str,
+        _buy: bool,
+        _price: Decimal,
+        _quantity: Decimal,
+    ) -> PyResult<String> {
+        Err(PyRuntimeError::new_err(
+            "this build has no `execution` feature",
+        ))
+    }
+}
+
+fn exchange_id(name: &str) -> PyResult<ExchangeId> {
+    ExchangeId::from_name(&name.to_lowercase())
+        .ok_or_else(|| PyValueError::new_err(format!("unknown exchange {:?}", name)))
+}
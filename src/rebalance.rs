@@ -0,0 +1,262 @@
+//! Rebalancing plans for inventory mode (`[engine.execution.inventory.rebalance]`).
+//!
+//! Inventory arbitrage drains the base currency from the dear exchange and
+//! the quote currency from the cheap one. Once an exchange holds more than
+//! `max_skew_percent` of either, a [`Plan`] proposes two ways back to an even
+//! split:
+//!
+//! - transfers: withdraw each surplus to the other exchange, costed with the
+//!   cheapest open network's fee and arrival estimate from `[transfers]`
+//!   (unknown without it);
+//! - offsetting trades: sell the base surplus where it sits and buy it back
+//!   on the other exchange, which gives up the spread between them and two
+//!   taker fees but is done at once.
+//!
+//! Trades are recommended when they cost less than the transfers or a
+//! transfer is blocked. With `execute = true` execution places them itself.
+//! Transfers are only ever proposed: the trading key must not be able to
+//! withdraw (see `crate::binance::permissions`).
+
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use crate::{
+    config::RebalanceConfig,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::{self, Decimal, RoundingStrategy},
+    },
+    state::{Balance, Inventory},
+    transfers::{self, TransferStatus},
+};
+
+/// Moving `amount` of `asset` from one exchange to the other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transfer {
+    pub asset: String,
+    pub amount: Decimal,
+    pub from: ExchangeId,
+    pub to: ExchangeId,
+    /// Withdrawal fee, in `asset`; `None` while unknown.
+    pub fee: Option<Decimal>,
+    pub eta: Duration,
+    /// Why the transfer can't be made right now.
+    pub blocked: Option<String>,
+}
+
+/// Selling the base surplus where it sits and buying it on the other
+/// exchange.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OffsetTrade {
+    pub sell: ExchangeId,
+    pub buy: ExchangeId,
+    pub quantity: Decimal,
+    pub sell_price: Decimal,
+    pub buy_price: Decimal,
+    /// The spread given up plus both taker fees, in the quote currency.
+    pub cost: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Transfers,
+    Trades,
+}
+
+/// Ways to bring the inventory of one symbol back to an even split.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub symbol: Symbol,
+    pub base: String,
+    pub quote: String,
+    /// The base currency's price, for costing its transfer in quote.
+    pub price: Decimal,
+    pub transfers: Vec<Transfer>,
+    /// `None` when the exchange short of base can't pay for any.
+    pub trade: Option<OffsetTrade>,
+}
+
+impl Plan {
+    /// What the transfers cost in the quote currency, if every fee is known.
+    pub fn transfer_cost(&self) -> Option<Decimal> {
+        self.transfers
+            .iter()
+            .map(|t| {
+                let fee = t.fee?;
+                Some(if t.asset == self.base {
+                    fee * self.price
+                } else {
+                    fee
+                })
+            })
+            .sum()
+    }
+
+    pub fn recommended(&self) -> Method {
+        let Some(trade) = &self.trade else {
+            return Method::Transfers;
+        };
+        if self.transfers.iter().any(|t| t.blocked.is_some()) {
+            return Method::Trades;
+        }
+        match self.transfer_cost() {
+            Some(cost) if trade.cost < cost => Method::Trades,
+            _ => Method::Transfers,
+        }
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Rebalance {}:", self.symbol)?;
+        for t in &self.transfers {
+            write!(
+                f,
+                "\n• transfer {} {} {} → {}: ",
+                t.amount.normalize(),
+                t.asset,
+                t.from,
+                t.to
+            )?;
+            match t.fee {
+                Some(fee) => write!(f, "fee {} {}", fee.normalize(), t.asset)?,
+                None => write!(f, "fee unknown")?,
+            }
+            write!(f, ", ~{} min", t.eta.as_secs().div_ceil(60))?;
+            if let Some(reason) = &t.blocked {
+                write!(f, " (blocked: {})", reason)?;
+            }
+        }
+        if let Some(trade) = &self.trade {
+            write!(
+                f,
+                "\n• or sell {} {} on {} at {} and buy it on {} at {}: ~{} {}",
+                trade.quantity.normalize(),
+                self.base,
+                trade.sell,
+                trade.sell_price,
+                trade.buy,
+                trade.buy_price,
+                trade.cost.round_dp(2),
+                self.quote
+            )?;
+        }
+        match (self.recommended(), self.transfer_cost()) {
+            (Method::Trades, _) => write!(f, "\n→ offsetting trades"),
+            (Method::Transfers, Some(cost)) => write!(
+                f,
+                "\n→ transfers, ~{} {} in fees",
+                cost.round_dp(2),
+                self.quote
+            ),
+            (Method::Transfers, None) => write!(f, "\n→ transfers"),
+        }
+    }
+}
+
+/// Draws up [`Plan`]s, costing transfers with the latest withdrawal fees
+/// when `[transfers]` is on.
+#[derive(Debug, Clone)]
+pub struct Planner {
+    config: RebalanceConfig,
+    transfers: Option<TransferStatus>,
+}
+
+impl Planner {
+    pub fn new(config: &RebalanceConfig, transfers: Option<TransferStatus>) -> Self {
+        Self {
+            config: config.clone(),
+            transfers,
+        }
+    }
+
+    /// Whether execution places the trades a plan recommends.
+    pub fn executes(&self) -> bool {
+        self.config.execute
+    }
+
+    /// A plan to even out `inventory` in `symbol`, given each exchange's
+    /// bid and ask; trade quantities are rounded down to `step`. `None`
+    /// when there's nothing to move or an exchange short of base has no
+    /// quote.
+    pub fn plan(
+        &self,
+        symbol: Symbol,
+        inventory: &BTreeMap<ExchangeId, Inventory>,
+        quotes: impl Fn(ExchangeId) -> Option<(Decimal, Decimal)>,
+        step: Decimal,
+    ) -> Option<Plan> {
+        let (base, quote) = transfers::currencies(symbol.as_str())?;
+        let (rich, poor, excess) = surplus(inventory, |b| b.base)?;
+        let (rich_bid, rich_ask) = quotes(rich)?;
+        let (poor_bid, poor_ask) = quotes(poor)?;
+        let price = (rich_bid + rich_ask + poor_bid + poor_ask) / Decimal::from(4);
+
+        let mut moves = vec![self.transfer(base, excess, rich, poor)];
+        if let Some((from, to, amount)) = surplus(inventory, |b| b.quote) {
+            moves.push(self.transfer(quote, amount, from, to));
+        }
+
+        let affordable = inventory[&poor]
+            .balance
+            .quote
+            .checked_div(poor_ask)
+            .unwrap_or_default();
+        let quantity = money::round_to_step(excess.min(affordable), step, RoundingStrategy::ToZero);
+        let fee = self.config.trade_fee_percent / Decimal::ONE_HUNDRED;
+        let trade = (quantity > Decimal::ZERO).then(|| OffsetTrade {
+            sell: rich,
+            buy: poor,
+            quantity,
+            sell_price: rich_bid,
+            buy_price: poor_ask,
+            cost: quantity * (poor_ask - rich_bid) + quantity * (poor_ask + rich_bid) * fee,
+        });
+
+        Some(Plan {
+            symbol,
+            base: base.to_string(),
+            quote: quote.to_string(),
+            price,
+            transfers: moves,
+            trade,
+        })
+    }
+
+    fn transfer(&self, asset: &str, amount: Decimal, from: ExchangeId, to: ExchangeId) -> Transfer {
+        let status = self.transfers.as_ref();
+        let withdrawal = status.and_then(|s| s.withdrawal(from, asset));
+        let blocked = status.and_then(|s| {
+            if s.get(from, asset).is_some_and(|a| !a.withdraw) {
+                Some(format!("{} withdrawals suspended on {}", asset, from))
+            } else if s.get(to, asset).is_some_and(|a| !a.deposit) {
+                Some(format!("{} deposits suspended on {}", asset, to))
+            } else {
+                None
+            }
+        });
+        Transfer {
+            asset: asset.to_string(),
+            amount,
+            from,
+            to,
+            fee: withdrawal.as_ref().map(|w| w.fee),
+            eta: withdrawal
+                .and_then(|w| w.eta)
+                .unwrap_or(self.config.transfer_eta()),
+            blocked,
+        }
+    }
+}
+
+/// The exchanges holding the most and the least of the currency `amount`
+/// picks out, and half the difference; `None` if they hold the same.
+fn surplus(
+    inventory: &BTreeMap<ExchangeId, Inventory>,
+    amount: impl Fn(&Balance) -> Decimal,
+) -> Option<(ExchangeId, ExchangeId, Decimal)> {
+    let held = |(exchange, i): (&ExchangeId, &Inventory)| (*exchange, amount(&i.balance));
+    let (rich, most) = inventory.iter().map(held).max_by_key(|(_, a)| *a)?;
+    let (poor, least) = inventory.iter().map(held).min_by_key(|(_, a)| *a)?;
+    let surplus = (most - least) / Decimal::TWO;
+    (surplus > Decimal::ZERO).then_some((rich, poor, surplus))
+}
@@ -0,0 +1,85 @@
+//! Warm start: what the exchanges still hold from a previous run.
+//!
+//! A crash can leave positions open and orders resting on the book that the
+//! saved state (`[engine] state_file`) doesn't know about, or knows wrong.
+//! Before execution is armed, every venue is asked for its open orders and
+//! position in the traded symbol, and those replace what was tracked for it
+//! (see [`ExecutionState::recover`]). Venues that can't say keep the saved
+//! state. Only futures mode recovers; inventory mode tracks spot balances
+//! from `[engine.execution.inventory]` instead.
+
+use std::{fmt, sync::Arc};
+
+use tokio::sync::watch;
+
+use crate::{
+    error::TradingError,
+    models::{ids::ExchangeId, money::Decimal},
+    state::{ExecutionState, OpenOrder},
+    ws::exchanges::Exchange,
+};
+
+/// What one venue reported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovered {
+    pub exchange: ExchangeId,
+    pub position: Decimal,
+    pub open_orders: Vec<OpenOrder>,
+    /// The exposure tracked before.
+    pub tracked: Decimal,
+    /// The exposure now, open orders counted as filled.
+    pub exposure: Decimal,
+}
+
+impl fmt::Display for Recovered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: position {}, {} open order(s), exposure {}",
+            self.exchange,
+            self.position.normalize(),
+            self.open_orders.len(),
+            self.exposure.normalize()
+        )?;
+        if self.exposure != self.tracked {
+            write!(f, " (was tracked at {})", self.tracked.normalize())?;
+        }
+        Ok(())
+    }
+}
+
+/// Asks each of `exchanges` for its open orders and position and loads them
+/// into `state`; returns each venue's outcome.
+pub async fn recover(
+    exchanges: &[Arc<dyn Exchange>],
+    state: &watch::Sender<ExecutionState>,
+) -> Vec<Result<Recovered, (ExchangeId, TradingError)>> {
+    let mut outcomes = Vec::new();
+    for exchange in exchanges {
+        let id = exchange.id();
+        let reported = async {
+            let open_orders = exchange.open_orders().await?;
+            let position = exchange.position().await?;
+            Ok::<_, TradingError>((open_orders, position))
+        }
+        .await;
+        outcomes.push(match reported {
+            Ok((open_orders, position)) => {
+                let mut tracked = Decimal::ZERO;
+                state.send_modify(|state| {
+                    tracked = state.recover(id, position, open_orders.clone());
+                });
+                let exposure = state.borrow().exposure[&id];
+                Ok(Recovered {
+                    exchange: id,
+                    position,
+                    open_orders,
+                    tracked,
+                    exposure,
+                })
+            }
+            Err(e) => Err((id, e)),
+        });
+    }
+    outcomes
+}
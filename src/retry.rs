@@ -0,0 +1,126 @@
+//! Trying order requests again after transient failures
+//! (`[engine.execution.retry]`).
+//!
+//! A request is tried up to `max_attempts` times, `backoff_ms` apart and
+//! doubling up to `max_backoff_ms`. Fatal failures (see
+//! [`TradingError::is_fatal`]) are never tried again, and with
+//! `retryable_only` neither is anything a retry can't help (see
+//! [`Classify::is_retryable`]).
+//!
+//! A failure can leave it open whether the exchange acted on the request
+//! (see [`TradingError::is_ambiguous`]); sending an order again then may
+//! place it twice. Idempotent requests are retried anyway: every order the
+//! Binance client places carries a fresh client order ID, and a retry first
+//! looks that ID up, taking the order it finds instead of placing another.
+//! Other requests are only retried when the exchange answered.
+
+use std::future::Future;
+
+use tokio::time::{self, Duration};
+
+use crate::{
+    config::RetryConfig,
+    error::{Classify, TradingError},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+    pub retryable_only: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from(&RetryConfig::default())
+    }
+}
+
+impl From<&RetryConfig> for RetryPolicy {
+    fn from(config: &RetryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts.max(1),
+            backoff: Duration::from_millis(config.backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+            retryable_only: config.retryable_only,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Tries every request once.
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// The pause before retry number `retry`, counting from 1.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use arbitrage_bot::retry::RetryPolicy;
+    ///
+    /// let policy = RetryPolicy {
+    ///     max_attempts: 5,
+    ///     backoff: Duration::from_millis(250),
+    ///     max_backoff: Duration::from_millis(600),
+    ///     retryable_only: true,
+    /// };
+    /// let delays: Vec<_> = (1..=3).map(|retry| policy.delay(retry).as_millis()).collect();
+    /// assert_eq!(delays, [250, 500, 600]);
+    /// ```
+    pub fn delay(&self, retry: u32) -> Duration {
+        let doubled = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+        doubled.min(self.max_backoff)
+    }
+
+    /// Whether a request that failed with `error` on attempt `attempt`
+    /// (counting from 1) is tried again. An `idempotent` one may be after
+    /// an ambiguous failure.
+    pub fn allows(&self, attempt: u32, error: &TradingError, idempotent: bool) -> bool {
+        attempt < self.max_attempts
+            && !error.is_fatal()
+            && (!self.retryable_only || error.is_retryable())
+            && (idempotent || !error.is_ambiguous())
+    }
+
+    /// Runs `request` until it succeeds or the policy gives up, returning
+    /// its last result. Each attempt is handed the failure of the one
+    /// before, if any, e.g. to reconnect or look the order up first.
+    pub async fn run<T, F, Fut>(
+        &self,
+        operation: &str,
+        idempotent: bool,
+        mut request: F,
+    ) -> Result<T, TradingError>
+    where
+        F: FnMut(Option<&TradingError>) -> Fut,
+        Fut: Future<Output = Result<T, TradingError>>,
+    {
+        let mut result = request(None).await;
+        let mut attempt = 1;
+        while let Err(error) = &result {
+            if !self.allows(attempt, error, idempotent) {
+                break;
+            }
+            let delay = self.delay(attempt);
+            eprintln!(
+                "🔁 {} failed ({}); attempt {}/{} in {}ms",
+                operation,
+                error,
+                attempt + 1,
+                self.max_attempts,
+                delay.as_millis()
+            );
+            time::sleep(delay).await;
+            attempt += 1;
+            result = request(Some(error)).await;
+        }
+        result
+    }
+}
@@ -0,0 +1,120 @@
+//! Optional dedicated runtime for the latency-critical path.
+//!
+//! With `[runtime] hot_path = "dedicated"`, feeds, frame parsing, the tracker
+//! and the strategy loop run on their own Tokio runtime: a current-thread
+//! runtime on one OS thread, or a small multi-thread runtime, with its threads
+//! optionally pinned to fixed cores. Storage, Telegram and the other
+//! I/O-heavy tasks stay on the main runtime and can't add jitter to the
+//! quote→order path.
+//!
+//! Hot-path tasks are spawned with [`spawn`]; constructors that spawn their own
+//! tasks are called inside `runtime::handle().enter()`. Anything those tasks
+//! spawn with `tokio::spawn` stays on the same runtime. In the default shared
+//! mode both simply use the current runtime.
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+};
+
+use tokio::{
+    runtime::{Builder, Handle},
+    task::JoinHandle,
+};
+
+use crate::config::{HotPathMode, RuntimeConfig};
+
+static HOT_PATH: OnceLock<Handle> = OnceLock::new();
+
+/// Starts the dedicated hot-path runtime if the config asks for one. Call once
+/// at startup, before any hot-path task is spawned.
+pub fn init(config: &RuntimeConfig) -> std::io::Result<()> {
+    if config.hot_path != HotPathMode::Dedicated || HOT_PATH.get().is_some() {
+        return Ok(());
+    }
+    let threads = config.threads();
+    let cores = Arc::new(config.cores.clone());
+    let (handle_tx, handle_rx) = std::sync::mpsc::channel();
+
+    std::thread::Builder::new()
+        .name("hot-path".into())
+        .spawn(move || {
+            let runtime = if threads == 1 {
+                pin_current_thread(&cores, 0);
+                Builder::new_current_thread().enable_all().build()
+            } else {
+                let next = AtomicUsize::new(0);
+                Builder::new_multi_thread()
+                    .worker_threads(threads)
+                    .thread_name("hot-path-worker")
+                    .on_thread_start(move || {
+                        // Workers start first; later starts are blocking-pool
+                        // threads, which stay unpinned.
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index < threads {
+                            pin_current_thread(&cores, index);
+                        }
+                    })
+                    .enable_all()
+                    .build()
+            };
+            match runtime {
+                Ok(runtime) => {
+                    let _ = handle_tx.send(Ok(runtime.handle().clone()));
+                    // A current-thread runtime only makes progress inside
+                    // block_on, so this thread drives it for good.
+                    runtime.block_on(std::future::pending::<()>());
+                }
+                Err(e) => {
+                    let _ = handle_tx.send(Err(e));
+                }
+            }
+        })?;
+
+    let handle = handle_rx
+        .recv()
+        .map_err(|_| std::io::Error::other("hot-path thread exited during startup"))??;
+    let _ = HOT_PATH.set(handle);
+    println!(
+        "⚡ Hot path on a dedicated {} runtime{}",
+        if threads == 1 {
+            "current-thread".to_string()
+        } else {
+            format!("{}-thread", threads)
+        },
+        if config.cores.is_empty() {
+            String::new()
+        } else {
+            format!(", pinned to cores {:?}", config.cores)
+        }
+    );
+    Ok(())
+}
+
+/// Pins the calling thread to the `index`-th configured core (wrapping around);
+/// a no-op without configured cores.
+fn pin_current_thread(cores: &[usize], index: usize) {
+    let Some(&core) = cores.get(index % cores.len().max(1)) else {
+        return;
+    };
+    if !core_affinity::set_for_current(core_affinity::CoreId { id: core }) {
+        eprintln!("⚠️ Could not pin hot-path thread to core {}", core);
+    }
+}
+
+/// The hot-path runtime, or the current one in shared mode.
+pub fn handle() -> Handle {
+    HOT_PATH.get().cloned().unwrap_or_else(Handle::current)
+}
+
+/// Spawns `future` on the hot path.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    handle().spawn(future)
+}
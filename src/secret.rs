@@ -0,0 +1,68 @@
+//! [`SecretString`]: a secret that stays out of logs.
+
+use std::fmt;
+
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+/// An API secret, token or passphrase. `{:?}` prints `***`, the memory is
+/// wiped on drop, and comparisons take the same time however many leading
+/// bytes match. [`expose`](Self::expose) is the only way to the value.
+///
+/// ```
+/// use arbitrage_bot::secret::SecretString;
+///
+/// let secret = SecretString::from("hunter2");
+/// assert_eq!(format!("{:?}", secret), "***");
+/// assert!(secret.matches("hunter2"));
+/// assert!(!secret.matches("hunter3"));
+/// ```
+#[derive(Clone, Default)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Compares in constant time; only the length can leak.
+    pub fn matches(&self, candidate: &str) -> bool {
+        self.0.as_bytes().ct_eq(candidate.as_bytes()).into()
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl PartialEq for SecretString {
+    fn eq(&self, other: &Self) -> bool {
+        self.matches(&other.0)
+    }
+}
+
+impl Eq for SecretString {}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "***")
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
@@ -0,0 +1,340 @@
+//! What one run of the engine did, printed on shutdown.
+//!
+//! A [`Session`] starts with the engine and counts, until it shuts down,
+//! the quotes every venue delivered, the opportunities the tracker found
+//! (and the widest of them), and how often each feed had to reconnect.
+//! On shutdown it adds what execution did since it started: trades begun,
+//! executed (every leg placed) and failed, their gross PnL, the fees on
+//! the orders placed at their account's rate (see `crate::fees`), and what
+//! is left net of those. Like exposure, PnL counts every accepted order as filled
+//! at its limit price. Legs on inverse contracts count at their USD value,
+//! so PnL always comes out in the quote currency (see `crate::contracts`).
+//!
+//! With `[engine] session_log` set, each summary is also appended to that
+//! file as a JSON line.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    binance::ws_handler::ConnectionEvent,
+    contracts::Contracts,
+    error::StorageError,
+    fees::FeeSchedule,
+    models::{ids::ExchangeId, money::Decimal, orderbook::Opportunity},
+    state::{self, ExecutionState, LegSide},
+    ws::quote_bus::QuoteBus,
+};
+
+/// Counts since the session started.
+#[derive(Debug, Default)]
+struct Counts {
+    quotes: BTreeMap<ExchangeId, u64>,
+    /// Quotes the counter fell too far behind to see.
+    quotes_missed: u64,
+    opportunities: u64,
+    max_spread: Option<Opportunity>,
+    /// Connections per feed URL; all but the first are reconnects.
+    connects: BTreeMap<String, u64>,
+}
+
+/// Counts what the engine sees while it runs; see the module docs.
+#[derive(Debug, Clone)]
+pub struct Session {
+    started: Instant,
+    started_at_ms: i64,
+    /// Plans started before this session.
+    planned_before: u64,
+    fees: FeeSchedule,
+    /// Values the orders on each exchange.
+    contracts: Contracts,
+    counts: Arc<Mutex<Counts>>,
+}
+
+impl Session {
+    /// Starts counting `quotes`, `opportunities` and `events` until `cancel`
+    /// fires. `execution` is the state carried over from earlier runs, so
+    /// its trades are left out. Must be called inside a Tokio runtime.
+    pub fn start(
+        quotes: &QuoteBus,
+        opportunities: broadcast::Receiver<Opportunity>,
+        events: broadcast::Receiver<ConnectionEvent>,
+        execution: &ExecutionState,
+        fee_percent: Decimal,
+        cancel: CancellationToken,
+    ) -> Self {
+        let session = Self {
+            started: Instant::now(),
+            started_at_ms: state::now_ms(),
+            planned_before: execution.planned,
+            fees: FeeSchedule::flat(fee_percent),
+            contracts: Contracts::default(),
+            counts: Arc::default(),
+        };
+        session.follow(
+            quotes.subscribe(),
+            cancel.clone(),
+            |counts, quote| *counts.quotes.entry(quote.exchange).or_default() += 1,
+            |counts, missed| counts.quotes_missed += missed,
+        );
+        session.follow(
+            opportunities,
+            cancel.clone(),
+            |counts, opportunity| {
+                counts.opportunities += 1;
+                if counts
+                    .max_spread
+                    .as_ref()
+                    .is_none_or(|max| opportunity.diff_percent > max.diff_percent)
+                {
+                    counts.max_spread = Some(opportunity);
+                }
+            },
+            |counts, missed| counts.opportunities += missed,
+        );
+        session.follow(
+            events,
+            cancel,
+            |counts, event| {
+                if let ConnectionEvent::Connected { url } = event {
+                    *counts.connects.entry(url).or_default() += 1;
+                }
+            },
+            |_, _| {},
+        );
+        session
+    }
+
+    fn counts(&self) -> MutexGuard<'_, Counts> {
+        self.counts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Counts what `rx` receives with `count`, and what it lagged behind
+    /// on with `missed`.
+    fn follow<T: Clone + Send + 'static>(
+        &self,
+        mut rx: broadcast::Receiver<T>,
+        cancel: CancellationToken,
+        count: impl Fn(&mut Counts, T) + Send + 'static,
+        missed: impl Fn(&mut Counts, u64) + Send + 'static,
+    ) {
+        let counts = self.counts.clone();
+        tokio::spawn(async move {
+            loop {
+                let item = tokio::select! {
+                    item = rx.recv() => item,
+                    _ = cancel.cancelled() => break,
+                };
+                let mut counts = counts.lock().unwrap_or_else(|e| e.into_inner());
+                match item {
+                    Ok(item) => count(&mut counts, item),
+                    Err(RecvError::Lagged(lagged)) => missed(&mut counts, lagged),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Charges each order its account's fee by `fees` instead of a flat
+    /// `fee_percent`.
+    pub fn with_fees(mut self, fees: FeeSchedule) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    /// Values the orders on `contracts`' inverse exchanges by their
+    /// contracts' USD value.
+    pub fn with_contracts(mut self, contracts: Contracts) -> Self {
+        self.contracts = contracts;
+        self
+    }
+
+    /// The session so far, with `execution` as it stands now.
+    pub fn summary(&self, execution: &ExecutionState) -> SessionSummary {
+        let counts = self.counts();
+        let plans: Vec<_> = execution
+            .plans
+            .iter()
+            .filter(|p| p.id > self.planned_before)
+            .collect();
+        let executed: Vec<_> = plans.iter().filter(|p| p.is_complete()).collect();
+        let gross_pnl = executed
+            .iter()
+            .flat_map(|p| &p.legs)
+            .map(|l| {
+                let notional = self
+                    .contracts
+                    .get(l.leg.exchange)
+                    .notional(l.leg.quantity, l.leg.price);
+                match l.leg.side {
+                    LegSide::Buy => -notional,
+                    LegSide::Sell => notional,
+                }
+            })
+            .sum::<Decimal>();
+        let fees = execution
+            .orders
+            .iter()
+            .filter(|o| o.order_id.is_some() && o.placed_at_ms >= self.started_at_ms)
+            .map(|o| {
+                let notional = self.contracts.get(o.exchange).notional(o.quantity, o.price);
+                notional * self.fees.percent(o.account.as_deref()) / Decimal::ONE_HUNDRED
+            })
+            .sum::<Decimal>();
+        let reconnects = counts
+            .connects
+            .iter()
+            .filter(|(_, connects)| **connects > 1)
+            .map(|(url, connects)| (url.clone(), connects - 1))
+            .collect();
+        SessionSummary {
+            started_at_ms: self.started_at_ms,
+            ended_at_ms: state::now_ms(),
+            runtime_secs: self.started.elapsed().as_secs(),
+            quotes: counts.quotes.clone(),
+            quotes_missed: counts.quotes_missed,
+            opportunities: counts.opportunities,
+            max_spread: counts.max_spread.clone(),
+            trades: plans.len() as u64,
+            executed: executed.len() as u64,
+            failed: plans.iter().filter(|p| p.failed()).count() as u64,
+            open_legs: plans
+                .iter()
+                .filter(|p| p.failed())
+                .flat_map(|p| p.open_legs())
+                .count() as u64,
+            gross_pnl,
+            fees,
+            net_pnl: gross_pnl - fees,
+            reconnects,
+        }
+    }
+}
+
+/// What a session did; see the module docs.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub started_at_ms: i64,
+    pub ended_at_ms: i64,
+    pub runtime_secs: u64,
+    /// Quotes delivered per venue.
+    pub quotes: BTreeMap<ExchangeId, u64>,
+    /// Quotes that went by too fast to tell which venue they came from.
+    pub quotes_missed: u64,
+    pub opportunities: u64,
+    /// The widest spread found.
+    pub max_spread: Option<Opportunity>,
+    /// Plans started.
+    pub trades: u64,
+    /// Plans with every leg placed.
+    pub executed: u64,
+    /// Plans with a failed leg.
+    pub failed: u64,
+    /// Legs of failed trades left with a position: not rolled back, or
+    /// the rollback failed.
+    pub open_legs: u64,
+    /// Sells minus buys of the executed trades.
+    pub gross_pnl: Decimal,
+    pub fees: Decimal,
+    pub net_pnl: Decimal,
+    /// Reconnects per feed URL; feeds that never reconnected are left out.
+    pub reconnects: BTreeMap<String, u64>,
+}
+
+impl fmt::Display for SessionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "📊 Session summary ({:?})",
+            Duration::from_secs(self.runtime_secs)
+        )?;
+        let quotes: Vec<_> = self
+            .quotes
+            .iter()
+            .map(|(exchange, count)| format!("{} {}", exchange, count))
+            .collect();
+        write!(
+            f,
+            "   Quotes: {}",
+            if quotes.is_empty() {
+                "none".to_string()
+            } else {
+                quotes.join(", ")
+            }
+        )?;
+        if self.quotes_missed > 0 {
+            write!(f, " ({} missed)", self.quotes_missed)?;
+        }
+        write!(f, "\n   Opportunities: {}", self.opportunities)?;
+        if let Some(max) = &self.max_spread {
+            write!(
+                f,
+                ", widest {} {}/{} {:.2}%",
+                max.symbol, max.exchange_a, max.exchange_b, max.diff_percent
+            )?;
+        }
+        write!(
+            f,
+            "\n   Trades: {} ({} executed, {} failed",
+            self.trades, self.executed, self.failed
+        )?;
+        if self.open_legs > 0 {
+            write!(f, ", {} legs left open", self.open_legs)?;
+        }
+        write!(
+            f,
+            ")\n   PnL: gross {}, fees {}, net {}",
+            self.gross_pnl.round_dp(4),
+            self.fees.round_dp(4),
+            self.net_pnl.round_dp(4)
+        )?;
+        if self.reconnects.is_empty() {
+            write!(f, "\n   Reconnects: none")
+        } else {
+            write!(f, "\n   Reconnects:")?;
+            for (url, count) in &self.reconnects {
+                write!(f, "\n     {} {}", url, count)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Appends session summaries to `[engine] session_log`.
+pub struct SessionLog {
+    path: PathBuf,
+}
+
+impl SessionLog {
+    /// Creates the file if it doesn't exist, so a bad path fails at startup.
+    pub fn open(path: &Path) -> Result<Self, StorageError> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(StorageError::io(path))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn append(&self, summary: &SessionSummary) -> Result<(), StorageError> {
+        let line = serde_json::to_string(summary)?;
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(StorageError::io(&self.path))?;
+        writeln!(file, "{}", line).map_err(StorageError::io(&self.path))
+    }
+}
@@ -0,0 +1,369 @@
+//! Runtime state that survives a restart.
+//!
+//! With `[engine] state_file` set, the engine saves an [`EngineState`] every
+//! minute, after every trade and on shutdown, and restores it on startup:
+//! the orders execution placed and the exposure they leave, the alert gate's
+//! dedup and cooldown state, and which feeds were held back by a tripped
+//! circuit breaker. A restart in the middle of an arbitrage position then
+//! still knows about both legs, and inventory mode about what each exchange
+//! holds.
+//!
+//! The file is JSON and is replaced atomically (written next to the target,
+//! then renamed), so a crash while saving leaves the previous state intact.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::StorageError,
+    models::{ids::ExchangeId, money::Decimal},
+    plan::PlanReport,
+    portfolio::Portfolio,
+};
+
+/// Milliseconds since the Unix epoch; the time base of everything saved.
+pub fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Whether buying or selling `quantity` against `exposure` (the net
+/// quantity bought) only shrinks it, without flipping it to the other side.
+///
+/// ```
+/// use arbitrage_bot::state::{reduces, LegSide};
+/// use rust_decimal_macros::dec;
+///
+/// assert!(reduces(dec!(0.3), LegSide::Sell, dec!(0.3)));
+/// assert!(!reduces(dec!(0.3), LegSide::Sell, dec!(0.4)));
+/// assert!(!reduces(dec!(0.3), LegSide::Buy, dec!(0.1)));
+/// assert!(!reduces(dec!(0), LegSide::Sell, dec!(0.1)));
+/// ```
+pub fn reduces(exposure: Decimal, side: LegSide, quantity: Decimal) -> bool {
+    match side {
+        LegSide::Buy => exposure < Decimal::ZERO && quantity <= -exposure,
+        LegSide::Sell => exposure > Decimal::ZERO && quantity <= exposure,
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineState {
+    /// When the state was saved.
+    pub saved_at_ms: i64,
+    pub execution: ExecutionState,
+    pub alerts: AlertGateState,
+    pub circuit_breakers: Vec<BreakerTrip>,
+}
+
+impl EngineState {
+    /// Reads the state saved at `path`; `Ok(None)` if there is none yet.
+    pub fn load(path: &Path) -> Result<Option<Self>, StorageError> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(StorageError::io(path)(e)),
+        };
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    /// Writes the state to `path`, replacing what was there.
+    pub fn save(&self, path: &Path) -> Result<(), StorageError> {
+        let json = serde_json::to_string_pretty(self)?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json).map_err(StorageError::io(&tmp))?;
+        fs::rename(&tmp, path).map_err(StorageError::io(path))
+    }
+}
+
+// ── Execution ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LegSide {
+    Buy,
+    Sell,
+}
+
+impl std::fmt::Display for LegSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Buy => "BUY",
+            Self::Sell => "SELL",
+        })
+    }
+}
+
+/// One order execution tried to place.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderLeg {
+    pub exchange: ExchangeId,
+    pub symbol: String,
+    pub side: LegSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    /// The exchange's order ID, or `None` if placing the order failed.
+    pub order_id: Option<String>,
+    /// The account it went to, where the exchange has several.
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Why placing the order failed.
+    pub error: Option<String>,
+    pub placed_at_ms: i64,
+}
+
+/// What execution has done so far. Fills are not tracked, so exposure counts
+/// every accepted order as filled; on startup it's recovered from what each
+/// exchange reports (see [`ExecutionState::recover`]).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionState {
+    /// Orders placed (and attempts that failed), oldest first; the oldest
+    /// are dropped beyond `[limits] order_history`.
+    pub orders: Vec<OrderLeg>,
+    /// Net quantity bought per exchange.
+    pub exposure: BTreeMap<ExchangeId, Decimal>,
+    /// Orders recorded so far, pruned ones included; lets a watcher tell
+    /// which orders are new (see [`ExecutionState::since`]).
+    pub recorded: u64,
+    /// What each exchange holds in inventory mode; empty otherwise.
+    pub inventory: BTreeMap<ExchangeId, Inventory>,
+    /// Every trade's plan and how each of its legs went, oldest first;
+    /// pruned like `orders`.
+    pub plans: Vec<PlanReport>,
+    /// Plans started so far, pruned ones included; the last one's ID.
+    pub planned: u64,
+    /// Orders each exchange still had on its book at startup.
+    pub open_orders: BTreeMap<ExchangeId, Vec<OpenOrder>>,
+    /// Positions and PnL per strategy (see `crate::portfolio`).
+    pub portfolio: Portfolio,
+}
+
+/// An amount of the traded symbol's base and quote currency.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Balance {
+    pub base: Decimal,
+    pub quote: Decimal,
+}
+
+/// Inventory pre-positioned on one exchange. Like exposure, it counts every
+/// accepted order as filled at its limit price, and fees aren't taken off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Inventory {
+    /// The configured balance this started from.
+    pub seeded: Balance,
+    /// What is left after the orders since.
+    pub balance: Balance,
+}
+
+/// An order resting on an exchange's book, as the exchange reports it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenOrder {
+    pub order_id: String,
+    pub symbol: String,
+    pub side: LegSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub filled: Decimal,
+    /// Whether it may only shrink the position.
+    #[serde(default)]
+    pub reduce_only: bool,
+    /// When the order last changed, by the exchange's clock.
+    pub updated_at_ms: i64,
+}
+
+impl OpenOrder {
+    /// The net quantity the unfilled rest would buy.
+    pub fn remaining(&self) -> Decimal {
+        let rest = self.quantity - self.filled;
+        match self.side {
+            LegSide::Buy => rest,
+            LegSide::Sell => -rest,
+        }
+    }
+}
+
+impl ExecutionState {
+    /// Takes what `exchange` reports, its `position` and `open_orders`, over
+    /// what was tracked for it. The exposure counts the open orders as
+    /// filled, like any other accepted order. Returns the exposure tracked
+    /// before.
+    ///
+    /// ```
+    /// use arbitrage_bot::{models::ids::ExchangeId, state::{ExecutionState, LegSide, OpenOrder}};
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let mut state = ExecutionState::default();
+    /// let order = OpenOrder {
+    ///     order_id: "1".into(),
+    ///     symbol: "BTCUSDT".into(),
+    ///     side: LegSide::Sell,
+    ///     price: dec!(100),
+    ///     quantity: dec!(0.3),
+    ///     filled: dec!(0.1),
+    ///     reduce_only: false,
+    ///     updated_at_ms: 0,
+    /// };
+    /// let tracked = state.recover(ExchangeId::Binance, dec!(0.5), vec![order]);
+    /// assert_eq!(tracked, dec!(0));
+    /// assert_eq!(state.exposure[&ExchangeId::Binance], dec!(0.3));
+    /// ```
+    pub fn recover(
+        &mut self,
+        exchange: ExchangeId,
+        position: Decimal,
+        open_orders: Vec<OpenOrder>,
+    ) -> Decimal {
+        let exposure = position
+            + open_orders
+                .iter()
+                .map(OpenOrder::remaining)
+                .sum::<Decimal>();
+        let tracked = self.exposure.insert(exchange, exposure).unwrap_or_default();
+        if open_orders.is_empty() {
+            self.open_orders.remove(&exchange);
+        } else {
+            self.open_orders.insert(exchange, open_orders);
+        }
+        tracked
+    }
+
+    /// Records a placed (or failed) order and updates the exposure.
+    pub fn record(&mut self, leg: OrderLeg) {
+        if leg.order_id.is_some() {
+            let signed = match leg.side {
+                LegSide::Buy => leg.quantity,
+                LegSide::Sell => -leg.quantity,
+            };
+            *self.exposure.entry(leg.exchange).or_default() += signed;
+            if let Some(inventory) = self.inventory.get_mut(&leg.exchange) {
+                inventory.balance.base += signed;
+                inventory.balance.quote -= signed * leg.price;
+            }
+        }
+        self.orders.push(leg);
+        self.recorded += 1;
+    }
+
+    /// Takes the unfilled rest of `order`, cancelled on `exchange`, back off
+    /// the exposure that counted it as filled, and forgets it if it was
+    /// still on the book at startup.
+    pub fn cancelled(&mut self, exchange: ExchangeId, order: &OpenOrder) {
+        *self.exposure.entry(exchange).or_default() -= order.remaining();
+        if let Some(open) = self.open_orders.get_mut(&exchange) {
+            open.retain(|o| o.order_id != order.order_id);
+            if open.is_empty() {
+                self.open_orders.remove(&exchange);
+            }
+        }
+    }
+
+    /// Keeps the outcome of a plan, replacing an earlier report of it.
+    pub fn report(&mut self, report: PlanReport) {
+        self.planned = self.planned.max(report.id);
+        match self.plans.iter_mut().find(|p| p.id == report.id) {
+            Some(earlier) => *earlier = report,
+            None => self.plans.push(report),
+        }
+    }
+
+    /// Orders recorded after the state's `recorded` count was `recorded`,
+    /// oldest first; those already pruned are missing.
+    pub fn since(&self, recorded: u64) -> &[OrderLeg] {
+        let new = self.recorded.saturating_sub(recorded);
+        let new = usize::try_from(new).unwrap_or(usize::MAX);
+        &self.orders[self.orders.len().saturating_sub(new)..]
+    }
+
+    /// Whether any exchange is left with a non-zero position.
+    pub fn is_exposed(&self) -> bool {
+        self.exposure.values().any(|q| !q.is_zero())
+    }
+
+    /// Starts inventory tracking from the configured `balances`. An exchange
+    /// keeps what it was tracked at unless its configured balance changed,
+    /// e.g. after a rebalance; exchanges no longer configured are dropped.
+    /// Returns the exchanges (re)seeded.
+    pub fn seed_inventory(
+        &mut self,
+        balances: impl IntoIterator<Item = (ExchangeId, Balance)>,
+    ) -> Vec<ExchangeId> {
+        let balances: BTreeMap<_, _> = balances.into_iter().collect();
+        self.inventory
+            .retain(|exchange, _| balances.contains_key(exchange));
+        let mut seeded = Vec::new();
+        for (exchange, balance) in balances {
+            let inventory = self.inventory.entry(exchange).or_default();
+            if inventory.seeded != balance {
+                *inventory = Inventory {
+                    seeded: balance,
+                    balance,
+                };
+                seeded.push(exchange);
+            }
+        }
+        seeded
+    }
+
+    /// The exchange holding the largest share of the currency `amount`
+    /// picks out of each balance, and that share in percent.
+    ///
+    /// ```
+    /// use arbitrage_bot::{models::ids::ExchangeId, state::{Balance, ExecutionState}};
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let mut state = ExecutionState::default();
+    /// state.seed_inventory([
+    ///     (ExchangeId::Binance, Balance { base: dec!(3), quote: dec!(100) }),
+    ///     (ExchangeId::Bybit, Balance { base: dec!(1), quote: dec!(100) }),
+    /// ]);
+    /// assert_eq!(
+    ///     state.inventory_share(|b| b.base),
+    ///     Some((ExchangeId::Binance, dec!(75)))
+    /// );
+    /// ```
+    pub fn inventory_share(
+        &self,
+        amount: impl Fn(&Balance) -> Decimal,
+    ) -> Option<(ExchangeId, Decimal)> {
+        let total: Decimal = self
+            .inventory
+            .values()
+            .map(|i| amount(&i.balance).max(Decimal::ZERO))
+            .sum();
+        let (exchange, most) = self
+            .inventory
+            .iter()
+            .map(|(exchange, i)| (*exchange, amount(&i.balance)))
+            .max_by_key(|(_, held)| *held)?;
+        let share = most.max(Decimal::ZERO).checked_div(total)?;
+        Some((exchange, share * Decimal::ONE_HUNDRED))
+    }
+}
+
+// ── Alert gate ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertGateState {
+    /// Last notified diff% per pair key.
+    pub last_notified: HashMap<String, Decimal>,
+    pub last_send_ms: Option<i64>,
+}
+
+// ── Circuit breakers ─────────────────────────────────────────────────────────
+
+/// A feed's circuit breaker that was still holding off reconnects.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakerTrip {
+    pub url: String,
+    pub disconnections: usize,
+    /// When reconnects may resume.
+    pub until_ms: i64,
+}
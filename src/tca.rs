@@ -0,0 +1,365 @@
+//! Trade cost analysis: what each executed trade was expected to get, at
+//! the quotes it was detected on, against what its orders filled at.
+//!
+//! Once a trade's legs are all placed, execution follows each order until
+//! it is done or `[engine.execution] tca_timeout_secs` pass, then records
+//! per leg:
+//! - slippage: how much worse the average fill price was than the quote at
+//!   detection (the ask to buy at, the bid to sell at), in basis points;
+//!   negative when it filled better;
+//! - time to fill: from detection to the order's last fill;
+//! - the fee rate actually paid, where the order's fills say what they
+//!   paid: fees in another asset (BNB) are converted at the `[fx]` rates.
+//!
+//! For the trade it records the edge decay: the edge at detection minus the
+//! edge between the two average fill prices, in percentage points.
+//!
+//! Every trade's costs are appended to `[engine] tca_log` as a JSON line,
+//! if set, and the engine prints their running averages with its periodic
+//! reports (`[limits] report_interval_secs`).
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::{
+    error::StorageError,
+    fills,
+    fx::Fx,
+    models::{ids::ExchangeId, money::Decimal},
+    state::LegSide,
+};
+
+/// The quotes a trade was detected at, to measure its fills against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detection {
+    pub at_ms: i64,
+    /// The same moment on the monotonic clock, which the latency budget
+    /// runs on.
+    pub at: Instant,
+    /// The ask on the buy leg.
+    pub buy: Decimal,
+    /// The bid on the sell leg.
+    pub sell: Decimal,
+}
+
+impl Detection {
+    /// The quote a leg on `side` was expected to trade at.
+    pub fn expected(&self, side: LegSide) -> Decimal {
+        match side {
+            LegSide::Buy => self.buy,
+            LegSide::Sell => self.sell,
+        }
+    }
+}
+
+/// How much of an order has filled so far.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fill {
+    pub quantity: Decimal,
+    /// `None` while nothing has filled.
+    pub avg_price: Option<Decimal>,
+    /// When the order last changed, by the exchange's clock.
+    pub updated_at_ms: i64,
+    /// Whether the order is done: filled, cancelled or expired.
+    pub done: bool,
+    /// Fees paid in the quote currency, net of rebates; `None` where the
+    /// exchange doesn't say or a fee couldn't be converted.
+    #[serde(default)]
+    pub fee: Option<Decimal>,
+}
+
+impl Fill {
+    /// Aggregates an order's executions: their total quantity at their
+    /// volume-weighted average price, as of the last one, and their fees
+    /// with those in another asset converted at `fx`'s rates.
+    pub fn from_fills(fills: &[fills::Fill], done: bool, fx: Option<&Fx>) -> Self {
+        let quantity: Decimal = fills.iter().map(|f| f.qty).sum();
+        let notional: Decimal = fills.iter().map(fills::Fill::notional).sum();
+        let fee = fills.iter().try_fold(Decimal::ZERO, |sum, f| {
+            Some(sum + f.fee_in(f.quote_asset()?, fx)?)
+        });
+        Self {
+            quantity,
+            avg_price: (!quantity.is_zero()).then(|| notional / quantity),
+            updated_at_ms: fills.iter().map(|f| f.ts).max().unwrap_or_default(),
+            done,
+            fee: fee.filter(|_| !fills.is_empty()),
+        }
+    }
+}
+
+/// One leg's costs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegCost {
+    pub exchange: ExchangeId,
+    pub side: LegSide,
+    /// The quote at detection.
+    pub expected: Decimal,
+    /// The order's limit price, from the re-check right before ordering.
+    pub limit: Decimal,
+    pub quantity: Decimal,
+    pub filled: Decimal,
+    pub avg_price: Option<Decimal>,
+    /// Positive when the fill was worse than `expected`.
+    pub slippage_bps: Option<Decimal>,
+    pub time_to_fill_ms: Option<i64>,
+    /// Fees paid, in the quote currency.
+    #[serde(default)]
+    pub fee: Option<Decimal>,
+    /// `fee` in percent of the filled notional: the rate actually paid.
+    #[serde(default)]
+    pub fee_percent: Option<Decimal>,
+}
+
+impl LegCost {
+    /// The costs of a leg expected at `expected` when detected at
+    /// `detected_at_ms`, sent at `limit`, that filled as `fill` (`None` if
+    /// that couldn't be told).
+    ///
+    /// ```
+    /// use arbitrage_bot::{models::ids::ExchangeId, state::LegSide, tca::{Fill, LegCost}};
+    /// use rust_decimal_macros::dec;
+    ///
+    /// let fill = Fill {
+    ///     quantity: dec!(1),
+    ///     avg_price: Some(dec!(100.1)),
+    ///     updated_at_ms: 1_250,
+    ///     done: true,
+    ///     fee: Some(dec!(0.03003)),
+    /// };
+    /// let leg = LegCost::new(ExchangeId::Binance, LegSide::Buy, dec!(100), dec!(100), dec!(1), Some(&fill), 1_000);
+    /// assert_eq!(leg.slippage_bps, Some(dec!(10)));
+    /// assert_eq!(leg.time_to_fill_ms, Some(250));
+    /// assert_eq!(leg.fee_percent, Some(dec!(0.03)));
+    /// ```
+    pub fn new(
+        exchange: ExchangeId,
+        side: LegSide,
+        expected: Decimal,
+        limit: Decimal,
+        quantity: Decimal,
+        fill: Option<&Fill>,
+        detected_at_ms: i64,
+    ) -> Self {
+        let avg_price = fill.and_then(|f| f.avg_price);
+        let slippage_bps = avg_price.and_then(|avg| {
+            let worse = match side {
+                LegSide::Buy => avg - expected,
+                LegSide::Sell => expected - avg,
+            };
+            Some(worse.checked_div(expected)? * dec!(10000))
+        });
+        Self {
+            exchange,
+            side,
+            expected,
+            limit,
+            quantity,
+            filled: fill.map_or(Decimal::ZERO, |f| f.quantity),
+            avg_price,
+            slippage_bps,
+            time_to_fill_ms: avg_price
+                .and(fill)
+                .map(|f| (f.updated_at_ms - detected_at_ms).max(0)),
+            fee: fill.and_then(|f| f.fee),
+            fee_percent: fill.and_then(|f| {
+                let notional = f.avg_price? * f.quantity;
+                Some(f.fee?.checked_div(notional)? * Decimal::ONE_HUNDRED)
+            }),
+        }
+    }
+}
+
+/// One trade's costs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeCost {
+    pub plan_id: u64,
+    pub symbol: String,
+    pub detected_at_ms: i64,
+    pub legs: Vec<LegCost>,
+    /// Sell minus buy relative to the buy, at detection, in percent.
+    pub expected_edge_percent: Decimal,
+    /// The same between the average fill prices, once both legs filled.
+    pub realized_edge_percent: Option<Decimal>,
+    /// Expected minus realized edge, in percentage points.
+    pub edge_decay_percent: Option<Decimal>,
+}
+
+impl TradeCost {
+    pub fn new(plan_id: u64, symbol: &str, detected_at_ms: i64, legs: Vec<LegCost>) -> Self {
+        let price = |side, pick: fn(&LegCost) -> Option<Decimal>| {
+            legs.iter().find(|l| l.side == side).and_then(pick)
+        };
+        let edge = |buy: Option<Decimal>, sell: Option<Decimal>| {
+            let buy = buy?;
+            Some((sell? - buy).checked_div(buy)? * Decimal::ONE_HUNDRED)
+        };
+        let expected_edge_percent = edge(
+            price(LegSide::Buy, |l| Some(l.expected)),
+            price(LegSide::Sell, |l| Some(l.expected)),
+        )
+        .unwrap_or(Decimal::ZERO);
+        let realized_edge_percent = edge(
+            price(LegSide::Buy, |l| l.avg_price),
+            price(LegSide::Sell, |l| l.avg_price),
+        );
+        Self {
+            plan_id,
+            symbol: symbol.to_string(),
+            detected_at_ms,
+            legs,
+            expected_edge_percent,
+            realized_edge_percent,
+            edge_decay_percent: realized_edge_percent.map(|r| expected_edge_percent - r),
+        }
+    }
+}
+
+/// Running totals, to average over.
+#[derive(Debug, Default)]
+struct Totals {
+    trades: u64,
+    slippage: BTreeMap<ExchangeId, (Decimal, u64)>,
+    worst_slippage_bps: Option<Decimal>,
+    time_to_fill: (i64, u64),
+    edge_decay: (Decimal, u64),
+    fee_percent: BTreeMap<ExchangeId, (Decimal, u64)>,
+}
+
+/// Averages over the trades recorded this run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TcaStats {
+    pub trades: u64,
+    /// Mean slippage of the filled legs per exchange, in basis points.
+    pub slippage_bps: BTreeMap<ExchangeId, Decimal>,
+    pub worst_slippage_bps: Option<Decimal>,
+    pub mean_time_to_fill_ms: Option<i64>,
+    pub mean_edge_decay_percent: Option<Decimal>,
+    /// Mean fee rate paid per exchange, in percent, over the legs whose
+    /// fees are known.
+    pub fee_percent: BTreeMap<ExchangeId, Decimal>,
+}
+
+impl fmt::Display for TcaStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "📐 TCA over {} trade(s):", self.trades)?;
+        for (exchange, slippage) in &self.slippage_bps {
+            write!(f, " {} slippage {:.2}bps,", exchange, slippage)?;
+        }
+        if let Some(worst) = self.worst_slippage_bps {
+            write!(f, " worst {:.2}bps,", worst)?;
+        }
+        match self.mean_time_to_fill_ms {
+            Some(ms) => write!(f, " time to fill {}ms,", ms)?,
+            None => write!(f, " no fills,")?,
+        }
+        match self.mean_edge_decay_percent {
+            Some(decay) => write!(f, " edge decay {:.4}%", decay)?,
+            None => write!(f, " edge decay n/a")?,
+        }
+        for (exchange, fee) in &self.fee_percent {
+            write!(f, ", {} fees {:.4}%", exchange, fee)?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects trade costs, appending each to `[engine] tca_log` if set.
+#[derive(Debug, Clone, Default)]
+pub struct Tca {
+    totals: Arc<Mutex<Totals>>,
+    log: Option<PathBuf>,
+}
+
+impl Tca {
+    /// Also appends to `path`; creates the file if it doesn't exist, so a
+    /// bad path fails at startup.
+    pub fn with_log(mut self, path: &Path) -> Result<Self, StorageError> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(StorageError::io(path))?;
+        self.log = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    pub fn record(&self, cost: &TradeCost) {
+        {
+            let mut totals = self.totals.lock().unwrap_or_else(|e| e.into_inner());
+            totals.trades += 1;
+            for leg in &cost.legs {
+                if let Some(fee) = leg.fee_percent {
+                    let (sum, count) = totals.fee_percent.entry(leg.exchange).or_default();
+                    *sum += fee;
+                    *count += 1;
+                }
+                let Some(slippage) = leg.slippage_bps else {
+                    continue;
+                };
+                let (sum, count) = totals.slippage.entry(leg.exchange).or_default();
+                *sum += slippage;
+                *count += 1;
+                totals.worst_slippage_bps = Some(
+                    totals
+                        .worst_slippage_bps
+                        .map_or(slippage, |worst| worst.max(slippage)),
+                );
+                if let Some(ms) = leg.time_to_fill_ms {
+                    totals.time_to_fill.0 += ms;
+                    totals.time_to_fill.1 += 1;
+                }
+            }
+            if let Some(decay) = cost.edge_decay_percent {
+                totals.edge_decay.0 += decay;
+                totals.edge_decay.1 += 1;
+            }
+        }
+        if let Some(path) = &self.log {
+            if let Err(e) = append(path, cost) {
+                eprintln!("❌ Failed to log trade costs: {}", e);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> TcaStats {
+        let totals = self.totals.lock().unwrap_or_else(|e| e.into_inner());
+        TcaStats {
+            trades: totals.trades,
+            slippage_bps: totals
+                .slippage
+                .iter()
+                .map(|(exchange, (sum, count))| (*exchange, *sum / Decimal::from(*count)))
+                .collect(),
+            worst_slippage_bps: totals.worst_slippage_bps,
+            mean_time_to_fill_ms: (totals.time_to_fill.1 > 0)
+                .then(|| totals.time_to_fill.0 / totals.time_to_fill.1 as i64),
+            mean_edge_decay_percent: (totals.edge_decay.1 > 0)
+                .then(|| totals.edge_decay.0 / Decimal::from(totals.edge_decay.1)),
+            fee_percent: totals
+                .fee_percent
+                .iter()
+                .map(|(exchange, (sum, count))| (*exchange, *sum / Decimal::from(*count)))
+                .collect(),
+        }
+    }
+}
+
+fn append(path: &Path, cost: &TradeCost) -> Result<(), StorageError> {
+    let line = serde_json::to_string(cost)?;
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(path)
+        .map_err(StorageError::io(path))?;
+    writeln!(file, "{}", line).map_err(StorageError::io(path))
+}
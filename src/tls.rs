@@ -0,0 +1,163 @@
+//! rustls client configuration shared by WebSocket and REST connections.
+//!
+//! Certificates are verified against the bundled webpki roots as usual. Hosts
+//! listed under `[tls.pins]` must additionally present a leaf certificate whose
+//! SHA-256 fingerprint is one of the configured pins, so a mis-issued or
+//! intercepting certificate is rejected even if it chains to a trusted root.
+
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+};
+
+use rustls::{
+    client::{
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        WebPkiServerVerifier,
+    },
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    CertificateError, ClientConfig, DigitallySignedStruct, OtherError, RootCertStore,
+    SignatureScheme,
+};
+use sha2::{Digest, Sha256};
+
+use crate::config::{self, TlsConfig};
+
+/// Returned by the verifier when a pinned host presents an unpinned certificate.
+#[derive(Debug)]
+pub struct PinMismatch {
+    pub host: String,
+    pub fingerprint: String,
+}
+
+impl fmt::Display for PinMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "certificate for {} (sha256 {}) matches no configured pin",
+            self.host, self.fingerprint
+        )
+    }
+}
+
+impl std::error::Error for PinMismatch {}
+
+/// Hex SHA-256 of a DER certificate, the format used in `[tls.pins]`.
+pub fn fingerprint(cert: &[u8]) -> String {
+    hex::encode(Sha256::digest(cert))
+}
+
+fn normalize_pin(pin: &str) -> String {
+    pin.chars()
+        .filter(|c| *c != ':')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+#[derive(Debug)]
+struct PinningVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    pins: Vec<(String, Vec<String>)>,
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        let host = server_name.to_str();
+        let Some((_, pins)) = self.pins.iter().find(|(h, _)| *h == host) else {
+            return Ok(verified);
+        };
+        let fingerprint = fingerprint(end_entity);
+        if pins.contains(&fingerprint) {
+            Ok(verified)
+        } else {
+            Err(rustls::Error::InvalidCertificate(CertificateError::Other(
+                OtherError(Arc::new(PinMismatch {
+                    host: host.to_string(),
+                    fingerprint,
+                })),
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// Builds a client config from `tls`.
+pub fn build_client_config(tls: &TlsConfig) -> Result<ClientConfig, rustls::Error> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let inner = WebPkiServerVerifier::builder(Arc::new(roots))
+        .build()
+        .map_err(|e| rustls::Error::General(e.to_string()))?;
+
+    let verifier = PinningVerifier {
+        inner,
+        pins: tls
+            .pins
+            .iter()
+            .map(|(host, pins)| {
+                (
+                    host.clone(),
+                    pins.iter().map(|p| normalize_pin(p)).collect(),
+                )
+            })
+            .collect(),
+    };
+
+    let builder = if tls.tls13_only {
+        ClientConfig::builder_with_protocol_versions(&[&rustls::version::TLS13])
+    } else {
+        ClientConfig::builder()
+    };
+    Ok(builder
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth())
+}
+
+/// The process-wide client config built from `config::get().tls`.
+pub fn client_config() -> Arc<ClientConfig> {
+    static CLIENT_CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+    CLIENT_CONFIG
+        .get_or_init(|| {
+            let config = build_client_config(&config::get().tls)
+                .expect("default rustls client config must build");
+            Arc::new(config)
+        })
+        .clone()
+}
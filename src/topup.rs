@@ -0,0 +1,399 @@
+//! Margin top-ups between the accounts of an exchange
+//! (`[engine.execution.topup]`).
+//!
+//! Under sustained one-directional flow one account keeps opening positions
+//! on the same side and its available margin drains, while another's sits
+//! idle. Every `interval_secs` each account's available margin is read (see
+//! `Exchange::available_margin`). Once one stays below `min_margin` for
+//! `low_checks` checks in a row, the account with the most margin tops it
+//! back up to `target_margin`, as long as that leaves the donor with at
+//! least `target_margin` itself. One transfer moves at most `max_transfer`,
+//! and all of them together at most `max_daily` in any 24 hours. A transfer
+//! counts towards that unless the exchange refused it, since one whose
+//! answer was lost may have gone through.
+//!
+//! Transfers are signed with the master account's key: Binance's
+//! `POST /sapi/v1/sub-account/universalTransfer` between USDⓈ-M futures
+//! wallets, Bybit's `POST /v5/asset/transfer/universal-transfer` between
+//! unified accounts. Without `execute = true` they are only announced. Each
+//! one is logged, written to the audit log (`[engine] audit_log`) and sent
+//! to Telegram.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+    sync::Arc,
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    audit,
+    binance::{api::WsError, auth::KeyVars},
+    config::{self, TopUpConfig},
+    constants::{exchange_names, urls},
+    error::TradingError,
+    keys,
+    models::{bybit_make_orders::BybitAuth, ids::ExchangeId, money::Decimal},
+    net,
+    notifications::telegram::Notification,
+    state,
+    ws::exchanges::Exchange,
+};
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// An account whose margin is watched.
+pub struct Member {
+    pub name: String,
+    /// Email (Binance) or member ID (Bybit); `None` for the master account.
+    pub sub_account: Option<String>,
+    pub exchange: Arc<dyn Exchange>,
+}
+
+/// Collateral to move from one account to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopUp {
+    pub exchange: ExchangeId,
+    pub from: String,
+    pub to: String,
+    pub asset: String,
+    pub amount: Decimal,
+}
+
+impl fmt::Display for TopUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} on {} from {} to {}",
+            self.amount.normalize(),
+            self.asset,
+            self.exchange,
+            self.from,
+            self.to
+        )
+    }
+}
+
+/// Decides the top-ups the margin readings call for; see the module docs.
+#[derive(Debug)]
+pub struct TopUps {
+    exchange: ExchangeId,
+    config: TopUpConfig,
+    /// Checks in a row each account has been below `min_margin`.
+    low: HashMap<String, u32>,
+    /// When each transfer of the last 24 hours went out, and its amount.
+    moved: VecDeque<(i64, Decimal)>,
+}
+
+impl TopUps {
+    pub fn new(exchange: ExchangeId, config: &TopUpConfig) -> Self {
+        Self {
+            exchange,
+            config: config.clone(),
+            low: HashMap::new(),
+            moved: VecDeque::new(),
+        }
+    }
+
+    /// Takes a reading of each account's available margin; returns the
+    /// top-up it calls for, if any.
+    pub fn check(&mut self, margins: &[(String, Decimal)], now_ms: i64) -> Option<TopUp> {
+        for (name, margin) in margins {
+            if *margin < self.config.min_margin {
+                *self.low.entry(name.clone()).or_default() += 1;
+            } else {
+                self.low.remove(name);
+            }
+        }
+        let (to, short) = margins
+            .iter()
+            .filter(|(name, _)| self.low.get(name).copied().unwrap_or(0) >= self.config.low_checks)
+            .min_by_key(|(_, margin)| *margin)?;
+        let (from, spare) = margins
+            .iter()
+            .filter(|(name, _)| name != to)
+            .max_by_key(|(_, margin)| *margin)?;
+        let amount = (self.config.target_margin - short)
+            .min(self.config.max_transfer)
+            .min(self.allowance(now_ms))
+            .min(*spare - self.config.target_margin);
+        if amount <= Decimal::ZERO {
+            return None;
+        }
+        // It waits `low_checks` again, so the transfer can land first.
+        self.low.remove(to);
+        Some(TopUp {
+            exchange: self.exchange,
+            from: from.clone(),
+            to: to.clone(),
+            asset: self.config.asset.clone(),
+            amount,
+        })
+    }
+
+    /// What `max_daily` still allows at `now_ms`.
+    pub fn allowance(&mut self, now_ms: i64) -> Decimal {
+        while self
+            .moved
+            .front()
+            .is_some_and(|(at, _)| *at <= now_ms - DAY_MS)
+        {
+            self.moved.pop_front();
+        }
+        self.config.max_daily - self.moved.iter().map(|(_, amount)| amount).sum::<Decimal>()
+    }
+
+    /// Counts a transfer that went out at `now_ms` against `max_daily`.
+    pub fn record(&mut self, top_up: &TopUp, now_ms: i64) {
+        self.moved.push_back((now_ms, top_up.amount));
+    }
+
+    /// Counts a transfer tried at `now_ms` against `max_daily` unless the
+    /// exchange definitely refused it.
+    pub fn attempted(
+        &mut self,
+        top_up: &TopUp,
+        result: &Result<String, TradingError>,
+        now_ms: i64,
+    ) {
+        match result {
+            Err(e) if !e.is_ambiguous() => {}
+            _ => self.record(top_up, now_ms),
+        }
+    }
+
+    /// Reads every account's margin each `interval_secs` and makes (or,
+    /// without `execute`, announces) the top-ups due, until `cancel` fires.
+    pub fn spawn(
+        mut self,
+        members: Vec<Member>,
+        alerts: Option<mpsc::Sender<Notification>>,
+        cancel: CancellationToken,
+    ) {
+        tokio::spawn(async move {
+            let client = net::http_client();
+            let mut interval = tokio::time::interval(self.config.interval());
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = cancel.cancelled() => break,
+                }
+                let mut margins = Vec::new();
+                for member in &members {
+                    match member.exchange.available_margin().await {
+                        Ok(margin) => margins.push((member.name.clone(), margin)),
+                        Err(e) => eprintln!("⚠️ No margin for account {}: {}", member.name, e),
+                    }
+                }
+                let Some(top_up) = self.check(&margins, state::now_ms()) else {
+                    continue;
+                };
+                println!("💸 Margin top-up: {}", top_up);
+                let result = if self.config.execute {
+                    let result = transfer(&client, &self.config, &members, &top_up).await;
+                    match &result {
+                        Ok(id) => println!("✅ Moved {} (transfer {})", top_up, id),
+                        Err(e) => eprintln!("❌ Moving {} failed: {}", top_up, e),
+                    }
+                    self.attempted(&top_up, &result, state::now_ms());
+                    Some(result.map_err(|e| e.to_string()))
+                } else {
+                    println!("💸 Not moved: [engine.execution.topup] execute is off");
+                    None
+                };
+                if let Some(alerts) = &alerts {
+                    let _ = alerts.try_send(Notification::MarginTopUp {
+                        plan: top_up.to_string(),
+                        result,
+                    });
+                }
+            }
+        });
+    }
+}
+
+/// Makes `top_up` with the master key; returns the exchange's transfer ID.
+pub async fn transfer(
+    client: &reqwest::Client,
+    config: &TopUpConfig,
+    members: &[Member],
+    top_up: &TopUp,
+) -> Result<String, TradingError> {
+    let sub_account = |name: &str| {
+        members
+            .iter()
+            .find(|m| m.name == name)
+            .and_then(|m| m.sub_account.as_deref())
+    };
+    let (from, to) = (sub_account(&top_up.from), sub_account(&top_up.to));
+    match top_up.exchange {
+        ExchangeId::Binance => binance_transfer(client, config, from, to, top_up).await,
+        ExchangeId::Bybit => bybit_transfer(client, config, from, to, top_up).await,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceTransfer {
+    tran_id: u64,
+}
+
+/// Binance's universal transfer between futures wallets; an account without
+/// an email is the master.
+async fn binance_transfer(
+    client: &reqwest::Client,
+    config: &TopUpConfig,
+    from: Option<&str>,
+    to: Option<&str>,
+    top_up: &TopUp,
+) -> Result<String, TradingError> {
+    let auth = KeyVars::new(&config.master_api_key_var, &config.master_secret_key_var).auth()?;
+    let mut params = BTreeMap::from([
+        ("fromAccountType".to_string(), "USDT_FUTURE".to_string()),
+        ("toAccountType".to_string(), "USDT_FUTURE".to_string()),
+        ("asset".to_string(), top_up.asset.clone()),
+        ("amount".to_string(), top_up.amount.normalize().to_string()),
+    ]);
+    if let Some(email) = from {
+        params.insert("fromEmail".to_string(), email.to_string());
+    }
+    if let Some(email) = to {
+        params.insert("toEmail".to_string(), email.to_string());
+    }
+    let url = format!(
+        "{}/sapi/v1/sub-account/universalTransfer?{}",
+        config::get().network.endpoint(urls::BINANCE_REST_SPOT),
+        auth.signed_query(params.clone())
+    );
+    let response = async {
+        let response = client
+            .post(&url)
+            .header("X-MBX-APIKEY", auth.api_key())
+            .send()
+            .await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.json::<Value>().await?);
+        }
+        let WsError { code, msg } = match response.json().await {
+            Ok(error) => error,
+            // A gateway error says nothing of whether the transfer was made.
+            Err(_) if status.is_server_error() => {
+                return Err(TradingError::EmptyResponse {
+                    operation: "sub-account transfer",
+                })
+            }
+            Err(_) => WsError {
+                code: status.as_u16().into(),
+                msg: status.to_string(),
+            },
+        };
+        Err(TradingError::Rejected {
+            exchange: ExchangeId::Binance,
+            operation: "sub-account transfer",
+            code,
+            msg,
+        })
+    }
+    .await;
+    audit::record(
+        exchange_names::BINANCE,
+        "sub-account transfer",
+        &params,
+        &response,
+    );
+    let BinanceTransfer { tran_id } = serde_json::from_value(response?)?;
+    Ok(tran_id.to_string())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitResponse {
+    ret_code: i32,
+    ret_msg: String,
+    result: Option<BybitTransfer>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitTransfer {
+    transfer_id: String,
+}
+
+/// Bybit's universal transfer between unified accounts, which needs every
+/// account's member ID, the master's included.
+async fn bybit_transfer(
+    client: &reqwest::Client,
+    config: &TopUpConfig,
+    from: Option<&str>,
+    to: Option<&str>,
+    top_up: &TopUp,
+) -> Result<String, TradingError> {
+    let member_id = |id: Option<&str>, name: &str| {
+        id.and_then(|id| id.parse::<u64>().ok()).ok_or_else(|| {
+            TradingError::MissingCredentials(format!("the Bybit member ID of account {}", name))
+        })
+    };
+    let body = json!({
+        "transferId": uuid::Uuid::new_v4().to_string(),
+        "coin": top_up.asset,
+        "amount": top_up.amount.normalize().to_string(),
+        "fromMemberId": member_id(from, &top_up.from)?,
+        "toMemberId": member_id(to, &top_up.to)?,
+        "fromAccountType": "UNIFIED",
+        "toAccountType": "UNIFIED",
+    });
+    let api_key = keys::var(&config.master_api_key_var)
+        .ok_or_else(|| TradingError::MissingCredentials(config.master_api_key_var.clone()))?;
+    let secret = keys::var(&config.master_secret_key_var)
+        .ok_or_else(|| TradingError::MissingCredentials(config.master_secret_key_var.clone()))?;
+    let auth = BybitAuth::new(api_key.expose(), secret);
+    let payload = body.to_string();
+    let url = format!(
+        "{}/v5/asset/transfer/universal-transfer",
+        config::get().network.endpoint(urls::BYBIT_REST)
+    );
+    let response = async {
+        let mut request = client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .body(payload.clone());
+        for (name, value) in auth.rest_headers(&payload) {
+            request = request.header(name, value);
+        }
+        let response: Value = request.send().await?.json().await?;
+        Ok(response)
+    }
+    .await;
+    let params = body
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(k, v)| (k.clone(), v.to_string().trim_matches('"').to_string()))
+        .collect();
+    audit::record(
+        exchange_names::BYBIT,
+        "universal-transfer",
+        &params,
+        &response,
+    );
+    match serde_json::from_value(response?)? {
+        BybitResponse {
+            ret_code: 0,
+            result: Some(BybitTransfer { transfer_id }),
+            ..
+        } => Ok(transfer_id),
+        BybitResponse {
+            ret_code, ret_msg, ..
+        } => Err(TradingError::Rejected {
+            exchange: ExchangeId::Bybit,
+            operation: "universal-transfer",
+            code: ret_code,
+            msg: ret_msg,
+        }),
+    }
+}
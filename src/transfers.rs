@@ -0,0 +1,434 @@
+//! Deposit and withdrawal status of assets on each exchange (`[transfers]`).
+//!
+//! Spot arbitrage between exchanges has to move the asset: buy where it's
+//! cheap, withdraw, deposit where it's dear, sell. When either transfer is
+//! suspended the gap can't be closed, and such "spreads" are mostly
+//! liquidity stranded on one side. [`TransferStatus`] keeps each exchange's
+//! per-coin status, refreshed every `refresh_secs` from Binance
+//! (`/sapi/v1/capital/config/getall`) and Bybit (`/v5/asset/coin/query-info`),
+//! and tells alerting which spreads it can't trade: flagged, or with `block
+//! = true` not sent at all.
+//!
+//! The same endpoints give each coin's cheapest open withdrawal network, its
+//! fee and (on Binance) how long it typically takes, which inventory
+//! rebalancing plans are costed with (see `crate::rebalance`).
+//!
+//! Both endpoints are signed, so fetching needs the `execution` feature and
+//! read-only keys (`API_KEY_BINANCE` / `SECRET_KEY_BINANCE`, `API_KEY_BYBIT`
+//! / `SECRET_KEY_BYBIT`); an exchange without keys is skipped. Assets whose
+//! status is unknown are never flagged. Futures execution settles in margin
+//! and moves nothing, so it isn't affected.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+
+use crate::models::{
+    ids::{ExchangeId, Symbol},
+    money::Decimal,
+};
+#[cfg(feature = "execution")]
+use {
+    crate::{
+        binance::BinanceAuth,
+        config::{self, TransfersConfig},
+        constants::urls,
+        error::TradingError,
+        keys,
+        models::{bybit_make_orders::BybitAuth, money},
+        net,
+    },
+    serde::Deserialize,
+    std::collections::BTreeMap,
+    tokio_util::sync::CancellationToken,
+};
+
+/// Quote currencies, longest first so `FDUSD` isn't read as `…USD`.
+const QUOTES: &[&str] = &[
+    "FDUSD", "USDT", "USDC", "BUSD", "TUSD", "USD", "EUR", "TRY", "BTC", "ETH", "BNB",
+];
+
+/// The base asset of `symbol`, i.e. what a transfer would move.
+///
+/// ```
+/// use arbitrage_bot::transfers::base_asset;
+///
+/// assert_eq!(base_asset("WLFIUSDT"), Some("WLFI"));
+/// assert_eq!(base_asset("ETHBTC"), Some("ETH"));
+/// assert_eq!(base_asset("USDT"), None);
+/// ```
+pub fn base_asset(symbol: &str) -> Option<&str> {
+    QUOTES
+        .iter()
+        .find_map(|quote| symbol.strip_suffix(quote))
+        .filter(|base| !base.is_empty())
+}
+
+/// The base and quote asset of `symbol`.
+///
+/// ```
+/// use arbitrage_bot::transfers::currencies;
+///
+/// assert_eq!(currencies("BTCFDUSD"), Some(("BTC", "FDUSD")));
+/// ```
+pub fn currencies(symbol: &str) -> Option<(&str, &str)> {
+    let base = base_asset(symbol)?;
+    Some((base, &symbol[base.len()..]))
+}
+
+/// Whether a coin can move in or out of an exchange, over any network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetStatus {
+    pub deposit: bool,
+    pub withdraw: bool,
+    /// Every network open for withdrawals is congested.
+    pub congested: bool,
+}
+
+/// The cheapest network a coin can be withdrawn over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Withdrawal {
+    pub network: String,
+    /// Charged in the coin itself.
+    pub fee: Decimal,
+    /// How long it typically takes to arrive, where the exchange says.
+    pub eta: Option<Duration>,
+}
+
+/// Status per exchange and coin.
+pub type Statuses = HashMap<(ExchangeId, String), AssetStatus>;
+
+/// Withdrawal route per exchange and coin.
+pub type Withdrawals = HashMap<(ExchangeId, String), Withdrawal>;
+
+/// The latest status of every coin, shared by the tracker and the refresh
+/// task and swapped whole on every refresh.
+#[derive(Debug, Clone, Default)]
+pub struct TransferStatus {
+    statuses: Arc<ArcSwap<Statuses>>,
+    withdrawals: Arc<ArcSwap<Withdrawals>>,
+}
+
+impl TransferStatus {
+    pub fn get(&self, exchange: ExchangeId, asset: &str) -> Option<AssetStatus> {
+        self.statuses
+            .load()
+            .get(&(exchange, asset.to_string()))
+            .copied()
+    }
+
+    pub fn withdrawal(&self, exchange: ExchangeId, asset: &str) -> Option<Withdrawal> {
+        self.withdrawals
+            .load()
+            .get(&(exchange, asset.to_string()))
+            .cloned()
+    }
+
+    /// Replaces what `exchange` reported last time.
+    pub fn update(&self, exchange: ExchangeId, coins: HashMap<String, AssetStatus>) {
+        replace(&self.statuses, exchange, &coins);
+    }
+
+    /// Replaces the withdrawal routes `exchange` reported last time.
+    pub fn update_withdrawals(&self, exchange: ExchangeId, coins: HashMap<String, Withdrawal>) {
+        replace(&self.withdrawals, exchange, &coins);
+    }
+
+    /// Why buying `symbol` on `buy` and moving it to `sell` can't be done
+    /// right now, if it can't.
+    pub fn blocker(&self, symbol: Symbol, buy: ExchangeId, sell: ExchangeId) -> Option<String> {
+        let asset = base_asset(symbol.as_str())?;
+        let mut problems = Vec::new();
+        if let Some(status) = self.get(buy, asset) {
+            if !status.withdraw {
+                problems.push(format!("{} withdrawals suspended on {}", asset, buy));
+            } else if status.congested {
+                problems.push(format!("{} withdrawals congested on {}", asset, buy));
+            }
+        }
+        if let Some(status) = self.get(sell, asset) {
+            if !status.deposit {
+                problems.push(format!("{} deposits suspended on {}", asset, sell));
+            }
+        }
+        (!problems.is_empty()).then(|| problems.join(", "))
+    }
+}
+
+/// Swaps in `coins` as everything `exchange` has in `map`.
+fn replace<T: Clone>(
+    map: &ArcSwap<HashMap<(ExchangeId, String), T>>,
+    exchange: ExchangeId,
+    coins: &HashMap<String, T>,
+) {
+    map.rcu(|map| {
+        let mut map: HashMap<_, _> = map
+            .iter()
+            .filter(|((e, _), _)| *e != exchange)
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        map.extend(
+            coins
+                .iter()
+                .map(|(coin, value)| ((exchange, coin.clone()), value.clone())),
+        );
+        map
+    });
+}
+
+#[cfg(feature = "execution")]
+impl TransferStatus {
+    /// Fetches the status from every exchange with keys now and then every
+    /// `refresh_secs`, until `cancel` fires.
+    pub fn spawn_refresh(&self, config: &TransfersConfig, cancel: CancellationToken) {
+        let status = self.clone();
+        let every = config.refresh();
+        tokio::spawn(async move {
+            let client = net::http_client();
+            let mut failing = HashMap::new();
+            let mut interval = tokio::time::interval(every);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = cancel.cancelled() => break,
+                }
+                for exchange in [ExchangeId::Binance, ExchangeId::Bybit] {
+                    let result = match exchange {
+                        ExchangeId::Binance => fetch_binance(&client).await,
+                        ExchangeId::Bybit => fetch_bybit(&client).await,
+                    };
+                    let was_failing = failing.insert(exchange, result.is_err()) == Some(true);
+                    match result {
+                        Ok(Some(coins)) => {
+                            if was_failing {
+                                println!("✅ Transfer status from {} readable again", exchange);
+                            }
+                            status.update(
+                                exchange,
+                                coins.iter().map(|(c, (s, _))| (c.clone(), *s)).collect(),
+                            );
+                            status.update_withdrawals(
+                                exchange,
+                                coins
+                                    .into_iter()
+                                    .filter_map(|(c, (_, w))| Some((c, w?)))
+                                    .collect(),
+                            );
+                        }
+                        Ok(None) => {}
+                        // Reported once per outage; the last status stays in use.
+                        Err(e) if !was_failing => {
+                            eprintln!(
+                                "❌ Fetching transfer status from {} failed: {}",
+                                exchange, e
+                            )
+                        }
+                        Err(_) => {}
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Binance `GET /sapi/v1/capital/config/getall`, reduced to what the status
+/// needs.
+#[cfg(feature = "execution")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinanceCoin {
+    pub coin: String,
+    pub deposit_all_enable: bool,
+    pub withdraw_all_enable: bool,
+    pub network_list: Vec<BinanceNetwork>,
+}
+
+#[cfg(feature = "execution")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinanceNetwork {
+    pub network: String,
+    pub deposit_enable: bool,
+    pub withdraw_enable: bool,
+    #[serde(default)]
+    pub busy: bool,
+    pub withdraw_fee: Decimal,
+    /// In minutes.
+    #[serde(default)]
+    pub estimated_arrival_time: Option<u64>,
+}
+
+#[cfg(feature = "execution")]
+impl BinanceCoin {
+    pub fn status(&self) -> AssetStatus {
+        let withdrawable: Vec<_> = self
+            .network_list
+            .iter()
+            .filter(|n| n.withdraw_enable)
+            .collect();
+        AssetStatus {
+            deposit: self.deposit_all_enable && self.network_list.iter().any(|n| n.deposit_enable),
+            withdraw: self.withdraw_all_enable && !withdrawable.is_empty(),
+            congested: !withdrawable.is_empty() && withdrawable.iter().all(|n| n.busy),
+        }
+    }
+
+    /// The cheapest network open for withdrawals, if any.
+    pub fn withdrawal(&self) -> Option<Withdrawal> {
+        if !self.withdraw_all_enable {
+            return None;
+        }
+        let network = self
+            .network_list
+            .iter()
+            .filter(|n| n.withdraw_enable)
+            .min_by_key(|n| n.withdraw_fee)?;
+        Some(Withdrawal {
+            network: network.network.clone(),
+            fee: network.withdraw_fee,
+            eta: network
+                .estimated_arrival_time
+                .map(|mins| Duration::from_secs(mins * 60)),
+        })
+    }
+}
+
+/// Bybit `GET /v5/asset/coin/query-info`, reduced to what the status needs.
+#[cfg(feature = "execution")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BybitCoinInfo {
+    pub ret_code: i32,
+    pub ret_msg: String,
+    #[serde(default)]
+    pub result: Option<BybitCoinRows>,
+}
+
+#[cfg(feature = "execution")]
+#[derive(Debug, Deserialize)]
+pub struct BybitCoinRows {
+    pub rows: Vec<BybitCoin>,
+}
+
+#[cfg(feature = "execution")]
+#[derive(Debug, Deserialize)]
+pub struct BybitCoin {
+    pub coin: String,
+    pub chains: Vec<BybitChain>,
+}
+
+/// Bybit sends its flags as `"1"` / `"0"`.
+#[cfg(feature = "execution")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BybitChain {
+    pub chain: String,
+    pub chain_deposit: String,
+    pub chain_withdraw: String,
+    /// Empty for chains without withdrawals.
+    #[serde(default)]
+    pub withdraw_fee: String,
+}
+
+#[cfg(feature = "execution")]
+impl BybitCoin {
+    pub fn status(&self) -> AssetStatus {
+        AssetStatus {
+            deposit: self.chains.iter().any(|c| c.chain_deposit == "1"),
+            withdraw: self.chains.iter().any(|c| c.chain_withdraw == "1"),
+            // Bybit doesn't report congestion.
+            congested: false,
+        }
+    }
+
+    /// The cheapest chain open for withdrawals, if any. Bybit gives no
+    /// arrival estimate.
+    pub fn withdrawal(&self) -> Option<Withdrawal> {
+        self.chains
+            .iter()
+            .filter(|c| c.chain_withdraw == "1")
+            .filter_map(|c| Some((c, money::parse(&c.withdraw_fee)?)))
+            .min_by_key(|(_, fee)| *fee)
+            .map(|(c, fee)| Withdrawal {
+                network: c.chain.clone(),
+                fee,
+                eta: None,
+            })
+    }
+}
+
+/// Status and withdrawal route per coin.
+#[cfg(feature = "execution")]
+type Coins = HashMap<String, (AssetStatus, Option<Withdrawal>)>;
+
+/// The status of every Binance coin, or `None` without keys.
+#[cfg(feature = "execution")]
+async fn fetch_binance(client: &reqwest::Client) -> Result<Option<Coins>, TradingError> {
+    let (Some(api_key), Some(secret)) = (
+        keys::var("API_KEY_BINANCE"),
+        keys::var("SECRET_KEY_BINANCE"),
+    ) else {
+        return Ok(None);
+    };
+    let auth = BinanceAuth::new(api_key.expose().into(), secret)?;
+    let url = format!(
+        "{}/sapi/v1/capital/config/getall?{}",
+        config::get().network.endpoint(urls::BINANCE_REST_SPOT),
+        auth.signed_query(BTreeMap::new())
+    );
+    let response = client
+        .get(&url)
+        .header("X-MBX-APIKEY", auth.api_key())
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(TradingError::Rejected {
+            exchange: ExchangeId::Binance,
+            operation: "capital/config/getall",
+            code: status.as_u16().into(),
+            msg: response.text().await.unwrap_or_else(|_| status.to_string()),
+        });
+    }
+    let coins: Vec<BinanceCoin> = response.json().await?;
+    Ok(Some(
+        coins
+            .iter()
+            .map(|c| (c.coin.clone(), (c.status(), c.withdrawal())))
+            .collect(),
+    ))
+}
+
+/// The status of every Bybit coin, or `None` without keys.
+#[cfg(feature = "execution")]
+async fn fetch_bybit(client: &reqwest::Client) -> Result<Option<Coins>, TradingError> {
+    let (Some(api_key), Some(secret)) = (keys::var("API_KEY_BYBIT"), keys::var("SECRET_KEY_BYBIT"))
+    else {
+        return Ok(None);
+    };
+    let auth = BybitAuth::new(api_key.expose(), secret);
+    let url = format!(
+        "{}/v5/asset/coin/query-info",
+        config::get().network.endpoint(urls::BYBIT_REST)
+    );
+    let mut request = client.get(&url);
+    for (name, value) in auth.rest_headers("") {
+        request = request.header(name, value);
+    }
+    let info: BybitCoinInfo = request.send().await?.json().await?;
+    match info.result {
+        Some(result) if info.ret_code == 0 => Ok(Some(
+            result
+                .rows
+                .iter()
+                .map(|c| (c.coin.clone(), (c.status(), c.withdrawal())))
+                .collect(),
+        )),
+        _ => Err(TradingError::Rejected {
+            exchange: ExchangeId::Bybit,
+            operation: "coin/query-info",
+            code: info.ret_code,
+            msg: info.ret_msg,
+        }),
+    }
+}
@@ -0,0 +1,437 @@
+//! `--tui`: a terminal dashboard over [`Control`].
+//!
+//! Shows every venue's bid/ask/mid, the spread of each symbol quoted on two
+//! exchanges against the alert threshold, feed connection states, recent
+//! alerts, and the orders and exposure execution left. The console output the
+//! bot normally prints would tear the screen, so it goes to `[tui] log_file`
+//! for as long as the dashboard is up (see [`OutputRedirect`]).
+//!
+//! Keys: `p` pauses/resumes execution; `q`, `Esc` or `Ctrl-C` shuts the bot
+//! down.
+
+use std::{fs::File, io, path::Path};
+
+use chrono::{Local, TimeZone};
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Cell, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+use tokio::runtime::Handle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config,
+    control::{Control, Status},
+    models::{
+        money::{self, Decimal},
+        orderbook::Opportunity,
+    },
+    state::{ExecutionState, LegSide},
+};
+
+/// Orders shown, newest first.
+const ORDER_ROWS: usize = 6;
+
+// ── Console output ───────────────────────────────────────────────────────────
+
+/// Sends stdout and stderr to a file until dropped, keeping the terminal for
+/// the dashboard.
+pub struct OutputRedirect {
+    #[cfg(unix)]
+    stdout: std::os::fd::OwnedFd,
+    #[cfg(unix)]
+    stderr: std::os::fd::OwnedFd,
+}
+
+#[cfg(unix)]
+impl OutputRedirect {
+    /// Appends everything printed from now on to `log_file`.
+    pub fn to_file(log_file: &Path) -> io::Result<Self> {
+        use std::{
+            io::Write,
+            os::fd::{AsFd, AsRawFd},
+        };
+
+        let log = File::options().create(true).append(true).open(log_file)?;
+        let redirect = Self {
+            stdout: io::stdout().as_fd().try_clone_to_owned()?,
+            stderr: io::stderr().as_fd().try_clone_to_owned()?,
+        };
+        io::stdout().flush()?;
+        dup2(log.as_raw_fd(), io::stdout().as_raw_fd())?;
+        dup2(log.as_raw_fd(), io::stderr().as_raw_fd())?;
+        Ok(redirect)
+    }
+
+    /// The terminal stdout pointed at before the redirect.
+    fn terminal(&self) -> io::Result<File> {
+        Ok(File::from(self.stdout.try_clone()?))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for OutputRedirect {
+    fn drop(&mut self) {
+        use std::{io::Write, os::fd::AsRawFd};
+
+        let _ = io::stdout().flush();
+        let _ = dup2(self.stdout.as_raw_fd(), io::stdout().as_raw_fd());
+        let _ = dup2(self.stderr.as_raw_fd(), io::stderr().as_raw_fd());
+    }
+}
+
+#[cfg(unix)]
+fn dup2(from: std::os::fd::RawFd, to: std::os::fd::RawFd) -> io::Result<()> {
+    // SAFETY: both descriptors are open for the duration of the call; `to`
+    // is one of the standard streams, which dup2 closes and reopens atomically.
+    if unsafe { libc::dup2(from, to) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+impl OutputRedirect {
+    pub fn to_file(_log_file: &Path) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the dashboard needs a Unix terminal",
+        ))
+    }
+
+    fn terminal(&self) -> io::Result<File> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+// ── Dashboard ────────────────────────────────────────────────────────────────
+
+/// Draws the dashboard until the bot shuts down, then restores the terminal
+/// and the console output.
+pub async fn run(
+    control: Control,
+    output: OutputRedirect,
+    cancel: CancellationToken,
+) -> io::Result<()> {
+    let handle = Handle::current();
+    tokio::task::spawn_blocking(move || {
+        let result = draw_until_cancelled(&handle, &control, &output, &cancel);
+        drop(output);
+        result
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+fn draw_until_cancelled(
+    handle: &Handle,
+    control: &Control,
+    output: &OutputRedirect,
+    cancel: &CancellationToken,
+) -> io::Result<()> {
+    let mut terminal = Terminal::new(CrosstermBackend::new(output.terminal()?))?;
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+
+    let result = event_loop(&mut terminal, handle, control, cancel);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<File>>,
+    handle: &Handle,
+    control: &Control,
+    cancel: &CancellationToken,
+) -> io::Result<()> {
+    let refresh = config::get().tui.refresh();
+    while !cancel.is_cancelled() {
+        let view = View {
+            status: control.status(),
+            positions: control.positions(),
+            alerts: handle.block_on(control.recent_alerts()).unwrap_or_default(),
+        };
+        terminal.draw(|frame| view.render(frame))?;
+
+        if !event::poll(refresh)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => control.kill(),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => control.kill(),
+            KeyCode::Char('p') if view.status.paused => control.resume(),
+            KeyCode::Char('p') => control.pause(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// One frame's worth of state.
+struct View {
+    status: Status,
+    positions: ExecutionState,
+    alerts: Vec<Opportunity>,
+}
+
+impl View {
+    fn render(&self, frame: &mut Frame) {
+        let [header, market, feeds, orders] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(10),
+            Constraint::Length(ORDER_ROWS as u16 + 3),
+        ])
+        .areas(frame.area());
+        let [quotes, spreads] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(market);
+        let [connections, alerts] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(feeds);
+
+        self.render_header(frame, header);
+        self.render_quotes(frame, quotes);
+        self.render_spreads(frame, spreads);
+        self.render_connections(frame, connections);
+        self.render_alerts(frame, alerts);
+        self.render_orders(frame, orders);
+    }
+
+    fn render_header(&self, frame: &mut Frame, area: Rect) {
+        let status = &self.status;
+        let (execution, style) = match (status.execution_enabled, status.paused) {
+            (false, _) => ("off", Style::new().fg(Color::DarkGray)),
+            (true, true) => ("paused", Style::new().fg(Color::Yellow)),
+            (true, false) => ("on", Style::new().fg(Color::Green)),
+        };
+        let uptime = status.uptime_secs;
+        let line = Line::from(vec![
+            format!(
+                "up {}h{:02}m{:02}s │ alert ≥ {}% │ trade ≥ {}% │ execution ",
+                uptime / 3600,
+                uptime / 60 % 60,
+                uptime % 60,
+                status.alert_threshold_percent,
+                status.execution_threshold_percent,
+            )
+            .into(),
+            Span::styled(execution, style.add_modifier(Modifier::BOLD)),
+        ]);
+        let block = Block::bordered()
+            .title(" arbitrage-bot ")
+            .title_bottom(" p pause/resume · q quit ");
+        frame.render_widget(Paragraph::new(line).block(block), area);
+    }
+
+    fn render_quotes(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.status.venues.iter().map(|venue| {
+            let mid = venue.mid();
+            Row::new([
+                Cell::from(venue.exchange.to_string()),
+                Cell::from(venue.symbol),
+                Cell::from(price(venue.bid)),
+                Cell::from(price(venue.ask)),
+                Cell::from(price(mid)),
+            ])
+            .style(if venue.polled {
+                // Read over REST while the WebSocket is down.
+                Style::new().fg(Color::Yellow)
+            } else {
+                Style::new()
+            })
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Length(14),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header_row(["Exchange", "Symbol", "Bid", "Ask", "Mid"]))
+        .block(Block::bordered().title(" Quotes "));
+        frame.render_widget(table, area);
+    }
+
+    /// Widest spreads first.
+    fn render_spreads(&self, frame: &mut Frame, area: Rect) {
+        let threshold = self.status.alert_threshold_percent;
+        let rows = self.status.spreads().into_iter().map(|spread| {
+            let style = if spread.percent >= threshold {
+                Style::new().fg(Color::Green).add_modifier(Modifier::BOLD)
+            } else {
+                Style::new()
+            };
+            Row::new([
+                Cell::from(spread.symbol),
+                Cell::from(format!("{}/{}", spread.exchange_a, spread.exchange_b)),
+                Cell::from(format!("{:.4}%", spread.percent)),
+            ])
+            .style(style)
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(14),
+                Constraint::Length(16),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header_row(["Symbol", "Exchanges", "Spread"]))
+        .block(Block::bordered().title(format!(" Spreads (alert ≥ {}%) ", threshold)));
+        frame.render_widget(table, area);
+    }
+
+    fn render_connections(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.status.connections.iter().map(|(url, link)| {
+            let color = match link.state {
+                "connected" => Color::Green,
+                "reconnecting" | "connect_failed" => Color::Yellow,
+                _ => Color::Red,
+            };
+            Row::new([
+                Cell::from(link.state).style(Style::new().fg(color)),
+                Cell::from(time(link.since_ms)),
+                Cell::from(url.as_str()),
+            ])
+        });
+        let title = match self.status.circuit_breakers.len() {
+            0 => " Connections ".to_string(),
+            n => format!(" Connections ({} circuit breakers tripped) ", n),
+        };
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(15),
+                Constraint::Length(8),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header_row(["State", "Since", "URL"]))
+        .block(Block::bordered().title(title));
+        frame.render_widget(table, area);
+    }
+
+    /// Newest first.
+    fn render_alerts(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.alerts.iter().rev().map(|o| {
+            Row::new([
+                Cell::from(time(o.at_ms)),
+                Cell::from(o.symbol.as_str()),
+                Cell::from(format!("{}/{}", o.exchange_a, o.exchange_b)),
+                Cell::from(format!("{:.4}%", o.diff_percent)),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Length(14),
+                Constraint::Length(16),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header_row(["Time", "Symbol", "Exchanges", "Spread"]))
+        .block(Block::bordered().title(" Recent alerts "));
+        frame.render_widget(table, area);
+    }
+
+    fn render_orders(&self, frame: &mut Frame, area: Rect) {
+        let rows = self
+            .positions
+            .orders
+            .iter()
+            .rev()
+            .take(ORDER_ROWS)
+            .map(|leg| {
+                let side = match leg.side {
+                    LegSide::Buy => Cell::from("buy").style(Style::new().fg(Color::Green)),
+                    LegSide::Sell => Cell::from("sell").style(Style::new().fg(Color::Red)),
+                };
+                let outcome = match (&leg.order_id, &leg.error) {
+                    (Some(id), _) => Cell::from(id.as_str()),
+                    (None, error) => Cell::from(error.as_deref().unwrap_or("failed"))
+                        .style(Style::new().fg(Color::Red)),
+                };
+                Row::new([
+                    Cell::from(time(leg.placed_at_ms)),
+                    Cell::from(leg.exchange.to_string()),
+                    Cell::from(leg.symbol.as_str()),
+                    side,
+                    Cell::from(format!("{} @ {}", leg.quantity, leg.price)),
+                    outcome,
+                ])
+            });
+        let exposure: Vec<String> = self
+            .positions
+            .exposure
+            .iter()
+            .filter(|(_, quantity)| !quantity.is_zero())
+            .map(|(exchange, quantity)| format!("{} {:+}", exchange, quantity))
+            .collect();
+        let title = if exposure.is_empty() {
+            " Orders (flat) ".to_string()
+        } else {
+            format!(" Orders (exposure: {}) ", exposure.join(", "))
+        };
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(14),
+                Constraint::Length(4),
+                Constraint::Length(24),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header_row([
+            "Time",
+            "Exchange",
+            "Symbol",
+            "Side",
+            "Qty @ Price",
+            "Order",
+        ]))
+        .block(Block::bordered().title(title));
+        frame.render_widget(table, area);
+    }
+}
+
+fn header_row<const N: usize>(titles: [&'static str; N]) -> Row<'static> {
+    Row::new(titles).style(Style::new().add_modifier(Modifier::BOLD))
+}
+
+fn price(value: Option<Decimal>) -> String {
+    value.map(money::to_param).unwrap_or_else(|| "—".into())
+}
+
+/// `HH:MM:SS` in local time.
+fn time(ms: i64) -> String {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map(|t| t.format("%H:%M:%S").to_string())
+        .unwrap_or_default()
+}
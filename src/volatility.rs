@@ -0,0 +1,197 @@
+//! Volatility regimes (`[volatility]`).
+//!
+//! During a flash move every exchange reprices at its own pace, and the gaps
+//! in between look like arbitrage: alerts come in storms and trades fill
+//! badly. The tracker hands every quote's mid to [`Volatility`], which keeps
+//! a price sample per exchange and symbol every `sample_secs` and measures
+//! realized volatility over the last `window_secs`: the square root of the
+//! summed squared log returns, in percent. A symbol turns volatile once that
+//! goes above `volatile_percent` on any exchange, and calms down once it's
+//! back under `calm_percent` on all of them. While it's volatile, alerts on
+//! it need `threshold_multiplier` times the usual spread and say why, and
+//! execution needs that many times its threshold.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::{
+    config::VolatilityConfig,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+    },
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Regime {
+    #[default]
+    Calm,
+    Volatile,
+}
+
+/// Price samples and regimes, shared by the tracker and execution.
+#[derive(Debug, Clone)]
+pub struct Volatility {
+    config: Arc<VolatilityConfig>,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Mids within the window, oldest first.
+    samples: HashMap<(Symbol, ExchangeId), VecDeque<(Instant, f64)>>,
+    /// Each exchange's latest realized volatility of a symbol, in percent,
+    /// and when it was measured.
+    realized: HashMap<Symbol, HashMap<ExchangeId, (Instant, f64)>>,
+    volatile: HashSet<Symbol>,
+}
+
+impl Volatility {
+    pub fn new(config: &VolatilityConfig) -> Self {
+        Self {
+            config: Arc::new(config.clone()),
+            state: Arc::default(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Takes `mid` as `exchange`'s price of `symbol`, unless it sampled one
+    /// less than `sample_secs` ago. Returns the symbol's new regime when
+    /// this changed it.
+    pub fn record(&self, exchange: ExchangeId, symbol: Symbol, mid: Decimal) -> Option<Regime> {
+        let price = mid.to_f64().filter(|p| *p > 0.0)?;
+        let now = Instant::now();
+        let window = self.config.window();
+        let mut state = self.state();
+        if !state.samples.contains_key(&(symbol, exchange)) {
+            // Symbols nobody quotes any more drop out as new ones come in.
+            state.samples.retain(|_, series| {
+                series
+                    .back()
+                    .is_some_and(|(at, _)| now.duration_since(*at) <= window)
+            });
+            let live: HashSet<_> = state.samples.keys().map(|(symbol, _)| *symbol).collect();
+            state.realized.retain(|symbol, _| live.contains(symbol));
+        }
+        let series = state.samples.entry((symbol, exchange)).or_default();
+        if series
+            .back()
+            .is_some_and(|(at, _)| now.duration_since(*at) < self.config.sample())
+        {
+            return None;
+        }
+        series.push_back((now, price));
+        while series
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > window)
+        {
+            series.pop_front();
+        }
+        let realized = realized_percent(series);
+        let exchanges = state.realized.entry(symbol).or_default();
+        exchanges.insert(exchange, (now, realized));
+        let worst_percent = self.worst(exchanges, now);
+
+        let volatile = state.volatile.contains(&symbol);
+        if !volatile && worst_percent > self.config.volatile_percent {
+            state.volatile.insert(symbol);
+            eprintln!(
+                "🌪️ {} turned volatile: {}% over {} min; thresholds ×{}",
+                symbol,
+                worst_percent,
+                self.window_mins(),
+                self.config.threshold_multiplier.normalize()
+            );
+            Some(Regime::Volatile)
+        } else if volatile && worst_percent < self.config.calm_percent {
+            state.volatile.remove(&symbol);
+            println!(
+                "✅ {} calmed down: {}% over {} min",
+                symbol,
+                worst_percent,
+                self.window_mins()
+            );
+            Some(Regime::Calm)
+        } else {
+            None
+        }
+    }
+
+    pub fn regime(&self, symbol: Symbol) -> Regime {
+        if self.state().volatile.contains(&symbol) {
+            Regime::Volatile
+        } else {
+            Regime::Calm
+        }
+    }
+
+    /// The highest realized volatility of `symbol` on any exchange, in
+    /// percent over the window.
+    pub fn realized(&self, symbol: Symbol) -> Option<Decimal> {
+        let state = self.state();
+        Some(self.worst(state.realized.get(&symbol)?, Instant::now()))
+    }
+
+    /// The highest of `exchanges`' volatilities measured within the window.
+    fn worst(&self, exchanges: &HashMap<ExchangeId, (Instant, f64)>, now: Instant) -> Decimal {
+        let worst = exchanges
+            .values()
+            .filter(|(at, _)| now.duration_since(*at) <= self.config.window())
+            .map(|(_, realized)| *realized)
+            .fold(0.0, f64::max);
+        to_decimal(worst)
+    }
+
+    /// What thresholds on `symbol` are multiplied by right now.
+    pub fn multiplier(&self, symbol: Symbol) -> Decimal {
+        match self.regime(symbol) {
+            Regime::Calm => Decimal::ONE,
+            Regime::Volatile => self.config.threshold_multiplier,
+        }
+    }
+
+    /// The regime, for alerts on a volatile `symbol`.
+    pub fn note(&self, symbol: Symbol) -> Option<String> {
+        if self.regime(symbol) == Regime::Calm {
+            return None;
+        }
+        Some(format!(
+            "volatile: {}% over {} min, thresholds ×{}",
+            self.realized(symbol).unwrap_or_default(),
+            self.window_mins(),
+            self.config.threshold_multiplier.normalize()
+        ))
+    }
+
+    fn window_mins(&self) -> Decimal {
+        (Decimal::from(self.config.window_secs) / Decimal::from(60))
+            .round_dp(1)
+            .normalize()
+    }
+}
+
+/// Square root of the summed squared log returns, in percent.
+fn realized_percent(series: &VecDeque<(Instant, f64)>) -> f64 {
+    let sum: f64 = series
+        .iter()
+        .zip(series.iter().skip(1))
+        .map(|((_, before), (_, after))| (after / before).ln().powi(2))
+        .sum();
+    sum.sqrt() * 100.0
+}
+
+fn to_decimal(percent: f64) -> Decimal {
+    Decimal::from_f64_retain(percent)
+        .unwrap_or_default()
+        .round_dp(2)
+}
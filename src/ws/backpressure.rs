@@ -0,0 +1,249 @@
+//! Explicit back-pressure policies for the channels between tasks.
+//!
+//! Quotes are only useful while fresh, so market-data queues coalesce to the
+//! latest value per symbol: a lagging consumer sees fewer, newer quotes and the
+//! producer never waits. Anything that must not be lost (raw frames feeding
+//! incremental books, outbound order requests, fills) goes through a bounded
+//! mpsc channel where the producer waits for capacity instead of dropping.
+//!
+//! Every queue registers itself so its depth can be inspected with
+//! [`queue_stats`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+use tokio::sync::{mpsc, Notify};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackpressurePolicy {
+    /// Keep only the newest item per key; producers never wait.
+    CoalesceLatest,
+    /// Producers wait for capacity; nothing is dropped.
+    Lossless,
+}
+
+/// Point-in-time view of one registered queue.
+#[derive(Debug, Clone)]
+pub struct QueueStats {
+    pub name: String,
+    pub policy: BackpressurePolicy,
+    pub depth: usize,
+    pub capacity: Option<usize>,
+    /// Highest depth observed (sampled at stats time for lossless channels).
+    pub high_water: usize,
+    /// Items replaced by a newer value before the consumer saw them.
+    pub coalesced: u64,
+}
+
+enum Depth {
+    /// Maintained by the owning queue, which holds the only other reference.
+    Counter(AtomicUsize),
+    /// Reads the depth from the channel; `None` once all senders are gone.
+    Probe(Box<dyn Fn() -> Option<usize> + Send + Sync>),
+}
+
+struct QueueMetrics {
+    name: String,
+    policy: BackpressurePolicy,
+    capacity: Option<usize>,
+    depth: Depth,
+    high_water: AtomicUsize,
+    coalesced: AtomicU64,
+}
+
+impl QueueMetrics {
+    fn register(
+        name: String,
+        policy: BackpressurePolicy,
+        capacity: Option<usize>,
+        depth: Depth,
+    ) -> Arc<Self> {
+        let metrics = Arc::new(Self {
+            name,
+            policy,
+            capacity,
+            depth,
+            high_water: AtomicUsize::new(0),
+            coalesced: AtomicU64::new(0),
+        });
+        if let Ok(mut queues) = registry().lock() {
+            queues.push(metrics.clone());
+        }
+        metrics
+    }
+
+    fn depth(&self) -> usize {
+        match &self.depth {
+            Depth::Counter(depth) => depth.load(Ordering::Relaxed),
+            Depth::Probe(probe) => probe().unwrap_or(0),
+        }
+    }
+
+    fn is_live(self: &Arc<Self>) -> bool {
+        match &self.depth {
+            Depth::Counter(_) => Arc::strong_count(self) > 1,
+            Depth::Probe(probe) => probe().is_some(),
+        }
+    }
+
+    fn snapshot(&self) -> QueueStats {
+        let depth = self.depth();
+        let high_water = self
+            .high_water
+            .fetch_max(depth, Ordering::Relaxed)
+            .max(depth);
+        QueueStats {
+            name: self.name.clone(),
+            policy: self.policy,
+            depth,
+            capacity: self.capacity,
+            high_water,
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn registry() -> &'static Mutex<Vec<Arc<QueueMetrics>>> {
+    static REGISTRY: OnceLock<Mutex<Vec<Arc<QueueMetrics>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Stats for every live queue; queues that have been dropped are pruned.
+pub fn queue_stats() -> Vec<QueueStats> {
+    let Ok(mut queues) = registry().lock() else {
+        return Vec::new();
+    };
+    queues.retain(QueueMetrics::is_live);
+    queues.iter().map(|q| q.snapshot()).collect()
+}
+
+/// Bounded mpsc channel for traffic that must never be dropped. Producers use
+/// `send().await`, so a slow consumer slows the producer down instead of
+/// losing messages. The channel's depth is reported under `name`.
+pub fn lossless_channel<T: Send + 'static>(
+    name: impl Into<String>,
+    capacity: usize,
+) -> (mpsc::Sender<T>, mpsc::Receiver<T>) {
+    let (tx, rx) = mpsc::channel(capacity);
+    let weak = tx.downgrade();
+    QueueMetrics::register(
+        name.into(),
+        BackpressurePolicy::Lossless,
+        Some(capacity),
+        Depth::Probe(Box::new(move || {
+            weak.upgrade().map(|tx| tx.max_capacity() - tx.capacity())
+        })),
+    );
+    (tx, rx)
+}
+
+struct Pending<K, V> {
+    latest: HashMap<K, V>,
+    /// Keys in first-arrival order, so no symbol starves behind a busy one.
+    order: VecDeque<K>,
+}
+
+/// Single-consumer queue holding at most one pending value per key.
+///
+/// Pushing a key that is already queued replaces its value in place and keeps
+/// its position; the replaced value is counted as coalesced.
+///
+/// ```
+/// use arbitrage_bot::ws::backpressure::CoalescingQueue;
+///
+/// let q = CoalescingQueue::new("doc");
+/// q.push("BTCUSDT", 1.0);
+/// q.push("ETHUSDT", 2.0);
+/// q.push("BTCUSDT", 3.0);
+/// assert_eq!(q.try_pop(), Some(("BTCUSDT", 3.0)));
+/// assert_eq!(q.try_pop(), Some(("ETHUSDT", 2.0)));
+/// assert!(q.is_empty());
+/// ```
+pub struct CoalescingQueue<K, V> {
+    pending: Mutex<Pending<K, V>>,
+    notify: Notify,
+    closed: AtomicBool,
+    metrics: Arc<QueueMetrics>,
+}
+
+impl<K: Eq + Hash + Clone, V> CoalescingQueue<K, V> {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            pending: Mutex::new(Pending {
+                latest: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+            notify: Notify::new(),
+            closed: AtomicBool::new(false),
+            metrics: QueueMetrics::register(
+                name.into(),
+                BackpressurePolicy::CoalesceLatest,
+                None,
+                Depth::Counter(AtomicUsize::new(0)),
+            ),
+        }
+    }
+
+    /// Never blocks: replaces any queued value for `key`.
+    pub fn push(&self, key: K, value: V) {
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        if pending.latest.insert(key.clone(), value).is_some() {
+            self.metrics.coalesced.fetch_add(1, Ordering::Relaxed);
+        } else {
+            pending.order.push_back(key);
+        }
+        self.set_depth(pending.order.len());
+        drop(pending);
+        self.notify.notify_one();
+    }
+
+    /// Waits for the next value. Returns `None` once the queue is closed and drained.
+    pub async fn pop(&self) -> Option<(K, V)> {
+        loop {
+            if let Some(item) = self.try_pop() {
+                return Some(item);
+            }
+            if self.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    pub fn try_pop(&self) -> Option<(K, V)> {
+        let mut pending = self.pending.lock().ok()?;
+        let key = pending.order.pop_front()?;
+        let value = pending.latest.remove(&key)?;
+        self.set_depth(pending.order.len());
+        Some((key, value))
+    }
+
+    /// Wakes the consumer; `pop` returns `None` after the remaining items.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.notify.notify_one();
+    }
+
+    pub fn len(&self) -> usize {
+        self.metrics.depth()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn set_depth(&self, depth: usize) {
+        if let Depth::Counter(counter) = &self.metrics.depth {
+            counter.store(depth, Ordering::Relaxed);
+        }
+        self.metrics.high_water.fetch_max(depth, Ordering::Relaxed);
+    }
+}
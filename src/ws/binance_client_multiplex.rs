@@ -1,83 +1,334 @@
-use futures_util::{SinkExt, StreamExt};
-use serde_json::from_str;
-use std::sync::Arc;
+//! Binance combined-stream client: many symbols over one connection.
+//!
+//! Uses the `/stream` endpoint, whose payloads are wrapped as
+//! `{"stream":"<name>","data":{...}}` and demuxed by stream name. Subscriptions
+//! are sent as SUBSCRIBE/UNSUBSCRIBE requests, chunked and throttled to stay
+//! under Binance's incoming-message limit, and each request is tracked by id
+//! until its ack arrives. Streams can be added or removed at runtime through
+//! the returned [`MultiplexHandle`].
+
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 use std::time::Duration;
-use tokio::{sync::Mutex, time};
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio::{
+    sync::{broadcast, mpsc},
+    time::{self, Instant},
+};
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
+    binance::ws_handler::{ConnectionEvent, KeepAlive, WsHandler},
+    config,
     constants::exchange_names,
-    models::orderbook::{BinanceOrderBookMsg, MarketTracker},
+    limits::SizeGauge,
+    ws::{
+        handlers::{self, BinanceDepthParser, FeedFrame, MessageParser},
+        quote_bus::QuoteBus,
+        redundant::Deduplicator,
+    },
 };
 
-// FOR MULTIPLE ASSETS SUBSCRIBE FOR BINANCE
-pub async fn _run_orderbook_stream_binance(symbols: Vec<&str>, tracker: Arc<Mutex<MarketTracker>>) {
-    // THIS FOR MULTIPLEX REQUESTS SINCE BINANCE HAS **5** SUBSCRIBE MESSAGE PER SECOND LIMIT
-    let url = "wss://stream.binance.com:9443/ws";
+/// Binance rejects connections that send more than 5 messages per second.
+const MAX_MESSAGES_PER_SEC: u32 = 5;
+/// Streams per SUBSCRIBE/UNSUBSCRIBE request.
+const MAX_STREAMS_PER_REQUEST: usize = 50;
+
+#[derive(Debug, Clone)]
+pub enum StreamCommand {
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+    /// Re-send SUBSCRIBE for every active stream (after a reconnect).
+    Resubscribe,
+}
+
+#[derive(Debug, Clone)]
+struct PendingRequest {
+    method: &'static str,
+    streams: Vec<String>,
+    sent_at: Instant,
+}
+
+/// Requests waiting for an ack, by id. Acks can go missing, so the map is
+/// capped by `[limits] pending_requests`, dropping the oldest request first.
+struct PendingRequests {
+    requests: std::sync::Mutex<HashMap<u64, PendingRequest>>,
+    gauge: Arc<SizeGauge>,
+}
+
+impl PendingRequests {
+    fn new(url: &str) -> Self {
+        Self {
+            requests: std::sync::Mutex::new(HashMap::new()),
+            gauge: SizeGauge::register(
+                format!("{} pending requests", url),
+                config::get().limits.pending_requests,
+            ),
+        }
+    }
+
+    fn insert(&self, id: u64, request: PendingRequest) {
+        let Ok(mut requests) = self.requests.lock() else {
+            return;
+        };
+        if self.gauge.is_full(requests.len()) {
+            let oldest = requests
+                .iter()
+                .min_by_key(|(_, r)| r.sent_at)
+                .map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                requests.remove(&oldest);
+                self.gauge.pruned(1);
+            }
+        }
+        requests.insert(id, request);
+        self.gauge.set(requests.len());
+    }
+
+    fn remove(&self, id: u64) -> Option<PendingRequest> {
+        let mut requests = self.requests.lock().ok()?;
+        let request = requests.remove(&id);
+        self.gauge.set(requests.len());
+        request
+    }
+
+    fn clear(&self) {
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.clear();
+        }
+        self.gauge.set(0);
+    }
+
+    fn len(&self) -> usize {
+        self.requests.lock().map(|r| r.len()).unwrap_or(0)
+    }
+}
+
+#[derive(Deserialize)]
+struct CombinedMsg<'a> {
+    #[serde(borrow)]
+    stream: &'a str,
+    #[serde(borrow)]
+    data: &'a RawValue,
+}
+
+#[derive(Deserialize)]
+struct RequestAck {
+    id: u64,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+}
+
+/// Handle for changing the subscription set of a running multiplexed stream.
+#[derive(Clone)]
+pub struct MultiplexHandle {
+    commands: mpsc::UnboundedSender<StreamCommand>,
+    pending: Arc<PendingRequests>,
+}
+
+impl MultiplexHandle {
+    pub fn subscribe(&self, streams: Vec<String>) {
+        let _ = self.commands.send(StreamCommand::Subscribe(streams));
+    }
 
-    loop {
-        println!("🔌 Connecting to {}", url);
+    pub fn unsubscribe(&self, streams: Vec<String>) {
+        let _ = self.commands.send(StreamCommand::Unsubscribe(streams));
+    }
 
-        let (ws_stream, _) = connect_async(url).await.expect("❌ Failed to connect");
-        println!("✅ WebSocket handshake completed for Binance");
+    /// Number of SUBSCRIBE/UNSUBSCRIBE requests still waiting for an ack.
+    pub fn pending_requests(&self) -> usize {
+        self.pending.len()
+    }
+}
 
-        let (mut write, mut read) = ws_stream.split();
+/// Partial-depth stream name for `symbol`, as set in `[feeds.binance]`.
+pub fn depth_stream(symbol: &str) -> String {
+    config::get().feeds.binance.depth_stream(symbol)
+}
 
-        // Build subscription params for all symbols
-        let params: Vec<String> = symbols
-            .iter()
-            .map(|s| format!("{}@depth", s.to_lowercase()))
-            .collect();
+/// Spawns a combined-stream connection to `url` (a `/stream` endpoint),
+/// subscribes to the depth streams of `symbols` and publishes quotes on `quotes`
+/// until `cancel` is cancelled.
+pub fn spawn_orderbook_stream_binance_multiplex(
+    symbols: &[&str],
+    quotes: QuoteBus,
+    url: &str,
+    events: broadcast::Sender<ConnectionEvent>,
+    cancel: CancellationToken,
+) -> MultiplexHandle {
+    let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
+    let pending = Arc::new(PendingRequests::new(url));
 
-        let subscribe_msg = serde_json::json!({
-            "method": "SUBSCRIBE",
-            "params": params,
-            "id": 1,
+    // Every (re)connect, and a rotation's new connection, starts with an
+    // empty subscription set on Binance's side, so ask the throttler to
+    // replay the active streams; the handler sends them on the new one.
+    let resubscribe = cmd_tx.clone();
+    let (tx, rx) = handlers::feed_channel(url);
+    let handler = WsHandler::new(url.to_string(), tx)
+        .for_exchange(exchange_names::BINANCE)
+        .with_keepalive(KeepAlive::binance())
+        .with_on_connect(move || {
+            let _ = resubscribe.send(StreamCommand::Resubscribe);
+            Vec::new()
         })
-        .to_string();
-
-        write
-            .send(Message::Text(subscribe_msg.into()))
-            .await
-            .unwrap();
-        println!("📡 Subscribed to Binance orderbooks: {:?}", symbols);
-
-        while let Some(msg_result) = read.next().await {
-            let msg = match msg_result {
-                Ok(msg) => msg,
-                Err(e) => {
-                    eprintln!("❌ WebSocket error: {:?}", e);
-                    time::sleep(Duration::from_secs(5)).await;
-                    break; // reconnect
-                }
-            };
-
-            match msg {
-                Message::Text(txt) => {
-                    if let Ok(parsed) = from_str::<BinanceOrderBookMsg>(&txt) {
-                        if let (Some(bid), Some(ask)) = (parsed.bids.get(0), parsed.asks.get(0)) {
-                            let bid_price: f64 = bid[0].parse().unwrap_or(0.0);
-                            let ask_price: f64 = ask[0].parse().unwrap_or(0.0);
-
-                            let mut tracker = tracker.lock().await;
-                            tracker.update(
-                                exchange_names::BINANCE,
-                                &parsed.symbol,
-                                bid_price,
-                                ask_price,
-                            );
-                        }
+        .with_event_channel(events)
+        .with_cancellation(cancel.clone());
+
+    // The initial streams start out active without being sent: the first
+    // connect's resubscribe is what subscribes them.
+    tokio::spawn(run_subscription_throttler(
+        cmd_rx,
+        handler.outbound(),
+        pending.clone(),
+        symbols.iter().map(|s| depth_stream(s)).collect(),
+        cancel,
+    ));
+    println!("📡 Subscribing to Binance orderbooks: {:?}", symbols);
+
+    let handle = MultiplexHandle {
+        commands: cmd_tx,
+        pending: pending.clone(),
+    };
+
+    tokio::spawn(run_demux(handler, rx, quotes, pending));
+    handle
+}
+
+/// Owns the active stream set, starting from `active`, and paces outgoing
+/// requests.
+async fn run_subscription_throttler(
+    mut commands: mpsc::UnboundedReceiver<StreamCommand>,
+    outbound: mpsc::Sender<Message>,
+    pending: Arc<PendingRequests>,
+    mut active: BTreeSet<String>,
+    cancel: CancellationToken,
+) {
+    let next_id = AtomicU64::new(1);
+    let min_gap = Duration::from_millis(1000 / MAX_MESSAGES_PER_SEC as u64);
+    let mut last_sent: Option<Instant> = None;
+
+    // The handle and the handler's on-connect hook keep `commands` open, so
+    // only cancellation ends this loop.
+    while let Some(cmd) = tokio::select! {
+        cmd = commands.recv() => cmd,
+        _ = cancel.cancelled() => None,
+    } {
+        let (method, streams): (&'static str, Vec<String>) = match cmd {
+            StreamCommand::Subscribe(streams) => {
+                let new: Vec<String> = streams
+                    .into_iter()
+                    .filter(|s| active.insert(s.clone()))
+                    .collect();
+                ("SUBSCRIBE", new)
+            }
+            StreamCommand::Unsubscribe(streams) => {
+                let removed: Vec<String> =
+                    streams.into_iter().filter(|s| active.remove(s)).collect();
+                ("UNSUBSCRIBE", removed)
+            }
+            StreamCommand::Resubscribe => {
+                // Requests sent on the old connection will never be acked.
+                pending.clear();
+                ("SUBSCRIBE", active.iter().cloned().collect())
+            }
+        };
+
+        for chunk in streams.chunks(MAX_STREAMS_PER_REQUEST) {
+            if let Some(last) = last_sent {
+                time::sleep_until(last + min_gap).await;
+            }
+
+            let id = next_id.fetch_add(1, Ordering::Relaxed);
+            let request = serde_json::json!({
+                "method": method,
+                "params": chunk,
+                "id": id,
+            })
+            .to_string();
+
+            pending.insert(
+                id,
+                PendingRequest {
+                    method,
+                    streams: chunk.to_vec(),
+                    sent_at: Instant::now(),
+                },
+            );
+            if outbound.send(Message::Text(request.into())).await.is_err() {
+                eprintln!("❌ Binance multiplex outbound channel closed");
+                return;
+            }
+            last_sent = Some(Instant::now());
+        }
+    }
+}
+
+/// Routes combined-stream payloads to the right parser and handles request acks.
+async fn run_demux(
+    handler: WsHandler,
+    mut rx: mpsc::Receiver<FeedFrame>,
+    quotes: QuoteBus,
+    pending: Arc<PendingRequests>,
+) {
+    let depth_parser = BinanceDepthParser;
+    let cancel = handler.cancellation();
+    handler.start().await;
+    let mut dedup = Deduplicator::default();
+
+    while let Some(msg_result) = tokio::select! {
+        msg = rx.recv() => msg,
+        _ = cancel.cancelled() => None,
+    } {
+        let txt = match msg_result {
+            Ok(Message::Text(txt)) => txt,
+            Ok(_) => continue,
+            Err(e) => {
+                eprintln!("❌ binance multiplex feed error: {}", e);
+                continue;
+            }
+        };
+
+        if let Ok(combined) = serde_json::from_str::<CombinedMsg>(&txt) {
+            if combined.stream.contains("@depth") {
+                if let Some(quote) = depth_parser.parse(combined.data.get()) {
+                    if dedup.is_new(quote.symbol, quote.update_id) {
+                        quotes.publish(depth_parser.exchange(), quote);
                     }
                 }
-                Message::Ping(data) => {
-                    println!("Got a ping, sending a pong!");
-                    write.send(Message::Pong(data)).await.unwrap();
-                }
-                _ => {}
             }
+            continue;
+        }
+
+        if let Ok(ack) = serde_json::from_str::<RequestAck>(&txt) {
+            let request = pending.remove(ack.id);
+            match (request, ack.error) {
+                (Some(req), None) => println!(
+                    "✅ Binance {} acked (id {}, {} streams, {:?})",
+                    req.method,
+                    ack.id,
+                    req.streams.len(),
+                    req.sent_at.elapsed()
+                ),
+                (Some(req), Some(err)) => eprintln!(
+                    "❌ Binance {} rejected (id {}): {} — streams: {:?}",
+                    req.method, ack.id, err, req.streams
+                ),
+                (None, _) => {}
+            }
+            continue;
         }
 
-        println!("Connection lost, reconnecting in 5s...");
-        time::sleep(Duration::from_secs(5)).await;
+        eprintln!("❌ Unrecognized Binance multiplex frame: {}", txt);
+    }
+
+    if cancel.is_cancelled() {
+        println!("🛑 Binance multiplex feed stopped");
+    } else {
+        println!("❌ Binance multiplex feed finished (channel closed)");
     }
 }
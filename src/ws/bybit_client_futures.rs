@@ -1,25 +1,23 @@
-use futures_util::{SinkExt, StreamExt};
-use serde_json::from_str;
-use std::{sync::Arc, time::Duration};
-use tokio::{sync::Mutex, time};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio::sync::{broadcast, mpsc};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
+    binance::ws_handler::{ConnectionEvent, KeepAlive, WsHandler},
     constants::exchange_names,
-    models::orderbook::{MarketTracker, MarketType, OrderBookMsg},
+    models::orderbook::MarketType,
+    ws::{
+        handlers::{self, BybitOrderBookParser, FeedFrame},
+        quote_bus::QuoteBus,
+        redundant,
+    },
 };
 
-pub async fn run_orderbook_stream_bybit_futures(
+fn orderbook_handler(
     symbol: &str,
-    tracker: Arc<Mutex<MarketTracker>>,
     url: &str,
-) {
-    println!("🔌 Connecting to {}", url);
-
-    let (ws_stream, _) = connect_async(url).await.expect("❌ Failed to connect");
-    println!("✅ WebSocket handshake completed for Futures");
-
-    let (mut write, mut read) = ws_stream.split();
+    events: broadcast::Sender<ConnectionEvent>,
+    cancel: CancellationToken,
+) -> (WsHandler, mpsc::Receiver<FeedFrame>) {
     // The subscription message for Bybit V5 linear futures is the same format as spot
     let subscribe_msg = serde_json::json!({
         "op": "subscribe",
@@ -27,56 +25,53 @@ pub async fn run_orderbook_stream_bybit_futures(
     })
     .to_string();
 
-    write
-        .send(Message::Text(subscribe_msg.into()))
-        .await
-        .unwrap();
-    println!("📡 Subscribed to {} futures orderbook", symbol);
+    let (tx, rx) = handlers::feed_channel(url);
+    let handler = WsHandler::new(url.to_string(), tx)
+        .with_subscriptions(vec![subscribe_msg])
+        .with_keepalive(KeepAlive::bybit())
+        .for_exchange(exchange_names::BYBIT)
+        .with_event_channel(events)
+        .with_cancellation(cancel);
+    (handler, rx)
+}
 
-    let mut ping_interval = time::interval(Duration::from_secs(20));
+pub async fn run_orderbook_stream_bybit_futures(
+    symbol: &str,
+    quotes: QuoteBus,
+    url: &str,
+    events: broadcast::Sender<ConnectionEvent>,
+    cancel: CancellationToken,
+) {
+    let (handler, rx) = orderbook_handler(symbol, url, events, cancel);
+    println!("📡 Subscribing to {} futures orderbook", symbol);
+
+    let parser = BybitOrderBookParser {
+        market_type: MarketType::Futures,
+    };
+    handlers::run_quote_feed(handler, rx, parser, quotes).await;
+}
 
-    loop {
-        tokio::select! {
-            msg = read.next() => {
-                let msg = match msg {
-                    Some(Ok(msg)) => msg,
-                    _ => {
-                        println!("Connection closed or error. Reconnecting...");
-                        break;
-                    }
-                };
-                match msg {
-                    Message::Text(txt) => {
-                        if let Ok(mut parsed) = from_str::<OrderBookMsg>(&txt) {
-                            // Manually set the market type after deserialization
-                            parsed.data.market_type = MarketType::Futures;
-                            if let (Some(bid), Some(ask)) = (parsed.data.b.first(), parsed.data.a.first()) {
-                                let bid_price: f64 = bid[0].parse().unwrap_or(0.0);
-                                let ask_price: f64 = ask[0].parse().unwrap_or(0.0);
+/// Same as [`run_orderbook_stream_bybit_futures`], but over one connection per
+/// URL in `urls`, forwarding whichever copy of each update arrives first.
+pub async fn run_orderbook_stream_bybit_futures_redundant(
+    symbol: &str,
+    quotes: QuoteBus,
+    urls: &[&str],
+    events: broadcast::Sender<ConnectionEvent>,
+    cancel: CancellationToken,
+) {
+    let legs = urls
+        .iter()
+        .map(|url| orderbook_handler(symbol, url, events.clone(), cancel.clone()))
+        .collect();
+    println!(
+        "📡 Subscribing to {} futures orderbook over {} connections",
+        symbol,
+        urls.len()
+    );
 
-                                // Update the tracker with the market type
-                                let mut tracker = tracker.lock().await;
-                                tracker.update(exchange_names::BYBIT, &parsed.data.s, bid_price, ask_price, parsed.data.market_type);
-                            }
-                        }
-                    },
-                    Message::Ping(data) => {
-                        // println!("Ping received from server, sending pong back.");
-                        if let Err(e) = write.send(Message::Pong(data)).await {
-                            eprintln!("Error sending pong: {:?}", e);
-                            break;
-                        }
-                    },
-                    _ => {}
-                }
-            },
-            _ = ping_interval.tick() => {
-                // println!("Sending client-side ping.");
-                if let Err(e) = write.send(Message::Ping(vec![].into())).await {
-                    eprintln!("Error sending ping: {:?}", e);
-                    break;
-                }
-            }
-        }
-    }
+    let parser = BybitOrderBookParser {
+        market_type: MarketType::Futures,
+    };
+    redundant::run_redundant_quote_feed(legs, parser, quotes, cancel).await;
 }
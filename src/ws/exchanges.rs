@@ -1,29 +1,59 @@
 use async_trait::async_trait;
-use std::{collections::HashMap, sync::Arc};
-use tokio::sync::mpsc::{self, Sender};
+use chrono::Utc;
+use futures_util::future::join_all;
+use rust_decimal_macros::dec;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+use tokio::sync::{
+    broadcast::error::RecvError,
+    mpsc::{self, Sender, WeakSender},
+    watch,
+};
+use tokio::task::JoinHandle;
 use tokio::time::{self, Duration};
 
-use crate::models::orderbook::{MarketTracker, MarketType, OrderBookMsg};
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum ExchangeId {
-    Binance,
-    Bybit,
-}
-
-// Implement Display for clean printing
-impl std::fmt::Display for ExchangeId {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
+pub use crate::models::ids::ExchangeId;
+use crate::{
+    calendar::{Calendar, Maintenance},
+    config::{self, FundingAction, LatencyConfig, StrategyConfig},
+    contracts::Contracts,
+    control::{ExecutionControl, Signal},
+    error::{Classify, TradingError},
+    failover::Failover,
+    fees::FeeSchedule,
+    funding::Funding,
+    latency::{Latency, Route},
+    limits::SizeGauge,
+    listing_mode::{Ineligible, ListingMode},
+    liveness::Liveness,
+    models::{
+        ids::Symbol,
+        money::Decimal,
+        orderbook::{MarketTracker, MarketType, OrderBookMsg},
+    },
+    notifications::telegram::Notification,
+    pauses::Pauses,
+    plan::{reconcile, ExecutionPlan, LegReport, LegStatus, PlanReport, PlannedLeg},
+    portfolio,
+    rebalance::{Method, Planner},
+    state::{self, ExecutionState, LegSide, OpenOrder, OrderLeg},
+    tca::{Detection, Fill, LegCost, Tca, TradeCost},
+    transfers,
+    volatility::Volatility,
+    ws::{latest::QuoteCell, quote_bus::QuoteBus},
+};
 
 #[derive(Debug, Clone)]
 pub struct PriceData {
     pub exchange: ExchangeId,
-    pub symbol: String,
-    pub bid: f64,
-    pub ask: f64,
+    pub symbol: Symbol,
+    pub bid: Decimal,
+    pub ask: Decimal,
+    /// What is offered at `bid` and `ask`, where the feed says.
+    pub bid_size: Option<Decimal>,
+    pub ask_size: Option<Decimal>,
 }
 
 #[derive(Debug, Clone)]
@@ -32,11 +62,22 @@ pub enum OrderSide {
     Sell,
 }
 
-#[derive(Debug)]
-pub enum ExchangeError {
-    ConnectionFailed(String),
-    OrderFailed(String),
-    WebSocketError(String),
+impl From<&OrderSide> for LegSide {
+    fn from(side: &OrderSide) -> Self {
+        match side {
+            OrderSide::Buy => Self::Buy,
+            OrderSide::Sell => Self::Sell,
+        }
+    }
+}
+
+impl From<LegSide> for OrderSide {
+    fn from(side: LegSide) -> Self {
+        match side {
+            LegSide::Buy => Self::Buy,
+            LegSide::Sell => Self::Sell,
+        }
+    }
 }
 
 #[async_trait]
@@ -48,22 +89,212 @@ pub trait Exchange: Send + Sync {
     async fn place_order_future(
         &self,
         side: OrderSide,
-        price: f64,
-        qty: f64,
-    ) -> Result<String, ExchangeError>;
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError>;
+
+    /// Places a futures order that may only shrink the position (Binance's
+    /// `reduceOnly`, Bybit's `reduce_only`), for unwinds and other exits.
+    /// Exchanges that can't send one refuse, rather than risk growing it.
+    async fn place_exit_future(
+        &self,
+        _side: OrderSide,
+        _price: Decimal,
+        _qty: Decimal,
+    ) -> Result<String, TradingError> {
+        Err(TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "reduce-only",
+        })
+    }
+
+    /// Places a futures market order, for what has to fill now at whatever
+    /// price (see `crate::expiry`).
+    async fn place_market_future(
+        &self,
+        _side: OrderSide,
+        _qty: Decimal,
+    ) -> Result<String, TradingError> {
+        Err(TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "market",
+        })
+    }
+
+    /// Places a futures market order that may only shrink the position,
+    /// for the rest of an exit that has to fill now.
+    async fn place_market_exit_future(
+        &self,
+        _side: OrderSide,
+        _qty: Decimal,
+    ) -> Result<String, TradingError> {
+        Err(TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "reduce-only market",
+        })
+    }
+
+    /// Places a spot order, for inventory mode; not every exchange can.
+    async fn place_order_spot(
+        &self,
+        _side: OrderSide,
+        _price: Decimal,
+        _qty: Decimal,
+    ) -> Result<String, TradingError> {
+        Err(TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "spot",
+        })
+    }
+
+    /// What new futures positions can still be margined with, in the
+    /// symbol's quote currency; routing between accounts by margin (see
+    /// `crate::accounts`) asks for it.
+    async fn available_margin(&self) -> Result<Decimal, TradingError> {
+        Err(TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "margin",
+        })
+    }
+
+    /// How order `order_id`, placed through this client, has filled so far;
+    /// trade cost analysis (see `crate::tca`) follows each order with it.
+    async fn fill(&self, _order_id: &str) -> Result<Fill, TradingError> {
+        Err(TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "fill",
+        })
+    }
+
+    /// Cancels order `order_id`, placed through this client.
+    async fn cancel_order(&self, _order_id: &str) -> Result<(), TradingError> {
+        Err(TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "cancel",
+        })
+    }
+
+    /// The ID of the latest futures order for `qty` on `side` that went
+    /// out without an answer, looked up by the client order ID it was sent
+    /// under; `None` if the exchange never got it.
+    async fn unanswered_order(
+        &self,
+        _side: OrderSide,
+        _qty: Decimal,
+    ) -> Result<Option<String>, TradingError> {
+        Err(TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "client order ID",
+        })
+    }
+
+    /// The orders for the traded symbol still on the book, e.g. left over
+    /// from a previous run (see `crate::recovery`).
+    async fn open_orders(&self) -> Result<Vec<OpenOrder>, TradingError> {
+        Err(TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "open orders",
+        })
+    }
+
+    /// The net futures position in the traded symbol; negative when short.
+    async fn position(&self) -> Result<Decimal, TradingError> {
+        Err(TradingError::Unsupported {
+            exchange: self.id().name(),
+            kind: "position",
+        })
+    }
+
+    /// The account order `order_id` went to, where there's a choice (see
+    /// `crate::accounts`).
+    fn account_of(&self, _order_id: &str) -> Option<String> {
+        None
+    }
 }
 
 pub struct ArbitrageEngine {
     exchanges: HashMap<ExchangeId, Arc<dyn Exchange>>,
     market_state: HashMap<ExchangeId, PriceData>,
+    /// Latest-quote cells of the legs fed from a `QuoteBus`; read before each
+    /// comparison so a leg is never compared at a queued, stale price.
+    legs: Vec<(ExchangeId, Arc<QuoteCell>)>,
     price_rx: mpsc::Receiver<PriceData>,
-    threshold: f64, // e.g., 0.001 for 0.1%
-    quantity: f64,
+    /// Lets `follow` add price sources without keeping `run` alive forever.
+    price_tx: WeakSender<PriceData>,
+    threshold: Decimal, // e.g., 0.001 for 0.1%
+    quantity: Decimal,
     is_executing: bool, // Simple mutex to prevent re-entrancy
+    /// Every order placed and the exposure it leaves, published for saving.
+    state: watch::Sender<ExecutionState>,
+    /// Size of the order history, capped by `[limits] order_history`.
+    orders: Arc<SizeGauge>,
+    /// Pause flag and threshold set at runtime (see `crate::control`).
+    control: Option<watch::Receiver<ExecutionControl>>,
+    /// External trade signals, vetted like the engine's own opportunities.
+    signals: Option<mpsc::Receiver<Signal>>,
+    /// Maintenance windows during which the affected legs don't trade.
+    calendar: Option<Calendar>,
+    /// The window currently holding trades off, as last announced.
+    held: Option<String>,
+    /// Set in inventory mode.
+    inventory: Option<InventoryMode>,
+    /// Plans (and maybe trades) a rebalance once inventory is skewed.
+    planner: Option<Planner>,
+    /// Whether a trade with a failed leg unwinds the legs that went through.
+    rollback: bool,
+    /// Measured latencies, taken off edges and used to pick between pairs.
+    latency: Latency,
+    latency_config: LatencyConfig,
+    /// Raises the threshold of symbols in a volatile regime.
+    volatility: Option<Volatility>,
+    /// The net edge a trade must still have when its quotes are read again
+    /// right before ordering, as a fraction.
+    recheck_floor: Decimal,
+    /// What each leg pays in fees.
+    fees: FeeSchedule,
+    /// REST probe results; impaired exchanges aren't traded on.
+    liveness: Option<Liveness>,
+    /// Feeds polled over REST; degraded exchanges aren't traded on.
+    failover: Option<Failover>,
+    /// Exchanges an order failed on fatally (see
+    /// [`TradingError::is_fatal`]); not traded on again until restart.
+    halted: HashSet<ExchangeId>,
+    /// Book and trade readings; thin venues of listing-mode symbols aren't
+    /// traded on.
+    listing_mode: Option<ListingMode>,
+    /// Where trade costs go, and how long to follow an order's fills.
+    tca: Option<(Tca, Duration)>,
+    /// The contract each exchange trades; legs on inverse ones are sized
+    /// in contracts.
+    contracts: Contracts,
+    /// Funding readings; entries that would pay too much at the next
+    /// settlement are held off or skipped.
+    funding: Option<Funding>,
+    /// The pairs of exchanges (in order) held off until a settlement, at
+    /// its time.
+    funding_holds: BTreeMap<[ExchangeId; 2], i64>,
+    /// Capital and limits per strategy (see `crate::portfolio`).
+    strategies: HashMap<String, StrategyConfig>,
+    /// Paused exchanges and symbols aren't traded on.
+    pauses: Pauses,
+}
+
+/// Inventory mode's skew limit and what it last announced.
+struct InventoryMode {
+    max_skew_percent: Decimal,
+    alerts: Option<Sender<Notification>>,
+    /// Why the inventory holds trades off, as last announced.
+    short: Option<String>,
+    /// Whether an exchange was over the skew limit after the last trade.
+    skewed: bool,
 }
 
 impl ArbitrageEngine {
-    pub fn new(exchange_list: Vec<Arc<dyn Exchange>>, threshold: f64, quantity: f64) -> Self {
+    pub fn new(
+        exchange_list: Vec<Arc<dyn Exchange>>,
+        threshold: Decimal,
+        quantity: Decimal,
+    ) -> Self {
         let (tx, rx) = mpsc::channel(100);
         let mut exchanges = HashMap::new();
 
@@ -81,22 +312,281 @@ impl ArbitrageEngine {
         Self {
             exchanges,
             market_state: HashMap::new(),
+            legs: Vec::new(),
+            price_tx: tx.downgrade(),
             price_rx: rx,
             threshold,
             quantity,
             is_executing: false,
+            state: watch::Sender::new(ExecutionState::default()),
+            orders: order_gauge(),
+            control: None,
+            signals: None,
+            calendar: None,
+            held: None,
+            inventory: None,
+            planner: None,
+            rollback: false,
+            latency: Latency::global().clone(),
+            latency_config: LatencyConfig::default(),
+            volatility: None,
+            recheck_floor: Decimal::ZERO,
+            fees: FeeSchedule::default(),
+            liveness: None,
+            failover: None,
+            halted: HashSet::new(),
+            listing_mode: None,
+            tca: None,
+            contracts: Contracts::default(),
+            funding: None,
+            funding_holds: BTreeMap::new(),
+            strategies: HashMap::new(),
+            pauses: Pauses::default(),
         }
     }
+
+    /// Engine that trades on `exchange_list` but takes its prices from `bus`
+    /// instead of opening its own feeds, those of `symbol_on(exchange)` for
+    /// each exchange. It stops once every clone of `bus` is dropped.
+    pub fn from_bus<'a>(
+        exchange_list: Vec<Arc<dyn Exchange>>,
+        bus: &QuoteBus,
+        symbol_on: impl Fn(ExchangeId) -> &'a str,
+        threshold: Decimal,
+        quantity: Decimal,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(100);
+        let symbols: HashMap<_, _> = exchange_list
+            .iter()
+            .map(|e| (e.id(), Symbol::intern(&symbol_on(e.id()).to_uppercase())))
+            .collect();
+        forward_quotes(
+            bus,
+            move |exchange, symbol| symbols.get(&exchange) == Some(&symbol),
+            tx.clone(),
+        );
+
+        Self {
+            legs: exchange_list
+                .iter()
+                .map(|e| (e.id(), bus.latest(e.id(), symbol_on(e.id()))))
+                .collect(),
+            exchanges: exchange_list.into_iter().map(|e| (e.id(), e)).collect(),
+            market_state: HashMap::new(),
+            price_tx: tx.downgrade(),
+            price_rx: rx,
+            threshold,
+            quantity,
+            is_executing: false,
+            state: watch::Sender::new(ExecutionState::default()),
+            orders: order_gauge(),
+            control: None,
+            signals: None,
+            calendar: None,
+            held: None,
+            inventory: None,
+            planner: None,
+            rollback: false,
+            latency: Latency::global().clone(),
+            latency_config: LatencyConfig::default(),
+            volatility: None,
+            recheck_floor: Decimal::ZERO,
+            fees: FeeSchedule::default(),
+            liveness: None,
+            failover: None,
+            halted: HashSet::new(),
+            listing_mode: None,
+            tca: None,
+            contracts: Contracts::default(),
+            funding: None,
+            funding_holds: BTreeMap::new(),
+            strategies: HashMap::new(),
+            pauses: Pauses::default(),
+        }
+    }
+
+    /// Records placed orders in `state`, carrying on from what it already
+    /// holds (e.g. the orders of a previous run).
+    pub fn with_state(mut self, state: watch::Sender<ExecutionState>) -> Self {
+        self.orders.set(state.borrow().orders.len());
+        self.state = state;
+        self
+    }
+
+    /// Follows `control`: no orders while paused, and its threshold replaces
+    /// the one given at construction.
+    pub fn with_control(mut self, control: watch::Receiver<ExecutionControl>) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    /// Also trades on `signals` (see [`Signal`]).
+    pub fn with_signals(mut self, signals: mpsc::Receiver<Signal>) -> Self {
+        self.signals = Some(signals);
+        self
+    }
+
+    /// Holds off trading a symbol while either leg's exchange is in a
+    /// maintenance window of `calendar`.
+    pub fn with_calendar(mut self, calendar: Calendar) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
+    /// Trades spot against the inventory in the state (see
+    /// [`ExecutionState::seed_inventory`]): a leg only sells what its
+    /// exchange holds and only buys what its quote balance pays for.
+    /// `alerts` hear when one exchange ends up with more than
+    /// `max_skew_percent` of either currency.
+    pub fn with_inventory(
+        mut self,
+        max_skew_percent: Decimal,
+        alerts: Option<Sender<Notification>>,
+    ) -> Self {
+        self.inventory = Some(InventoryMode {
+            max_skew_percent,
+            alerts,
+            short: None,
+            skewed: false,
+        });
+        self
+    }
+
+    /// Proposes a rebalancing plan whenever the inventory gets skewed, and
+    /// with `execute` set places the offsetting trades it recommends.
+    /// Needs [`with_inventory`](Self::with_inventory).
+    pub fn with_rebalancing(mut self, planner: Planner) -> Self {
+        self.planner = Some(planner);
+        self
+    }
+
+    /// Unwinds the legs of a trade that went through when another one
+    /// failed (see [`reconcile`]), instead of leaving them open.
+    pub fn with_rollback(mut self, rollback: bool) -> Self {
+        self.rollback = rollback;
+        self
+    }
+
+    /// Takes latencies from `latency` instead of [`Latency::global`] (and
+    /// records its order round trips there), weighed as `config` says.
+    pub fn with_latency(mut self, latency: Latency, config: LatencyConfig) -> Self {
+        self.latency = latency;
+        self.latency_config = config;
+        self
+    }
+
+    /// Multiplies the threshold while `volatility` has the symbol in a
+    /// volatile regime (see `crate::volatility`).
+    pub fn with_volatility(mut self, volatility: Volatility) -> Self {
+        self.volatility = Some(volatility);
+        self
+    }
+
+    /// Drops a trade unless, at the legs' quotes right before ordering, its
+    /// edge net of the latency haircut and `fee_percent` on both legs is
+    /// still above `floor_percent`.
+    pub fn with_recheck(mut self, floor_percent: Decimal, fee_percent: Decimal) -> Self {
+        self.recheck_floor = floor_percent / dec!(100);
+        self.fees = FeeSchedule::flat(fee_percent);
+        self
+    }
+
+    /// Charges the re-check each leg's fee by `fees` instead of a flat
+    /// `fee_percent` (see `crate::fees`).
+    pub fn with_fees(mut self, fees: FeeSchedule) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    /// Leaves out the exchanges `liveness` finds execution-impaired (see
+    /// `crate::liveness`); their quotes are still taken.
+    pub fn with_liveness(mut self, liveness: Liveness) -> Self {
+        self.liveness = Some(liveness);
+        self
+    }
+
+    /// Leaves out the exchanges `failover` finds degraded, with a feed
+    /// polled over REST (see `crate::failover`).
+    pub fn with_failover(mut self, failover: Failover) -> Self {
+        self.failover = Some(failover);
+        self
+    }
+
+    /// Leaves out trades on a listing-mode symbol while either leg's venue
+    /// lacks the depth or trades `listing_mode` asks for (see
+    /// `crate::listing_mode`).
+    pub fn with_listing_mode(mut self, listing_mode: ListingMode) -> Self {
+        self.listing_mode = Some(listing_mode);
+        self
+    }
+
+    /// Measures each trade's fills against the quotes it was detected at
+    /// into `tca` (see `crate::tca`), following an order for at most
+    /// `timeout`.
+    pub fn with_tca(mut self, tca: Tca, timeout: Duration) -> Self {
+        self.tca = Some((tca, timeout));
+        self
+    }
+
+    /// Sizes the legs on `contracts`' inverse exchanges in whole contracts
+    /// and cuts the other legs down to match (see `crate::contracts`).
+    pub fn with_contracts(mut self, contracts: Contracts) -> Self {
+        self.contracts = contracts;
+        self
+    }
+
+    /// Delays or skips entries that would pay more funding at the next
+    /// settlement than `funding` allows (see `crate::funding`).
+    pub fn with_funding(mut self, funding: Funding) -> Self {
+        self.funding = Some(funding);
+        self
+    }
+
+    /// Holds each strategy to its capital and limits in `strategies`, by
+    /// name (see `crate::portfolio`); strategies not in it trade freely.
+    pub fn with_strategies(mut self, strategies: HashMap<String, StrategyConfig>) -> Self {
+        self.strategies = strategies;
+        self
+    }
+
+    /// Leaves out trades on symbols and exchanges paused in `pauses` (see
+    /// `crate::pauses`).
+    pub fn with_pauses(mut self, pauses: Pauses) -> Self {
+        self.pauses = pauses;
+        self
+    }
+
+    /// Also takes `symbol` quotes from `bus`, e.g. from the tracker's feeds.
+    pub fn follow(&self, bus: &QuoteBus, symbol: &str) {
+        if let Some(price_tx) = self.price_tx.upgrade() {
+            let symbol = Symbol::intern(&symbol.to_uppercase());
+            forward_quotes(bus, move |_, quoted| quoted == symbol, price_tx);
+        }
+    }
+
     /// The main event loop for the engine
     pub async fn run(&mut self) {
         println!("🚀 Arbitrage Engine is running...");
-        while let Some(price_data) = self.price_rx.recv().await {
-            // 1. Update the market state for the exchange that sent data
+        loop {
+            let price_data = tokio::select! {
+                price_data = self.price_rx.recv() => match price_data {
+                    Some(price_data) => price_data,
+                    None => break,
+                },
+                Some(signal) = next_signal(&mut self.signals) => {
+                    self.take_signal(signal).await;
+                    continue;
+                }
+            };
+            // 1. Update the market state for the exchange that sent data, and
+            // with any prices that queued up behind it during a trade
             self.market_state
                 .insert(price_data.exchange, price_data.clone());
+            self.drain_prices();
+            self.refresh_legs();
 
-            // 2. If we're already busy placing an order, skip this tick
-            if self.is_executing {
+            // 2. If we're already busy placing an order or paused, skip this tick
+            if self.is_executing || self.paused() {
                 continue;
             }
 
@@ -105,110 +595,1175 @@ impl ArbitrageEngine {
         }
     }
 
-    /// This function replaces your `compare_and_execute`
+    /// Whether `control` holds off new orders; also picks up its threshold.
+    fn paused(&mut self) -> bool {
+        let Some(control) = &self.control else {
+            return false;
+        };
+        let control = *control.borrow();
+        self.threshold = control.threshold_percent / dec!(100);
+        control.paused
+    }
+
+    /// Trades `signal` if it passes the checks the engine's own
+    /// opportunities do, at the legs' latest prices. A signal that arrives
+    /// during a trade waits for it and is checked afterwards.
+    async fn take_signal(&mut self, signal: Signal) {
+        let source = signal.strategy();
+        println!(
+            "📡 Signal from {}: BUY {} on {} | SELL on {}",
+            source, signal.symbol, signal.buy, signal.sell
+        );
+        self.drain_prices();
+        self.refresh_legs();
+        match self.vet(&signal) {
+            Ok((symbol, detection)) => {
+                self.execute_trade(symbol, signal.buy, signal.sell, detection, source)
+                    .await
+            }
+            Err(reason) => println!("📡 Signal from {} skipped: {}", source, reason),
+        }
+    }
+
+    /// The symbol to trade `signal` on and its quotes, or why not to.
+    fn vet(&mut self, signal: &Signal) -> Result<(Symbol, Detection), String> {
+        if self.paused() {
+            return Err("execution is paused".into());
+        }
+        for exchange in [signal.buy, signal.sell] {
+            if !self.exchanges.contains_key(&exchange) {
+                return Err(format!("no order client for {}", exchange));
+            }
+        }
+        let (Some(buy), Some(sell)) = (
+            self.market_state.get(&signal.buy),
+            self.market_state.get(&signal.sell),
+        ) else {
+            return Err("no quotes for both legs yet".into());
+        };
+        if !buy.symbol.as_str().eq_ignore_ascii_case(&signal.symbol) {
+            return Err(format!(
+                "execution trades {}, not {}",
+                buy.symbol, signal.symbol
+            ));
+        }
+        if let Some(reason) = self.holding(buy.symbol, [signal.buy, signal.sell]) {
+            return Err(reason);
+        }
+        if let Some(thin) = self.thin(buy.symbol, [signal.buy, signal.sell]) {
+            return Err(thin.to_string());
+        }
+        let strategy = signal.strategy();
+        let quantity = self.quantity(strategy);
+        if let Some(short) =
+            self.shortfall(buy.symbol, [signal.buy, signal.sell], buy.ask, quantity)
+        {
+            return Err(short);
+        }
+        let edge = (sell.bid - buy.ask)
+            .checked_div(buy.ask)
+            .unwrap_or(Decimal::ZERO)
+            - self.haircut(signal.buy, signal.sell);
+        let threshold = self.threshold(buy.symbol, strategy);
+        if edge <= threshold {
+            return Err(format!(
+                "edge {:.4}% is not above {}%",
+                edge * dec!(100),
+                (threshold * dec!(100)).normalize()
+            ));
+        }
+        let detection = Detection {
+            at_ms: state::now_ms(),
+            at: time::Instant::now(),
+            buy: buy.ask,
+            sell: sell.bid,
+        };
+        Ok((buy.symbol, detection))
+    }
+
+    /// What keeps `symbol` from trading on `legs` right now, whatever the
+    /// prices: a maintenance window, a pause, or an impaired exchange.
+    fn holding(&self, symbol: Symbol, legs: [ExchangeId; 2]) -> Option<String> {
+        if let Some(window) = self.maintenance(symbol, legs) {
+            return Some(window.to_string());
+        }
+        if let Some(target) = self.pauses.holding(symbol, legs) {
+            return Some(format!("{} is paused", target));
+        }
+        self.impaired(legs)
+            .map(|exchange| format!("{} is execution-impaired", exchange))
+    }
+
+    /// The maintenance window that keeps `symbol` from trading on either of
+    /// `legs` right now.
+    fn maintenance(&self, symbol: Symbol, legs: [ExchangeId; 2]) -> Option<Maintenance> {
+        let calendar = self.calendar.as_ref()?;
+        let now = Utc::now();
+        legs.into_iter()
+            .find_map(|exchange| calendar.maintenance(exchange, symbol, now))
+    }
+
+    /// Whether a maintenance window holds off this trade; announced when a
+    /// window starts holding trades off and once trades go through again.
+    fn held(&mut self, symbol: Symbol, legs: [ExchangeId; 2]) -> bool {
+        let window = self.maintenance(symbol, legs).map(|m| m.to_string());
+        if window != self.held {
+            match &window {
+                Some(window) => println!("🛠️ Execution held off: {}", window),
+                None => println!("🛠️ Maintenance over, execution resumed"),
+            }
+            self.held = window;
+        }
+        self.held.is_some()
+    }
+
+    /// Whether funding holds off buying `symbol` on `legs[0]` and selling
+    /// on `legs[1]`: the pair waits for a settlement, or the trade would pay
+    /// too much at the next one.
+    fn pays_funding(&mut self, symbol: Symbol, legs: [ExchangeId; 2]) -> bool {
+        let Some(funding) = &self.funding else {
+            return false;
+        };
+        let now = state::now_ms();
+        let mut pair = legs;
+        pair.sort();
+        if let Some(&until) = self.funding_holds.get(&pair) {
+            if now < until {
+                return true;
+            }
+            self.funding_holds.remove(&pair);
+            println!(
+                "💸 Funding settled on {} / {}, entries resumed",
+                pair[0], pair[1]
+            );
+        }
+        let symbols = legs.map(|exchange| self.symbol_on(exchange, symbol));
+        let Some(cost) = funding.excessive(legs, symbols, now) else {
+            return false;
+        };
+        match funding.config().action {
+            FundingAction::Delay => {
+                println!(
+                    "💸 {} {} → {} held off until funding settles: {}",
+                    symbol, legs[0], legs[1], cost
+                );
+                self.funding_holds.insert(pair, cost.settles_at_ms);
+            }
+            FundingAction::Skip => {
+                println!("⏭️ {} {} → {} dropped: {}", symbol, legs[0], legs[1], cost)
+            }
+        }
+        true
+    }
+
+    /// The symbol `exchange` is quoted under, going by its latest quote;
+    /// `symbol` before it has one.
+    fn symbol_on(&self, exchange: ExchangeId, symbol: Symbol) -> Symbol {
+        self.market_state
+            .get(&exchange)
+            .map_or(symbol, |p| p.symbol)
+    }
+
+    /// What the inventory lacks to buy `quantity` on `legs[0]` at
+    /// `buy_price` and sell it on `legs[1]`, in inventory mode.
+    fn shortfall(
+        &self,
+        symbol: Symbol,
+        [buy, sell]: [ExchangeId; 2],
+        buy_price: Decimal,
+        quantity: Decimal,
+    ) -> Option<String> {
+        self.inventory.as_ref()?;
+        let state = self.state.borrow();
+        let balance = |exchange| {
+            state
+                .inventory
+                .get(&exchange)
+                .map(|i| i.balance)
+                .unwrap_or_default()
+        };
+        let (base, quote) = currencies(symbol);
+        let held = balance(sell).base;
+        if held < quantity {
+            return Some(format!(
+                "{} holds {} {}, too little to sell {}",
+                sell, held, base, quantity
+            ));
+        }
+        let held = balance(buy).quote;
+        if held < quantity * buy_price {
+            return Some(format!(
+                "{} holds {} {}, too little to buy {} {} at {}",
+                buy, held, quote, quantity, base, buy_price
+            ));
+        }
+        None
+    }
+
+    /// Whether the inventory holds off this trade; announced like
+    /// [`held`](Self::held).
+    fn starved(
+        &mut self,
+        symbol: Symbol,
+        legs: [ExchangeId; 2],
+        buy_price: Decimal,
+        quantity: Decimal,
+    ) -> bool {
+        let short = self.shortfall(symbol, legs, buy_price, quantity);
+        let Some(inventory) = &mut self.inventory else {
+            return false;
+        };
+        if short != inventory.short {
+            match &short {
+                Some(short) => println!("📦 Execution held off: {}", short),
+                None => println!("📦 Inventory covers trades again"),
+            }
+            inventory.short = short;
+        }
+        inventory.short.is_some()
+    }
+
+    /// Alerts once an exchange holds more than the skew limit of either
+    /// currency; again only after it has been back within the limit.
+    /// Returns whether it alerted.
+    fn check_skew(&mut self, symbol: Symbol) -> bool {
+        let Some(inventory) = &mut self.inventory else {
+            return false;
+        };
+        let state = self.state.borrow();
+        let (base, quote) = currencies(symbol);
+        let over = [
+            (base, state.inventory_share(|b| b.base)),
+            (quote, state.inventory_share(|b| b.quote)),
+        ]
+        .into_iter()
+        .find_map(|(asset, share)| {
+            share
+                .filter(|(_, percent)| *percent > inventory.max_skew_percent)
+                .map(|(exchange, percent)| (asset, exchange, percent))
+        });
+        let alert = over.is_some() && !inventory.skewed;
+        match &over {
+            Some((asset, exchange, percent)) if !inventory.skewed => {
+                println!(
+                    "⚖️ Inventory skewed: {} holds {:.1}% of the {}",
+                    exchange, percent, asset
+                );
+                if let Some(alerts) = &inventory.alerts {
+                    let _ = alerts.try_send(Notification::InventorySkew {
+                        exchange: exchange.to_string(),
+                        asset: asset.clone(),
+                        percent: *percent,
+                        limit: inventory.max_skew_percent,
+                    });
+                }
+            }
+            None if inventory.skewed => println!(
+                "⚖️ Inventory back within {}%",
+                inventory.max_skew_percent.normalize()
+            ),
+            _ => {}
+        }
+        inventory.skewed = over.is_some();
+        alert
+    }
+
+    /// Proposes a plan to even out the inventory and, if the planner may,
+    /// places the offsetting trades it recommends.
+    async fn rebalance(&mut self, symbol: Symbol) {
+        let Some(planner) = &self.planner else {
+            return;
+        };
+        let step = Decimal::new(1, self.quantity.scale());
+        let plan = planner.plan(
+            symbol,
+            &self.state.borrow().inventory,
+            |exchange| self.market_state.get(&exchange).map(|p| (p.bid, p.ask)),
+            step,
+        );
+        let Some(plan) = plan else {
+            return;
+        };
+        let trade = plan
+            .trade
+            .clone()
+            .filter(|_| planner.executes() && plan.recommended() == Method::Trades);
+        println!("🔁 {}", plan);
+        // Held off like any other trade.
+        let held = match &trade {
+            Some(_) if self.paused() => Some("execution is paused".to_string()),
+            Some(trade) => self.holding(symbol, [trade.buy, trade.sell]),
+            None => None,
+        };
+        if let Some(reason) = &held {
+            println!("🔁 Offsetting trades held off: {}", reason);
+        }
+        let trade = trade.filter(|_| held.is_none());
+        if let Some(alerts) = self.inventory.as_ref().and_then(|i| i.alerts.as_ref()) {
+            let _ = alerts.try_send(Notification::Rebalance {
+                plan: plan.to_string(),
+                executing: trade.is_some(),
+            });
+        }
+        let Some(trade) = trade else {
+            return;
+        };
+        let (Some(sell), Some(buy)) = (
+            self.exchanges.get(&trade.sell),
+            self.exchanges.get(&trade.buy),
+        ) else {
+            eprintln!("❌ Rebalance needs order clients on both exchanges");
+            return;
+        };
+        println!("🔁 Placing the offsetting trades");
+        let (sell_result, buy_result) = tokio::join!(
+            place(
+                &**sell,
+                true,
+                OrderSide::Sell,
+                trade.sell_price,
+                trade.quantity
+            ),
+            place(
+                &**buy,
+                true,
+                OrderSide::Buy,
+                trade.buy_price,
+                trade.quantity
+            ),
+        );
+        if let (Err(e), _) | (_, Err(e)) = (&sell_result, &buy_result) {
+            eprintln!("❌ Rebalance trade failed ({}): {}", e.severity(), e);
+        }
+        self.record(
+            symbol,
+            trade.sell,
+            OrderSide::Sell,
+            trade.sell_price,
+            trade.quantity,
+            &sell_result,
+        );
+        self.record(
+            symbol,
+            trade.buy,
+            OrderSide::Buy,
+            trade.buy_price,
+            trade.quantity,
+            &buy_result,
+        );
+        self.check_skew(symbol);
+    }
+
+    /// Applies every price already queued, so nothing is decided at prices
+    /// that were superseded while a trade held the engine.
+    fn drain_prices(&mut self) {
+        while let Ok(price_data) = self.price_rx.try_recv() {
+            self.market_state.insert(price_data.exchange, price_data);
+        }
+    }
+
+    /// Overwrites the bus-fed legs' state with their latest published quotes.
+    fn refresh_legs(&mut self) {
+        for (exchange, cell) in &self.legs {
+            if let Some(quote) = cell.load() {
+                self.market_state.insert(
+                    *exchange,
+                    PriceData {
+                        exchange: *exchange,
+                        symbol: quote.top.symbol,
+                        bid: quote.top.bid,
+                        ask: quote.top.ask,
+                        bid_size: quote.top.bid_size,
+                        ask_size: quote.top.ask_size,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Trades the best opportunity between the exchange that just updated
+    /// and the others: the fastest of those whose edge, after the latency
+    /// haircut, clears the threshold and is about as good as the best.
     async fn check_for_opportunity(&mut self, updated_exchange_id: ExchangeId) {
         // Get the snapshot for the exchange that just updated
-        // Replaces: guard!(let Some(a_snapshot) = ... else { return; });
         let Some(a_snapshot) = self.market_state.get(&updated_exchange_id) else {
             return; // No data for this exchange yet, just return.
         };
 
-        // Iterate over all *other* exchanges in our state
+        let threshold = self.threshold(a_snapshot.symbol, portfolio::SPREAD);
+        let mut routes = Vec::new();
         for (b_exchange_id, b_snapshot) in &self.market_state {
             if *b_exchange_id == updated_exchange_id {
                 continue; // Don't compare with self
             }
+            // Buy on A and sell on B, or buy on B and sell on A.
+            for (buy, sell) in [(a_snapshot, b_snapshot), (b_snapshot, a_snapshot)] {
+                if self.impaired([buy.exchange, sell.exchange]).is_some()
+                    || self
+                        .pauses
+                        .holding(buy.symbol, [buy.exchange, sell.exchange])
+                        .is_some()
+                    || self
+                        .thin(buy.symbol, [buy.exchange, sell.exchange])
+                        .is_some()
+                {
+                    continue;
+                }
+                let Some(diff) = (sell.bid - buy.ask).checked_div(buy.ask) else {
+                    continue;
+                };
+                let edge = diff - self.haircut(buy.exchange, sell.exchange);
+                if edge > threshold {
+                    routes.push(Route {
+                        buy: buy.exchange,
+                        sell: sell.exchange,
+                        edge,
+                    });
+                }
+            }
+        }
+        let similar = self.latency_config.similar_edge_percent / dec!(100);
+        let Some(route) = self.latency.fastest(&routes, similar) else {
+            return;
+        };
 
-            // --- ARBITRAGE CHECK ---
-            // Opportunity 1: Buy on A, Sell on B
-            let diff_ab = (b_snapshot.bid - a_snapshot.ask) / a_snapshot.ask;
+        let buy = &self.market_state[&route.buy];
+        let sell = &self.market_state[&route.sell];
+        let (symbol, buy_price, sell_price) = (a_snapshot.symbol, buy.ask, sell.bid);
+        println!(
+            "📈 OPPORTUNITY ({}): BUY {:.5} @ {} | SELL {:.5} @ {}",
+            symbol, route.buy, buy_price, route.sell, sell_price,
+        );
+        if routes.len() > 1 {
+            println!(
+                "⏱️ Picked {} → {} ({:.4}% net) of {} pairs",
+                route.buy,
+                route.sell,
+                route.edge * dec!(100),
+                routes.len()
+            );
+        }
+        let detection = Detection {
+            at_ms: state::now_ms(),
+            at: time::Instant::now(),
+            buy: buy_price,
+            sell: sell_price,
+        };
+        self.execute_trade(symbol, route.buy, route.sell, detection, portfolio::SPREAD)
+            .await;
+    }
 
-            if diff_ab > self.threshold {
-                println!(
-                    "📈 OPPORTUNITY ({}): BUY {:.5} @ {} | SELL {:.5} @ {}",
-                    a_snapshot.symbol,
-                    a_snapshot.exchange,
-                    a_snapshot.ask,
-                    b_snapshot.exchange,
-                    b_snapshot.bid,
-                );
+    /// The first of `exchanges` whose REST API is failing its probes, whose
+    /// quotes are polled, or that an order failed on fatally.
+    fn impaired(&self, exchanges: [ExchangeId; 2]) -> Option<ExchangeId> {
+        exchanges.into_iter().find(|&e| {
+            self.halted.contains(&e)
+                || self.liveness.as_ref().is_some_and(|l| l.is_impaired(e))
+                || self.failover.as_ref().is_some_and(|f| f.is_degraded(e))
+        })
+    }
 
-                self.execute_trade(
-                    updated_exchange_id,
-                    *b_exchange_id,
-                    a_snapshot.ask,
-                    b_snapshot.bid,
-                )
-                .await;
-                return; // Stop checking after finding one
-            }
+    /// The first of `exchanges` too thin for a listing-mode `symbol`.
+    fn thin(&self, symbol: Symbol, exchanges: [ExchangeId; 2]) -> Option<Ineligible> {
+        self.listing_mode.as_ref()?.ineligible(symbol, exchanges)
+    }
 
-            // Opportunity 2: Buy on B, Sell on A
-            let diff_ba = (a_snapshot.bid - b_snapshot.ask) / b_snapshot.ask;
+    /// The minimum edge for `strategy`'s trades on `symbol`, raised while
+    /// it's volatile.
+    fn threshold(&self, symbol: Symbol, strategy: &str) -> Decimal {
+        let threshold = self
+            .state
+            .borrow()
+            .portfolio
+            .threshold_percent(strategy, self.strategies.get(strategy))
+            .map_or(self.threshold, |percent| percent / dec!(100));
+        self.volatility
+            .as_ref()
+            .map_or(threshold, |v| threshold * v.multiplier(symbol))
+    }
 
-            if diff_ba > self.threshold {
-                println!(
-                    "📈 OPPORTUNITY ({}): BUY {:.5} @ {} | SELL {:.5} @ {}",
-                    a_snapshot.symbol,
-                    b_snapshot.exchange,
-                    b_snapshot.ask,
-                    a_snapshot.exchange,
-                    a_snapshot.bid,
-                );
+    /// What `strategy` trades per leg.
+    fn quantity(&self, strategy: &str) -> Decimal {
+        self.state
+            .borrow()
+            .portfolio
+            .quantity(strategy, self.strategies.get(strategy))
+            .unwrap_or(self.quantity)
+    }
 
-                self.execute_trade(
-                    *b_exchange_id,
-                    updated_exchange_id,
-                    b_snapshot.ask,
-                    a_snapshot.bid,
-                )
-                .await;
-                return; // Stop checking after finding one
-            }
-        }
+    /// The edge a trade across `buy` and `sell` is expected to lose while
+    /// it lands, as a fraction.
+    fn haircut(&self, buy: ExchangeId, sell: ExchangeId) -> Decimal {
+        self.latency
+            .haircut(buy, sell, self.latency_config.haircut_percent_per_sec)
     }
 
-    /// Executes the buy and sell orders concurrently
+    /// Executes the buy and sell orders concurrently, at the prices the
+    /// re-check reads, as a trade of `strategy`
     async fn execute_trade(
         &mut self,
+        symbol: Symbol,
         buy_exchange_id: ExchangeId,
         sell_exchange_id: ExchangeId,
-        buy_price: f64,
-        sell_price: f64,
+        detection: Detection,
+        strategy: &str,
     ) {
-        self.is_executing = true; // Lock the engine
-
-        let Some(buy_exchange) = self.exchanges.get(&buy_exchange_id) else {
-            eprintln!("Error: Buy exchange not found");
-            self.is_executing = false;
+        if self.held(symbol, [buy_exchange_id, sell_exchange_id])
+            || self.pays_funding(symbol, [buy_exchange_id, sell_exchange_id])
+        {
+            return;
+        }
+        let quantity = self.quantity(strategy);
+        let (buy_price, sell_price) =
+            match self.recheck(buy_exchange_id, sell_exchange_id, quantity) {
+                Ok(prices) => prices,
+                Err(reason) => {
+                    println!(
+                        "⏭️ {} {} → {} dropped before ordering: {}",
+                        symbol, buy_exchange_id, sell_exchange_id, reason
+                    );
+                    return;
+                }
+            };
+        if self.starved(
+            symbol,
+            [buy_exchange_id, sell_exchange_id],
+            buy_price,
+            quantity,
+        ) {
+            return;
+        }
+        let step = Decimal::new(1, quantity.scale());
+        let Some([buy_quantity, sell_quantity]) = self.contracts.size(
+            quantity,
+            step,
+            [(buy_exchange_id, buy_price), (sell_exchange_id, sell_price)],
+        ) else {
+            println!(
+                "⏭️ {} {} → {} dropped: {} is less than a contract",
+                symbol, buy_exchange_id, sell_exchange_id, quantity
+            );
             return;
         };
+        let notional = self
+            .contracts
+            .get(buy_exchange_id)
+            .notional(buy_quantity, buy_price)
+            + self
+                .contracts
+                .get(sell_exchange_id)
+                .notional(sell_quantity, sell_price);
+        let refusal = self.state.borrow().portfolio.refusal(
+            strategy,
+            self.strategies.get(strategy),
+            notional,
+            &self.contracts,
+        );
+        if let Some(reason) = refusal {
+            println!(
+                "⏭️ {} {} → {} dropped: strategy {} {}",
+                symbol, buy_exchange_id, sell_exchange_id, strategy, reason
+            );
+            return;
+        }
+        let buy = PlannedLeg::buy(buy_exchange_id, buy_price, buy_quantity);
+        let sell = PlannedLeg::sell(sell_exchange_id, sell_price, sell_quantity);
+        // Under a latency budget the sell waits for the buy's ack, so a
+        // late one keeps it from going out.
+        let plan = if self.latency_config.budget().is_some() {
+            ExecutionPlan::new(symbol).then([buy]).then([sell])
+        } else {
+            ExecutionPlan::arbitrage(symbol, buy, sell)
+        }
+        .with_rollback(self.rollback)
+        .with_strategy(strategy);
+        self.run_plan(plan, Some(detection)).await;
+    }
 
-        let Some(sell_exchange) = self.exchanges.get(&sell_exchange_id) else {
-            eprintln!("Error: Sell exchange not found");
+    /// Reads the legs' latest quotes again and returns the prices to trade
+    /// at, unless the edge at those prices, net of the latency haircut and
+    /// both legs' fees (or rebates), is no longer above the floor, or a leg
+    /// offers less than `quantity` at them. Only the best level is quoted,
+    /// so a leg whose feed doesn't give its size isn't checked for it.
+    fn recheck(
+        &mut self,
+        buy: ExchangeId,
+        sell: ExchangeId,
+        quantity: Decimal,
+    ) -> Result<(Decimal, Decimal), String> {
+        self.drain_prices();
+        self.refresh_legs();
+        let (Some(buy_quote), Some(sell_quote)) =
+            (self.market_state.get(&buy), self.market_state.get(&sell))
+        else {
+            return Err("a leg has no quote".into());
+        };
+        let (ask, bid) = (buy_quote.ask, sell_quote.bid);
+        let fees = (self.fees.highest(buy) + self.fees.highest(sell)) / dec!(100);
+        let edge =
+            (bid - ask).checked_div(ask).unwrap_or(Decimal::ZERO) - self.haircut(buy, sell) - fees;
+        if edge <= self.recheck_floor {
+            return Err(format!(
+                "net edge {:.4}% at {} / {} is not above {}%",
+                edge * dec!(100),
+                ask,
+                bid,
+                (self.recheck_floor * dec!(100)).normalize()
+            ));
+        }
+        for (exchange, side, price, size) in [
+            (buy, "ask", ask, buy_quote.ask_size),
+            (sell, "bid", bid, sell_quote.bid_size),
+        ] {
+            let wanted = self.contracts.get(exchange).order_quantity(quantity, price);
+            if let Some(size) = size.filter(|size| *size < wanted) {
+                return Err(format!(
+                    "{} has only {} at its {} of {}, not {}",
+                    exchange, size, side, price, wanted
+                ));
+            }
+        }
+        Ok((ask, bid))
+    }
+
+    /// Places `plan` stage by stage, unwinds it if a leg failed and it asks
+    /// for that, and keeps its report with the state.
+    pub async fn execute_plan(&mut self, plan: ExecutionPlan) {
+        self.run_plan(plan, None).await;
+    }
+
+    /// [`execute_plan`](Self::execute_plan), measuring the costs of a trade
+    /// detected as `detection` once it went through.
+    async fn run_plan(&mut self, plan: ExecutionPlan, detection: Option<Detection>) {
+        self.is_executing = true; // Lock the engine
+
+        if let Some((_, leg)) = plan
+            .legs()
+            .find(|(_, leg)| !self.exchanges.contains_key(&leg.exchange))
+        {
+            eprintln!("Error: {} exchange not found", leg.exchange);
             self.is_executing = false;
             return;
-        };
+        }
 
         println!("--- EXECUTION ---");
-        let buy_future = buy_exchange.place_order_future(OrderSide::Buy, buy_price, self.quantity);
-        let sell_future =
-            sell_exchange.place_order_future(OrderSide::Sell, sell_price, self.quantity);
-
-        match tokio::try_join!(buy_future, sell_future) {
-            Ok((buy_id, sell_id)) => {
-                println!("✅✅✅ TRADE EXECUTED ✅✅✅");
-                println!("  -> BUY ID:  {}", buy_id);
-                println!("  -> SELL ID: {}", sell_id);
-            }
-            Err(e) => {
-                eprintln!("❌❌❌ TRADE FAILED: {:?} ❌❌❌", e);
+        let id = self.state.borrow().planned + 1;
+        let mut report = PlanReport::new(id, &plan, state::now_ms());
+        // The budget runs from the signal: time spent since, e.g. on the
+        // re-check, counts against it, and may have used it up already.
+        let detected = detection.map_or_else(time::Instant::now, |d| d.at);
+        let deadline = self.latency_config.budget().map(|budget| detected + budget);
+        let mut first = 0;
+        for legs in &plan.stages {
+            let reports = first..first + legs.len();
+            first = reports.end;
+            if report.failed() {
+                for leg in &mut report.legs[reports] {
+                    leg.status = LegStatus::Skipped;
+                }
+                continue;
+            }
+            // Every leg of a stage runs to completion, so a failed leg never
+            // hides the order ID of one that went through. Past the budget
+            // they don't go out at all.
+            let (results, late) = if deadline.is_some_and(|d| time::Instant::now() >= d) {
+                let results = legs
+                    .iter()
+                    .map(|leg| Err(self.over_budget(leg.exchange)))
+                    .collect();
+                (results, Vec::new())
+            } else {
+                self.place_legs(plan.symbol, legs, deadline).await
+            };
+            for (leg, result) in report.legs[reports.clone()].iter_mut().zip(results) {
+                leg.status = match result {
+                    Ok(order_id) => LegStatus::Placed { order_id },
+                    Err(e) => {
+                        eprintln!(
+                            "❌ {} {} failed ({}): {}",
+                            leg.leg.side,
+                            leg.leg.exchange,
+                            e.severity(),
+                            e
+                        );
+                        if e.is_fatal() && self.halted.insert(leg.leg.exchange) {
+                            eprintln!(
+                                "🛑 Not trading on {} again until restarted; fix its keys \
+                                 or connection first",
+                                leg.leg.exchange
+                            );
+                        }
+                        LegStatus::Failed {
+                            error: e.to_string(),
+                        }
+                    }
+                };
+            }
+            for (index, sending) in late {
+                let leg = &mut report.legs[reports.start + index];
+                self.settle_late(plan.symbol, leg, sending).await;
+            }
+        }
+
+        if report.is_complete() {
+            println!("✅✅✅ TRADE EXECUTED ✅✅✅");
+            for leg in &report.legs {
+                if let LegStatus::Placed { order_id } = &leg.status {
+                    println!("  -> {} {}: {}", leg.leg.side, leg.leg.exchange, order_id);
+                }
+            }
+            if let Some(detection) = detection {
+                self.measure(plan.symbol, &report, detection);
+            }
+        } else {
+            eprintln!("❌❌❌ TRADE FAILED ❌❌❌");
+            if plan.rollback {
+                self.unwind(plan.symbol, &mut report).await;
+            }
+            if report.open_legs().next().is_some() {
                 eprintln!("!!! CRITICAL: Check for partial fills!");
             }
         }
         println!("-----------------");
+        self.state.send_modify(|state| {
+            state
+                .portfolio
+                .record(&report, &state.orders, &self.contracts);
+            if let Some(strategy) = &report.strategy {
+                let config = self.strategies.get(strategy);
+                if let Some(reason) = state.portfolio.enforce(strategy, config) {
+                    eprintln!("🚫 Strategy {} disabled: {}", strategy, reason);
+                }
+            }
+            state.report(report);
+            let excess = state.plans.len().saturating_sub(self.orders.cap());
+            state.plans.drain(..excess);
+        });
+        if self.check_skew(plan.symbol) {
+            self.rebalance(plan.symbol).await;
+        }
 
         time::sleep(Duration::from_secs(5)).await;
         self.is_executing = false; // Unlock the engine
     }
+
+    /// Follows the fills of `report`'s orders in the background and records
+    /// the trade's costs against `detection` once they're done.
+    fn measure(&self, symbol: Symbol, report: &PlanReport, detection: Detection) {
+        let Some((tca, timeout)) = &self.tca else {
+            return;
+        };
+        let orders: Vec<_> = report
+            .legs
+            .iter()
+            .filter_map(|leg| match &leg.status {
+                LegStatus::Placed { order_id } => Some((
+                    self.exchanges[&leg.leg.exchange].clone(),
+                    leg.leg.clone(),
+                    order_id.clone(),
+                )),
+                _ => None,
+            })
+            .collect();
+        let (tca, deadline, plan_id) = (tca.clone(), time::Instant::now() + *timeout, report.id);
+        tokio::spawn(async move {
+            let fills = join_all(
+                orders
+                    .iter()
+                    .map(|(exchange, _, order_id)| follow(&**exchange, order_id, deadline)),
+            )
+            .await;
+            let legs = orders
+                .iter()
+                .zip(&fills)
+                .map(|((_, leg, _), fill)| {
+                    LegCost::new(
+                        leg.exchange,
+                        leg.side,
+                        detection.expected(leg.side),
+                        leg.price,
+                        leg.quantity,
+                        fill.as_ref(),
+                        detection.at_ms,
+                    )
+                })
+                .collect();
+            tca.record(&TradeCost::new(
+                plan_id,
+                symbol.as_str(),
+                detection.at_ms,
+                legs,
+            ));
+        });
+    }
+
+    /// Places `legs` concurrently and records each one. A leg not
+    /// acknowledged by `deadline` fails, and comes back with the index of
+    /// its order, still being sent (see [`within`]), for
+    /// [`settle_late`](Self::settle_late) to record once it's answered.
+    ///
+    /// The budget can't hold back a leg of the same stage as a late one: it
+    /// went out alongside it, and is left to the plan's rollback. Trades
+    /// are planned a leg per stage under a budget for that reason.
+    async fn place_legs(
+        &self,
+        symbol: Symbol,
+        legs: &[PlannedLeg],
+        deadline: Option<time::Instant>,
+    ) -> (Vec<Result<String, TradingError>>, Vec<(usize, Sending)>) {
+        let spot = self.inventory.is_some();
+        let refused = self.refused(legs);
+        let answers = join_all(legs.iter().zip(refused).map(|(leg, refused)| async {
+            if let Some(error) = refused {
+                return Ok(Err(error));
+            }
+            let exchange = &self.exchanges[&leg.exchange];
+            let sent = time::Instant::now();
+            let answer = match deadline {
+                Some(deadline) => within(exchange.clone(), spot, leg.clone(), deadline).await,
+                None => Ok(send(&**exchange, spot, leg).await),
+            };
+            // Only acknowledgements time the round trip; errors may be
+            // local or timeouts.
+            if matches!(answer, Ok(Ok(_))) {
+                self.latency.record_order(leg.exchange, sent.elapsed());
+            }
+            answer
+        }))
+        .await;
+        let mut late = Vec::new();
+        let results: Vec<_> = answers
+            .into_iter()
+            .zip(legs)
+            .enumerate()
+            .map(|(index, (answer, leg))| {
+                answer.unwrap_or_else(|sending| {
+                    late.push((index, sending));
+                    Err(self.over_budget(leg.exchange))
+                })
+            })
+            .collect();
+        for (index, (leg, result)) in legs.iter().zip(&results).enumerate() {
+            if late.iter().any(|(late, _)| *late == index) {
+                continue;
+            }
+            // A venue may trade the pair under its own symbol, e.g. an
+            // inverse BTCUSD against a linear BTCUSDT.
+            self.record(
+                self.symbol_on(leg.exchange, symbol),
+                leg.exchange,
+                OrderSide::from(leg.side),
+                leg.price,
+                leg.quantity,
+                result,
+            );
+        }
+        (results, late)
+    }
+
+    /// Waits up to [`LATE_ACK_WAIT`] for the order of `leg`, failed for
+    /// missing the latency budget, and cancels it once it's acknowledged: by
+    /// then its price is likely gone. One never answered is given up on and
+    /// looked up by the exchange instead (see
+    /// [`Exchange::unanswered_order`]), and cancelled if it got there. Only
+    /// then is it recorded: as failed, unless the exchange won't cancel it.
+    /// Then it went through, is recorded like any placed order, and the leg
+    /// is left to the plan's rollback.
+    async fn settle_late(&self, symbol: Symbol, leg: &mut LegReport, mut sending: Sending) {
+        let exchange = leg.leg.exchange;
+        let sent = match time::timeout(LATE_ACK_WAIT, &mut sending).await {
+            Ok(sent) => Some(sent.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))),
+            Err(_) => {
+                sending.abort();
+                None
+            }
+        };
+        let order_id = match sent {
+            Some(Ok(order_id)) => Some(order_id),
+            Some(Err(e)) if !e.is_ambiguous() => None,
+            _ => {
+                let side = OrderSide::from(leg.leg.side);
+                match self.exchanges[&exchange]
+                    .unanswered_order(side, leg.leg.quantity)
+                    .await
+                {
+                    Ok(order_id) => order_id,
+                    Err(e) => {
+                        eprintln!(
+                            "!!! CRITICAL: {} never answered an order past the latency \
+                             budget, and it couldn't be looked up: {}",
+                            exchange, e
+                        );
+                        None
+                    }
+                }
+            }
+        };
+        let mut result = Err(self.over_budget(exchange));
+        if let Some(order_id) = order_id {
+            println!(
+                "⌛ {} order {} went in past the latency budget; cancelling it",
+                exchange, order_id
+            );
+            leg.status = match self.exchanges[&exchange].cancel_order(&order_id).await {
+                Ok(()) => LegStatus::LateCancelled { order_id },
+                Err(e) => {
+                    eprintln!(
+                        "!!! CRITICAL: {} order {} went through past the latency budget \
+                         and couldn't be cancelled: {}",
+                        exchange, order_id, e
+                    );
+                    result = Ok(order_id.clone());
+                    LegStatus::LateFilled { order_id }
+                }
+            };
+        }
+        self.record(
+            self.symbol_on(exchange, symbol),
+            exchange,
+            OrderSide::from(leg.leg.side),
+            leg.leg.price,
+            leg.leg.quantity,
+            &result,
+        );
+    }
+
+    /// The failure of a leg on `exchange` the budget ran out on.
+    fn over_budget(&self, exchange: ExchangeId) -> TradingError {
+        TradingError::OverBudget {
+            exchange: exchange.name(),
+            budget_ms: self.latency_config.budget_ms,
+        }
+    }
+
+    /// Why each of `legs` may not go out: reduce-only legs that, by the
+    /// exposure in the state and the legs before them, would grow or flip
+    /// their exchange's position. Spot has no positions, so in inventory
+    /// mode nothing is refused.
+    fn refused(&self, legs: &[PlannedLeg]) -> Vec<Option<TradingError>> {
+        let mut exposure = self.state.borrow().exposure.clone();
+        legs.iter()
+            .map(|leg| {
+                if !leg.reduce_only || self.inventory.is_some() {
+                    return None;
+                }
+                let position = exposure.entry(leg.exchange).or_default();
+                if !state::reduces(*position, leg.side, leg.quantity) {
+                    return Some(TradingError::NotReducing {
+                        exchange: leg.exchange.name(),
+                        side: leg.side.to_string(),
+                        quantity: leg.quantity.to_string(),
+                        exposure: position.to_string(),
+                    });
+                }
+                *position += match leg.side {
+                    LegSide::Buy => leg.quantity,
+                    LegSide::Sell => -leg.quantity,
+                };
+                None
+            })
+            .collect()
+    }
+
+    /// Offsets the legs of a failed plan that went through (see
+    /// [`reconcile`]), at the latest quotes.
+    async fn unwind(&self, symbol: Symbol, report: &mut PlanReport) {
+        let unwinds = reconcile(report, |exchange| {
+            self.market_state.get(&exchange).map(|p| (p.bid, p.ask))
+        });
+        if unwinds.is_empty() {
+            return;
+        }
+        println!("↩️ Unwinding {} leg(s)", unwinds.len());
+        let (indices, legs): (Vec<_>, Vec<_>) = unwinds.into_iter().unzip();
+        let (results, _) = self.place_legs(symbol, &legs, None).await;
+        for (index, result) in indices.into_iter().zip(results) {
+            let leg = &mut report.legs[index];
+            let (LegStatus::Placed { order_id } | LegStatus::LateFilled { order_id }) = &leg.status
+            else {
+                continue;
+            };
+            leg.status = match result {
+                Ok(unwind) => LegStatus::Unwound { order_id: unwind },
+                Err(e) => {
+                    eprintln!(
+                        "❌ Unwinding {} {} failed ({}): {}",
+                        leg.leg.side,
+                        leg.leg.exchange,
+                        e.severity(),
+                        e
+                    );
+                    LegStatus::UnwindFailed {
+                        order_id: order_id.clone(),
+                        error: e.to_string(),
+                    }
+                }
+            };
+        }
+    }
+
+    /// Adds a placed (or failed) order to the published state, dropping the
+    /// oldest orders beyond the cap.
+    fn record(
+        &self,
+        symbol: Symbol,
+        exchange: ExchangeId,
+        side: OrderSide,
+        price: Decimal,
+        quantity: Decimal,
+        result: &Result<String, TradingError>,
+    ) {
+        let (order_id, error) = match result {
+            Ok(id) => (Some(id.clone()), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let account = order_id
+            .as_deref()
+            .and_then(|id| self.exchanges.get(&exchange)?.account_of(id));
+        let leg = OrderLeg {
+            exchange,
+            symbol: symbol.to_string(),
+            side: LegSide::from(&side),
+            price,
+            quantity,
+            order_id,
+            account,
+            error,
+            placed_at_ms: state::now_ms(),
+        };
+        self.state.send_modify(|state| {
+            state.record(leg);
+            let excess = state.orders.len().saturating_sub(self.orders.cap());
+            if excess > 0 {
+                state.orders.drain(..excess);
+                self.orders.pruned(excess);
+            }
+            self.orders.set(state.orders.len());
+        });
+    }
+}
+
+/// Places one leg: a spot order in inventory mode, a futures order otherwise.
+async fn place(
+    exchange: &dyn Exchange,
+    spot: bool,
+    side: OrderSide,
+    price: Decimal,
+    qty: Decimal,
+) -> Result<String, TradingError> {
+    if spot {
+        exchange.place_order_spot(side, price, qty).await
+    } else {
+        exchange.place_order_future(side, price, qty).await
+    }
+}
+
+/// Places `leg`: reduce-only where it says so, unless it's spot.
+async fn send(
+    exchange: &dyn Exchange,
+    spot: bool,
+    leg: &PlannedLeg,
+) -> Result<String, TradingError> {
+    let side = OrderSide::from(leg.side);
+    if leg.reduce_only && !spot {
+        exchange
+            .place_exit_future(side, leg.price, leg.quantity)
+            .await
+    } else {
+        place(exchange, spot, side, leg.price, leg.quantity).await
+    }
+}
+
+/// How long an order that missed the latency budget is waited for before
+/// it's looked up instead.
+const LATE_ACK_WAIT: Duration = Duration::from_secs(10);
+
+/// An order still being sent.
+type Sending = JoinHandle<Result<String, TradingError>>;
+
+/// [`send`]s `leg` and waits for its answer until `deadline`. Past it the
+/// order still goes on, and comes back to be settled once it's answered.
+async fn within(
+    exchange: Arc<dyn Exchange>,
+    spot: bool,
+    leg: PlannedLeg,
+    deadline: time::Instant,
+) -> Result<Result<String, TradingError>, Sending> {
+    let mut sending = tokio::spawn(async move { send(&*exchange, spot, &leg).await });
+    tokio::select! {
+        sent = &mut sending => {
+            Ok(sent.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic())))
+        }
+        _ = time::sleep_until(deadline) => Err(sending),
+    }
+}
+
+/// How order `order_id` filled, asked once a second until it's done or
+/// `deadline` passes; `None` if the exchange can't tell.
+async fn follow(exchange: &dyn Exchange, order_id: &str, deadline: time::Instant) -> Option<Fill> {
+    let mut last = None;
+    loop {
+        match exchange.fill(order_id).await {
+            Ok(fill) if fill.done => return Some(fill),
+            Ok(fill) => last = Some(fill),
+            Err(TradingError::Unsupported { .. }) => return None,
+            Err(e) => eprintln!("⚠️ No fill for {} order {}: {}", exchange.id(), order_id, e),
+        }
+        if time::Instant::now() >= deadline {
+            return last;
+        }
+        time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// The base and quote currency of `symbol`, for messages.
+fn currencies(symbol: Symbol) -> (String, String) {
+    let (base, quote) = transfers::currencies(symbol.as_str()).unwrap_or(("base", "quote"));
+    (base.to_string(), quote.to_string())
+}
+
+/// The next signal; never resolves without a signal channel.
+async fn next_signal(signals: &mut Option<mpsc::Receiver<Signal>>) -> Option<Signal> {
+    match signals {
+        Some(signals) => signals.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn order_gauge() -> Arc<SizeGauge> {
+    SizeGauge::register("execution orders", config::get().limits.order_history)
+}
+
+/// Forwards the quotes from `bus` that an exchange's symbol is `wanted` for
+/// to an engine's price channel until either side closes.
+fn forward_quotes(
+    bus: &QuoteBus,
+    wanted: impl Fn(ExchangeId, Symbol) -> bool + Send + 'static,
+    price_tx: Sender<PriceData>,
+) {
+    let mut quotes = bus.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let quote = match quotes.recv().await {
+                Ok(quote) => quote,
+                // Stale prices are useless to the engine; just carry on.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            if !wanted(quote.exchange, quote.top.symbol) {
+                continue;
+            }
+            let data = PriceData {
+                exchange: quote.exchange,
+                symbol: quote.top.symbol,
+                bid: quote.top.bid,
+                ask: quote.top.ask,
+                bid_size: quote.top.bid_size,
+                ask_size: quote.top.ask_size,
+            };
+            if price_tx.send(data).await.is_err() {
+                break;
+            }
+        }
+    });
 }
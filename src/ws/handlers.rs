@@ -0,0 +1,271 @@
+//! Pluggable message parsers for market-data feeds.
+//!
+//! Every feed runs on a managed [`WsHandler`] connection; the only per-exchange
+//! piece is a [`MessageParser`] that turns a raw text frame into a
+//! [`TopOfBook`] quote. Frames that are not quotes (subscription acks, pongs)
+//! parse to `None`.
+//!
+//! Parsers deserialize straight from the frame into structs that borrow from
+//! it and keep only the best level of each book side; the symbol is interned
+//! (see `models::ids`), so a tick doesn't allocate at all.
+//!
+//! Raw frames travel over a lossless channel (parsing is cheap and ordering
+//! matters); parsed quotes are published on a [`QuoteBus`] for any number of
+//! consumers (see `ws::quote_bus`). A quote whose update ID is not newer than
+//! the last one published for its symbol is stale (a replay, or a late frame
+//! from before a reconnect) and is dropped.
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{IgnoredAny, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    binance::ws_handler::WsHandler,
+    error::FeedError,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::{self, Decimal},
+        orderbook::MarketType,
+    },
+    ws::{backpressure, quote_bus::QuoteBus, redundant::Deduplicator},
+};
+
+/// Capacity of the channel between a `WsHandler` and its feed consumer.
+pub const FEED_CHANNEL_CAPACITY: usize = 256;
+
+/// A frame from the handler, or the error that ended its connection.
+pub type FeedFrame = Result<Message, FeedError>;
+
+/// Raw-frame channel between a `WsHandler` and its feed consumer, reported as
+/// `"<url> frames"` in the queue stats.
+pub fn feed_channel(url: &str) -> (mpsc::Sender<FeedFrame>, mpsc::Receiver<FeedFrame>) {
+    backpressure::lossless_channel(format!("{} frames", url), FEED_CHANNEL_CAPACITY)
+}
+
+/// Best bid/ask extracted from a single exchange message.
+#[derive(Debug, Clone)]
+pub struct TopOfBook {
+    pub symbol: Symbol,
+    pub bid: Decimal,
+    pub ask: Decimal,
+    /// What is offered at `bid` and `ask`; `None` where the source doesn't
+    /// say.
+    pub bid_size: Option<Decimal>,
+    pub ask_size: Option<Decimal>,
+    pub market_type: MarketType,
+    /// Exchange sequence number, used to drop stale and duplicate updates.
+    pub update_id: Option<u64>,
+}
+
+pub trait MessageParser: Send + Sync {
+    /// Exchange the parsed quotes belong to.
+    fn exchange(&self) -> ExchangeId;
+
+    /// Parses one text frame. Returns `None` for non-quote or malformed frames.
+    fn parse(&self, txt: &str) -> Option<TopOfBook>;
+}
+
+/// The best (first) price and size of one book side, borrowed from the
+/// frame. The remaining levels are skipped without being materialized.
+#[derive(Default)]
+struct BestLevel<'a>(Option<(&'a str, &'a str)>);
+
+impl<'de: 'a, 'a> Deserialize<'de> for BestLevel<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct LevelsVisitor<'a>(PhantomData<&'a ()>);
+
+        impl<'de: 'a, 'a> Visitor<'de> for LevelsVisitor<'a> {
+            type Value = BestLevel<'a>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of [price, size] levels")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut levels: A) -> Result<Self::Value, A::Error> {
+                let best = levels.next_element::<(&'a str, &'a str)>()?;
+                while levels.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(BestLevel(best))
+            }
+        }
+
+        deserializer.deserialize_seq(LevelsVisitor(PhantomData))
+    }
+}
+
+impl BestLevel<'_> {
+    fn price(&self) -> Option<Decimal> {
+        money::parse(self.0?.0)
+    }
+
+    fn size(&self) -> Option<Decimal> {
+        money::parse(self.0?.1)
+    }
+}
+
+/// Fields of a Binance depth event the parser needs, borrowed from the frame.
+/// Everything is optional so subscription acks parse too and are told apart by
+/// the event type.
+#[derive(Deserialize)]
+struct BinanceDepthFrame<'a> {
+    #[serde(rename = "e", borrow, default)]
+    event_type: Option<&'a str>,
+    #[serde(rename = "s", borrow, default)]
+    symbol: Option<&'a str>,
+    /// Transaction time; only futures depth events carry it.
+    #[serde(rename = "T", default)]
+    transaction_time: Option<u64>,
+    #[serde(rename = "u", default)]
+    final_update_id: Option<u64>,
+    #[serde(rename = "b", borrow, default)]
+    bids: BestLevel<'a>,
+    #[serde(rename = "a", borrow, default)]
+    asks: BestLevel<'a>,
+}
+
+/// Binance `@depth` / `@depthN` streams (spot and USDⓈ-M futures).
+///
+/// ```
+/// use arbitrage_bot::ws::handlers::{BinanceDepthParser, MessageParser};
+///
+/// let quote = BinanceDepthParser
+///     .parse(r#"{"e":"depthUpdate","E":1,"T":1,"s":"BTCUSDT","U":5,"u":7,"pu":4,"b":[["100.5","1"],["100.4","2"]],"a":[["100.6","3"]]}"#)
+///     .unwrap();
+/// assert_eq!(quote.symbol, "BTCUSDT");
+/// assert_eq!(quote.bid.to_string(), "100.5");
+/// assert_eq!(quote.ask_size.unwrap().to_string(), "3");
+/// assert_eq!(quote.update_id, Some(7));
+/// assert!(BinanceDepthParser.parse(r#"{"result":null,"id":1}"#).is_none());
+/// ```
+pub struct BinanceDepthParser;
+
+impl MessageParser for BinanceDepthParser {
+    fn exchange(&self) -> ExchangeId {
+        ExchangeId::Binance
+    }
+
+    fn parse(&self, txt: &str) -> Option<TopOfBook> {
+        let frame = match serde_json::from_str::<BinanceDepthFrame>(txt) {
+            Ok(frame) => frame,
+            Err(e) => {
+                eprintln!("❌ Failed to parse Binance depth: {:?}", e);
+                return None;
+            }
+        };
+        // Subscription acks and other non-depth frames
+        if frame.event_type != Some("depthUpdate") {
+            return None;
+        }
+
+        Some(TopOfBook {
+            symbol: Symbol::intern(frame.symbol?),
+            bid: frame.bids.price()?,
+            ask: frame.asks.price()?,
+            bid_size: frame.bids.size(),
+            ask_size: frame.asks.size(),
+            market_type: if frame.transaction_time.is_some() {
+                MarketType::Futures
+            } else {
+                MarketType::Spot
+            },
+            update_id: frame.final_update_id,
+        })
+    }
+}
+
+/// A Bybit V5 orderbook frame; op responses and pongs have neither field.
+#[derive(Deserialize)]
+struct BybitFrame<'a> {
+    #[serde(borrow, default)]
+    topic: Option<&'a str>,
+    #[serde(borrow, default)]
+    data: Option<BybitBookData<'a>>,
+}
+
+#[derive(Deserialize)]
+struct BybitBookData<'a> {
+    #[serde(borrow)]
+    s: &'a str,
+    #[serde(borrow)]
+    b: BestLevel<'a>,
+    #[serde(borrow)]
+    a: BestLevel<'a>,
+    seq: u64,
+}
+
+/// Bybit V5 `orderbook.N.SYMBOL` topics (spot and linear).
+pub struct BybitOrderBookParser {
+    pub market_type: MarketType,
+}
+
+impl MessageParser for BybitOrderBookParser {
+    fn exchange(&self) -> ExchangeId {
+        ExchangeId::Bybit
+    }
+
+    fn parse(&self, txt: &str) -> Option<TopOfBook> {
+        let frame = match serde_json::from_str::<BybitFrame>(txt) {
+            Ok(frame) => frame,
+            Err(e) => {
+                eprintln!("❌ Failed to parse Bybit orderbook: {:?}", e);
+                return None;
+            }
+        };
+        if !frame.topic?.starts_with("orderbook.") {
+            return None;
+        }
+        let data = frame.data?;
+        Some(TopOfBook {
+            bid: data.b.price()?,
+            ask: data.a.price()?,
+            bid_size: data.b.size(),
+            ask_size: data.a.size(),
+            symbol: Symbol::intern(data.s),
+            market_type: self.market_type,
+            // `u` restarts at 1 on a service-side snapshot; `seq` never goes back.
+            update_id: Some(data.seq),
+        })
+    }
+}
+
+/// Starts `handler` and publishes every new parsed quote on `quotes` until
+/// the handler is cancelled or its channel closes.
+pub async fn run_quote_feed(
+    handler: WsHandler,
+    mut rx: mpsc::Receiver<FeedFrame>,
+    parser: impl MessageParser,
+    quotes: QuoteBus,
+) {
+    let cancel = handler.cancellation();
+    handler.start().await;
+    let mut dedup = Deduplicator::default();
+
+    while let Some(msg_result) = tokio::select! {
+        msg = rx.recv() => msg,
+        _ = cancel.cancelled() => None,
+    } {
+        match msg_result {
+            Ok(Message::Text(txt)) => {
+                if let Some(quote) = parser.parse(&txt) {
+                    if dedup.is_new(quote.symbol, quote.update_id) {
+                        quotes.publish(parser.exchange(), quote);
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("❌ {} feed error: {}", parser.exchange(), e);
+            }
+        }
+    }
+
+    if cancel.is_cancelled() {
+        println!("🛑 {} feed stopped", parser.exchange());
+    } else {
+        println!("❌ {} feed finished (channel closed)", parser.exchange());
+    }
+}
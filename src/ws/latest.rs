@@ -0,0 +1,66 @@
+//! Lock-free latest-quote cells.
+//!
+//! Each watched (exchange, symbol) gets a [`QuoteCell`] holding its most
+//! recent quote. The publisher swaps a new `Arc<Quote>` in and readers load
+//! the current one; neither side ever takes a lock or waits for the other,
+//! so a strategy comparing legs can't stall a feed (or be stalled by one).
+//!
+//! The key → cell index is itself copy-on-write: looking up a cell on publish
+//! is a lock-free load, and only registering a new key copies the index.
+
+use std::{collections::HashMap, sync::Arc};
+
+use arc_swap::{ArcSwap, ArcSwapOption};
+
+use crate::{
+    models::ids::{ExchangeId, Symbol},
+    ws::quote_bus::Quote,
+};
+
+/// The latest quote for one exchange and symbol.
+#[derive(Debug, Default)]
+pub struct QuoteCell(ArcSwapOption<Quote>);
+
+impl QuoteCell {
+    /// The most recent quote, or `None` if none has been published since the
+    /// cell was registered.
+    pub fn load(&self) -> Option<Arc<Quote>> {
+        self.0.load_full()
+    }
+
+    pub fn store(&self, quote: Arc<Quote>) {
+        self.0.store(Some(quote));
+    }
+}
+
+type Cells = HashMap<(ExchangeId, Symbol), Arc<QuoteCell>>;
+
+/// Cells for every registered (exchange, symbol). Cheap to clone; all clones
+/// share the same cells.
+#[derive(Clone, Default)]
+pub struct LatestQuotes {
+    cells: Arc<ArcSwap<Cells>>,
+}
+
+impl LatestQuotes {
+    /// The cell for `exchange` and `symbol`, registering it on first use.
+    pub fn cell(&self, exchange: ExchangeId, symbol: Symbol) -> Arc<QuoteCell> {
+        if let Some(cell) = self.cells.load().get(&(exchange, symbol)) {
+            return cell.clone();
+        }
+        self.cells.rcu(|cells| {
+            let mut cells = Cells::clone(cells);
+            cells.entry((exchange, symbol)).or_default();
+            cells
+        });
+        // Cells are never removed, so the one just registered is still there.
+        self.cells.load()[&(exchange, symbol)].clone()
+    }
+
+    /// Updates the cell for the quote's exchange and symbol, if registered.
+    pub fn store(&self, quote: &Arc<Quote>) {
+        if let Some(cell) = self.cells.load().get(&(quote.exchange, quote.top.symbol)) {
+            cell.store(quote.clone());
+        }
+    }
+}
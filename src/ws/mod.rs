@@ -1,5 +1,16 @@
+pub mod backpressure;
+#[cfg(feature = "binance")]
 pub mod binance_client;
-// pub mod binance_client_multiplex;
+#[cfg(feature = "binance")]
+pub mod binance_client_multiplex;
+#[cfg(feature = "bybit")]
 pub mod bybit_client_futures;
+#[cfg(feature = "bybit")]
 pub mod client;
+#[cfg(feature = "execution")]
 pub mod exchanges;
+pub mod handlers;
+pub mod latest;
+pub mod quote_bus;
+pub mod redundant;
+pub mod tap;
@@ -0,0 +1,186 @@
+//! Fan-out of parsed quotes to any number of independent consumers.
+//!
+//! Feeds publish every quote once; the tracker, strategies, recorders and
+//! dashboards subscribe on their own. Two flavours:
+//!
+//! - [`QuoteBus::subscribe`]: every quote, in order. A subscriber that falls
+//!   more than the bus capacity behind gets `RecvError::Lagged` and skips
+//!   ahead; it never slows the feeds or the other subscribers down.
+//! - [`QuoteBus::watch`]: only the latest quote of one exchange and symbol,
+//!   for consumers that sample rather than process every tick.
+//! - [`QuoteBus::latest`]: the same, as a lock-free cell (see `ws::latest`)
+//!   for readers on the hot path that need no change notification.
+//!
+//! Quotes read over REST while a feed's WebSocket is down (see
+//! `crate::failover`) go out through [`QuoteBus::publish_polled`] and are
+//! flagged [`Quote::polled`].
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use tokio::{
+    sync::{broadcast, watch},
+    time::{self, Duration, Instant},
+};
+
+use crate::{
+    models::ids::{ExchangeId, Symbol},
+    ws::{
+        handlers::TopOfBook,
+        latest::{LatestQuotes, QuoteCell},
+    },
+};
+
+/// Quotes buffered per subscriber before it starts lagging.
+pub const QUOTE_BUS_CAPACITY: usize = 1024;
+
+/// A quote and the exchange it came from.
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub exchange: ExchangeId,
+    pub top: TopOfBook,
+    /// Read over REST rather than streamed; seconds old at best.
+    pub polled: bool,
+}
+
+/// Exchange -> symbol -> latest-quote channel, for symbols someone watches.
+type Watchers = HashMap<ExchangeId, HashMap<Symbol, watch::Sender<Option<Arc<Quote>>>>>;
+
+/// Cheap to clone; all clones publish to the same subscribers.
+///
+/// ```
+/// use arbitrage_bot::{
+///     models::{ids::ExchangeId, orderbook::MarketType},
+///     ws::{handlers::TopOfBook, quote_bus::QuoteBus},
+/// };
+/// use rust_decimal_macros::dec;
+///
+/// let bus = QuoteBus::default();
+/// let mut every = bus.subscribe();
+/// let latest = bus.watch(ExchangeId::Bybit, "btcusdt");
+/// let cell = bus.latest(ExchangeId::Bybit, "BTCUSDT");
+/// bus.publish(
+///     ExchangeId::Bybit,
+///     TopOfBook {
+///         symbol: "BTCUSDT".into(),
+///         bid: dec!(100),
+///         ask: dec!(101),
+///         bid_size: None,
+///         ask_size: None,
+///         market_type: MarketType::Futures,
+///         update_id: None,
+///     },
+/// );
+/// assert_eq!(every.try_recv().unwrap().top.bid, dec!(100));
+/// assert_eq!(latest.borrow().as_ref().unwrap().top.ask, dec!(101));
+/// assert_eq!(cell.load().unwrap().top.ask, dec!(101));
+/// ```
+#[derive(Clone)]
+pub struct QuoteBus {
+    tx: broadcast::Sender<Arc<Quote>>,
+    watchers: Arc<RwLock<Watchers>>,
+    latest: LatestQuotes,
+}
+
+impl Default for QuoteBus {
+    fn default() -> Self {
+        Self::new(QUOTE_BUS_CAPACITY)
+    }
+}
+
+impl QuoteBus {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            tx: broadcast::channel(capacity).0,
+            watchers: Arc::new(RwLock::new(HashMap::new())),
+            latest: LatestQuotes::default(),
+        }
+    }
+
+    /// Never blocks; having no subscribers is fine.
+    pub fn publish(&self, exchange: ExchangeId, top: TopOfBook) {
+        self.send(Quote {
+            exchange,
+            top,
+            polled: false,
+        });
+    }
+
+    /// Publishes a quote read over REST, flagged as polled.
+    pub fn publish_polled(&self, exchange: ExchangeId, top: TopOfBook) {
+        self.send(Quote {
+            exchange,
+            top,
+            polled: true,
+        });
+    }
+
+    fn send(&self, quote: Quote) {
+        let quote = Arc::new(quote);
+        self.latest.store(&quote);
+        if let Ok(watchers) = self.watchers.read() {
+            if let Some(watcher) = watchers
+                .get(&quote.exchange)
+                .and_then(|symbols| symbols.get(&quote.top.symbol))
+            {
+                watcher.send_replace(Some(quote.clone()));
+            }
+        }
+        let _ = self.tx.send(quote);
+    }
+
+    /// Every quote published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Quote>> {
+        self.tx.subscribe()
+    }
+
+    /// Latest quote for `symbol` on `exchange`; `None` until the first one
+    /// is published after this call.
+    pub fn watch(&self, exchange: ExchangeId, symbol: &str) -> watch::Receiver<Option<Arc<Quote>>> {
+        let mut watchers = self.watchers.write().unwrap_or_else(|e| e.into_inner());
+        watchers
+            .entry(exchange)
+            .or_default()
+            .entry(Symbol::intern(&symbol.to_uppercase()))
+            .or_insert_with(|| watch::channel(None).0)
+            .subscribe()
+    }
+
+    /// Cell holding the latest quote for `symbol` on `exchange`; empty until
+    /// the first one is published after this call.
+    pub fn latest(&self, exchange: ExchangeId, symbol: &str) -> Arc<QuoteCell> {
+        self.latest
+            .cell(exchange, Symbol::intern(&symbol.to_uppercase()))
+    }
+
+    /// Waits until every (exchange, symbol) in `venues` has published a quote
+    /// after this call, or `timeout` passes. Returns the venues still silent.
+    pub async fn wait_for_quotes(
+        &self,
+        venues: &[(ExchangeId, &str)],
+        timeout: Duration,
+    ) -> Vec<(ExchangeId, Symbol)> {
+        let deadline = Instant::now() + timeout;
+        let watchers: Vec<_> = venues
+            .iter()
+            .map(|&(exchange, symbol)| {
+                let symbol = Symbol::intern(&symbol.to_uppercase());
+                (exchange, symbol, self.watch(exchange, symbol.as_str()))
+            })
+            .collect();
+        let mut silent = Vec::new();
+        for (exchange, symbol, mut latest) in watchers {
+            let arrived = time::timeout_at(deadline, latest.wait_for(Option::is_some)).await;
+            if !matches!(arrived, Ok(Ok(_))) {
+                silent.push((exchange, symbol));
+            }
+        }
+        silent
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
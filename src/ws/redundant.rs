@@ -0,0 +1,123 @@
+//! Redundant feeds: the same subscription over several parallel connections.
+//!
+//! Each leg is an independent [`WsHandler`] (optionally to a different
+//! endpoint). Quotes from all legs are merged and the first arrival of each
+//! update ID wins; later copies are dropped. A stall or reconnect on one leg
+//! is masked as long as another leg keeps delivering.
+
+use std::collections::{hash_map::Entry, HashMap};
+
+use tokio::{
+    sync::mpsc,
+    time::{self, Duration},
+};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    binance::ws_handler::WsHandler,
+    models::ids::Symbol,
+    ws::{
+        handlers::{self, FeedFrame, MessageParser},
+        quote_bus::QuoteBus,
+    },
+};
+
+/// How often the per-leg "first arrival" counts are logged.
+const LEG_STATS_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Tracks the highest update ID forwarded per symbol, across redundant legs
+/// or across reconnects of a single connection.
+#[derive(Debug, Default)]
+pub struct Deduplicator {
+    last_seen: HashMap<Symbol, u64>,
+}
+
+impl Deduplicator {
+    /// Returns `true` if this update has not been forwarded yet. Quotes without
+    /// an update ID can't be matched across legs and are always forwarded.
+    pub fn is_new(&mut self, symbol: Symbol, update_id: Option<u64>) -> bool {
+        let Some(id) = update_id else {
+            return true;
+        };
+        match self.last_seen.entry(symbol) {
+            Entry::Occupied(last) if id <= *last.get() => false,
+            Entry::Occupied(mut last) => {
+                last.insert(id);
+                true
+            }
+            Entry::Vacant(slot) => {
+                slot.insert(id);
+                true
+            }
+        }
+    }
+}
+
+/// Starts every leg and publishes de-duplicated quotes on `quotes` until
+/// `cancel` is cancelled or all legs' channels close. Legs should be built
+/// with (a child of) `cancel` so they stop with the feed.
+pub async fn run_redundant_quote_feed(
+    legs: Vec<(WsHandler, mpsc::Receiver<FeedFrame>)>,
+    parser: impl MessageParser,
+    quotes: QuoteBus,
+    cancel: CancellationToken,
+) {
+    let leg_urls: Vec<String> = legs.iter().map(|(h, _)| h.url.clone()).collect();
+
+    // Fan the legs into one channel, tagging each frame with its leg index.
+    let (merged_tx, mut merged_rx) =
+        mpsc::channel::<(usize, FeedFrame)>(handlers::FEED_CHANNEL_CAPACITY);
+    for (leg, (handler, mut rx)) in legs.into_iter().enumerate() {
+        handler.start().await;
+        let merged_tx = merged_tx.clone();
+        tokio::spawn(async move {
+            while let Some(frame) = rx.recv().await {
+                if merged_tx.send((leg, frame)).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(merged_tx);
+
+    let mut dedup = Deduplicator::default();
+    let mut first_arrivals = vec![0u64; leg_urls.len()];
+    let mut stats_interval = time::interval(LEG_STATS_INTERVAL);
+    stats_interval.tick().await; // first tick fires immediately — skip it
+
+    loop {
+        tokio::select! {
+            frame = merged_rx.recv() => {
+                let Some((leg, frame)) = frame else { break };
+                match frame {
+                    Ok(Message::Text(txt)) => {
+                        let Some(quote) = parser.parse(&txt) else { continue };
+                        if dedup.is_new(quote.symbol, quote.update_id) {
+                            first_arrivals[leg] += 1;
+                            quotes.publish(parser.exchange(), quote);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        eprintln!("❌ {} feed error on {}: {}", parser.exchange(), leg_urls[leg], e);
+                    }
+                }
+            }
+            _ = stats_interval.tick() => {
+                for (url, wins) in leg_urls.iter().zip(&first_arrivals) {
+                    println!("🏁 {} first arrivals via {}: {}", parser.exchange(), url, wins);
+                }
+            }
+            _ = cancel.cancelled() => {
+                println!("🛑 {} redundant feed stopped", parser.exchange());
+                return;
+            }
+        }
+    }
+
+    println!(
+        "❌ {} redundant feed finished (all legs closed)",
+        parser.exchange()
+    );
+}
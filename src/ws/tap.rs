@@ -0,0 +1,315 @@
+//! Raw-frame taps for debugging individual connections in production.
+//!
+//! Each `[[tap]]` in the config copies the inbound frames of one exchange
+//! (optionally only frames mentioning a symbol) to a file and/or a local debug
+//! WebSocket endpoint, as `timestamp \t url \t frame` lines. Taps never block
+//! the feed: if a sink falls behind, lines are dropped and counted.
+//!
+//! Watch a live tap with any WebSocket client, e.g. `websocat ws://127.0.0.1:9901`.
+//!
+//! [`Recordings`] are taps of every exchange's frames for one symbol, to
+//! `[recording] dir`, switched on and off at runtime (see
+//! `Control::set_recording`) so heavyweight capture only runs while a
+//! symbol is being investigated.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{BufWriter, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+use arc_swap::ArcSwap;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use futures_util::SinkExt;
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast, mpsc},
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    config::{self, TapConfig},
+    error::ControlError,
+};
+
+/// Lines buffered per sink before new ones are dropped.
+const TAP_BUFFER: usize = 4096;
+
+pub struct FrameTap {
+    /// Uppercased symbol filter.
+    symbol: Option<String>,
+    file_tx: Option<mpsc::Sender<Arc<str>>>,
+    live_tx: Option<broadcast::Sender<Arc<str>>>,
+    dropped: AtomicU64,
+}
+
+impl FrameTap {
+    /// Creates the tap and spawns its sinks. Must be called inside a Tokio runtime.
+    pub fn spawn(config: &TapConfig) -> Arc<Self> {
+        let file_tx = config.file.as_ref().map(|path| spawn_file(path));
+
+        let live_tx = config.listen.map(|addr| {
+            let (tx, _) = broadcast::channel(TAP_BUFFER);
+            tokio::spawn(serve_live(addr, tx.clone()));
+            tx
+        });
+
+        println!(
+            "🔎 Tapping {} frames{}{}{}",
+            config.exchange,
+            config
+                .symbol
+                .as_ref()
+                .map(|s| format!(" for {}", s))
+                .unwrap_or_default(),
+            config
+                .file
+                .as_ref()
+                .map(|p| format!(" → {}", p.display()))
+                .unwrap_or_default(),
+            config
+                .listen
+                .map(|a| format!(" → ws://{}", a))
+                .unwrap_or_default(),
+        );
+
+        Arc::new(Self {
+            symbol: config.symbol.as_ref().map(|s| s.to_ascii_uppercase()),
+            file_tx,
+            live_tx,
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// A tap of the frames mentioning `symbol` to the file at `path` only.
+    fn recording(symbol: &str, path: &Path) -> Arc<Self> {
+        Arc::new(Self {
+            symbol: Some(symbol.to_string()),
+            file_tx: Some(spawn_file(path)),
+            live_tx: None,
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Copies `frame` to the sinks if it passes the symbol filter.
+    pub fn record(&self, url: &str, frame: &Message) {
+        let raw: &[u8] = match frame {
+            Message::Text(txt) => txt.as_bytes(),
+            Message::Binary(bytes) => bytes,
+            _ => return,
+        };
+        if let Some(symbol) = &self.symbol {
+            let symbol = symbol.as_bytes();
+            if !raw
+                .windows(symbol.len())
+                .any(|w| w.eq_ignore_ascii_case(symbol))
+            {
+                return;
+            }
+        }
+        let payload = match frame {
+            Message::Binary(bytes) => format!("<binary base64>{}", BASE64.encode(bytes)),
+            _ => String::from_utf8_lossy(raw).into_owned(),
+        };
+
+        let line: Arc<str> = format!(
+            "{}\t{}\t{}",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            url,
+            payload
+        )
+        .into();
+        if let Some(tx) = &self.file_tx {
+            if tx.try_send(line.clone()).is_err() {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        if let Some(tx) = &self.live_tx {
+            // No receivers is fine: nobody is watching right now.
+            let _ = tx.send(line);
+        }
+    }
+
+    /// Lines dropped because the file sink fell behind.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Spawns the writer appending the lines sent to it to the file at `path`;
+/// it stops once every sender is dropped.
+fn spawn_file(path: &Path) -> mpsc::Sender<Arc<str>> {
+    let (tx, mut rx) = mpsc::channel::<Arc<str>>(TAP_BUFFER);
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let file = match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("❌ Cannot open tap file {}: {}", path.display(), e);
+                return;
+            }
+        };
+        let mut out = BufWriter::new(file);
+        while let Some(line) = rx.blocking_recv() {
+            // Flush per line so a crash loses nothing already tapped.
+            if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+                eprintln!("❌ Tap file {} write failed, stopping", path.display());
+                return;
+            }
+        }
+    });
+    tx
+}
+
+async fn serve_live(addr: SocketAddr, lines: broadcast::Sender<Arc<str>>) {
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("❌ Cannot bind tap endpoint {}: {}", addr, e);
+            return;
+        }
+    };
+    loop {
+        let Ok((stream, peer)) = listener.accept().await else {
+            continue;
+        };
+        let mut rx = lines.subscribe();
+        tokio::spawn(async move {
+            let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+                return;
+            };
+            println!("🔎 Tap client connected: {}", peer);
+            loop {
+                let line = match rx.recv().await {
+                    Ok(line) => line,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        format!("<tap client lagged, {} lines skipped>", missed).into()
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if ws.send(Message::Text(line.as_ref().into())).await.is_err() {
+                    break;
+                }
+            }
+            println!("🔎 Tap client disconnected: {}", peer);
+        });
+    }
+}
+
+/// Taps configured for `exchange` (see `constants::exchange_names`). Sinks are
+/// created on first use and shared by every connection of that exchange.
+pub fn taps_for(exchange: &str) -> Vec<Arc<FrameTap>> {
+    static TAPS: OnceLock<Vec<(String, Arc<FrameTap>)>> = OnceLock::new();
+    TAPS.get_or_init(|| {
+        config::get()
+            .taps
+            .iter()
+            .map(|cfg| (cfg.exchange.clone(), FrameTap::spawn(cfg)))
+            .collect()
+    })
+    .iter()
+    .filter(|(name, _)| name == exchange)
+    .map(|(_, tap)| tap.clone())
+    .collect()
+}
+
+/// The symbols being recorded, each to its own file; see the module docs.
+pub struct Recordings {
+    dir: PathBuf,
+    /// Read by every feed for every frame, so cheap to check while empty.
+    taps: ArcSwap<BTreeMap<String, Arc<FrameTap>>>,
+    /// Serializes starting and stopping.
+    changing: Mutex<()>,
+}
+
+impl Recordings {
+    /// Records to `dir`, which is created on the first recording.
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            taps: ArcSwap::default(),
+            changing: Mutex::new(()),
+        }
+    }
+
+    /// The recordings of `[recording] dir`, shared by every connection.
+    /// The symbols in `[recording] symbols` are started by the engine.
+    pub fn global() -> &'static Self {
+        static RECORDINGS: OnceLock<Recordings> = OnceLock::new();
+        RECORDINGS.get_or_init(|| Self::new(&config::get().recording.dir))
+    }
+
+    /// Where `symbol`'s frames go.
+    pub fn path(&self, symbol: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.tsv", symbol.to_ascii_uppercase()))
+    }
+
+    /// Starts recording `symbol`, unless it already is. Must be called
+    /// inside a Tokio runtime.
+    pub fn start(&self, symbol: &str) -> Result<(), ControlError> {
+        let symbol = checked(symbol)?;
+        let _changing = self.changing.lock().unwrap_or_else(|e| e.into_inner());
+        if self.taps.load().contains_key(&symbol) {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.dir).map_err(|source| ControlError::Recording {
+            symbol: symbol.clone(),
+            source,
+        })?;
+        let path = self.path(&symbol);
+        println!("⏺️ Recording {} frames to {}", symbol, path.display());
+        let mut taps = BTreeMap::clone(&self.taps.load());
+        taps.insert(symbol.clone(), FrameTap::recording(&symbol, &path));
+        self.taps.store(Arc::new(taps));
+        Ok(())
+    }
+
+    /// Stops recording `symbol`; what it already took is still written out.
+    pub fn stop(&self, symbol: &str) -> Result<(), ControlError> {
+        let symbol = checked(symbol)?;
+        let _changing = self.changing.lock().unwrap_or_else(|e| e.into_inner());
+        let mut taps = BTreeMap::clone(&self.taps.load());
+        if let Some(tap) = taps.remove(&symbol) {
+            println!(
+                "⏹️ Stopped recording {} ({} lines dropped)",
+                symbol,
+                tap.dropped()
+            );
+            self.taps.store(Arc::new(taps));
+        }
+        Ok(())
+    }
+
+    /// The symbols being recorded, in order.
+    pub fn symbols(&self) -> Vec<String> {
+        self.taps.load().keys().cloned().collect()
+    }
+
+    /// Copies `frame` to the recording of every symbol it mentions.
+    pub fn record(&self, url: &str, frame: &Message) {
+        let taps = self.taps.load();
+        for tap in taps.values() {
+            tap.record(url, frame);
+        }
+    }
+}
+
+/// `symbol` upper-cased, if it's fit for a file name.
+fn checked(symbol: &str) -> Result<String, ControlError> {
+    let fit = !symbol.is_empty()
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !fit {
+        return Err(ControlError::InvalidSymbol(symbol.to_string()));
+    }
+    Ok(symbol.to_ascii_uppercase())
+}
@@ -0,0 +1,172 @@
+//! Routing orders between several accounts of one exchange: round-robin,
+//! by symbol and by available margin, with reduce-only exits going to the
+//! account that holds the position.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    accounts::{Account, Accounts, REMEMBERED_ORDERS},
+    config::AccountRouting,
+    models::{ids::ExchangeId, money::Decimal},
+    ws::exchanges::{Exchange, OrderSide},
+};
+use rust_decimal_macros::dec;
+
+use support::exchange::{FakeExchange, Ledger};
+
+/// Accounts named after `specs`, with their margin and symbols; every
+/// order fills in full by the second time it's asked about.
+fn accounts(
+    routing: AccountRouting,
+    specs: &[(&'static str, Option<Decimal>, &[&str])],
+) -> (Accounts, Arc<Ledger>) {
+    let ledger = Ledger::new();
+    let accounts = specs
+        .iter()
+        .map(|(name, margin, symbols)| {
+            let mut fake = FakeExchange::new(ExchangeId::Binance, &ledger)
+                .account(name)
+                .with_fills(dec!(0));
+            if let Some(margin) = margin {
+                fake = fake.with_margin(*margin);
+            }
+            Account {
+                name: name.to_string(),
+                exchange: Arc::new(fake),
+                symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            }
+        })
+        .collect();
+    (Accounts::new("BTCUSDT", routing, accounts), ledger)
+}
+
+fn names(ledger: &Ledger) -> Vec<&'static str> {
+    ledger.accounts().into_iter().flatten().collect()
+}
+
+#[tokio::test]
+async fn round_robin_takes_turns() {
+    let (accounts, ledger) = accounts(
+        AccountRouting::RoundRobin,
+        &[("main", None, &[]), ("sub", None, &[])],
+    );
+    for _ in 0..3 {
+        accounts
+            .place_order_future(OrderSide::Buy, dec!(100), dec!(1))
+            .await
+            .unwrap();
+    }
+    assert_eq!(names(&ledger), ["main", "sub", "main"]);
+    assert_eq!(accounts.positions(), [("main", dec!(2)), ("sub", dec!(1))]);
+}
+
+#[tokio::test]
+async fn symbol_routing_prefers_the_account_listing_it() {
+    let (listed, ledger) = accounts(
+        AccountRouting::Symbol,
+        &[
+            ("rest", None, &[]),
+            ("eth", None, &["ETHUSDT"]),
+            ("btc", None, &["btcusdt"]),
+        ],
+    );
+    for _ in 0..2 {
+        listed
+            .place_order_future(OrderSide::Sell, dec!(100), dec!(1))
+            .await
+            .unwrap();
+    }
+    assert_eq!(names(&ledger), ["btc", "btc"]);
+
+    // Nobody lists it: the accounts without symbols take it.
+    let (unlisted, ledger) = accounts(
+        AccountRouting::Symbol,
+        &[("eth", None, &["ETHUSDT"]), ("rest", None, &[])],
+    );
+    unlisted
+        .place_order_future(OrderSide::Sell, dec!(100), dec!(1))
+        .await
+        .unwrap();
+    assert_eq!(names(&ledger), ["rest"]);
+}
+
+#[tokio::test]
+async fn margin_routing_picks_the_most_available() {
+    let (accounts, ledger) = accounts(
+        AccountRouting::Margin,
+        &[
+            ("small", Some(dec!(100)), &[]),
+            ("unknown", None, &[]),
+            ("large", Some(dec!(5000)), &[]),
+        ],
+    );
+    accounts
+        .place_order_future(OrderSide::Buy, dec!(100), dec!(1))
+        .await
+        .unwrap();
+    assert_eq!(names(&ledger), ["large"]);
+    // What the accounts that can tell have, together.
+    assert_eq!(accounts.available_margin().await.unwrap(), dec!(5100));
+}
+
+#[tokio::test]
+async fn exits_go_to_the_account_holding_the_position() {
+    let (accounts, ledger) = accounts(
+        AccountRouting::RoundRobin,
+        &[("main", None, &[]), ("sub", None, &[])],
+    );
+    // main buys, sub sells.
+    accounts
+        .place_order_future(OrderSide::Buy, dec!(100), dec!(1))
+        .await
+        .unwrap();
+    accounts
+        .place_order_future(OrderSide::Sell, dec!(100), dec!(2))
+        .await
+        .unwrap();
+
+    // Buying back closes sub's short, selling closes main's long, whatever
+    // round-robin's turn.
+    accounts
+        .place_exit_future(OrderSide::Buy, dec!(100), dec!(2))
+        .await
+        .unwrap();
+    accounts
+        .place_exit_future(OrderSide::Sell, dec!(100), dec!(1))
+        .await
+        .unwrap();
+    let exits: Vec<_> = ledger.orders()[2..]
+        .iter()
+        .map(|o| (o.account, o.exit))
+        .collect();
+    assert_eq!(exits, [(Some("sub"), true), (Some("main"), true)]);
+    assert_eq!(accounts.positions(), [("main", dec!(0)), ("sub", dec!(0))]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn orders_are_forgotten_once_filled_or_old() {
+    let (accounts, _) = accounts(
+        AccountRouting::RoundRobin,
+        &[("main", None, &[]), ("sub", None, &[])],
+    );
+    let place = || accounts.place_order_future(OrderSide::Buy, dec!(100), dec!(1));
+
+    let filled = place().await.unwrap();
+    assert_eq!(accounts.account_of(&filled).as_deref(), Some("main"));
+    // Half filled, it's still followed.
+    assert!(!accounts.fill(&filled).await.unwrap().done);
+    assert_eq!(accounts.account_of(&filled).as_deref(), Some("main"));
+    assert!(accounts.fill(&filled).await.unwrap().done);
+    assert_eq!(accounts.account_of(&filled), None);
+
+    // One never followed goes once enough newer ones were placed.
+    let oldest = place().await.unwrap();
+    let mut newest = String::new();
+    for _ in 0..REMEMBERED_ORDERS {
+        newest = place().await.unwrap();
+    }
+    assert_eq!(accounts.account_of(&oldest), None);
+    assert!(accounts.account_of(&newest).is_some());
+}
@@ -0,0 +1,216 @@
+//! Routing alerts to channels by symbol and spread band, from
+//! `[[notifications.route]]` through the alert gate to the workers.
+
+mod support;
+
+use arbitrage_bot::{
+    config::AlertChannel,
+    models::money::Decimal,
+    notifications::{
+        alert_gate::AlertGate,
+        router::{self, AlertRouter},
+        telegram::{AppAlert, Notification},
+    },
+};
+use rust_decimal_macros::dec;
+use tokio::sync::mpsc;
+
+use support::load_config;
+
+const ROUTES: &str = r#"
+[[notifications.route]]
+min_diff = "1"
+max_diff = "2"
+channels = ["digest"]
+
+[[notifications.route]]
+min_diff = "2"
+max_diff = "5"
+channels = ["telegram"]
+
+[[notifications.route]]
+min_diff = "5"
+channels = ["telegram", "email"]
+
+[[notifications.route]]
+symbols = ["ethusdt"]
+min_diff = "3"
+channels = ["telegram"]
+
+[notifications.email]
+url = "https://mail.example.test/send"
+from = "bot@example.test"
+to = ["me@example.test"]
+"#;
+
+fn router() -> AlertRouter {
+    AlertRouter::new(&load_config("routes", ROUTES).unwrap().notifications.routes)
+}
+
+fn alert(symbol: &str, diff: Decimal) -> AppAlert {
+    let mid = dec!(100);
+    AppAlert {
+        symbol: symbol.to_string(),
+        exchange_a: "binance".to_string(),
+        exchange_b: "bybit".to_string(),
+        mid_a: mid,
+        mid_b: mid,
+        diff_percent: diff,
+        ..AppAlert::default()
+    }
+}
+
+#[test]
+fn bands_pick_the_channels() {
+    let router = router();
+    assert_eq!(router.floor(), Some(dec!(1)));
+    assert!(router.uses_digest());
+    assert!(router.channels("BTCUSDT", dec!(0.9)).is_empty());
+    assert_eq!(
+        router.channels("BTCUSDT", dec!(1.9)),
+        [AlertChannel::Digest].into()
+    );
+    assert_eq!(
+        router.channels("BTCUSDT", dec!(4.99)),
+        [AlertChannel::Telegram].into()
+    );
+    assert_eq!(
+        router.channels("BTCUSDT", dec!(5)),
+        [AlertChannel::Telegram, AlertChannel::Email].into()
+    );
+    // ETHUSDT has its own route, which replaces the catch-all ones.
+    assert!(router.channels("ETHUSDT", dec!(2.5)).is_empty());
+    assert_eq!(
+        router.channels("ethusdt", dec!(10)),
+        [AlertChannel::Telegram].into()
+    );
+    // Without routes everything goes to Telegram, as before.
+    assert_eq!(
+        AlertRouter::default().channels("BTCUSDT", dec!(0)),
+        [AlertChannel::Telegram].into()
+    );
+}
+
+#[test]
+fn routes_are_validated() {
+    let invalid = [
+        r#"[[notifications.route]]
+min_diff = "2"
+max_diff = "1"
+channels = ["telegram"]"#,
+        r#"[[notifications.route]]
+min_diff = "1"
+channels = []"#,
+        r#"[[notifications.route]]
+min_diff = "1"
+channels = ["email"]"#,
+        r#"[[notifications.route]]
+min_diff = "1"
+channels = ["sms"]"#,
+    ];
+    for (i, toml) in invalid.iter().enumerate() {
+        assert!(
+            load_config(&format!("invalid-{}", i), toml).is_err(),
+            "{}",
+            toml
+        );
+    }
+}
+
+/// An arbitrage alert's diff and channels.
+type Sent = (Decimal, Vec<AlertChannel>);
+
+/// The arbitrage alerts and digests (as their diffs) received so far.
+fn drain(rx: &mut mpsc::Receiver<Notification>) -> (Vec<Sent>, Vec<Vec<Decimal>>) {
+    let (mut alerts, mut digests) = (Vec::new(), Vec::new());
+    while let Ok(notification) = rx.try_recv() {
+        match notification {
+            Notification::Arbitrage(alert) => {
+                alerts.push((alert.diff_percent, alert.channels.into_iter().collect()))
+            }
+            Notification::Digest(alerts) => {
+                digests.push(alerts.iter().map(|a| a.diff_percent).collect())
+            }
+            other => panic!("unexpected notification {:?}", other),
+        }
+    }
+    (alerts, digests)
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_gate_sends_each_band_to_its_channels() {
+    let (tx, mut rx) = mpsc::channel(16);
+    let mut gate = AlertGate::new(dec!(1), dec!(1), 60).with_router(router());
+    let offer = |gate: &mut AlertGate, symbol: &str, diff| {
+        gate.maybe_send(&tx, alert(symbol, diff));
+    };
+
+    offer(&mut gate, "BTCUSDT", dec!(1.2));
+    offer(&mut gate, "BTCUSDT", dec!(1.8));
+    offer(&mut gate, "BTCUSDT", dec!(1.5));
+    offer(&mut gate, "SOLUSDT", dec!(1.1));
+    // The digest doesn't use up the cooldown.
+    offer(&mut gate, "ETHUSDT", dec!(6));
+    // …which now holds back even the biggest band.
+    offer(&mut gate, "BTCUSDT", dec!(7));
+    let (alerts, digests) = drain(&mut rx);
+    assert_eq!(alerts, [(dec!(6), vec![AlertChannel::Telegram])]);
+    assert!(digests.is_empty(), "the digest waits for its flush");
+
+    gate.flush_digest(&tx);
+    let (_, digests) = drain(&mut rx);
+    assert_eq!(digests, [vec![dec!(1.8), dec!(1.1)]]);
+    gate.flush_digest(&tx);
+    assert!(rx.try_recv().is_err(), "an empty digest isn't sent");
+
+    tokio::time::advance(std::time::Duration::from_secs(60)).await;
+    offer(&mut gate, "BTCUSDT", dec!(7));
+    let (alerts, _) = drain(&mut rx);
+    assert_eq!(
+        alerts,
+        [(dec!(7), vec![AlertChannel::Telegram, AlertChannel::Email])]
+    );
+}
+
+#[tokio::test]
+async fn the_router_fans_out_to_the_workers() {
+    let (telegram_tx, mut telegram) = mpsc::channel(16);
+    let (email_tx, mut email) = mpsc::channel(16);
+    let tx = router::spawn(Some(telegram_tx), Some(email_tx)).unwrap();
+    let mut gate = AlertGate::new(dec!(1), dec!(0), 0).with_router(router());
+    for (symbol, diff) in [
+        ("BTCUSDT", dec!(3)),
+        ("XRPUSDT", dec!(8)),
+        ("SOLUSDT", dec!(1)),
+    ] {
+        gate.maybe_send(&tx, alert(symbol, diff));
+    }
+    gate.flush_digest(&tx);
+    drop(tx);
+    // The router stops once its sender is gone, dropping the workers' ones.
+    let (mut to_telegram, mut to_email) = (Vec::new(), Vec::new());
+    while let Some(notification) = telegram.recv().await {
+        to_telegram.push(notification);
+    }
+    while let Some(notification) = email.recv().await {
+        to_email.push(notification);
+    }
+
+    let symbols = |notifications: &[Notification]| -> Vec<String> {
+        notifications
+            .iter()
+            .map(|n| match n {
+                Notification::Arbitrage(alert) => alert.symbol.clone(),
+                Notification::Digest(alerts) => format!("digest of {}", alerts.len()),
+                other => panic!("unexpected notification {:?}", other),
+            })
+            .collect()
+    };
+    assert_eq!(symbols(&to_telegram), ["BTCUSDT", "XRPUSDT", "digest of 1"]);
+    assert_eq!(symbols(&to_email), ["XRPUSDT", "digest of 1"]);
+    // Without an email worker, Telegram gets everything directly.
+    let (telegram_tx, _telegram) = mpsc::channel(1);
+    assert!(router::spawn(Some(telegram_tx.clone()), None)
+        .unwrap()
+        .same_channel(&telegram_tx));
+}
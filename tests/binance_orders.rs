@@ -0,0 +1,401 @@
+//! The Binance futures order client against the mock exchange, with
+//! `wss://ws-fapi.binance.com` rewritten to it, and its retries: found by
+//! client order ID rather than placed twice. `https://fapi.binance.com`
+//! answers every request with `binance_user_trades.json`.
+
+mod support;
+
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::OnceLock,
+    time::Duration,
+};
+
+use arbitrage_bot::{
+    binance::{
+        api::BinanceTradingClient,
+        auth::KeyVars,
+        binance_exchange::BinanceExchange,
+        order::{create_limit_order, BinanceOrderSide, TimeInForce},
+    },
+    config::{FxConfig, FxSource},
+    error::TradingError,
+    fx::Fx,
+    models::ids::ExchangeId,
+    retry::RetryPolicy,
+    ws::exchanges::{Exchange, OrderSide},
+};
+use rust_decimal_macros::dec;
+use serde_json::Value;
+use tokio::sync::{Mutex, MutexGuard};
+
+use support::{Fault, Flavor, MockExchange};
+
+/// The one mock all tests share, since the endpoint rewrite is global.
+/// Holding the guard keeps injected faults away from the other tests.
+async fn exchange() -> MutexGuard<'static, MockExchange> {
+    static EXCHANGE: OnceLock<Mutex<MockExchange>> = OnceLock::new();
+    let exchange = EXCHANGE.get_or_init(|| {
+        let mock = MockExchange::start(Flavor::Binance);
+        support::init_config(&[
+            ("wss://ws-fapi.binance.com", mock.url()),
+            ("https://fapi.binance.com", serve_trades()),
+        ]);
+        Mutex::new(mock)
+    });
+    exchange.lock().await
+}
+
+/// Answers every HTTP request with the trades in `binance_user_trades.json`,
+/// on a thread of its own so it outlives each test's runtime.
+fn serve_trades() -> String {
+    const TRADES: &str = include_str!("../fixtures/binance_user_trades.json");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for mut socket in listener.incoming().flatten() {
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match socket.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                TRADES.len(),
+                TRADES
+            );
+            let _ = socket.write_all(response.as_bytes());
+        }
+    });
+    url
+}
+
+async fn client() -> BinanceTradingClient {
+    BinanceTradingClient::connect("test-key".into(), "test-secret".into())
+        .await
+        .expect("connect to the mock exchange")
+}
+
+#[tokio::test]
+async fn places_queries_and_cancels_an_order() {
+    let mock = exchange().await;
+    let mut client = client().await;
+    let order = create_limit_order(
+        "BTCUSDT".into(),
+        BinanceOrderSide::BUY,
+        dec!(0.010),
+        dec!(100000),
+    );
+
+    let placed = client.future_order_place(&order).await.unwrap();
+    assert_eq!(placed.symbol, "BTCUSDT");
+    assert_eq!(placed.status, "NEW");
+    assert_eq!(placed.side, "BUY");
+    let request = mock.requests().pop().unwrap();
+    assert_eq!(request["method"], "order.place");
+    let params = &request["params"];
+    assert_eq!(params["apiKey"], "test-key");
+    assert_eq!(params["quantity"], "0.01");
+    assert_eq!(params["recvWindow"], "5000");
+    assert!(params["signature"].is_string() && params["timestamp"].is_string());
+
+    let status = client
+        .future_order_status("BTCUSDT".into(), placed.order_id)
+        .await
+        .unwrap();
+    assert_eq!(status.status, "NEW");
+    let cancelled = client
+        .future_order_cancel("BTCUSDT".into(), placed.order_id)
+        .await
+        .unwrap();
+    assert_eq!(cancelled.order_id, placed.order_id);
+    assert_eq!(cancelled.status, "CANCELED");
+}
+
+#[tokio::test]
+async fn ioc_orders_that_fill_nothing_fail() {
+    let mock = exchange().await;
+    let exchange = BinanceExchange::new("BTCUSDT", "test-key".into(), "test-secret".into())
+        .await
+        .unwrap()
+        .with_time_in_force(TimeInForce::IOC);
+
+    let error = exchange
+        .place_order_future(OrderSide::Buy, dec!(100000), dec!(0.01))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            TradingError::Unfilled { time_in_force, status, .. }
+                if time_in_force == "IOC" && status == "EXPIRED"
+        ),
+        "{:?}",
+        error
+    );
+    let request = mock.requests().pop().unwrap();
+    assert_eq!(request["params"]["timeInForce"], "IOC");
+}
+
+#[tokio::test]
+async fn rejections_carry_the_exchange_error() {
+    let mock = exchange().await;
+    let mut client = client().await;
+    mock.inject(Fault::Reject {
+        code: -2019,
+        msg: "Margin is insufficient.".into(),
+    });
+    let order = create_limit_order("BTCUSDT".into(), BinanceOrderSide::SELL, dec!(1), dec!(1));
+
+    let error = client.future_order_place(&order).await.unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            TradingError::Rejected {
+                exchange: ExchangeId::Binance,
+                operation: "order.place",
+                code: -2019,
+                msg,
+            }
+                if msg == "Margin is insufficient."
+        ),
+        "{:?}",
+        error
+    );
+
+    let error = client
+        .future_order_cancel("BTCUSDT".into(), 42)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, TradingError::Rejected { code: -2011, .. }),
+        "{:?}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn a_dropped_connection_fails_the_request() {
+    let mock = exchange().await;
+    let mut client = client().await;
+    mock.inject(Fault::Disconnect);
+
+    let error = client
+        .future_order_status("BTCUSDT".into(), 1)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, TradingError::ConnectionClosed),
+        "{:?}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn a_malformed_answer_fails_the_request() {
+    let mock = exchange().await;
+    let mut client = client().await;
+    mock.inject(Fault::Garbage);
+
+    let error = client
+        .future_order_status("BTCUSDT".into(), 1)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, TradingError::Serialization(_)),
+        "{:?}",
+        error
+    );
+}
+
+async fn retrying_exchange() -> BinanceExchange {
+    BinanceExchange::new("BTCUSDT", "test-key".into(), "test-secret".into())
+        .await
+        .unwrap()
+        .with_retry(RetryPolicy {
+            max_attempts: 3,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+            retryable_only: true,
+        })
+}
+
+/// The requests the mock got since it had `before`.
+fn since(mock: &MockExchange, before: usize) -> Vec<Value> {
+    mock.requests()[before..].to_vec()
+}
+
+fn methods(requests: &[Value]) -> Vec<&str> {
+    requests
+        .iter()
+        .map(|r| r["method"].as_str().unwrap_or_default())
+        .collect()
+}
+
+#[tokio::test]
+async fn an_order_whose_answer_was_lost_is_found_not_placed_again() {
+    let mock = exchange().await;
+    let exchange = retrying_exchange().await;
+    let before = mock.requests().len();
+    mock.inject(Fault::Lost);
+
+    let order_id = exchange
+        .place_order_future(OrderSide::Buy, dec!(100000), dec!(0.01))
+        .await
+        .unwrap();
+    let requests = since(&mock, before);
+    assert_eq!(methods(&requests), ["order.place", "order.status"]);
+    let client_order_id = requests[0]["params"]["newClientOrderId"].as_str().unwrap();
+    assert!(client_order_id.starts_with("arb-") && client_order_id.len() <= 36);
+    assert_eq!(requests[1]["params"]["origClientOrderId"], client_order_id);
+    assert!(requests[1]["params"].get("orderId").is_none());
+    assert!(order_id.parse::<u64>().is_ok());
+}
+
+#[tokio::test]
+async fn an_order_that_never_arrived_is_placed_again_under_its_id() {
+    let mock = exchange().await;
+    let exchange = retrying_exchange().await;
+    let before = mock.requests().len();
+    mock.inject(Fault::Disconnect);
+
+    exchange
+        .place_order_future(OrderSide::Sell, dec!(100000), dec!(0.01))
+        .await
+        .unwrap();
+    let requests = since(&mock, before);
+    assert_eq!(
+        methods(&requests),
+        ["order.place", "order.status", "order.place"]
+    );
+    assert_eq!(
+        requests[0]["params"]["newClientOrderId"],
+        requests[2]["params"]["newClientOrderId"]
+    );
+}
+
+#[tokio::test]
+async fn an_order_that_may_be_placed_is_looked_up_on_every_retry() {
+    let mock = exchange().await;
+    let exchange = retrying_exchange().await;
+    let before = mock.requests().len();
+    // Placed, but the answer is lost; then the lookup is rate-limited.
+    mock.inject(Fault::Lost);
+    mock.inject(Fault::Reject {
+        code: -1003,
+        msg: "Too many requests.".into(),
+    });
+
+    exchange
+        .place_order_future(OrderSide::Buy, dec!(100000), dec!(0.01))
+        .await
+        .unwrap();
+    assert_eq!(
+        methods(&since(&mock, before)),
+        ["order.place", "order.status", "order.status"]
+    );
+}
+
+#[tokio::test]
+async fn only_retryable_rejections_are_retried() {
+    let mock = exchange().await;
+    let exchange = retrying_exchange().await;
+
+    // Rate-limited: nothing was placed, so it's simply sent again.
+    let before = mock.requests().len();
+    mock.inject(Fault::Reject {
+        code: -1003,
+        msg: "Too many requests.".into(),
+    });
+    exchange
+        .place_order_future(OrderSide::Buy, dec!(100000), dec!(0.01))
+        .await
+        .unwrap();
+    assert_eq!(
+        methods(&since(&mock, before)),
+        ["order.place", "order.place"]
+    );
+
+    let before = mock.requests().len();
+    mock.inject(Fault::Reject {
+        code: -2019,
+        msg: "Margin is insufficient.".into(),
+    });
+    let error = exchange
+        .place_order_future(OrderSide::Buy, dec!(100000), dec!(0.01))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, TradingError::Rejected { code: -2019, .. }),
+        "{:?}",
+        error
+    );
+    assert_eq!(methods(&since(&mock, before)), ["order.place"]);
+}
+
+#[tokio::test]
+async fn a_cancellation_whose_answer_was_lost_counts() {
+    let mock = exchange().await;
+    let exchange = retrying_exchange().await;
+    let order_id = exchange
+        .place_order_future(OrderSide::Buy, dec!(100000), dec!(0.01))
+        .await
+        .unwrap();
+    let before = mock.requests().len();
+    mock.inject(Fault::Lost);
+
+    exchange.cancel_order(&order_id).await.unwrap();
+    // The retry finds it gone, and asks why.
+    assert_eq!(
+        methods(&since(&mock, before)),
+        ["order.cancel", "order.cancel", "order.status"]
+    );
+
+    // Cancelling it again is an error like any other unknown order.
+    let error = exchange.cancel_order(&order_id).await.unwrap_err();
+    assert!(
+        matches!(error, TradingError::Rejected { code: -2011, .. }),
+        "{:?}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn a_filled_order_pays_its_trades_commission() {
+    let mock = exchange().await;
+    std::env::set_var("TEST_API_KEY_BINANCE", "test-key");
+    std::env::set_var("TEST_SECRET_KEY_BINANCE", "test-secret");
+    let mut config = FxConfig::default();
+    config.currencies.insert(
+        "BNB".into(),
+        FxSource {
+            rate: Some(dec!(600)),
+            pegged: false,
+            ..FxSource::default()
+        },
+    );
+    let exchange = BinanceExchange::new("BTCUSDT", "test-key".into(), "test-secret".into())
+        .await
+        .unwrap()
+        .with_key_vars(KeyVars::new(
+            "TEST_API_KEY_BINANCE",
+            "TEST_SECRET_KEY_BINANCE",
+        ))
+        .with_fx(Fx::new(&config));
+    let order_id = exchange
+        .place_order_future(OrderSide::Buy, dec!(112400), dec!(0.010))
+        .await
+        .unwrap();
+    assert_eq!(exchange.fill(&order_id).await.unwrap().fee, None);
+
+    mock.fill(order_id.parse().unwrap());
+    let fill = exchange.fill(&order_id).await.unwrap();
+    assert!(fill.done);
+    assert_eq!(fill.quantity, dec!(0.010));
+    // 0.0001124 BNB at 600 USDT.
+    assert_eq!(fill.fee, Some(dec!(0.06744)));
+}
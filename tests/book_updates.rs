@@ -0,0 +1,157 @@
+//! Property tests for how book updates reach the quote bus: picking the top
+//! of book out of a depth frame, and merging redundant connections by update
+//! ID (`ws::redundant`).
+//!
+//! The bot keeps top of book only; invariants over a full local book (sorted
+//! levels, no crossing after deltas, resync on gaps) belong here once one
+//! exists.
+
+use arbitrage_bot::{
+    models::{ids::Symbol, money::Decimal},
+    ws::{
+        handlers::{BinanceDepthParser, BybitOrderBookParser, MessageParser},
+        redundant::Deduplicator,
+    },
+};
+use proptest::prelude::*;
+use serde_json::json;
+
+/// `(price in cents, size in thousandths)` levels, best first.
+type Side = Vec<(i64, i64)>;
+
+/// A valid book: bids strictly descending, asks strictly ascending and above
+/// the best bid.
+fn book() -> impl Strategy<Value = (Side, Side)> {
+    (
+        1_000i64..20_000_000,
+        1i64..5_000,
+        prop::collection::vec((1i64..500, 1i64..1_000_000), 0..20),
+        prop::collection::vec((1i64..500, 1i64..1_000_000), 0..20),
+    )
+        .prop_map(|(best_bid, spread, bid_steps, ask_steps)| {
+            let ladder = |start: i64, direction: i64, steps: Vec<(i64, i64)>| {
+                steps
+                    .into_iter()
+                    .scan(start + direction, |price, (step, size)| {
+                        *price -= direction * step;
+                        Some((*price, size))
+                    })
+                    .filter(|(price, _)| *price > 0)
+                    .collect::<Side>()
+            };
+            (
+                ladder(best_bid, 1, bid_steps),
+                ladder(best_bid + spread, -1, ask_steps),
+            )
+        })
+}
+
+fn levels(side: &[(i64, i64)]) -> Vec<[String; 2]> {
+    side.iter()
+        .map(|&(price, size)| {
+            [
+                Decimal::new(price, 2).to_string(),
+                Decimal::new(size, 3).to_string(),
+            ]
+        })
+        .collect()
+}
+
+fn best(side: &[(i64, i64)]) -> Option<Decimal> {
+    side.first().map(|&(price, _)| Decimal::new(price, 2))
+}
+
+proptest! {
+    #[test]
+    fn parsers_take_the_best_level((bids, asks) in book(), update_id in 1u64..u64::MAX / 2) {
+        let binance = json!({
+            "e": "depthUpdate", "E": 1, "T": 1, "s": "BTCUSDT",
+            "U": update_id - 1, "u": update_id, "pu": update_id - 2,
+            "b": levels(&bids), "a": levels(&asks),
+        });
+        let bybit = json!({
+            "topic": "orderbook.50.BTCUSDT", "type": "snapshot", "ts": 1,
+            "data": {"s": "BTCUSDT", "b": levels(&bids), "a": levels(&asks), "u": 1, "seq": update_id},
+            "cts": 1,
+        });
+        let parsers: [&dyn MessageParser; 2] = [
+            &BinanceDepthParser,
+            &BybitOrderBookParser { market_type: Default::default() },
+        ];
+        for (parser, frame) in parsers.into_iter().zip([binance, bybit]) {
+            let top = parser.parse(&frame.to_string());
+            match (best(&bids), best(&asks)) {
+                (Some(bid), Some(ask)) => {
+                    let top = top.expect("a two-sided book is a quote");
+                    prop_assert_eq!(top.symbol, "BTCUSDT");
+                    prop_assert_eq!((top.bid, top.ask), (bid, ask));
+                    prop_assert!(top.bid < top.ask);
+                    prop_assert_eq!(top.update_id, Some(update_id));
+                }
+                // Never half a quote.
+                _ => prop_assert!(top.is_none()),
+            }
+        }
+    }
+
+    /// Legs carry the same updates in order, each with its own latency and
+    /// gaps; the merged stream must stay in order and never lose the latest.
+    #[test]
+    fn merged_legs_forward_each_update_once_and_in_order(
+        ids in prop::collection::btree_set(1u64..100_000, 1..200),
+        legs in prop::collection::vec(
+            (0u64..50, prop::collection::vec((any::<bool>(), 0u64..10), 200)),
+            1..4,
+        ),
+    ) {
+        let ids: Vec<u64> = ids.into_iter().collect();
+        let mut arrivals = Vec::new();
+        for (leg, (latency, events)) in legs.iter().enumerate() {
+            for (i, (&id, &(delivered, jitter))) in ids.iter().zip(events).enumerate() {
+                // Later updates arrive later on the same leg; other legs may
+                // overtake it.
+                if delivered || i == ids.len() - 1 {
+                    arrivals.push((i as u64 * 10 + latency + jitter, leg, id));
+                }
+            }
+        }
+        arrivals.sort();
+
+        let symbol = Symbol::intern("BTCUSDT");
+        let mut dedup = Deduplicator::default();
+        let forwarded: Vec<u64> = arrivals
+            .iter()
+            .filter(|&&(_, _, id)| dedup.is_new(symbol, Some(id)))
+            .map(|&(_, _, id)| id)
+            .collect();
+
+        prop_assert!(forwarded.windows(2).all(|w| w[0] < w[1]), "{:?}", forwarded);
+        prop_assert_eq!(forwarded.last(), ids.last());
+        if legs.len() == 1 {
+            let delivered: Vec<u64> = arrivals.iter().map(|&(_, _, id)| id).collect();
+            prop_assert_eq!(forwarded, delivered);
+        }
+    }
+
+    #[test]
+    fn symbols_are_merged_independently(
+        first in prop::collection::vec(1u64..1_000, 1..50),
+        second in prop::collection::vec(1u64..1_000, 1..50),
+    ) {
+        let (a, b) = (Symbol::intern("BTCUSDT"), Symbol::intern("ETHUSDT"));
+        let mut alone = Deduplicator::default();
+        let expected: Vec<bool> = first.iter().map(|&id| alone.is_new(a, Some(id))).collect();
+
+        let mut dedup = Deduplicator::default();
+        let mut forwarded = Vec::new();
+        for (i, &id) in first.iter().enumerate() {
+            if let Some(&other) = second.get(i) {
+                dedup.is_new(b, Some(other));
+            }
+            // Quotes without an update ID can't be matched and always pass.
+            prop_assert!(dedup.is_new(a, None));
+            forwarded.push(dedup.is_new(a, Some(id)));
+        }
+        prop_assert_eq!(forwarded, expected);
+    }
+}
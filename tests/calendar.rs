@@ -0,0 +1,224 @@
+//! The maintenance and listing calendar: which windows hold off execution,
+//! which alerts get a listing note, and how a reloaded schedule replaces the
+//! old one. Windows are laid out around the wall clock, which is what the
+//! calendar compares against.
+
+use std::{path::PathBuf, time::Duration};
+
+use arbitrage_bot::{
+    calendar::{Calendar, Schedule},
+    config::CalendarConfig,
+    models::{
+        ids::{ExchangeId, Symbol},
+        orderbook::{MarketTracker, MarketType},
+    },
+    notifications::{alert_gate::AlertGate, telegram::Notification},
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use rust_decimal_macros::dec;
+use tokio::{sync::mpsc, time};
+use tokio_util::sync::CancellationToken;
+
+fn at(offset_mins: i64) -> DateTime<Utc> {
+    Utc::now() + TimeDelta::minutes(offset_mins)
+}
+
+fn config() -> CalendarConfig {
+    CalendarConfig {
+        margin_secs: 300,
+        listing_window_secs: 3600,
+        ..CalendarConfig::default()
+    }
+}
+
+fn schedule(toml: &str) -> Schedule {
+    toml::from_str(toml).expect("a valid schedule")
+}
+
+/// A maintenance window from `start` to `end` minutes from now.
+fn window(exchange: &str, symbols: &str, start: i64, end: i64) -> String {
+    format!(
+        "[[maintenance]]\nexchange = \"{}\"\nsymbols = [{}]\nstart = \"{}\"\nend = \"{}\"\n",
+        exchange,
+        symbols,
+        at(start).to_rfc3339(),
+        at(end).to_rfc3339()
+    )
+}
+
+fn listing(exchange: &str, symbol: &str, offset_mins: i64) -> String {
+    format!(
+        "[[listing]]\nexchange = \"{}\"\nsymbol = \"{}\"\nat = \"{}\"\n",
+        exchange,
+        symbol,
+        at(offset_mins).to_rfc3339()
+    )
+}
+
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("arb-calendar-{}-{}.toml", std::process::id(), name))
+}
+
+#[test]
+fn maintenance_covers_its_venue_and_symbols_within_the_margin() {
+    let btc = Symbol::intern("BTCUSDT");
+    let eth = Symbol::intern("ETHUSDT");
+    let calendar = Calendar::new(
+        schedule(&format!(
+            "{}{}",
+            window("bybit", "", 3, 60),
+            window("binance", "\"ETHUSDT\"", -60, -10)
+        )),
+        &config(),
+    );
+
+    // Bybit's window starts in 3 minutes, inside the 5-minute margin, and
+    // covers every symbol.
+    let held = calendar.maintenance(ExchangeId::Bybit, btc, Utc::now());
+    assert!(held.is_some_and(|m| m.to_string().starts_with("bybit maintenance")));
+    assert!(calendar
+        .maintenance(ExchangeId::Bybit, eth, Utc::now())
+        .is_some());
+    // ...but not 10 minutes from now before the window, nor once the margin
+    // after it has passed.
+    assert!(calendar
+        .maintenance(ExchangeId::Bybit, btc, at(-10))
+        .is_none());
+    assert!(calendar
+        .maintenance(ExchangeId::Bybit, btc, at(66))
+        .is_none());
+
+    // Binance's window ended 10 minutes ago and only covered ETHUSDT.
+    assert!(calendar
+        .maintenance(ExchangeId::Binance, eth, Utc::now())
+        .is_none());
+    assert!(calendar
+        .maintenance(ExchangeId::Binance, eth, at(-30))
+        .is_some());
+    assert!(calendar
+        .maintenance(ExchangeId::Binance, btc, at(-30))
+        .is_none());
+}
+
+#[test]
+fn listings_match_within_the_window() {
+    let calendar = Calendar::new(
+        schedule(&format!(
+            "{}{}",
+            listing("binance", "NEWUSDT", -12),
+            listing("bybit", "OLDUSDT", -120)
+        )),
+        &config(),
+    );
+    let new = Symbol::intern("NEWUSDT");
+    let found = calendar
+        .listing(ExchangeId::Binance, new, Utc::now())
+        .expect("listed 12 minutes ago");
+    assert_eq!(
+        found.describe(Utc::now()),
+        "binance listed NEWUSDT 12 min ago"
+    );
+    assert_eq!(found.describe(at(-42)), "binance lists NEWUSDT in 30 min");
+    assert!(calendar
+        .listing(ExchangeId::Bybit, new, Utc::now())
+        .is_none());
+    // Two hours is past the one-hour window.
+    assert!(calendar
+        .listing(ExchangeId::Bybit, Symbol::intern("OLDUSDT"), Utc::now())
+        .is_none());
+}
+
+#[test]
+fn schedule_rejects_unknown_fields_and_exchanges() {
+    assert!(toml::from_str::<Schedule>("[[maintenance]]\nexchange = \"bybit\"\n").is_err());
+    assert!(toml::from_str::<Schedule>(&window("kraken", "", 0, 10)).is_err());
+    assert!(toml::from_str::<Schedule>(&format!(
+        "{}venue = \"bybit\"\n",
+        listing("bybit", "NEWUSDT", 0)
+    ))
+    .is_err());
+}
+
+#[tokio::test]
+async fn alerts_near_a_listing_carry_a_note() {
+    let calendar = Calendar::new(schedule(&listing("bybit", "NEWUSDT", -5)), &config());
+    let (tx, mut rx) = mpsc::channel(4);
+    let mut tracker = MarketTracker::new(dec!(1), Some(tx), AlertGate::new(dec!(5), dec!(1), 0))
+        .with_calendar(calendar);
+
+    let mut alert = |symbol: &str| {
+        let symbol = Symbol::intern(symbol);
+        tracker.update(
+            ExchangeId::Binance,
+            symbol,
+            dec!(99.9),
+            dec!(100.1),
+            MarketType::Futures,
+        );
+        tracker.update(
+            ExchangeId::Bybit,
+            symbol,
+            dec!(109.9),
+            dec!(110.1),
+            MarketType::Futures,
+        );
+        match rx.try_recv() {
+            Ok(Notification::Arbitrage(alert)) => alert,
+            other => panic!("expected an alert, got {:?}", other),
+        }
+    };
+
+    let listed = alert("NEWUSDT");
+    let note = listed.note.expect("a listing note");
+    assert!(
+        note.starts_with("bybit listed NEWUSDT 5 min ago"),
+        "{}",
+        note
+    );
+    assert_eq!(alert("BTCUSDT").note, None);
+}
+
+#[tokio::test]
+async fn reloads_keep_the_last_good_schedule() {
+    let path = temp_file("reload");
+    std::fs::write(&path, window("bybit", "", -5, 60)).unwrap();
+    let config = CalendarConfig {
+        file: Some(path.clone()),
+        refresh_secs: 1,
+        ..config()
+    };
+    let calendar = Calendar::load(&config).await.unwrap().expect("enabled");
+    let btc = Symbol::intern("BTCUSDT");
+    assert!(calendar
+        .maintenance(ExchangeId::Bybit, btc, Utc::now())
+        .is_some());
+
+    let cancel = CancellationToken::new();
+    calendar.spawn_refresh(&config, cancel.clone());
+    std::fs::write(&path, window("binance", "", -5, 60)).unwrap();
+    time::timeout(Duration::from_secs(10), async {
+        while calendar
+            .maintenance(ExchangeId::Binance, btc, Utc::now())
+            .is_none()
+        {
+            time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the new schedule is picked up");
+    assert!(calendar
+        .maintenance(ExchangeId::Bybit, btc, Utc::now())
+        .is_none());
+
+    // A broken file leaves the schedule as it was.
+    std::fs::write(&path, "[[maintenance]]\nexchange = ").unwrap();
+    time::sleep(Duration::from_millis(2500)).await;
+    assert!(calendar
+        .maintenance(ExchangeId::Binance, btc, Utc::now())
+        .is_some());
+    cancel.cancel();
+    std::fs::remove_file(&path).unwrap();
+
+    // At startup, a file that can't be read is an error.
+    assert!(Calendar::load(&config).await.is_err());
+}
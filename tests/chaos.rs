@@ -0,0 +1,229 @@
+//! The feeds under a misbehaving exchange: connections dropped mid-stream,
+//! frames held back, truncated JSON, and old updates sent again. Whatever
+//! gets through, consumers must only ever see real quotes with update IDs
+//! that move forward: the bus stream (what the tracker follows) and the
+//! latest-quote cell (what a strategy loads). Once the chaos stops, the feed
+//! has to catch up to the newest update.
+//!
+//! Runs with a fixed seed; set `CHAOS_SEED` to try others.
+
+mod support;
+
+use std::time::Duration;
+
+use arbitrage_bot::{
+    binance::ws_handler::EVENT_CHANNEL_CAPACITY,
+    models::{ids::ExchangeId, money::Decimal},
+    ws::{
+        binance_client::{run_orderbook_stream_binance, run_orderbook_stream_binance_redundant},
+        bybit_client_futures::run_orderbook_stream_bybit_futures,
+        quote_bus::{Quote, QuoteBus},
+    },
+};
+use serde_json::json;
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    task::JoinHandle,
+    time,
+};
+use tokio_util::sync::CancellationToken;
+
+use support::{Chaos, Flavor, MockExchange, WAIT};
+
+/// Updates pushed while the chaos lasts; the next ID is the final one.
+const UPDATES: u64 = 400;
+const FINAL: u64 = UPDATES + 1;
+const PUSH_INTERVAL: Duration = Duration::from_millis(2);
+
+fn seed() -> u64 {
+    std::env::var("CHAOS_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(0x5eed)
+}
+
+fn chaos() -> Chaos {
+    Chaos {
+        seed: seed(),
+        drop: 0.02,
+        delay: 0.05,
+        max_delay: Duration::from_millis(20),
+        malformed: 0.05,
+        replay: 0.1,
+    }
+}
+
+/// Bid and ask are derived from the update ID, so a quote that mixes up two
+/// updates (or isn't one of them) doesn't match.
+fn prices(update_id: u64) -> (Decimal, Decimal) {
+    let bid = Decimal::new(10_000_000 + update_id as i64 * 10, 2);
+    (bid, bid + Decimal::new(5, 2))
+}
+
+fn binance_frame(update_id: u64) -> String {
+    let (bid, ask) = prices(update_id);
+    json!({
+        "e": "depthUpdate", "E": update_id, "T": update_id, "s": "BTCUSDT",
+        "U": update_id, "u": update_id, "pu": update_id - 1,
+        "b": [[bid.to_string(), "1.000"]], "a": [[ask.to_string(), "1.000"]],
+    })
+    .to_string()
+}
+
+fn bybit_frame(update_id: u64) -> String {
+    let (bid, ask) = prices(update_id);
+    json!({
+        "topic": "orderbook.1.BTCUSDT", "type": "snapshot", "ts": update_id,
+        "data": {
+            "s": "BTCUSDT", "u": update_id, "seq": update_id,
+            "b": [[bid.to_string(), "1.000"]], "a": [[ask.to_string(), "1.000"]],
+        },
+        "cts": update_id,
+    })
+    .to_string()
+}
+
+/// Panics unless `quote` is exactly the update it claims to be, and at
+/// least update `oldest`. Returns its update ID.
+fn check(quote: &Quote, exchange: ExchangeId, oldest: u64, what: &str) -> u64 {
+    let seed = seed();
+    let id = quote
+        .top
+        .update_id
+        .unwrap_or_else(|| panic!("{}: quote without an update ID (seed {})", what, seed));
+    assert_eq!(quote.exchange, exchange, "{} (seed {})", what, seed);
+    assert_eq!(quote.top.symbol, "BTCUSDT", "{} (seed {})", what, seed);
+    assert_eq!(
+        (quote.top.bid, quote.top.ask),
+        prices(id),
+        "{}: corrupt quote for update {} (seed {})",
+        what,
+        id,
+        seed
+    );
+    assert!(
+        id >= oldest,
+        "{}: stale update {}, expected {} or later (seed {})",
+        what,
+        id,
+        oldest,
+        seed
+    );
+    id
+}
+
+/// Runs a feed against a chaotic `mock` and checks what reaches the bus.
+/// `legs` is how many connections the feed opens.
+async fn survive(
+    flavor: Flavor,
+    legs: usize,
+    frame: fn(u64) -> String,
+    start: impl FnOnce(QuoteBus, String, CancellationToken) -> JoinHandle<()>,
+) {
+    support::init_config(&[]);
+    let exchange = match flavor {
+        Flavor::Binance => ExchangeId::Binance,
+        Flavor::Bybit => ExchangeId::Bybit,
+    };
+    let mock = MockExchange::start(flavor);
+    let bus = QuoteBus::default();
+    let cell = bus.latest(exchange, "BTCUSDT");
+    let mut published = bus.subscribe();
+    let cancel = CancellationToken::new();
+
+    // The tracker's view: every quote, in order, strictly newer each time.
+    let tracker = tokio::spawn(async move {
+        let (mut last, mut count) = (0, 0u64);
+        loop {
+            match published.recv().await {
+                Ok(quote) => {
+                    let id = check(&quote, exchange, last + 1, "bus");
+                    count += 1;
+                    last = id;
+                    if id == FINAL {
+                        return count;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => panic!("quote bus closed"),
+            }
+        }
+    });
+    // The strategy's view: the latest cell, never going back.
+    let strategy = tokio::spawn({
+        let (cell, cancel) = (cell.clone(), cancel.clone());
+        async move {
+            let mut last = 0;
+            while !cancel.is_cancelled() {
+                if let Some(quote) = cell.load() {
+                    last = check(&quote, exchange, last, "latest cell");
+                }
+                time::sleep(Duration::from_millis(1)).await;
+            }
+        }
+    });
+
+    let feed = start(bus, mock.url(), cancel.clone());
+    mock.wait_for_requests(legs).await;
+    mock.set_chaos(Some(chaos()));
+    for id in 1..=UPDATES {
+        mock.push(&frame(id));
+        time::sleep(PUSH_INTERVAL).await;
+    }
+
+    mock.set_chaos(None);
+    let caught_up = time::timeout(WAIT, async {
+        while cell.load().and_then(|q| q.top.update_id) != Some(FINAL) {
+            mock.push(&frame(FINAL));
+            time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await;
+    assert!(
+        caught_up.is_ok(),
+        "the feed never caught up (seed {})",
+        seed()
+    );
+    // At least one hangup, or the chaos didn't test much.
+    mock.wait_for_connections(legs + 1).await;
+
+    let delivered = tracker.await.unwrap();
+    assert!(delivered > 1, "only {} quotes got through", delivered);
+    cancel.cancel();
+    strategy.await.unwrap();
+    feed.await.unwrap();
+}
+
+#[tokio::test]
+async fn binance_feed_survives_chaos() {
+    survive(Flavor::Binance, 1, binance_frame, |bus, url, cancel| {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            run_orderbook_stream_binance("BTCUSDT", bus, &url, events, cancel).await
+        })
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn bybit_feed_survives_chaos() {
+    survive(Flavor::Bybit, 1, bybit_frame, |bus, url, cancel| {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            run_orderbook_stream_bybit_futures("BTCUSDT", bus, &url, events, cancel).await
+        })
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn redundant_feed_survives_chaos() {
+    survive(Flavor::Binance, 2, binance_frame, |bus, url, cancel| {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            run_orderbook_stream_binance_redundant("BTCUSDT", bus, &[&url, &url], events, cancel)
+                .await
+        })
+    })
+    .await;
+}
@@ -0,0 +1,329 @@
+//! The shared state between tasks: the engine's one-trade-at-a-time flag,
+//! a feed's circuit breaker, and the maps quotes go through on their way to
+//! the tracker and the strategy.
+//!
+//! All of it sits on tokio primitives and `arc-swap`, which loom can't model.
+//! Instead the engine and breaker tests run on tokio's paused clock, where
+//! every interleaving they depend on is fixed by the timeline, and the map
+//! tests hammer the real thing from several threads.
+
+mod support;
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use arbitrage_bot::{
+    binance::ws_handler::{ConnectionEvent, ConnectionState, WsHandler, EVENT_CHANNEL_CAPACITY},
+    config::WsConfig,
+    control::Signal,
+    models::{ids::Symbol, orderbook::MarketType},
+    state::LegSide,
+    ws::{
+        backpressure::CoalescingQueue,
+        exchanges::{ArbitrageEngine, ExchangeId},
+        handlers::TopOfBook,
+        quote_bus::QuoteBus,
+    },
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::{broadcast, mpsc},
+    time::{self, Instant},
+};
+use tokio_util::sync::CancellationToken;
+
+use support::exchange::{until_subscribed, FakeExchange, Ledger};
+
+// ── Executing flag ───────────────────────────────────────────────────────────
+
+/// How long each fake order takes; both legs of a trade overlap.
+const ORDER_LATENCY: Duration = Duration::from_millis(100);
+/// How long the engine holds off after a trade.
+const TRADE_LOCK: Duration = Duration::from_secs(5);
+
+struct Market {
+    binance: Arc<FakeExchange>,
+    bybit: Arc<FakeExchange>,
+    ledger: Arc<Ledger>,
+    signals: mpsc::Sender<Signal>,
+}
+
+impl Market {
+    /// Start times of the trades, one per buy leg.
+    fn trades(&self) -> Vec<Instant> {
+        let orders = self.ledger.orders();
+        let buys: Vec<_> = orders.iter().filter(|o| o.side == LegSide::Buy).collect();
+        assert_eq!(buys.len() * 2, orders.len(), "unpaired legs: {:?}", orders);
+        buys.iter().map(|o| o.at - ORDER_LATENCY).collect()
+    }
+
+    /// An engine trading on both fakes above a 0.1% edge.
+    async fn start() -> Self {
+        let ledger = Ledger::new();
+        let fake = |id| Arc::new(FakeExchange::new(id, &ledger).with_ack_delay(ORDER_LATENCY));
+        let (binance, bybit) = (fake(ExchangeId::Binance), fake(ExchangeId::Bybit));
+        let (signals, signal_rx) = mpsc::channel(4);
+        let mut engine = ArbitrageEngine::new(
+            vec![binance.clone(), bybit.clone()],
+            dec!(0.001),
+            dec!(0.01),
+        )
+        .with_signals(signal_rx);
+        tokio::spawn(async move { engine.run().await });
+        until_subscribed(&[&binance, &bybit]).await;
+        Self {
+            binance,
+            bybit,
+            ledger,
+            signals,
+        }
+    }
+
+    /// Bybit's bid 0.9% over Binance's ask.
+    async fn opportunity(&self) {
+        self.binance.quote(dec!(100)).await;
+        self.bybit.quote(dec!(101)).await;
+    }
+
+    async fn flat(&self) {
+        self.binance.quote(dec!(100)).await;
+        self.bybit.quote(dec!(100)).await;
+    }
+
+    async fn signal(&self) {
+        self.signals
+            .send(Signal {
+                symbol: "BTCUSDT".into(),
+                buy: ExchangeId::Binance,
+                sell: ExchangeId::Bybit,
+                source: Some("test".into()),
+            })
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn one_trade_at_a_time() {
+    let market = Market::start().await;
+    let start = Instant::now();
+    // An opportunity every 200ms for 12s.
+    while start.elapsed() < Duration::from_secs(12) {
+        market.opportunity().await;
+        time::sleep(Duration::from_millis(200)).await;
+    }
+    time::sleep(Duration::from_secs(30)).await;
+
+    assert_eq!(market.ledger.max_in_flight(), 2);
+    let trades = market.trades();
+    assert!(trades.len() >= 3, "{} trades", trades.len());
+    for pair in trades.windows(2) {
+        assert!(
+            pair[1] - pair[0] >= TRADE_LOCK + ORDER_LATENCY,
+            "trades {:?} apart",
+            pair[1] - pair[0]
+        );
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn prices_queued_during_a_trade_are_applied_first() {
+    let market = Market::start().await;
+    market.opportunity().await;
+    time::sleep(Duration::from_secs(1)).await;
+    // The spread closes while the engine is held; Binance's tick is first in
+    // the queue, but Bybit's old price must not be traded on.
+    market.flat().await;
+    time::sleep(Duration::from_secs(10)).await;
+    assert_eq!(market.trades().len(), 1);
+
+    market.opportunity().await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(market.trades().len(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn signal_during_a_trade_waits_for_it() {
+    let market = Market::start().await;
+    market.opportunity().await;
+    time::sleep(Duration::from_secs(1)).await;
+    market.signal().await;
+    time::sleep(Duration::from_secs(10)).await;
+
+    // Not lost: traded once the first trade released the engine.
+    let trades = market.trades();
+    assert_eq!(trades.len(), 2);
+    assert!(trades[1] - trades[0] >= TRADE_LOCK);
+}
+
+#[tokio::test(start_paused = true)]
+async fn signal_during_a_trade_sees_current_prices() {
+    let market = Market::start().await;
+    market.opportunity().await;
+    time::sleep(Duration::from_secs(1)).await;
+    market.flat().await;
+    market.signal().await;
+    time::sleep(Duration::from_secs(10)).await;
+    assert_eq!(market.trades().len(), 1);
+}
+
+// ── Circuit breaker ──────────────────────────────────────────────────────────
+
+#[tokio::test(start_paused = true)]
+async fn breaker_trips_waits_and_resets() {
+    let config = WsConfig {
+        base_backoff_ms: 50,
+        max_backoff_ms: 200,
+        circuit_breaker_limit: 3,
+        circuit_breaker_window_secs: 60,
+        ..WsConfig::default()
+    };
+    // Nothing listens on port 1: every attempt fails straight away.
+    let (tx, _rx) = mpsc::channel(16);
+    let (events, mut rx) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    let cancel = CancellationToken::new();
+    let handler = WsHandler::new("ws://127.0.0.1:1".into(), tx)
+        .with_config(config)
+        .with_event_channel(events)
+        .with_cancellation(cancel.clone());
+    handler.start().await;
+
+    // Failed connects and trips, with when they happened.
+    let mut next = async || loop {
+        match rx.recv().await.unwrap() {
+            ConnectionEvent::ConnectFailed { .. } => return (false, Instant::now()),
+            ConnectionEvent::CircuitBreakerTripped {
+                disconnections,
+                cooldown,
+                ..
+            } => {
+                assert_eq!((disconnections, cooldown), (3, Duration::from_secs(60)));
+                return (true, Instant::now());
+            }
+            _ => {}
+        }
+    };
+    let (mut failures, mut trips) = (0, Vec::new());
+    while trips.len() < 3 {
+        let (tripped, at) = next().await;
+        if tripped {
+            assert_eq!(failures, 3, "tripped after {} failures", failures);
+            failures = 0;
+            trips.push(at);
+            continue;
+        }
+        // No attempt during the cooldown, and the count starts over after it.
+        if let (0, Some(&trip)) = (failures, trips.last()) {
+            assert!(
+                at - trip >= Duration::from_secs(60),
+                "retried during the cooldown"
+            );
+        }
+        failures += 1;
+    }
+
+    // Cancelling in the middle of a cooldown stops the handler.
+    cancel.cancel();
+    time::timeout(Duration::from_secs(1), async {
+        while handler.state().await != ConnectionState::Disconnected {
+            time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the handler didn't stop during the cooldown");
+}
+
+// ── Quote maps ───────────────────────────────────────────────────────────────
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn coalescing_queue_never_loses_the_latest_value() {
+    const PRODUCERS: usize = 4;
+    const KEYS: usize = 8;
+    const UPDATES: u64 = 2_000;
+    for _ in 0..20 {
+        let queue = Arc::new(CoalescingQueue::new("concurrency test"));
+        let consumer = tokio::spawn({
+            let queue = queue.clone();
+            async move {
+                let mut last = HashMap::new();
+                while let Some((key, value)) = queue.pop().await {
+                    let previous = last.insert(key, value).unwrap_or(0);
+                    assert!(
+                        value > previous,
+                        "key {}: {} after {}",
+                        key,
+                        value,
+                        previous
+                    );
+                }
+                last
+            }
+        });
+        // Each key has one producer, so its values only go up.
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let queue = queue.clone();
+                std::thread::spawn(move || {
+                    for value in 1..=UPDATES {
+                        for key in p * KEYS..(p + 1) * KEYS {
+                            queue.push(key, value);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        queue.close();
+
+        let last = time::timeout(Duration::from_secs(10), consumer)
+            .await
+            .expect("the consumer missed a wakeup")
+            .unwrap();
+        assert_eq!(last.len(), PRODUCERS * KEYS);
+        assert!(last.values().all(|&value| value == UPDATES), "{:?}", last);
+    }
+}
+
+#[test]
+fn latest_cells_registered_concurrently_all_receive_quotes() {
+    const THREADS: usize = 8;
+    const SYMBOLS: usize = 64;
+    let bus = QuoteBus::default();
+    let symbol = |thread: usize, i: usize| format!("T{}S{}USDT", thread, i);
+    let threads: Vec<_> = (0..THREADS)
+        .map(|thread| {
+            let bus = bus.clone();
+            std::thread::spawn(move || {
+                for i in 0..SYMBOLS {
+                    let name = symbol(thread, i);
+                    let cell = bus.latest(ExchangeId::Binance, &name);
+                    bus.publish(
+                        ExchangeId::Binance,
+                        TopOfBook {
+                            symbol: Symbol::intern(&name),
+                            bid: dec!(1),
+                            ask: dec!(2),
+                            bid_size: None,
+                            ask_size: None,
+                            market_type: MarketType::Futures,
+                            update_id: Some(i as u64),
+                        },
+                    );
+                    // Another thread's registration must not have replaced
+                    // the index with one that lacks this cell.
+                    assert!(cell.load().is_some(), "{} lost its quote", name);
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    for thread in 0..THREADS {
+        for i in 0..SYMBOLS {
+            let quote = bus.latest(ExchangeId::Binance, &symbol(thread, i)).load();
+            assert_eq!(quote.unwrap().top.update_id, Some(i as u64));
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Inverse contracts: a BTCUSD inverse leg against a BTCUSDT linear one is
+//! sized in whole contracts, the linear leg cut down to match, and the
+//! session values both legs in USD.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    config::ContractKind,
+    contracts::{Contract, Contracts},
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+    },
+    plan::{ExecutionPlan, LegStatus, PlanReport, PlannedLeg},
+    session::Session,
+    state::{self, ExecutionState, LegSide, OrderLeg},
+    ws::{exchanges::ArbitrageEngine, quote_bus::QuoteBus},
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::{broadcast, watch},
+    time::{self, Duration},
+};
+use tokio_util::sync::CancellationToken;
+
+use support::{
+    exchange::{until_subscribed, FakeExchange, Ledger},
+    load_config,
+};
+
+const EXECUTION: &str = r#"
+[engine.execution]
+enabled = true
+symbol = "BTCUSDT"
+quantity = "0.01"
+"#;
+
+#[test]
+fn venues_trade_their_own_contracts() {
+    let config = load_config(
+        "valid",
+        &format!(
+            r#"{}
+[engine.execution.contracts.bybit]
+symbol = "BTCPERP"
+"#,
+            EXECUTION
+        ),
+    )
+    .unwrap();
+    let execution = &config.engine.execution;
+    assert_eq!(execution.symbol_on(ExchangeId::Bybit), "BTCPERP");
+    assert_eq!(execution.symbol_on(ExchangeId::Binance), "BTCUSDT");
+    assert_eq!(
+        execution.contract(ExchangeId::Bybit).unwrap().kind,
+        ContractKind::Linear
+    );
+    let contracts = Contracts::new(execution);
+    assert_eq!(contracts.get(ExchangeId::Bybit), Contract::LINEAR);
+    assert_eq!(contracts.get(ExchangeId::Binance), Contract::LINEAR);
+
+    // No venue has a coin-margined order client to trade one with.
+    for exchange in ["binance", "bybit"] {
+        let toml = format!(
+            "{}
+[engine.execution.contracts.{}]
+kind = \"inverse\"",
+            EXECUTION, exchange
+        );
+        let error = load_config(&format!("inverse-{}", exchange), &toml).unwrap_err();
+        assert!(
+            format!("{error:#}").contains("coin-margined order client"),
+            "{error:#}"
+        );
+    }
+
+    let invalid = [
+        "[engine.execution.contracts.kraken]\nkind = \"inverse\"",
+        "[engine.execution.contracts.bybit]\ncontract_size = \"1\"",
+        "[engine.execution.contracts.bybit]\nkind = \"inverse\"\ncontract_size = \"0\"",
+        "[engine.execution.contracts.bybit]\nkind = \"quanto\"",
+        "[engine.execution.contracts.bybit]\nsymbol = \" \"",
+    ];
+    for (i, toml) in invalid.iter().enumerate() {
+        let toml = format!("{}\n{}", EXECUTION, toml);
+        assert!(
+            load_config(&format!("invalid-{}", i), &toml).is_err(),
+            "{}",
+            toml
+        );
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn inverse_legs_go_out_in_whole_contracts() {
+    let ledger = Ledger::new();
+    let fake = |id, symbol| Arc::new(FakeExchange::new(id, &ledger).trading(symbol));
+    let binance = fake(ExchangeId::Binance, "BTCUSDT");
+    let bybit = fake(ExchangeId::Bybit, "BTCUSD");
+    let state = watch::Sender::new(ExecutionState::default());
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.015),
+    )
+    .with_state(state.clone())
+    .with_contracts(Contracts::default().with(ExchangeId::Bybit, Contract::inverse(dec!(1))));
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // Buy BTCUSDT at 60000.1, sell BTCUSD at 60300: 0.015 BTC is 904
+    // contracts there, worth 0.01499… BTC. The buy rounds that down to
+    // 0.014, and the sell is the 844 contracts that's worth.
+    binance.quote(dec!(60000)).await;
+    bybit.quote(dec!(60300)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        ledger.quantities(),
+        [
+            (ExchangeId::Binance, LegSide::Buy, dec!(0.014)),
+            (ExchangeId::Bybit, LegSide::Sell, dec!(844)),
+        ]
+    );
+    let state = state.borrow();
+    let symbols: Vec<_> = state.orders.iter().map(|o| o.symbol.as_str()).collect();
+    assert_eq!(symbols, ["BTCUSDT", "BTCUSD"]);
+    // Exposure stays in each venue's unit.
+    assert_eq!(state.exposure[&ExchangeId::Bybit], dec!(-844));
+}
+
+fn placed(exchange: ExchangeId, side: LegSide, price: Decimal, quantity: Decimal) -> OrderLeg {
+    OrderLeg {
+        exchange,
+        symbol: "BTCUSD".into(),
+        side,
+        price,
+        quantity,
+        order_id: Some("1".into()),
+        account: None,
+        error: None,
+        placed_at_ms: state::now_ms(),
+    }
+}
+
+#[tokio::test]
+async fn session_pnl_values_inverse_legs_in_usd() {
+    let (_opportunities, opportunities_rx) = broadcast::channel(1);
+    let (_events, events_rx) = broadcast::channel(1);
+    let session = Session::start(
+        &QuoteBus::default(),
+        opportunities_rx,
+        events_rx,
+        &ExecutionState::default(),
+        dec!(0.1),
+        CancellationToken::new(),
+    )
+    .with_contracts(Contracts::default().with(ExchangeId::Bybit, Contract::inverse(dec!(1))));
+
+    // 600 contracts of 1 USD bought at 60000 (0.01 BTC), 0.01 BTC sold at
+    // 60300.
+    let plan = ExecutionPlan::arbitrage(
+        Symbol::intern("BTCUSDT"),
+        PlannedLeg::buy(ExchangeId::Bybit, dec!(60000), dec!(600)),
+        PlannedLeg::sell(ExchangeId::Binance, dec!(60300), dec!(0.01)),
+    );
+    let mut report = PlanReport::new(1, &plan, state::now_ms());
+    for leg in &mut report.legs {
+        leg.status = LegStatus::Placed {
+            order_id: "1".into(),
+        };
+    }
+    let execution = ExecutionState {
+        orders: vec![
+            placed(ExchangeId::Bybit, LegSide::Buy, dec!(60000), dec!(600)),
+            placed(ExchangeId::Binance, LegSide::Sell, dec!(60300), dec!(0.01)),
+        ],
+        plans: vec![report],
+        planned: 1,
+        ..ExecutionState::default()
+    };
+
+    let summary = session.summary(&execution);
+    assert_eq!(summary.gross_pnl, dec!(3));
+    // 0.1% of 600 and 603.
+    assert_eq!(summary.fees, dec!(1.203));
+}
@@ -0,0 +1,214 @@
+//! Configuration from `ARB__` environment variables, alone or over a file.
+
+use std::path::PathBuf;
+
+use arbitrage_bot::{config::Config, models::ids::ExchangeId};
+use rust_decimal_macros::dec;
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn file(name: &str, toml: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("arb-env-{}-{}.toml", std::process::id(), name));
+    std::fs::write(&path, toml).unwrap();
+    path
+}
+
+#[test]
+fn the_whole_config_can_come_from_the_environment() {
+    let config = Config::load_from(
+        None,
+        vars(&[
+            ("ARB__ENGINE__EXECUTION__ENABLED", "true"),
+            ("ARB__ENGINE__EXECUTION__QUANTITY", "0.01"),
+            ("ARB__ENGINE__EXECUTION__EXPIRY__TTL_SECS", "30"),
+            ("ARB__PAUSES__SYMBOLS", r#"["BTCUSDT", "ETHUSDT"]"#),
+            ("ARB__PAUSES__EXCHANGES", r#"["bybit"]"#),
+            ("ARB__FX__ENABLED", "true"),
+            ("arb__fx__reference", "USDT"),
+            (
+                "ARB__FX__CURRENCIES",
+                r#"{ USDC = { symbol = "USDCUSDT" } }"#,
+            ),
+            // Not ours.
+            ("ARB_CONFIG_UNUSED", "1"),
+            ("PATH", "/usr/bin"),
+        ]),
+    )
+    .unwrap();
+
+    let execution = &config.engine.execution;
+    assert!(execution.enabled);
+    assert_eq!(execution.quantity, dec!(0.01));
+    assert_eq!(execution.expiry.ttl_secs, 30);
+    assert_eq!(config.pauses.symbols, ["BTCUSDT", "ETHUSDT"]);
+    assert_eq!(config.pauses.exchanges, [ExchangeId::Bybit]);
+    assert!(config.fx.enabled);
+    assert_eq!(
+        config.fx.currencies["USDC"].symbol.as_deref(),
+        Some("USDCUSDT")
+    );
+    // Everything else keeps its default.
+    assert_eq!(config.fx.refresh_secs, Config::default().fx.refresh_secs);
+}
+
+#[test]
+fn variables_are_set_over_the_file() {
+    let path = file(
+        "over",
+        "[fx]\nenabled = true\nrefresh_secs = 30\n[fx.currencies.USDC]\nsymbol = \"USDCUSDT\"\n",
+    );
+    let config = Config::load_from(
+        Some(&path),
+        vars(&[
+            ("ARB__FX__REFRESH_SECS", "120"),
+            (
+                "ARB__FX__CURRENCIES",
+                r#"{ FDUSD = { symbol = "FDUSDUSDT" } }"#,
+            ),
+            ("ARB__PAUSES__SYMBOLS", r#"["WLFIUSDT"]"#),
+        ]),
+    );
+    let _ = std::fs::remove_file(&path);
+    let config = config.unwrap();
+
+    assert_eq!(config.fx.refresh_secs, 120);
+    // Merged with the file's currencies, not replacing them.
+    let mut currencies: Vec<_> = config.fx.currencies.keys().cloned().collect();
+    currencies.sort();
+    assert_eq!(currencies, ["FDUSD", "USDC"]);
+    assert_eq!(config.pauses.symbols, ["WLFIUSDT"]);
+}
+
+#[test]
+fn values_that_are_not_toml_are_strings() {
+    let config = Config::load_from(
+        None,
+        vars(&[
+            ("ARB__FX__REFERENCE", "USDT,USDC"),
+            ("ARB__PAUSES__SYMBOLS", r#"["BTCUSDT"]"#),
+        ]),
+    )
+    .unwrap();
+    // Commas don't make a list…
+    assert_eq!(config.fx.reference, "USDT,USDC");
+
+    // …so a list key needs an array.
+    let error =
+        Config::load_from(None, vars(&[("ARB__PAUSES__SYMBOLS", "BTCUSDT,ETHUSDT")])).unwrap_err();
+    let message = format!("{error:#}");
+    assert!(message.contains("ARB__PAUSES__SYMBOLS"), "{message}");
+}
+
+#[test]
+fn numbers_set_for_string_keys_are_strings() {
+    let path = file(
+        "numbers",
+        r#"
+[[engine.execution.accounts]]
+name = "main"
+api_key_var = "MAIN_KEY"
+secret_key_var = "MAIN_SECRET"
+
+[[engine.execution.accounts]]
+name = "sub"
+api_key_var = "SUB_KEY"
+secret_key_var = "SUB_SECRET"
+"#,
+    );
+    let config = Config::load_from(
+        Some(&path),
+        vars(&[
+            (
+                "ARB__ENGINE__EXECUTION__ACCOUNTS__1__SUB_ACCOUNT",
+                "123456789",
+            ),
+            ("ARB__MQTT__USERNAME", "1001"),
+            ("ARB__MQTT__CLIENT_ID", "42"),
+            ("ARB__MQTT__PORT", "1884"),
+        ]),
+    );
+    let missing = Config::load_from(
+        Some(&path),
+        vars(&[("ARB__ENGINE__EXECUTION__ACCOUNTS__2__SUB_ACCOUNT", "1")]),
+    );
+    let _ = std::fs::remove_file(&path);
+    let config = config.unwrap();
+
+    let accounts = &config.engine.execution.accounts;
+    assert_eq!(accounts[0].sub_account, None);
+    assert_eq!(accounts[1].sub_account.as_deref(), Some("123456789"));
+    assert_eq!(config.mqtt.username.as_deref(), Some("1001"));
+    assert_eq!(config.mqtt.client_id, "42");
+    // A number key still takes the number.
+    assert_eq!(config.mqtt.port, 1884);
+
+    let message = format!("{:#}", missing.unwrap_err());
+    assert!(message.contains("has no entry 2"), "{message}");
+
+    // A string is no fallback for a number key: the error is the number's.
+    let error = Config::load_from(None, vars(&[("ARB__MQTT__PORT", "70000")])).unwrap_err();
+    let message = format!("{error:#}");
+    assert!(message.contains("ARB__MQTT__PORT"), "{message}");
+    assert!(!message.contains("string"), "{message}");
+}
+
+#[test]
+fn bad_variables_are_refused() {
+    let error = Config::load_from(
+        None,
+        vars(&[
+            ("ARB__FX__ENABLED", "true"),
+            ("ARB__ENGINE__NO_SUCH_KEY", "1"),
+        ]),
+    )
+    .unwrap_err();
+    let message = format!("{error:#}");
+    assert!(message.contains("ARB__* variables"), "{message}");
+    // Names the variable, not just the key.
+    assert!(message.contains("ARB__ENGINE__NO_SUCH_KEY"), "{message}");
+    assert!(!message.contains("ARB__FX__ENABLED"), "{message}");
+    assert!(message.contains("no_such_key"), "{message}");
+
+    let error = Config::load_from(None, vars(&[("ARB__FX____ENABLED", "true")])).unwrap_err();
+    assert!(
+        format!("{error:#}").contains("ARB__FX____ENABLED"),
+        "{error:#}"
+    );
+
+    let error = Config::load_from(
+        None,
+        vars(&[
+            ("ARB__FX__ENABLED", "true"),
+            ("ARB__FX__ENABLED__AGAIN", "true"),
+        ]),
+    )
+    .unwrap_err();
+    assert!(
+        format!("{error:#}").contains("isn't a section"),
+        "{error:#}"
+    );
+
+    // Validated like the file: the refresh interval can't be zero.
+    let error = Config::load_from(
+        None,
+        vars(&[("ARB__FX__ENABLED", "true"), ("ARB__FX__REFRESH_SECS", "0")]),
+    )
+    .unwrap_err();
+    assert!(format!("{error:#}").contains("refresh_secs"), "{error:#}");
+}
+
+#[test]
+fn without_variables_the_file_loads_as_before() {
+    let path = file("plain", "[fx]\nrefresh_secs = \"soon\"\n");
+    let error = Config::load_from(Some(&path), vars(&[("API_KEY", "k")])).unwrap_err();
+    let _ = std::fs::remove_file(&path);
+    // Errors still point at the file's line.
+    let message = format!("{error:#}");
+    assert!(message.contains(&*path.display().to_string()), "{message}");
+    assert!(message.contains("line 2"), "{message}");
+}
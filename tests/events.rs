@@ -0,0 +1,136 @@
+//! The `events` timeline: opportunities from the spread log, orders from the
+//! state file and connection events from the event log, merged in time order
+//! and filtered by age and type.
+
+use std::{path::PathBuf, time::Duration};
+
+use arbitrage_bot::{
+    binance::ws_handler::{ConnectionEvent, DisconnectReason},
+    config::EngineConfig,
+    events::{self, EventKind, EventLog},
+    models::ids::ExchangeId,
+    state::{self, EngineState, LegSide, OrderLeg},
+};
+use rust_decimal_macros::dec;
+
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("arb-events-{}-{}", std::process::id(), name))
+}
+
+fn order(at_ms: i64, side: LegSide, order_id: Option<&str>, error: Option<&str>) -> OrderLeg {
+    OrderLeg {
+        exchange: ExchangeId::Binance,
+        symbol: "BTCUSDT".into(),
+        side,
+        price: dec!(100),
+        quantity: dec!(0.01),
+        order_id: order_id.map(Into::into),
+        account: None,
+        error: error.map(Into::into),
+        placed_at_ms: at_ms,
+    }
+}
+
+#[test]
+fn merges_every_source_in_time_order() {
+    let now = state::now_ms();
+    let hours_ago = |hours: i64| now - hours * 3_600_000;
+
+    let spread_log = temp_file("spreads.csv");
+    std::fs::write(
+        &spread_log,
+        format!(
+            "symbol,exchange_a,exchange_b,bid_a,ask_a,mid_a,bid_b,ask_b,mid_b,diff_percent,timestamp\n\
+             BTCUSDT,binance,bybit,1,1,1,1,1,1,5.20%,{}\n\
+             ETHUSDT,binance,bybit,1,1,1,1,1,1,6.00%,{}\n",
+            hours_ago(3) / 1000,
+            hours_ago(1) / 1000
+        ),
+    )
+    .unwrap();
+
+    let state_file = temp_file("state.json");
+    let mut saved = EngineState::default();
+    saved.execution.orders = vec![
+        order(hours_ago(1) + 1000, LegSide::Buy, Some("42"), None),
+        order(
+            hours_ago(1) + 1000,
+            LegSide::Sell,
+            None,
+            Some("insufficient balance"),
+        ),
+    ];
+    saved.save(&state_file).unwrap();
+
+    let event_log = temp_file("events.jsonl");
+    let _ = std::fs::remove_file(&event_log);
+    let log = EventLog::open(&event_log).unwrap();
+    let url = "wss://stream.bybit.com/v5/public/linear".to_string();
+    log.append(&ConnectionEvent::Disconnected {
+        url: url.clone(),
+        reason: DisconnectReason::ServerClosed,
+    })
+    .unwrap();
+    log.append(&ConnectionEvent::CircuitBreakerTripped {
+        url: url.clone(),
+        disconnections: 5,
+        cooldown: Duration::from_secs(60),
+    })
+    .unwrap();
+
+    let config = EngineConfig {
+        spread_log: Some(spread_log.clone()),
+        state_file: Some(state_file.clone()),
+        event_log: Some(event_log.clone()),
+        ..EngineConfig::default()
+    };
+    let all = events::load(&config).unwrap();
+    let kinds: Vec<_> = all.iter().map(|e| e.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            EventKind::Opportunity,
+            EventKind::Opportunity,
+            EventKind::Trade,
+            EventKind::Error,
+            EventKind::Connection,
+            EventKind::Error,
+        ]
+    );
+    assert_eq!(all[0].text, "BTCUSDT binance/bybit 5.20%");
+    assert_eq!(all[2].text, "BUY 0.01 BTCUSDT on binance @ 100 → order 42");
+    assert_eq!(
+        all[3].text,
+        "SELL 0.01 BTCUSDT on binance @ 100 failed: insufficient balance"
+    );
+    assert_eq!(
+        all[4].text,
+        format!("{} disconnected: closed by server", url)
+    );
+
+    // The last two hours' trades and errors.
+    let since = now - events::parse_since("2h").unwrap().as_millis() as i64;
+    let recent = events::filter(all, since, &[EventKind::Trade, EventKind::Error]);
+    assert_eq!(recent.len(), 3);
+    assert!(recent[2].text.contains("circuit breaker tripped after 5"));
+
+    for path in [spread_log, state_file, event_log] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[test]
+fn types_and_missing_sources() {
+    assert_eq!("trade".parse::<EventKind>().unwrap(), EventKind::Trade);
+    assert_eq!("orders".parse::<EventKind>().unwrap(), EventKind::Trade);
+    assert!("fills".parse::<EventKind>().is_err());
+
+    // Configured but not written yet: nothing to show, not an error.
+    let config = EngineConfig {
+        spread_log: Some(temp_file("missing.csv")),
+        state_file: Some(temp_file("missing.json")),
+        event_log: Some(temp_file("missing.jsonl")),
+        ..EngineConfig::default()
+    };
+    assert!(events::load(&config).unwrap().is_empty());
+}
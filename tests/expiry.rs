@@ -0,0 +1,315 @@
+//! Expiring orders left resting past `[engine.execution.expiry] ttl_secs`.
+
+mod support;
+
+use std::{sync::Arc, time::Duration};
+
+use arbitrage_bot::{
+    config::{ExpiryAction, ExpiryConfig},
+    expiry::Expiry,
+    models::{ids::ExchangeId, money::Decimal, orderbook::MarketType},
+    state::{ExecutionState, LegSide},
+    ws::{handlers::TopOfBook, quote_bus::QuoteBus},
+};
+use rust_decimal_macros::dec;
+use tokio::{sync::watch, time};
+
+use support::{exchange::FakeVenue, load_config};
+
+fn expiry(action: ExpiryAction) -> ExpiryConfig {
+    ExpiryConfig {
+        enabled: true,
+        ttl_secs: 60,
+        interval_secs: 5,
+        action,
+        max_amends: 1,
+    }
+}
+
+/// Publishes a quote to `bus`.
+fn quote(bus: &QuoteBus, bid: Decimal, ask: Decimal) {
+    bus.publish(
+        ExchangeId::Binance,
+        TopOfBook {
+            symbol: "BTCUSDT".into(),
+            bid,
+            ask,
+            bid_size: None,
+            ask_size: None,
+            market_type: MarketType::Futures,
+            update_id: None,
+        },
+    );
+}
+
+/// The resting orders' rest, 0.3, counted as bought.
+fn exposed() -> watch::Sender<ExecutionState> {
+    let mut state = ExecutionState::default();
+    state.exposure.insert(ExchangeId::Binance, dec!(0.3));
+    watch::Sender::new(state)
+}
+
+#[tokio::test(start_paused = true)]
+async fn orders_resting_past_the_ttl_are_cancelled() {
+    let venue = Arc::new(FakeVenue::new(ExchangeId::Binance));
+    venue.rest(LegSide::Buy, dec!(100));
+    let state = exposed();
+    let mut expiry = Expiry::new(
+        &expiry(ExpiryAction::Cancel),
+        vec![venue.clone()],
+        &QuoteBus::default(),
+        |_| "BTCUSDT",
+        state.clone(),
+    );
+
+    assert!(expiry.check().await.is_empty());
+    time::advance(Duration::from_secs(59)).await;
+    assert!(expiry.check().await.is_empty());
+    time::advance(Duration::from_secs(1)).await;
+    let outcomes = expiry.check().await;
+
+    let expired = outcomes[0].as_ref().unwrap();
+    assert_eq!(expired.action, ExpiryAction::Cancel);
+    assert_eq!(expired.replacement, None);
+    assert_eq!(
+        expired.to_string(),
+        "binance BUY 0.3 @ 100 (order 40) rested past its TTL; cancelled"
+    );
+    assert!(venue.book().is_empty());
+    assert!(venue.placed().is_empty());
+    assert!(!state.borrow().is_exposed());
+}
+
+#[tokio::test(start_paused = true)]
+async fn amended_orders_rest_again_at_the_touch_until_out_of_amends() {
+    let venue = Arc::new(FakeVenue::new(ExchangeId::Binance));
+    venue.rest(LegSide::Sell, dec!(105));
+    let state = exposed();
+    state.send_modify(|state| {
+        state.exposure.insert(ExchangeId::Binance, dec!(-0.3));
+    });
+    let bus = QuoteBus::default();
+    let mut expiry = Expiry::new(
+        &expiry(ExpiryAction::Amend),
+        vec![venue.clone()],
+        &bus,
+        |_| "BTCUSDT",
+        state.clone(),
+    );
+    quote(&bus, dec!(100), dec!(101));
+
+    expiry.check().await;
+    time::advance(Duration::from_secs(60)).await;
+    let outcomes = expiry.check().await;
+
+    let expired = outcomes[0].as_ref().unwrap();
+    assert_eq!(expired.action, ExpiryAction::Amend);
+    let replacement = "limit Sell 0.3 @ 101";
+    assert_eq!(expired.replacement, Some(Ok(replacement.to_string())));
+    assert!(expired
+        .to_string()
+        .ends_with("re-priced as order limit Sell 0.3 @ 101"));
+    {
+        let state = state.borrow();
+        assert_eq!(state.exposure[&ExchangeId::Binance], dec!(-0.3));
+        assert_eq!(state.orders[0].price, dec!(101));
+    }
+
+    // The replacement gets a TTL of its own, and then no more amends.
+    time::advance(Duration::from_secs(30)).await;
+    assert!(expiry.check().await.is_empty());
+    time::advance(Duration::from_secs(30)).await;
+    let outcomes = expiry.check().await;
+
+    let expired = outcomes[0].as_ref().unwrap();
+    assert_eq!(expired.order.order_id, replacement);
+    assert_eq!(expired.action, ExpiryAction::Cancel);
+    assert!(venue.book().is_empty());
+    assert!(!state.borrow().is_exposed());
+}
+
+#[tokio::test(start_paused = true)]
+async fn orders_without_a_quote_to_amend_at_are_cancelled() {
+    let venue = Arc::new(FakeVenue::new(ExchangeId::Binance));
+    venue.rest(LegSide::Buy, dec!(100));
+    let mut expiry = Expiry::new(
+        &expiry(ExpiryAction::Amend),
+        vec![venue.clone()],
+        &QuoteBus::default(),
+        |_| "BTCUSDT",
+        exposed(),
+    );
+
+    expiry.check().await;
+    time::advance(Duration::from_secs(60)).await;
+    let outcomes = expiry.check().await;
+
+    assert_eq!(outcomes[0].as_ref().unwrap().action, ExpiryAction::Cancel);
+    assert!(venue.placed().is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn market_expiry_sends_the_rest_at_market() {
+    let venue = Arc::new(FakeVenue::new(ExchangeId::Binance));
+    venue.rest(LegSide::Buy, dec!(100));
+    let state = exposed();
+    let bus = QuoteBus::default();
+    let mut expiry = Expiry::new(
+        &expiry(ExpiryAction::Market),
+        vec![venue.clone()],
+        &bus,
+        |_| "BTCUSDT",
+        state.clone(),
+    );
+    quote(&bus, dec!(102), dec!(103));
+
+    expiry.check().await;
+    time::advance(Duration::from_secs(60)).await;
+    let outcomes = expiry.check().await;
+
+    let expired = outcomes[0].as_ref().unwrap();
+    assert_eq!(expired.replacement, Some(Ok("market Buy 0.3".to_string())));
+    assert!(venue.book().is_empty());
+    let state = state.borrow();
+    assert_eq!(state.exposure[&ExchangeId::Binance], dec!(0.3));
+    // Recorded at the ask it crosses.
+    assert_eq!(state.orders[0].price, dec!(103));
+}
+
+#[tokio::test(start_paused = true)]
+async fn reduce_only_orders_are_placed_again_as_exits() {
+    let venue = Arc::new(FakeVenue::new(ExchangeId::Binance));
+    venue.rest_exit(LegSide::Sell, dec!(105));
+    let bus = QuoteBus::default();
+    let mut expiry = Expiry::new(
+        &expiry(ExpiryAction::Amend),
+        vec![venue.clone()],
+        &bus,
+        |_| "BTCUSDT",
+        exposed(),
+    );
+    quote(&bus, dec!(100), dec!(101));
+
+    expiry.check().await;
+    time::advance(Duration::from_secs(60)).await;
+    let outcomes = expiry.check().await;
+
+    let expired = outcomes[0].as_ref().unwrap();
+    assert_eq!(
+        expired.replacement,
+        Some(Ok("exit Sell 0.3 @ 101".to_string()))
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn reduce_only_orders_go_to_market_as_exits() {
+    let venue = Arc::new(FakeVenue::new(ExchangeId::Binance));
+    venue.rest_exit(LegSide::Sell, dec!(105));
+    let mut expiry = Expiry::new(
+        &expiry(ExpiryAction::Market),
+        vec![venue.clone()],
+        &QuoteBus::default(),
+        |_| "BTCUSDT",
+        exposed(),
+    );
+
+    expiry.check().await;
+    time::advance(Duration::from_secs(60)).await;
+    expiry.check().await;
+
+    assert_eq!(venue.placed(), ["market exit Sell 0.3"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_rest_is_what_the_cancel_left() {
+    let venue = Arc::new(FakeVenue::new(ExchangeId::Binance));
+    venue.rest(LegSide::Buy, dec!(100));
+    venue.fill_late(dec!(0.1));
+    let state = exposed();
+    let mut expiry = Expiry::new(
+        &expiry(ExpiryAction::Market),
+        vec![venue.clone()],
+        &QuoteBus::default(),
+        |_| "BTCUSDT",
+        state.clone(),
+    );
+
+    expiry.check().await;
+    time::advance(Duration::from_secs(60)).await;
+    let outcomes = expiry.check().await;
+
+    let expired = outcomes[0].as_ref().unwrap();
+    assert_eq!(expired.order.filled, dec!(0.3));
+    assert_eq!(expired.replacement, Some(Ok("market Buy 0.2".to_string())));
+    assert_eq!(state.borrow().exposure[&ExchangeId::Binance], dec!(0.3));
+
+    // Filled in full before the cancel took: nothing is left to place.
+    venue.rest(LegSide::Buy, dec!(100));
+    venue.fill_late(dec!(0.3));
+    expiry.check().await;
+    time::advance(Duration::from_secs(60)).await;
+    let outcomes = expiry.check().await;
+
+    let expired = outcomes[0].as_ref().unwrap();
+    assert_eq!(expired.action, ExpiryAction::Cancel);
+    assert_eq!(expired.replacement, None);
+    assert_eq!(venue.placed().len(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn orders_gone_from_the_book_are_forgotten() {
+    let venue = Arc::new(FakeVenue::new(ExchangeId::Binance));
+    let order_id = venue.rest(LegSide::Buy, dec!(100));
+    let mut expiry = Expiry::new(
+        &expiry(ExpiryAction::Cancel),
+        vec![venue.clone()],
+        &QuoteBus::default(),
+        |_| "BTCUSDT",
+        exposed(),
+    );
+
+    expiry.check().await;
+    time::advance(Duration::from_secs(40)).await;
+    // Filled, then an order under the same ID shows up again.
+    venue.clear_book();
+    expiry.check().await;
+    venue.rest(LegSide::Buy, dec!(100));
+    assert_eq!(venue.book()[0].order_id, order_id);
+    time::advance(Duration::from_secs(40)).await;
+
+    assert!(expiry.check().await.is_empty());
+}
+
+#[test]
+fn expiry_settings_are_validated() {
+    let config = load_config("default", "").unwrap();
+    assert!(!config.engine.execution.expiry.enabled);
+
+    let execution = "[engine.execution]\nenabled = true\nquantity = 0.01\n";
+    let config = load_config(
+        "valid",
+        &format!("{execution}[engine.execution.expiry]\nenabled = true\nttl_secs = 30\naction = \"amend\"\n"),
+    )
+    .unwrap();
+    let expiry = &config.engine.execution.expiry;
+    assert_eq!(expiry.ttl(), Duration::from_secs(30));
+    assert_eq!(expiry.action, ExpiryAction::Amend);
+
+    let error = load_config(
+        "zero-ttl",
+        &format!("{execution}[engine.execution.expiry]\nenabled = true\nttl_secs = 0\n"),
+    )
+    .unwrap_err();
+    assert!(format!("{error:#}").contains("ttl_secs"), "{error}");
+    let error = load_config(
+        "inventory",
+        &format!("{execution}mode = \"inventory\"\n[engine.execution.expiry]\nenabled = true\n"),
+    )
+    .unwrap_err();
+    assert!(format!("{error:#}").contains("futures"), "{error}");
+    assert!(load_config(
+        "unknown-action",
+        &format!("{execution}[engine.execution.expiry]\naction = \"hold\"\n"),
+    )
+    .is_err());
+}
@@ -0,0 +1,148 @@
+//! REST failover for quiet feeds: polled quotes are flagged as such, and
+//! execution leaves an exchange alone while one of its feeds is polled.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    bridge::QuoteEvent,
+    failover::{Failover, Transition},
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::MarketType,
+    },
+    state::LegSide,
+    ws::{exchanges::ArbitrageEngine, handlers::TopOfBook, quote_bus::QuoteBus},
+};
+use rust_decimal_macros::dec;
+use tokio::time::{self, Duration};
+
+use support::{
+    exchange::{until_subscribed, FakeExchange, Ledger},
+    load_config,
+};
+
+#[test]
+fn failover_settings_are_validated() {
+    let config = load_config("default", "").unwrap();
+    assert!(!config.failover.enabled);
+    assert_eq!(config.failover.after(), Duration::from_secs(10));
+
+    let config = load_config(
+        "valid",
+        "[failover]\nenabled = true\nafter_secs = 5\npoll_interval_ms = 500",
+    )
+    .unwrap();
+    assert!(config.failover.enabled);
+    assert_eq!(config.failover.after(), Duration::from_secs(5));
+    assert_eq!(config.failover.poll_interval(), Duration::from_millis(500));
+
+    for (i, toml) in ["after_secs = 0", "poll_interval_ms = 100"]
+        .iter()
+        .enumerate()
+    {
+        let toml = format!("[failover]\n{}", toml);
+        assert!(
+            load_config(&format!("invalid-{}", i), &toml).is_err(),
+            "{}",
+            toml
+        );
+    }
+}
+
+#[test]
+fn an_exchange_is_degraded_while_any_feed_is_polled() {
+    let failover = Failover::new();
+    let (btc, eth) = (Symbol::intern("BTCUSDT"), Symbol::intern("ETHUSDT"));
+    assert!(!failover.is_degraded(ExchangeId::Bybit));
+
+    assert_eq!(
+        failover.record(ExchangeId::Bybit, btc, true),
+        Some(Transition::Degraded)
+    );
+    assert_eq!(failover.record(ExchangeId::Bybit, btc, true), None);
+    assert_eq!(
+        failover.record(ExchangeId::Bybit, eth, true),
+        Some(Transition::Degraded)
+    );
+    assert!(failover.is_degraded(ExchangeId::Bybit));
+    assert!(!failover.is_degraded(ExchangeId::Binance));
+
+    // One feed streaming again isn't enough.
+    assert_eq!(
+        failover.record(ExchangeId::Bybit, btc, false),
+        Some(Transition::Recovered)
+    );
+    assert!(!failover.is_polled(ExchangeId::Bybit, btc));
+    assert!(failover.is_degraded(ExchangeId::Bybit));
+    assert_eq!(
+        failover.record(ExchangeId::Bybit, eth, false),
+        Some(Transition::Recovered)
+    );
+    assert!(!failover.is_degraded(ExchangeId::Bybit));
+    assert_eq!(failover.record(ExchangeId::Bybit, eth, false), None);
+}
+
+#[test]
+fn polled_quotes_are_flagged() {
+    let bus = QuoteBus::default();
+    let mut every = bus.subscribe();
+    let cell = bus.latest(ExchangeId::Binance, "BTCUSDT");
+    let top = |bid: Decimal| TopOfBook {
+        symbol: Symbol::intern("BTCUSDT"),
+        bid,
+        ask: bid + dec!(0.1),
+        bid_size: None,
+        ask_size: None,
+        market_type: MarketType::Futures,
+        update_id: None,
+    };
+
+    bus.publish_polled(ExchangeId::Binance, top(dec!(100)));
+    let polled = every.try_recv().unwrap();
+    assert!(polled.polled);
+    assert!(cell.load().unwrap().polled);
+    let json = serde_json::to_value(QuoteEvent::from(polled.as_ref())).unwrap();
+    assert_eq!(json["polled"], true);
+
+    bus.publish(ExchangeId::Binance, top(dec!(101)));
+    assert!(!every.try_recv().unwrap().polled);
+    assert!(!cell.load().unwrap().polled);
+}
+
+#[tokio::test(start_paused = true)]
+async fn degraded_exchanges_are_not_traded_on() {
+    let ledger = Ledger::new();
+    let fake = |id| Arc::new(FakeExchange::new(id, &ledger));
+    let (binance, bybit) = (fake(ExchangeId::Binance), fake(ExchangeId::Bybit));
+    let failover = Failover::new();
+    failover.record(ExchangeId::Bybit, Symbol::intern("ETHUSDT"), true);
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_failover(failover.clone());
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // About 1%, with Bybit's ETHUSDT feed polled.
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101.1)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert!(ledger.is_empty());
+
+    // Streaming again.
+    failover.record(ExchangeId::Bybit, Symbol::intern("ETHUSDT"), false);
+    bybit.quote(dec!(101.1)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        ledger.legs(),
+        [
+            (ExchangeId::Binance, LegSide::Buy),
+            (ExchangeId::Bybit, LegSide::Sell),
+        ]
+    );
+}
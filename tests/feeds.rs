@@ -0,0 +1,238 @@
+//! The Binance and Bybit feed clients against the mock exchange: subscribing,
+//! publishing quotes, and recovering from bad frames, dropped connections and
+//! failed handshakes.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    binance::ws_handler::{ConnectionEvent, WsHandler, EVENT_CHANNEL_CAPACITY},
+    error::FeedError,
+    models::{ids::ExchangeId, orderbook::MarketType},
+    ws::{
+        binance_client::run_orderbook_stream_binance,
+        binance_client_multiplex::spawn_orderbook_stream_binance_multiplex,
+        bybit_client_futures::run_orderbook_stream_bybit_futures,
+        handlers,
+        quote_bus::{Quote, QuoteBus},
+    },
+};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use tokio::{
+    io::AsyncWriteExt,
+    sync::{broadcast, watch},
+};
+use tokio_util::sync::CancellationToken;
+
+use support::{Fault, Flavor, MockExchange, WAIT};
+
+async fn next_quote(latest: &mut watch::Receiver<Option<Arc<Quote>>>) -> Arc<Quote> {
+    tokio::time::timeout(WAIT, latest.wait_for(Option::is_some))
+        .await
+        .expect("no quote in time")
+        .expect("quote bus dropped")
+        .clone()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn binance_depth_reaches_the_quote_bus() {
+    support::init_config(&[]);
+    let mock = MockExchange::start(Flavor::Binance);
+    let bus = QuoteBus::default();
+    let mut latest = bus.watch(ExchangeId::Binance, "BTCUSDT");
+    let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    let cancel = CancellationToken::new();
+    let feed = tokio::spawn({
+        let (bus, url, cancel) = (bus.clone(), mock.url(), cancel.clone());
+        async move { run_orderbook_stream_binance("BTCUSDT", bus, &url, events, cancel).await }
+    });
+
+    let requests = mock.wait_for_requests(1).await;
+    assert_eq!(
+        requests[0],
+        json!({"method": "SUBSCRIBE", "params": ["btcusdt@depth5@100ms"], "id": 1})
+    );
+    mock.push(support::BINANCE_FUTURES_DEPTH);
+
+    let quote = next_quote(&mut latest).await;
+    assert_eq!(quote.exchange, ExchangeId::Binance);
+    assert_eq!(quote.top.bid, dec!(112543.10));
+    assert_eq!(quote.top.ask, dec!(112543.20));
+    assert!(matches!(quote.top.market_type, MarketType::Futures));
+
+    cancel.cancel();
+    feed.await.unwrap();
+}
+
+#[tokio::test]
+async fn binance_multiplex_subscribes_each_stream_once() {
+    support::init_config(&[]);
+    let mock = MockExchange::start(Flavor::Binance);
+    let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    let cancel = CancellationToken::new();
+    let _handle = spawn_orderbook_stream_binance_multiplex(
+        &["BTCUSDT", "ETHUSDT"],
+        QuoteBus::default(),
+        &mock.url(),
+        events,
+        cancel.clone(),
+    );
+
+    let requests = mock.wait_for_requests(1).await;
+    assert_eq!(
+        requests[0],
+        json!({
+            "method": "SUBSCRIBE",
+            "params": ["btcusdt@depth5@100ms", "ethusdt@depth5@100ms"],
+            "id": 1
+        })
+    );
+    // Well past the throttler's gap between requests.
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    assert_eq!(mock.requests().len(), 1, "streams sent more than once");
+
+    cancel.cancel();
+}
+
+#[tokio::test]
+async fn bybit_orderbook_reaches_the_quote_bus() {
+    support::init_config(&[]);
+    let mock = MockExchange::start(Flavor::Bybit);
+    let bus = QuoteBus::default();
+    let mut latest = bus.watch(ExchangeId::Bybit, "BTCUSDT");
+    let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    let cancel = CancellationToken::new();
+    let feed = tokio::spawn({
+        let (bus, url, cancel) = (bus.clone(), mock.url(), cancel.clone());
+        async move { run_orderbook_stream_bybit_futures("BTCUSDT", bus, &url, events, cancel).await }
+    });
+
+    let requests = mock.wait_for_requests(1).await;
+    assert_eq!(
+        requests[0],
+        json!({"op": "subscribe", "args": ["orderbook.1.BTCUSDT"]})
+    );
+    mock.push(support::BYBIT_ORDERBOOK);
+
+    let quote = next_quote(&mut latest).await;
+    assert_eq!(quote.exchange, ExchangeId::Bybit);
+    assert_eq!(quote.top.bid, dec!(112540.80));
+    assert_eq!(quote.top.ask, dec!(112540.90));
+    assert_eq!(quote.top.update_id, Some(428716307725));
+
+    cancel.cancel();
+    feed.await.unwrap();
+}
+
+#[tokio::test]
+async fn malformed_frames_are_skipped() {
+    support::init_config(&[]);
+    let mock = MockExchange::start(Flavor::Binance);
+    let bus = QuoteBus::default();
+    let mut latest = bus.watch(ExchangeId::Binance, "BTCUSDT");
+    let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    let cancel = CancellationToken::new();
+    let feed = tokio::spawn({
+        let (bus, url, cancel) = (bus.clone(), mock.url(), cancel.clone());
+        async move { run_orderbook_stream_binance("BTCUSDT", bus, &url, events, cancel).await }
+    });
+
+    mock.wait_for_requests(1).await;
+    mock.push("}{ not json");
+    mock.push(r#"{"e":"depthUpdate","s":"BTCUSDT","b":[],"a":[]}"#);
+    mock.push(support::BINANCE_FUTURES_DEPTH);
+
+    let quote = next_quote(&mut latest).await;
+    assert_eq!(quote.top.bid, dec!(112543.10));
+    assert_eq!(mock.requests().len(), 1, "the feed should not reconnect");
+
+    cancel.cancel();
+    feed.await.unwrap();
+}
+
+#[tokio::test]
+async fn feed_reconnects_and_resubscribes() {
+    support::init_config(&[]);
+    let mock = MockExchange::start(Flavor::Bybit);
+    // The first subscription is answered by hanging up.
+    mock.inject(Fault::Disconnect);
+    let bus = QuoteBus::default();
+    let mut latest = bus.watch(ExchangeId::Bybit, "BTCUSDT");
+    let (events, mut connection_events) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    let cancel = CancellationToken::new();
+    let feed = tokio::spawn({
+        let (bus, url, cancel) = (bus.clone(), mock.url(), cancel.clone());
+        async move { run_orderbook_stream_bybit_futures("BTCUSDT", bus, &url, events, cancel).await }
+    });
+
+    mock.wait_for_connections(2).await;
+    let requests = mock.wait_for_requests(2).await;
+    assert_eq!(requests[0], requests[1]);
+    mock.push(support::BYBIT_ORDERBOOK);
+    next_quote(&mut latest).await;
+
+    let mut seen = Vec::new();
+    while let Ok(event) = connection_events.try_recv() {
+        seen.push(match event {
+            ConnectionEvent::Connected { .. } => "connected",
+            ConnectionEvent::Disconnected { .. } => "disconnected",
+            ConnectionEvent::Reconnecting { .. } => "reconnecting",
+            _ => "other",
+        });
+    }
+    let expected = ["connected", "disconnected", "reconnecting", "connected"];
+    assert!(
+        seen.windows(expected.len()).any(|w| w == expected),
+        "unexpected connection events: {:?}",
+        seen
+    );
+
+    cancel.cancel();
+    feed.await.unwrap();
+}
+
+#[tokio::test]
+async fn a_tls_failure_is_reported_once_per_attempt() {
+    support::init_config(&[]);
+    // A server that answers the TLS handshake in plaintext.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("wss://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        }
+    });
+    let (events, mut connection_events) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    let cancel = CancellationToken::new();
+    let (tx, _rx) = handlers::feed_channel(&url);
+    let handler = WsHandler::new(url, tx)
+        .with_event_channel(events)
+        .with_cancellation(cancel.clone());
+    handler.start().await;
+
+    let mut failures = 0;
+    let seen = tokio::time::timeout(WAIT, async {
+        loop {
+            match connection_events.recv().await.unwrap() {
+                ConnectionEvent::ConnectFailed { error, .. } => {
+                    assert!(
+                        matches!(&*error, FeedError::Connect(e) if e.is_tls()),
+                        "{}",
+                        error
+                    );
+                    failures += 1;
+                }
+                ConnectionEvent::Reconnecting { .. } => break,
+                _ => {}
+            }
+        }
+    })
+    .await;
+    assert!(seen.is_ok(), "no reconnect after the failure");
+    assert_eq!(failures, 1);
+
+    cancel.cancel();
+}
@@ -0,0 +1,181 @@
+//! Maker and taker fees per account: VIP tiers override the default, maker
+//! rebates add to a trade's edge, and the session charges each order its
+//! account's fee.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    accounts::{Account, Accounts},
+    config::AccountRouting,
+    fees::{FeeRates, FeeSchedule, Liquidity},
+    models::{ids::ExchangeId, money::Decimal},
+    session::Session,
+    state::{ExecutionState, LegSide},
+    ws::{exchanges::ArbitrageEngine, quote_bus::QuoteBus},
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::{broadcast, watch},
+    time::{self, Duration},
+};
+use tokio_util::sync::CancellationToken;
+
+use support::{
+    exchange::{until_subscribed, FakeExchange, Ledger},
+    load_config,
+};
+
+const EXECUTION: &str = r#"
+[engine.execution]
+enabled = true
+symbol = "BTCUSDT"
+quantity = "0.01"
+fee_percent = "0.05"
+maker_fee_percent = "-0.005"
+"#;
+
+#[test]
+fn account_tiers_override_the_default() {
+    let config = load_config(
+        "valid",
+        &format!(
+            r#"{}
+[[engine.execution.accounts]]
+name = "main"
+api_key_var = "MAIN_KEY"
+secret_key_var = "MAIN_SECRET"
+
+[[engine.execution.accounts]]
+name = "vip"
+api_key_var = "VIP_KEY"
+secret_key_var = "VIP_SECRET"
+fee_percent = "0.03"
+maker_fee_percent = "-0.01"
+"#,
+            EXECUTION
+        ),
+    )
+    .unwrap();
+    let fees = FeeSchedule::from_config(&config.engine.execution);
+    assert_eq!(fees.liquidity(), Liquidity::Taker);
+    assert_eq!(fees.percent(Some("main")), dec!(0.05));
+    assert_eq!(fees.percent(Some("vip")), dec!(0.03));
+    assert_eq!(fees.percent(None), dec!(0.05));
+    assert_eq!(fees.highest(ExchangeId::Binance), dec!(0.05));
+    let fees = fees.with_liquidity(Liquidity::Maker);
+    assert_eq!(fees.percent(Some("main")), dec!(-0.005));
+    assert_eq!(fees.percent(Some("vip")), dec!(-0.01));
+    assert_eq!(fees.highest(ExchangeId::Binance), dec!(-0.005));
+
+    let toml = format!(
+        r#"{}
+[[engine.execution.accounts]]
+name = "vip"
+api_key_var = "VIP_KEY"
+secret_key_var = "VIP_SECRET"
+fee_percent = "-0.01"
+"#,
+        EXECUTION
+    );
+    assert!(load_config("invalid", &toml).is_err());
+}
+
+fn rates(maker_percent: Decimal, taker_percent: Decimal) -> FeeRates {
+    FeeRates {
+        maker_percent,
+        taker_percent,
+    }
+}
+
+/// Two Binance accounts, a VIP one first, and Bybit on the default tier.
+fn schedule() -> FeeSchedule {
+    FeeSchedule::new(rates(dec!(0.05), dec!(0.1)))
+        .with_account(ExchangeId::Binance, "vip", rates(dec!(-0.025), dec!(0.04)))
+        .with_account(ExchangeId::Binance, "main", rates(dec!(-0.01), dec!(0.1)))
+}
+
+/// Trades on Binance's `vip` and `main` accounts and Bybit with `fees`, at
+/// a 0.1% threshold and floor; returns what was placed and the state.
+async fn trade(fees: FeeSchedule) -> (Arc<Ledger>, watch::Sender<ExecutionState>) {
+    let ledger = Ledger::new();
+    let fake = |id| Arc::new(FakeExchange::new(id, &ledger));
+    let (vip, main, bybit) = (
+        fake(ExchangeId::Binance),
+        fake(ExchangeId::Binance),
+        fake(ExchangeId::Bybit),
+    );
+    let account = |name: &str, exchange: &Arc<FakeExchange>| Account {
+        name: name.into(),
+        exchange: exchange.clone(),
+        symbols: Vec::new(),
+    };
+    let binance = Accounts::new(
+        "BTCUSDT",
+        AccountRouting::RoundRobin,
+        vec![account("vip", &vip), account("main", &main)],
+    );
+    let state = watch::Sender::new(ExecutionState::default());
+    let mut engine = ArbitrageEngine::new(
+        vec![Arc::new(binance), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(state.clone())
+    .with_recheck(dec!(0.1), Decimal::ZERO)
+    .with_fees(fees);
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&vip, &bybit]).await;
+
+    // Buy at 100.1, sell at 100.3: about 0.2% before fees.
+    vip.quote(dec!(100)).await;
+    bybit.quote(dec!(100.3)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    (ledger, state)
+}
+
+#[tokio::test(start_paused = true)]
+async fn taker_fees_leave_too_little() {
+    // 0.1% on Binance's main account and on Bybit.
+    let (ledger, _) = trade(schedule()).await;
+    assert!(ledger.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn maker_rebates_add_to_the_edge() {
+    let (_opportunities, opportunities_rx) = broadcast::channel(1);
+    let (_events, events_rx) = broadcast::channel(1);
+    let session = Session::start(
+        &QuoteBus::default(),
+        opportunities_rx,
+        events_rx,
+        &ExecutionState::default(),
+        dec!(0.1),
+        CancellationToken::new(),
+    )
+    .with_fees(schedule().with_liquidity(Liquidity::Maker));
+
+    // Binance's accounts earn at least 0.01%, Bybit's costs 0.05%.
+    let (ledger, state) = trade(schedule().with_liquidity(Liquidity::Maker)).await;
+    assert_eq!(
+        ledger.legs(),
+        [
+            (ExchangeId::Binance, LegSide::Buy),
+            (ExchangeId::Bybit, LegSide::Sell),
+        ]
+    );
+    let execution = state.borrow().clone();
+    let accounts: Vec<_> = execution
+        .orders
+        .iter()
+        .map(|o| o.account.as_deref())
+        .collect();
+    assert_eq!(accounts, [Some("vip"), None]);
+
+    // -0.025% of 1.001 on the VIP account, 0.05% of 1.003 on Bybit.
+    assert_eq!(
+        session.summary(&execution).fees,
+        dec!(-0.00025025) + dec!(0.0005015)
+    );
+}
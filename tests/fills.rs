@@ -0,0 +1,106 @@
+//! The normalized fill type and what is computed from it.
+
+use arbitrage_bot::{
+    config::{FxConfig, FxSource},
+    fills::Fill,
+    fx::Fx,
+    models::{ids::ExchangeId, money::Decimal},
+    state::LegSide,
+    tca,
+};
+use rust_decimal_macros::dec;
+
+fn fill(venue: ExchangeId, side: LegSide, price: Decimal, qty: Decimal, fee: Decimal) -> Fill {
+    Fill {
+        venue,
+        symbol: "BTCUSDT".into(),
+        side,
+        price,
+        qty,
+        fee,
+        fee_asset: "USDT".into(),
+        ts: 1_000,
+        order_id: "1".into(),
+        client_order_id: None,
+    }
+}
+
+/// `[fx]` with BNB at 600 USDT.
+fn bnb_fx() -> Fx {
+    let mut config = FxConfig::default();
+    config.currencies.insert(
+        "BNB".into(),
+        FxSource {
+            rate: Some(dec!(600)),
+            pegged: false,
+            ..FxSource::default()
+        },
+    );
+    Fx::new(&config)
+}
+
+#[test]
+fn fees_paid_in_bnb_are_converted_at_the_live_rate() {
+    let fx = bnb_fx();
+    let mut buy = fill(
+        ExchangeId::Binance,
+        LegSide::Buy,
+        dec!(100),
+        dec!(2),
+        dec!(0.0002),
+    );
+    buy.fee_asset = "BNB".into();
+    let sell = fill(
+        ExchangeId::Bybit,
+        LegSide::Sell,
+        dec!(101),
+        dec!(2),
+        dec!(0.11),
+    );
+    assert_eq!(buy.fee_in("USDT", Some(&fx)), Some(dec!(0.12)));
+    assert_eq!(buy.fee_in("USDT", None), None);
+
+    assert_eq!(sell.fee_in("USDT", None), Some(dec!(0.11)));
+
+    // An asset `[fx]` doesn't list stays unconverted.
+    buy.fee_asset = "BIT".into();
+    assert_eq!(buy.fee_in("USDT", Some(&fx)), None);
+}
+
+#[test]
+fn an_orders_fills_aggregate_for_tca() {
+    let mut fills = vec![
+        fill(
+            ExchangeId::Bybit,
+            LegSide::Sell,
+            dec!(100),
+            dec!(1),
+            dec!(0),
+        ),
+        fill(
+            ExchangeId::Bybit,
+            LegSide::Sell,
+            dec!(103),
+            dec!(2),
+            dec!(0),
+        ),
+    ];
+    fills[1].ts = 2_000;
+    fills[0].fee = dec!(0.05);
+    fills[1].fee = dec!(0.0001);
+    fills[1].fee_asset = "BNB".into();
+    assert_eq!(
+        tca::Fill::from_fills(&fills, true, Some(&bnb_fx())),
+        tca::Fill {
+            quantity: dec!(3),
+            avg_price: Some(dec!(102)),
+            updated_at_ms: 2_000,
+            done: true,
+            fee: Some(dec!(0.11)),
+        }
+    );
+    // Without a BNB rate the total isn't known.
+    assert_eq!(tca::Fill::from_fills(&fills, true, None).fee, None);
+    let empty = tca::Fill::from_fills(&[], false, None);
+    assert_eq!((empty.avg_price, empty.fee), (None, None));
+}
@@ -0,0 +1,442 @@
+//! Every payload in `fixtures/` through the parser or type that handles it
+//! in production. The parsers drop frames they can't read, so a schema
+//! change on the exchange side only shows up here: refresh the fixture from
+//! a capture (`[[tap]]`) and these tests say what no longer fits.
+
+use std::{fs, path::PathBuf, time::Duration};
+
+use arbitrage_bot::{
+    backtest::{klines, replay},
+    binance::{
+        api::BinanceOrderResponse,
+        rate_limits::{RateLimit, RateLimitType},
+        spot::SpotOrderResult,
+        trades::FutureTrade,
+    },
+    config::{FxConfig, FxSource},
+    error::TradingError,
+    fx::{BinanceTicker, Fx},
+    models::{ids::ExchangeId, orderbook::MarketType},
+    state::LegSide,
+    transfers::{AssetStatus, BinanceCoin, BybitCoinInfo, Withdrawal},
+    ws::handlers::{BinanceDepthParser, BybitOrderBookParser, MessageParser, TopOfBook},
+};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::Value;
+
+/// Fixtures the tests below cover; a new one has to be added here.
+const FIXTURES: &[&str] = &[
+    "binance_capital_config_getall.json",
+    "binance_futures_combined_depth5.json",
+    "binance_futures_depth20.json",
+    "binance_futures_depth5.json",
+    "binance_futures_klines_1m.json",
+    "binance_order_cancel.json",
+    "binance_order_error.json",
+    "binance_order_place.json",
+    "binance_order_status.json",
+    "binance_spot_depth.json",
+    "binance_spot_order_full.json",
+    "binance_subscribe_ack.json",
+    "binance_subscribe_error.json",
+    "binance_ticker_price.json",
+    "binance_user_trades.json",
+    "bybit_coin_query_info.json",
+    "bybit_kline_linear_1m.json",
+    "bybit_orderbook1_linear.json",
+    "bybit_orderbook1_linear_delta.json",
+    "bybit_orderbook1_spot.json",
+    "bybit_pong.json",
+    "bybit_subscribe_ack.json",
+];
+
+/// Order fields Binance sends that `BinanceOrderResult` leaves out on purpose.
+const IGNORED_ORDER_FIELDS: &[&str] = &["time"];
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+fn fixture(name: &str) -> String {
+    let path = fixtures_dir().join(name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn quote(parser: &impl MessageParser, name: &str) -> TopOfBook {
+    parser
+        .parse(fixture(name).trim())
+        .unwrap_or_else(|| panic!("{} didn't parse into a quote", name))
+}
+
+const LINEAR: BybitOrderBookParser = BybitOrderBookParser {
+    market_type: MarketType::Futures,
+};
+const SPOT: BybitOrderBookParser = BybitOrderBookParser {
+    market_type: MarketType::Spot,
+};
+
+fn assert_top(top: &TopOfBook, bid: Decimal, ask: Decimal, update_id: u64) {
+    assert_eq!(top.symbol, "BTCUSDT");
+    assert_eq!((top.bid, top.ask), (bid, ask));
+    assert_eq!(top.update_id, Some(update_id));
+}
+
+#[test]
+fn every_fixture_is_covered() {
+    let mut found: Vec<String> = fs::read_dir(fixtures_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".json"))
+        .collect();
+    found.sort();
+    assert_eq!(found, FIXTURES);
+    for name in FIXTURES {
+        let payload = fixture(name);
+        assert!(
+            serde_json::from_str::<Value>(&payload).is_ok(),
+            "{} isn't JSON",
+            name
+        );
+    }
+}
+
+#[test]
+fn binance_depth() {
+    let futures = quote(&BinanceDepthParser, "binance_futures_depth5.json");
+    assert_top(&futures, dec!(112543.10), dec!(112543.20), 8421339031987);
+    assert!(matches!(futures.market_type, MarketType::Futures));
+    // What's offered at the touch, which the re-check sizes orders by.
+    assert_eq!(
+        (futures.bid_size, futures.ask_size),
+        (Some(dec!(4.812)), Some(dec!(2.605)))
+    );
+
+    // `[feeds.binance] depth_levels = 20`: same event, more levels.
+    let deep = quote(&BinanceDepthParser, "binance_futures_depth20.json");
+    assert_top(&deep, dec!(112543.10), dec!(112543.20), 8421339033512);
+
+    let spot = quote(&BinanceDepthParser, "binance_spot_depth.json");
+    assert_top(&spot, dec!(112561.99), dec!(112562.00), 74125993107);
+    assert!(matches!(spot.market_type, MarketType::Spot));
+
+    // Combined streams wrap the event; the multiplexed feed unwraps `data`.
+    let combined: Value =
+        serde_json::from_str(&fixture("binance_futures_combined_depth5.json")).unwrap();
+    assert_eq!(combined["stream"], "btcusdt@depth5@100ms");
+    let top = BinanceDepthParser
+        .parse(&combined["data"].to_string())
+        .unwrap();
+    assert_top(&top, dec!(112543.20), dec!(112543.30), 8421339035371);
+}
+
+#[test]
+fn bybit_orderbook() {
+    let snapshot = quote(&LINEAR, "bybit_orderbook1_linear.json");
+    assert_top(&snapshot, dec!(112540.80), dec!(112540.90), 428716307725);
+    assert_eq!(
+        (snapshot.bid_size, snapshot.ask_size),
+        (Some(dec!(6.428)), Some(dec!(3.114)))
+    );
+    assert!(matches!(snapshot.market_type, MarketType::Futures));
+
+    let delta = quote(&LINEAR, "bybit_orderbook1_linear_delta.json");
+    assert_top(&delta, dec!(112540.70), dec!(112540.90), 428716307741);
+
+    let spot = quote(&SPOT, "bybit_orderbook1_spot.json");
+    assert_top(&spot, dec!(112555.3), dec!(112555.4), 78140235018);
+    assert!(matches!(spot.market_type, MarketType::Spot));
+}
+
+#[test]
+fn acks_errors_and_pongs_are_not_quotes() {
+    for name in ["binance_subscribe_ack.json", "binance_subscribe_error.json"] {
+        assert!(
+            BinanceDepthParser.parse(&fixture(name)).is_none(),
+            "{}",
+            name
+        );
+    }
+    for name in ["bybit_subscribe_ack.json", "bybit_pong.json"] {
+        assert!(LINEAR.parse(&fixture(name)).is_none(), "{}", name);
+    }
+}
+
+#[test]
+fn binance_order_responses_round_trip() {
+    for name in [
+        "binance_order_place.json",
+        "binance_order_cancel.json",
+        "binance_order_status.json",
+    ] {
+        let payload = fixture(name);
+        let response: BinanceOrderResponse = serde_json::from_str(&payload)
+            .unwrap_or_else(|e| panic!("{} doesn't deserialize: {}", name, e));
+        let reserialized = serde_json::to_value(&response).unwrap();
+        let result = response.into_result("order").unwrap();
+        assert_eq!(result.order_id, 325078477, "{}", name);
+
+        // Everything Binance sent survives the round trip, so a renamed or
+        // added field shows up as a difference.
+        let mut original: Value = serde_json::from_str(&payload).unwrap();
+        let original = original["result"].as_object_mut().unwrap();
+        for field in IGNORED_ORDER_FIELDS {
+            original.remove(*field);
+        }
+        let mut roundtripped = reserialized["result"].as_object().unwrap().clone();
+        roundtripped.retain(|_, v| !v.is_null());
+        assert_eq!(&roundtripped, original, "{}", name);
+    }
+}
+
+#[test]
+fn binance_order_error_becomes_rejected() {
+    let response: BinanceOrderResponse =
+        serde_json::from_str(&fixture("binance_order_error.json")).unwrap();
+    assert_eq!(response.status, 400);
+    let error = response.into_result("order.place").unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            TradingError::Rejected {
+                exchange: ExchangeId::Binance,
+                operation: "order.place",
+                code: -2019,
+                msg,
+            }
+                if msg == "Margin is insufficient."
+        ),
+        "{:?}",
+        error
+    );
+}
+
+#[test]
+fn binance_fills_carry_their_commission() {
+    let trades: Vec<FutureTrade> =
+        serde_json::from_str(&fixture("binance_user_trades.json")).unwrap();
+    let fills: Vec<_> = trades.iter().filter_map(FutureTrade::fill).collect();
+    assert_eq!(fills.len(), 2);
+    assert!(fills
+        .iter()
+        .all(|f| f.order_id == "325078477" && f.fee_asset == "BNB"));
+    assert_eq!(fills.iter().map(|f| f.qty).sum::<Decimal>(), dec!(0.010));
+    assert_eq!(
+        fills.iter().map(|f| f.fee).sum::<Decimal>(),
+        dec!(0.0001124)
+    );
+
+    let spot: SpotOrderResult =
+        serde_json::from_str(&fixture("binance_spot_order_full.json")).unwrap();
+    let fills = spot.fills(LegSide::Sell);
+    assert_eq!(fills.len(), 2);
+    assert!(fills
+        .iter()
+        .all(|f| f.side == LegSide::Sell && f.fee_asset == "USDT"));
+    assert_eq!(fills.iter().map(|f| f.fee).sum::<Decimal>(), dec!(1.12405));
+}
+
+#[test]
+fn binance_rate_limits_are_read_from_every_answer() {
+    for (name, windows) in [
+        ("binance_order_place.json", 3),
+        ("binance_order_error.json", 3),
+        ("binance_order_cancel.json", 1),
+        ("binance_order_status.json", 1),
+    ] {
+        let response: Value = serde_json::from_str(&fixture(name)).unwrap();
+        let limits = RateLimit::from_response(&response);
+        assert_eq!(limits.len(), windows, "{}", name);
+        let weight = limits
+            .iter()
+            .find(|l| l.rate_limit_type == RateLimitType::RequestWeight)
+            .unwrap();
+        assert_eq!((weight.limit, weight.count), (2400, 1), "{}", name);
+        assert_eq!(weight.window_ms(), Some(60_000), "{}", name);
+    }
+    let response: Value = serde_json::from_str(&fixture("binance_order_place.json")).unwrap();
+    let orders: Vec<_> = RateLimit::from_response(&response)
+        .into_iter()
+        .filter(|l| l.rate_limit_type == RateLimitType::Orders)
+        .filter_map(|l| l.window_ms())
+        .collect();
+    assert_eq!(orders, [10_000, 60_000]);
+}
+
+#[test]
+fn kline_spread_history() {
+    let binance = klines::binance_klines(&fixture("binance_futures_klines_1m.json")).unwrap();
+    let bybit = klines::bybit_klines(&fixture("bybit_kline_linear_1m.json")).unwrap();
+    assert_eq!(
+        binance[0],
+        klines::Kline {
+            open_time: 1757412720000,
+            close: dec!(112543.10),
+        }
+    );
+    // Oldest first, like Binance's.
+    assert_eq!(bybit[0].open_time, 1757412720000);
+    assert_eq!(bybit[3].close, dec!(112589.9));
+
+    // Binance has no kline for 12:14, so that minute is left out.
+    let samples = klines::spreads("btcusdt", &binance, &bybit);
+    let spreads: Vec<_> = samples
+        .iter()
+        .map(|s| (s.timestamp, (s.diff_percent * 1e6).round() / 1e6))
+        .collect();
+    assert_eq!(
+        spreads,
+        [
+            (1757412720, 0.000178),
+            (1757412780, 0.000622),
+            (1757412900, 0.000089)
+        ]
+    );
+    assert_eq!(
+        (samples[0].symbol.as_str(), samples[0].exchange_a.as_str()),
+        ("BTCUSDT", "binance")
+    );
+
+    let distribution = klines::Distribution::of("BTCUSDT", &samples, 0.95, 0.0).unwrap();
+    assert_eq!(distribution.samples, 3);
+    assert_eq!(distribution.p50, samples[0].diff_percent);
+    assert_eq!(distribution.max, samples[1].diff_percent);
+    assert_eq!(distribution.threshold, samples[1].diff_percent);
+    // The floor wins over a calmer history.
+    let floored = klines::Distribution::of("BTCUSDT", &samples, 0.95, 0.1).unwrap();
+    assert_eq!(floored.threshold, 0.1);
+    assert_eq!(klines::Distribution::of("BTCUSDT", &[], 0.95, 0.0), None);
+
+    // Written as a spread log `backtest walk-forward` reads back.
+    let path = std::env::temp_dir().join(format!("arb-klines-{}.csv", std::process::id()));
+    klines::write_csv(&path, &samples).unwrap();
+    let replayed = replay::load_csv(path.to_str().unwrap()).unwrap();
+    let _ = fs::remove_file(&path);
+    assert_eq!(replayed.len(), 3);
+    assert_eq!(replayed[1].timestamp, 1757412780);
+    assert!((replayed[1].diff_percent - samples[1].diff_percent).abs() < 1e-4);
+
+    let error = klines::bybit_klines(
+        r#"{"retCode":10001,"retMsg":"params error: symbol invalid","result":{}}"#,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("symbol invalid"), "{}", error);
+}
+
+#[test]
+fn asset_transfer_status() {
+    let open = AssetStatus {
+        deposit: true,
+        withdraw: true,
+        congested: false,
+    };
+
+    let coins: Vec<BinanceCoin> =
+        serde_json::from_str(&fixture("binance_capital_config_getall.json")).unwrap();
+    let binance: Vec<_> = coins
+        .iter()
+        .map(|c| (c.coin.as_str(), c.status()))
+        .collect();
+    assert_eq!(
+        binance,
+        [
+            ("BTC", open),
+            (
+                "WLFI",
+                AssetStatus {
+                    withdraw: false,
+                    ..open
+                }
+            ),
+            (
+                "ETH",
+                AssetStatus {
+                    congested: true,
+                    ..open
+                }
+            ),
+        ]
+    );
+
+    let info: BybitCoinInfo = serde_json::from_str(&fixture("bybit_coin_query_info.json")).unwrap();
+    assert_eq!(info.ret_code, 0);
+    let bybit: Vec<_> = info
+        .result
+        .unwrap()
+        .rows
+        .iter()
+        .map(|c| (c.coin.clone(), c.status()))
+        .collect();
+    assert_eq!(
+        bybit,
+        [
+            ("BTC".to_string(), open),
+            (
+                "WLFI".to_string(),
+                AssetStatus {
+                    deposit: false,
+                    ..open
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn cheapest_withdrawal_routes() {
+    let coins: Vec<BinanceCoin> =
+        serde_json::from_str(&fixture("binance_capital_config_getall.json")).unwrap();
+    let binance: Vec<_> = coins.iter().map(BinanceCoin::withdrawal).collect();
+    assert_eq!(
+        binance,
+        [
+            // BSC undercuts the BTC network.
+            Some(Withdrawal {
+                network: "BSC".into(),
+                fee: dec!(0.0000013),
+                eta: Some(Duration::from_secs(60)),
+            }),
+            // Withdrawals are suspended.
+            None,
+            Some(Withdrawal {
+                network: "ETH".into(),
+                fee: dec!(0.00011),
+                eta: Some(Duration::from_secs(120)),
+            }),
+        ]
+    );
+
+    let info: BybitCoinInfo = serde_json::from_str(&fixture("bybit_coin_query_info.json")).unwrap();
+    let rows = info.result.unwrap().rows;
+    assert_eq!(
+        rows[0].withdrawal(),
+        Some(Withdrawal {
+            network: "BTC".into(),
+            fee: dec!(0.0002),
+            eta: None,
+        })
+    );
+    assert_eq!(rows[1].withdrawal().map(|w| w.fee), Some(dec!(20)));
+}
+
+#[test]
+fn fx_rates_either_way_round() {
+    let tickers: Vec<BinanceTicker> =
+        serde_json::from_str(&fixture("binance_ticker_price.json")).unwrap();
+    let mut config = FxConfig::default();
+    for (currency, symbol) in [("USDC", "USDCUSDT"), ("TRY", "USDTTRY")] {
+        config.currencies.insert(
+            currency.into(),
+            FxSource {
+                symbol: Some(symbol.into()),
+                ..FxSource::default()
+            },
+        );
+    }
+    let fx = Fx::new(&config);
+    fx.apply(&tickers);
+    assert_eq!(fx.rate("USDC"), Some(dec!(0.9998)));
+    assert_eq!(fx.rate("TRY").map(|r| r.round_dp(6)), Some(dec!(0.024242)));
+    // Not configured, so not taken.
+    assert_eq!(fx.rate("FDUSD"), None);
+}
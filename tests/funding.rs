@@ -0,0 +1,230 @@
+//! Funding-aware entries: trades that would pay a large funding payment at
+//! the next settlement are delayed until it has passed, or skipped.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    config::{FundingAction, FundingConfig},
+    funding::{Funding, FundingReading},
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+    },
+    state::{self, ExecutionState, LegSide},
+    ws::exchanges::ArbitrageEngine,
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::watch,
+    time::{self, Duration},
+};
+
+use support::{
+    exchange::{until_subscribed, FakeExchange, Ledger, Placed},
+    load_config,
+};
+
+fn funding(action: FundingAction) -> Funding {
+    Funding::new(&FundingConfig {
+        enabled: true,
+        window_secs: 600,
+        max_cost_percent: dec!(0.01),
+        action,
+        ..FundingConfig::default()
+    })
+}
+
+/// A reading taken `age_ms` ago of a settlement `in_ms` from now.
+fn reading(rate: Decimal, in_ms: i64, age_ms: i64) -> Result<FundingReading, String> {
+    reading_at(state::now_ms(), rate, in_ms, age_ms)
+}
+
+/// [`reading`] as of `now`.
+fn reading_at(now: i64, rate: Decimal, in_ms: i64, age_ms: i64) -> Result<FundingReading, String> {
+    Ok(FundingReading {
+        rate,
+        next_funding_ms: now + in_ms,
+        at_ms: now - age_ms,
+    })
+}
+
+const EXECUTION: &str = r#"
+[engine.execution]
+enabled = true
+symbol = "BTCUSDT"
+quantity = "0.01"
+
+[engine.execution.funding]
+enabled = true
+"#;
+
+#[test]
+fn funding_config_is_validated() {
+    let config = load_config(
+        "valid",
+        &format!(
+            "{}action = \"skip\"\nmax_cost_percent = \"0.02\"",
+            EXECUTION
+        ),
+    )
+    .unwrap();
+    let funding = &config.engine.execution.funding;
+    assert!(funding.enabled);
+    assert_eq!(funding.action, FundingAction::Skip);
+    assert_eq!(funding.max_cost_percent, dec!(0.02));
+    assert_eq!(funding.window_secs, 900);
+
+    let invalid = [
+        "interval_secs = 0",
+        "max_cost_percent = \"-0.01\"",
+        "action = \"close\"",
+    ];
+    for (i, toml) in invalid.iter().enumerate() {
+        let toml = format!("{}{}", EXECUTION, toml);
+        assert!(
+            load_config(&format!("invalid-{}", i), &toml).is_err(),
+            "{}",
+            toml
+        );
+    }
+}
+
+const LEGS: [ExchangeId; 2] = [ExchangeId::Binance, ExchangeId::Bybit];
+
+#[test]
+fn only_fresh_readings_of_settlements_in_the_window_count() {
+    let funding = funding(FundingAction::Skip);
+    let btc = Symbol::intern("BTCUSDT");
+    let symbols = [btc, btc];
+    let now = state::now_ms();
+    assert_eq!(funding.cost(LEGS, symbols, now), None);
+
+    // Long Binance pays 0.03%, short Bybit collects 0.01%.
+    funding.record(
+        ExchangeId::Binance,
+        btc,
+        reading_at(now, dec!(0.0003), 60_000, 0),
+    );
+    funding.record(
+        ExchangeId::Bybit,
+        btc,
+        reading_at(now, dec!(0.0001), 120_000, 0),
+    );
+    let cost = funding.excessive(LEGS, symbols, now).unwrap();
+    assert_eq!(cost.percent, dec!(0.02));
+    assert_eq!(cost.settles_at_ms, now + 60_000);
+    assert!(cost
+        .to_string()
+        .starts_with("would pay 0.02% in funding at "));
+    // The other way round collects it.
+    let reversed = funding.cost([ExchangeId::Bybit, ExchangeId::Binance], symbols, now);
+    assert_eq!(reversed.unwrap().percent, dec!(-0.02));
+
+    // A settlement beyond the window doesn't count, nor does a stale or
+    // failed reading.
+    funding.record(
+        ExchangeId::Bybit,
+        btc,
+        reading_at(now, dec!(0.0001), 700_000, 0),
+    );
+    assert_eq!(
+        funding.cost(LEGS, symbols, now).unwrap().percent,
+        dec!(0.03)
+    );
+    funding.record(
+        ExchangeId::Binance,
+        btc,
+        reading_at(now, dec!(0.0003), 60_000, 181_000),
+    );
+    assert_eq!(funding.cost(LEGS, symbols, now), None);
+    funding.record(ExchangeId::Binance, btc, Err("HTTP 503".into()));
+    assert_eq!(funding.cost(LEGS, symbols, now), None);
+
+    // Within the allowance.
+    funding.record(
+        ExchangeId::Binance,
+        btc,
+        reading_at(now, dec!(0.0001), 60_000, 0),
+    );
+    assert!(funding.cost(LEGS, symbols, now).is_some());
+    assert_eq!(funding.excessive(LEGS, symbols, now), None);
+}
+
+/// An engine on Binance and Bybit following `funding`, and its orders.
+async fn engine(funding: Funding) -> (Arc<FakeExchange>, Arc<FakeExchange>, Arc<Ledger>) {
+    let ledger = Ledger::new();
+    let fake = |id| Arc::new(FakeExchange::new(id, &ledger));
+    let (binance, bybit) = (fake(ExchangeId::Binance), fake(ExchangeId::Bybit));
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(watch::Sender::new(ExecutionState::default()))
+    .with_funding(funding);
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+    (binance, bybit, ledger)
+}
+
+fn taken(ledger: &Ledger) -> Vec<(ExchangeId, LegSide)> {
+    ledger.take().iter().map(Placed::leg).collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn skip_drops_only_the_direction_that_pays() {
+    let funding = funding(FundingAction::Skip);
+    let btc = Symbol::intern("BTCUSDT");
+    // Longs on Binance pay 0.05% within a minute.
+    funding.record(ExchangeId::Binance, btc, reading(dec!(0.0005), 60_000, 0));
+    let (binance, bybit, ledger) = engine(funding).await;
+
+    // Buying on Binance would pay it.
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert!(taken(&ledger).is_empty());
+
+    // Selling there collects it.
+    bybit.quote(dec!(99)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        taken(&ledger),
+        [
+            (ExchangeId::Bybit, LegSide::Buy),
+            (ExchangeId::Binance, LegSide::Sell),
+        ]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn delay_holds_the_pair_until_the_settlement() {
+    let funding = funding(FundingAction::Delay);
+    let btc = Symbol::intern("BTCUSDT");
+    // Binance settles in 300ms, at 0.05% paid by longs.
+    funding.record(ExchangeId::Binance, btc, reading(dec!(0.0005), 300, 0));
+    let (binance, bybit, ledger) = engine(funding).await;
+
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert!(taken(&ledger).is_empty());
+    // The other direction waits too.
+    bybit.quote(dec!(99)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert!(taken(&ledger).is_empty());
+
+    // Settlements go by the wall clock.
+    std::thread::sleep(std::time::Duration::from_millis(400));
+    bybit.quote(dec!(98)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        taken(&ledger),
+        [
+            (ExchangeId::Bybit, LegSide::Buy),
+            (ExchangeId::Binance, LegSide::Sell),
+        ]
+    );
+}
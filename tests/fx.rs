@@ -0,0 +1,136 @@
+//! Quote currency conversion: symbols quoted in different currencies are
+//! compared at the configured rates, and spreads against a depegged one are
+//! left out instead of reported.
+
+use arbitrage_bot::{
+    config::{FxConfig, FxSource},
+    fx::Fx,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::{MarketTracker, MarketType},
+    },
+    notifications::{
+        alert_gate::AlertGate,
+        telegram::{AppAlert, Notification},
+    },
+};
+use rust_decimal_macros::dec;
+use tokio::sync::mpsc;
+
+/// USDC a hair under parity, FDUSD depegged by 1.3% and TRY floating, all
+/// fixed; EUR live, and not fetched here.
+fn fx() -> Fx {
+    let mut config = FxConfig::default();
+    for (currency, rate, pegged) in [
+        ("USDC", dec!(0.9998), true),
+        ("FDUSD", dec!(0.987), true),
+        ("TRY", dec!(0.0242), false),
+    ] {
+        config.currencies.insert(
+            currency.into(),
+            FxSource {
+                rate: Some(rate),
+                pegged,
+                ..FxSource::default()
+            },
+        );
+    }
+    config.currencies.insert(
+        "EUR".into(),
+        FxSource {
+            symbol: Some("EURUSDT".into()),
+            ..FxSource::default()
+        },
+    );
+    Fx::new(&config)
+}
+
+fn watching() -> (MarketTracker, mpsc::Receiver<Notification>) {
+    let (tx, rx) = mpsc::channel(4);
+    let tracker =
+        MarketTracker::new(dec!(1), Some(tx), AlertGate::new(dec!(5), dec!(1), 0)).with_fx(fx());
+    (tracker, rx)
+}
+
+fn quote(tracker: &mut MarketTracker, exchange: ExchangeId, symbol: &str, mid: Decimal) {
+    let symbol = Symbol::intern(symbol);
+    tracker.update(
+        exchange,
+        symbol,
+        mid - dec!(0.1),
+        mid + dec!(0.1),
+        MarketType::Spot,
+    );
+}
+
+fn next_alert(rx: &mut mpsc::Receiver<Notification>) -> Option<AppAlert> {
+    match rx.try_recv() {
+        Ok(Notification::Arbitrage(alert)) => Some(alert),
+        _ => None,
+    }
+}
+
+#[test]
+fn depegs_and_floating_rates() {
+    let fx = fx();
+    assert_eq!(fx.depeg("USDC"), None);
+    assert_eq!(fx.depeg("FDUSD"), Some(dec!(-1.3)));
+    // Not pegged, so never depegged.
+    assert_eq!(fx.depeg("TRY"), None);
+    assert_eq!(fx.depeg("USDT"), None);
+
+    fx.set_rate("USDC", dec!(1.006));
+    assert_eq!(fx.depeg("USDC"), Some(dec!(0.6)));
+}
+
+#[test]
+fn compares_across_quote_currencies() {
+    let (mut tracker, mut rx) = watching();
+    quote(&mut tracker, ExchangeId::Binance, "BTCUSDT", dec!(100));
+    quote(&mut tracker, ExchangeId::Bybit, "BTCUSDC", dec!(110));
+    let alert = next_alert(&mut rx).expect("a spread against BTCUSDT");
+    assert_eq!(alert.symbol, "BTCUSDT");
+    // 110 USDC at 0.9998.
+    assert_eq!(alert.mid_b, dec!(109.978));
+    assert_eq!(
+        alert.note.as_deref(),
+        Some("bybit quotes BTCUSDC, converted at 0.9998 USDT per USDC")
+    );
+
+    // A floating currency converts the same way.
+    let (mut tracker, mut rx) = watching();
+    quote(&mut tracker, ExchangeId::Binance, "ETHUSDT", dec!(2000));
+    quote(&mut tracker, ExchangeId::Bybit, "ETHTRY", dec!(90000));
+    assert_eq!(next_alert(&mut rx).map(|a| a.mid_b), Some(dec!(2178)));
+}
+
+#[test]
+fn depegged_spreads_are_not_arbitrage() {
+    let (mut tracker, mut rx) = watching();
+    quote(&mut tracker, ExchangeId::Binance, "BTCUSDT", dec!(100));
+    quote(&mut tracker, ExchangeId::Bybit, "BTCFDUSD", dec!(110));
+    assert!(next_alert(&mut rx).is_none());
+
+    // Both sides in FDUSD: the depeg cancels out.
+    let (mut tracker, mut rx) = watching();
+    quote(&mut tracker, ExchangeId::Binance, "SOLFDUSD", dec!(100));
+    quote(&mut tracker, ExchangeId::Bybit, "SOLFDUSD", dec!(110));
+    let alert = next_alert(&mut rx).expect("a spread within FDUSD");
+    assert_eq!(alert.symbol, "SOLUSDT");
+    assert_eq!(alert.note, None);
+}
+
+#[test]
+fn native_quotes_win_and_unknown_rates_wait() {
+    let (mut tracker, mut rx) = watching();
+    quote(&mut tracker, ExchangeId::Binance, "BTCUSDT", dec!(100));
+    // Binance quotes BTCUSDT itself, so its BTCUSDC is left alone.
+    quote(&mut tracker, ExchangeId::Binance, "BTCUSDC", dec!(110));
+    quote(&mut tracker, ExchangeId::Bybit, "BTCUSDT", dec!(100));
+    assert!(next_alert(&mut rx).is_none());
+
+    // No EUR rate yet: held back rather than compared unconverted.
+    quote(&mut tracker, ExchangeId::Bybit, "BTCEUR", dec!(90));
+    assert!(next_alert(&mut rx).is_none());
+}
@@ -0,0 +1,123 @@
+//! Catching up on the market over REST after a feed reconnects.
+
+mod support;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use arbitrage_bot::{
+    binance::ws_handler::EVENT_CHANNEL_CAPACITY,
+    control::{Control, ExecutionControl, Feeds},
+    gap_fill,
+    models::{ids::ExchangeId, orderbook::MarketTracker},
+    notifications::alert_gate::AlertGate,
+    ws::quote_bus::{Quote, QuoteBus},
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::{broadcast, watch},
+};
+use tokio_util::sync::CancellationToken;
+
+use support::{Flavor, MockExchange, WAIT};
+
+const BYBIT_TICKERS: &str = r#"{"retCode":0,"retMsg":"OK","result":{"category":"linear","list":[{"symbol":"BTCUSDT","bid1Price":"112480.50","ask1Price":"112480.60"}]}}"#;
+
+/// Answers every HTTP request with `body`; returns its URL and how many
+/// requests it took.
+async fn serve(body: &'static str) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counted = hits.clone();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            counted.fetch_add(1, Ordering::SeqCst);
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match socket.read(&mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+    (url, hits)
+}
+
+async fn next_quote(latest: &mut watch::Receiver<Option<Arc<Quote>>>, polled: bool) -> Arc<Quote> {
+    tokio::time::timeout(
+        WAIT,
+        latest.wait_for(|quote| quote.as_ref().is_some_and(|q| q.polled == polled)),
+    )
+    .await
+    .expect("no quote in time")
+    .expect("quote bus dropped")
+    .clone()
+    .unwrap()
+}
+
+#[tokio::test]
+async fn a_reconnected_feed_is_caught_up_over_rest() {
+    let mock = MockExchange::start(Flavor::Bybit);
+    let (rest, hits) = serve(BYBIT_TICKERS).await;
+    support::init_config(&[
+        ("wss://stream.bybit.com", mock.url()),
+        ("https://api.bybit.com", rest),
+    ]);
+    let quotes = QuoteBus::default();
+    let cancel = CancellationToken::new();
+    let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    let tracker = MarketTracker::new(dec!(1), None, AlertGate::new(dec!(5), dec!(1), 0)).spawn();
+    let control = Control::new(
+        quotes.clone(),
+        tracker,
+        Feeds::new(quotes.clone(), events.clone(), cancel.clone()),
+        watch::Sender::new(Default::default()),
+        ExecutionControl {
+            paused: false,
+            threshold_percent: dec!(0.1),
+        },
+        false,
+        dec!(5),
+        cancel.clone(),
+    );
+    gap_fill::spawn(
+        quotes.clone(),
+        control.clone(),
+        events.subscribe(),
+        cancel.clone(),
+    );
+    let mut latest = quotes.watch(ExchangeId::Bybit, "BTCUSDT");
+    control
+        .feeds()
+        .subscribe(ExchangeId::Bybit, &["BTCUSDT"])
+        .unwrap();
+
+    mock.wait_for_requests(1).await;
+    mock.push(support::BYBIT_ORDERBOOK);
+    let streamed = next_quote(&mut latest, false).await;
+    assert_eq!(streamed.top.bid, dec!(112540.80));
+    // Nothing to catch up on after the first connection.
+    assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+    // Back before anything streams again: the REST snapshot fills the gap.
+    mock.disconnect_all();
+    let snapshot = next_quote(&mut latest, true).await;
+    assert_eq!(snapshot.top.bid, dec!(112480.50));
+    assert_eq!(snapshot.top.ask, dec!(112480.60));
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    cancel.cancel();
+}
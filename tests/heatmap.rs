@@ -0,0 +1,120 @@
+//! The opportunity heatmap: counts and widest spreads per symbol and hour
+//! of day, the printed table, and keeping it across runs.
+
+use arbitrage_bot::{
+    config::Config,
+    heatmap::{Grid, Heatmap},
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::Opportunity,
+    },
+};
+use rust_decimal_macros::dec;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+const HOUR_MS: i64 = 3_600_000;
+/// 2024-01-01 00:00 UTC.
+const DAY_MS: i64 = 1_704_067_200_000;
+
+fn opportunity(symbol: &str, hour: i64, diff_percent: Decimal) -> Opportunity {
+    Opportunity {
+        symbol: Symbol::intern(symbol),
+        exchange_a: ExchangeId::Binance,
+        exchange_b: ExchangeId::Bybit,
+        mid_a: dec!(100),
+        mid_b: dec!(100) + diff_percent,
+        diff_percent,
+        at_ms: DAY_MS + hour * HOUR_MS + 1000,
+    }
+}
+
+#[test]
+fn opportunities_are_counted_by_symbol_and_hour() {
+    let mut grid = Grid::default();
+    for (symbol, hour, percent) in [
+        ("BTCUSDT", 14, dec!(0.6)),
+        ("BTCUSDT", 14, dec!(1.2)),
+        ("BTCUSDT", 2, dec!(0.5)),
+        ("ETHUSDT", 2, dec!(0.8)),
+        // The next day, same hour.
+        ("ETHUSDT", 26, dec!(0.7)),
+    ] {
+        grid.record(&opportunity(symbol, hour, percent));
+    }
+
+    assert_eq!(grid.since_ms, DAY_MS + 14 * HOUR_MS + 1000);
+    let btc = &grid.symbols["BTCUSDT"];
+    assert_eq!((btc[14].opportunities, btc[14].max_percent), (2, dec!(1.2)));
+    assert_eq!(btc[2].opportunities, 1);
+    assert_eq!(btc[3].opportunities, 0);
+    let eth = &grid.symbols["ETHUSDT"];
+    assert_eq!((eth[2].opportunities, eth[2].max_percent), (2, dec!(0.8)));
+    assert_eq!(grid.busiest("BTCUSDT"), Some(14));
+    assert_eq!(grid.busiest("ETHUSDT"), Some(2));
+    assert_eq!(grid.busiest("SOLUSDT"), None);
+    assert_eq!(grid.totals()[2], 3);
+    assert_eq!(grid.totals()[14], 2);
+
+    let table = grid.table(false);
+    let lines: Vec<_> = table.lines().collect();
+    assert!(lines[0].starts_with("UTC"), "{}", table);
+    assert!(lines[0].ends_with("   23  busiest"), "{}", table);
+    assert!(lines[1].starts_with("BTCUSDT"), "{}", table);
+    assert!(lines[1].ends_with("  14:00"), "{}", table);
+    assert!(lines[3].starts_with("all"), "{}", table);
+    let widest = grid.table(true);
+    assert!(widest.contains(" 1.2 "), "{}", widest);
+    assert!(!widest.contains("all"), "{}", widest);
+}
+
+#[test]
+fn the_grid_is_kept_across_runs() {
+    let path = std::env::temp_dir().join(format!("arb-heatmap-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(Grid::load(&path).unwrap(), None);
+
+    let heatmap = Heatmap::default();
+    heatmap.record(&opportunity("BTCUSDT", 9, dec!(0.4)));
+    heatmap.save(&path).unwrap();
+
+    // The next run carries on counting.
+    let heatmap = Heatmap::new(Grid::load(&path).unwrap().unwrap());
+    heatmap.record(&opportunity("BTCUSDT", 9, dec!(0.9)));
+    let grid = heatmap.grid();
+    assert_eq!(grid.symbols["BTCUSDT"][9].opportunities, 2);
+    assert_eq!(grid.symbols["BTCUSDT"][9].max_percent, dec!(0.9));
+    assert_eq!(grid.since_ms, DAY_MS + 9 * HOUR_MS + 1000);
+    let _ = std::fs::remove_file(&path);
+
+    let config_path = std::env::temp_dir().join(format!("arb-heatmap-{}.toml", std::process::id()));
+    std::fs::write(&config_path, "[engine]\nheatmap_file = \"heatmap.json\"").unwrap();
+    let config = Config::load(&config_path).unwrap();
+    let _ = std::fs::remove_file(&config_path);
+    assert_eq!(
+        config.engine.heatmap_file.as_deref(),
+        Some(std::path::Path::new("heatmap.json"))
+    );
+}
+
+#[tokio::test]
+async fn the_heatmap_follows_the_tracker() {
+    let (tx, rx) = broadcast::channel(16);
+    let heatmap = Heatmap::default();
+    let cancel = CancellationToken::new();
+    heatmap.follow(rx, cancel.clone());
+
+    tx.send(opportunity("BTCUSDT", 20, dec!(0.3))).unwrap();
+    tx.send(opportunity("BTCUSDT", 20, dec!(0.5))).unwrap();
+    while heatmap
+        .grid()
+        .symbols
+        .get("BTCUSDT")
+        .is_none_or(|cells| cells[20].opportunities < 2)
+    {
+        tokio::task::yield_now().await;
+    }
+    assert_eq!(heatmap.grid().symbols["BTCUSDT"][20].max_percent, dec!(0.5));
+    cancel.cancel();
+}
@@ -0,0 +1,307 @@
+//! Inventory mode: the balances execution tracks per exchange, how they are
+//! seeded from the config, and an engine that only trades what both legs
+//! hold, raises an alert once one side ends up with most of it and, when
+//! asked to, evens it out again with offsetting trades.
+
+mod support;
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use arbitrage_bot::{
+    config::RebalanceConfig,
+    models::money::Decimal,
+    notifications::telegram::Notification,
+    pauses::{PauseTarget, Pauses},
+    rebalance::Planner,
+    state::{Balance, ExecutionState, LegSide, OrderLeg},
+    transfers::{AssetStatus, TransferStatus},
+    ws::exchanges::{ArbitrageEngine, ExchangeId},
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::{mpsc, watch},
+    time,
+};
+
+use support::exchange::{until_subscribed, FakeExchange, Ledger};
+
+fn balance(base: Decimal, quote: Decimal) -> Balance {
+    Balance { base, quote }
+}
+
+fn leg(exchange: ExchangeId, side: LegSide, price: Decimal, order_id: Option<&str>) -> OrderLeg {
+    OrderLeg {
+        exchange,
+        symbol: "BTCUSDT".into(),
+        side,
+        price,
+        quantity: dec!(0.5),
+        order_id: order_id.map(Into::into),
+        account: None,
+        error: None,
+        placed_at_ms: 0,
+    }
+}
+
+#[test]
+fn orders_move_inventory_and_config_changes_reseed_it() {
+    let mut state = ExecutionState::default();
+    let config = [
+        (ExchangeId::Binance, balance(dec!(1), dec!(1000))),
+        (ExchangeId::Bybit, balance(dec!(1), dec!(1000))),
+    ];
+    assert_eq!(state.seed_inventory(config).len(), 2);
+
+    state.record(leg(ExchangeId::Binance, LegSide::Buy, dec!(100), Some("1")));
+    state.record(leg(ExchangeId::Bybit, LegSide::Sell, dec!(102), Some("2")));
+    // A failed order moves nothing.
+    state.record(leg(ExchangeId::Bybit, LegSide::Sell, dec!(102), None));
+    assert_eq!(
+        state.inventory[&ExchangeId::Binance].balance,
+        balance(dec!(1.5), dec!(950))
+    );
+    assert_eq!(
+        state.inventory[&ExchangeId::Bybit].balance,
+        balance(dec!(0.5), dec!(1051))
+    );
+    assert_eq!(
+        state.inventory_share(|b| b.base),
+        Some((ExchangeId::Binance, dec!(75)))
+    );
+
+    // A restart with the same config carries on from the tracked balances...
+    let mut restarted = state.clone();
+    assert!(restarted.seed_inventory(config).is_empty());
+    assert_eq!(restarted.inventory, state.inventory);
+
+    // ...while a changed balance (a rebalance) replaces the tracked one, and
+    // an exchange no longer configured is dropped.
+    let rebalanced = [(ExchangeId::Binance, balance(dec!(1), dec!(1000)))];
+    let mut restarted = state.clone();
+    assert!(restarted.seed_inventory(rebalanced).is_empty());
+    assert_eq!(restarted.inventory.len(), 1);
+    let rebalanced = [
+        (ExchangeId::Binance, balance(dec!(2), dec!(1000))),
+        (ExchangeId::Bybit, balance(dec!(1), dec!(1000))),
+    ];
+    assert_eq!(state.seed_inventory(rebalanced), [ExchangeId::Binance]);
+    assert_eq!(
+        state.inventory[&ExchangeId::Binance].balance,
+        balance(dec!(2), dec!(1000))
+    );
+    assert_eq!(
+        state.inventory[&ExchangeId::Bybit].balance,
+        balance(dec!(0.5), dec!(1051))
+    );
+}
+
+/// Spot fakes for Binance and Bybit, acknowledging `delay` after each
+/// order, and the ledger they share.
+fn spot_venues(delay: Duration) -> (Arc<FakeExchange>, Arc<FakeExchange>, Arc<Ledger>) {
+    let ledger = Ledger::new();
+    let fake = |id| Arc::new(FakeExchange::new(id, &ledger).spot().with_ack_delay(delay));
+    (fake(ExchangeId::Binance), fake(ExchangeId::Bybit), ledger)
+}
+
+#[tokio::test(start_paused = true)]
+async fn trades_only_what_the_legs_hold_and_alerts_on_skew() {
+    let (binance, bybit, ledger) = spot_venues(Duration::ZERO);
+    let mut state = ExecutionState::default();
+    // Bybit holds enough BTC to sell twice.
+    state.seed_inventory([
+        (ExchangeId::Binance, balance(dec!(0.02), dec!(10))),
+        (ExchangeId::Bybit, balance(dec!(0.02), dec!(10))),
+    ]);
+    let state = watch::Sender::new(state);
+    let (alerts, mut alert_rx) = mpsc::channel(4);
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(state.clone())
+    .with_inventory(dec!(70), Some(alerts));
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // Bybit 1% over Binance, for a minute: buy on Binance, sell on Bybit.
+    for _ in 0..60 {
+        binance.quote(dec!(100)).await;
+        bybit.quote(dec!(101)).await;
+        time::sleep(Duration::from_secs(1)).await;
+    }
+    assert_eq!(
+        ledger.legs(),
+        [
+            (ExchangeId::Binance, LegSide::Buy),
+            (ExchangeId::Bybit, LegSide::Sell),
+            (ExchangeId::Binance, LegSide::Buy),
+            (ExchangeId::Bybit, LegSide::Sell),
+        ]
+    );
+    let inventory = state.borrow().inventory.clone();
+    assert_eq!(inventory[&ExchangeId::Bybit].balance.base, dec!(0));
+    assert_eq!(inventory[&ExchangeId::Binance].balance.base, dec!(0.04));
+
+    // After the first trade Binance held 75% of the BTC; one alert only.
+    match alert_rx.try_recv() {
+        Ok(Notification::InventorySkew {
+            exchange,
+            asset,
+            percent,
+            limit,
+        }) => {
+            assert_eq!((exchange.as_str(), asset.as_str()), ("binance", "BTC"));
+            assert_eq!((percent, limit), (dec!(75), dec!(70)));
+        }
+        other => panic!("expected a skew alert, got {:?}", other),
+    }
+    assert!(alert_rx.try_recv().is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn rebalances_with_offsetting_trades_once_skewed() {
+    let (binance, bybit, ledger) = spot_venues(Duration::ZERO);
+    let mut state = ExecutionState::default();
+    state.seed_inventory([
+        (ExchangeId::Binance, balance(dec!(0.02), dec!(10))),
+        (ExchangeId::Bybit, balance(dec!(0.02), dec!(10))),
+    ]);
+    let state = watch::Sender::new(state);
+    // BTC can't be withdrawn from Binance, so trades are the only way back.
+    let transfers = TransferStatus::default();
+    transfers.update(
+        ExchangeId::Binance,
+        HashMap::from([(
+            "BTC".to_string(),
+            AssetStatus {
+                deposit: true,
+                withdraw: false,
+                congested: false,
+            },
+        )]),
+    );
+    let planner = Planner::new(
+        &RebalanceConfig {
+            enabled: true,
+            execute: true,
+            ..RebalanceConfig::default()
+        },
+        Some(transfers),
+    );
+    let (alerts, mut alert_rx) = mpsc::channel(4);
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(state.clone())
+    .with_inventory(dec!(70), Some(alerts))
+    .with_rebalancing(planner);
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101)).await;
+    time::sleep(Duration::from_secs(1)).await;
+
+    // The arbitrage, then the BTC it moved to Binance sold there and bought
+    // back on Bybit.
+    assert_eq!(
+        ledger.legs(),
+        [
+            (ExchangeId::Binance, LegSide::Buy),
+            (ExchangeId::Bybit, LegSide::Sell),
+            (ExchangeId::Binance, LegSide::Sell),
+            (ExchangeId::Bybit, LegSide::Buy),
+        ]
+    );
+    let inventory = state.borrow().inventory.clone();
+    assert_eq!(inventory[&ExchangeId::Binance].balance.base, dec!(0.02));
+    assert_eq!(inventory[&ExchangeId::Bybit].balance.base, dec!(0.02));
+
+    assert!(matches!(
+        alert_rx.try_recv(),
+        Ok(Notification::InventorySkew { .. })
+    ));
+    match alert_rx.try_recv() {
+        Ok(Notification::Rebalance { plan, executing }) => {
+            assert!(executing);
+            assert!(plan.contains("(blocked: BTC withdrawals suspended on binance)"));
+            assert!(plan.ends_with("→ offsetting trades"));
+        }
+        other => panic!("expected a rebalance plan, got {:?}", other),
+    }
+    assert!(alert_rx.try_recv().is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn offsetting_trades_wait_out_a_pause() {
+    let (binance, bybit, ledger) = spot_venues(Duration::from_millis(100));
+    let mut state = ExecutionState::default();
+    state.seed_inventory([
+        (ExchangeId::Binance, balance(dec!(0.02), dec!(10))),
+        (ExchangeId::Bybit, balance(dec!(0.02), dec!(10))),
+    ]);
+    let state = watch::Sender::new(state);
+    let transfers = TransferStatus::default();
+    transfers.update(
+        ExchangeId::Binance,
+        HashMap::from([(
+            "BTC".to_string(),
+            AssetStatus {
+                deposit: true,
+                withdraw: false,
+                congested: false,
+            },
+        )]),
+    );
+    let planner = Planner::new(
+        &RebalanceConfig {
+            enabled: true,
+            execute: true,
+            ..RebalanceConfig::default()
+        },
+        Some(transfers),
+    );
+    let pauses = Pauses::default();
+    let (alerts, mut alert_rx) = mpsc::channel(4);
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(state.clone())
+    .with_inventory(dec!(70), Some(alerts))
+    .with_rebalancing(planner)
+    .with_pauses(pauses.clone());
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101)).await;
+    // Binance is paused while the arbitrage's orders are out.
+    time::sleep(Duration::from_millis(10)).await;
+    pauses.set(PauseTarget::Exchange(ExchangeId::Binance), true);
+    time::sleep(Duration::from_secs(1)).await;
+
+    // The arbitrage only: selling the BTC back on Binance waits.
+    assert_eq!(
+        ledger.legs(),
+        [
+            (ExchangeId::Binance, LegSide::Buy),
+            (ExchangeId::Bybit, LegSide::Sell)
+        ]
+    );
+    assert!(matches!(
+        alert_rx.try_recv(),
+        Ok(Notification::InventorySkew { .. })
+    ));
+    match alert_rx.try_recv() {
+        Ok(Notification::Rebalance { plan, executing }) => {
+            assert!(!executing);
+            assert!(plan.ends_with("→ offsetting trades"), "{plan}");
+        }
+        other => panic!("expected a rebalance plan, got {:?}", other),
+    }
+}
@@ -0,0 +1,306 @@
+//! Per-venue latency: moving averages of ping and order round trips, the
+//! haircut they put on an opportunity's edge, the faster pair winning
+//! between similar edges, and the budget a trade's acknowledgements must
+//! arrive within.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    config::LatencyConfig,
+    latency::{Latency, Route},
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+    },
+    plan::{ExecutionPlan, LegStatus, PlannedLeg},
+    state::{ExecutionState, LegSide},
+    ws::exchanges::ArbitrageEngine,
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::watch,
+    time::{self, Duration},
+};
+
+use support::exchange::{until_subscribed, FakeExchange, Ledger};
+
+const BINANCE: ExchangeId = ExchangeId::Binance;
+const BYBIT: ExchangeId = ExchangeId::Bybit;
+
+#[test]
+fn averages_and_estimates() {
+    let latency = Latency::default();
+    assert_eq!(latency.pair(BINANCE, BYBIT), None);
+    assert_eq!(latency.haircut(BINANCE, BYBIT, dec!(1)), Decimal::ZERO);
+
+    latency.record_feed(BINANCE, Duration::from_millis(100));
+    latency.record_feed(BINANCE, Duration::from_millis(200));
+    let binance = latency.get(BINANCE);
+    assert_eq!(binance.feed, Some(Duration::from_millis(120)));
+    // No order timed yet: a ping round trip stands in for it.
+    assert_eq!(binance.expected(), Some(Duration::from_millis(180)));
+
+    latency.record_order(BINANCE, Duration::from_millis(50));
+    assert_eq!(
+        latency.get(BINANCE).expected(),
+        Some(Duration::from_millis(110))
+    );
+
+    // The slower exchange sets the pace.
+    latency.record_feed(BYBIT, Duration::from_millis(300));
+    assert_eq!(
+        latency.pair(BINANCE, BYBIT),
+        Some(Duration::from_millis(450))
+    );
+    assert_eq!(latency.haircut(BYBIT, BINANCE, dec!(2)), dec!(0.009));
+}
+
+#[test]
+fn similar_edges_go_to_the_faster_pair() {
+    let latency = Latency::default();
+    latency.record_order(BINANCE, Duration::from_millis(30));
+    latency.record_order(BYBIT, Duration::from_millis(80));
+    let route = |buy, sell, edge| Route { buy, sell, edge };
+    // Between two exchanges both directions take as long, so a route
+    // within Binance stands in for a faster pair.
+    let routes = [
+        route(BYBIT, BINANCE, dec!(0.0030)),
+        route(BINANCE, BINANCE, dec!(0.0028)),
+    ];
+    assert_eq!(latency.fastest(&routes, dec!(0.0005)), Some(routes[1]));
+    // Too far apart: the edge wins.
+    assert_eq!(latency.fastest(&routes, dec!(0.0001)), Some(routes[0]));
+    assert_eq!(latency.fastest(&[], dec!(0.0005)), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_haircut_uses_live_latency() {
+    let ledger = Ledger::new();
+    let fake =
+        |id| Arc::new(FakeExchange::new(id, &ledger).with_ack_delay(Duration::from_millis(40)));
+    let (binance, bybit) = (fake(BINANCE), fake(BYBIT));
+    let latency = Latency::default();
+    // 100ms for the quote and 200ms for the order: 0.3% at 1% a second.
+    latency.record_feed(BINANCE, Duration::from_millis(200));
+    latency.record_feed(BYBIT, Duration::from_millis(200));
+    let config = LatencyConfig {
+        haircut_percent_per_sec: dec!(1),
+        ..LatencyConfig::default()
+    };
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(watch::Sender::new(ExecutionState::default()))
+    .with_latency(latency.clone(), config);
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // About 0.2% gross: gone by the time the orders land.
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(100.3)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert!(ledger.is_empty());
+
+    // About 0.5% gross clears it.
+    bybit.quote(dec!(100.6)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(ledger.len(), 2);
+
+    // The acknowledgements were timed: 40ms in.
+    assert_eq!(latency.get(BINANCE).order, Some(Duration::from_millis(40)));
+    assert_eq!(latency.get(BYBIT).order, Some(Duration::from_millis(40)));
+}
+
+fn slow(id: ExchangeId, ack_ms: u64, ledger: &Arc<Ledger>) -> Arc<FakeExchange> {
+    Arc::new(FakeExchange::new(id, ledger).with_ack_delay(Duration::from_millis(ack_ms)))
+}
+
+/// What `exchange` was sent, with the IDs it acknowledged them under.
+fn placed(ledger: &Ledger, exchange: ExchangeId) -> Vec<(LegSide, String)> {
+    ledger
+        .orders()
+        .into_iter()
+        .filter(|o| o.exchange == exchange)
+        .map(|o| (o.side, o.order_id))
+        .collect()
+}
+
+/// Trades on `binance` and `bybit` within a `budget_ms` budget.
+fn budgeted(
+    binance: &Arc<FakeExchange>,
+    bybit: &Arc<FakeExchange>,
+    budget_ms: u64,
+    state: &watch::Sender<ExecutionState>,
+) -> ArbitrageEngine {
+    let config = LatencyConfig {
+        budget_ms,
+        ..LatencyConfig::default()
+    };
+    ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(state.clone())
+    .with_latency(Latency::default(), config)
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_late_ack_fails_the_trade_and_is_cancelled() {
+    let ledger = Ledger::new();
+    let (binance, bybit) = (slow(BINANCE, 40, &ledger), slow(BYBIT, 500, &ledger));
+    let state = watch::Sender::new(ExecutionState::default());
+    let mut engine = budgeted(&binance, &bybit, 100, &state);
+
+    let plan = ExecutionPlan::arbitrage(
+        Symbol::intern("BTCUSDT"),
+        PlannedLeg::buy(BINANCE, dec!(100), dec!(0.01)),
+        PlannedLeg::sell(BYBIT, dec!(101), dec!(0.01)),
+    )
+    .with_rollback(true);
+    engine.execute_plan(plan).await;
+
+    let report = state.borrow().plans[0].clone();
+    // Binance's buy was in on time, and is unwound.
+    assert!(matches!(report.legs[0].status, LegStatus::Unwound { .. }));
+    // Bybit's sell went through later, and was cancelled right away.
+    let sells = placed(&ledger, BYBIT);
+    assert_eq!(sells.len(), 1);
+    assert_eq!(sells[0].0, LegSide::Sell);
+    assert_eq!(
+        report.legs[1].status,
+        LegStatus::LateCancelled {
+            order_id: sells[0].1.clone()
+        }
+    );
+    assert!(report.failed());
+    assert_eq!(ledger.cancels(), [sells[0].1.clone()]);
+    // Recorded once, as failed: the buy, the late sell and the unwind.
+    let orders = state.borrow().orders.clone();
+    assert_eq!(orders.len(), 3);
+    let late: Vec<_> = orders.iter().filter(|o| o.exchange == BYBIT).collect();
+    assert_eq!(late.len(), 1);
+    assert!(late[0].order_id.is_none() && late[0].error.is_some());
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_late_order_that_wont_cancel_is_unwound() {
+    let ledger = Ledger::new();
+    let (binance, bybit) = (slow(BINANCE, 40, &ledger), slow(BYBIT, 500, &ledger));
+    bybit.fail_cancels(true);
+    let state = watch::Sender::new(ExecutionState::default());
+    let mut engine = budgeted(&binance, &bybit, 100, &state);
+
+    let plan = ExecutionPlan::arbitrage(
+        Symbol::intern("BTCUSDT"),
+        PlannedLeg::buy(BINANCE, dec!(100), dec!(0.01)),
+        PlannedLeg::sell(BYBIT, dec!(101), dec!(0.01)),
+    )
+    .with_rollback(true);
+    engine.execute_plan(plan).await;
+
+    let report = state.borrow().plans[0].clone();
+    // Both legs went through, so both are unwound: Bybit's sell by a buy.
+    assert!(matches!(report.legs[0].status, LegStatus::Unwound { .. }));
+    assert!(matches!(report.legs[1].status, LegStatus::Unwound { .. }));
+    assert_eq!(report.open_legs().count(), 0);
+    let sides: Vec<_> = placed(&ledger, BYBIT).into_iter().map(|o| o.0).collect();
+    assert_eq!(sides, [LegSide::Sell, LegSide::Buy]);
+    assert_eq!(ledger.cancels().len(), 1);
+    // The late sell is recorded once, as placed, next to the other three.
+    let orders = state.borrow().orders.clone();
+    assert_eq!(orders.len(), 4);
+    assert!(orders.iter().all(|o| o.order_id.is_some()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_late_first_stage_aborts_the_next() {
+    let ledger = Ledger::new();
+    let (binance, bybit) = (slow(BINANCE, 500, &ledger), slow(BYBIT, 40, &ledger));
+    let state = watch::Sender::new(ExecutionState::default());
+    let mut engine = budgeted(&binance, &bybit, 100, &state);
+
+    let plan = ExecutionPlan::new(Symbol::intern("BTCUSDT"))
+        .then([PlannedLeg::buy(BINANCE, dec!(100), dec!(0.01))])
+        .then([PlannedLeg::sell(BYBIT, dec!(101), dec!(0.01))]);
+    engine.execute_plan(plan).await;
+
+    let report = state.borrow().plans[0].clone();
+    assert!(matches!(
+        report.legs[0].status,
+        LegStatus::LateCancelled { .. }
+    ));
+    assert_eq!(report.legs[1].status, LegStatus::Skipped);
+    assert!(placed(&ledger, BYBIT).is_empty());
+    let buys = placed(&ledger, BINANCE);
+    assert_eq!(buys.len(), 1);
+    assert_eq!(buys[0].0, LegSide::Buy);
+    assert_eq!(ledger.cancels(), [buys[0].1.clone()]);
+
+    // Without a budget the same plan waits for the slow ack.
+    let state = watch::Sender::new(ExecutionState::default());
+    let mut engine = budgeted(&binance, &bybit, 0, &state);
+    let plan = ExecutionPlan::new(Symbol::intern("BTCUSDT"))
+        .then([PlannedLeg::buy(BINANCE, dec!(100), dec!(0.01))])
+        .then([PlannedLeg::sell(BYBIT, dec!(101), dec!(0.01))]);
+    engine.execute_plan(plan).await;
+    assert!(state.borrow().plans[0].is_complete());
+}
+
+#[tokio::test(start_paused = true)]
+async fn an_order_never_answered_is_looked_up_and_cancelled() {
+    let ledger = Ledger::new();
+    let (binance, bybit) = (slow(BINANCE, 40, &ledger), slow(BYBIT, 40, &ledger));
+    bybit.go_silent(true);
+    let state = watch::Sender::new(ExecutionState::default());
+    let mut engine = budgeted(&binance, &bybit, 100, &state);
+
+    let plan = ExecutionPlan::arbitrage(
+        Symbol::intern("BTCUSDT"),
+        PlannedLeg::buy(BINANCE, dec!(100), dec!(0.01)),
+        PlannedLeg::sell(BYBIT, dec!(101), dec!(0.01)),
+    )
+    .with_rollback(true);
+    // Bybit never answers: the engine stops waiting for it.
+    engine.execute_plan(plan).await;
+
+    let report = state.borrow().plans[0].clone();
+    let sells = placed(&ledger, BYBIT);
+    assert_eq!(sells.len(), 1);
+    assert_eq!(
+        report.legs[1].status,
+        LegStatus::LateCancelled {
+            order_id: sells[0].1.clone()
+        }
+    );
+    assert_eq!(ledger.cancels(), [sells[0].1.clone()]);
+    assert!(matches!(report.legs[0].status, LegStatus::Unwound { .. }));
+}
+
+#[tokio::test(start_paused = true)]
+async fn under_a_budget_the_sell_waits_for_the_buy() {
+    let ledger = Ledger::new();
+    let (binance, bybit) = (slow(BINANCE, 500, &ledger), slow(BYBIT, 40, &ledger));
+    let state = watch::Sender::new(ExecutionState::default());
+    let mut engine = budgeted(&binance, &bybit, 100, &state);
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(100.6)).await;
+    time::sleep(Duration::from_secs(1)).await;
+
+    // Binance's buy was late, so Bybit's sell never went out.
+    assert_eq!(placed(&ledger, BINANCE).len(), 1);
+    assert!(placed(&ledger, BYBIT).is_empty());
+    let report = state.borrow().plans[0].clone();
+    assert!(matches!(
+        report.legs[0].status,
+        LegStatus::LateCancelled { .. }
+    ));
+    assert_eq!(report.legs[1].status, LegStatus::Skipped);
+}
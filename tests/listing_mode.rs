@@ -0,0 +1,154 @@
+//! Listing mode: spreads on thin pairs only count while both venues show
+//! enough depth and trades, with thresholds per venue.
+
+use std::collections::HashMap;
+
+use arbitrage_bot::{
+    config::{LiquidityThresholds, ListingModeConfig},
+    listing_mode::{Liquidity, ListingMode},
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::{MarketTracker, MarketType},
+    },
+    notifications::{alert_gate::AlertGate, telegram::Notification},
+    state,
+};
+use rust_decimal_macros::dec;
+use tokio::sync::mpsc;
+
+/// WLFIUSDT in listing mode: 1000 of depth and 10 trades a minute, but
+/// Binance's book is trusted with half of that.
+fn listing_mode() -> ListingMode {
+    ListingMode::new(&ListingModeConfig {
+        enabled: true,
+        symbols: vec!["wlfiusdt".into()],
+        min_depth: dec!(1000),
+        min_trades_per_minute: 10,
+        exchanges: HashMap::from([(
+            "binance".to_string(),
+            LiquidityThresholds {
+                min_depth: dec!(500),
+                min_trades_per_minute: 5,
+            },
+        )]),
+        ..ListingModeConfig::default()
+    })
+}
+
+/// A reading with `depth` on both sides, taken `age_ms` ago.
+fn liquidity(depth: Decimal, trades_per_minute: u32, age_ms: i64) -> Result<Liquidity, String> {
+    Ok(Liquidity {
+        bid_depth: depth,
+        ask_depth: depth,
+        trades_per_minute,
+        at_ms: state::now_ms() - age_ms,
+    })
+}
+
+#[test]
+fn each_venue_is_held_to_its_own_thresholds() {
+    let listing = listing_mode();
+    let wlfi = Symbol::intern("WLFIUSDT");
+    assert!(listing.applies(wlfi));
+    assert_eq!(
+        listing.shortfall(ExchangeId::Bybit, wlfi).as_deref(),
+        Some("no reading yet")
+    );
+
+    listing.record(ExchangeId::Binance, wlfi, liquidity(dec!(600), 6, 0));
+    listing.record(ExchangeId::Bybit, wlfi, liquidity(dec!(600), 50, 0));
+    assert_eq!(listing.shortfall(ExchangeId::Binance, wlfi), None);
+    assert_eq!(
+        listing.shortfall(ExchangeId::Bybit, wlfi).as_deref(),
+        Some("depth 600 is under 1000")
+    );
+    let thin = listing
+        .ineligible(wlfi, [ExchangeId::Binance, ExchangeId::Bybit])
+        .unwrap();
+    assert_eq!(thin.exchange, ExchangeId::Bybit);
+    assert_eq!(
+        thin.to_string(),
+        "bybit is too thin: depth 600 is under 1000"
+    );
+
+    listing.record(ExchangeId::Bybit, wlfi, liquidity(dec!(5000), 9, 0));
+    assert_eq!(
+        listing.shortfall(ExchangeId::Bybit, wlfi).as_deref(),
+        Some("9 trade(s) a minute, under 10")
+    );
+    listing.record(ExchangeId::Bybit, wlfi, liquidity(dec!(5000), 10, 0));
+    assert_eq!(
+        listing.ineligible(wlfi, [ExchangeId::Binance, ExchangeId::Bybit]),
+        None
+    );
+
+    // Three intervals (15s each) old is too old, and a failure shows nothing.
+    listing.record(ExchangeId::Bybit, wlfi, liquidity(dec!(5000), 10, 46_000));
+    assert_eq!(
+        listing.shortfall(ExchangeId::Bybit, wlfi).as_deref(),
+        Some("reading is stale")
+    );
+    listing.record(ExchangeId::Bybit, wlfi, Err("HTTP 503".into()));
+    assert_eq!(
+        listing.shortfall(ExchangeId::Bybit, wlfi).as_deref(),
+        Some("reading failed: HTTP 503")
+    );
+
+    // Other symbols are left alone.
+    let btc = Symbol::intern("BTCUSDT");
+    assert!(!listing.applies(btc));
+    assert_eq!(
+        listing.ineligible(btc, [ExchangeId::Binance, ExchangeId::Bybit]),
+        None
+    );
+}
+
+#[test]
+fn a_one_sided_book_has_no_depth() {
+    let liquidity = Liquidity::measure(&[(dec!(1), dec!(1_000_000))], &[], 100, dec!(1), 0);
+    assert_eq!(liquidity.bid_depth, Decimal::ZERO);
+    assert_eq!(liquidity.ask_depth, Decimal::ZERO);
+}
+
+fn quote(tracker: &mut MarketTracker, exchange: ExchangeId, symbol: &str, mid: Decimal) {
+    tracker.update(
+        exchange,
+        Symbol::intern(symbol),
+        mid - dec!(0.01),
+        mid + dec!(0.01),
+        MarketType::Futures,
+    );
+}
+
+fn alerted(rx: &mut mpsc::Receiver<Notification>) -> Vec<String> {
+    let mut symbols = Vec::new();
+    while let Ok(notification) = rx.try_recv() {
+        if let Notification::Arbitrage(alert) = notification {
+            symbols.push(alert.symbol);
+        }
+    }
+    symbols
+}
+
+#[tokio::test]
+async fn thin_spreads_are_not_alerted() {
+    let listing = listing_mode();
+    let wlfi = Symbol::intern("WLFIUSDT");
+    let (tx, mut rx) = mpsc::channel(8);
+    let mut tracker = MarketTracker::new(dec!(0.01), Some(tx), AlertGate::new(dec!(5), dec!(1), 0))
+        .with_listing_mode(listing.clone());
+
+    // A 10% spread on WLFI with no readings yet, and one on BTC.
+    quote(&mut tracker, ExchangeId::Binance, "WLFIUSDT", dec!(0.20));
+    quote(&mut tracker, ExchangeId::Bybit, "WLFIUSDT", dec!(0.22));
+    quote(&mut tracker, ExchangeId::Binance, "BTCUSDT", dec!(100));
+    quote(&mut tracker, ExchangeId::Bybit, "BTCUSDT", dec!(110));
+    assert_eq!(alerted(&mut rx), ["BTCUSDT"]);
+
+    // Once both venues show enough, WLFI's spread counts.
+    listing.record(ExchangeId::Binance, wlfi, liquidity(dec!(500), 5, 0));
+    listing.record(ExchangeId::Bybit, wlfi, liquidity(dec!(1000), 10, 0));
+    quote(&mut tracker, ExchangeId::Bybit, "WLFIUSDT", dec!(0.23));
+    assert_eq!(alerted(&mut rx), ["WLFIUSDT"]);
+}
@@ -0,0 +1,87 @@
+//! REST liveness: failed probes in a row impair an exchange, and execution
+//! leaves trades with a leg on it alone until a probe goes through again.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    liveness::{Liveness, Transition},
+    models::ids::ExchangeId,
+    state::{ExecutionState, LegSide},
+    ws::exchanges::ArbitrageEngine,
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::watch,
+    time::{self, Duration},
+};
+
+use support::exchange::{until_subscribed, FakeExchange, Ledger};
+
+#[test]
+fn failures_in_a_row_impair_and_a_success_recovers() {
+    let liveness = Liveness::new(3);
+    let timeout = || Err("timed out".to_string());
+
+    assert_eq!(liveness.record(ExchangeId::Bybit, timeout()), None);
+    assert_eq!(liveness.record(ExchangeId::Bybit, timeout()), None);
+    // A success in between starts the count over.
+    assert_eq!(liveness.record(ExchangeId::Bybit, Ok(())), None);
+    assert_eq!(liveness.record(ExchangeId::Bybit, timeout()), None);
+    assert_eq!(liveness.record(ExchangeId::Bybit, timeout()), None);
+    assert!(!liveness.is_impaired(ExchangeId::Bybit));
+
+    assert_eq!(
+        liveness.record(ExchangeId::Bybit, timeout()),
+        Some(Transition::Impaired)
+    );
+    assert!(liveness.is_impaired(ExchangeId::Bybit));
+    assert!(!liveness.is_impaired(ExchangeId::Binance));
+    // Further failures don't announce it again.
+    assert_eq!(liveness.record(ExchangeId::Bybit, timeout()), None);
+    let state = liveness.get(ExchangeId::Bybit);
+    assert_eq!(state.failures, 4);
+    assert_eq!(state.error.as_deref(), Some("timed out"));
+
+    assert_eq!(
+        liveness.record(ExchangeId::Bybit, Ok(())),
+        Some(Transition::Recovered)
+    );
+    assert!(!liveness.is_impaired(ExchangeId::Bybit));
+}
+
+#[tokio::test(start_paused = true)]
+async fn impaired_exchanges_are_not_traded_on() {
+    let ledger = Ledger::new();
+    let fake = |id| Arc::new(FakeExchange::new(id, &ledger));
+    let (binance, bybit) = (fake(ExchangeId::Binance), fake(ExchangeId::Bybit));
+    let liveness = Liveness::new(1);
+    liveness.record(ExchangeId::Bybit, Err("HTTP 503".into()));
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(watch::Sender::new(ExecutionState::default()))
+    .with_liveness(liveness.clone());
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // Bybit's quotes still come in, but its REST API is failing.
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert!(ledger.is_empty());
+
+    liveness.record(ExchangeId::Bybit, Ok(()));
+    bybit.quote(dec!(101)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        ledger.prices(),
+        [
+            (ExchangeId::Binance, LegSide::Buy, dec!(100.1)),
+            (ExchangeId::Bybit, LegSide::Sell, dec!(101)),
+        ]
+    );
+}
@@ -0,0 +1,205 @@
+//! Pausing one exchange or one symbol: from config, the control commands
+//! and Telegram, and the tracker and execution leaving it alone while the
+//! rest carries on.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    control::{Command, Control, ExecutionControl, Feeds},
+    error::ControlError,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::{MarketTracker, MarketType},
+    },
+    notifications::{alert_gate::AlertGate, telegram::Notification, telegram_commands::BotCommand},
+    pauses::{PauseTarget, Pauses},
+    state::LegSide,
+    ws::{exchanges::ArbitrageEngine, quote_bus::QuoteBus},
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::{broadcast, mpsc, watch},
+    time::{self, Duration},
+};
+use tokio_util::sync::CancellationToken;
+
+use support::{
+    exchange::{until_subscribed, FakeExchange, Ledger},
+    load_config,
+};
+
+#[test]
+fn pause_settings_are_validated() {
+    let config = load_config("default", "").unwrap();
+    assert_eq!(Pauses::new(&config.pauses).current(), Default::default());
+
+    let config = load_config(
+        "valid",
+        "[pauses]\nexchanges = [\"bybit\"]\nsymbols = [\"wlfiusdt\"]",
+    )
+    .unwrap();
+    let pauses = Pauses::new(&config.pauses);
+    assert!(pauses.is_paused(PauseTarget::Exchange(ExchangeId::Bybit)));
+    assert!(!pauses.is_paused(PauseTarget::Exchange(ExchangeId::Binance)));
+    assert!(pauses.is_paused(PauseTarget::Symbol(Symbol::intern("WLFIUSDT"))));
+
+    for (i, toml) in [
+        "exchanges = [\"okx\"]",
+        "symbols = [\"\"]",
+        "symbols = [\"BTC/USDT\"]",
+    ]
+    .iter()
+    .enumerate()
+    {
+        let toml = format!("[pauses]\n{}", toml);
+        assert!(
+            load_config(&format!("invalid-{}", i), &toml).is_err(),
+            "{}",
+            toml
+        );
+    }
+}
+
+fn quote(tracker: &mut MarketTracker, exchange: ExchangeId, symbol: &str, mid: Decimal) {
+    tracker.update(
+        exchange,
+        Symbol::intern(symbol),
+        mid - dec!(0.01),
+        mid + dec!(0.01),
+        MarketType::Futures,
+    );
+}
+
+fn alerted(rx: &mut mpsc::Receiver<Notification>) -> Vec<String> {
+    let mut symbols = Vec::new();
+    while let Ok(notification) = rx.try_recv() {
+        if let Notification::Arbitrage(alert) = notification {
+            symbols.push(alert.symbol);
+        }
+    }
+    symbols
+}
+
+#[tokio::test]
+async fn paused_spreads_are_not_alerted() {
+    let pauses = Pauses::default();
+    let (tx, mut rx) = mpsc::channel(8);
+    let mut tracker = MarketTracker::new(dec!(0.01), Some(tx), AlertGate::new(dec!(5), dec!(1), 0))
+        .with_pauses(pauses.clone());
+
+    pauses.set(PauseTarget::Symbol(Symbol::intern("ETHUSDT")), true);
+    quote(&mut tracker, ExchangeId::Binance, "ETHUSDT", dec!(100));
+    quote(&mut tracker, ExchangeId::Bybit, "ETHUSDT", dec!(110));
+    quote(&mut tracker, ExchangeId::Binance, "BTCUSDT", dec!(100));
+    quote(&mut tracker, ExchangeId::Bybit, "BTCUSDT", dec!(110));
+    assert_eq!(alerted(&mut rx), ["BTCUSDT"]);
+
+    // With Bybit paused, nothing has two legs left.
+    pauses.set(PauseTarget::Symbol(Symbol::intern("ETHUSDT")), false);
+    pauses.set(PauseTarget::Exchange(ExchangeId::Bybit), true);
+    quote(&mut tracker, ExchangeId::Bybit, "ETHUSDT", dec!(111));
+    assert!(alerted(&mut rx).is_empty());
+
+    pauses.set(PauseTarget::Exchange(ExchangeId::Bybit), false);
+    quote(&mut tracker, ExchangeId::Bybit, "ETHUSDT", dec!(112));
+    assert_eq!(alerted(&mut rx), ["ETHUSDT"]);
+}
+
+fn start_control() -> Control {
+    let quotes = QuoteBus::default();
+    let cancel = CancellationToken::new();
+    let tracker = MarketTracker::new(dec!(1), None, AlertGate::new(dec!(5), dec!(1), 0)).spawn();
+    let (events, _) = broadcast::channel(1);
+    Control::new(
+        quotes.clone(),
+        tracker,
+        Feeds::new(quotes, events, cancel.clone()),
+        watch::Sender::new(Default::default()),
+        ExecutionControl {
+            paused: false,
+            threshold_percent: dec!(0.1),
+        },
+        true,
+        dec!(5),
+        cancel,
+    )
+}
+
+#[tokio::test]
+async fn pauses_change_through_control_and_telegram() {
+    let control = start_control();
+    let run = |text: &str| {
+        BotCommand::parse(text)
+            .unwrap()
+            .unwrap()
+            .run(&control, "telegram:42")
+    };
+
+    assert_eq!(run("/pause bybit"), "⏸️ Paused: bybit");
+    assert_eq!(run("/pause wlfiusdt"), "⏸️ Paused: bybit, WLFIUSDT");
+    let status = control.status();
+    assert!(status.pauses.exchanges.contains(&ExchangeId::Bybit));
+    assert!(status.pauses.symbols.contains("WLFIUSDT"));
+    assert!(!status.paused, "the rest of execution carries on");
+
+    assert_eq!(run("/resume BYBIT"), "⏸️ Paused: WLFIUSDT");
+    control
+        .apply(
+            serde_json::from_str::<Command>(
+                r#"{"command":"venue","symbol":"WLFIUSDT","paused":false}"#,
+            )
+            .unwrap(),
+            "mqtt",
+        )
+        .await
+        .unwrap();
+    assert_eq!(run("/pauses"), "▶️ Nothing is paused.");
+
+    assert!(BotCommand::parse("/pause BTC/USDT").unwrap().is_err());
+    let both = serde_json::from_str::<Command>(
+        r#"{"command":"venue","exchange":"bybit","symbol":"BTCUSDT","paused":true}"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        control.apply(both, "mqtt").await,
+        Err(ControlError::InvalidPause(_))
+    ));
+    assert!(control.status().pauses.exchanges.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn paused_venues_are_not_traded_on() {
+    let ledger = Ledger::new();
+    let fake = |id| Arc::new(FakeExchange::new(id, &ledger));
+    let (binance, bybit) = (fake(ExchangeId::Binance), fake(ExchangeId::Bybit));
+    let pauses = Pauses::default();
+    pauses.set(PauseTarget::Exchange(ExchangeId::Bybit), true);
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_pauses(pauses.clone());
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // About 1%, with Bybit paused.
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101.1)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert!(ledger.is_empty());
+
+    pauses.set(PauseTarget::Exchange(ExchangeId::Bybit), false);
+    bybit.quote(dec!(101.1)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        ledger.legs(),
+        [
+            (ExchangeId::Binance, LegSide::Buy),
+            (ExchangeId::Bybit, LegSide::Sell),
+        ]
+    );
+}
@@ -0,0 +1,165 @@
+//! Execution plans: stages placed one after the other, legs of a stage at
+//! once, the rest skipped after a failure, and the legs that went through
+//! unwound, reduce-only, when the plan asks for it.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    models::ids::{ExchangeId, Symbol},
+    plan::{ExecutionPlan, LegStatus, PlannedLeg},
+    state::{ExecutionState, LegSide},
+    ws::exchanges::ArbitrageEngine,
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::watch,
+    time::{self, Duration},
+};
+
+use support::exchange::{until_subscribed, FakeExchange, Ledger};
+
+/// Binance and Bybit, Bybit rejecting every sell.
+fn exchanges(ledger: &Arc<Ledger>) -> (Arc<FakeExchange>, Arc<FakeExchange>) {
+    let bybit = FakeExchange::new(ExchangeId::Bybit, ledger);
+    bybit.reject_sells(true);
+    (
+        Arc::new(FakeExchange::new(ExchangeId::Binance, ledger)),
+        Arc::new(bybit),
+    )
+}
+
+/// Orders sent reduce-only.
+fn exits(ledger: &Ledger) -> usize {
+    ledger.orders().iter().filter(|o| o.exit).count()
+}
+
+#[tokio::test(start_paused = true)]
+async fn stages_stop_at_the_first_failure() {
+    let ledger = Ledger::new();
+    let (binance, bybit) = exchanges(&ledger);
+    let state = watch::Sender::new(ExecutionState::default());
+    let mut engine = ArbitrageEngine::new(vec![binance, bybit], dec!(0.001), dec!(0.01))
+        .with_state(state.clone());
+
+    let btc = Symbol::intern("BTCUSDT");
+    let plan = ExecutionPlan::new(btc)
+        .then([PlannedLeg::buy(ExchangeId::Binance, dec!(100), dec!(0.01))])
+        .then([
+            PlannedLeg::buy(ExchangeId::Binance, dec!(100), dec!(0.01)),
+            PlannedLeg::sell(ExchangeId::Bybit, dec!(101), dec!(0.01)),
+        ])
+        .then([PlannedLeg::sell(ExchangeId::Binance, dec!(102), dec!(0.02))]);
+    engine.execute_plan(plan).await;
+
+    // The last stage never went out.
+    assert_eq!(
+        ledger.prices(),
+        [
+            (ExchangeId::Binance, LegSide::Buy, dec!(100)),
+            (ExchangeId::Binance, LegSide::Buy, dec!(100)),
+            (ExchangeId::Bybit, LegSide::Sell, dec!(101)),
+        ]
+    );
+    let state = state.borrow();
+    assert_eq!(state.planned, 1);
+    let report = &state.plans[0];
+    let statuses: Vec<_> = report.legs.iter().map(|l| &l.status).collect();
+    assert!(matches!(
+        statuses[..],
+        [
+            LegStatus::Placed { .. },
+            LegStatus::Placed { .. },
+            LegStatus::Failed { .. },
+            LegStatus::Skipped
+        ]
+    ));
+    assert_eq!(
+        report.legs.iter().map(|l| l.stage).collect::<Vec<_>>(),
+        [0, 1, 1, 2]
+    );
+    // Without rollback both buys stay open.
+    assert_eq!(report.open_legs().count(), 2);
+    assert_eq!(state.exposure[&ExchangeId::Binance], dec!(0.02));
+    assert_eq!(state.orders.len(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_failed_leg_unwinds_the_other() {
+    let ledger = Ledger::new();
+    let (binance, bybit) = exchanges(&ledger);
+    let state = watch::Sender::new(ExecutionState::default());
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(state.clone())
+    .with_rollback(true);
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // Buy on Binance at 100.1, sell on Bybit at 101: the sell is rejected.
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101)).await;
+    time::sleep(Duration::from_secs(1)).await;
+
+    // The buy is sold back at Binance's bid, reduce-only.
+    assert_eq!(exits(&ledger), 1);
+    assert_eq!(
+        ledger.prices(),
+        [
+            (ExchangeId::Binance, LegSide::Buy, dec!(100.1)),
+            (ExchangeId::Bybit, LegSide::Sell, dec!(101)),
+            (ExchangeId::Binance, LegSide::Sell, dec!(100)),
+        ]
+    );
+    let state = state.borrow();
+    let report = &state.plans[0];
+    match &report.legs[0].status {
+        LegStatus::Unwound { order_id } => assert_eq!(order_id, "binance-3"),
+        other => panic!("expected the buy unwound, got {:?}", other),
+    }
+    assert!(matches!(report.legs[1].status, LegStatus::Failed { .. }));
+    assert_eq!(report.open_legs().count(), 0);
+    assert!(!state.is_exposed());
+}
+
+#[tokio::test(start_paused = true)]
+async fn unwinds_never_grow_a_position() {
+    let ledger = Ledger::new();
+    let (binance, bybit) = exchanges(&ledger);
+    // Short on Binance from earlier trades: the buy shrinks that, and
+    // selling it back would grow it again.
+    let mut earlier = ExecutionState::default();
+    earlier.exposure.insert(ExchangeId::Binance, dec!(-0.05));
+    let state = watch::Sender::new(earlier);
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(state.clone());
+
+    let plan = ExecutionPlan::arbitrage(
+        Symbol::intern("BTCUSDT"),
+        PlannedLeg::buy(ExchangeId::Binance, dec!(100), dec!(0.01)),
+        PlannedLeg::sell(ExchangeId::Bybit, dec!(101), dec!(0.01)),
+    )
+    .with_rollback(true);
+    engine.execute_plan(plan).await;
+
+    // The unwind never went out.
+    assert_eq!(ledger.len(), 2);
+    assert_eq!(exits(&ledger), 0);
+    let state = state.borrow();
+    match &state.plans[0].legs[0].status {
+        LegStatus::UnwindFailed { error, .. } => {
+            assert!(error.contains("would not reduce"), "{}", error)
+        }
+        other => panic!("expected the unwind refused, got {:?}", other),
+    }
+    assert_eq!(state.exposure[&ExchangeId::Binance], dec!(-0.04));
+    assert!(state.orders[2].error.is_some());
+}
@@ -0,0 +1,386 @@
+//! Per-strategy capital and accounting: each strategy's book, trades refused
+//! beyond its capital, and one disabled at its limits while the others go on.
+
+mod support;
+
+use std::{collections::HashMap, sync::Arc};
+
+use arbitrage_bot::{
+    config::StrategyConfig,
+    contracts::{Contract, Contracts},
+    control::Signal,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+    },
+    plan::{ExecutionPlan, LegStatus, PlanReport, PlannedLeg},
+    portfolio::{self, Portfolio},
+    state::{self, ExecutionState, LegSide, OrderLeg},
+    ws::exchanges::ArbitrageEngine,
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::{mpsc, watch},
+    time::{self, Duration},
+};
+
+use support::{
+    exchange::{until_subscribed, FakeExchange, Ledger, Placed},
+    load_config,
+};
+
+const EXECUTION: &str = r#"
+[engine.execution]
+enabled = true
+symbol = "BTCUSDT"
+quantity = "0.01"
+"#;
+
+#[test]
+fn strategy_config_is_validated() {
+    let config = load_config(
+        "valid",
+        &format!(
+            r#"{}
+[engine.execution.strategies.spread]
+capital = "5000"
+max_loss = "100"
+
+[engine.execution.strategies.momentum]
+enabled = false
+max_failed = 3
+"#,
+            EXECUTION
+        ),
+    )
+    .unwrap();
+    let strategies = &config.engine.execution.strategies;
+    assert!(strategies["spread"].enabled);
+    assert_eq!(strategies["spread"].capital, Some(dec!(5000)));
+    assert_eq!(strategies["spread"].max_failed, None);
+    assert!(!strategies["momentum"].enabled);
+    assert_eq!(strategies["momentum"].max_failed, Some(3));
+
+    let invalid = [
+        "[engine.execution.strategies.spread]\ncapital = \"0\"",
+        "[engine.execution.strategies.spread]\nmax_loss = \"-10\"",
+        "[engine.execution.strategies.spread]\nmax_failed = 0",
+        "[engine.execution.strategies.\" \"]\ncapital = \"100\"",
+        "[engine.execution.strategies.spread]\nbudget = \"100\"",
+    ];
+    for (i, toml) in invalid.iter().enumerate() {
+        let toml = format!("{}{}", EXECUTION, toml);
+        assert!(
+            load_config(&format!("invalid-{}", i), &toml).is_err(),
+            "{}",
+            toml
+        );
+    }
+}
+
+/// One BTC bought on Binance at `buy` and sold on Bybit at `sell` for
+/// `strategy`, with the sell placed or failed.
+fn report(id: u64, strategy: &str, buy: Decimal, sell: Decimal, sold: bool) -> PlanReport {
+    let plan = ExecutionPlan::arbitrage(
+        Symbol::intern("BTCUSDT"),
+        PlannedLeg::buy(ExchangeId::Binance, buy, dec!(1)),
+        PlannedLeg::sell(ExchangeId::Bybit, sell, dec!(1)),
+    )
+    .with_strategy(strategy);
+    let mut report = PlanReport::new(id, &plan, state::now_ms());
+    report.legs[0].status = LegStatus::Placed {
+        order_id: format!("{}-buy", id),
+    };
+    report.legs[1].status = if sold {
+        LegStatus::Placed {
+            order_id: format!("{}-sell", id),
+        }
+    } else {
+        LegStatus::Failed {
+            error: "rejected".into(),
+        }
+    };
+    report
+}
+
+fn unwind(order_id: &str, price: Decimal) -> OrderLeg {
+    OrderLeg {
+        exchange: ExchangeId::Binance,
+        symbol: "BTCUSDT".into(),
+        side: LegSide::Sell,
+        price,
+        quantity: dec!(1),
+        order_id: Some(order_id.into()),
+        account: None,
+        error: None,
+        placed_at_ms: state::now_ms(),
+    }
+}
+
+#[test]
+fn books_keep_each_strategy_apart() {
+    let contracts = Contracts::default();
+    let mut portfolio = Portfolio::default();
+    portfolio.record(
+        &report(1, "spread", dec!(100), dec!(101), true),
+        &[],
+        &contracts,
+    );
+    portfolio.record(
+        &report(2, "desk", dec!(100), dec!(102), true),
+        &[],
+        &contracts,
+    );
+
+    // The desk's buy was unwound at 99 after its sell failed.
+    let mut failed = report(3, "desk", dec!(100), dec!(102), false);
+    failed.legs[0].status = LegStatus::Unwound {
+        order_id: "3-unwind".into(),
+    };
+    portfolio.record(&failed, &[unwind("3-unwind", dec!(99))], &contracts);
+
+    let spread = portfolio.get("spread").unwrap();
+    assert_eq!((spread.trades, spread.failed, spread.pnl), (1, 0, dec!(1)));
+    assert_eq!(spread.exposure[&ExchangeId::Binance], dec!(1));
+    assert_eq!(spread.exposure[&ExchangeId::Bybit], dec!(-1));
+    assert_eq!(spread.deployed(&contracts), dec!(201));
+    let desk = portfolio.get("desk").unwrap();
+    assert_eq!((desk.trades, desk.failed, desk.pnl), (2, 1, dec!(1)));
+    assert_eq!(desk.exposure[&ExchangeId::Binance], dec!(1));
+
+    // A leg left open makes nothing yet.
+    portfolio.record(
+        &report(4, "spread", dec!(100), dec!(90), false),
+        &[],
+        &contracts,
+    );
+    let spread = portfolio.get("spread").unwrap();
+    assert_eq!((spread.failed, spread.pnl), (1, dec!(1)));
+    assert_eq!(spread.exposure[&ExchangeId::Binance], dec!(2));
+
+    // Inverse contracts are valued at their face.
+    let inverse = Contracts::default().with(ExchangeId::Bybit, Contract::inverse(dec!(100)));
+    assert_eq!(
+        portfolio.get("spread").unwrap().deployed(&inverse),
+        dec!(300)
+    );
+}
+
+#[test]
+fn limits_refuse_and_disable_one_strategy() {
+    let contracts = Contracts::default();
+    let limits = StrategyConfig {
+        capital: Some(dec!(300)),
+        max_loss: Some(dec!(5)),
+        max_failed: Some(2),
+        ..StrategyConfig::default()
+    };
+    let mut portfolio = Portfolio::default();
+    assert_eq!(
+        portfolio.refusal("spread", Some(&limits), dec!(201), &contracts),
+        None
+    );
+    portfolio.record(
+        &report(1, "spread", dec!(100), dec!(101), true),
+        &[],
+        &contracts,
+    );
+    assert_eq!(
+        portfolio
+            .refusal("spread", Some(&limits), dec!(201), &contracts)
+            .unwrap(),
+        "would have 402 of its 300 capital deployed"
+    );
+    // Other strategies have their own capital, or none.
+    assert_eq!(
+        portfolio.refusal("desk", Some(&limits), dec!(201), &contracts),
+        None
+    );
+    assert_eq!(
+        portfolio.refusal("desk", None, dec!(10_000), &contracts),
+        None
+    );
+
+    assert_eq!(portfolio.enforce("spread", Some(&limits)), None);
+    portfolio.record(
+        &report(2, "spread", dec!(100), dec!(94), true),
+        &[],
+        &contracts,
+    );
+    assert_eq!(
+        portfolio.enforce("spread", Some(&limits)).unwrap(),
+        "lost 5"
+    );
+    // Only once.
+    assert_eq!(portfolio.enforce("spread", Some(&limits)), None);
+    assert_eq!(
+        portfolio
+            .refusal("spread", Some(&limits), dec!(1), &contracts)
+            .unwrap(),
+        "is disabled: lost 5"
+    );
+
+    // Failed trades count too.
+    for id in 3..5 {
+        portfolio.record(
+            &report(id, "desk", dec!(100), dec!(101), false),
+            &[],
+            &contracts,
+        );
+    }
+    assert_eq!(
+        portfolio.enforce("desk", Some(&limits)).unwrap(),
+        "2 failed trade(s)"
+    );
+
+    // Enabling it again keeps its book.
+    let book = portfolio.set_enabled("spread", true);
+    assert_eq!((book.disabled.as_deref(), book.trades), (None, 2));
+    assert_eq!(
+        portfolio
+            .refusal(
+                "spread",
+                Some(&StrategyConfig {
+                    enabled: false,
+                    ..limits
+                }),
+                dec!(1),
+                &contracts
+            )
+            .unwrap(),
+        "is disabled in the config"
+    );
+}
+
+struct Market {
+    binance: Arc<FakeExchange>,
+    bybit: Arc<FakeExchange>,
+    ledger: Arc<Ledger>,
+    signals: mpsc::Sender<Signal>,
+    state: watch::Sender<ExecutionState>,
+}
+
+impl Market {
+    /// An engine on Binance and Bybit trading 0.01 BTC under `strategies`.
+    async fn start(strategies: HashMap<String, StrategyConfig>) -> Self {
+        let ledger = Ledger::new();
+        let fake = |id| Arc::new(FakeExchange::new(id, &ledger));
+        let (binance, bybit) = (fake(ExchangeId::Binance), fake(ExchangeId::Bybit));
+        let (signals, signals_rx) = mpsc::channel(4);
+        let state = watch::Sender::new(ExecutionState::default());
+        let mut engine = ArbitrageEngine::new(
+            vec![binance.clone(), bybit.clone()],
+            dec!(0.001),
+            dec!(0.01),
+        )
+        .with_state(state.clone())
+        .with_signals(signals_rx)
+        .with_strategies(strategies);
+        tokio::spawn(async move { engine.run().await });
+        until_subscribed(&[&binance, &bybit]).await;
+        Self {
+            binance,
+            bybit,
+            ledger,
+            signals,
+            state,
+        }
+    }
+
+    /// Bybit's bid about 0.9% over Binance's ask, then once execution has
+    /// cooled down.
+    async fn opportunity(&self) {
+        self.binance.quote(dec!(100)).await;
+        self.bybit.quote(dec!(101)).await;
+        time::sleep(Duration::from_secs(6)).await;
+    }
+
+    /// A signal from `source` for the same trade, then once execution has
+    /// cooled down.
+    async fn signal(&self, source: &str) {
+        self.signals
+            .send(Signal {
+                symbol: "BTCUSDT".into(),
+                buy: ExchangeId::Binance,
+                sell: ExchangeId::Bybit,
+                source: Some(source.into()),
+            })
+            .await
+            .unwrap();
+        time::sleep(Duration::from_secs(6)).await;
+    }
+
+    fn taken(&self) -> Vec<(ExchangeId, LegSide)> {
+        self.ledger.take().iter().map(Placed::leg).collect()
+    }
+}
+
+const TRADE: [(ExchangeId, LegSide); 2] = [
+    (ExchangeId::Binance, LegSide::Buy),
+    (ExchangeId::Bybit, LegSide::Sell),
+];
+
+#[tokio::test(start_paused = true)]
+async fn trades_beyond_capital_are_refused() {
+    // Room for one trade of about 2 USDT, not two.
+    let strategies = HashMap::from([(
+        portfolio::SPREAD.to_string(),
+        StrategyConfig {
+            capital: Some(dec!(3)),
+            ..StrategyConfig::default()
+        },
+    )]);
+    let market = Market::start(strategies).await;
+
+    market.opportunity().await;
+    assert_eq!(market.taken(), TRADE);
+    market.opportunity().await;
+    assert!(market.taken().is_empty());
+
+    // Signals are another strategy, with no cap.
+    market.signal("desk").await;
+    assert_eq!(market.taken(), TRADE);
+    let state = market.state.borrow();
+    assert_eq!(state.portfolio.get("spread").unwrap().trades, 1);
+    assert_eq!(state.portfolio.get("desk").unwrap().trades, 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_failing_strategy_is_disabled_while_others_trade() {
+    let strategies = HashMap::from([(
+        portfolio::SPREAD.to_string(),
+        StrategyConfig {
+            max_failed: Some(1),
+            ..StrategyConfig::default()
+        },
+    )]);
+    let market = Market::start(strategies).await;
+
+    // Bybit rejects the sell.
+    market.bybit.reject_sells(true);
+    market.opportunity().await;
+    assert_eq!(market.taken(), TRADE);
+    market.bybit.reject_sells(false);
+    let spread = market
+        .state
+        .borrow()
+        .portfolio
+        .get("spread")
+        .cloned()
+        .unwrap();
+    assert_eq!(spread.failed, 1);
+    assert_eq!(spread.disabled.as_deref(), Some("1 failed trade(s)"));
+
+    market.opportunity().await;
+    assert!(market.taken().is_empty());
+    market.signal("desk").await;
+    assert_eq!(market.taken(), TRADE);
+
+    // Enabled again, it trades.
+    market
+        .state
+        .send_modify(|state| _ = state.portfolio.set_enabled("spread", true));
+    market.bybit.quote(dec!(100)).await;
+    market.opportunity().await;
+    assert_eq!(market.taken(), TRADE);
+}
@@ -0,0 +1,107 @@
+//! Pacing Binance requests by the usage the WS API reports: which windows
+//! a request counts towards, readings from a past window, and the settings.
+
+mod support;
+
+use arbitrage_bot::{
+    binance::rate_limits::{RateLimit, RateLimitType, RateLimiter},
+    config::RateLimitConfig,
+};
+
+use support::load_config;
+
+fn limit(
+    kind: RateLimitType,
+    interval: &str,
+    interval_num: u32,
+    limit: u32,
+    count: u32,
+) -> RateLimit {
+    RateLimit {
+        rate_limit_type: kind,
+        interval: interval.into(),
+        interval_num,
+        limit,
+        count,
+    }
+}
+
+#[test]
+fn rate_limit_settings_are_validated() {
+    let config = load_config("default", "").unwrap();
+    assert!(config.engine.execution.rate_limits.enabled);
+    assert_eq!(config.engine.execution.rate_limits.slow_down_percent, 80);
+
+    for (i, percent) in [0, 101].iter().enumerate() {
+        let toml = format!(
+            "[engine.execution]\nenabled = true\nquantity = 0.01\n[engine.execution.rate_limits]\nslow_down_percent = {}",
+            percent
+        );
+        assert!(
+            load_config(&format!("invalid-{}", i), &toml).is_err(),
+            "{}",
+            toml
+        );
+    }
+}
+
+#[test]
+fn request_weight_holds_back_every_request() {
+    let mut limiter = RateLimiter::default();
+    // 30s into the minute, the weight used up.
+    let now = 30_000;
+    limiter.record(
+        &[
+            limit(RateLimitType::Orders, "SECOND", 10, 300, 1),
+            limit(RateLimitType::RequestWeight, "MINUTE", 1, 2400, 2400),
+        ],
+        now,
+    );
+    assert_eq!(limiter.delay(false, now).as_millis(), 30_000);
+    assert_eq!(limiter.delay(true, now).as_millis(), 30_000);
+    assert_eq!(
+        limiter.remaining(RateLimitType::RequestWeight, now),
+        Some(0)
+    );
+    assert_eq!(limiter.remaining(RateLimitType::Orders, now), Some(299));
+}
+
+#[test]
+fn the_most_used_window_sets_the_pace() {
+    let mut limiter = RateLimiter::default();
+    let now = 61_000;
+    limiter.record(
+        &[
+            // 90% used, 9s left: 9000ms / 30.
+            limit(RateLimitType::Orders, "SECOND", 10, 300, 270),
+            // 85% used, 59s left: 59000ms / 180.
+            limit(RateLimitType::Orders, "MINUTE", 1, 1200, 1020),
+        ],
+        now,
+    );
+    assert_eq!(limiter.delay(true, now).as_millis(), 327);
+    assert_eq!(limiter.remaining(RateLimitType::Orders, now), Some(30));
+
+    // Once the 10s window resets only the minute one is left.
+    assert_eq!(limiter.delay(true, 70_000).as_millis(), 277);
+    assert_eq!(limiter.remaining(RateLimitType::Orders, 70_000), Some(180));
+    // And in the next minute nothing is known.
+    assert!(limiter.delay(true, 120_000).is_zero());
+    assert_eq!(limiter.remaining(RateLimitType::Orders, 120_000), None);
+}
+
+#[test]
+fn unknown_windows_are_ignored_and_pacing_can_be_off() {
+    let mut limiter = RateLimiter::default();
+    limiter.record(&[limit(RateLimitType::Other, "FORTNIGHT", 1, 10, 10)], 0);
+    assert!(limiter.delay(true, 0).is_zero());
+
+    let mut off = RateLimiter::new(&RateLimitConfig {
+        enabled: false,
+        ..RateLimitConfig::default()
+    });
+    off.record(&[limit(RateLimitType::Orders, "SECOND", 10, 300, 300)], 0);
+    assert!(off.delay(true, 0).is_zero());
+    // Still tracked.
+    assert_eq!(off.remaining(RateLimitType::Orders, 0), Some(0));
+}
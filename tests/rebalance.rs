@@ -0,0 +1,209 @@
+//! Rebalancing plans: how much of each currency has to move, what moving it
+//! costs by transfer and by offsetting trades, and which one is recommended.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use arbitrage_bot::{
+    config::RebalanceConfig,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+    },
+    rebalance::{Method, Planner},
+    state::{Balance, Inventory},
+    transfers::{AssetStatus, TransferStatus, Withdrawal},
+};
+use rust_decimal_macros::dec;
+
+const OPEN: AssetStatus = AssetStatus {
+    deposit: true,
+    withdraw: true,
+    congested: false,
+};
+
+fn inventory(
+    binance: (Decimal, Decimal),
+    bybit: (Decimal, Decimal),
+) -> BTreeMap<ExchangeId, Inventory> {
+    [(ExchangeId::Binance, binance), (ExchangeId::Bybit, bybit)]
+        .into_iter()
+        .map(|(exchange, (base, quote))| {
+            let balance = Balance { base, quote };
+            (
+                exchange,
+                Inventory {
+                    seeded: balance,
+                    balance,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Binance 100 / 100.1, Bybit 101 / 101.1.
+fn quotes(exchange: ExchangeId) -> Option<(Decimal, Decimal)> {
+    Some(match exchange {
+        ExchangeId::Binance => (dec!(100), dec!(100.1)),
+        ExchangeId::Bybit => (dec!(101), dec!(101.1)),
+    })
+}
+
+/// BTC leaves Binance over BSC in a minute; USDT leaves Bybit for 1 USDT
+/// with no estimate.
+fn status(btc: AssetStatus) -> TransferStatus {
+    let status = TransferStatus::default();
+    status.update(
+        ExchangeId::Binance,
+        HashMap::from([("BTC".to_string(), btc), ("USDT".to_string(), OPEN)]),
+    );
+    status.update(
+        ExchangeId::Bybit,
+        HashMap::from([("BTC".to_string(), OPEN), ("USDT".to_string(), OPEN)]),
+    );
+    status.update_withdrawals(
+        ExchangeId::Binance,
+        HashMap::from([(
+            "BTC".to_string(),
+            Withdrawal {
+                network: "BSC".into(),
+                fee: dec!(0.0000013),
+                eta: Some(Duration::from_secs(60)),
+            },
+        )]),
+    );
+    status.update_withdrawals(
+        ExchangeId::Bybit,
+        HashMap::from([(
+            "USDT".to_string(),
+            Withdrawal {
+                network: "TRX".into(),
+                fee: dec!(1),
+                eta: None,
+            },
+        )]),
+    );
+    status
+}
+
+fn config() -> RebalanceConfig {
+    RebalanceConfig {
+        enabled: true,
+        ..RebalanceConfig::default()
+    }
+}
+
+#[test]
+fn plans_both_ways_and_prefers_the_cheaper() {
+    let btc = Symbol::intern("BTCUSDT");
+    // Arbitrage bought on Binance and sold on Bybit until Bybit ran out.
+    let skewed = inventory((dec!(0.04), dec!(8)), (dec!(0), dec!(12)));
+    let planner = Planner::new(&config(), Some(status(OPEN)));
+    let plan = planner
+        .plan(btc, &skewed, quotes, dec!(0.001))
+        .expect("a plan");
+
+    let moved: Vec<_> = plan
+        .transfers
+        .iter()
+        .map(|t| (t.asset.as_str(), t.amount, t.from, t.to, t.fee, t.eta))
+        .collect();
+    assert_eq!(
+        moved,
+        [
+            (
+                "BTC",
+                dec!(0.02),
+                ExchangeId::Binance,
+                ExchangeId::Bybit,
+                Some(dec!(0.0000013)),
+                Duration::from_secs(60)
+            ),
+            // No estimate from Bybit: the configured 30 minutes.
+            (
+                "USDT",
+                dec!(2),
+                ExchangeId::Bybit,
+                ExchangeId::Binance,
+                Some(dec!(1)),
+                Duration::from_secs(1800)
+            ),
+        ]
+    );
+    assert_eq!(plan.transfer_cost(), Some(dec!(1.000130715)));
+
+    let trade = plan.trade.clone().expect("an offsetting trade");
+    assert_eq!(
+        (trade.sell, trade.buy, trade.quantity),
+        (ExchangeId::Binance, ExchangeId::Bybit, dec!(0.02))
+    );
+    // 1.1 of spread per BTC and 0.1% on 201.1 of notional per BTC.
+    assert_eq!(trade.cost, dec!(0.026022));
+    assert_eq!(plan.recommended(), Method::Trades);
+    assert_eq!(
+        plan.to_string(),
+        "Rebalance BTCUSDT:\n\
+         • transfer 0.02 BTC binance → bybit: fee 0.0000013 BTC, ~1 min\n\
+         • transfer 2 USDT bybit → binance: fee 1 USDT, ~30 min\n\
+         • or sell 0.02 BTC on binance at 100 and buy it on bybit at 101.1: ~0.03 USDT\n\
+         → offsetting trades"
+    );
+
+    // With the spread wide enough, moving the coins is cheaper.
+    let wide = |exchange| match exchange {
+        ExchangeId::Binance => Some((dec!(100), dec!(100.1))),
+        ExchangeId::Bybit => Some((dec!(200), dec!(200.1))),
+    };
+    let plan = planner.plan(btc, &skewed, wide, dec!(0.001)).unwrap();
+    assert_eq!(plan.recommended(), Method::Transfers);
+}
+
+#[test]
+fn unknown_fees_and_blocked_transfers() {
+    let btc = Symbol::intern("BTCUSDT");
+    let skewed = inventory((dec!(0.04), dec!(8)), (dec!(0), dec!(12)));
+
+    // Without [transfers] the fees are unknown, so there's nothing to weigh
+    // the trades against.
+    let plan = Planner::new(&config(), None)
+        .plan(btc, &skewed, quotes, dec!(0.001))
+        .unwrap();
+    assert_eq!(plan.transfer_cost(), None);
+    assert_eq!(plan.recommended(), Method::Transfers);
+    assert!(
+        plan.to_string().ends_with("fee unknown, ~30 min\n• or sell 0.02 BTC on binance at 100 and buy it on bybit at 101.1: ~0.03 USDT\n→ transfers"),
+        "{}",
+        plan
+    );
+
+    // BTC can't leave Binance: the trades are the only way.
+    let suspended = AssetStatus {
+        withdraw: false,
+        ..OPEN
+    };
+    let plan = Planner::new(&config(), Some(status(suspended)))
+        .plan(btc, &skewed, quotes, dec!(0.001))
+        .unwrap();
+    assert_eq!(
+        plan.transfers[0].blocked.as_deref(),
+        Some("BTC withdrawals suspended on binance")
+    );
+    assert_eq!(plan.recommended(), Method::Trades);
+
+    // Bybit has no USDT to buy with; the base transfer is all there is.
+    let broke = inventory((dec!(0.04), dec!(0)), (dec!(0), dec!(0)));
+    let plan = Planner::new(&config(), Some(status(suspended)))
+        .plan(btc, &broke, quotes, dec!(0.001))
+        .unwrap();
+    assert_eq!(plan.trade, None);
+    assert_eq!(plan.transfers.len(), 1);
+    assert_eq!(plan.recommended(), Method::Transfers);
+
+    // Nothing to move.
+    let even = inventory((dec!(0.02), dec!(10)), (dec!(0.02), dec!(10)));
+    assert!(Planner::new(&config(), None)
+        .plan(btc, &even, quotes, dec!(0.001))
+        .is_none());
+}
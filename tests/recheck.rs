@@ -0,0 +1,87 @@
+//! The re-check right before ordering: an opportunity that clears the
+//! threshold is still dropped unless its edge, net of both legs' fees, is
+//! above the floor at the legs' latest quotes, and both legs offer the
+//! order's quantity at them.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    models::ids::ExchangeId,
+    state::{ExecutionState, LegSide},
+    ws::exchanges::ArbitrageEngine,
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::watch,
+    time::{self, Duration},
+};
+
+use support::exchange::{until_subscribed, FakeExchange, Ledger};
+
+#[tokio::test(start_paused = true)]
+async fn fees_below_the_floor_drop_the_trade() {
+    let ledger = Ledger::new();
+    let fake = |id| Arc::new(FakeExchange::new(id, &ledger));
+    let (binance, bybit) = (fake(ExchangeId::Binance), fake(ExchangeId::Bybit));
+    // 0.1% threshold; 0.2% a leg in fees, and 0.1% must be left after them.
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(watch::Sender::new(ExecutionState::default()))
+    .with_recheck(dec!(0.1), dec!(0.2));
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // About 0.4% gross clears the threshold, but fees take all of it.
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(100.5)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert!(ledger.is_empty());
+
+    // About 0.6% gross leaves 0.2%.
+    bybit.quote(dec!(100.7)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        ledger.prices(),
+        [
+            (ExchangeId::Binance, LegSide::Buy, dec!(100.1)),
+            (ExchangeId::Bybit, LegSide::Sell, dec!(100.7)),
+        ]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn too_little_at_the_touch_drops_the_trade() {
+    let ledger = Ledger::new();
+    let fake = |id| Arc::new(FakeExchange::new(id, &ledger));
+    let (binance, bybit) = (fake(ExchangeId::Binance), fake(ExchangeId::Bybit));
+    // Orders are for 0.01.
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(watch::Sender::new(ExecutionState::default()));
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // 1% apart, but Binance offers only half the order at its ask.
+    binance.quote_sized(dec!(100), dec!(0.005)).await;
+    bybit.quote_sized(dec!(101), dec!(1)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert!(ledger.is_empty());
+
+    binance.quote_sized(dec!(100), dec!(0.01)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        ledger.prices(),
+        [
+            (ExchangeId::Binance, LegSide::Buy, dec!(100.1)),
+            (ExchangeId::Bybit, LegSide::Sell, dec!(101)),
+        ]
+    );
+}
@@ -0,0 +1,75 @@
+//! Raw-feed recording per symbol, switched on and off at runtime.
+
+use arbitrage_bot::{error::ControlError, ws::tap::Recordings};
+use tokio::time::{self, Duration};
+use tokio_tungstenite::tungstenite::Message;
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("arb-recording-{}-{}", std::process::id(), name))
+}
+
+/// Gives the file writers time to catch up.
+async fn settle() {
+    time::sleep(Duration::from_millis(100)).await;
+}
+
+#[tokio::test]
+async fn only_frames_of_recorded_symbols_are_kept() {
+    let dir = temp_dir("toggle");
+    let _ = std::fs::remove_dir_all(&dir);
+    let recordings = Recordings::new(&dir);
+    let url = "wss://example.test/ws";
+    let frame = |symbol: &str| Message::Text(format!(r#"{{"s":"{}","b":"1"}}"#, symbol).into());
+
+    recordings.record(url, &frame("BTCUSDT"));
+    assert!(recordings.symbols().is_empty());
+    assert!(
+        !dir.exists(),
+        "nothing is created before a recording starts"
+    );
+
+    recordings.start("btcusdt").unwrap();
+    recordings.start("BTCUSDT").unwrap();
+    assert_eq!(recordings.symbols(), ["BTCUSDT"]);
+    recordings.record(url, &frame("BTCUSDT"));
+    recordings.record(url, &frame("ETHUSDT"));
+    recordings.stop("BTCUSDT").unwrap();
+    recordings.record(url, &frame("BTCUSDT"));
+    settle().await;
+
+    let recorded = std::fs::read_to_string(recordings.path("BTCUSDT")).unwrap();
+    let lines: Vec<_> = recorded.lines().collect();
+    assert_eq!(lines.len(), 1, "{}", recorded);
+    let fields: Vec<_> = lines[0].splitn(3, '\t').collect();
+    assert_eq!(fields[1], url);
+    assert_eq!(fields[2], r#"{"s":"BTCUSDT","b":"1"}"#);
+    assert!(!recordings.path("ETHUSDT").exists());
+    assert!(recordings.symbols().is_empty());
+
+    // Recording again appends to the same file.
+    recordings.start("BTCUSDT").unwrap();
+    recordings.record(url, &frame("BTCUSDT"));
+    settle().await;
+    let recorded = std::fs::read_to_string(recordings.path("BTCUSDT")).unwrap();
+    assert_eq!(recorded.lines().count(), 2);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn symbols_unfit_for_a_file_name_are_refused() {
+    let dir = temp_dir("invalid");
+    let recordings = Recordings::new(&dir);
+    for symbol in ["", "../BTCUSDT", "BTC/USDT"] {
+        assert!(
+            matches!(
+                recordings.start(symbol),
+                Err(ControlError::InvalidSymbol(_))
+            ),
+            "{:?}",
+            symbol
+        );
+    }
+    assert!(recordings.stop("a b").is_err());
+    assert!(recordings.symbols().is_empty());
+    assert!(!dir.exists());
+}
@@ -0,0 +1,126 @@
+//! Warm start: loading what the venues still hold over the saved state.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    accounts::{Account, Accounts},
+    config::AccountRouting,
+    error::TradingError,
+    models::{ids::ExchangeId, money::Decimal},
+    recovery,
+    state::{ExecutionState, LegSide, OpenOrder},
+    ws::exchanges::{Exchange, OrderSide},
+};
+use rust_decimal_macros::dec;
+use tokio::sync::watch;
+
+use support::exchange::FakeVenue;
+
+/// A venue reporting `position`, or failing to with `None`, and
+/// `open_orders`.
+fn venue(id: ExchangeId, position: Option<Decimal>, open_orders: Vec<OpenOrder>) -> Arc<FakeVenue> {
+    let venue = FakeVenue::new(id).with_orders(open_orders);
+    Arc::new(match position {
+        Some(position) => venue.with_position(position),
+        None => venue,
+    })
+}
+
+fn order(side: LegSide, quantity: Decimal, filled: Decimal) -> OpenOrder {
+    OpenOrder {
+        order_id: "42".into(),
+        symbol: "BTCUSDT".into(),
+        side,
+        price: dec!(100),
+        quantity,
+        filled,
+        reduce_only: false,
+        updated_at_ms: 1_000,
+    }
+}
+
+#[tokio::test]
+async fn reported_positions_and_orders_replace_the_saved_ones() {
+    let mut saved = ExecutionState::default();
+    saved.exposure.insert(ExchangeId::Binance, dec!(0.1));
+    saved.exposure.insert(ExchangeId::Bybit, dec!(-0.2));
+    let state = watch::Sender::new(saved);
+    let exchanges: Vec<Arc<dyn Exchange>> = vec![
+        venue(
+            ExchangeId::Binance,
+            Some(dec!(0.5)),
+            vec![order(LegSide::Sell, dec!(0.3), dec!(0.1))],
+        ),
+        // Bybit can't say, so its saved exposure stays.
+        venue(ExchangeId::Bybit, None, Vec::new()),
+    ];
+
+    let outcomes = recovery::recover(&exchanges, &state).await;
+
+    let recovered = outcomes[0].as_ref().unwrap();
+    assert_eq!(recovered.position, dec!(0.5));
+    assert_eq!(recovered.tracked, dec!(0.1));
+    // The unfilled 0.2 of the sell counts as filled, like any order.
+    assert_eq!(recovered.exposure, dec!(0.3));
+    assert!(recovered.to_string().contains("was tracked at 0.1"));
+    assert!(matches!(
+        outcomes[1],
+        Err((ExchangeId::Bybit, TradingError::EmptyResponse { .. }))
+    ));
+    let state = state.borrow();
+    assert_eq!(state.exposure[&ExchangeId::Binance], dec!(0.3));
+    assert_eq!(state.exposure[&ExchangeId::Bybit], dec!(-0.2));
+    assert_eq!(state.open_orders[&ExchangeId::Binance].len(), 1);
+    assert!(!state.open_orders.contains_key(&ExchangeId::Bybit));
+}
+
+#[tokio::test]
+async fn a_flat_venue_clears_stale_orders() {
+    let mut saved = ExecutionState::default();
+    saved.exposure.insert(ExchangeId::Binance, dec!(0.4));
+    saved.open_orders.insert(
+        ExchangeId::Binance,
+        vec![order(LegSide::Buy, dec!(1), dec!(0))],
+    );
+    let state = watch::Sender::new(saved);
+    let exchanges: Vec<Arc<dyn Exchange>> =
+        vec![venue(ExchangeId::Binance, Some(dec!(0)), Vec::new())];
+
+    let outcomes = recovery::recover(&exchanges, &state).await;
+
+    assert_eq!(outcomes[0].as_ref().unwrap().exposure, dec!(0));
+    assert!(!state.borrow().is_exposed());
+    assert!(state.borrow().open_orders.is_empty());
+}
+
+#[tokio::test]
+async fn recovered_account_positions_route_exits() {
+    let (main, sub) = (
+        venue(ExchangeId::Binance, Some(dec!(0)), Vec::new()),
+        venue(ExchangeId::Binance, Some(dec!(0.5)), Vec::new()),
+    );
+    let account = |name: &str, venue: &Arc<FakeVenue>| Account {
+        name: name.to_string(),
+        exchange: venue.clone(),
+        symbols: Vec::new(),
+    };
+    let accounts: Arc<dyn Exchange> = Arc::new(Accounts::new(
+        "BTCUSDT",
+        AccountRouting::RoundRobin,
+        vec![account("main", &main), account("sub", &sub)],
+    ));
+    let state = watch::Sender::new(ExecutionState::default());
+
+    let outcomes = recovery::recover(std::slice::from_ref(&accounts), &state).await;
+
+    assert_eq!(outcomes[0].as_ref().unwrap().position, dec!(0.5));
+    // Only the sub-account holds the long a sell exit closes.
+    accounts
+        .place_exit_future(OrderSide::Sell, dec!(100), dec!(0.5))
+        .await
+        .unwrap();
+    assert!(main.placed().is_empty());
+    assert_eq!(sub.placed(), ["exit Sell 0.5 @ 100"]);
+}
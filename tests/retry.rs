@@ -0,0 +1,131 @@
+//! The retry policy for order requests: what is tried again, how often, and
+//! its settings.
+
+mod support;
+
+use std::{cell::Cell, time::Duration};
+
+use arbitrage_bot::{error::TradingError, models::ids::ExchangeId, retry::RetryPolicy};
+
+use support::load_config;
+
+#[test]
+fn retry_settings_are_validated() {
+    let config = load_config("default", "").unwrap();
+    let policy = RetryPolicy::from(&config.engine.execution.retry);
+    assert_eq!(policy, RetryPolicy::default());
+    assert_eq!(policy.max_attempts, 2);
+    assert!(policy.retryable_only);
+
+    let config = load_config(
+        "valid",
+        "[engine.execution.retry]\nmax_attempts = 4\nbackoff_ms = 100\nmax_backoff_ms = 800\nretryable_only = false",
+    )
+    .unwrap();
+    let policy = RetryPolicy::from(&config.engine.execution.retry);
+    assert_eq!(policy.max_attempts, 4);
+    assert_eq!(policy.delay(3), Duration::from_millis(400));
+    assert!(!policy.retryable_only);
+
+    for (i, toml) in [
+        "max_attempts = 0",
+        "max_attempts = 11",
+        "backoff_ms = 5000\nmax_backoff_ms = 1000",
+    ]
+    .iter()
+    .enumerate()
+    {
+        let toml = format!(
+            "[engine.execution]\nenabled = true\nquantity = 0.01\n[engine.execution.retry]\n{}",
+            toml
+        );
+        assert!(
+            load_config(&format!("invalid-{}", i), &toml).is_err(),
+            "{}",
+            toml
+        );
+    }
+}
+
+fn rejected(code: i32) -> TradingError {
+    TradingError::Rejected {
+        exchange: ExchangeId::Binance,
+        operation: "order.place",
+        code,
+        msg: "rejected".into(),
+    }
+}
+
+#[test]
+fn what_is_tried_again() {
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        ..RetryPolicy::default()
+    };
+    let rate_limited = rejected(-1003);
+    assert!(policy.allows(1, &rate_limited, false));
+    assert!(policy.allows(2, &rate_limited, false));
+    assert!(!policy.allows(3, &rate_limited, false));
+
+    // Maybe placed: only if the order can be looked up.
+    assert!(policy.allows(1, &TradingError::ConnectionClosed, true));
+    assert!(!policy.allows(1, &TradingError::ConnectionClosed, false));
+
+    // Insufficient balance only with retryable_only off; bad keys never.
+    let insufficient = rejected(-2010);
+    assert!(!policy.allows(1, &insufficient, true));
+    let any = RetryPolicy {
+        retryable_only: false,
+        ..policy
+    };
+    assert!(any.allows(1, &insufficient, true));
+    assert!(!any.allows(1, &rejected(-2015), true));
+    assert!(!RetryPolicy::never().allows(1, &rate_limited, true));
+}
+
+#[tokio::test(start_paused = true)]
+async fn requests_run_until_they_succeed_or_the_policy_gives_up() {
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_secs(1),
+        retryable_only: true,
+    };
+
+    // Each attempt hears how the one before failed.
+    let attempts = Cell::new(0);
+    let seen = std::cell::RefCell::new(Vec::new());
+    let started = tokio::time::Instant::now();
+    let result = policy
+        .run("order.place", true, |last| {
+            seen.borrow_mut()
+                .push(last.map(|e| matches!(e, TradingError::ConnectionClosed)));
+            attempts.set(attempts.get() + 1);
+            let n = attempts.get();
+            async move {
+                match n {
+                    1 => Err(TradingError::ConnectionClosed),
+                    2 => Err(rejected(-1003)),
+                    _ => Ok(n),
+                }
+            }
+        })
+        .await;
+    assert_eq!(result.unwrap(), 3);
+    assert_eq!(*seen.borrow(), [None, Some(true), Some(false)]);
+    assert_eq!(started.elapsed(), Duration::from_millis(300));
+
+    // A rejection that can't pass is returned at once.
+    attempts.set(0);
+    let result: Result<(), _> = policy
+        .run("order.place", true, |_| {
+            attempts.set(attempts.get() + 1);
+            async { Err(rejected(-2019)) }
+        })
+        .await;
+    assert!(matches!(
+        result,
+        Err(TradingError::Rejected { code: -2019, .. })
+    ));
+    assert_eq!(attempts.get(), 1);
+}
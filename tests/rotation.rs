@@ -0,0 +1,77 @@
+//! Make-before-break rotation of a feed connection against the mock
+//! exchange: the replacement connection is subscribed before it takes over.
+
+mod support;
+
+use arbitrage_bot::{
+    binance::ws_handler::{ConnectionEvent, EVENT_CHANNEL_CAPACITY},
+    config::{self, Config},
+    models::ids::ExchangeId,
+    ws::{binance_client_multiplex::spawn_orderbook_stream_binance_multiplex, quote_bus::QuoteBus},
+};
+use rust_decimal_macros::dec;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use support::{Flavor, MockExchange, WAIT};
+
+const COMBINED_DEPTH: &str = include_str!("../fixtures/binance_futures_combined_depth5.json");
+
+#[tokio::test]
+async fn a_rotated_multiplex_feed_keeps_quoting() {
+    let mut config = Config::default();
+    config.ws.default.rotation_period_secs = 1;
+    config::init(config);
+    let mock = MockExchange::start(Flavor::Binance);
+    let bus = QuoteBus::default();
+    let mut latest = bus.watch(ExchangeId::Binance, "BTCUSDT");
+    let (events, mut connection_events) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    let cancel = CancellationToken::new();
+    let _handle = spawn_orderbook_stream_binance_multiplex(
+        &["BTCUSDT"],
+        bus,
+        &mock.url(),
+        events,
+        cancel.clone(),
+    );
+
+    mock.wait_for_requests(1).await;
+    mock.push(COMBINED_DEPTH);
+    tokio::time::timeout(WAIT, latest.wait_for(Option::is_some))
+        .await
+        .expect("no quote before the rotation")
+        .unwrap();
+
+    // The new connection subscribes, is acked, and takes over.
+    mock.wait_for_connections(2).await;
+    let rotated = tokio::time::timeout(WAIT, async {
+        loop {
+            if let Ok(ConnectionEvent::Rotated { .. }) = connection_events.recv().await {
+                return;
+            }
+        }
+    })
+    .await;
+    assert!(rotated.is_ok(), "the rotation never completed");
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1]["params"], requests[0]["params"]);
+
+    // Only the new connection is read now; a later update still arrives.
+    let update = COMBINED_DEPTH
+        .replace("\"u\":8421339035371", "\"u\":8421339035372")
+        .replace("\"112543.20\",\"3.907\"", "\"112543.00\",\"3.907\"");
+    mock.push(&update);
+    let quote = tokio::time::timeout(
+        WAIT,
+        latest.wait_for(|q| q.as_ref().is_some_and(|q| q.top.bid == dec!(112543.00))),
+    )
+    .await
+    .expect("no quote after the rotation")
+    .unwrap()
+    .clone()
+    .unwrap();
+    assert_eq!(quote.exchange, ExchangeId::Binance);
+
+    cancel.cancel();
+}
@@ -0,0 +1,179 @@
+//! Session summaries: what a run saw and traded, leaving out the trades
+//! carried over from earlier runs.
+
+use arbitrage_bot::{
+    binance::ws_handler::ConnectionEvent,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::{MarketType, Opportunity},
+    },
+    plan::{ExecutionPlan, LegStatus, PlanReport, PlannedLeg},
+    session::{Session, SessionLog},
+    state::{self, ExecutionState, OrderLeg},
+    ws::{handlers::TopOfBook, quote_bus::QuoteBus},
+};
+use rust_decimal_macros::dec;
+use tokio::{sync::broadcast, time};
+use tokio_util::sync::CancellationToken;
+
+fn top(bid: Decimal) -> TopOfBook {
+    TopOfBook {
+        symbol: Symbol::intern("BTCUSDT"),
+        bid,
+        ask: bid + dec!(0.1),
+        bid_size: None,
+        ask_size: None,
+        market_type: MarketType::Futures,
+        update_id: None,
+    }
+}
+
+fn opportunity(diff_percent: Decimal) -> Opportunity {
+    Opportunity {
+        symbol: Symbol::intern("BTCUSDT"),
+        exchange_a: ExchangeId::Binance,
+        exchange_b: ExchangeId::Bybit,
+        mid_a: dec!(100),
+        mid_b: dec!(101),
+        diff_percent,
+        at_ms: state::now_ms(),
+    }
+}
+
+/// A two-leg plan whose legs were all placed, or whose sell failed.
+fn plan(id: u64, buy: Decimal, sell: Decimal, sold: bool) -> PlanReport {
+    let plan = ExecutionPlan::arbitrage(
+        Symbol::intern("BTCUSDT"),
+        PlannedLeg::buy(ExchangeId::Binance, buy, dec!(1)),
+        PlannedLeg::sell(ExchangeId::Bybit, sell, dec!(1)),
+    );
+    let mut report = PlanReport::new(id, &plan, state::now_ms());
+    report.legs[0].status = LegStatus::Placed {
+        order_id: format!("{}-buy", id),
+    };
+    report.legs[1].status = if sold {
+        LegStatus::Placed {
+            order_id: format!("{}-sell", id),
+        }
+    } else {
+        LegStatus::Failed {
+            error: "rejected".into(),
+        }
+    };
+    report
+}
+
+fn order(exchange: ExchangeId, price: Decimal, placed_at_ms: i64) -> OrderLeg {
+    OrderLeg {
+        exchange,
+        symbol: "BTCUSDT".into(),
+        side: state::LegSide::Buy,
+        price,
+        quantity: dec!(1),
+        order_id: Some("1".into()),
+        account: None,
+        error: None,
+        placed_at_ms,
+    }
+}
+
+#[tokio::test]
+async fn counts_quotes_opportunities_and_reconnects() {
+    let bus = QuoteBus::default();
+    let (opportunities, opportunities_rx) = broadcast::channel(16);
+    let (events, events_rx) = broadcast::channel(16);
+    let cancel = CancellationToken::new();
+    let session = Session::start(
+        &bus,
+        opportunities_rx,
+        events_rx,
+        &ExecutionState::default(),
+        dec!(0.1),
+        cancel.clone(),
+    );
+
+    bus.publish(ExchangeId::Binance, top(dec!(100)));
+    bus.publish(ExchangeId::Binance, top(dec!(100.5)));
+    bus.publish(ExchangeId::Bybit, top(dec!(101)));
+    for diff in [dec!(0.6), dec!(1.2), dec!(0.8)] {
+        opportunities.send(opportunity(diff)).unwrap();
+    }
+    let connected = |url: &str| ConnectionEvent::Connected { url: url.into() };
+    for url in ["wss://a", "wss://b", "wss://a", "wss://a"] {
+        events.send(connected(url)).unwrap();
+    }
+    time::sleep(time::Duration::from_millis(50)).await;
+    cancel.cancel();
+
+    let summary = session.summary(&ExecutionState::default());
+    assert_eq!(summary.quotes[&ExchangeId::Binance], 2);
+    assert_eq!(summary.quotes[&ExchangeId::Bybit], 1);
+    assert_eq!(summary.opportunities, 3);
+    assert_eq!(summary.max_spread.unwrap().diff_percent, dec!(1.2));
+    // b connected once and never reconnected.
+    assert_eq!(summary.reconnects.len(), 1);
+    assert_eq!(summary.reconnects["wss://a"], 2);
+    assert_eq!(summary.trades, 0);
+    assert_eq!(summary.net_pnl, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn pnl_covers_this_session_only() {
+    let earlier = ExecutionState {
+        orders: vec![order(ExchangeId::Binance, dec!(50), 0)],
+        plans: vec![plan(1, dec!(50), dec!(60), true)],
+        planned: 1,
+        ..ExecutionState::default()
+    };
+    let (_opportunities, opportunities_rx) = broadcast::channel(1);
+    let (_events, events_rx) = broadcast::channel(1);
+    let session = Session::start(
+        &QuoteBus::default(),
+        opportunities_rx,
+        events_rx,
+        &earlier,
+        dec!(0.1),
+        CancellationToken::new(),
+    );
+
+    let now = state::now_ms();
+    let mut execution = earlier.clone();
+    execution.plans.push(plan(2, dec!(100), dec!(102), true));
+    execution.plans.push(plan(3, dec!(100), dec!(103), false));
+    execution.planned = 3;
+    execution.orders.extend([
+        order(ExchangeId::Binance, dec!(100), now),
+        order(ExchangeId::Bybit, dec!(102), now),
+        order(ExchangeId::Binance, dec!(100), now),
+    ]);
+
+    let summary = session.summary(&execution);
+    assert_eq!(summary.trades, 2);
+    assert_eq!(summary.executed, 1);
+    assert_eq!(summary.failed, 1);
+    // Without rollback the failed trade's buy stays open.
+    assert_eq!(summary.open_legs, 1);
+    assert_eq!(summary.gross_pnl, dec!(2));
+    // 0.1% of the 302 placed this session.
+    assert_eq!(summary.fees, dec!(0.302));
+    assert_eq!(summary.net_pnl, dec!(1.698));
+
+    let path = std::env::temp_dir().join(format!(
+        "arb-session-{}-{}",
+        std::process::id(),
+        "sessions.jsonl"
+    ));
+    let _ = std::fs::remove_file(&path);
+    let log = SessionLog::open(&path).unwrap();
+    log.append(&summary).unwrap();
+    log.append(&summary).unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<serde_json::Value> = content
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["executed"], 1);
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,286 @@
+//! The alerting decisions: which spreads `Comparator` reports, and which of
+//! those `AlertGate` lets through to Telegram. Cooldowns run on tokio's
+//! paused clock.
+
+use std::{collections::HashMap, time::Duration};
+
+use arbitrage_bot::{
+    error::Severity,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::{Comparator, MarketSnapshot, MarketType},
+    },
+    notifications::{
+        alert_gate::AlertGate,
+        telegram::{AppAlert, Notification},
+    },
+    state::AlertGateState,
+};
+use rust_decimal_macros::dec;
+use tokio::{sync::mpsc, time};
+
+fn snapshots(quotes: &[(ExchangeId, Decimal)]) -> HashMap<ExchangeId, MarketSnapshot> {
+    quotes
+        .iter()
+        .map(|&(exchange, mid)| {
+            let snapshot = MarketSnapshot::new(
+                exchange,
+                Symbol::intern("BTCUSDT"),
+                mid - dec!(0.5),
+                mid + dec!(0.5),
+                MarketType::Futures,
+            );
+            (exchange, snapshot)
+        })
+        .collect()
+}
+
+/// The reported diffs, as `(exchange_a, exchange_b, diff)`.
+fn compare(
+    comparator: &mut Comparator,
+    quotes: &[(ExchangeId, Decimal)],
+) -> Vec<(ExchangeId, ExchangeId, Decimal)> {
+    comparator
+        .compare(&snapshots(quotes))
+        .into_iter()
+        .map(|(a, b, diff)| (a.exchange, b.exchange, diff))
+        .collect()
+}
+
+#[test]
+fn comparator_diff_is_relative_to_the_first_exchange() {
+    // Every map gets its own hash seed; the order must not depend on it.
+    for _ in 0..32 {
+        let mut comparator = Comparator::new(dec!(1));
+        assert_eq!(
+            compare(
+                &mut comparator,
+                &[
+                    (ExchangeId::Bybit, dec!(110)),
+                    (ExchangeId::Binance, dec!(100))
+                ]
+            ),
+            [(ExchangeId::Binance, ExchangeId::Bybit, dec!(10))]
+        );
+        let reversed = compare(
+            &mut comparator,
+            &[
+                (ExchangeId::Bybit, dec!(100)),
+                (ExchangeId::Binance, dec!(110)),
+            ],
+        );
+        assert_eq!(reversed.len(), 1);
+        assert_eq!(
+            (reversed[0].0, reversed[0].1),
+            (ExchangeId::Binance, ExchangeId::Bybit)
+        );
+        assert_eq!(reversed[0].2.round_dp(4), dec!(9.0909));
+    }
+}
+
+#[test]
+fn comparator_threshold_is_inclusive() {
+    let quotes = [
+        (ExchangeId::Binance, dec!(100)),
+        (ExchangeId::Bybit, dec!(105)),
+    ];
+    assert_eq!(compare(&mut Comparator::new(dec!(5)), &quotes).len(), 1);
+    assert!(compare(&mut Comparator::new(dec!(5.0001)), &quotes).is_empty());
+    // Identical mids are a 0% diff, reported only with a zero threshold.
+    let flat = [
+        (ExchangeId::Binance, dec!(100)),
+        (ExchangeId::Bybit, dec!(100)),
+    ];
+    assert!(compare(&mut Comparator::new(dec!(0.0001)), &flat).is_empty());
+    assert_eq!(
+        compare(&mut Comparator::new(Decimal::ZERO), &flat),
+        [(ExchangeId::Binance, ExchangeId::Bybit, Decimal::ZERO)]
+    );
+}
+
+#[test]
+fn comparator_needs_two_exchanges_with_prices() {
+    let mut comparator = Comparator::new(Decimal::ZERO);
+    assert!(compare(&mut comparator, &[]).is_empty());
+    assert!(compare(&mut comparator, &[(ExchangeId::Binance, dec!(100))]).is_empty());
+    // No mid on the first exchange yet: nothing to measure the diff against.
+    let unpriced = snapshots(&[(ExchangeId::Bybit, dec!(100))])
+        .into_iter()
+        .chain([(
+            ExchangeId::Binance,
+            MarketSnapshot::new(
+                ExchangeId::Binance,
+                Symbol::intern("BTCUSDT"),
+                Decimal::ZERO,
+                Decimal::ZERO,
+                MarketType::Futures,
+            ),
+        )])
+        .collect();
+    assert!(comparator.compare(&unpriced).is_empty());
+}
+
+#[test]
+fn comparator_remembers_the_biggest_diff() {
+    let mut comparator = Comparator::new(dec!(2));
+    compare(
+        &mut comparator,
+        &[
+            (ExchangeId::Binance, dec!(100)),
+            (ExchangeId::Bybit, dec!(106)),
+        ],
+    );
+    assert_eq!(comparator.biggest_diff, dec!(6));
+    compare(
+        &mut comparator,
+        &[
+            (ExchangeId::Binance, dec!(100)),
+            (ExchangeId::Bybit, dec!(103)),
+        ],
+    );
+    // Below the threshold doesn't count, even if bigger.
+    comparator.threshold = dec!(50);
+    compare(
+        &mut comparator,
+        &[
+            (ExchangeId::Binance, dec!(100)),
+            (ExchangeId::Bybit, dec!(140)),
+        ],
+    );
+    assert_eq!(comparator.biggest_diff, dec!(6));
+}
+
+fn alert(symbol: &str, a: &str, b: &str, diff: Decimal) -> AppAlert {
+    let mid = dec!(100);
+    AppAlert {
+        symbol: symbol.to_string(),
+        exchange_a: a.to_string(),
+        exchange_b: b.to_string(),
+        mid_a: mid,
+        mid_b: mid,
+        diff_percent: diff,
+        ..AppAlert::default()
+    }
+}
+
+struct Harness {
+    gate: AlertGate,
+    tx: mpsc::Sender<Notification>,
+    rx: mpsc::Receiver<Notification>,
+}
+
+impl Harness {
+    /// A 5% minimum, re-alerts after another 1pp, a 60s cooldown.
+    fn new() -> Self {
+        let (tx, rx) = mpsc::channel(4);
+        Self {
+            gate: AlertGate::new(dec!(5), dec!(1), 60),
+            tx,
+            rx,
+        }
+    }
+
+    /// Offers an alert; returns whether it went out.
+    fn offer(&mut self, symbol: &str, a: &str, b: &str, diff: Decimal) -> bool {
+        self.gate.maybe_send(&self.tx, alert(symbol, a, b, diff));
+        match self.rx.try_recv() {
+            Ok(Notification::Arbitrage(alert)) => {
+                assert_eq!(
+                    (
+                        alert.symbol.as_str(),
+                        alert.exchange_a.as_str(),
+                        alert.exchange_b.as_str()
+                    ),
+                    (symbol, a, b)
+                );
+                assert_eq!(alert.diff_percent, diff);
+                true
+            }
+            Ok(other) => panic!("unexpected notification {:?}", other),
+            Err(_) => false,
+        }
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn gate_minimum_diff_is_inclusive() {
+    let mut h = Harness::new();
+    assert!(!h.offer("BTCUSDT", "binance", "bybit", dec!(4.99)));
+    assert!(h.offer("BTCUSDT", "binance", "bybit", dec!(5)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn gate_cooldown_is_global() {
+    let mut h = Harness::new();
+    assert!(h.offer("BTCUSDT", "binance", "bybit", dec!(6)));
+    // Another pair, far above the threshold, still waits out the cooldown.
+    assert!(!h.offer("ETHUSDT", "binance", "bybit", dec!(20)));
+    time::advance(Duration::from_secs(59)).await;
+    assert!(!h.offer("ETHUSDT", "binance", "bybit", dec!(20)));
+    time::advance(Duration::from_secs(1)).await;
+    assert!(h.offer("ETHUSDT", "binance", "bybit", dec!(20)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn gate_re_alerts_only_on_a_bigger_jump() {
+    let mut h = Harness::new();
+    assert!(h.offer("BTCUSDT", "binance", "bybit", dec!(6)));
+    time::advance(Duration::from_secs(60)).await;
+    assert!(!h.offer("BTCUSDT", "binance", "bybit", dec!(6.99)));
+    // A smaller diff doesn't lower the bar either.
+    assert!(!h.offer("BTCUSDT", "binance", "bybit", dec!(5.5)));
+    assert!(h.offer("BTCUSDT", "binance", "bybit", dec!(7)));
+    time::advance(Duration::from_secs(60)).await;
+    assert!(!h.offer("BTCUSDT", "binance", "bybit", dec!(7.5)));
+    assert!(h.offer("BTCUSDT", "binance", "bybit", dec!(8)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn gate_keys_ignore_case_and_exchange_order() {
+    let mut h = Harness::new();
+    assert!(h.offer("BTCUSDT", "binance", "bybit", dec!(6)));
+    time::advance(Duration::from_secs(60)).await;
+    assert!(!h.offer("btcusdt", "bybit", "binance", dec!(6.5)));
+    // Other symbols keep their own history.
+    assert!(h.offer("ETHUSDT", "bybit", "binance", dec!(6)));
+    time::advance(Duration::from_secs(60)).await;
+    assert!(h.offer("btcusdt", "bybit", "binance", dec!(7)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn gate_dropped_alerts_start_no_cooldown() {
+    let mut h = Harness::new();
+    let (tx, mut rx) = mpsc::channel(1);
+    tx.try_send(Notification::FeedReconnecting {
+        url: "wss://example".into(),
+        reason: "test".into(),
+        severity: Severity::Info,
+    })
+    .unwrap();
+    h.gate
+        .maybe_send(&tx, alert("BTCUSDT", "binance", "bybit", dec!(9)));
+    rx.recv().await.unwrap();
+    assert!(rx.try_recv().is_err(), "the queue was full");
+    assert!(h.offer("BTCUSDT", "binance", "bybit", dec!(6)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn gate_reset_and_restore() {
+    let mut h = Harness::new();
+    assert!(h.offer("BTCUSDT", "binance", "bybit", dec!(6)));
+    h.gate.reset();
+    assert!(h.offer("BTCUSDT", "binance", "bybit", dec!(6)));
+
+    let saved = h.gate.state();
+    assert_eq!(saved.last_notified["BTCUSDT|binance|bybit"], dec!(6));
+    assert!(saved.last_send_ms.is_some());
+
+    let mut restored = Harness::new();
+    restored.gate.restore(AlertGateState {
+        last_notified: saved.last_notified,
+        last_send_ms: None,
+    });
+    assert!(!restored.offer("BTCUSDT", "bybit", "binance", dec!(6.5)));
+    assert!(restored.offer("BTCUSDT", "bybit", "binance", dec!(7)));
+}
@@ -0,0 +1,259 @@
+//! Changing a strategy at runtime: enabling and disabling it, its threshold
+//! and its size, from the Telegram chat or the control API, with every
+//! change in the audit log.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    audit,
+    control::{Control, ExecutionControl, Feeds, StrategyUpdate},
+    error::ControlError,
+    models::{ids::ExchangeId, money::Decimal, orderbook::MarketTracker},
+    notifications::{alert_gate::AlertGate, telegram_commands::BotCommand},
+    portfolio,
+    state::{ExecutionState, LegSide},
+    ws::{exchanges::ArbitrageEngine, quote_bus::QuoteBus},
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::{broadcast, watch},
+    time::{self, Duration},
+};
+use tokio_util::sync::CancellationToken;
+
+use support::{
+    exchange::{until_subscribed, FakeExchange, Ledger},
+    load_config,
+};
+
+const EXECUTION: &str = r#"
+[engine.execution]
+enabled = true
+symbol = "BTCUSDT"
+quantity = "0.01"
+
+[engine.execution.strategies.spread]
+"#;
+
+#[test]
+fn strategy_settings_are_validated() {
+    let config = load_config(
+        "valid",
+        &format!(
+            "{}threshold_percent = \"0.3\"\nquantity = \"0.02\"\n\n[notifications.telegram]\ncommands = true\nallowed_users = [42]",
+            EXECUTION
+        ),
+    )
+    .unwrap();
+    let spread = &config.engine.execution.strategies["spread"];
+    assert_eq!(spread.threshold_percent, Some(dec!(0.3)));
+    assert_eq!(spread.quantity, Some(dec!(0.02)));
+    assert!(config.notifications.telegram.commands);
+    assert_eq!(config.notifications.telegram.allowed_users, [42]);
+
+    for (i, toml) in ["threshold_percent = \"0\"", "quantity = \"-0.01\""]
+        .iter()
+        .enumerate()
+    {
+        let toml = format!("{}{}", EXECUTION, toml);
+        assert!(
+            load_config(&format!("invalid-{}", i), &toml).is_err(),
+            "{}",
+            toml
+        );
+    }
+}
+
+#[test]
+fn chat_commands_are_parsed() {
+    let parse = |text| BotCommand::parse(text).unwrap().unwrap();
+    assert_eq!(parse("/strategies"), BotCommand::Strategies);
+    let strategy = |name: &str, update| BotCommand::Strategy {
+        name: name.into(),
+        update,
+    };
+    assert_eq!(
+        parse("/strategy spread off"),
+        strategy(
+            "spread",
+            StrategyUpdate {
+                enabled: Some(false),
+                ..StrategyUpdate::default()
+            }
+        )
+    );
+    assert_eq!(
+        parse("/strategy desk threshold 0.25%"),
+        strategy(
+            "desk",
+            StrategyUpdate {
+                threshold_percent: Some(dec!(0.25)),
+                ..StrategyUpdate::default()
+            }
+        )
+    );
+    assert_eq!(
+        parse("/strategy desk size 0.05"),
+        strategy(
+            "desk",
+            StrategyUpdate {
+                quantity: Some(dec!(0.05)),
+                ..StrategyUpdate::default()
+            }
+        )
+    );
+
+    // Anything else gets the usage.
+    for text in [
+        "/help",
+        "/strategy",
+        "/strategy spread",
+        "/strategy spread pause",
+        "/strategy spread threshold",
+        "/strategy spread threshold 0",
+        "/strategy spread size lots",
+        "/strategies now",
+    ] {
+        let usage = BotCommand::parse(text).unwrap().unwrap_err();
+        assert!(usage.contains("/strategy <name> on|off"), "{}", text);
+    }
+    assert_eq!(BotCommand::parse("strategy spread off"), None);
+}
+
+fn start_control(execution: watch::Sender<ExecutionState>, enabled: bool) -> Control {
+    let quotes = QuoteBus::default();
+    let cancel = CancellationToken::new();
+    let tracker = MarketTracker::new(dec!(1), None, AlertGate::new(dec!(5), dec!(1), 0)).spawn();
+    let (events, _) = broadcast::channel(1);
+    Control::new(
+        quotes.clone(),
+        tracker,
+        Feeds::new(quotes, events, cancel.clone()),
+        execution,
+        ExecutionControl {
+            paused: false,
+            threshold_percent: dec!(0.1),
+        },
+        enabled,
+        dec!(5),
+        cancel,
+    )
+}
+
+#[tokio::test]
+async fn changes_are_applied_and_audited() {
+    let path = std::env::temp_dir().join(format!(
+        "arb-strategy-controls-{}-audit.jsonl",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    audit::open(&path).unwrap();
+    let state = watch::Sender::new(ExecutionState::default());
+    let control = start_control(state.clone(), true);
+
+    let reply = BotCommand::parse("/strategy spread threshold 0.3")
+        .unwrap()
+        .unwrap()
+        .run(&control, "telegram:42");
+    assert!(reply.contains("▶️ spread, threshold 0.3%"), "{}", reply);
+    let reply = BotCommand::parse("/strategy spread off")
+        .unwrap()
+        .unwrap()
+        .run(&control, "telegram:42");
+    assert!(
+        reply.starts_with("⏸️ spread: disabled through the control API"),
+        "{}",
+        reply
+    );
+
+    let update = StrategyUpdate {
+        enabled: Some(true),
+        quantity: Some(dec!(0.02)),
+        ..StrategyUpdate::default()
+    };
+    let book = control.update_strategy("spread", &update, "api").unwrap();
+    assert_eq!(book.disabled, None);
+    assert_eq!(
+        (book.threshold_percent, book.quantity),
+        (Some(dec!(0.3)), Some(dec!(0.02)))
+    );
+    assert_eq!(state.borrow().portfolio.get("spread"), Some(&book));
+
+    let zero = StrategyUpdate {
+        quantity: Some(Decimal::ZERO),
+        ..StrategyUpdate::default()
+    };
+    assert!(matches!(
+        control.update_strategy("spread", &zero, "api"),
+        Err(ControlError::InvalidQuantity(_))
+    ));
+    let reply = BotCommand::Strategies.run(&control, "telegram:42");
+    assert!(
+        reply.starts_with("▶️ spread, threshold 0.3%, size 0.02"),
+        "{}",
+        reply
+    );
+
+    // Every change and refusal is on the chain, with where it came from.
+    assert_eq!(audit::verify(&path, None).unwrap(), 4);
+    let log = std::fs::read_to_string(&path).unwrap();
+    let entries: Vec<serde_json::Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let via: Vec<_> = entries.iter().map(|e| e["via"].as_str().unwrap()).collect();
+    assert_eq!(via, ["telegram:42", "telegram:42", "api", "api"]);
+    assert_eq!(entries[0]["params"]["update"]["threshold_percent"], "0.3");
+    assert_eq!(entries[2]["response"]["quantity"], "0.02");
+    assert_eq!(entries[3]["error"], "quantities must be positive, got 0");
+    let _ = std::fs::remove_file(&path);
+
+    // Without execution there's nothing to change.
+    let off = start_control(watch::Sender::new(ExecutionState::default()), false);
+    let reply = BotCommand::parse("/strategy spread on")
+        .unwrap()
+        .unwrap()
+        .run(&off, "telegram:42");
+    assert_eq!(reply, "❌ execution is not running");
+}
+
+#[tokio::test(start_paused = true)]
+async fn trades_follow_the_strategy_threshold_and_size() {
+    let ledger = Ledger::new();
+    let fake = |id| Arc::new(FakeExchange::new(id, &ledger));
+    let (binance, bybit) = (fake(ExchangeId::Binance), fake(ExchangeId::Bybit));
+    let state = watch::Sender::new(ExecutionState::default());
+    // Spread trades only above 1%.
+    state.send_modify(|s| _ = s.portfolio.set_threshold(portfolio::SPREAD, dec!(1)));
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(state.clone());
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // About 0.9%.
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert!(ledger.is_empty());
+
+    // Lowered, and at twice the size.
+    state.send_modify(|s| {
+        s.portfolio.set_threshold(portfolio::SPREAD, dec!(0.5));
+        s.portfolio.set_quantity(portfolio::SPREAD, dec!(0.02));
+    });
+    bybit.quote(dec!(101)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        ledger.quantities(),
+        [
+            (ExchangeId::Binance, LegSide::Buy, dec!(0.02)),
+            (ExchangeId::Bybit, LegSide::Sell, dec!(0.02)),
+        ]
+    );
+}
@@ -0,0 +1,621 @@
+//! A stand-in for an exchange's order client, for tests that drive
+//! `ArbitrageEngine` without the network.
+//!
+//! [`FakeExchange`] takes prices from [`FakeExchange::quote`] and records
+//! every order and cancel in a [`Ledger`] shared by the fakes of a test, so
+//! the order the legs went out in can be checked across venues. It can be
+//! made slow to acknowledge ([`FakeExchange::with_ack_delay`]), to refuse
+//! orders ([`FakeExchange::fail_next`], [`FakeExchange::reject_sells`]) or
+//! cancels ([`FakeExchange::fail_cancels`]), to never answer
+//! ([`FakeExchange::go_silent`]), or to fill away from the order's price
+//! ([`FakeExchange::with_fills`]). [`FakeExchange::spot`] makes it a spot
+//! venue, for inventory mode, and [`FakeExchange::account`] one of several
+//! accounts on its exchange, with [`FakeExchange::with_margin`] to route by.
+//!
+//! [`FakeVenue`] is for what a venue holds rather than how it trades: the
+//! position it reports, and a book of resting orders that limit orders join
+//! and cancels take orders off, filling them [`FakeVenue::fill_late`] more
+//! on the way.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use arbitrage_bot::{
+    error::TradingError,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+    },
+    state::{self, LegSide, OpenOrder},
+    tca::Fill,
+    ws::exchanges::{Exchange, OrderSide, PriceData},
+};
+use async_trait::async_trait;
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::mpsc,
+    time::{self, Instant},
+};
+
+/// An order a fake took, failed ones included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placed {
+    pub exchange: ExchangeId,
+    pub side: LegSide,
+    pub price: Decimal,
+    pub qty: Decimal,
+    /// Sent reduce-only.
+    pub exit: bool,
+    /// The account it went to, for fakes standing in for one.
+    pub account: Option<&'static str>,
+    pub order_id: String,
+    /// When it was acknowledged, after the fake's ack delay.
+    pub at: Instant,
+}
+
+impl Placed {
+    pub fn leg(&self) -> (ExchangeId, LegSide) {
+        (self.exchange, self.side)
+    }
+}
+
+/// What the fakes of one test were sent, in the order they acknowledged it.
+#[derive(Debug, Default)]
+pub struct Ledger {
+    orders: Mutex<Vec<Placed>>,
+    cancels: Mutex<Vec<String>>,
+    /// Orders taken so far, [`Ledger::take`] or not.
+    placed: AtomicUsize,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl Ledger {
+    pub fn new() -> Arc<Self> {
+        Arc::default()
+    }
+
+    pub fn orders(&self) -> Vec<Placed> {
+        self.orders.lock().unwrap().clone()
+    }
+
+    /// The orders so far, leaving none.
+    pub fn take(&self) -> Vec<Placed> {
+        std::mem::take(&mut *self.orders.lock().unwrap())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.lock().unwrap().is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.lock().unwrap().len()
+    }
+
+    /// Each order's venue and side.
+    pub fn legs(&self) -> Vec<(ExchangeId, LegSide)> {
+        self.orders().iter().map(Placed::leg).collect()
+    }
+
+    /// Each order's venue, side and price.
+    pub fn prices(&self) -> Vec<(ExchangeId, LegSide, Decimal)> {
+        self.orders()
+            .iter()
+            .map(|o| (o.exchange, o.side, o.price))
+            .collect()
+    }
+
+    /// Each order's venue, side and quantity.
+    pub fn quantities(&self) -> Vec<(ExchangeId, LegSide, Decimal)> {
+        self.orders()
+            .iter()
+            .map(|o| (o.exchange, o.side, o.qty))
+            .collect()
+    }
+
+    /// Each order's account.
+    pub fn accounts(&self) -> Vec<Option<&'static str>> {
+        self.orders().iter().map(|o| o.account).collect()
+    }
+
+    /// The order IDs cancelled, in order.
+    pub fn cancels(&self) -> Vec<String> {
+        self.cancels.lock().unwrap().clone()
+    }
+
+    /// The most orders any of the fakes were waiting to acknowledge at once.
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+/// Takes every order on `symbol` unless told otherwise. Order IDs are
+/// `<exchange>-<n>` for the ledger's `n`th order.
+pub struct FakeExchange {
+    pub id: ExchangeId,
+    pub symbol: &'static str,
+    prices: Mutex<Option<mpsc::Sender<PriceData>>>,
+    ledger: Arc<Ledger>,
+    ack_delay: Duration,
+    /// Errors for the next orders, one each.
+    failures: Mutex<VecDeque<TradingError>>,
+    reject_sells: AtomicBool,
+    fail_cancels: AtomicBool,
+    silent: AtomicBool,
+    /// Takes spot orders only.
+    spot: bool,
+    account: Option<&'static str>,
+    /// Reported as available, when set.
+    margin: Option<Decimal>,
+    /// Orders taken without an answer, for `unanswered_order`.
+    unanswered: Mutex<Vec<Placed>>,
+    /// Fills each order at its price plus this, when set.
+    slippage: Option<Decimal>,
+    /// How often each order's fill was asked for.
+    fills_asked: Mutex<HashMap<String, u32>>,
+}
+
+impl FakeExchange {
+    pub fn new(id: ExchangeId, ledger: &Arc<Ledger>) -> Self {
+        Self {
+            id,
+            symbol: "BTCUSDT",
+            prices: Mutex::default(),
+            ledger: Arc::clone(ledger),
+            ack_delay: Duration::ZERO,
+            failures: Mutex::default(),
+            reject_sells: AtomicBool::new(false),
+            fail_cancels: AtomicBool::new(false),
+            silent: AtomicBool::new(false),
+            spot: false,
+            account: None,
+            margin: None,
+            unanswered: Mutex::default(),
+            slippage: None,
+            fills_asked: Mutex::default(),
+        }
+    }
+
+    /// Quotes `symbol` instead of `BTCUSDT`.
+    pub fn trading(mut self, symbol: &'static str) -> Self {
+        self.symbol = symbol;
+        self
+    }
+
+    /// Acknowledges each order `delay` after it was sent.
+    pub fn with_ack_delay(mut self, delay: Duration) -> Self {
+        self.ack_delay = delay;
+        self
+    }
+
+    /// Reports fills: each order fills at its price plus `slippage`, half
+    /// the first time it's asked and the rest the next.
+    pub fn with_fills(mut self, slippage: Decimal) -> Self {
+        self.slippage = Some(slippage);
+        self
+    }
+
+    /// Takes spot orders, and panics at a futures one.
+    pub fn spot(mut self) -> Self {
+        self.spot = true;
+        self
+    }
+
+    /// Stands in for account `name`, recorded with each order.
+    pub fn account(mut self, name: &'static str) -> Self {
+        self.account = Some(name);
+        self
+    }
+
+    /// Reports `margin` available; without it, can't say.
+    pub fn with_margin(mut self, margin: Decimal) -> Self {
+        self.margin = Some(margin);
+        self
+    }
+
+    /// Fails the next orders with `errors`, one each.
+    pub fn fail_next(&self, errors: impl IntoIterator<Item = TradingError>) {
+        self.failures.lock().unwrap().extend(errors);
+    }
+
+    /// Rejects every sell for insufficient balance while `reject` is set.
+    pub fn reject_sells(&self, reject: bool) {
+        self.reject_sells.store(reject, Ordering::SeqCst);
+    }
+
+    /// Refuses every cancel, as for an order that already filled, while
+    /// `fail` is set. Refused cancels are still recorded.
+    pub fn fail_cancels(&self, fail: bool) {
+        self.fail_cancels.store(fail, Ordering::SeqCst);
+    }
+
+    /// Takes every order without ever answering while `silent` is set;
+    /// they're recorded all the same.
+    pub fn go_silent(&self, silent: bool) {
+        self.silent.store(silent, Ordering::SeqCst);
+    }
+
+    /// Whether the engine has subscribed to this fake's prices.
+    pub fn subscribed(&self) -> bool {
+        self.prices.lock().unwrap().is_some()
+    }
+
+    /// Quotes `bid`, with the ask 0.1 above.
+    pub async fn quote(&self, bid: Decimal) {
+        self.quote_at(bid, bid + dec!(0.1)).await;
+    }
+
+    pub async fn quote_at(&self, bid: Decimal, ask: Decimal) {
+        self.send(bid, ask, None).await;
+    }
+
+    /// Quotes `bid`, with the ask 0.1 above and `size` at each.
+    pub async fn quote_sized(&self, bid: Decimal, size: Decimal) {
+        self.send(bid, bid + dec!(0.1), Some(size)).await;
+    }
+
+    async fn send(&self, bid: Decimal, ask: Decimal, size: Option<Decimal>) {
+        let tx = self.prices.lock().unwrap().clone().unwrap();
+        tx.send(PriceData {
+            exchange: self.id,
+            symbol: Symbol::intern(self.symbol),
+            bid,
+            ask,
+            bid_size: size,
+            ask_size: size,
+        })
+        .await
+        .unwrap();
+    }
+
+    async fn place(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+        exit: bool,
+        spot: bool,
+    ) -> Result<String, TradingError> {
+        assert_eq!(
+            spot, self.spot,
+            "{} took an order for the wrong market",
+            self.id
+        );
+        let ledger = &self.ledger;
+        let in_flight = ledger.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        ledger.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        time::sleep(self.ack_delay).await;
+        ledger.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        let side = LegSide::from(&side);
+        let n = ledger.placed.fetch_add(1, Ordering::SeqCst) + 1;
+        let order_id = format!("{}-{}", self.id, n);
+        let placed = Placed {
+            exchange: self.id,
+            side,
+            price,
+            qty,
+            exit,
+            account: self.account,
+            order_id: order_id.clone(),
+            at: Instant::now(),
+        };
+        ledger.orders.lock().unwrap().push(placed.clone());
+        if self.silent.load(Ordering::SeqCst) {
+            self.unanswered.lock().unwrap().push(placed);
+            std::future::pending::<()>().await;
+        }
+        if let Some(error) = self.failures.lock().unwrap().pop_front() {
+            return Err(error);
+        }
+        if side == LegSide::Sell && self.reject_sells.load(Ordering::SeqCst) {
+            return Err(TradingError::Rejected {
+                exchange: self.id,
+                operation: "order.place",
+                code: -2010,
+                msg: "Account has insufficient balance".into(),
+            });
+        }
+        Ok(order_id)
+    }
+}
+
+/// Waits until the engine has subscribed to every one of `fakes`.
+pub async fn until_subscribed(fakes: &[&FakeExchange]) {
+    while !fakes.iter().all(|fake| fake.subscribed()) {
+        tokio::task::yield_now().await;
+    }
+}
+
+#[async_trait]
+impl Exchange for FakeExchange {
+    fn id(&self) -> ExchangeId {
+        self.id
+    }
+
+    async fn subscribe_prices(&self, tx: mpsc::Sender<PriceData>) {
+        *self.prices.lock().unwrap() = Some(tx);
+    }
+
+    async fn place_order_future(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        self.place(side, price, qty, false, false).await
+    }
+
+    async fn place_exit_future(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        self.place(side, price, qty, true, false).await
+    }
+
+    async fn place_order_spot(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        self.place(side, price, qty, false, true).await
+    }
+
+    async fn available_margin(&self) -> Result<Decimal, TradingError> {
+        self.margin.ok_or(TradingError::Unsupported {
+            exchange: self.id.name(),
+            kind: "margin",
+        })
+    }
+
+    async fn fill(&self, order_id: &str) -> Result<Fill, TradingError> {
+        let Some(slippage) = self.slippage else {
+            return Err(TradingError::Unsupported {
+                exchange: self.id.name(),
+                kind: "fill",
+            });
+        };
+        let order = self
+            .ledger
+            .orders()
+            .into_iter()
+            .find(|o| o.order_id == order_id)
+            .expect("fill asked for an order never placed");
+        let mut asked = self.fills_asked.lock().unwrap();
+        let asked = asked.entry(order_id.to_string()).or_default();
+        *asked += 1;
+        let done = *asked > 1;
+        Ok(Fill {
+            quantity: if done { order.qty } else { order.qty / dec!(2) },
+            avg_price: Some(order.price + slippage),
+            updated_at_ms: state::now_ms(),
+            done,
+            fee: None,
+        })
+    }
+
+    async fn unanswered_order(
+        &self,
+        side: OrderSide,
+        qty: Decimal,
+    ) -> Result<Option<String>, TradingError> {
+        let side = LegSide::from(&side);
+        let mut unanswered = self.unanswered.lock().unwrap();
+        let found = unanswered
+            .iter()
+            .rposition(|o| o.side == side && o.qty == qty);
+        Ok(found.map(|i| unanswered.remove(i).order_id))
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<(), TradingError> {
+        self.ledger
+            .cancels
+            .lock()
+            .unwrap()
+            .push(order_id.to_string());
+        if self.fail_cancels.load(Ordering::SeqCst) {
+            return Err(TradingError::Rejected {
+                exchange: self.id,
+                operation: "order.cancel",
+                code: -2011,
+                msg: "Unknown order sent.".into(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Reports a position and the orders resting on its book, and records the
+/// orders placed on it by ID: `limit Buy 0.5 @ 100`, `market Sell 0.3`,
+/// `exit Sell 0.5 @ 100` or `market exit Sell 0.3`. Limit orders rest on
+/// the book under that ID.
+pub struct FakeVenue {
+    pub id: ExchangeId,
+    /// `None` fails the query.
+    position: Option<Decimal>,
+    book: Mutex<Vec<OpenOrder>>,
+    placed: Mutex<Vec<String>>,
+    /// Filled between the last read of an order and its cancel.
+    late: Mutex<Decimal>,
+    cancelled: Mutex<Vec<OpenOrder>>,
+}
+
+impl FakeVenue {
+    /// A venue that can't say what position it holds.
+    pub fn new(id: ExchangeId) -> Self {
+        Self {
+            id,
+            position: None,
+            book: Mutex::default(),
+            placed: Mutex::default(),
+            late: Mutex::default(),
+            cancelled: Mutex::default(),
+        }
+    }
+
+    pub fn with_position(mut self, position: Decimal) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// Starts with `orders` resting.
+    pub fn with_orders(self, orders: Vec<OpenOrder>) -> Self {
+        *self.book.lock().unwrap() = orders;
+        self
+    }
+
+    /// Rests a `side` order for 0.5 at `price`, 0.2 of it filled, under
+    /// the next numeric ID, which it returns.
+    pub fn rest(&self, side: LegSide, price: Decimal) -> String {
+        self.rest_order(side, price, false)
+    }
+
+    /// [`FakeVenue::rest`], reduce-only.
+    pub fn rest_exit(&self, side: LegSide, price: Decimal) -> String {
+        self.rest_order(side, price, true)
+    }
+
+    fn rest_order(&self, side: LegSide, price: Decimal, reduce_only: bool) -> String {
+        let mut book = self.book.lock().unwrap();
+        let order_id = format!("{}", 40 + self.placed.lock().unwrap().len() + book.len());
+        book.push(OpenOrder {
+            order_id: order_id.clone(),
+            symbol: "BTCUSDT".into(),
+            side,
+            price,
+            quantity: dec!(0.5),
+            filled: dec!(0.2),
+            reduce_only,
+            updated_at_ms: 0,
+        });
+        order_id
+    }
+
+    /// The orders resting now.
+    pub fn book(&self) -> Vec<OpenOrder> {
+        self.book.lock().unwrap().clone()
+    }
+
+    /// Takes every order off the book, as if they had filled.
+    pub fn clear_book(&self) {
+        self.book.lock().unwrap().clear();
+    }
+
+    /// Fills each order `qty` more between its last read and its cancel.
+    pub fn fill_late(&self, qty: Decimal) {
+        *self.late.lock().unwrap() = qty;
+    }
+
+    /// The IDs of the orders placed so far.
+    pub fn placed(&self) -> Vec<String> {
+        self.placed.lock().unwrap().clone()
+    }
+
+    fn record(&self, order_id: String) -> Result<String, TradingError> {
+        self.placed.lock().unwrap().push(order_id.clone());
+        Ok(order_id)
+    }
+}
+
+#[async_trait]
+impl Exchange for FakeVenue {
+    fn id(&self) -> ExchangeId {
+        self.id
+    }
+
+    async fn subscribe_prices(&self, _tx: mpsc::Sender<PriceData>) {}
+
+    async fn place_order_future(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        let order_id = self.record(format!("limit {:?} {} @ {}", side, qty, price))?;
+        self.book.lock().unwrap().push(OpenOrder {
+            order_id: order_id.clone(),
+            symbol: "BTCUSDT".into(),
+            side: LegSide::from(&side),
+            price,
+            quantity: qty,
+            filled: dec!(0),
+            reduce_only: false,
+            updated_at_ms: 0,
+        });
+        Ok(order_id)
+    }
+
+    async fn place_market_future(
+        &self,
+        side: OrderSide,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        self.record(format!("market {:?} {}", side, qty))
+    }
+
+    async fn place_exit_future(
+        &self,
+        side: OrderSide,
+        price: Decimal,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        self.record(format!("exit {:?} {} @ {}", side, qty, price))
+    }
+
+    async fn place_market_exit_future(
+        &self,
+        side: OrderSide,
+        qty: Decimal,
+    ) -> Result<String, TradingError> {
+        self.record(format!("market exit {:?} {}", side, qty))
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<(), TradingError> {
+        let mut book = self.book.lock().unwrap();
+        if let Some(i) = book.iter().position(|o| o.order_id == order_id) {
+            let mut order = book.remove(i);
+            order.filled = (order.filled + *self.late.lock().unwrap()).min(order.quantity);
+            self.cancelled.lock().unwrap().push(order);
+        }
+        Ok(())
+    }
+
+    /// How a cancelled order ended up; others can't say.
+    async fn fill(&self, order_id: &str) -> Result<Fill, TradingError> {
+        let cancelled = self.cancelled.lock().unwrap();
+        let order =
+            cancelled
+                .iter()
+                .find(|o| o.order_id == order_id)
+                .ok_or(TradingError::Unsupported {
+                    exchange: self.id.name(),
+                    kind: "fill",
+                })?;
+        Ok(Fill {
+            quantity: order.filled,
+            avg_price: Some(order.price),
+            updated_at_ms: 0,
+            done: true,
+            fee: None,
+        })
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OpenOrder>, TradingError> {
+        Ok(self.book.lock().unwrap().clone())
+    }
+
+    async fn position(&self) -> Result<Decimal, TradingError> {
+        self.position.ok_or(TradingError::EmptyResponse {
+            operation: "account.position",
+        })
+    }
+}
@@ -0,0 +1,561 @@
+//! A local stand-in for the Binance and Bybit WebSocket APIs, so the feeds
+//! and the order client can be tested without the network or credentials.
+//!
+//! [`MockExchange::start`] listens on 127.0.0.1 and answers in the
+//! exchange's own format:
+//!
+//! - Binance: `SUBSCRIBE`/`UNSUBSCRIBE` acks, and `order.place`,
+//!   `order.cancel` and `order.status` results as the futures WS API sends
+//!   them.
+//! - Bybit: `subscribe`, `auth` and `ping` acks, and `order.create` and
+//!   `order.cancel` results as the V5 trade stream sends them.
+//!
+//! Market data goes out with [`MockExchange::push`], a Binance combined-stream
+//! frame only to connections subscribed to its stream; [`MockExchange::inject`]
+//! makes the next request fail, and [`MockExchange::set_chaos`] makes pushed
+//! data arrive late, broken, twice or not at all. The server runs on its own
+//! thread, so it can outlive the runtime of the test that started it.
+//!
+//! Tests of execution itself skip the wire and use [`exchange::FakeExchange`]
+//! as the engine's order client.
+
+#![allow(dead_code)] // each test binary uses its own part
+
+#[cfg(feature = "execution")]
+pub mod exchange;
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Once,
+    },
+    time::Duration,
+};
+
+use arbitrage_bot::config::{self, Config};
+use futures_util::{SinkExt, StreamExt};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde_json::{json, Value};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{broadcast, watch},
+};
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+use tokio_util::sync::CancellationToken;
+
+/// How long the `wait_for_*` helpers wait before failing the test.
+pub const WAIT: Duration = Duration::from_secs(5);
+
+pub const BINANCE_FUTURES_DEPTH: &str = include_str!("../../fixtures/binance_futures_depth5.json");
+pub const BINANCE_SPOT_DEPTH: &str = include_str!("../../fixtures/binance_spot_depth.json");
+pub const BYBIT_ORDERBOOK: &str = include_str!("../../fixtures/bybit_orderbook1_linear.json");
+
+/// Sets the global config once per test binary: quick reconnects, and the
+/// given `(prefix, replacement)` endpoint rewrites. Later calls are no-ops.
+pub fn init_config(endpoints: &[(&str, String)]) {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let mut config = Config::default();
+        config.ws.default.base_backoff_ms = 50;
+        config.ws.default.max_backoff_ms = 200;
+        for (prefix, replacement) in endpoints {
+            config
+                .network
+                .endpoints
+                .insert(prefix.to_string(), replacement.clone());
+        }
+        config::init(config);
+    });
+}
+
+/// Loads a [`Config`] from `toml` through a temp file, as the binary does
+/// from disk; `name` keeps tests running in parallel apart.
+pub fn load_config(name: &str, toml: &str) -> anyhow::Result<Config> {
+    let path =
+        std::env::temp_dir().join(format!("arb-config-{}-{}.toml", std::process::id(), name));
+    std::fs::write(&path, toml).unwrap();
+    let config = Config::load(&path);
+    let _ = std::fs::remove_file(&path);
+    config
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    Binance,
+    Bybit,
+}
+
+/// What goes wrong with the next request.
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// An exchange error instead of the result.
+    Reject { code: i32, msg: String },
+    /// The connection closes without an answer.
+    Disconnect,
+    /// The answer isn't JSON.
+    Garbage,
+    /// No answer at all.
+    Silence,
+    /// The request is carried out, but the connection closes before the
+    /// answer goes out.
+    Lost,
+}
+
+/// How pushed market data misbehaves; each field but `seed` and
+/// `max_delay` is a per-frame, per-connection probability.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chaos {
+    pub seed: u64,
+    /// Hang up instead of sending the frame.
+    pub drop: f64,
+    /// Hold the frame, and everything behind it, for up to `max_delay`.
+    pub delay: f64,
+    pub max_delay: Duration,
+    /// Send a truncated copy of the frame first.
+    pub malformed: f64,
+    /// Send one of the last [`REPLAY_WINDOW`] frames again afterwards.
+    pub replay: f64,
+}
+
+/// How far back a replayed frame can come from.
+pub const REPLAY_WINDOW: usize = 32;
+
+/// What a connection does with one pushed frame.
+enum Delivery {
+    Send(Duration, Vec<String>),
+    Hangup,
+}
+
+#[derive(Debug, Clone)]
+enum Push {
+    Text(String),
+    Close,
+}
+
+pub struct MockExchange {
+    addr: SocketAddr,
+    state: Arc<State>,
+    cancel: CancellationToken,
+}
+
+struct State {
+    flavor: Flavor,
+    faults: Mutex<VecDeque<Fault>>,
+    requests: Mutex<Vec<Value>>,
+    request_count: watch::Sender<usize>,
+    connections: watch::Sender<usize>,
+    pushes: broadcast::Sender<Push>,
+    next_order_id: AtomicU64,
+    /// Order ID -> the order as last reported.
+    orders: Mutex<HashMap<u64, Value>>,
+    chaos: Mutex<Option<(Chaos, StdRng)>>,
+    /// The last [`REPLAY_WINDOW`] pushed frames.
+    pushed: Mutex<VecDeque<String>>,
+}
+
+impl MockExchange {
+    pub fn start(flavor: Flavor) -> Self {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind the mock exchange");
+        listener
+            .set_nonblocking(true)
+            .expect("non-blocking listener");
+        let addr = listener.local_addr().expect("mock exchange address");
+        let state = Arc::new(State {
+            flavor,
+            faults: Mutex::default(),
+            requests: Mutex::default(),
+            request_count: watch::Sender::new(0),
+            connections: watch::Sender::new(0),
+            pushes: broadcast::channel(256).0,
+            next_order_id: AtomicU64::new(1_000_001),
+            orders: Mutex::default(),
+            chaos: Mutex::default(),
+            pushed: Mutex::default(),
+        });
+        let cancel = CancellationToken::new();
+        let (server, stop) = (state.clone(), cancel.clone());
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("mock exchange runtime");
+            runtime.block_on(async move {
+                let listener = TcpListener::from_std(listener).expect("tokio listener");
+                loop {
+                    tokio::select! {
+                        accepted = listener.accept() => {
+                            if let Ok((stream, _)) = accepted {
+                                tokio::spawn(serve(stream, server.clone(), stop.clone()));
+                            }
+                        }
+                        _ = stop.cancelled() => return,
+                    }
+                }
+            });
+        });
+        Self {
+            addr,
+            state,
+            cancel,
+        }
+    }
+
+    /// `ws://127.0.0.1:<port>`, for feeds that take a URL or as an
+    /// `[network] endpoints` rewrite.
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// Sends `frame` to every open connection.
+    pub fn push(&self, frame: &str) {
+        let mut pushed = self.state.pushed.lock().unwrap();
+        if pushed.len() == REPLAY_WINDOW {
+            pushed.pop_front();
+        }
+        pushed.push_back(frame.to_string());
+        let _ = self.state.pushes.send(Push::Text(frame.to_string()));
+    }
+
+    /// Applies `chaos` to every frame pushed from now on; `None` stops it.
+    pub fn set_chaos(&self, chaos: Option<Chaos>) {
+        *self.state.chaos.lock().unwrap() =
+            chaos.map(|chaos| (chaos, StdRng::seed_from_u64(chaos.seed)));
+    }
+
+    /// Closes every open connection.
+    pub fn disconnect_all(&self) {
+        let _ = self.state.pushes.send(Push::Close);
+    }
+
+    /// Makes the next request fail with `fault`; several queue up in order.
+    pub fn inject(&self, fault: Fault) {
+        self.state.faults.lock().unwrap().push_back(fault);
+    }
+
+    /// Fills futures order `order_id` in full at its price; status queries
+    /// report it filled from then on.
+    pub fn fill(&self, order_id: u64) {
+        if let Some(order) = self.state.orders.lock().unwrap().get_mut(&order_id) {
+            order["status"] = "FILLED".into();
+            order["executedQty"] = order["origQty"].clone();
+            order["avgPrice"] = order["price"].clone();
+        }
+    }
+
+    /// Every JSON request received so far, oldest first.
+    pub fn requests(&self) -> Vec<Value> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Waits until `count` requests have arrived and returns them all.
+    pub async fn wait_for_requests(&self, count: usize) -> Vec<Value> {
+        wait_for(&self.state.request_count, count, "requests").await;
+        self.requests()
+    }
+
+    /// Waits until `count` connections have been accepted in total.
+    pub async fn wait_for_connections(&self, count: usize) {
+        wait_for(&self.state.connections, count, "connections").await;
+    }
+}
+
+impl Drop for MockExchange {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+async fn wait_for(counter: &watch::Sender<usize>, count: usize, what: &str) {
+    let mut counter = counter.subscribe();
+    let reached = tokio::time::timeout(WAIT, counter.wait_for(|n| *n >= count))
+        .await
+        .is_ok_and(|r| r.is_ok());
+    assert!(
+        reached,
+        "expected {} {}, got {}",
+        count,
+        what,
+        *counter.borrow()
+    );
+}
+
+async fn serve(stream: TcpStream, state: Arc<State>, cancel: CancellationToken) {
+    let Ok(ws) = accept_async(stream).await else {
+        return;
+    };
+    let mut pushes = state.pushes.subscribe();
+    state.connections.send_modify(|n| *n += 1);
+    let (mut sink, mut source) = ws.split();
+    // Binance streams subscribed on this connection.
+    let mut streams = HashSet::new();
+    loop {
+        let replies = tokio::select! {
+            push = pushes.recv() => match push {
+                Ok(Push::Text(text)) if !state.wanted(&text, &streams) => Vec::new(),
+                Ok(Push::Text(text)) => match state.deliver(text) {
+                    Delivery::Send(delay, frames) => {
+                        if !delay.is_zero() {
+                            tokio::time::sleep(delay).await;
+                        }
+                        frames
+                    }
+                    Delivery::Hangup => break,
+                },
+                Ok(Push::Close) | Err(broadcast::error::RecvError::Closed) => break,
+                Err(broadcast::error::RecvError::Lagged(_)) => Vec::new(),
+            },
+            msg = source.next() => match msg {
+                Some(Ok(Message::Text(text))) => {
+                    let Ok(request) = serde_json::from_str::<Value>(&text) else {
+                        continue;
+                    };
+                    state.track(&request, &mut streams);
+                    state.requests.lock().unwrap().push(request.clone());
+                    state.request_count.send_modify(|n| *n += 1);
+                    let fault = state.faults.lock().unwrap().pop_front();
+                    match fault {
+                        Some(Fault::Disconnect) => break,
+                        Some(Fault::Lost) => {
+                            state.answer(&request);
+                            break;
+                        }
+                        Some(Fault::Silence) => Vec::new(),
+                        Some(Fault::Garbage) => vec!["}{ not json".to_string()],
+                        Some(Fault::Reject { code, msg }) => {
+                            vec![state.reject(&request, code, &msg).to_string()]
+                        }
+                        None => vec![state.answer(&request).to_string()],
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => Vec::new(),
+            },
+            _ = cancel.cancelled() => break,
+        };
+        for reply in replies {
+            if sink.send(Message::Text(reply.into())).await.is_err() {
+                return;
+            }
+        }
+    }
+    let _ = sink.close().await;
+}
+
+impl State {
+    /// Whether a connection subscribed to `streams` gets `frame`: any but a
+    /// Binance combined-stream frame of another stream.
+    fn wanted(&self, frame: &str, streams: &HashSet<String>) -> bool {
+        if self.flavor != Flavor::Binance {
+            return true;
+        }
+        match serde_json::from_str::<Value>(frame) {
+            Ok(frame) => frame["stream"]
+                .as_str()
+                .is_none_or(|stream| streams.contains(stream)),
+            Err(_) => true,
+        }
+    }
+
+    /// Applies a Binance `SUBSCRIBE` or `UNSUBSCRIBE` to `streams`.
+    fn track(&self, request: &Value, streams: &mut HashSet<String>) {
+        if self.flavor != Flavor::Binance {
+            return;
+        }
+        let params = request["params"].as_array().into_iter().flatten();
+        let names = params.filter_map(|p| p.as_str()).map(str::to_string);
+        match request["method"].as_str() {
+            Some("SUBSCRIBE") => streams.extend(names),
+            Some("UNSUBSCRIBE") => {
+                for name in names {
+                    streams.remove(&name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn deliver(&self, frame: String) -> Delivery {
+        let mut chaos = self.chaos.lock().unwrap();
+        let Some((chaos, rng)) = chaos.as_mut() else {
+            return Delivery::Send(Duration::ZERO, vec![frame]);
+        };
+        if rng.gen_bool(chaos.drop) {
+            return Delivery::Hangup;
+        }
+        let delay = if rng.gen_bool(chaos.delay) {
+            chaos.max_delay.mul_f64(rng.gen())
+        } else {
+            Duration::ZERO
+        };
+        let mut frames = Vec::new();
+        if rng.gen_bool(chaos.malformed) {
+            // Any strict prefix of a JSON object is unbalanced.
+            let cut = rng.gen_range(0..frame.len());
+            frames.push(frame[..cut].to_string());
+        }
+        frames.push(frame);
+        if rng.gen_bool(chaos.replay) {
+            let pushed = self.pushed.lock().unwrap();
+            frames.push(pushed[rng.gen_range(0..pushed.len())].clone());
+        }
+        Delivery::Send(delay, frames)
+    }
+
+    fn answer(&self, request: &Value) -> Value {
+        match self.flavor {
+            Flavor::Binance => self.binance_answer(request),
+            Flavor::Bybit => self.bybit_answer(request),
+        }
+    }
+
+    fn reject(&self, request: &Value, code: i32, msg: &str) -> Value {
+        match self.flavor {
+            Flavor::Binance
+                if request["method"]
+                    .as_str()
+                    .unwrap_or("")
+                    .starts_with("order.") =>
+            {
+                json!({
+                    "id": request["id"],
+                    "status": 400,
+                    "error": {"code": code, "msg": msg},
+                })
+            }
+            Flavor::Binance => json!({"error": {"code": code, "msg": msg}, "id": request["id"]}),
+            Flavor::Bybit if request["op"].as_str().unwrap_or("").starts_with("order.") => json!({
+                "reqId": request["reqId"],
+                "retCode": code,
+                "retMsg": msg,
+                "op": request["op"],
+                "data": {},
+                "connId": "mock",
+            }),
+            Flavor::Bybit => json!({
+                "success": false,
+                "ret_msg": msg,
+                "conn_id": "mock",
+                "req_id": request["req_id"],
+                "op": request["op"],
+            }),
+        }
+    }
+
+    fn binance_answer(&self, request: &Value) -> Value {
+        let params = &request["params"];
+        let method = request["method"].as_str().unwrap_or_default();
+        let order = match method {
+            "SUBSCRIBE" | "UNSUBSCRIBE" => return json!({"result": null, "id": request["id"]}),
+            "order.place" => {
+                let order_id = self.next_order_id.fetch_add(1, Ordering::Relaxed);
+                let order = binance_order(order_id, params);
+                self.orders.lock().unwrap().insert(order_id, order.clone());
+                Ok(order)
+            }
+            "order.cancel" | "order.status" => {
+                let mut orders = self.orders.lock().unwrap();
+                let order_id = match params["origClientOrderId"].as_str() {
+                    Some(client_order_id) => orders
+                        .iter()
+                        .find(|(_, order)| order["clientOrderId"] == client_order_id)
+                        .map(|(&id, _)| id)
+                        .unwrap_or_default(),
+                    None => params["orderId"]
+                        .as_str()
+                        .and_then(|id| id.parse().ok())
+                        .unwrap_or_default(),
+                };
+                match orders.get_mut(&order_id) {
+                    Some(order) if method == "order.cancel" && order["status"] != "NEW" => {
+                        Err((-2011, "Unknown order sent."))
+                    }
+                    Some(order) if method == "order.cancel" => {
+                        order["status"] = "CANCELED".into();
+                        Ok(order.clone())
+                    }
+                    Some(order) => Ok(order.clone()),
+                    None if method == "order.cancel" => Err((-2011, "Unknown order sent.")),
+                    None => Err((-2013, "Order does not exist.")),
+                }
+            }
+            _ => Err((-1000, "Unknown method.")),
+        };
+        match order {
+            Ok(order) => json!({"id": request["id"], "status": 200, "result": order}),
+            Err((code, msg)) => self.reject(request, code, msg),
+        }
+    }
+
+    fn bybit_answer(&self, request: &Value) -> Value {
+        let op = request["op"].as_str().unwrap_or_default();
+        match op {
+            "subscribe" | "unsubscribe" | "auth" | "ping" => json!({
+                "success": true,
+                "ret_msg": if op == "ping" { "pong" } else { "" },
+                "conn_id": "mock",
+                "req_id": request["req_id"],
+                "op": op,
+            }),
+            "order.create" | "order.cancel" => {
+                let args = &request["args"][0];
+                let order_id = match op {
+                    "order.create" => self
+                        .next_order_id
+                        .fetch_add(1, Ordering::Relaxed)
+                        .to_string(),
+                    _ => args["orderId"].as_str().unwrap_or_default().to_string(),
+                };
+                json!({
+                    "reqId": request["reqId"],
+                    "retCode": 0,
+                    "retMsg": "OK",
+                    "op": op,
+                    "data": {"orderId": order_id, "orderLinkId": args["orderLinkId"]},
+                    "connId": "mock",
+                })
+            }
+            _ => self.reject(request, 10001, "unknown op"),
+        }
+    }
+}
+
+/// A freshly accepted futures order, echoing the request parameters. There
+/// is nothing to trade against, so IOC and FOK orders expire unfilled.
+fn binance_order(order_id: u64, params: &Value) -> Value {
+    let param = |name: &str, default: &str| params[name].as_str().unwrap_or(default).to_string();
+    let time = params["timestamp"]
+        .as_str()
+        .and_then(|t| t.parse::<u64>().ok())
+        .unwrap_or_default();
+    json!({
+        "orderId": order_id,
+        "symbol": param("symbol", ""),
+        "status": match params["timeInForce"].as_str() {
+            Some("IOC" | "FOK") => "EXPIRED",
+            _ => "NEW",
+        },
+        "clientOrderId": param("newClientOrderId", &format!("mock-{}", order_id)),
+        "price": param("price", "0"),
+        "avgPrice": "0.00",
+        "origQty": param("quantity", "0"),
+        "executedQty": "0",
+        "cumQty": "0",
+        "cumQuote": "0",
+        "timeInForce": param("timeInForce", "GTC"),
+        "type": param("type", "LIMIT"),
+        "reduceOnly": param("reduceOnly", "false") == "true",
+        "closePosition": param("closePosition", "false") == "true",
+        "side": param("side", "BUY"),
+        "positionSide": param("positionSide", "BOTH"),
+        "stopPrice": param("stopPrice", "0"),
+        "workingType": param("workingType", "CONTRACT_PRICE"),
+        "priceProtect": param("priceProtect", "false") == "true",
+        "origType": param("type", "LIMIT"),
+        "priceMatch": "NONE",
+        "selfTradePreventionMode": "NONE",
+        "goodTillDate": 0,
+        "updateTime": time,
+    })
+}
@@ -0,0 +1,192 @@
+//! Trade cost analysis: each trade's fills are followed until they're done
+//! and measured against the quotes it was detected at.
+
+mod support;
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use arbitrage_bot::{
+    models::{ids::ExchangeId, money::Decimal},
+    state::{ExecutionState, LegSide},
+    tca::{Fill, LegCost, Tca, TradeCost},
+    ws::exchanges::ArbitrageEngine,
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::watch,
+    time::{self, Duration},
+};
+
+use support::exchange::{until_subscribed, FakeExchange, Ledger};
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("arb-tca-{}-{}", std::process::id(), name))
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_trade_is_measured_once_its_orders_are_done() {
+    let path = temp_path("trade.jsonl");
+    let _ = std::fs::remove_file(&path);
+    let tca = Tca::default().with_log(&path).unwrap();
+    // The buy fills 0.05 above its limit; the sell at its limit.
+    let ledger = Ledger::new();
+    let binance = Arc::new(FakeExchange::new(ExchangeId::Binance, &ledger).with_fills(dec!(0.05)));
+    let bybit = Arc::new(FakeExchange::new(ExchangeId::Bybit, &ledger).with_fills(dec!(0)));
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    )
+    .with_state(watch::Sender::new(ExecutionState::default()))
+    .with_tca(tca.clone(), Duration::from_secs(10));
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // Buy at 100 on Binance, sell at 100.5 on Bybit: a 0.5% edge.
+    binance.quote_at(dec!(99.9), dec!(100)).await;
+    bybit.quote_at(dec!(100.5), dec!(100.6)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(tca.stats().trades, 0, "the fills aren't done yet");
+    time::sleep(Duration::from_secs(2)).await;
+
+    let stats = tca.stats();
+    assert_eq!(stats.trades, 1);
+    assert_eq!(
+        stats.slippage_bps,
+        BTreeMap::from([(ExchangeId::Binance, dec!(5)), (ExchangeId::Bybit, dec!(0))])
+    );
+    assert_eq!(stats.worst_slippage_bps, Some(dec!(5)));
+    // Filled at 100.05 / 100.5, about 0.45% apart.
+    let decay = stats.mean_edge_decay_percent.unwrap();
+    assert!(decay > dec!(0.04) && decay < dec!(0.06), "{}", decay);
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<_> = log.lines().collect();
+    assert_eq!(lines.len(), 1);
+    let cost: TradeCost = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(cost.plan_id, 1);
+    assert_eq!(cost.symbol, "BTCUSDT");
+    assert_eq!(cost.expected_edge_percent, dec!(0.5));
+    assert!(cost.legs.iter().all(|leg| leg.filled == dec!(0.01)));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn a_sell_filled_above_its_quote_has_negative_slippage() {
+    let fill = |avg_price| Fill {
+        quantity: dec!(1),
+        avg_price: Some(avg_price),
+        updated_at_ms: 2_000,
+        done: true,
+        fee: None,
+    };
+    let buy = LegCost::new(
+        ExchangeId::Binance,
+        LegSide::Buy,
+        dec!(100),
+        dec!(100),
+        dec!(1),
+        Some(&fill(dec!(100))),
+        1_000,
+    );
+    let sell = LegCost::new(
+        ExchangeId::Bybit,
+        LegSide::Sell,
+        dec!(101),
+        dec!(101),
+        dec!(1),
+        Some(&fill(dec!(101.101))),
+        1_000,
+    );
+    assert_eq!(sell.slippage_bps, Some(dec!(-10)));
+    assert_eq!(sell.time_to_fill_ms, Some(1_000));
+
+    let cost = TradeCost::new(7, "BTCUSDT", 1_000, vec![buy, sell]);
+    assert_eq!(cost.expected_edge_percent, dec!(1));
+    assert_eq!(cost.realized_edge_percent, Some(dec!(1.101)));
+    assert_eq!(cost.edge_decay_percent, Some(dec!(-0.101)));
+}
+
+#[test]
+fn an_unfilled_leg_leaves_the_edge_unrealized() {
+    let tca = Tca::default();
+    let buy = LegCost::new(
+        ExchangeId::Binance,
+        LegSide::Buy,
+        dec!(100),
+        dec!(100),
+        dec!(1),
+        None,
+        1_000,
+    );
+    assert_eq!(buy.slippage_bps, None);
+    assert_eq!(buy.time_to_fill_ms, None);
+    assert_eq!(buy.filled, Decimal::ZERO);
+    let sell = LegCost::new(
+        ExchangeId::Bybit,
+        LegSide::Sell,
+        dec!(101),
+        dec!(101),
+        dec!(1),
+        Some(&Fill {
+            quantity: dec!(1),
+            avg_price: Some(dec!(100.899)),
+            updated_at_ms: 1_500,
+            done: true,
+            fee: None,
+        }),
+        1_000,
+    );
+    let cost = TradeCost::new(1, "BTCUSDT", 1_000, vec![buy, sell]);
+    assert_eq!(cost.realized_edge_percent, None);
+    assert_eq!(cost.edge_decay_percent, None);
+
+    tca.record(&cost);
+    let stats = tca.stats();
+    assert_eq!(stats.trades, 1);
+    assert_eq!(stats.slippage_bps[&ExchangeId::Bybit].round_dp(2), dec!(10));
+    assert_eq!(stats.mean_time_to_fill_ms, Some(500));
+    assert_eq!(stats.mean_edge_decay_percent, None);
+    assert!(stats.to_string().contains("edge decay n/a"));
+}
+
+#[test]
+fn fees_paid_are_averaged_as_a_rate() {
+    let tca = Tca::default();
+    let leg = |exchange, price, fee| {
+        LegCost::new(
+            exchange,
+            LegSide::Buy,
+            dec!(100),
+            dec!(100),
+            dec!(2),
+            Some(&Fill {
+                quantity: dec!(2),
+                avg_price: Some(price),
+                updated_at_ms: 1_500,
+                done: true,
+                fee,
+            }),
+            1_000,
+        )
+    };
+    let paid = leg(ExchangeId::Binance, dec!(100), Some(dec!(0.08)));
+    assert_eq!(paid.fee, Some(dec!(0.08)));
+    assert_eq!(paid.fee_percent, Some(dec!(0.04)));
+    tca.record(&TradeCost::new(1, "BTCUSDT", 1_000, vec![paid]));
+    tca.record(&TradeCost::new(
+        2,
+        "BTCUSDT",
+        1_000,
+        vec![leg(ExchangeId::Binance, dec!(100), Some(dec!(0.04)))],
+    ));
+    // Unknown fees don't count towards the mean.
+    let unknown = leg(ExchangeId::Bybit, dec!(100), None);
+    assert_eq!(unknown.fee_percent, None);
+    tca.record(&TradeCost::new(3, "BTCUSDT", 1_000, vec![unknown]));
+
+    let stats = tca.stats();
+    assert_eq!(stats.fee_percent.len(), 1);
+    assert_eq!(stats.fee_percent[&ExchangeId::Binance], dec!(0.03));
+    assert!(stats.to_string().ends_with(", binance fees 0.0300%"));
+}
@@ -0,0 +1,195 @@
+//! Smoke test against the real Binance and Bybit testnets, with the
+//! production endpoints rewritten to them. Needs the network and testnet keys,
+//! so it only runs on request:
+//!
+//! ```text
+//! cargo test --features testnet --test testnet -- --ignored
+//! ```
+//!
+//! Keys come from the environment or `.env`: `API_KEY_BINANCE_TESTNET` /
+//! `SECRET_KEY_BINANCE_TESTNET` for a Binance futures testnet account with some
+//! USDT, and `API_KEY_BYBIT_TESTNET` / `SECRET_KEY_BYBIT_TESTNET`.
+//!
+//! Binance goes through the full trading round trip: the orders are far from
+//! the market and cancelled straight away. Bybit has no order client, so only
+//! its feed and trade-stream authentication are checked.
+
+mod support;
+
+use std::{sync::Arc, time::Duration};
+
+use arbitrage_bot::{
+    binance::{
+        api::BinanceTradingClient,
+        order::{create_limit_order, BinanceOrderSide},
+        ws_handler::EVENT_CHANNEL_CAPACITY,
+    },
+    constants::urls,
+    models::{bybit_make_orders::BybitAuth, ids::ExchangeId, money::Decimal},
+    net,
+    ws::{
+        binance_client::run_orderbook_stream_binance,
+        bybit_client_futures::run_orderbook_stream_bybit_futures,
+        quote_bus::{Quote, QuoteBus},
+    },
+};
+use futures_util::{SinkExt, StreamExt};
+use rust_decimal::RoundingStrategy;
+use rust_decimal_macros::dec;
+use serde_json::Value;
+use tokio::{sync::broadcast, time};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+
+/// How long the testnets get for each step.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn init() {
+    dotenv::dotenv().ok();
+    support::init_config(&[
+        (
+            "wss://fstream.binance.com",
+            "wss://fstream.binancefuture.com".into(),
+        ),
+        (
+            "wss://ws-fapi.binance.com",
+            "wss://testnet.binancefuture.com".into(),
+        ),
+        (
+            "wss://stream.bybit.com",
+            "wss://stream-testnet.bybit.com".into(),
+        ),
+    ]);
+}
+
+fn var(name: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| panic!("{} is not set", name))
+}
+
+/// Runs `feed` until the first BTCUSDT quote from `exchange` arrives.
+async fn first_quote<F>(
+    exchange: ExchangeId,
+    feed: impl FnOnce(QuoteBus, CancellationToken) -> F,
+) -> Arc<Quote>
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    let bus = QuoteBus::default();
+    let mut latest = bus.watch(exchange, "BTCUSDT");
+    let cancel = CancellationToken::new();
+    let task = tokio::spawn(feed(bus, cancel.clone()));
+    let quote = time::timeout(TIMEOUT, latest.wait_for(Option::is_some))
+        .await
+        .unwrap_or_else(|_| panic!("no {} quote from the testnet", exchange))
+        .unwrap()
+        .clone()
+        .unwrap();
+    cancel.cancel();
+    task.await.unwrap();
+    assert!(quote.top.bid > Decimal::ZERO && quote.top.bid < quote.top.ask);
+    quote
+}
+
+#[tokio::test]
+#[ignore = "needs the Binance futures testnet and API keys"]
+async fn binance_feed_and_order_round_trip() {
+    init();
+    let quote = first_quote(ExchangeId::Binance, |bus, cancel| async move {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        run_orderbook_stream_binance("BTCUSDT", bus, urls::BINANCE_URL_FUTURES, events, cancel)
+            .await
+    })
+    .await;
+
+    let mut client = time::timeout(
+        TIMEOUT,
+        BinanceTradingClient::connect(
+            var("API_KEY_BINANCE_TESTNET"),
+            var("SECRET_KEY_BINANCE_TESTNET").into(),
+        ),
+    )
+    .await
+    .expect("connect in time")
+    .expect("connect to the testnet WS API");
+
+    // A bid 4% under the market on the 0.1 tick, just over the 100 USDT
+    // minimum notional: it rests on the book until cancelled.
+    let price = (quote.top.bid * dec!(0.96)).round_dp(1);
+    let quantity = (dec!(110) / price).round_dp_with_strategy(3, RoundingStrategy::AwayFromZero);
+    let order = create_limit_order("BTCUSDT".into(), BinanceOrderSide::BUY, quantity, price);
+    let placed = client
+        .future_order_place(&order)
+        .await
+        .expect("place the order");
+    assert_eq!(placed.symbol, "BTCUSDT");
+    assert_eq!(placed.status, "NEW");
+    assert_eq!(placed.price.parse::<Decimal>().unwrap(), price);
+    assert_eq!(placed.orig_qty.parse::<Decimal>().unwrap(), quantity);
+
+    let status = client
+        .future_order_status("BTCUSDT".into(), placed.order_id)
+        .await
+        .expect("query the order");
+    assert_eq!(status.order_id, placed.order_id);
+    assert_eq!(status.status, "NEW");
+
+    let cancelled = client
+        .future_order_cancel("BTCUSDT".into(), placed.order_id)
+        .await
+        .expect("cancel the order");
+    assert_eq!(cancelled.order_id, placed.order_id);
+    assert_eq!(cancelled.status, "CANCELED");
+    let status = client
+        .future_order_status("BTCUSDT".into(), placed.order_id)
+        .await
+        .expect("query the cancelled order");
+    assert_eq!(status.status, "CANCELED");
+}
+
+#[tokio::test]
+#[ignore = "needs the Bybit testnet and API keys"]
+async fn bybit_feed_and_trade_stream_auth() {
+    init();
+    first_quote(ExchangeId::Bybit, |bus, cancel| async move {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        run_orderbook_stream_bybit_futures(
+            "BTCUSDT",
+            bus,
+            urls::BYBIT_URL_FUTURES_LINEAR,
+            events,
+            cancel,
+        )
+        .await
+    })
+    .await;
+
+    let auth = BybitAuth::new(
+        var("API_KEY_BYBIT_TESTNET"),
+        var("SECRET_KEY_BYBIT_TESTNET"),
+    );
+    let (mut ws, _) = net::connect_ws(urls::BYBIT_URL_FUTURES)
+        .await
+        .expect("connect to the testnet trade stream");
+    let frame = serde_json::to_string(&auth.auth_msg()).unwrap();
+    ws.send(Message::Text(frame.into())).await.unwrap();
+    let ack = time::timeout(TIMEOUT, async {
+        while let Some(msg) = ws.next().await {
+            if let Message::Text(text) = msg.expect("trade stream error") {
+                let reply: Value = serde_json::from_str(&text).unwrap();
+                if reply["op"] == "auth" {
+                    return reply;
+                }
+            }
+        }
+        panic!("trade stream closed before the auth reply");
+    })
+    .await
+    .expect("no auth reply in time");
+    // The trade stream answers like the REST API; the other private streams
+    // with `success`.
+    assert!(
+        ack["retCode"] == 0 || ack["success"] == true,
+        "auth rejected: {}",
+        ack
+    );
+}
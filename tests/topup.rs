@@ -0,0 +1,112 @@
+//! Margin top-ups: when an account has been low for long enough, how much
+//! moves and from where, within the per-transfer and daily limits.
+
+use arbitrage_bot::{
+    config::TopUpConfig,
+    error::TradingError,
+    models::{ids::ExchangeId, money::Decimal},
+    topup::{TopUp, TopUps},
+};
+use rust_decimal_macros::dec;
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+
+fn config() -> TopUpConfig {
+    TopUpConfig {
+        enabled: true,
+        min_margin: dec!(500),
+        low_checks: 2,
+        target_margin: dec!(1000),
+        max_transfer: dec!(400),
+        max_daily: dec!(1000),
+        ..TopUpConfig::default()
+    }
+}
+
+fn margins(main: Decimal, sub: Decimal) -> Vec<(String, Decimal)> {
+    vec![("main".to_string(), main), ("sub".to_string(), sub)]
+}
+
+#[test]
+fn tops_up_only_after_low_checks_in_a_row() {
+    let mut top_ups = TopUps::new(ExchangeId::Binance, &config());
+    assert_eq!(top_ups.check(&margins(dec!(5000), dec!(300)), 0), None);
+    // Back above min_margin starts the count over.
+    assert_eq!(top_ups.check(&margins(dec!(5000), dec!(600)), 0), None);
+    assert_eq!(top_ups.check(&margins(dec!(5000), dec!(300)), 0), None);
+
+    // 700 short of the target, but one transfer moves at most 400.
+    assert_eq!(
+        top_ups.check(&margins(dec!(5000), dec!(300)), 0),
+        Some(TopUp {
+            exchange: ExchangeId::Binance,
+            from: "main".into(),
+            to: "sub".into(),
+            asset: "USDT".into(),
+            amount: dec!(400),
+        })
+    );
+    // Then it waits low_checks again for the transfer to land.
+    assert_eq!(top_ups.check(&margins(dec!(5000), dec!(300)), 0), None);
+}
+
+#[test]
+fn the_donor_keeps_the_target_margin() {
+    let mut top_ups = TopUps::new(ExchangeId::Binance, &config());
+    top_ups.check(&margins(dec!(1100), dec!(200)), 0);
+    let top_up = top_ups.check(&margins(dec!(1100), dec!(200)), 0).unwrap();
+    assert_eq!(top_up.amount, dec!(100));
+
+    // Neither can spare anything.
+    let mut top_ups = TopUps::new(ExchangeId::Binance, &config());
+    top_ups.check(&margins(dec!(900), dec!(200)), 0);
+    assert_eq!(top_ups.check(&margins(dec!(900), dec!(200)), 0), None);
+}
+
+#[test]
+fn transfers_stay_within_the_daily_limit() {
+    let mut top_ups = TopUps::new(ExchangeId::Binance, &config());
+    let mut move_at = |now_ms| {
+        // Still low from the last time, or low twice now.
+        let top_up = (0..2).find_map(|_| top_ups.check(&margins(dec!(9000), dec!(100)), now_ms));
+        if let Some(top_up) = &top_up {
+            top_ups.record(top_up, now_ms);
+        }
+        top_up.map(|t| t.amount)
+    };
+    assert_eq!(move_at(0), Some(dec!(400)));
+    assert_eq!(move_at(HOUR_MS), Some(dec!(400)));
+    assert_eq!(move_at(2 * HOUR_MS), Some(dec!(200)));
+    assert_eq!(move_at(3 * HOUR_MS), None);
+    // A day after the first transfer, its 400 is allowed again.
+    assert_eq!(move_at(24 * HOUR_MS), Some(dec!(400)));
+}
+
+#[test]
+fn only_refused_transfers_leave_the_daily_limit_alone() {
+    let mut top_ups = TopUps::new(ExchangeId::Binance, &config());
+    let top_up = |amount| TopUp {
+        exchange: ExchangeId::Binance,
+        from: "main".to_string(),
+        to: "sub".to_string(),
+        asset: "USDT".to_string(),
+        amount,
+    };
+    let refused = Err(TradingError::Rejected {
+        exchange: ExchangeId::Binance,
+        operation: "sub-account transfer",
+        code: -1100,
+        msg: "Illegal characters found in parameter".to_string(),
+    });
+    top_ups.attempted(&top_up(dec!(400)), &refused, 0);
+    assert_eq!(top_ups.allowance(0), dec!(1000));
+
+    // Made, or maybe made: either counts.
+    top_ups.attempted(&top_up(dec!(300)), &Ok("1".to_string()), 0);
+    top_ups.attempted(&top_up(dec!(200)), &Err(TradingError::ConnectionClosed), 0);
+    let lost = Err(TradingError::EmptyResponse {
+        operation: "sub-account transfer",
+    });
+    top_ups.attempted(&top_up(dec!(100)), &lost, 0);
+    assert_eq!(top_ups.allowance(0), dec!(400));
+}
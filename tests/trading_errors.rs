@@ -0,0 +1,87 @@
+//! Exchange rejections classified by venue, and no more trading on an
+//! exchange after a fatal failure.
+
+mod support;
+
+use std::sync::Arc;
+
+use arbitrage_bot::{
+    error::{Classify, Severity, TradingError},
+    models::ids::ExchangeId,
+    ws::exchanges::ArbitrageEngine,
+};
+use rust_decimal_macros::dec;
+use tokio::time::{self, Duration};
+
+use support::exchange::{until_subscribed, FakeExchange, Ledger};
+
+fn rejected(exchange: ExchangeId, code: i32) -> TradingError {
+    TradingError::Rejected {
+        exchange,
+        operation: "order.place",
+        code,
+        msg: "rejected".into(),
+    }
+}
+
+#[test]
+fn rejections_are_classified_by_venue() {
+    let binance_limit = rejected(ExchangeId::Binance, -1003);
+    assert!(binance_limit.is_rate_limited());
+    assert!(binance_limit.is_retryable());
+    assert!(!binance_limit.is_fatal());
+    assert_eq!(binance_limit.severity(), Severity::Warning);
+    assert_eq!(
+        binance_limit.to_string(),
+        "binance order.place rejected (-1003): rejected"
+    );
+
+    let bybit_limit = rejected(ExchangeId::Bybit, 10006);
+    assert!(bybit_limit.is_rate_limited());
+    assert!(bybit_limit.is_retryable());
+    // Binance's codes mean nothing on Bybit, and the other way round.
+    assert!(!rejected(ExchangeId::Bybit, -1003).is_rate_limited());
+    assert!(!rejected(ExchangeId::Binance, 10006).is_retryable());
+
+    for error in [
+        rejected(ExchangeId::Binance, -2015),
+        rejected(ExchangeId::Bybit, 10003),
+    ] {
+        assert!(error.is_auth(), "{}", error);
+        assert!(error.is_fatal(), "{}", error);
+        assert!(!error.is_retryable(), "{}", error);
+        assert_eq!(error.severity(), Severity::Critical);
+    }
+    assert!(TradingError::MissingCredentials("API_KEY_BINANCE".into()).is_fatal());
+    assert!(!TradingError::ConnectionClosed.is_fatal());
+    // Insufficient balance: this order failed, the next one may not.
+    assert!(!rejected(ExchangeId::Binance, -2010).is_fatal());
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_fatal_failure_stops_trading_on_the_exchange() {
+    let ledger = Ledger::new();
+    let binance = Arc::new(FakeExchange::new(ExchangeId::Binance, &ledger));
+    let bybit = Arc::new(FakeExchange::new(ExchangeId::Bybit, &ledger));
+    bybit.fail_next([rejected(ExchangeId::Bybit, 10003)]);
+    let mut engine = ArbitrageEngine::new(
+        vec![binance.clone(), bybit.clone()],
+        dec!(0.001),
+        dec!(0.01),
+    );
+    tokio::spawn(async move { engine.run().await });
+    until_subscribed(&[&binance, &bybit]).await;
+
+    // About 1%: Bybit refuses the key.
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101.1)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(ledger.len(), 2);
+
+    // Past the cooldown the spread is still there, but Bybit is left alone.
+    time::sleep(Duration::from_secs(10)).await;
+    binance.quote(dec!(100)).await;
+    bybit.quote(dec!(101.1)).await;
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(ledger.len(), 2);
+}
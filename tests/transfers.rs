@@ -0,0 +1,150 @@
+//! Deposit and withdrawal status: which direction of a spread a suspended
+//! transfer blocks, and how alerts on such spreads are flagged or dropped.
+
+use std::collections::HashMap;
+
+use arbitrage_bot::{
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::{MarketTracker, MarketType},
+    },
+    notifications::{alert_gate::AlertGate, telegram::Notification},
+    transfers::{AssetStatus, TransferStatus},
+};
+use rust_decimal_macros::dec;
+use tokio::sync::mpsc;
+
+const OPEN: AssetStatus = AssetStatus {
+    deposit: true,
+    withdraw: true,
+    congested: false,
+};
+
+/// WLFI can't be withdrawn from Binance; BTC moves freely.
+fn status() -> TransferStatus {
+    let status = TransferStatus::default();
+    status.update(
+        ExchangeId::Binance,
+        HashMap::from([
+            ("BTC".to_string(), OPEN),
+            (
+                "WLFI".to_string(),
+                AssetStatus {
+                    withdraw: false,
+                    ..OPEN
+                },
+            ),
+        ]),
+    );
+    status.update(
+        ExchangeId::Bybit,
+        HashMap::from([("BTC".to_string(), OPEN), ("WLFI".to_string(), OPEN)]),
+    );
+    status
+}
+
+#[test]
+fn blocker_follows_the_transfer_direction() {
+    let status = status();
+    let wlfi = Symbol::intern("WLFIUSDT");
+    assert_eq!(
+        status.blocker(wlfi, ExchangeId::Binance, ExchangeId::Bybit),
+        Some("WLFI withdrawals suspended on binance".to_string())
+    );
+    // Moving the other way needs Binance deposits, which are open.
+    assert_eq!(
+        status.blocker(wlfi, ExchangeId::Bybit, ExchangeId::Binance),
+        None
+    );
+    let btc = Symbol::intern("BTCUSDT");
+    assert_eq!(
+        status.blocker(btc, ExchangeId::Binance, ExchangeId::Bybit),
+        None
+    );
+    // Unknown assets are never flagged.
+    assert_eq!(
+        status.blocker(
+            Symbol::intern("NEWUSDT"),
+            ExchangeId::Binance,
+            ExchangeId::Bybit
+        ),
+        None
+    );
+
+    // A refresh replaces what the exchange reported before.
+    status.update(
+        ExchangeId::Bybit,
+        HashMap::from([(
+            "BTC".to_string(),
+            AssetStatus {
+                deposit: false,
+                ..OPEN
+            },
+        )]),
+    );
+    assert_eq!(
+        status.blocker(btc, ExchangeId::Binance, ExchangeId::Bybit),
+        Some("BTC deposits suspended on bybit".to_string())
+    );
+    assert_eq!(
+        status.blocker(wlfi, ExchangeId::Bybit, ExchangeId::Binance),
+        None
+    );
+}
+
+/// Feeds a 10% spread on `symbol`, cheaper on `cheap`, and returns the alert
+/// that went out, if any.
+fn spread(
+    tracker: &mut MarketTracker,
+    rx: &mut mpsc::Receiver<Notification>,
+    symbol: &str,
+    cheap: ExchangeId,
+) -> Option<Option<String>> {
+    let symbol = Symbol::intern(symbol);
+    let dear = match cheap {
+        ExchangeId::Binance => ExchangeId::Bybit,
+        ExchangeId::Bybit => ExchangeId::Binance,
+    };
+    let quote = |mid: Decimal| (mid - dec!(0.1), mid + dec!(0.1));
+    for (exchange, mid) in [(cheap, dec!(100)), (dear, dec!(110))] {
+        let (bid, ask) = quote(mid);
+        tracker.update(exchange, symbol, bid, ask, MarketType::Spot);
+    }
+    match rx.try_recv() {
+        Ok(Notification::Arbitrage(alert)) => Some(alert.note),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn alerts_on_stranded_spreads_are_flagged_or_dropped() {
+    let (tx, mut rx) = mpsc::channel(4);
+    let mut tracker = MarketTracker::new(dec!(1), Some(tx), AlertGate::new(dec!(5), dec!(1), 0))
+        .with_transfers(status(), false);
+    let note = spread(&mut tracker, &mut rx, "WLFIUSDT", ExchangeId::Binance)
+        .expect("flagged, not dropped")
+        .expect("a transfer note");
+    assert!(
+        note.starts_with("WLFI withdrawals suspended on binance"),
+        "{}",
+        note
+    );
+    assert_eq!(
+        spread(&mut tracker, &mut rx, "BTCUSDT", ExchangeId::Binance),
+        Some(None)
+    );
+
+    let (tx, mut rx) = mpsc::channel(4);
+    let mut tracker = MarketTracker::new(dec!(1), Some(tx), AlertGate::new(dec!(5), dec!(1), 0))
+        .with_transfers(status(), true);
+    assert_eq!(
+        spread(&mut tracker, &mut rx, "WLFIUSDT", ExchangeId::Binance),
+        None
+    );
+    // Cheaper on Bybit, the asset moves the open way.
+    assert_eq!(
+        spread(&mut tracker, &mut rx, "WLFIUSDT", ExchangeId::Bybit),
+        Some(None)
+    );
+}
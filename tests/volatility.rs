@@ -0,0 +1,110 @@
+//! Volatility regimes: realized volatility over the window moves a symbol
+//! between calm and volatile, and while volatile its alerts need a raised
+//! spread and say why.
+
+use arbitrage_bot::{
+    config::VolatilityConfig,
+    models::{
+        ids::{ExchangeId, Symbol},
+        money::Decimal,
+        orderbook::{MarketTracker, MarketType},
+    },
+    notifications::{
+        alert_gate::AlertGate,
+        telegram::{AppAlert, Notification},
+    },
+    volatility::{Regime, Volatility},
+};
+use rust_decimal_macros::dec;
+use tokio::{
+    sync::mpsc,
+    time::{self, Duration},
+};
+
+/// Volatile above 1% over a minute, calm again under 0.5%.
+fn volatility() -> Volatility {
+    Volatility::new(&VolatilityConfig {
+        enabled: true,
+        window_secs: 60,
+        ..VolatilityConfig::default()
+    })
+}
+
+fn quote(tracker: &mut MarketTracker, exchange: ExchangeId, symbol: &str, mid: Decimal) {
+    tracker.update(
+        exchange,
+        Symbol::intern(symbol),
+        mid - dec!(0.1),
+        mid + dec!(0.1),
+        MarketType::Spot,
+    );
+}
+
+fn next_alert(rx: &mut mpsc::Receiver<Notification>) -> Option<AppAlert> {
+    match rx.try_recv() {
+        Ok(Notification::Arbitrage(alert)) => Some(alert),
+        _ => None,
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn regimes_shift_with_hysteresis() {
+    let volatility = volatility();
+    let btc = Symbol::intern("BTCUSDT");
+    let record = |mid| volatility.record(ExchangeId::Binance, btc, mid);
+
+    assert_eq!(record(dec!(100)), None);
+    time::advance(Duration::from_secs(1)).await;
+    // 0.5%: still calm.
+    assert_eq!(record(dec!(100.5)), None);
+    assert_eq!(volatility.realized(btc), Some(dec!(0.5)));
+    time::advance(Duration::from_secs(1)).await;
+    assert_eq!(record(dec!(101.5)), Some(Regime::Volatile));
+    assert_eq!(volatility.multiplier(btc), dec!(2));
+    assert_eq!(
+        volatility.note(btc).as_deref(),
+        Some("volatile: 1.11% over 1 min, thresholds ×2")
+    );
+    // Sampled this second already.
+    assert_eq!(record(dec!(90)), None);
+
+    // Quieter, but not under 0.5% yet.
+    time::advance(Duration::from_secs(59)).await;
+    assert_eq!(record(dec!(101)), None);
+    assert_eq!(volatility.regime(btc), Regime::Volatile);
+    // The move has left the window.
+    time::advance(Duration::from_secs(2)).await;
+    assert_eq!(record(dec!(101)), Some(Regime::Calm));
+    assert_eq!(volatility.multiplier(btc), dec!(1));
+    assert_eq!(volatility.note(btc), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn volatile_symbols_need_wider_spreads() {
+    let (tx, mut rx) = mpsc::channel(4);
+    let mut tracker = MarketTracker::new(dec!(1), Some(tx), AlertGate::new(dec!(5), dec!(1), 0))
+        .with_volatility(volatility());
+
+    // Calm: 6% is enough.
+    quote(&mut tracker, ExchangeId::Binance, "SOLUSDT", dec!(100));
+    quote(&mut tracker, ExchangeId::Bybit, "SOLUSDT", dec!(106));
+    let alert = next_alert(&mut rx).expect("a calm alert");
+    assert_eq!(alert.symbol, "SOLUSDT");
+    assert_eq!(alert.note, None);
+
+    quote(&mut tracker, ExchangeId::Binance, "ETHUSDT", dec!(100));
+    quote(&mut tracker, ExchangeId::Bybit, "ETHUSDT", dec!(100));
+    time::advance(Duration::from_secs(1)).await;
+    quote(&mut tracker, ExchangeId::Binance, "ETHUSDT", dec!(102));
+    // Volatile: 7.8% is no longer enough...
+    quote(&mut tracker, ExchangeId::Bybit, "ETHUSDT", dec!(94));
+    assert!(next_alert(&mut rx).is_none());
+    // ...but 11.8% is, and the alert says why it took that much.
+    quote(&mut tracker, ExchangeId::Bybit, "ETHUSDT", dec!(90));
+    let alert = next_alert(&mut rx).expect("a volatile alert");
+    assert_eq!(alert.diff_percent.round_dp(2), dec!(11.76));
+    assert_eq!(
+        alert.note.as_deref(),
+        Some("volatile: 6.19% over 1 min, thresholds ×2")
+    );
+}
@@ -0,0 +1,75 @@
+//! The walk-forward split: calibration and evaluation windows rolling over
+//! recorded spreads.
+
+use arbitrage_bot::backtest::{
+    replay::SpreadSample,
+    walk_forward::{self, WalkForwardConfig},
+};
+
+/// A binance/bybit spread every 30 minutes for 4 hours, each wider than
+/// the last: 0%, 1%, … 8%.
+fn samples() -> Vec<SpreadSample> {
+    (0..=8)
+        .map(|i| SpreadSample {
+            timestamp: i * 1800,
+            symbol: "BTCUSDT".into(),
+            exchange_a: "binance".into(),
+            exchange_b: "bybit".into(),
+            diff_percent: i as f64,
+        })
+        .collect()
+}
+
+fn config(train_hours: i64, test_hours: i64) -> WalkForwardConfig {
+    WalkForwardConfig {
+        train_window_secs: train_hours * 3600,
+        test_window_secs: test_hours * 3600,
+        quantile: 1.0,
+        min_threshold: 0.0,
+    }
+}
+
+#[test]
+fn folds_calibrate_on_one_window_and_test_on_the_next() {
+    let folds = walk_forward::run(&samples(), &config(2, 1)).unwrap();
+
+    // Another fold would train up to the last sample, leaving nothing to
+    // test on.
+    assert_eq!(folds.len(), 2);
+    let first = &folds[0];
+    assert_eq!((first.fold, first.test_start), (0, 7200));
+    assert_eq!(first.exchanges, "binance/bybit");
+    // Trained on [0h, 2h), tested on [2h, 3h): windows end exclusive.
+    assert_eq!((first.train_samples, first.test_samples), (4, 2));
+    assert_eq!(first.threshold, 3.0);
+    assert_eq!(first.test_hits, 2);
+    assert_eq!(first.expected_hit_rate, 0.25);
+    assert_eq!(first.realized_hit_rate, 1.0);
+    assert_eq!(first.mean_test_excess, 1.5);
+
+    // Rolled forward by one test window.
+    let second = &folds[1];
+    assert_eq!((second.fold, second.test_start), (1, 10800));
+    assert_eq!((second.train_samples, second.test_samples), (4, 2));
+    assert_eq!(second.threshold, 5.0);
+}
+
+#[test]
+fn the_threshold_has_a_floor() {
+    let mut config = config(2, 1);
+    config.min_threshold = 10.0;
+    let folds = walk_forward::run(&samples(), &config).unwrap();
+    assert!(folds
+        .iter()
+        .all(|f| f.threshold == 10.0 && f.test_hits == 0));
+}
+
+#[test]
+fn empty_windows_are_refused_instead_of_looping() {
+    for (train, test) in [(2, 0), (2, -1), (0, 1)] {
+        let config = config(train, test);
+        let error = config.validate().unwrap_err();
+        assert!(error.to_string().contains("must be positive"), "{}", error);
+        assert!(walk_forward::run(&samples(), &config).is_err());
+    }
+}